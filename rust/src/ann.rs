@@ -0,0 +1,459 @@
+//! Approximate nearest-neighbor index for novelty-driven mutation selection
+//!
+//! A Hierarchical Navigable Small World (HNSW) graph over cosine distance.
+//! Each vector is linked to its closest neighbors on a random number of
+//! layers; queries descend greedily from the sparse top layer and then run
+//! a bounded best-first search on the dense bottom layer, so checking how
+//! novel a candidate mutation's embedding is stays sub-millisecond even
+//! after many thousands of insertions.
+
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use numpy::PyReadonlyArray1;
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// (distance, node) ordered by distance so it can live in a BinaryHeap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate(f32, usize);
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0).then(self.1.cmp(&other.1))
+    }
+}
+
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm == 0.0 {
+        vector.to_vec()
+    } else {
+        vector.iter().map(|x| x / norm).collect()
+    }
+}
+
+/// HNSW index over cosine distance.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    dim: usize,
+    /// Neighbors kept per node on upper layers (twice this on layer 0)
+    m: usize,
+    ef_construction: usize,
+    ef_search: usize,
+    /// Unit-normalized vectors, indexed by id
+    vectors: Vec<Vec<f32>>,
+    /// links[node][layer] = neighbor ids
+    links: Vec<Vec<Vec<usize>>>,
+    entry_point: Option<usize>,
+    rng_state: u64,
+}
+
+impl HnswIndex {
+    pub fn new(dim: usize, m: usize, ef_construction: usize, ef_search: usize) -> Self {
+        Self {
+            dim,
+            m: m.max(2),
+            ef_construction: ef_construction.max(1),
+            ef_search: ef_search.max(1),
+            vectors: Vec::new(),
+            links: Vec::new(),
+            entry_point: None,
+            rng_state: 0x2545f4914f6cdd1d,
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.dim
+    }
+
+    pub fn len(&self) -> usize {
+        self.vectors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vectors.is_empty()
+    }
+
+    fn distance(&self, query: &[f32], node: usize) -> f32 {
+        let dot: f32 = query.iter().zip(&self.vectors[node]).map(|(a, b)| a * b).sum();
+        1.0 - dot
+    }
+
+    /// Draw a layer with P(layer >= l) = (1/m)^l.
+    fn random_level(&mut self) -> usize {
+        // xorshift64*
+        self.rng_state ^= self.rng_state >> 12;
+        self.rng_state ^= self.rng_state << 25;
+        self.rng_state ^= self.rng_state >> 27;
+        let bits = self.rng_state.wrapping_mul(0x2545f4914f6cdd1d) >> 11;
+        let uniform = (bits as f64 + 1.0) / (1u64 << 53) as f64;
+        (-uniform.ln() / (self.m as f64).ln()).floor() as usize
+    }
+
+    fn top_layer(&self) -> usize {
+        self.entry_point.map(|e| self.links[e].len() - 1).unwrap_or(0)
+    }
+
+    /// Best-first search on one layer; returns up to `ef` nodes, closest first.
+    fn search_layer(&self, query: &[f32], entry: &[usize], ef: usize, layer: usize) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry.iter().copied().collect();
+        let mut candidates: BinaryHeap<Reverse<Candidate>> = BinaryHeap::new();
+        let mut nearest: BinaryHeap<Candidate> = BinaryHeap::new();
+
+        for &e in entry {
+            let c = Candidate(self.distance(query, e), e);
+            candidates.push(Reverse(c));
+            nearest.push(c);
+        }
+
+        while let Some(Reverse(current)) = candidates.pop() {
+            let furthest = nearest.peek().map(|c| c.0).unwrap_or(f32::INFINITY);
+            if current.0 > furthest && nearest.len() >= ef {
+                break;
+            }
+            for &neighbor in &self.links[current.1][layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let d = self.distance(query, neighbor);
+                let furthest = nearest.peek().map(|c| c.0).unwrap_or(f32::INFINITY);
+                if nearest.len() < ef || d < furthest {
+                    candidates.push(Reverse(Candidate(d, neighbor)));
+                    nearest.push(Candidate(d, neighbor));
+                    if nearest.len() > ef {
+                        nearest.pop();
+                    }
+                }
+            }
+        }
+
+        nearest.into_sorted_vec()
+    }
+
+    /// Keep only the `max` closest links of `node` on `layer`.
+    fn prune(&mut self, node: usize, layer: usize, max: usize) {
+        if self.links[node][layer].len() <= max {
+            return;
+        }
+        let base = self.vectors[node].clone();
+        let mut scored: Vec<Candidate> = self.links[node][layer]
+            .iter()
+            .map(|&n| Candidate(self.distance(&base, n), n))
+            .collect();
+        scored.sort();
+        self.links[node][layer] = scored.into_iter().take(max).map(|c| c.1).collect();
+    }
+
+    /// Insert a vector and return its id. Fails on dimension mismatch.
+    pub fn add(&mut self, vector: &[f32]) -> Result<usize, String> {
+        if vector.len() != self.dim {
+            return Err(format!(
+                "dimension mismatch: index has dim {}, got {}",
+                self.dim,
+                vector.len()
+            ));
+        }
+
+        let id = self.vectors.len();
+        let level = self.random_level();
+        let query = normalize(vector);
+        self.vectors.push(query.clone());
+        self.links.push(vec![Vec::new(); level + 1]);
+
+        let Some(entry) = self.entry_point else {
+            self.entry_point = Some(id);
+            return Ok(id);
+        };
+
+        let top = self.top_layer();
+        let mut nearest = vec![entry];
+        for layer in (level + 1..=top).rev() {
+            nearest = vec![self.search_layer(&query, &nearest, 1, layer)[0].1];
+        }
+
+        for layer in (0..=level.min(top)).rev() {
+            let found = self.search_layer(&query, &nearest, self.ef_construction, layer);
+            let max_links = if layer == 0 { 2 * self.m } else { self.m };
+            let neighbors: Vec<usize> = found.iter().take(self.m).map(|c| c.1).collect();
+
+            for &neighbor in &neighbors {
+                self.links[neighbor][layer].push(id);
+                self.prune(neighbor, layer, max_links);
+            }
+            self.links[id][layer] = neighbors;
+            nearest = found.into_iter().map(|c| c.1).collect();
+        }
+
+        if level > top {
+            self.entry_point = Some(id);
+        }
+        Ok(id)
+    }
+
+    /// Up to `k` approximate nearest neighbors as (id, cosine similarity), closest first.
+    pub fn search(&self, vector: &[f32], k: usize) -> Result<Vec<(usize, f32)>, String> {
+        if vector.len() != self.dim {
+            return Err(format!(
+                "dimension mismatch: index has dim {}, got {}",
+                self.dim,
+                vector.len()
+            ));
+        }
+        let Some(entry) = self.entry_point else {
+            return Ok(Vec::new());
+        };
+
+        let query = normalize(vector);
+        let mut nearest = vec![entry];
+        for layer in (1..=self.top_layer()).rev() {
+            nearest = vec![self.search_layer(&query, &nearest, 1, layer)[0].1];
+        }
+        Ok(self
+            .search_layer(&query, &nearest, self.ef_search.max(k), 0)
+            .into_iter()
+            .take(k)
+            .map(|Candidate(d, id)| (id, 1.0 - d))
+            .collect())
+    }
+
+    /// 1 - similarity to the closest indexed vector; 1.0 for an empty index.
+    pub fn novelty(&self, vector: &[f32]) -> Result<f32, String> {
+        Ok(self
+            .search(vector, 1)?
+            .first()
+            .map(|(_, sim)| 1.0 - sim)
+            .unwrap_or(1.0))
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self).map_err(std::io::Error::other)
+    }
+
+    /// Load an index written by `save`. A file that does not parse or
+    /// whose graph is inconsistent is `InvalidData`.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+        let reader = BufReader::new(File::open(path)?);
+        let index: Self = serde_json::from_reader(reader).map_err(|e| invalid(e.to_string()))?;
+        index.validate().map_err(invalid)?;
+        Ok(index)
+    }
+
+    /// Check what searches index into: vector dimensions, neighbor ids and
+    /// their layers, and the entry point.
+    fn validate(&self) -> Result<(), String> {
+        if self.dim == 0 || self.m < 2 || self.ef_construction == 0 || self.ef_search == 0 {
+            return Err("dim, ef_construction and ef_search must be positive and m at least 2".to_string());
+        }
+        if self.links.len() != self.vectors.len() {
+            return Err(format!("{} vectors but {} link lists", self.vectors.len(), self.links.len()));
+        }
+        for (node, (vector, layers)) in self.vectors.iter().zip(&self.links).enumerate() {
+            if vector.len() != self.dim {
+                return Err(format!("vector {} has dimension {}, expected {}", node, vector.len(), self.dim));
+            }
+            if layers.is_empty() {
+                return Err(format!("node {} has no layers", node));
+            }
+            for (layer, neighbors) in layers.iter().enumerate() {
+                if let Some(&bad) = neighbors
+                    .iter()
+                    .find(|&&n| n >= self.links.len() || self.links[n].len() <= layer)
+                {
+                    return Err(format!("node {} links to {} missing on layer {}", node, bad, layer));
+                }
+            }
+        }
+        match self.entry_point {
+            None if !self.vectors.is_empty() => Err("non-empty index without an entry point".to_string()),
+            Some(e) if e >= self.vectors.len() => Err(format!("entry point {} out of range", e)),
+            Some(e) if self.links.iter().any(|l| l.len() > self.links[e].len()) => {
+                Err(format!("entry point {} is not on the top layer", e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Approximate nearest-neighbor index over float32 embeddings (cosine).
+#[pyclass(name = "AnnIndex")]
+pub struct PyAnnIndex {
+    inner: HnswIndex,
+}
+
+#[pymethods]
+impl PyAnnIndex {
+    #[new]
+    #[pyo3(signature = (dim, m = 16, ef_construction = 200, ef_search = 50))]
+    fn new(dim: usize, m: usize, ef_construction: usize, ef_search: usize) -> Self {
+        Self {
+            inner: HnswIndex::new(dim, m, ef_construction, ef_search),
+        }
+    }
+
+    /// Add a vector and return its id.
+    fn add_vector(&mut self, vector: PyReadonlyArray1<f32>) -> PyResult<usize> {
+        let vector = vector.as_array().to_vec();
+        self.inner.add(&vector).map_err(PyValueError::new_err)
+    }
+
+    /// The `k` nearest indexed vectors as (id, cosine similarity) pairs.
+    #[pyo3(signature = (vector, k = 10))]
+    fn query_nearest(&self, vector: PyReadonlyArray1<f32>, k: usize) -> PyResult<Vec<(usize, f32)>> {
+        let vector = vector.as_array().to_vec();
+        self.inner.search(&vector, k).map_err(PyValueError::new_err)
+    }
+
+    /// Distance from `vector` to its nearest indexed neighbor (1.0 = fully novel).
+    fn novelty(&self, vector: PyReadonlyArray1<f32>) -> PyResult<f32> {
+        let vector = vector.as_array().to_vec();
+        self.inner.novelty(&vector).map_err(PyValueError::new_err)
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.inner
+            .save(Path::new(path))
+            .map_err(|e| PyIOError::new_err(format!("failed to save index to {}: {}", path, e)))
+    }
+
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        HnswIndex::load(Path::new(path))
+            .map(|inner| Self { inner })
+            .map_err(|e| {
+                let message = format!("failed to load index from {}: {}", path, e);
+                match e.kind() {
+                    std::io::ErrorKind::InvalidData => PyValueError::new_err(message),
+                    _ => PyIOError::new_err(message),
+                }
+            })
+    }
+
+    #[getter]
+    fn dim(&self) -> usize {
+        self.inner.dim()
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pseudo_random_vectors(count: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                (0..dim)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        ((state >> 40) as f32 / (1u64 << 24) as f32) - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn exact_nearest(vectors: &[Vec<f32>], query: &[f32]) -> usize {
+        let q = normalize(query);
+        (0..vectors.len())
+            .max_by(|&a, &b| {
+                let da: f32 = normalize(&vectors[a]).iter().zip(&q).map(|(x, y)| x * y).sum();
+                let db: f32 = normalize(&vectors[b]).iter().zip(&q).map(|(x, y)| x * y).sum();
+                da.total_cmp(&db)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_recall_against_exact_search() {
+        let vectors = pseudo_random_vectors(500, 16, 7);
+        let mut index = HnswIndex::new(16, 16, 100, 50);
+        for v in &vectors {
+            index.add(v).unwrap();
+        }
+        assert_eq!(index.len(), 500);
+
+        let queries = pseudo_random_vectors(50, 16, 99);
+        let hits = queries
+            .iter()
+            .filter(|q| index.search(q, 1).unwrap()[0].0 == exact_nearest(&vectors, q))
+            .count();
+        assert!(hits >= 45, "recall too low: {}/50", hits);
+
+        // An indexed vector is its own nearest neighbor and not novel
+        let (id, sim) = index.search(&vectors[42], 1).unwrap()[0];
+        assert_eq!(id, 42);
+        assert!((sim - 1.0).abs() < 1e-5);
+        assert!(index.novelty(&vectors[42]).unwrap() < 1e-5);
+    }
+
+    #[test]
+    fn test_empty_and_dimension_errors() {
+        let mut index = HnswIndex::new(3, 8, 50, 10);
+        assert!(index.search(&[1.0, 0.0, 0.0], 5).unwrap().is_empty());
+        assert_eq!(index.novelty(&[1.0, 0.0, 0.0]).unwrap(), 1.0);
+        assert!(index.add(&[1.0, 2.0]).is_err());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut index = HnswIndex::new(4, 8, 50, 10);
+        for v in pseudo_random_vectors(20, 4, 3) {
+            index.add(&v).unwrap();
+        }
+        let path = std::env::temp_dir().join(format!("flakestorm_ann_{}.json", std::process::id()));
+        index.save(&path).unwrap();
+        let loaded = HnswIndex::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        let query = [0.1, -0.2, 0.3, 0.4];
+        assert_eq!(index.search(&query, 3).unwrap(), loaded.search(&query, 3).unwrap());
+    }
+
+    #[test]
+    fn test_load_rejects_inconsistent_graphs() {
+        let mut index = HnswIndex::new(4, 8, 50, 10);
+        for v in pseudo_random_vectors(5, 4, 3) {
+            index.add(&v).unwrap();
+        }
+        assert!(index.validate().is_ok());
+        let path = std::env::temp_dir().join(format!("flakestorm_ann_bad_{}.json", std::process::id()));
+        let corruptions: [fn(&mut HnswIndex); 4] = [
+            |i| i.links[0][0].push(99),
+            |i| {
+                i.vectors[1].pop();
+            },
+            |i| i.entry_point = Some(5),
+            |i| {
+                i.links.pop();
+            },
+        ];
+        for corrupt in corruptions {
+            let mut bad = index.clone();
+            corrupt(&mut bad);
+            bad.save(&path).unwrap();
+            assert_eq!(HnswIndex::load(&path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        }
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -0,0 +1,175 @@
+//! Expected-answer matching
+//!
+//! Many prompts have several acceptable answers ("Paris", "It's Paris",
+//! "The capital is Paris"). A response passes when it is similar enough to
+//! any of them; the result names the closest candidate and how far above or
+//! below the threshold it landed, so borderline passes are visible.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::normalize::{normalize, NormalizeOptions};
+use crate::sanitize::{PyResponseSanitizer, Sanitizer};
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+use crate::similarity::{parse_metric, Metric};
+
+/// Best expected answer for one response
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnswerMatch {
+    pub passed: bool,
+    /// Index of the most similar candidate, None when there are no candidates
+    pub best_index: Option<usize>,
+    pub best_candidate: Option<String>,
+    pub score: f64,
+    /// score - threshold; negative when the response failed
+    pub margin: f64,
+}
+
+impl AnswerMatch {
+    pub fn to_check_result(&self) -> CheckResult {
+        let details = match &self.best_candidate {
+            Some(candidate) => format!(
+                "closest expected answer '{}' scored {:.3} (margin {:+.3})",
+                candidate, self.score, self.margin
+            ),
+            None => "no expected answers configured".to_string(),
+        };
+        CheckResult {
+            check_type: "expected_answers".to_string(),
+            passed: self.passed,
+            details,
+            severity: Severity::Error,
+        }
+    }
+}
+
+/// Match one response against every candidate; ties go to the earlier candidate.
+pub fn best_answer(response: &str, candidates: &[String], metric: Metric, threshold: f64, canonical: bool) -> AnswerMatch {
+    let opts = NormalizeOptions {
+        casefold: true,
+        ..Default::default()
+    };
+    let prepare = |s: &str| if canonical { normalize(s, &opts) } else { s.to_string() };
+    let response = prepare(response);
+
+    let mut best: Option<(usize, f64)> = None;
+    for (i, candidate) in candidates.iter().enumerate() {
+        let score = metric.similarity(&response, &prepare(candidate));
+        if best.is_none_or(|(_, s)| score > s) {
+            best = Some((i, score));
+        }
+    }
+
+    let (best_index, score) = match best {
+        Some((i, s)) => (Some(i), s),
+        None => (None, 0.0),
+    };
+    AnswerMatch {
+        passed: best_index.is_some() && score >= threshold,
+        best_index,
+        best_candidate: best_index.map(|i| candidates[i].clone()),
+        score,
+        margin: score - threshold,
+    }
+}
+
+/// Match every response of a run against the same candidate list, in
+/// parallel. With a `sanitizer`, responses are stripped of its noise first.
+/// `threshold` must be in [0, 1].
+pub fn match_all(
+    responses: &[String],
+    candidates: &[String],
+    metric: Metric,
+    threshold: f64,
+    canonical: bool,
+    sanitizer: Option<&Sanitizer>,
+) -> Result<Vec<AnswerMatch>, String> {
+    if !(0.0..=1.0).contains(&threshold) {
+        return Err(format!("threshold must be between 0 and 1, got {}", threshold));
+    }
+    Ok(responses
+        .par_iter()
+        .map(|r| match sanitizer {
+            Some(s) => best_answer(&s.sanitize(r), candidates, metric, threshold, canonical),
+            None => best_answer(r, candidates, metric, threshold, canonical),
+        })
+        .collect())
+}
+
+/// Check each response against a list of acceptable answers.
+///
+/// A response passes when its similarity to any candidate reaches
+/// `threshold`. With `normalize` (the default) both sides are compared on
+/// their canonical form: NFKC, case-folded, invisible characters removed
+/// and whitespace collapsed. A `sanitizer` (ResponseSanitizer) strips tool
+/// scaffolding and similar noise from each response before matching.
+#[pyfunction]
+#[pyo3(signature = (
+    responses, candidates, threshold = 0.8, metric = "levenshtein", normalize = true, sanitizer = None
+))]
+pub fn match_expected_answers(
+    py: Python<'_>,
+    responses: Vec<String>,
+    candidates: Vec<String>,
+    threshold: f64,
+    metric: &str,
+    normalize: bool,
+    sanitizer: Option<PyRef<'_, PyResponseSanitizer>>,
+) -> PyResult<Vec<AnswerMatch>> {
+    let metric = parse_metric(metric)?;
+    let sanitizer = sanitizer.map(|s| s.sanitizer().clone());
+    py.allow_threads(|| match_all(&responses, &candidates, metric, threshold, normalize, sanitizer.as_ref()))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidates() -> Vec<String> {
+        vec!["Paris".into(), "The capital of France is Paris".into()]
+    }
+
+    #[test]
+    fn test_best_candidate_and_margin() {
+        let m = best_answer("the capital of france is paris.", &candidates(), Metric::Levenshtein, 0.8, true);
+        assert!(m.passed);
+        assert_eq!(m.best_index, Some(1));
+        assert!(m.margin > 0.0 && (m.score - 0.8 - m.margin).abs() < 1e-12);
+
+        let m = best_answer("PARIS", &candidates(), Metric::Levenshtein, 0.8, true);
+        assert_eq!((m.best_index, m.score), (Some(0), 1.0));
+
+        let m = best_answer("PARIS", &candidates(), Metric::Levenshtein, 0.8, false);
+        assert!(!m.passed);
+        assert!(m.margin < 0.0);
+        assert!(!m.to_check_result().passed);
+    }
+
+    #[test]
+    fn test_batch_and_empty_candidates() {
+        let responses = vec!["Paris".to_string(), "Berlin".to_string()];
+        let results = match_all(&responses, &candidates(), Metric::Jaccard, 0.5, true, None).unwrap();
+        assert!(results[0].passed);
+        assert!(!results[1].passed);
+
+        let wrapped = vec!["<think>Recall the capital.</think>\nParis. I hope this helps!".to_string()];
+        assert!(!match_all(&wrapped, &candidates(), Metric::Levenshtein, 0.8, true, None).unwrap()[0].passed);
+        let sanitizer = Sanitizer::new(&["reasoning", "boilerplate"], &[]).unwrap();
+        let cleaned = match_all(&wrapped, &candidates(), Metric::Levenshtein, 0.8, true, Some(&sanitizer)).unwrap();
+        assert_eq!((cleaned[0].passed, cleaned[0].best_index), (true, Some(0)));
+
+        let none = best_answer("Paris", &[], Metric::Levenshtein, 0.5, true);
+        assert!(!none.passed);
+        assert_eq!(none.best_index, None);
+        assert!(none.to_check_result().details.contains("no expected answers"));
+        for threshold in [f64::NAN, -0.1, 1.5] {
+            let err = match_all(&responses, &candidates(), Metric::Jaccard, threshold, true, None).unwrap_err();
+            assert!(err.starts_with("threshold must be between 0 and 1"), "{}", err);
+        }
+    }
+}
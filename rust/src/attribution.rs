@@ -0,0 +1,334 @@
+//! Score attribution
+//!
+//! "Why is the score 0.74?" is answered by where the missing 0.26 went.
+//! The run is scored through its `ScoringConfig`, so the explained score
+//! is the robustness score. Every failed mutation loses its weight (or,
+//! in severity scoring, the part of its weight it did not earn); only the
+//! losses the strategy counts are kept (the weakest type's under
+//! worst_category, the k heaviest under top_k_failures), and each is
+//! attributed:
+//!
+//! - to its mutation type, in full,
+//! - to its failed checks and their severities, in proportion to the
+//!   severity penalties in severity scoring and evenly otherwise; a
+//!   mutation that failed without a failed check is attributed to
+//!   "(no failed check)".
+//!
+//! Every breakdown therefore adds up to the total loss, and the entries'
+//! `score_impact`s add up to the points the score is short of 1. Under
+//! weighted_average an entry's impact is how much higher the score would
+//! be had that category lost nothing.
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{CheckResult, MutationResult};
+use crate::severity::ScoringMode;
+use crate::strategy::ScoringConfig;
+
+/// Check category for failed mutations without a failed check
+pub const NO_FAILED_CHECK: &str = "(no failed check)";
+
+/// Weight lost by one mutation type or check type
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LossShare {
+    pub category: String,
+    /// Weight of the mutations in the category
+    pub weight: f64,
+    pub lost_weight: f64,
+    /// Fraction of the run's total loss
+    pub share_of_loss: f64,
+    /// Score points lost to the category: its share of the loss times
+    /// the points the score is short of 1
+    pub score_impact: f64,
+}
+
+/// One of the mutations that lost the most weight
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Offender {
+    /// Position in the results
+    pub index: usize,
+    pub mutation_type: String,
+    pub weight: f64,
+    pub lost_weight: f64,
+    pub failed_checks: Vec<String>,
+}
+
+/// Breakdown of the weighted score's losses
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreExplanation {
+    pub score: f64,
+    pub total_weight: f64,
+    pub lost_weight: f64,
+    /// Sorted by lost weight, largest first
+    pub by_mutation_type: Vec<LossShare>,
+    /// Sorted by lost weight, largest first
+    pub by_check_type: Vec<LossShare>,
+    /// By failed check severity, sorted by lost weight, largest first
+    pub by_severity: Vec<LossShare>,
+    /// Largest losses first, ties in result order
+    pub top_offenders: Vec<Offender>,
+}
+
+#[pymethods]
+impl ScoreExplanation {
+    fn __str__(&self) -> String {
+        self.summary()
+    }
+}
+
+impl ScoreExplanation {
+    /// One line: the score and its largest mutation-type and check losses.
+    pub fn summary(&self) -> String {
+        let mut summary = format!("score {:.3}", self.score);
+        for (label, shares) in [("type", &self.by_mutation_type), ("check", &self.by_check_type)] {
+            if let Some(top) = shares.first().filter(|s| s.lost_weight > 0.0) {
+                summary.push_str(&format!(
+                    "; largest {} loss: {} (-{:.3})",
+                    label, top.category, top.score_impact
+                ));
+            }
+        }
+        summary
+    }
+}
+
+fn loss_shares(groups: BTreeMap<&str, (f64, f64)>, lost_weight: f64, score_lost: f64) -> Vec<LossShare> {
+    let mut shares: Vec<LossShare> = groups
+        .into_iter()
+        .map(|(category, (weight, lost))| {
+            let share_of_loss = if lost_weight > 0.0 { lost / lost_weight } else { 0.0 };
+            LossShare {
+                category: category.to_string(),
+                weight,
+                lost_weight: lost,
+                share_of_loss,
+                score_impact: share_of_loss * score_lost,
+            }
+        })
+        .collect();
+    shares.sort_by(|a, b| b.lost_weight.total_cmp(&a.lost_weight));
+    shares
+}
+
+/// Fraction of a mutation's loss attributed to each of its failed checks:
+/// in proportion to their severity penalties in severity scoring, evenly
+/// otherwise.
+fn check_shares(failed: &[&CheckResult], mode: &ScoringMode) -> Vec<f64> {
+    if let ScoringMode::Severity(penalties) = mode {
+        let total: f64 = failed.iter().map(|c| penalties.of(c.severity)).sum();
+        if total > 0.0 {
+            return failed.iter().map(|c| penalties.of(c.severity) / total).collect();
+        }
+    }
+    vec![1.0 / failed.len() as f64; failed.len()]
+}
+
+/// Attribute the losses of the score `scoring` gives `results`, keeping
+/// the `top_n` largest individual losses.
+pub fn explain_score(
+    results: &[MutationResult],
+    scoring: &ScoringConfig,
+    top_n: usize,
+) -> Result<ScoreExplanation, String> {
+    let credits = scoring.credits(results)?;
+    let score = scoring.strategy.score(&credits);
+    let losses = scoring.strategy.counted_losses(&credits);
+    let mut by_type: BTreeMap<&str, (f64, f64)> = BTreeMap::new();
+    let mut by_check: BTreeMap<&str, (f64, f64)> = BTreeMap::new();
+    let mut by_severity: BTreeMap<&str, (f64, f64)> = BTreeMap::new();
+    let mut offenders: Vec<Offender> = Vec::new();
+    let (mut total_weight, mut lost_weight) = (0.0, 0.0);
+
+    for (index, (result, &lost)) in results.iter().zip(&losses).enumerate() {
+        total_weight += result.weight;
+        lost_weight += lost;
+
+        let entry = by_type.entry(&result.mutation_type).or_default();
+        entry.0 += result.weight;
+        entry.1 += lost;
+
+        let failed: Vec<&CheckResult> = result.checks.iter().filter(|c| !c.passed).collect();
+        for check in &result.checks {
+            by_check.entry(&check.check_type).or_default().0 += result.weight;
+            by_severity.entry(check.severity.name()).or_default().0 += result.weight;
+        }
+        if lost > 0.0 {
+            if failed.is_empty() {
+                for groups in [&mut by_check, &mut by_severity] {
+                    let entry = groups.entry(NO_FAILED_CHECK).or_default();
+                    entry.0 += result.weight;
+                    entry.1 += lost;
+                }
+            }
+            for (check, share) in failed.iter().zip(check_shares(&failed, &scoring.mode)) {
+                by_check.entry(&check.check_type).or_default().1 += lost * share;
+                by_severity.entry(check.severity.name()).or_default().1 += lost * share;
+            }
+            offenders.push(Offender {
+                index,
+                mutation_type: result.mutation_type.clone(),
+                weight: result.weight,
+                lost_weight: lost,
+                failed_checks: failed.iter().map(|c| c.check_type.clone()).collect(),
+            });
+        }
+    }
+
+    offenders.sort_by(|a, b| b.lost_weight.total_cmp(&a.lost_weight));
+    offenders.truncate(top_n);
+    let shares = |groups| loss_shares(groups, lost_weight, 1.0 - score);
+    Ok(ScoreExplanation {
+        score,
+        total_weight,
+        lost_weight,
+        by_mutation_type: shares(by_type),
+        by_check_type: shares(by_check),
+        by_severity: shares(by_severity),
+        top_offenders: offenders,
+    })
+}
+
+/// Where a run's score was lost.
+///
+/// Losses of the score `scoring` gives `results` (by default the weighted
+/// pass rate) are broken down by mutation type, by check type and by
+/// check severity, and the `top_n` mutations that lost the most weight
+/// are listed.
+#[pyfunction]
+#[pyo3(name = "explain_score", signature = (results, top_n = 5, scoring = None))]
+pub fn py_explain_score(
+    py: Python<'_>,
+    results: Vec<MutationResult>,
+    top_n: usize,
+    scoring: Option<ScoringConfig>,
+) -> PyResult<ScoreExplanation> {
+    py.allow_threads(|| explain_score(&results, &scoring.unwrap_or_default(), top_n))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::{calculate_statistics_with, StatisticsConfig};
+    use crate::severity::Severity;
+    use crate::strategy::ScoringStrategy;
+
+    fn result(mutation_type: &str, weight: f64, failed_checks: &[&str]) -> MutationResult {
+        MutationResult {
+            mutation_type: mutation_type.to_string(),
+            passed: failed_checks.is_empty(),
+            weight,
+            latency_ms: 10.0,
+            checks: failed_checks
+                .iter()
+                .map(|c| CheckResult {
+                    check_type: c.to_string(),
+                    passed: false,
+                    details: String::new(),
+                    severity: Severity::Error,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_losses_add_up() {
+        let results = vec![
+            result("noise", 1.0, &[]),
+            result("noise", 1.0, &["contains"]),
+            result("prompt_injection", 3.0, &["canary_leak", "contains"]),
+            result("paraphrase", 1.0, &[]),
+        ];
+        let explanation = explain_score(&results, &ScoringConfig::default(), 1).unwrap();
+        assert!((explanation.score - 2.0 / 6.0).abs() < 1e-12);
+        assert_eq!(explanation.lost_weight, 4.0);
+
+        let types: Vec<(&str, f64)> = explanation
+            .by_mutation_type
+            .iter()
+            .map(|s| (s.category.as_str(), s.lost_weight))
+            .collect();
+        assert_eq!(
+            types,
+            vec![("prompt_injection", 3.0), ("noise", 1.0), ("paraphrase", 0.0)]
+        );
+        let contains = &explanation.by_check_type[0];
+        assert_eq!((contains.category.as_str(), contains.lost_weight), ("contains", 2.5));
+        assert!((contains.share_of_loss - 0.625).abs() < 1e-12);
+        let total: f64 = explanation.by_check_type.iter().map(|s| s.lost_weight).sum();
+        assert!((total - explanation.lost_weight).abs() < 1e-12);
+
+        assert_eq!(explanation.top_offenders.len(), 1);
+        assert_eq!(explanation.top_offenders[0].index, 2);
+        assert!(explanation
+            .summary()
+            .contains("largest type loss: prompt_injection (-0.500)"));
+    }
+
+    #[test]
+    fn test_failures_without_checks() {
+        let mut failed = result("noise", 2.0, &[]);
+        failed.passed = false;
+        let explanation = explain_score(&[failed, result("noise", 2.0, &[])], &ScoringConfig::default(), 5).unwrap();
+        assert_eq!(explanation.by_check_type[0].category, NO_FAILED_CHECK);
+        assert_eq!(explanation.by_check_type[0].score_impact, 0.5);
+        assert_eq!(explain_score(&[], &ScoringConfig::default(), 5).unwrap().score, 0.0);
+    }
+
+    #[test]
+    fn test_scoring_config_and_severities() {
+        let mut results = vec![
+            result("noise", 1.0, &["tone"]),
+            result("noise", 1.0, &[]),
+            result("prompt_injection", 2.0, &["canary_leak", "tone"]),
+        ];
+        results[0].checks[0].severity = Severity::Warn;
+        results[2].checks[0].severity = Severity::Critical;
+        results[2].checks[1].severity = Severity::Warn;
+        for strategy in [ScoringStrategy::WeightedAverage, ScoringStrategy::WorstCategory] {
+            let scoring = ScoringConfig {
+                mode: ScoringMode::Severity(Default::default()),
+                strategy,
+            };
+            let config = StatisticsConfig {
+                scoring,
+                ..Default::default()
+            };
+            let explanation = explain_score(&results, &scoring, 5).unwrap();
+            let stats = calculate_statistics_with(&results, &config).unwrap();
+            assert_eq!(explanation.score, stats.robustness_score);
+            let impact: f64 = explanation.by_severity.iter().map(|s| s.score_impact).sum();
+            assert!((impact - (1.0 - explanation.score)).abs() < 1e-12);
+        }
+
+        let scoring = ScoringConfig {
+            mode: ScoringMode::Severity(Default::default()),
+            strategy: ScoringStrategy::WeightedAverage,
+        };
+        let explanation = explain_score(&results, &scoring, 5).unwrap();
+        // The critical check costs four times what the warning beside it
+        // does, and the lone warning costs a quarter of its mutation
+        let severities: Vec<(&str, f64)> = explanation
+            .by_severity
+            .iter()
+            .map(|s| (s.category.as_str(), s.lost_weight))
+            .collect();
+        assert_eq!(severities, vec![("critical", 1.6), ("warn", 0.65)]);
+        let worst = ScoringConfig {
+            strategy: ScoringStrategy::WorstCategory,
+            ..scoring
+        };
+        // Only the weakest type's losses count under worst_category
+        let explanation = explain_score(&results, &worst, 5).unwrap();
+        assert_eq!(explanation.top_offenders.len(), 1);
+        assert_eq!(explanation.by_mutation_type[0].score_impact, 1.0);
+    }
+}
@@ -0,0 +1,378 @@
+//! Baseline store with EWMA trends
+//!
+//! Comparing a run with the single previous run mistakes one noisy night
+//! for a trend. The baseline store keeps, per mutation type, an
+//! exponentially weighted moving average of the pass rate and its
+//! exponentially weighted variance, updated once per run:
+//!
+//! ```text
+//! diff = x - mean
+//! mean = mean + alpha * diff
+//! var  = (1 - alpha) * (var + alpha * diff²)
+//! ```
+//!
+//! Drift is a pass rate more than `z_threshold` standard deviations from
+//! the smoothed trend, once a type has `min_runs` runs of history. The
+//! store is persisted as versioned JSON.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::TestStatistics;
+use crate::serialization::write_atomically;
+
+/// On-disk format version written by `save`
+pub const BASELINE_FORMAT_VERSION: u32 = 1;
+
+/// Smoothed pass-rate history of one mutation type
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeTrend {
+    pub mutation_type: String,
+    pub runs: usize,
+    pub last_pass_rate: f64,
+    pub ewma: f64,
+    pub ewm_variance: f64,
+    pub last_run_id: String,
+}
+
+/// A pass rate compared with its trend
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrendDrift {
+    pub mutation_type: String,
+    pub pass_rate: f64,
+    pub ewma: f64,
+    /// Trend standard deviation, floored at `min_std`
+    pub std_dev: f64,
+    /// Signed distance from the trend in standard deviations
+    pub z_score: f64,
+    pub drifted: bool,
+}
+
+/// Drift detection settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct DriftSettings {
+    pub z_threshold: f64,
+    /// Types with fewer runs of history are never reported as drifted
+    pub min_runs: usize,
+    /// Floor for the standard deviation so a perfectly flat history does
+    /// not turn every wobble into drift
+    pub min_std: f64,
+}
+
+impl Default for DriftSettings {
+    fn default() -> Self {
+        Self {
+            z_threshold: 3.0,
+            min_runs: 3,
+            min_std: 0.02,
+        }
+    }
+}
+
+/// Per-type EWMA pass rates across runs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineStore {
+    pub format_version: u32,
+    /// Weight of the newest run, in (0, 1]
+    pub alpha: f64,
+    pub runs: usize,
+    pub last_run_id: Option<String>,
+    types: BTreeMap<String, TypeTrend>,
+}
+
+impl BaselineStore {
+    pub fn new(alpha: f64) -> Result<Self, String> {
+        if !(alpha > 0.0 && alpha <= 1.0) {
+            return Err(format!("alpha must be in (0, 1], got {}", alpha));
+        }
+        Ok(Self {
+            format_version: BASELINE_FORMAT_VERSION,
+            alpha,
+            runs: 0,
+            last_run_id: None,
+            types: BTreeMap::new(),
+        })
+    }
+
+    pub fn trend(&self, mutation_type: &str) -> Option<&TypeTrend> {
+        self.types.get(mutation_type)
+    }
+
+    /// Trends sorted by mutation type.
+    pub fn trends(&self) -> Vec<&TypeTrend> {
+        self.types.values().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.types.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.types.is_empty()
+    }
+
+    /// Fold one run's (mutation type, pass rate) pairs into the trends.
+    pub fn update(&mut self, run_id: &str, pass_rates: &[(String, f64)]) -> Result<(), String> {
+        if self.last_run_id.as_deref() == Some(run_id) {
+            return Err(format!("run '{}' is already the latest in the baseline", run_id));
+        }
+        if let Some((t, rate)) = pass_rates.iter().find(|(_, r)| !(0.0..=1.0).contains(r)) {
+            return Err(format!("pass rate for '{}' must be between 0 and 1, got {}", t, rate));
+        }
+        let alpha = self.alpha;
+        for (mutation_type, rate) in pass_rates {
+            let trend = self.types.entry(mutation_type.clone()).or_insert_with(|| TypeTrend {
+                mutation_type: mutation_type.clone(),
+                runs: 0,
+                last_pass_rate: *rate,
+                ewma: *rate,
+                ewm_variance: 0.0,
+                last_run_id: String::new(),
+            });
+            if trend.runs > 0 {
+                let diff = rate - trend.ewma;
+                trend.ewma += alpha * diff;
+                trend.ewm_variance = (1.0 - alpha) * (trend.ewm_variance + alpha * diff * diff);
+            }
+            trend.runs += 1;
+            trend.last_pass_rate = *rate;
+            trend.last_run_id = run_id.to_string();
+        }
+        self.runs += 1;
+        self.last_run_id = Some(run_id.to_string());
+        Ok(())
+    }
+
+    /// `update` with the per-type pass rates of a run's statistics.
+    pub fn update_from_statistics(&mut self, run_id: &str, stats: &TestStatistics) -> Result<(), String> {
+        let rates: Vec<(String, f64)> = stats
+            .by_type
+            .iter()
+            .map(|t| (t.mutation_type.clone(), t.pass_rate))
+            .collect();
+        self.update(run_id, &rates)
+    }
+
+    /// Compare pass rates with the current trends, before updating them.
+    /// Types without a trend are skipped.
+    pub fn drift(&self, pass_rates: &[(String, f64)], settings: &DriftSettings) -> Vec<TrendDrift> {
+        let mut drifts: Vec<TrendDrift> = pass_rates
+            .iter()
+            .filter_map(|(mutation_type, rate)| {
+                let trend = self.types.get(mutation_type)?;
+                let std_dev = trend.ewm_variance.sqrt().max(settings.min_std);
+                let z_score = (rate - trend.ewma) / std_dev;
+                Some(TrendDrift {
+                    mutation_type: mutation_type.clone(),
+                    pass_rate: *rate,
+                    ewma: trend.ewma,
+                    std_dev,
+                    z_score,
+                    drifted: trend.runs >= settings.min_runs && z_score.abs() >= settings.z_threshold,
+                })
+            })
+            .collect();
+        drifts.sort_by(|a, b| a.mutation_type.cmp(&b.mutation_type));
+        drifts
+    }
+
+    /// Write the store to `path` through a temporary file in the same
+    /// directory, so an interrupted save leaves the previous store intact.
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        write_atomically(path, |out| {
+            serde_json::to_writer_pretty(out, self).map_err(std::io::Error::other)
+        })
+    }
+
+    /// Read a store written by `save`. A file that does not parse, has
+    /// another format version or holds an alpha or pass rate out of range
+    /// fails with `InvalidData`.
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let invalid = |e: String| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+        let reader = BufReader::new(File::open(path)?);
+        let store: Self = serde_json::from_reader(reader).map_err(|e| invalid(e.to_string()))?;
+        if store.format_version != BASELINE_FORMAT_VERSION {
+            return Err(invalid(format!(
+                "unsupported baseline format version {} (expected {})",
+                store.format_version, BASELINE_FORMAT_VERSION
+            )));
+        }
+        BaselineStore::new(store.alpha).map_err(invalid)?;
+        if let Some(t) = store.types.values().find(|t| {
+            let rates_valid = [t.last_pass_rate, t.ewma].iter().all(|r| (0.0..=1.0).contains(r));
+            !(rates_valid && t.ewm_variance.is_finite() && t.ewm_variance >= 0.0)
+        }) {
+            return Err(invalid(format!("trend for '{}' is out of range", t.mutation_type)));
+        }
+        Ok(store)
+    }
+}
+
+/// Per-mutation-type pass-rate trends persisted across runs.
+#[pyclass(name = "BaselineStore")]
+#[derive(Debug, Clone)]
+pub struct PyBaselineStore {
+    inner: BaselineStore,
+}
+
+#[pymethods]
+impl PyBaselineStore {
+    #[new]
+    #[pyo3(signature = (alpha = 0.3))]
+    fn new(alpha: f64) -> PyResult<Self> {
+        BaselineStore::new(alpha)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    #[getter]
+    fn alpha(&self) -> f64 {
+        self.inner.alpha
+    }
+
+    #[getter]
+    fn runs(&self) -> usize {
+        self.inner.runs
+    }
+
+    #[getter]
+    fn last_run_id(&self) -> Option<String> {
+        self.inner.last_run_id.clone()
+    }
+
+    /// Fold a run's {mutation_type: pass_rate} into the trends.
+    fn update(&mut self, run_id: &str, pass_rates: BTreeMap<String, f64>) -> PyResult<()> {
+        let rates: Vec<(String, f64)> = pass_rates.into_iter().collect();
+        self.inner.update(run_id, &rates).map_err(PyValueError::new_err)
+    }
+
+    /// Fold the per-type pass rates of a run's statistics into the trends.
+    #[pyo3(name = "update_from_statistics")]
+    fn py_update_from_statistics(&mut self, run_id: &str, stats: TestStatistics) -> PyResult<()> {
+        self.inner
+            .update_from_statistics(run_id, &stats)
+            .map_err(PyValueError::new_err)
+    }
+
+    /// The trend for one mutation type, if it has history.
+    fn query(&self, mutation_type: &str) -> Option<TypeTrend> {
+        self.inner.trend(mutation_type).cloned()
+    }
+
+    fn trends(&self) -> Vec<TypeTrend> {
+        self.inner.trends().into_iter().cloned().collect()
+    }
+
+    /// Compare {mutation_type: pass_rate} with the trends before updating.
+    #[pyo3(signature = (pass_rates, z_threshold = 3.0, min_runs = 3, min_std = 0.02))]
+    fn drift(
+        &self,
+        pass_rates: BTreeMap<String, f64>,
+        z_threshold: f64,
+        min_runs: usize,
+        min_std: f64,
+    ) -> Vec<TrendDrift> {
+        let rates: Vec<(String, f64)> = pass_rates.into_iter().collect();
+        let settings = DriftSettings {
+            z_threshold,
+            min_runs,
+            min_std,
+        };
+        self.inner.drift(&rates, &settings)
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.inner
+            .save(Path::new(path))
+            .map_err(|e| PyIOError::new_err(format!("failed to save baseline to {}: {}", path, e)))
+    }
+
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        BaselineStore::load(Path::new(path))
+            .map(|inner| Self { inner })
+            .map_err(|e| {
+                let message = format!("failed to load baseline from {}: {}", path, e);
+                match e.kind() {
+                    std::io::ErrorKind::InvalidData => PyValueError::new_err(message),
+                    _ => PyIOError::new_err(message),
+                }
+            })
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rates(items: &[(&str, f64)]) -> Vec<(String, f64)> {
+        items.iter().map(|(t, r)| (t.to_string(), *r)).collect()
+    }
+
+    #[test]
+    fn test_ewma_update() {
+        let mut store = BaselineStore::new(0.5).unwrap();
+        store.update("r1", &rates(&[("noise", 0.8)])).unwrap();
+        store
+            .update("r2", &rates(&[("noise", 0.6), ("paraphrase", 1.0)]))
+            .unwrap();
+        let noise = store.trend("noise").unwrap();
+        assert_eq!(noise.runs, 2);
+        assert!((noise.ewma - 0.7).abs() < 1e-12);
+        // (1 - 0.5) * (0 + 0.5 * 0.2²)
+        assert!((noise.ewm_variance - 0.01).abs() < 1e-12);
+        assert_eq!(store.trend("paraphrase").unwrap().runs, 1);
+        assert!(store.update("r2", &rates(&[("noise", 0.5)])).is_err());
+        assert!(store.update("r3", &rates(&[("noise", 1.5)])).is_err());
+        assert!(BaselineStore::new(0.0).is_err());
+    }
+
+    #[test]
+    fn test_drift_against_trend() {
+        let mut store = BaselineStore::new(0.3).unwrap();
+        for (i, r) in [0.90, 0.92, 0.89, 0.91, 0.90].iter().enumerate() {
+            store.update(&format!("r{}", i), &rates(&[("noise", *r)])).unwrap();
+        }
+        let drifts = store.drift(&rates(&[("noise", 0.70), ("unknown", 0.1)]), &DriftSettings::default());
+        assert_eq!(drifts.len(), 1);
+        assert!(drifts[0].drifted && drifts[0].z_score < -3.0);
+        assert!(!store.drift(&rates(&[("noise", 0.905)]), &DriftSettings::default())[0].drifted);
+    }
+
+    #[test]
+    fn test_persistence() {
+        let mut store = BaselineStore::new(0.3).unwrap();
+        store.update("r1", &rates(&[("noise", 0.9)])).unwrap();
+        let path = std::env::temp_dir().join(format!("flakestorm-baseline-{}.json", std::process::id()));
+        store.save(&path).unwrap();
+        let loaded = BaselineStore::load(&path).unwrap();
+        assert_eq!(loaded, store);
+
+        let mut future = store.clone();
+        future.format_version = 99;
+        future.save(&path).unwrap();
+        assert_eq!(BaselineStore::load(&path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        let mut reckless = store.clone();
+        reckless.alpha = 0.0;
+        reckless.save(&path).unwrap();
+        assert!(BaselineStore::load(&path).unwrap_err().to_string().contains("alpha"));
+        let mut corrupt = store.clone();
+        corrupt.types.get_mut("noise").unwrap().ewma = 1.5;
+        corrupt.save(&path).unwrap();
+        assert!(BaselineStore::load(&path).is_err());
+        std::fs::remove_file(&path).ok();
+    }
+}
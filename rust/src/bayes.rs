@@ -0,0 +1,281 @@
+//! Bayesian robustness estimation
+//!
+//! Small nightly runs make the raw pass rate swing: 9/10 one night, 6/10
+//! the next. Treating each mutation as a Bernoulli trial with a Beta prior
+//! on the pass probability gives a posterior Beta(α + passed, β + failed)
+//! whose mean moves only as far as the evidence justifies. The prior is
+//! either set directly (Beta(1, 1) is uniform) or seeded from earlier runs,
+//! whose counts are discounted by `prior_weight` so old history cannot
+//! drown out a real regression.
+//!
+//! Weighted results count as their Kish effective sample size, as in the
+//! confidence intervals, so equal weights reduce to plain pass/fail counts.
+//! The credible interval is equal-tailed and computed from the exact Beta
+//! quantiles.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::confidence::{effective_n, weighted_rate};
+
+/// Beta prior on the pass probability
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BetaPrior {
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+impl Default for BetaPrior {
+    fn default() -> Self {
+        Self { alpha: 1.0, beta: 1.0 }
+    }
+}
+
+impl BetaPrior {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.alpha > 0.0 && self.beta > 0.0 && self.alpha.is_finite() && self.beta.is_finite()) {
+            return Err(format!(
+                "prior parameters must be positive, got alpha {} and beta {}",
+                self.alpha, self.beta
+            ));
+        }
+        Ok(())
+    }
+
+    /// This prior updated with (passed, total) counts from earlier runs,
+    /// each count scaled by `weight` (0 ignores history, 1 trusts it fully).
+    pub fn seeded(&self, previous_runs: &[(f64, f64)], weight: f64) -> Result<Self, String> {
+        if !(0.0..=1.0).contains(&weight) {
+            return Err(format!("prior_weight must be between 0 and 1, got {}", weight));
+        }
+        if let Some((passed, total)) = previous_runs
+            .iter()
+            .find(|(p, t)| !(*p >= 0.0 && p <= t && t.is_finite()))
+        {
+            return Err(format!("previous run has {} passed out of {}", passed, total));
+        }
+        let passed: f64 = previous_runs.iter().map(|(p, _)| p).sum();
+        let failed: f64 = previous_runs.iter().map(|(p, t)| t - p).sum();
+        Ok(Self {
+            alpha: self.alpha + weight * passed,
+            beta: self.beta + weight * failed,
+        })
+    }
+}
+
+/// Posterior robustness estimate
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BayesianEstimate {
+    pub posterior_mean: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub credible: f64,
+    /// Posterior Beta parameters; pass them as the next run's prior
+    pub alpha: f64,
+    pub beta: f64,
+    pub prior_alpha: f64,
+    pub prior_beta: f64,
+    /// Raw weighted pass rate of this run
+    pub observed_score: f64,
+    /// Effective number of trials observed in this run
+    pub observed_n: f64,
+}
+
+/// ln Γ(x) for x > 0 (Lanczos, g = 7).
+pub(crate) fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFS[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+/// Continued fraction for the incomplete beta function (modified Lentz).
+fn beta_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=300 {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for numerator in [
+            m * (b - m) * x / ((a + m2 - 1.0) * (a + m2)),
+            -(a + m) * (a + b + m) * x / ((a + m2) * (a + m2 + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    h
+}
+
+/// Regularized incomplete beta I_x(a, b), the Beta(a, b) CDF at x.
+pub(crate) fn beta_cdf(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        ln_front.exp() * beta_fraction(x, a, b) / a
+    } else {
+        1.0 - ln_front.exp() * beta_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Beta(a, b) quantile by bisection on the CDF.
+pub(crate) fn beta_quantile(p: f64, a: f64, b: f64) -> f64 {
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = 0.5 * (lo + hi);
+        if beta_cdf(mid, a, b) < p {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    0.5 * (lo + hi)
+}
+
+/// Posterior for `passed` successes in `n` trials under `prior`.
+pub fn beta_posterior(passed: f64, n: f64, prior: &BetaPrior, credible: f64) -> Result<BayesianEstimate, String> {
+    prior.validate()?;
+    if credible <= 0.0 || credible >= 1.0 || credible.is_nan() {
+        return Err(format!("credible must be between 0 and 1, got {}", credible));
+    }
+    let alpha = prior.alpha + passed;
+    let beta = prior.beta + (n - passed);
+    let tail = (1.0 - credible) / 2.0;
+    Ok(BayesianEstimate {
+        posterior_mean: alpha / (alpha + beta),
+        lower: beta_quantile(tail, alpha, beta),
+        upper: beta_quantile(1.0 - tail, alpha, beta),
+        credible,
+        alpha,
+        beta,
+        prior_alpha: prior.alpha,
+        prior_beta: prior.beta,
+        observed_score: if n > 0.0 { passed / n } else { 0.0 },
+        observed_n: n,
+    })
+}
+
+/// Posterior robustness for (passed, weight) pairs.
+pub fn bayesian_estimate(results: &[(bool, f64)], prior: &BetaPrior, credible: f64) -> Result<BayesianEstimate, String> {
+    if let Some((_, w)) = results.iter().find(|(_, w)| *w < 0.0 || w.is_nan()) {
+        return Err(format!("weights must be non-negative, got {}", w));
+    }
+    let n = effective_n(results.iter().map(|r| r.1));
+    beta_posterior(weighted_rate(results) * n, n, prior, credible)
+}
+
+/// Bayesian robustness score with a Beta prior.
+///
+/// `results` are (passed, weight) pairs. The prior is Beta(`prior_alpha`,
+/// `prior_beta`), updated with `previous_runs` (passed, total) counts
+/// scaled by `prior_weight` when given.
+#[pyfunction]
+#[pyo3(signature = (results, prior_alpha = 1.0, prior_beta = 1.0, previous_runs = None, prior_weight = 0.5, credible = 0.95))]
+pub fn bayesian_robustness(
+    results: Vec<(bool, f64)>,
+    prior_alpha: f64,
+    prior_beta: f64,
+    previous_runs: Option<Vec<(f64, f64)>>,
+    prior_weight: f64,
+    credible: f64,
+) -> PyResult<BayesianEstimate> {
+    let mut prior = BetaPrior {
+        alpha: prior_alpha,
+        beta: prior_beta,
+    };
+    if let Some(runs) = previous_runs {
+        prior = prior.seeded(&runs, prior_weight).map_err(PyValueError::new_err)?;
+    }
+    bayesian_estimate(&results, &prior, credible).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unweighted(passed: usize, total: usize) -> Vec<(bool, f64)> {
+        (0..total).map(|i| (i < passed, 1.0)).collect()
+    }
+
+    #[test]
+    fn test_beta_distribution() {
+        assert!((ln_gamma(5.0) - 24f64.ln()).abs() < 1e-12);
+        assert!((beta_cdf(0.3, 1.0, 1.0) - 0.3).abs() < 1e-12);
+        // Beta(2, 2) CDF is 3x² - 2x³
+        assert!((beta_cdf(0.4, 2.0, 2.0) - 0.352).abs() < 1e-12);
+        assert!((beta_quantile(0.352, 2.0, 2.0) - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_posterior() {
+        let estimate = bayesian_estimate(&unweighted(9, 10), &BetaPrior::default(), 0.95).unwrap();
+        assert_eq!((estimate.alpha, estimate.beta), (10.0, 2.0));
+        assert!((estimate.posterior_mean - 10.0 / 12.0).abs() < 1e-12);
+        assert!(estimate.lower < estimate.posterior_mean && estimate.posterior_mean < estimate.upper);
+        assert!((estimate.observed_score - 0.9).abs() < 1e-12);
+
+        let empty = bayesian_estimate(&[], &BetaPrior::default(), 0.95).unwrap();
+        assert_eq!(empty.posterior_mean, 0.5);
+        assert!((empty.lower - 0.025).abs() < 1e-9);
+        assert!(bayesian_estimate(&[], &BetaPrior { alpha: 0.0, beta: 1.0 }, 0.95).is_err());
+    }
+
+    #[test]
+    fn test_seeded_prior_stabilizes() {
+        let history = [(90.0, 100.0), (88.0, 100.0)];
+        let prior = BetaPrior::default().seeded(&history, 0.5).unwrap();
+        assert_eq!((prior.alpha, prior.beta), (90.0, 12.0));
+
+        // A bad small night moves the estimate far less than the raw rate
+        let night = unweighted(6, 10);
+        let seeded = bayesian_estimate(&night, &prior, 0.95).unwrap();
+        let flat = bayesian_estimate(&night, &BetaPrior::default(), 0.95).unwrap();
+        assert!(seeded.posterior_mean > 0.85);
+        assert!(seeded.upper - seeded.lower < flat.upper - flat.lower);
+
+        assert!(BetaPrior::default().seeded(&[(5.0, 3.0)], 0.5).is_err());
+        assert!(BetaPrior::default().seeded(&history, 2.0).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! User-defined checks called back from the parallel engine
+//!
+//! Some invariants can only be written in Python. A check callback wraps a
+//! Python callable so it runs inside the same rayon pipeline as the native
+//! checks: each worker thread takes the GIL for the duration of one call
+//! and releases it afterwards, so native checks keep running in parallel
+//! while a callback waits for the GIL.
+//!
+//! The callable receives the output text and returns a bool, a
+//! `(passed, details)` tuple or a `CheckResult`. An exception fails the
+//! check with the exception as details instead of aborting the run, but a
+//! `BaseException` that is not an `Exception` (KeyboardInterrupt,
+//! SystemExit) stops all callbacks: later calls are skipped and the
+//! exception is re-raised once the evaluation returns to Python. The
+//! timeout is soft: a Python call cannot be interrupted safely, so it is
+//! only compared after the call returns, and a call that overruns
+//! completes but its result is recorded as a failure.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+
+/// Check body: output text to (passed, details), or an error message
+pub type CheckFn = dyn Fn(&str) -> Result<(bool, String), String> + Send + Sync;
+
+/// Set while a KeyboardInterrupt, SystemExit or other non-Exception raised
+/// by a callback waits to be re-raised
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static PENDING_INTERRUPT: Mutex<Option<PyErr>> = Mutex::new(None);
+
+/// The KeyboardInterrupt or SystemExit a callback raised since the last
+/// call, if any; taking it lets callbacks run again.
+pub fn take_interrupt() -> Option<PyErr> {
+    let mut pending = PENDING_INTERRUPT.lock().unwrap_or_else(|p| p.into_inner());
+    INTERRUPTED.store(false, Ordering::SeqCst);
+    pending.take()
+}
+
+/// A user-supplied check with exception capture and a soft timeout
+#[derive(Clone)]
+pub struct CheckCallback {
+    func: Arc<CheckFn>,
+    /// Soft limit, compared once the call has returned
+    timeout: Option<Duration>,
+}
+
+impl fmt::Debug for CheckCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CheckCallback").field("timeout", &self.timeout).finish_non_exhaustive()
+    }
+}
+
+impl CheckCallback {
+    pub fn new(func: Arc<CheckFn>, timeout: Option<Duration>) -> Self {
+        Self { func, timeout }
+    }
+
+    /// Wrap a Python callable `func(output) -> bool | (bool, str) | CheckResult`.
+    ///
+    /// `timeout` is soft, see the module docs.
+    pub fn from_python(func: PyObject, timeout: Option<Duration>) -> Self {
+        let run = move |output: &str| {
+            Python::with_gil(|py| {
+                let ret = func.call1(py, (output,)).map_err(|e| {
+                    let message = format!("raised {}", e);
+                    if !e.is_instance_of::<PyException>(py) {
+                        let mut pending = PENDING_INTERRUPT.lock().unwrap_or_else(|p| p.into_inner());
+                        pending.get_or_insert(e);
+                        INTERRUPTED.store(true, Ordering::SeqCst);
+                    }
+                    message
+                })?;
+                convert_return(ret.as_ref(py))
+            })
+        };
+        Self::new(Arc::new(run), timeout)
+    }
+
+    /// Run against one output: (passed, details). Skipped while an
+    /// interrupt is pending; an overrun of the soft timeout fails the check.
+    pub fn run(&self, output: &str) -> (bool, String) {
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            return (false, "check skipped: an earlier call was interrupted".to_string());
+        }
+        let started = Instant::now();
+        let outcome = (self.func)(output);
+        let elapsed = started.elapsed();
+        match (outcome, self.timeout) {
+            (_, Some(limit)) if elapsed > limit => (
+                false,
+                format!(
+                    "check took {} ms, over its {} ms timeout",
+                    elapsed.as_millis(),
+                    limit.as_millis()
+                ),
+            ),
+            (Ok(result), _) => result,
+            (Err(e), _) => (false, format!("check {}", e)),
+        }
+    }
+
+    /// `run` as a check result named `name`.
+    pub fn check(&self, name: &str, output: &str) -> CheckResult {
+        let (passed, details) = self.run(output);
+        CheckResult {
+            check_type: name.to_string(),
+            passed,
+            details,
+            severity: Severity::Error,
+        }
+    }
+}
+
+fn convert_return(ret: &PyAny) -> Result<(bool, String), String> {
+    if let Ok(result) = ret.extract::<CheckResult>() {
+        return Ok((result.passed, result.details));
+    }
+    if ret.is_instance_of::<PyTuple>() {
+        return ret
+            .extract::<(bool, String)>()
+            .map_err(|_| "returned a tuple that is not (bool, str)".to_string());
+    }
+    match ret.extract::<bool>() {
+        Ok(passed) => Ok((passed, if passed { "passed" } else { "failed" }.to_string())),
+        Err(_) => Err(format!(
+            "returned {}, expected bool, (bool, str) or CheckResult",
+            ret.get_type().name().unwrap_or("an unknown type")
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_results_errors_and_timeout() {
+        let cb = CheckCallback::new(Arc::new(|o: &str| Ok((o.len() < 5, format!("{} chars", o.len())))), None);
+        assert_eq!(cb.run("abc"), (true, "3 chars".to_string()));
+        let result = cb.check("short", "abcdef");
+        assert_eq!((result.check_type.as_str(), result.passed), ("short", false));
+
+        let raising = CheckCallback::new(Arc::new(|_: &str| Err("raised ValueError: bad".to_string())), None);
+        assert_eq!(raising.run("x"), (false, "check raised ValueError: bad".to_string()));
+
+        let slow = CheckCallback::new(
+            Arc::new(|_: &str| {
+                std::thread::sleep(Duration::from_millis(20));
+                Ok((true, String::new()))
+            }),
+            Some(Duration::from_millis(1)),
+        );
+        let (passed, details) = slow.run("x");
+        assert!(!passed);
+        assert!(details.ends_with("over its 1 ms timeout"), "{}", details);
+    }
+}
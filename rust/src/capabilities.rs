@@ -0,0 +1,109 @@
+//! Optional subsystem introspection
+//!
+//! Some subsystems are behind Cargo features so a slim wheel can leave
+//! them out. `features()` tells Python which ones were compiled in, and
+//! `require_feature()` lets APIs that depend on a missing feature fail with
+//! an error naming the feature to enable instead of an obscure
+//! AttributeError. A build without a feature registers stand-ins for its
+//! APIs that raise that error, so the module's names stay the same.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+
+/// Optional subsystems and whether each was compiled into this build.
+pub fn compiled_features() -> Vec<(&'static str, bool)> {
+    vec![("embeddings", cfg!(feature = "embeddings"))]
+}
+
+/// Error message for a missing or unknown feature, or `None` if it is available.
+pub fn missing_feature_message(feature: &str) -> Option<String> {
+    match compiled_features().into_iter().find(|(name, _)| *name == feature) {
+        Some((_, true)) => None,
+        Some((name, false)) => Some(format!(
+            "flakestorm_rust was built without the '{}' feature; \
+             rebuild with `maturin build --features {}` to use this API",
+            name, name
+        )),
+        None => Some(format!("unknown flakestorm_rust feature '{}'", feature)),
+    }
+}
+
+/// Raise a RuntimeError unless `feature` was compiled in.
+///
+/// Python wrappers of an optional subsystem call this before touching it,
+/// so a missing feature surfaces as an error naming the build flag.
+#[pyfunction]
+pub fn require_feature(feature: &str) -> PyResult<()> {
+    match missing_feature_message(feature) {
+        None => Ok(()),
+        Some(message) => Err(PyRuntimeError::new_err(message)),
+    }
+}
+
+/// Map of optional subsystem name to whether it is available in this build.
+#[pyfunction]
+pub fn features() -> HashMap<&'static str, bool> {
+    compiled_features().into_iter().collect()
+}
+
+/// Embedding APIs of a build without the `embeddings` feature; each raises
+/// the error from `require_feature`.
+#[cfg(not(feature = "embeddings"))]
+pub mod embeddings_stubs {
+    use pyo3::prelude::*;
+    use pyo3::types::{PyDict, PyTuple};
+
+    use super::require_feature;
+
+    #[pyfunction]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn cosine_similarity(_args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        require_feature("embeddings")
+    }
+
+    #[pyfunction]
+    #[pyo3(signature = (*_args, **_kwargs))]
+    pub fn batch_cosine_similarity(_args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<()> {
+        require_feature("embeddings")
+    }
+
+    #[pyclass(name = "AnnIndex")]
+    pub struct PyAnnIndex;
+
+    #[pymethods]
+    impl PyAnnIndex {
+        #[new]
+        #[pyo3(signature = (*_args, **_kwargs))]
+        fn new(_args: &PyTuple, _kwargs: Option<&PyDict>) -> PyResult<Self> {
+            require_feature("embeddings")?;
+            Ok(Self)
+        }
+
+        #[staticmethod]
+        fn load(_path: &str) -> PyResult<Self> {
+            require_feature("embeddings")?;
+            Ok(Self)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_feature_message() {
+        for (name, enabled) in compiled_features() {
+            assert_eq!(missing_feature_message(name).is_none(), enabled);
+        }
+        if !cfg!(feature = "embeddings") {
+            let message = missing_feature_message("embeddings").unwrap();
+            assert!(message.contains("--features embeddings"));
+        }
+        assert!(missing_feature_message("teleport")
+            .unwrap()
+            .contains("unknown"));
+    }
+}
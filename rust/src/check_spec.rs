@@ -0,0 +1,1033 @@
+//! Declarative check specifications
+//!
+//! Teams describe the checks for a run in a YAML or TOML file instead of
+//! wiring them together in Python, so results from different teams use
+//! the same check names and severities. Each entry names a check type,
+//! its parameters, a severity and optionally the mutation types it
+//! applies to:
+//!
+//! ```yaml
+//! - type: contains_ignore_case
+//!   value: refund
+//!   severity: high
+//!   applies_to: [paraphrase, noise]
+//! - type: no_pii
+//!   categories: [email, credit_card]
+//! ```
+//!
+//! ```toml
+//! [[checks]]
+//! type = "regex"
+//! name = "no_stack_traces"
+//! pattern = 'Traceback \(most recent call last\)'
+//! rule = "must_not_match"
+//! severity = "critical"
+//! ```
+//!
+//! Specs are validated as a whole before anything runs. Every error names
+//! the line, the entry and the key at fault, e.g.
+//! `line 7: checks[1].severity: unknown severity 'urgent' (expected critical, high, medium or low)`.
+//! TOML support covers `[[checks]]` tables with string, number, boolean
+//! and array values; arrays and `"""`/`'''` strings may span lines. A
+//! leading byte order mark is skipped in either format.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde_json::{Map, Number, Value};
+
+use crate::callback::{take_interrupt, CheckCallback};
+use crate::canary::CanaryScanner;
+use crate::corpus::{yaml_mappings, YamlMapping};
+use crate::degenerate::{analyze_degeneration, DegenerationLimits};
+use crate::invariants::{RegexCheckSet, RegexInvariant, RegexRule, SimpleCheck, SEVERITIES};
+use crate::json_schema::JsonSchema;
+use crate::langid::{identify_language, language_check_result, validate_language_code};
+use crate::length::{LengthThresholds, OutputLength};
+use crate::pii::{check_categories, find_pii, pii_check_result};
+use crate::refusal::RefusalDetector;
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+use crate::toxicity::{toxicity_check_result, MatchMode, ToxicityChecker};
+
+/// (check type, parameters beyond type/name/severity/applies_to)
+const CHECK_TYPES: &[(&str, &[&str])] = &[
+    ("contains", &["value"]),
+    ("not_contains", &["value"]),
+    ("contains_ignore_case", &["value"]),
+    ("starts_with", &["value"]),
+    ("min_length", &["value"]),
+    ("max_length", &["value"]),
+    ("regex", &["pattern", "rule", "case_insensitive"]),
+    ("refusal", &["expect_refusal", "languages"]),
+    ("no_pii", &["categories"]),
+    ("toxicity", &["mode", "include_defaults", "terms", "terms_category"]),
+    ("no_degeneration", &["max_loop_repeats", "max_token_run", "min_entropy"]),
+    ("language", &["expected", "min_confidence"]),
+    ("json_schema", &["schema", "lenient"]),
+    ("canary_leak", &["canaries", "case_insensitive", "loose"]),
+    ("length_drift", &["baseline_chars", "short_ratio", "long_ratio", "min_baseline_chars"]),
+];
+
+const COMMON_KEYS: &[&str] = &["type", "name", "severity", "applies_to"];
+
+/// Spec file syntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecFormat {
+    Yaml,
+    Toml,
+}
+
+impl SpecFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "yaml" | "yml" => Ok(SpecFormat::Yaml),
+            "toml" => Ok(SpecFormat::Toml),
+            other => Err(format!("unknown check spec format '{}' (expected yaml or toml)", other)),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        Self::parse(ext).map_err(|_| format!("{}: cannot infer check spec format from extension", path.display()))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// TOML subset
+// ---------------------------------------------------------------------------
+
+struct TomlCursor<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    /// Lines after the current one, for multi-line strings
+    rest: &'a [&'a str],
+    consumed: usize,
+}
+
+impl TomlCursor<'_> {
+    fn err(&self, msg: &str) -> String {
+        format!("line {}: {}", self.line, msg)
+    }
+
+    fn skip_ws(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| *c == ' ' || *c == '\t') {
+            self.pos += 1;
+        }
+    }
+
+    fn starts_with(&self, s: &str) -> bool {
+        s.chars().enumerate().all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    /// Only whitespace or a comment remains on the line.
+    fn at_end(&mut self) -> bool {
+        self.skip_ws();
+        matches!(self.chars.get(self.pos), None | Some('#'))
+    }
+
+    fn key(&mut self) -> Result<String, String> {
+        self.skip_ws();
+        let key = match self.chars.get(self.pos) {
+            Some('"') | Some('\'') => self.string()?,
+            _ => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+                {
+                    self.pos += 1;
+                }
+                self.chars[start..self.pos].iter().collect()
+            }
+        };
+        if key.is_empty() {
+            return Err(self.err("expected a key"));
+        }
+        self.skip_ws();
+        match self.chars.get(self.pos) {
+            Some('=') => {
+                self.pos += 1;
+                Ok(key)
+            }
+            Some('.') => Err(self.err("dotted keys are not supported")),
+            _ => Err(self.err(&format!("expected '=' after key '{}'", key))),
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, String> {
+        let c = self.chars.get(self.pos).copied();
+        self.pos += 1;
+        match c {
+            Some('n') => Ok('\n'),
+            Some('t') => Ok('\t'),
+            Some('r') => Ok('\r'),
+            Some('b') => Ok('\u{8}'),
+            Some('f') => Ok('\u{c}'),
+            Some('"') => Ok('"'),
+            Some('\\') => Ok('\\'),
+            Some(e @ ('u' | 'U')) => {
+                let digits = if e == 'u' { 4 } else { 8 };
+                let hex: String = self.chars.iter().skip(self.pos).take(digits).collect();
+                self.pos += digits;
+                Some(&hex)
+                    .filter(|h| h.len() == digits && h.chars().all(|c| c.is_ascii_hexdigit()))
+                    .and_then(|h| u32::from_str_radix(h, 16).ok())
+                    .and_then(char::from_u32)
+                    .ok_or_else(|| self.err(&format!("invalid unicode escape '\\{}{}'", e, hex)))
+            }
+            other => Err(self.err(&format!("unsupported escape '\\{}'", other.unwrap_or(' ')))),
+        }
+    }
+
+    /// Replace the cursor's text with the next physical line.
+    fn next_line(&mut self) -> bool {
+        match self.rest.get(self.consumed) {
+            Some(l) => {
+                self.chars = l.chars().collect();
+                self.pos = 0;
+                self.consumed += 1;
+                self.line += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn multiline_string(&mut self, delim: &str) -> Result<String, String> {
+        let start_line = self.line;
+        let literal = delim == "'''";
+        self.pos += 3;
+        let mut out = String::new();
+        // A newline right after the opening delimiter is dropped
+        if self.pos >= self.chars.len() && !self.next_line() {
+            return Err(format!("line {}: unterminated multi-line string", start_line));
+        }
+        loop {
+            if self.starts_with(delim) {
+                self.pos += 3;
+                return Ok(out);
+            }
+            match self.chars.get(self.pos).copied() {
+                // A line-ending backslash trims the break and the whitespace after it
+                Some('\\') if !literal && self.chars[self.pos + 1..].iter().all(|c| c.is_whitespace()) => loop {
+                    if !self.next_line() {
+                        return Err(format!("line {}: unterminated multi-line string", start_line));
+                    }
+                    self.skip_ws();
+                    if self.pos < self.chars.len() {
+                        break;
+                    }
+                },
+                Some('\\') if !literal => {
+                    self.pos += 1;
+                    out.push(self.escape()?);
+                }
+                Some(c) => {
+                    out.push(c);
+                    self.pos += 1;
+                }
+                None => {
+                    if !self.next_line() {
+                        return Err(format!("line {}: unterminated multi-line string", start_line));
+                    }
+                    out.push('\n');
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let literal = self.chars[self.pos] == '\'';
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            match self.chars.get(self.pos).copied() {
+                None => return Err(self.err("unterminated string")),
+                Some('\'') if literal => break,
+                Some('"') if !literal => break,
+                Some('\\') if !literal => {
+                    self.pos += 1;
+                    out.push(self.escape()?);
+                    continue;
+                }
+                Some(c) => out.push(c),
+            }
+            self.pos += 1;
+        }
+        self.pos += 1;
+        Ok(out)
+    }
+
+    fn scalar(&mut self) -> Result<Value, String> {
+        self.skip_ws();
+        if self.starts_with("\"\"\"") {
+            return self.multiline_string("\"\"\"").map(Value::String);
+        }
+        if self.starts_with("'''") {
+            return self.multiline_string("'''").map(Value::String);
+        }
+        match self.chars.get(self.pos) {
+            Some('"') | Some('\'') => return self.string().map(Value::String),
+            None => return Err(self.err("expected a value")),
+            _ => {}
+        }
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| !matches!(c, ',' | ']' | '#' | ' ' | '\t'))
+        {
+            self.pos += 1;
+        }
+        let raw: String = self.chars[start..self.pos].iter().filter(|c| **c != '_').collect();
+        match raw.as_str() {
+            "true" => return Ok(Value::Bool(true)),
+            "false" => return Ok(Value::Bool(false)),
+            _ => {}
+        }
+        if let Ok(i) = raw.parse::<i64>() {
+            return Ok(Value::Number(i.into()));
+        }
+        raw.parse::<f64>()
+            .ok()
+            .and_then(Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| self.err(&format!("invalid value '{}'", raw)))
+    }
+
+    /// Skip whitespace, comments and line breaks inside an array.
+    fn skip_array_space(&mut self, start_line: usize) -> Result<(), String> {
+        loop {
+            self.skip_ws();
+            match self.chars.get(self.pos).copied() {
+                None | Some('#') if !self.next_line() => {
+                    return Err(format!("line {}: unterminated array", start_line));
+                }
+                None | Some('#') => {}
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_ws();
+        if self.chars.get(self.pos) != Some(&'[') {
+            return self.scalar();
+        }
+        let start_line = self.line;
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_array_space(start_line)?;
+            if self.chars.get(self.pos) == Some(&']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.scalar()?);
+            self.skip_array_space(start_line)?;
+            match self.chars.get(self.pos) {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return Err(self.err("expected ',' or ']' in array")),
+            }
+        }
+    }
+}
+
+/// The `[[checks]]` tables of a TOML document.
+fn toml_mappings(text: &str) -> Result<Vec<YamlMapping>, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut records: Vec<YamlMapping> = Vec::new();
+    let mut i = 0;
+    while i < lines.len() {
+        let no = i + 1;
+        let trimmed = lines[i].trim();
+        i += 1;
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with('[') {
+            let header = trimmed.split(" #").next().unwrap_or(trimmed).trim_end();
+            if header.replace(' ', "") != "[[checks]]" {
+                return Err(format!("line {}: unsupported table '{}' (expected [[checks]])", no, header));
+            }
+            records.push(YamlMapping {
+                line: no,
+                fields: Map::new(),
+                key_lines: HashMap::new(),
+            });
+            continue;
+        }
+        let Some(record) = records.last_mut() else {
+            return Err(format!("line {}: expected a [[checks]] table before any keys", no));
+        };
+        let mut cursor = TomlCursor {
+            chars: lines[no - 1].chars().collect(),
+            pos: 0,
+            line: no,
+            rest: &lines[no..],
+            consumed: 0,
+        };
+        let key = cursor.key()?;
+        let value = cursor.value()?;
+        if !cursor.at_end() {
+            return Err(cursor.err("unexpected text after value"));
+        }
+        i += cursor.consumed;
+        if record.fields.insert(key.clone(), value).is_some() {
+            return Err(format!("line {}: duplicate key '{}'", no, key));
+        }
+        record.key_lines.insert(key, no);
+    }
+    Ok(records)
+}
+
+// ---------------------------------------------------------------------------
+// Compilation
+// ---------------------------------------------------------------------------
+
+/// Typed access to one spec entry's keys, with errors that point at the key
+struct Entry<'a> {
+    index: usize,
+    record: &'a YamlMapping,
+}
+
+impl Entry<'_> {
+    fn err(&self, key: &str, msg: &str) -> String {
+        let line = self.record.key_lines.get(key).copied().unwrap_or(self.record.line);
+        format!("line {}: checks[{}].{}: {}", line, self.index, key, msg)
+    }
+
+    fn get(&self, key: &str) -> Option<&Value> {
+        self.record.fields.get(key).filter(|v| !v.is_null())
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>, String> {
+        match self.get(key) {
+            None => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s.clone())),
+            Some(Value::Number(n)) => Ok(Some(n.to_string())),
+            Some(_) => Err(self.err(key, "expected a string")),
+        }
+    }
+
+    fn required(&self, key: &str) -> Result<String, String> {
+        self.string(key)?.ok_or_else(|| self.err(key, "required"))
+    }
+
+    fn strings(&self, key: &str) -> Result<Vec<String>, String> {
+        match self.get(key) {
+            None => Ok(Vec::new()),
+            Some(Value::String(s)) => Ok(vec![s.clone()]),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|v| match v {
+                    Value::String(s) => Ok(s.clone()),
+                    _ => Err(self.err(key, "expected a list of strings")),
+                })
+                .collect(),
+            Some(_) => Err(self.err(key, "expected a list of strings")),
+        }
+    }
+
+    fn bool(&self, key: &str, default: bool) -> Result<bool, String> {
+        match self.get(key) {
+            None => Ok(default),
+            Some(Value::Bool(b)) => Ok(*b),
+            Some(Value::String(s)) if s == "true" => Ok(true),
+            Some(Value::String(s)) if s == "false" => Ok(false),
+            Some(_) => Err(self.err(key, "expected true or false")),
+        }
+    }
+
+    fn float(&self, key: &str, default: f64) -> Result<f64, String> {
+        let parsed = match self.get(key) {
+            None => return Ok(default),
+            Some(Value::Number(n)) => n.as_f64(),
+            Some(Value::String(s)) => s.trim().parse::<f64>().ok(),
+            Some(_) => None,
+        };
+        parsed
+            .filter(|f| f.is_finite())
+            .ok_or_else(|| self.err(key, "expected a number"))
+    }
+
+    fn count(&self, key: &str, default: usize) -> Result<usize, String> {
+        let parsed = match self.get(key) {
+            None => return Ok(default),
+            Some(Value::Number(n)) => n.as_u64().map(|n| n as usize),
+            Some(Value::String(s)) => s.trim().parse::<usize>().ok(),
+            Some(_) => None,
+        };
+        parsed.ok_or_else(|| self.err(key, "expected a non-negative integer"))
+    }
+}
+
+/// A compiled check and what it needs at evaluation time
+#[derive(Debug, Clone)]
+enum SpecCheckKind {
+    Simple(SimpleCheck),
+    Regex(RegexCheckSet),
+    Refusal {
+        detector: RefusalDetector,
+        expect_refusal: bool,
+    },
+    Pii(Vec<String>),
+    Toxicity(ToxicityChecker),
+    Degeneration(DegenerationLimits),
+    Language {
+        expected: String,
+        min_confidence: f64,
+    },
+    JsonSchema {
+        schema: JsonSchema,
+        lenient: bool,
+    },
+    Canary(CanaryScanner),
+    LengthDrift {
+        thresholds: LengthThresholds,
+        baseline_chars: usize,
+    },
+    Callback(CheckCallback),
+}
+
+/// One check from a spec file
+#[derive(Debug, Clone)]
+pub struct SpecCheck {
+    pub name: String,
+    pub check_type: String,
+    pub severity: String,
+    /// Mutation types the check runs for; empty means all
+    pub applies_to: Vec<String>,
+    kind: SpecCheckKind,
+    /// `severity`, parsed
+    level: Severity,
+}
+
+impl SpecCheck {
+    fn compile(entry: &Entry) -> Result<Self, String> {
+        let check_type = entry.required("type")?;
+        let Some((_, params)) = CHECK_TYPES.iter().find(|(t, _)| *t == check_type) else {
+            let known: Vec<&str> = CHECK_TYPES.iter().map(|(t, _)| *t).collect();
+            return Err(entry.err(
+                "type",
+                &format!("unknown check type '{}' (expected one of {})", check_type, known.join(", ")),
+            ));
+        };
+        let mut keys: Vec<&String> = entry.record.fields.keys().collect();
+        keys.sort_by_key(|k| entry.record.key_lines.get(*k));
+        if let Some(unknown) = keys
+            .into_iter()
+            .find(|k| !COMMON_KEYS.contains(&k.as_str()) && !params.contains(&k.as_str()))
+        {
+            return Err(entry.err(
+                unknown,
+                &format!("unknown key for check type '{}' (expected {})", check_type, params.join(", ")),
+            ));
+        }
+
+        let name = entry.string("name")?.unwrap_or_else(|| check_type.clone());
+        // A leaked secret is critical unless the spec says otherwise
+        let default_severity = if check_type == "canary_leak" { "critical" } else { "medium" };
+        let severity = entry.string("severity")?.unwrap_or_else(|| default_severity.to_string());
+        if !SEVERITIES.contains(&severity.as_str()) {
+            return Err(entry.err(
+                "severity",
+                &format!("unknown severity '{}' (expected critical, high, medium or low)", severity),
+            ));
+        }
+        let level = Severity::parse(&severity).map_err(|e| entry.err("severity", &e))?;
+        let applies_to = entry.strings("applies_to")?;
+
+        let kind = match check_type.as_str() {
+            "regex" => {
+                let pattern = entry.required("pattern")?;
+                let rule = match entry.string("rule")? {
+                    Some(r) => RegexRule::parse(&r).map_err(|e| entry.err("rule", &e))?,
+                    None => RegexRule::MustMatch,
+                };
+                let invariant = RegexInvariant {
+                    name: name.clone(),
+                    pattern,
+                    rule,
+                    severity: severity.clone(),
+                };
+                let case_insensitive = entry.bool("case_insensitive", false)?;
+                SpecCheckKind::Regex(
+                    RegexCheckSet::compile(vec![invariant], case_insensitive).map_err(|e| entry.err("pattern", &e))?,
+                )
+            }
+            "refusal" => SpecCheckKind::Refusal {
+                detector: RefusalDetector::new(&entry.strings("languages")?, &[], 60)
+                    .map_err(|e| entry.err("languages", &e))?,
+                expect_refusal: entry.bool("expect_refusal", true)?,
+            },
+            "no_pii" => {
+                let categories = entry.strings("categories")?;
+                check_categories(&categories).map_err(|e| entry.err("categories", &e))?;
+                SpecCheckKind::Pii(categories)
+            }
+            "toxicity" => {
+                let mode = match entry.string("mode")? {
+                    Some(m) => MatchMode::parse(&m).map_err(|e| entry.err("mode", &e))?,
+                    None => MatchMode::Obfuscated,
+                };
+                let category = entry.string("terms_category")?.unwrap_or_else(|| "custom".to_string());
+                let terms = entry.strings("terms")?;
+                let custom: HashMap<String, Vec<String>> = if terms.is_empty() {
+                    HashMap::new()
+                } else {
+                    HashMap::from([(category, terms)])
+                };
+                let include_defaults = entry.bool("include_defaults", true)?;
+                SpecCheckKind::Toxicity(
+                    ToxicityChecker::new(mode, &custom, include_defaults).map_err(|e| entry.err("terms", &e))?,
+                )
+            }
+            "no_degeneration" => {
+                let defaults = DegenerationLimits::default();
+                SpecCheckKind::Degeneration(DegenerationLimits {
+                    max_loop_repeats: entry.count("max_loop_repeats", defaults.max_loop_repeats)?,
+                    max_token_run: entry.count("max_token_run", defaults.max_token_run)?,
+                    min_entropy: entry.float("min_entropy", defaults.min_entropy)?,
+                    ..defaults
+                })
+            }
+            "language" => {
+                let expected = entry.required("expected")?;
+                validate_language_code(&expected).map_err(|e| entry.err("expected", &e))?;
+                SpecCheckKind::Language {
+                    expected,
+                    min_confidence: entry.float("min_confidence", 0.5)?,
+                }
+            }
+            "json_schema" => SpecCheckKind::JsonSchema {
+                schema: JsonSchema::parse(&entry.required("schema")?).map_err(|e| entry.err("schema", &e))?,
+                lenient: entry.bool("lenient", false)?,
+            },
+            "canary_leak" => {
+                let canaries = entry.strings("canaries")?;
+                if canaries.is_empty() || canaries.iter().any(|c| c.is_empty()) {
+                    return Err(entry.err("canaries", "needs at least one non-empty canary"));
+                }
+                let case_insensitive = entry.bool("case_insensitive", false)?;
+                SpecCheckKind::Canary(CanaryScanner::new(&canaries, case_insensitive, entry.bool("loose", false)?))
+            }
+            "length_drift" => {
+                if entry.get("baseline_chars").is_none() {
+                    return Err(entry.err("baseline_chars", "required"));
+                }
+                let defaults = LengthThresholds::default();
+                let thresholds = LengthThresholds {
+                    short_ratio: entry.float("short_ratio", defaults.short_ratio)?,
+                    long_ratio: entry.float("long_ratio", defaults.long_ratio)?,
+                    min_baseline_chars: entry.count("min_baseline_chars", defaults.min_baseline_chars)?,
+                };
+                if let Err(e) = thresholds.validate() {
+                    let key = if e.starts_with("short_ratio") { "short_ratio" } else { "long_ratio" };
+                    return Err(entry.err(key, &e));
+                }
+                SpecCheckKind::LengthDrift {
+                    thresholds,
+                    baseline_chars: entry.count("baseline_chars", 0)?,
+                }
+            }
+            simple => {
+                SpecCheckKind::Simple(SimpleCheck::parse(simple, &entry.required("value")?).map_err(|e| entry.err("value", &e))?)
+            }
+        };
+        Ok(Self {
+            name,
+            check_type,
+            severity,
+            applies_to,
+            kind,
+            level,
+        })
+    }
+
+    /// A user-supplied check, of check type "callback".
+    pub fn callback(name: &str, severity: &str, applies_to: Vec<String>, callback: CheckCallback) -> Result<Self, String> {
+        if !SEVERITIES.contains(&severity) {
+            return Err(format!(
+                "check '{}': unknown severity '{}' (expected critical, high, medium or low)",
+                name, severity
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            check_type: "callback".to_string(),
+            severity: severity.to_string(),
+            applies_to,
+            kind: SpecCheckKind::Callback(callback),
+            level: Severity::parse(severity).map_err(|e| format!("check '{}': {}", name, e))?,
+        })
+    }
+
+    pub fn applies(&self, mutation_type: Option<&str>) -> bool {
+        match mutation_type {
+            Some(t) if !self.applies_to.is_empty() => self.applies_to.iter().any(|a| a == t),
+            _ => true,
+        }
+    }
+
+    /// Run against one output. The result is named after the check and
+    /// carries its severity.
+    pub fn evaluate(&self, output: &str) -> CheckResult {
+        let result = match &self.kind {
+            SpecCheckKind::Regex(set) => return set.check(output).remove(0),
+            SpecCheckKind::Simple(check) => check.check(output),
+            SpecCheckKind::Refusal {
+                detector,
+                expect_refusal,
+            } => detector.classify(output).to_check_result(*expect_refusal),
+            SpecCheckKind::Pii(categories) => pii_check_result(&find_pii(0, output, categories)),
+            SpecCheckKind::Toxicity(checker) => toxicity_check_result(&checker.scan(0, output)),
+            SpecCheckKind::Degeneration(limits) => analyze_degeneration(output, limits).to_check_result(),
+            SpecCheckKind::Language {
+                expected,
+                min_confidence,
+            } => language_check_result(&identify_language(output, 1), expected, *min_confidence),
+            SpecCheckKind::JsonSchema { schema, lenient } => schema.check_output(output, *lenient),
+            SpecCheckKind::Canary(scanner) => scanner.check(output),
+            SpecCheckKind::LengthDrift {
+                thresholds,
+                baseline_chars,
+            } => thresholds.check(&OutputLength {
+                output_chars: output.chars().count(),
+                baseline_chars: *baseline_chars,
+            }),
+            SpecCheckKind::Callback(callback) => callback.check(&self.name, output),
+        };
+        CheckResult {
+            check_type: self.name.clone(),
+            severity: self.level,
+            ..result
+        }
+    }
+}
+
+/// Checks compiled from a spec file
+#[derive(Debug, Clone)]
+pub struct CheckSuite {
+    pub checks: Vec<SpecCheck>,
+}
+
+impl CheckSuite {
+    pub fn parse(text: &str, format: SpecFormat) -> Result<Self, String> {
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        let records = match format {
+            SpecFormat::Yaml => yaml_mappings(text, "checks ('- type: ...')")?,
+            SpecFormat::Toml => toml_mappings(text)?,
+        };
+        let checks = records
+            .par_iter()
+            .enumerate()
+            .map(|(index, record)| SpecCheck::compile(&Entry { index, record }))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut seen: HashSet<&str> = HashSet::new();
+        for (index, (check, record)) in checks.iter().zip(&records).enumerate() {
+            if !seen.insert(check.name.as_str()) {
+                let entry = Entry { index, record };
+                let key = if record.fields.contains_key("name") { "name" } else { "type" };
+                return Err(entry.err(key, &format!("duplicate check name '{}'", check.name)));
+            }
+        }
+        Ok(Self { checks })
+    }
+
+    /// Append a check built outside the spec file, such as a callback.
+    pub fn add(&mut self, check: SpecCheck) -> Result<(), String> {
+        if self.checks.iter().any(|c| c.name == check.name) {
+            return Err(format!("duplicate check name '{}'", check.name));
+        }
+        self.checks.push(check);
+        Ok(())
+    }
+
+    /// Read and compile a spec file; errors are prefixed with the path.
+    pub fn load(path: &Path, format: Option<SpecFormat>) -> Result<Self, String> {
+        let format = match format {
+            Some(f) => f,
+            None => SpecFormat::from_path(path)?,
+        };
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        Self::parse(&text, format).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Results of the checks that apply to `mutation_type` (all when None).
+    pub fn evaluate(&self, output: &str, mutation_type: Option<&str>) -> Vec<CheckResult> {
+        self.checks
+            .iter()
+            .filter(|c| c.applies(mutation_type))
+            .map(|c| c.evaluate(output))
+            .collect()
+    }
+
+    /// Evaluate outputs in parallel; `mutation_types` pairs with `outputs` when given.
+    pub fn evaluate_all(&self, outputs: &[String], mutation_types: Option<&[String]>) -> Result<Vec<Vec<CheckResult>>, String> {
+        if let Some(types) = mutation_types {
+            if types.len() != outputs.len() {
+                return Err(format!("got {} outputs but {} mutation types", outputs.len(), types.len()));
+            }
+        }
+        Ok(outputs
+            .par_iter()
+            .enumerate()
+            .map(|(i, o)| self.evaluate(o, mutation_types.map(|t| t[i].as_str())))
+            .collect())
+    }
+}
+
+/// Checks loaded from a YAML or TOML spec file.
+///
+/// The format comes from the file extension unless `format` ("yaml" or
+/// "toml") is given. Spec errors raise ValueError naming the line and key.
+#[pyclass(name = "CheckSuite")]
+pub struct PyCheckSuite {
+    inner: CheckSuite,
+}
+
+#[pymethods]
+impl PyCheckSuite {
+    #[new]
+    #[pyo3(signature = (path, format = None))]
+    fn new(py: Python<'_>, path: &str, format: Option<&str>) -> PyResult<Self> {
+        let format = format.map(SpecFormat::parse).transpose().map_err(PyValueError::new_err)?;
+        let path = Path::new(path);
+        if !path.is_file() {
+            return Err(PyIOError::new_err(format!("{}: no such file", path.display())));
+        }
+        py.allow_threads(|| CheckSuite::load(path, format))
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Compile a spec held in memory.
+    #[staticmethod]
+    fn from_text(text: &str, format: &str) -> PyResult<Self> {
+        let format = SpecFormat::parse(format).map_err(PyValueError::new_err)?;
+        CheckSuite::parse(text, format)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Check names in spec order.
+    #[getter]
+    fn names(&self) -> Vec<String> {
+        self.inner.checks.iter().map(|c| c.name.clone()).collect()
+    }
+
+    /// Add a check implemented in Python.
+    ///
+    /// `func(output)` returns a bool, a `(passed, details)` tuple or a
+    /// CheckResult. Exceptions fail the check instead of the run, while
+    /// KeyboardInterrupt and SystemExit are re-raised by `evaluate`.
+    /// `timeout_ms` is a soft limit: calls are not cut short, but calls
+    /// slower than it count as failures.
+    #[pyo3(signature = (name, func, severity = "medium", applies_to = None, timeout_ms = None))]
+    fn add_python_check(
+        &mut self,
+        name: &str,
+        func: PyObject,
+        severity: &str,
+        applies_to: Option<Vec<String>>,
+        timeout_ms: Option<u64>,
+    ) -> PyResult<()> {
+        let callback = CheckCallback::from_python(func, timeout_ms.map(Duration::from_millis));
+        SpecCheck::callback(name, severity, applies_to.unwrap_or_default(), callback)
+            .and_then(|check| self.inner.add(check))
+            .map_err(PyValueError::new_err)
+    }
+
+    /// One list of check results per output. With `mutation_types`, each
+    /// output only runs the checks that apply to its mutation type.
+    #[pyo3(signature = (outputs, mutation_types = None))]
+    fn evaluate(
+        &self,
+        py: Python<'_>,
+        outputs: Vec<String>,
+        mutation_types: Option<Vec<String>>,
+    ) -> PyResult<Vec<Vec<CheckResult>>> {
+        let results = py
+            .allow_threads(|| self.inner.evaluate_all(&outputs, mutation_types.as_deref()))
+            .map_err(PyValueError::new_err)?;
+        match take_interrupt() {
+            Some(interrupt) => Err(interrupt),
+            None => Ok(results),
+        }
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.checks.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    const YAML: &str = r#"
+- type: contains_ignore_case
+  value: refund
+  severity: high
+  applies_to: [paraphrase, noise]
+- type: regex
+  name: no_traceback
+  pattern: 'Traceback \(most recent'
+  rule: must_not_match
+  severity: critical
+- type: max_length
+  value: 40
+"#;
+
+    const TOML: &str = r#"
+# checks for the support agent
+[[checks]]
+type = "contains_ignore_case"
+value = "refund"
+severity = "high"
+applies_to = ["paraphrase", "noise"]
+
+[[checks]]
+type = "regex"
+name = "no_traceback"
+pattern = 'Traceback \(most recent'
+rule = "must_not_match"
+severity = "critical"
+
+[[checks]]
+type = "json_schema"
+schema = """
+{"type": "object", "required": ["id"]}
+"""
+"#;
+
+    #[test]
+    fn test_yaml_spec() {
+        let suite = CheckSuite::parse(YAML, SpecFormat::Yaml).unwrap();
+        let names: Vec<&str> = suite.checks.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["contains_ignore_case", "no_traceback", "max_length"]);
+
+        let results = suite.evaluate("Traceback (most recent call last): refund failed", Some("paraphrase"));
+        assert_eq!(results.len(), 3);
+        assert!(results[0].passed);
+        assert_eq!(results[1].details, "forbidden /Traceback \\(most recent/ matched 'Traceback (most recent'");
+        assert_eq!(results[2].details, "expected length 48 <= 40");
+        let severities: Vec<Severity> = results.iter().map(|r| r.severity).collect();
+        assert_eq!(severities, vec![Severity::Error, Severity::Critical, Severity::Warn]);
+
+        // Mutation types outside applies_to skip the check
+        assert_eq!(suite.evaluate("ok", Some("prompt_injection")).len(), 2);
+        assert!(suite.evaluate_all(&["a".to_string()], Some(&[])).is_err());
+    }
+
+    #[test]
+    fn test_toml_spec() {
+        let suite = CheckSuite::parse(TOML, SpecFormat::Toml).unwrap();
+        assert_eq!(suite.checks.len(), 3);
+        assert_eq!(suite.checks[0].applies_to, vec!["paraphrase", "noise"]);
+        let results = suite.evaluate(r#"{"name": "x"}"#, None);
+        assert!(!results[0].passed);
+        assert_eq!(results[2].check_type, "json_schema");
+        assert!(!results[2].passed);
+        assert!(suite.evaluate(r#"{"id": 1}"#, None)[2].passed);
+    }
+
+    #[test]
+    fn test_toml_arrays_escapes_and_line_continuations() {
+        let text = "\u{feff}[[checks]]\n\
+                    type = \"contains\"\n\
+                    value = \"\"\"\\\n    caf\\u00e9 \\\n    \\U0001F600\"\"\"\n\
+                    applies_to = [ # mutation types\n  \"noise\",\n\n  \"paraphrase\", # trailing comma\n]\n";
+        let suite = CheckSuite::parse(text, SpecFormat::Toml).unwrap();
+        assert_eq!(suite.checks[0].applies_to, vec!["noise", "paraphrase"]);
+        assert!(suite.evaluate("café 😀", None)[0].passed);
+    }
+
+    #[test]
+    fn test_canary_and_length_checks() {
+        let text = "- type: canary_leak\n  canaries: [CANARY-7f3a]\n  loose: true\n\
+                    - type: length_drift\n  baseline_chars: 40\n  long_ratio: 2\n";
+        let suite = CheckSuite::parse(text, SpecFormat::Yaml).unwrap();
+        assert_eq!(suite.checks[0].level, Severity::Critical);
+        let leaked = suite.evaluate("the secret is c a n a r y 7 F 3 A", None);
+        assert!(!leaked[0].passed);
+        assert_eq!((leaked[0].check_type.as_str(), leaked[0].severity), ("canary_leak", Severity::Critical));
+        assert!(leaked[1].passed);
+        let runaway = suite.evaluate(&"word ".repeat(20), None);
+        assert!(runaway[0].passed);
+        assert!(!runaway[1].passed && runaway[1].details.contains("runaway"));
+        assert_eq!(runaway[1].severity, Severity::Warn);
+    }
+
+    #[test]
+    fn test_callback_checks() {
+        let mut suite = CheckSuite::parse(YAML, SpecFormat::Yaml).unwrap();
+        let polite = CheckCallback::new(Arc::new(|o: &str| Ok((o.contains("please"), "politeness".to_string()))), None);
+        suite
+            .add(SpecCheck::callback("polite", "low", vec!["noise".to_string()], polite.clone()).unwrap())
+            .unwrap();
+        assert!(suite.add(SpecCheck::callback("polite", "low", vec![], polite.clone()).unwrap()).is_err());
+        assert!(SpecCheck::callback("x", "urgent", vec![], polite).is_err());
+
+        let results = suite.evaluate_all(&["refund now".to_string(), "refund please".to_string()], Some(&["noise".to_string(), "noise".to_string()])).unwrap();
+        assert_eq!(results[0][3].check_type, "polite");
+        assert_eq!(results[0][3].details, "politeness");
+        assert!(results[1][3].passed);
+        assert_eq!(suite.evaluate("refund", Some("paraphrase")).len(), 3);
+    }
+
+    #[test]
+    fn test_errors_point_at_keys() {
+        let err = |text: &str, format| CheckSuite::parse(text, format).unwrap_err();
+        assert_eq!(
+            err("- type: contains\n  value: x\n  severity: urgent\n", SpecFormat::Yaml),
+            "line 3: checks[0].severity: unknown severity 'urgent' (expected critical, high, medium or low)"
+        );
+        assert_eq!(
+            err("- type: contains\n  value: a\n- type: max_length\n  valeu: 3\n", SpecFormat::Yaml),
+            "line 4: checks[1].valeu: unknown key for check type 'max_length' (expected value)"
+        );
+        assert_eq!(
+            err("[[checks]]\ntype = \"max_length\"\nvalue = \"lots\"\n", SpecFormat::Toml),
+            "line 3: checks[0].value: max_length needs a non-negative integer, got 'lots'"
+        );
+        assert!(err("[[checks]]\ntype = \"sentiment\"\n", SpecFormat::Toml).starts_with("line 2: checks[0].type: unknown check type"));
+        assert!(err("[[checks]]\ntype = \"regex\"\n", SpecFormat::Toml).starts_with("line 1: checks[0].pattern: required"));
+        assert_eq!(
+            err("[[checks]]\ntype = \"contains\"\nvalue = \"a\"\n[[checks]]\ntype = \"contains\"\nvalue = \"b\"\n", SpecFormat::Toml),
+            "line 5: checks[1].type: duplicate check name 'contains'"
+        );
+        assert_eq!(err("[checks]\n", SpecFormat::Toml), "line 1: unsupported table '[checks]' (expected [[checks]])");
+        assert_eq!(err("type = \"x\"\n", SpecFormat::Toml), "line 1: expected a [[checks]] table before any keys");
+        assert!(err("[[checks]]\nschema = '''\n{\n", SpecFormat::Toml).starts_with("line 2: unterminated"));
+        assert_eq!(err("[[checks]]\napplies_to = [\n  \"a\",\n", SpecFormat::Toml), "line 2: unterminated array");
+        assert_eq!(
+            err("[[checks]]\nvalue = \"\\u12\"\n", SpecFormat::Toml),
+            "line 2: invalid unicode escape '\\u12\"'"
+        );
+        assert_eq!(
+            err("- type: canary_leak\n  canaries: []\n", SpecFormat::Yaml),
+            "line 2: checks[0].canaries: needs at least one non-empty canary"
+        );
+        assert_eq!(
+            err("- type: length_drift\n  baseline_chars: 10\n  long_ratio: 0.5\n", SpecFormat::Yaml),
+            "line 3: checks[0].long_ratio: long_ratio must be greater than 1, got 0.5"
+        );
+        assert!(err("- type: length_drift\n", SpecFormat::Yaml).contains("baseline_chars: required"));
+        assert!(err("- type: language\n  expected: english\n", SpecFormat::Yaml)
+            .starts_with("line 2: checks[0].expected: unknown language 'english'"));
+        assert_eq!(SpecFormat::from_path(Path::new("checks.yml")).unwrap(), SpecFormat::Yaml);
+    }
+}
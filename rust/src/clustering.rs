@@ -0,0 +1,256 @@
+//! Failure clustering by output similarity
+//!
+//! A run with hundreds of failures usually has a handful of distinct
+//! failure modes: the same refusal, the same stack trace, the same wrong
+//! answer. Failed outputs are grouped so reviewers read one representative
+//! per mode instead of every failure.
+//!
+//! Similarities between all pairs are computed in parallel with one of the
+//! crate's metrics, keeping only pairs at or above the threshold. Clusters
+//! are then formed greedily around centers: the output with the most
+//! unassigned neighbours becomes a center and takes those neighbours, and
+//! so on until every output is assigned. Unlike single linkage this never
+//! chains two dissimilar outputs together through intermediate ones, and
+//! each center is a natural representative of its cluster.
+//!
+//! Comparing all pairs is quadratic, so runs with more failed outputs than
+//! `max_outputs` are rejected rather than clustered for minutes. Unassigned
+//! neighbour counts are kept up to date as outputs are assigned, and the
+//! next center comes from a max-heap of those counts.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::similarity::{parse_metric, Metric};
+
+/// Clustering settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClusterConfig {
+    pub metric: Metric,
+    /// Minimum similarity between an output and its cluster's center
+    pub threshold: f64,
+    /// Most outputs clustered in one call
+    pub max_outputs: usize,
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            metric: Metric::Shingle,
+            threshold: 0.5,
+            max_outputs: 5000,
+        }
+    }
+}
+
+impl ClusterConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.threshold > 0.0 && self.threshold <= 1.0) {
+            return Err(format!("threshold must be in (0, 1], got {}", self.threshold));
+        }
+        Ok(())
+    }
+}
+
+/// One group of similar failed outputs
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureCluster {
+    /// Index of the center output
+    pub representative: usize,
+    pub representative_output: String,
+    pub size: usize,
+    /// Indices of all members, the center included, ascending
+    pub members: Vec<usize>,
+    /// Mean similarity of the other members to the center; 1.0 for a
+    /// singleton
+    pub mean_similarity: f64,
+    /// Member count per mutation type, when types were given
+    pub mutation_types: BTreeMap<String, usize>,
+}
+
+/// Neighbours at or above the threshold, per output.
+fn neighbours(outputs: &[String], config: &ClusterConfig) -> Vec<Vec<(usize, f64)>> {
+    let n = outputs.len();
+    let upper: Vec<Vec<(usize, f64)>> = (0..n)
+        .into_par_iter()
+        .map(|i| {
+            (i + 1..n)
+                .map(|j| (j, config.metric.similarity(&outputs[i], &outputs[j])))
+                .filter(|&(_, sim)| sim >= config.threshold)
+                .collect()
+        })
+        .collect();
+    let mut all = upper.clone();
+    for (i, row) in upper.into_iter().enumerate() {
+        for (j, sim) in row {
+            all[j].push((i, sim));
+        }
+    }
+    all
+}
+
+/// Cluster failed outputs, largest cluster first (ties by center index).
+///
+/// `mutation_types`, when given, holds one type per output and is
+/// tallied per cluster.
+pub fn cluster_outputs(
+    outputs: &[String],
+    mutation_types: Option<&[String]>,
+    config: &ClusterConfig,
+) -> Result<Vec<FailureCluster>, String> {
+    config.validate()?;
+    if outputs.len() > config.max_outputs {
+        return Err(format!(
+            "got {} outputs, more than max_outputs ({}); sample the failures first",
+            outputs.len(),
+            config.max_outputs
+        ));
+    }
+    if let Some(types) = mutation_types.filter(|t| t.len() != outputs.len()) {
+        return Err(format!(
+            "got {} outputs but {} mutation types",
+            outputs.len(),
+            types.len()
+        ));
+    }
+    let adjacency = neighbours(outputs, config);
+    let mut assigned = vec![false; outputs.len()];
+    let mut degree: Vec<usize> = adjacency.iter().map(Vec::len).collect();
+    // Most unassigned neighbours first, the earliest output winning ties;
+    // entries whose count has since dropped are skipped when popped
+    let mut heap: BinaryHeap<(usize, Reverse<usize>)> =
+        degree.iter().enumerate().map(|(i, &d)| (d, Reverse(i))).collect();
+    let mut clusters = Vec::new();
+    while let Some((d, Reverse(center))) = heap.pop() {
+        if assigned[center] || d != degree[center] {
+            continue;
+        }
+        let joined: Vec<(usize, f64)> = adjacency[center]
+            .iter()
+            .copied()
+            .filter(|(j, _)| !assigned[*j])
+            .collect();
+        for j in joined.iter().map(|(j, _)| *j).chain(std::iter::once(center)) {
+            assigned[j] = true;
+            for &(k, _) in &adjacency[j] {
+                if !assigned[k] {
+                    degree[k] -= 1;
+                    heap.push((degree[k], Reverse(k)));
+                }
+            }
+        }
+        let mut members: Vec<usize> = joined.iter().map(|(j, _)| *j).chain(std::iter::once(center)).collect();
+        members.sort_unstable();
+        let mean_similarity = if joined.is_empty() {
+            1.0
+        } else {
+            joined.iter().map(|(_, sim)| sim).sum::<f64>() / joined.len() as f64
+        };
+        let mut types: BTreeMap<String, usize> = BTreeMap::new();
+        if let Some(all_types) = mutation_types {
+            for &m in &members {
+                *types.entry(all_types[m].clone()).or_default() += 1;
+            }
+        }
+        clusters.push(FailureCluster {
+            representative: center,
+            representative_output: outputs[center].clone(),
+            size: members.len(),
+            members,
+            mean_similarity,
+            mutation_types: types,
+        });
+    }
+    clusters.sort_by(|a, b| b.size.cmp(&a.size).then(a.representative.cmp(&b.representative)));
+    Ok(clusters)
+}
+
+/// Group failed outputs into clusters of similar responses.
+///
+/// `metric` is one of "levenshtein", "jaccard", "dice", "shingle" or "lcs";
+/// an output joins a cluster when its similarity to the cluster's
+/// representative is at least `threshold`. More than `max_outputs`
+/// outputs is an error. The GIL is released while
+/// similarities are computed.
+#[pyfunction]
+#[pyo3(signature = (outputs, mutation_types = None, metric = "shingle", threshold = 0.5, max_outputs = 5000))]
+pub fn cluster_failures(
+    py: Python<'_>,
+    outputs: Vec<String>,
+    mutation_types: Option<Vec<String>>,
+    metric: &str,
+    threshold: f64,
+    max_outputs: usize,
+) -> PyResult<Vec<FailureCluster>> {
+    let config = ClusterConfig {
+        metric: parse_metric(metric)?,
+        threshold,
+        max_outputs,
+    };
+    py.allow_threads(|| cluster_outputs(&outputs, mutation_types.as_deref(), &config))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_failure_modes() {
+        let outputs = strings(&[
+            "I'm sorry, but I can't help with that request.",
+            "Traceback (most recent call last): KeyError: 'order_id'",
+            "I'm sorry, but I cannot help with that request.",
+            "I'm sorry, but I can't help with this request.",
+            "Traceback (most recent call last): KeyError: 'user_id'",
+            "The capital of France is Berlin.",
+        ]);
+        let types = strings(&["noise", "paraphrase", "noise", "tone_shift", "noise", "paraphrase"]);
+        let clusters = cluster_outputs(&outputs, Some(&types), &ClusterConfig::default()).unwrap();
+        let members: Vec<Vec<usize>> = clusters.iter().map(|c| c.members.clone()).collect();
+        assert_eq!(members, vec![vec![0, 2, 3], vec![1, 4], vec![5]]);
+        assert_eq!(clusters[0].representative, 0);
+        assert_eq!(clusters[0].mutation_types["noise"], 2);
+        assert!(clusters[0].mean_similarity >= 0.5 && clusters[0].mean_similarity < 1.0);
+        assert_eq!(clusters[2].mean_similarity, 1.0);
+    }
+
+    #[test]
+    fn test_center_choice_and_validation() {
+        // The middle output is close to both others, which are not close
+        // to each other, so it becomes the center
+        let outputs = strings(&["aaaa bbbb", "aaaa bbbb cccc", "bbbb cccc"]);
+        let config = ClusterConfig {
+            metric: Metric::Jaccard,
+            threshold: 0.6,
+            ..Default::default()
+        };
+        let clusters = cluster_outputs(&outputs, None, &config).unwrap();
+        assert_eq!(clusters[0].representative, 1);
+        assert_eq!(clusters[0].size, 3);
+        assert!(clusters[0].mutation_types.is_empty());
+
+        assert!(cluster_outputs(&outputs, Some(&strings(&["noise"])), &config).is_err());
+        let bad = ClusterConfig {
+            threshold: 0.0,
+            ..config
+        };
+        assert!(cluster_outputs(&outputs, None, &bad).is_err());
+        assert!(cluster_outputs(&[], None, &config).unwrap().is_empty());
+        let capped = ClusterConfig {
+            max_outputs: 2,
+            ..config
+        };
+        assert!(cluster_outputs(&outputs, None, &capped).unwrap_err().contains("max_outputs"));
+    }
+}
@@ -0,0 +1,409 @@
+//! Composite multi-objective score
+//!
+//! Deploy gates usually care about more than correctness: an agent that
+//! passes every mutation but takes 20 seconds or costs a dollar per run is
+//! not shippable either. The composite score is a weighted mean of three
+//! components, each normalized to 0..=1:
+//!
+//! - robustness: the robustness score as is,
+//! - latency: one latency statistic (p50, p95, p99 or mean),
+//! - cost: the run's cost, when known.
+//!
+//! Latency and cost are normalized against a target (scores 1.0) and a
+//! limit (scores 0.0), either linearly in between or as `target / value`.
+//! When the cost is unknown its weight is dropped and the others are
+//! renormalized, so runs without billing data still get a score.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{validate_cost, MutationResult, TestStatistics};
+
+/// Latency statistic a composite score normalizes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LatencyStatistic {
+    #[serde(rename = "p50_latency_ms")]
+    P50,
+    #[default]
+    #[serde(rename = "p95_latency_ms")]
+    P95,
+    #[serde(rename = "p99_latency_ms")]
+    P99,
+    #[serde(rename = "avg_latency_ms")]
+    Mean,
+}
+
+impl LatencyStatistic {
+    /// Statistic by its `TestStatistics` field name
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "p50_latency_ms" => Ok(LatencyStatistic::P50),
+            "p95_latency_ms" => Ok(LatencyStatistic::P95),
+            "p99_latency_ms" => Ok(LatencyStatistic::P99),
+            "avg_latency_ms" => Ok(LatencyStatistic::Mean),
+            other => Err(format!(
+                "unknown latency statistic '{}' (expected p50_latency_ms, p95_latency_ms, p99_latency_ms or \
+                 avg_latency_ms)",
+                other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LatencyStatistic::P50 => "p50_latency_ms",
+            LatencyStatistic::P95 => "p95_latency_ms",
+            LatencyStatistic::P99 => "p99_latency_ms",
+            LatencyStatistic::Mean => "avg_latency_ms",
+        }
+    }
+
+    pub fn of(&self, stats: &TestStatistics) -> f64 {
+        match self {
+            LatencyStatistic::P50 => stats.p50_latency_ms,
+            LatencyStatistic::P95 => stats.p95_latency_ms,
+            LatencyStatistic::P99 => stats.p99_latency_ms,
+            LatencyStatistic::Mean => stats.avg_latency_ms,
+        }
+    }
+}
+
+/// How a latency or cost between target and limit is scored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Normalization {
+    /// Falls linearly from 1.0 at the target to 0.0 at the limit
+    #[default]
+    Linear,
+    /// `target / value`
+    Ratio,
+}
+
+impl Normalization {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "linear" => Ok(Normalization::Linear),
+            "ratio" => Ok(Normalization::Ratio),
+            other => Err(format!("unknown normalization '{}' (expected linear or ratio)", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Normalization::Linear => "linear",
+            Normalization::Ratio => "ratio",
+        }
+    }
+}
+
+/// Weights, targets and limits of the composite score
+#[pyclass]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositeConfig {
+    #[pyo3(get, set)]
+    pub robustness_weight: f64,
+    #[pyo3(get, set)]
+    pub latency_weight: f64,
+    #[pyo3(get, set)]
+    pub cost_weight: f64,
+    pub latency_statistic: LatencyStatistic,
+    #[pyo3(get, set)]
+    pub latency_target_ms: f64,
+    #[pyo3(get, set)]
+    pub latency_limit_ms: f64,
+    #[pyo3(get, set)]
+    pub cost_target: f64,
+    #[pyo3(get, set)]
+    pub cost_limit: f64,
+    pub normalization: Normalization,
+}
+
+impl Default for CompositeConfig {
+    fn default() -> Self {
+        Self {
+            robustness_weight: 0.6,
+            latency_weight: 0.25,
+            cost_weight: 0.15,
+            latency_statistic: LatencyStatistic::P95,
+            latency_target_ms: 1000.0,
+            latency_limit_ms: 10_000.0,
+            cost_target: 0.1,
+            cost_limit: 1.0,
+            normalization: Normalization::Linear,
+        }
+    }
+}
+
+impl CompositeConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let weights = [self.robustness_weight, self.latency_weight, self.cost_weight];
+        if weights.iter().any(|w| !w.is_finite() || *w < 0.0) || weights.iter().sum::<f64>() <= 0.0 {
+            return Err(format!(
+                "weights must be finite and non-negative with a positive sum, got {:?}",
+                weights
+            ));
+        }
+        for (name, target, limit) in [
+            ("latency", self.latency_target_ms, self.latency_limit_ms),
+            ("cost", self.cost_target, self.cost_limit),
+        ] {
+            if !(target > 0.0 && limit > target && limit.is_finite()) {
+                return Err(format!(
+                    "{}: need 0 < target < limit, got target {} and limit {}",
+                    name, target, limit
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Map a latency or cost to 0..=1: 1.0 at or below `target`, 0.0 at
+    /// or above `limit`.
+    fn normalize(&self, value: f64, target: f64, limit: f64) -> f64 {
+        if value <= target {
+            1.0
+        } else if value >= limit {
+            0.0
+        } else {
+            match self.normalization {
+                Normalization::Ratio => target / value,
+                Normalization::Linear => (limit - value) / (limit - target),
+            }
+        }
+    }
+
+    /// Composite of a robustness score, a latency in ms and an optional cost.
+    pub fn score(&self, robustness: f64, latency_ms: f64, cost: Option<f64>) -> Result<CompositeScore, String> {
+        self.validate()?;
+        if let Some(c) = cost.filter(|c| c.is_nan() || *c < 0.0) {
+            return Err(format!("cost must be non-negative, got {}", c));
+        }
+        if latency_ms.is_nan() {
+            return Err("latency is NaN".to_string());
+        }
+        let latency_score = self.normalize(latency_ms, self.latency_target_ms, self.latency_limit_ms);
+        let cost_score = cost.map(|c| self.normalize(c, self.cost_target, self.cost_limit));
+        let cost_weight = if cost.is_some() { self.cost_weight } else { 0.0 };
+        let total_weight = self.robustness_weight + self.latency_weight + cost_weight;
+        if total_weight <= 0.0 {
+            return Err("no component with a positive weight has a value".to_string());
+        }
+        let weighted = self.robustness_weight * robustness
+            + self.latency_weight * latency_score
+            + cost_weight * cost_score.unwrap_or(0.0);
+        Ok(CompositeScore {
+            score: weighted / total_weight,
+            robustness,
+            latency_ms,
+            latency_score,
+            cost,
+            cost_score,
+        })
+    }
+
+    /// Composite of a run's statistics.
+    pub fn score_statistics(&self, stats: &TestStatistics, cost: Option<f64>) -> Result<CompositeScore, String> {
+        self.score(stats.robustness_score, self.latency_statistic.of(stats), cost)
+    }
+}
+
+#[pymethods]
+impl CompositeConfig {
+    #[new]
+    #[pyo3(signature = (
+        robustness_weight = 0.6,
+        latency_weight = 0.25,
+        cost_weight = 0.15,
+        latency_statistic = "p95_latency_ms",
+        latency_target_ms = 1000.0,
+        latency_limit_ms = 10_000.0,
+        cost_target = 0.1,
+        cost_limit = 1.0,
+        normalization = "linear"
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        robustness_weight: f64,
+        latency_weight: f64,
+        cost_weight: f64,
+        latency_statistic: &str,
+        latency_target_ms: f64,
+        latency_limit_ms: f64,
+        cost_target: f64,
+        cost_limit: f64,
+        normalization: &str,
+    ) -> PyResult<Self> {
+        let config = Self {
+            robustness_weight,
+            latency_weight,
+            cost_weight,
+            latency_statistic: LatencyStatistic::parse(latency_statistic).map_err(PyValueError::new_err)?,
+            latency_target_ms,
+            latency_limit_ms,
+            cost_target,
+            cost_limit,
+            normalization: Normalization::parse(normalization).map_err(PyValueError::new_err)?,
+        };
+        config.validate().map_err(PyValueError::new_err)?;
+        Ok(config)
+    }
+
+    /// One of "p50_latency_ms", "p95_latency_ms", "p99_latency_ms" or "avg_latency_ms"
+    #[getter(latency_statistic)]
+    fn latency_statistic_name(&self) -> &'static str {
+        self.latency_statistic.name()
+    }
+
+    #[setter(latency_statistic)]
+    fn set_latency_statistic(&mut self, name: &str) -> PyResult<()> {
+        self.latency_statistic = LatencyStatistic::parse(name).map_err(PyValueError::new_err)?;
+        Ok(())
+    }
+
+    /// "linear" or "ratio"
+    #[getter(normalization)]
+    fn normalization_name(&self) -> &'static str {
+        self.normalization.name()
+    }
+
+    #[setter(normalization)]
+    fn set_normalization(&mut self, name: &str) -> PyResult<()> {
+        self.normalization = Normalization::parse(name).map_err(PyValueError::new_err)?;
+        Ok(())
+    }
+}
+
+/// Composite score with its normalized components
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CompositeScore {
+    pub score: f64,
+    pub robustness: f64,
+    pub latency_ms: f64,
+    pub latency_score: f64,
+    /// Absent when the run's cost is unknown
+    pub cost: Option<f64>,
+    pub cost_score: Option<f64>,
+}
+
+/// Cost of a run: each result's recorded cost, or its prompt and
+/// completion tokens at `cost_per_1k_tokens` when it recorded no cost.
+/// None when no result recorded either; fails on a negative or
+/// non-finite cost or rate.
+pub fn run_cost(results: &[MutationResult], cost_per_1k_tokens: f64) -> Result<Option<f64>, String> {
+    if !(cost_per_1k_tokens.is_finite() && cost_per_1k_tokens >= 0.0) {
+        return Err(format!(
+            "cost per 1k tokens must be finite and non-negative, got {}",
+            cost_per_1k_tokens
+        ));
+    }
+    let mut total: Option<f64> = None;
+    for r in results {
+        validate_cost(r)?;
+        let cost = match (r.cost, r.prompt_tokens, r.completion_tokens) {
+            (Some(cost), _, _) => cost,
+            (None, None, None) => continue,
+            (None, prompt, completion) => {
+                (prompt.unwrap_or(0) + completion.unwrap_or(0)) as f64 / 1000.0 * cost_per_1k_tokens
+            }
+        };
+        *total.get_or_insert(0.0) += cost;
+    }
+    Ok(total)
+}
+
+/// Cost of a run's mutation results.
+///
+/// Results that recorded a cost count at that cost; the others count
+/// their prompt and completion tokens at `cost_per_1k_tokens`. None when
+/// no result recorded a cost or tokens.
+#[pyfunction]
+#[pyo3(name = "run_cost", signature = (results, cost_per_1k_tokens = 0.0))]
+pub fn py_run_cost(results: Vec<MutationResult>, cost_per_1k_tokens: f64) -> PyResult<Option<f64>> {
+    run_cost(&results, cost_per_1k_tokens).map_err(PyValueError::new_err)
+}
+
+/// Composite robustness/latency/cost score for a run.
+///
+/// `statistics` are the run's statistics; the configured latency
+/// statistic is read from them. `cost` is the run's cost in the units of
+/// the configured target and limit, see `run_cost`.
+#[pyfunction]
+#[pyo3(signature = (statistics, config = None, cost = None))]
+pub fn composite_score(
+    statistics: TestStatistics,
+    config: Option<CompositeConfig>,
+    cost: Option<f64>,
+) -> PyResult<CompositeScore> {
+    config
+        .unwrap_or_default()
+        .score_statistics(&statistics, cost)
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalization_and_weights() {
+        let config = CompositeConfig::default();
+        let fast = config.score(0.9, 800.0, Some(0.05)).unwrap();
+        assert_eq!((fast.latency_score, fast.cost_score), (1.0, Some(1.0)));
+        assert!((fast.score - (0.6 * 0.9 + 0.25 + 0.15)).abs() < 1e-12);
+
+        let slow = config.score(0.9, 5500.0, Some(2.0)).unwrap();
+        assert!((slow.latency_score - 0.5).abs() < 1e-12);
+        assert_eq!(slow.cost_score, Some(0.0));
+
+        let ratio = CompositeConfig {
+            normalization: Normalization::Ratio,
+            ..Default::default()
+        };
+        assert!((ratio.score(1.0, 2000.0, None).unwrap().latency_score - 0.5).abs() < 1e-12);
+
+        // Unknown cost: its weight is dropped and the rest renormalized
+        let no_cost = config.score(0.8, 800.0, None).unwrap();
+        assert!((no_cost.score - (0.6 * 0.8 + 0.25) / 0.85).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_validation_and_cost() {
+        assert!(LatencyStatistic::parse("p90_latency_ms").unwrap_err().contains("p90_latency_ms"));
+        assert!(Normalization::parse("log").is_err());
+        assert_eq!(LatencyStatistic::parse("avg_latency_ms").unwrap().name(), "avg_latency_ms");
+        let config: CompositeConfig = serde_json::from_str(
+            "{\"robustness_weight\": 1.0, \"latency_weight\": 0.0, \"cost_weight\": 0.0, \
+             \"latency_statistic\": \"p99_latency_ms\", \"latency_target_ms\": 1.0, \"latency_limit_ms\": 2.0, \
+             \"cost_target\": 1.0, \"cost_limit\": 2.0, \"normalization\": \"ratio\"}",
+        )
+        .unwrap();
+        assert_eq!((config.latency_statistic, config.normalization), (LatencyStatistic::P99, Normalization::Ratio));
+        let inverted = CompositeConfig {
+            cost_limit: 0.01,
+            ..Default::default()
+        };
+        assert!(inverted.validate().is_err());
+
+        let result = |tokens: Option<(usize, usize)>, cost: Option<f64>| MutationResult {
+            mutation_type: "noise".to_string(),
+            passed: true,
+            latency_ms: 10.0,
+            prompt_tokens: tokens.map(|t| t.0),
+            completion_tokens: tokens.map(|t| t.1),
+            cost,
+            ..Default::default()
+        };
+        let results = vec![
+            result(Some((1000, 500)), None),
+            result(None, None),
+            result(Some((400, 100)), Some(0.5)),
+        ];
+        // The recorded cost wins over the tokens of the same result
+        assert!((run_cost(&results, 0.02).unwrap().unwrap() - 0.53).abs() < 1e-12);
+        assert_eq!(run_cost(&results[1..2], 0.02), Ok(None));
+        assert!(run_cost(&results, f64::NAN).is_err());
+        assert!(run_cost(&[result(None, Some(-1.0))], 0.02).is_err());
+    }
+}
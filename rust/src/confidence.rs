@@ -0,0 +1,329 @@
+//! Confidence intervals on the robustness score
+//!
+//! A score of 0.87 from 30 mutations says much less than 0.87 from 3,000.
+//! Two intervals are offered:
+//!
+//! - Wilson score interval: closed form and well behaved near 0 and 1.
+//!   For weighted scores the sample size is Kish's effective size
+//!   `(Σw)² / Σw²`, which equals the mutation count when all weights match.
+//! - Percentile bootstrap: resample (credit, weight) pairs with
+//!   replacement and take the empirical quantiles of the rescored runs.
+//!   Resamples are seeded per index, so the interval is reproducible and
+//!   independent of rayon scheduling.
+//!
+//! Both work on mutation credits under the run's scoring strategy, so
+//! severity scoring's partial credits and the strict or worst-category
+//! scores get an interval the same way the weighted pass rate does.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::rng::{resolve_seed, SplitMix64};
+use crate::scoring::{percentile, PercentileMethod};
+use crate::strategy::{validate_credits, Credit, ScoringStrategy};
+
+/// How the interval is computed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IntervalMethod {
+    Wilson,
+    Bootstrap,
+}
+
+impl IntervalMethod {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "wilson" => Ok(IntervalMethod::Wilson),
+            "bootstrap" => Ok(IntervalMethod::Bootstrap),
+            other => Err(format!("unknown interval method '{}' (expected wilson or bootstrap)", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntervalMethod::Wilson => "wilson",
+            IntervalMethod::Bootstrap => "bootstrap",
+        }
+    }
+}
+
+/// Interval settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IntervalConfig {
+    /// Two-sided confidence level, strictly between 0 and 1
+    pub confidence: f64,
+    pub method: IntervalMethod,
+    /// Bootstrap resamples; ignored by Wilson
+    pub resamples: usize,
+    /// Bootstrap seed; the run seed when None
+    pub seed: Option<u64>,
+}
+
+impl Default for IntervalConfig {
+    fn default() -> Self {
+        Self {
+            confidence: 0.95,
+            method: IntervalMethod::Wilson,
+            resamples: 2000,
+            seed: None,
+        }
+    }
+}
+
+impl IntervalConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.confidence <= 0.0 || self.confidence >= 1.0 || self.confidence.is_nan() {
+            return Err(format!("confidence must be between 0 and 1, got {}", self.confidence));
+        }
+        if self.method == IntervalMethod::Bootstrap && self.resamples == 0 {
+            return Err("bootstrap needs at least one resample".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Robustness score with lower and upper bounds
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreInterval {
+    /// Point estimate: weighted mean credit
+    pub score: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub confidence: f64,
+    /// "wilson" or "bootstrap"
+    pub method: String,
+    /// Effective sample size the interval is based on
+    pub effective_n: f64,
+}
+
+/// Standard normal quantile (Acklam's rational approximation, relative
+/// error below 1.2e-9); `p` must be in (0, 1).
+pub(crate) fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1,
+        2.209460984245205e2,
+        -2.759285104469687e2,
+        1.38357751867269e2,
+        -3.066479806614716e1,
+        2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1,
+        1.615858368580409e2,
+        -1.556989798598866e2,
+        6.680131188771972e1,
+        -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3,
+        -3.223964580411365e-1,
+        -2.400758277161838,
+        -2.549732539343734,
+        4.374664141464968,
+        2.938163982698783,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-3,
+        3.224671290700398e-1,
+        2.445134137142996,
+        3.754408661907416,
+    ];
+    const LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+/// Weighted pass rate of (passed, weight) pairs; 0.0 when the weights sum to zero.
+pub(crate) fn weighted_rate(results: &[(bool, f64)]) -> f64 {
+    let total: f64 = results.iter().map(|(_, w)| w).sum();
+    if total <= 0.0 {
+        return 0.0;
+    }
+    results.iter().filter(|(p, _)| *p).map(|(_, w)| w).sum::<f64>() / total
+}
+
+/// Kish's effective sample size for weights.
+pub(crate) fn effective_n(weights: impl IntoIterator<Item = f64>) -> f64 {
+    let (sum, sum_sq) = weights.into_iter().fold((0.0, 0.0), |(s, sq), w| (s + w, sq + w * w));
+    if sum_sq > 0.0 {
+        sum * sum / sum_sq
+    } else {
+        0.0
+    }
+}
+
+/// Wilson score interval for pass rate `p` over `n` (possibly effective) trials.
+pub fn wilson_interval(p: f64, n: f64, confidence: f64) -> (f64, f64) {
+    if n <= 0.0 {
+        return (0.0, 1.0);
+    }
+    let z = normal_quantile(0.5 + confidence / 2.0);
+    let z2 = z * z;
+    let denom = 1.0 + z2 / n;
+    let centre = (p + z2 / (2.0 * n)) / denom;
+    let half = z * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denom;
+    ((centre - half).max(0.0), (centre + half).min(1.0))
+}
+
+fn bootstrap_bounds(credits: &[Credit], strategy: ScoringStrategy, config: &IntervalConfig) -> (f64, f64) {
+    if credits.is_empty() {
+        return (0.0, 1.0);
+    }
+    let seed = resolve_seed(config.seed);
+    let mut scores: Vec<f64> = (0..config.resamples)
+        .into_par_iter()
+        .map(|i| {
+            let mut rng = SplitMix64::for_item(seed, i);
+            let sample: Vec<Credit> = (0..credits.len()).map(|_| credits[rng.below(credits.len())]).collect();
+            strategy.score(&sample)
+        })
+        .collect();
+    scores.sort_by(f64::total_cmp);
+    let alpha = (1.0 - config.confidence) / 2.0;
+    let at = |q: f64| percentile(&scores, q * 100.0, PercentileMethod::Linear);
+    (at(alpha), at(1.0 - alpha))
+}
+
+/// Interval around the weighted robustness score of (credit, weight)
+/// pairs; credits are in 0..=1, 1.0 for a pass and 0.0 for a failure.
+pub fn score_interval(results: &[(f64, f64)], config: &IntervalConfig) -> Result<ScoreInterval, String> {
+    let credits: Vec<Credit> = results.iter().map(|&(credit, weight)| ("", weight, credit)).collect();
+    strategy_interval(&credits, ScoringStrategy::WeightedAverage, config)
+}
+
+/// Interval around the robustness score of `credits` under `strategy`.
+///
+/// The Wilson interval is centred on the strategy's score with the
+/// effective size of the weights; the bootstrap rescores every resample
+/// with the strategy.
+pub fn strategy_interval(
+    credits: &[Credit],
+    strategy: ScoringStrategy,
+    config: &IntervalConfig,
+) -> Result<ScoreInterval, String> {
+    config.validate()?;
+    validate_credits(credits)?;
+    let score = strategy.score(credits);
+    let n = effective_n(credits.iter().map(|c| c.1));
+    let (lower, upper) = match config.method {
+        IntervalMethod::Wilson => wilson_interval(score, n, config.confidence),
+        IntervalMethod::Bootstrap => bootstrap_bounds(credits, strategy, config),
+    };
+    Ok(ScoreInterval {
+        score,
+        lower,
+        upper,
+        confidence: config.confidence,
+        method: config.method.name().to_string(),
+        effective_n: n,
+    })
+}
+
+/// Confidence interval on the weighted robustness score.
+///
+/// `results` are (passed, weight) pairs as for `calculate_weighted_score`.
+/// `method` is "wilson" or "bootstrap"; `resamples` and `seed` only apply
+/// to the bootstrap.
+#[pyfunction]
+#[pyo3(signature = (results, confidence = 0.95, method = "wilson", resamples = 2000, seed = None))]
+pub fn score_confidence_interval(
+    py: Python<'_>,
+    results: Vec<(bool, f64)>,
+    confidence: f64,
+    method: &str,
+    resamples: usize,
+    seed: Option<u64>,
+) -> PyResult<ScoreInterval> {
+    let config = IntervalConfig {
+        confidence,
+        method: IntervalMethod::parse(method).map_err(PyValueError::new_err)?,
+        resamples,
+        seed,
+    };
+    let credits: Vec<(f64, f64)> = results
+        .iter()
+        .map(|&(passed, weight)| (if passed { 1.0 } else { 0.0 }, weight))
+        .collect();
+    py.allow_threads(|| score_interval(&credits, &config))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unweighted(passed: usize, total: usize) -> Vec<(f64, f64)> {
+        (0..total).map(|i| (if i < passed { 1.0 } else { 0.0 }, 1.0)).collect()
+    }
+
+    #[test]
+    fn test_wilson() {
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.005) + 2.575829).abs() < 1e-6);
+
+        let small = score_interval(&unweighted(8, 10), &IntervalConfig::default()).unwrap();
+        assert!((small.lower - 0.4902).abs() < 1e-4, "{}", small.lower);
+        assert!((small.upper - 0.9433).abs() < 1e-4, "{}", small.upper);
+
+        // Same rate, a hundred times the evidence
+        let large = score_interval(&unweighted(800, 1000), &IntervalConfig::default()).unwrap();
+        assert!(large.upper - large.lower < (small.upper - small.lower) / 5.0);
+
+        let all = score_interval(&unweighted(5, 5), &IntervalConfig::default()).unwrap();
+        assert_eq!(all.upper, 1.0);
+        assert!(all.lower < 1.0);
+    }
+
+    #[test]
+    fn test_weighted_effective_n() {
+        let mut results = unweighted(8, 10);
+        results[0].1 = 10.0;
+        let interval = score_interval(&results, &IntervalConfig::default()).unwrap();
+        assert!((interval.score - 17.0 / 19.0).abs() < 1e-12);
+        assert!(interval.effective_n < 10.0);
+        assert!(score_interval(&[(1.0, -1.0)], &IntervalConfig::default()).is_err());
+        assert!(score_interval(&[(1.5, 1.0)], &IntervalConfig::default()).is_err());
+
+        let partial = score_interval(&[(0.75, 1.0), (0.25, 1.0), (1.0, 2.0)], &IntervalConfig::default()).unwrap();
+        assert_eq!(partial.score, 0.75);
+        assert!(partial.lower < 0.75 && 0.75 < partial.upper);
+    }
+
+    #[test]
+    fn test_bootstrap() {
+        let config = IntervalConfig {
+            method: IntervalMethod::Bootstrap,
+            seed: Some(7),
+            ..Default::default()
+        };
+        let results = unweighted(80, 100);
+        let a = score_interval(&results, &config).unwrap();
+        let b = score_interval(&results, &config).unwrap();
+        assert_eq!(a, b);
+        assert!(a.lower < 0.8 && a.upper > 0.8);
+        let wilson = score_interval(&results, &IntervalConfig::default()).unwrap();
+        assert!((a.lower - wilson.lower).abs() < 0.03 && (a.upper - wilson.upper).abs() < 0.03);
+
+        let bad = IntervalConfig {
+            confidence: 1.0,
+            ..Default::default()
+        };
+        assert!(score_interval(&results, &bad).is_err());
+        assert!(IntervalMethod::parse("exact").is_err());
+    }
+}
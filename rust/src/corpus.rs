@@ -0,0 +1,751 @@
+//! Seed corpus import
+//!
+//! Seed prompts and their golden outputs live in JSONL, CSV or YAML files
+//! that can run to hundreds of thousands of rows. They are parsed and
+//! validated here against one schema:
+//!
+//! | field      | type                      |                      |
+//! |------------|---------------------------|----------------------|
+//! | `prompt`   | string                    | required, non-empty  |
+//! | `id`       | string or number          | unique when present  |
+//! | `expected` | string or list of strings | golden outputs       |
+//! | `category` | string                    |                      |
+//! | `tags`     | list of strings           | `;`-separated in CSV |
+//!
+//! Unknown fields are rejected, and every error names the source line and
+//! the offending field. CSV files need a header row; a leading byte order
+//! mark is skipped in every format. YAML support covers the subset corpora
+//! use: a top-level list of mappings whose values are plain, quoted or
+//! block (`|`, `>`) scalars, or lists of scalars, indented under their key
+//! or not. Quoted scalars may span lines and double-quoted ones take the
+//! `\x`, `\u` and `\U` escapes.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+const FIELDS: &[&str] = &["id", "prompt", "expected", "category", "tags"];
+
+/// One validated seed
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SeedEntry {
+    pub id: Option<String>,
+    pub prompt: String,
+    /// Golden outputs
+    pub expected: Vec<String>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    /// 1-based line the entry starts on
+    pub line: usize,
+}
+
+/// Corpus file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorpusFormat {
+    Jsonl,
+    Csv,
+    Yaml,
+}
+
+impl CorpusFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "jsonl" | "ndjson" => Ok(CorpusFormat::Jsonl),
+            "csv" => Ok(CorpusFormat::Csv),
+            "yaml" | "yml" => Ok(CorpusFormat::Yaml),
+            other => Err(format!("unknown corpus format '{}' (expected jsonl, csv or yaml)", other)),
+        }
+    }
+
+    /// Format implied by a file extension.
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        Self::parse(&ext.to_ascii_lowercase())
+            .map_err(|_| format!("{}: cannot infer corpus format from extension", path.display()))
+    }
+}
+
+fn string_list(value: &Value, field: &str) -> Result<Vec<String>, String> {
+    match value {
+        Value::Null => Ok(Vec::new()),
+        Value::String(s) => Ok(vec![s.clone()]),
+        Value::Array(items) => items
+            .iter()
+            .map(|v| match v {
+                Value::String(s) => Ok(s.clone()),
+                _ => Err(format!("field '{}' must contain only strings", field)),
+            })
+            .collect(),
+        _ => Err(format!("field '{}' must be a string or a list of strings", field)),
+    }
+}
+
+fn optional_string(value: &Value, field: &str) -> Result<Option<String>, String> {
+    match value {
+        Value::Null => Ok(None),
+        Value::String(s) if s.is_empty() => Ok(None),
+        Value::String(s) => Ok(Some(s.clone())),
+        Value::Number(n) if field == "id" => Ok(Some(n.to_string())),
+        _ => Err(format!("field '{}' must be a string", field)),
+    }
+}
+
+/// Check one raw record against the schema.
+pub fn validate_entry(record: &Value, line: usize) -> Result<SeedEntry, String> {
+    let fields = record
+        .as_object()
+        .ok_or_else(|| format!("line {}: expected an object with a 'prompt' field", line))?;
+    let at = |e: String| format!("line {}: {}", line, e);
+    if let Some(unknown) = fields.keys().find(|k| !FIELDS.contains(&k.as_str())) {
+        return Err(at(format!(
+            "unknown field '{}' (expected id, prompt, expected, category or tags)",
+            unknown
+        )));
+    }
+    let prompt = match fields.get("prompt") {
+        None => return Err(at("missing required field 'prompt'".to_string())),
+        Some(Value::String(s)) if !s.trim().is_empty() => s.clone(),
+        Some(_) => return Err(at("field 'prompt' must be a non-empty string".to_string())),
+    };
+    let get = |k: &str| fields.get(k).unwrap_or(&Value::Null);
+    Ok(SeedEntry {
+        id: optional_string(get("id"), "id").map_err(at)?,
+        prompt,
+        expected: string_list(get("expected"), "expected").map_err(at)?,
+        category: optional_string(get("category"), "category").map_err(at)?,
+        tags: string_list(get("tags"), "tags").map_err(at)?,
+        line,
+    })
+}
+
+/// Split CSV text into records (RFC 4180 quoting), each with its start line.
+pub fn parse_csv(text: &str) -> Result<Vec<(usize, Vec<String>)>, String> {
+    let mut records = Vec::new();
+    let (mut record, mut field) = (Vec::new(), String::new());
+    let (mut line, mut start, mut quote_line) = (1, 1, 0);
+    let (mut in_quotes, mut quoted) = (false, false);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                _ => {
+                    if c == '\n' {
+                        line += 1;
+                    }
+                    field.push(c);
+                }
+            }
+            continue;
+        }
+        match c {
+            '"' if field.is_empty() && !quoted => {
+                in_quotes = true;
+                quoted = true;
+                quote_line = line;
+            }
+            ',' => {
+                record.push(std::mem::take(&mut field));
+                quoted = false;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                quoted = false;
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push((start, std::mem::take(&mut record)));
+                }
+                record.clear();
+                line += 1;
+                start = line;
+            }
+            _ => field.push(c),
+        }
+    }
+    if in_quotes {
+        return Err(format!("line {}: unterminated quoted field", quote_line));
+    }
+    if !field.is_empty() || !record.is_empty() || quoted {
+        record.push(field);
+        records.push((start, record));
+    }
+    Ok(records)
+}
+
+fn csv_records(text: &str) -> Result<Vec<(usize, Value)>, String> {
+    let mut rows = parse_csv(text)?.into_iter();
+    let (_, header) = rows.next().ok_or("empty CSV file: a header row is required")?;
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_string()).collect();
+    rows.map(|(line, row)| {
+        if row.len() != header.len() {
+            return Err(format!("line {}: expected {} columns, found {}", line, header.len(), row.len()));
+        }
+        let mut fields = Map::new();
+        for (name, cell) in header.iter().zip(row) {
+            let value = match name.as_str() {
+                "tags" => Value::Array(
+                    cell.split(';')
+                        .map(str::trim)
+                        .filter(|t| !t.is_empty())
+                        .map(|t| Value::String(t.to_string()))
+                        .collect(),
+                ),
+                "expected" if cell.is_empty() => Value::Null,
+                _ => Value::String(cell),
+            };
+            fields.insert(name.clone(), value);
+        }
+        Ok((line, Value::Object(fields)))
+    })
+    .collect()
+}
+
+fn jsonl_records(text: &str) -> Result<Vec<(usize, Value)>, String> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .map(|(i, l)| (i + 1, l))
+        .filter(|(_, l)| !l.trim().is_empty())
+        .collect();
+    lines
+        .par_iter()
+        .map(|&(line, l)| {
+            serde_json::from_str(l)
+                .map(|v| (line, v))
+                .map_err(|e| format!("line {}: invalid JSON: {}", line, e))
+        })
+        .collect()
+}
+
+/// Line-oriented reader for the YAML subset
+struct YamlReader<'a> {
+    lines: Vec<&'a str>,
+    pos: usize,
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn strip_comment(s: &str) -> &str {
+    match s.find(" #") {
+        Some(i) => &s[..i],
+        None if s.starts_with('#') => "",
+        None => s,
+    }
+}
+
+/// The character of a `\x`, `\u` or `\U` escape with `digits` hex
+/// digits; a `\u` high surrogate must be followed by its low surrogate.
+fn hex_escape(chars: &mut std::str::Chars<'_>, kind: char, digits: usize, line: usize) -> Result<char, String> {
+    let read = |chars: &mut std::str::Chars<'_>| -> Result<u32, String> {
+        let hex: String = chars.by_ref().take(digits).collect();
+        if hex.len() != digits || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("line {}: '\\{}' needs {} hex digits", line, kind, digits));
+        }
+        Ok(u32::from_str_radix(&hex, 16).unwrap_or_default())
+    };
+    let mut code = read(chars)?;
+    if kind == 'u' && (0xD800..0xDC00).contains(&code) {
+        let low = match (chars.next(), chars.next()) {
+            (Some('\\'), Some('u')) => read(chars)?,
+            _ => 0,
+        };
+        if !(0xDC00..0xE000).contains(&low) {
+            return Err(format!("line {}: unpaired surrogate '\\u{:04X}'", line, code));
+        }
+        code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+    }
+    char::from_u32(code).ok_or_else(|| format!("line {}: '\\{}' escape {:X} is not a character", line, kind, code))
+}
+
+/// Whether the quoted scalar opened by `text`'s first character is closed.
+fn closes_quote(text: &str, quote: char) -> bool {
+    let mut chars = text.chars().skip(1).peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if quote == '"' => {
+                chars.next();
+            }
+            '\'' if quote == '\'' && chars.peek() == Some(&'\'') => {
+                chars.next();
+            }
+            c if c == quote => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+fn yaml_scalar(raw: &str, line: usize) -> Result<Value, String> {
+    let s = raw.trim();
+    if let Some(body) = s.strip_prefix('"') {
+        let mut out = String::new();
+        let mut chars = body.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => {
+                    let rest: String = chars.collect();
+                    return match strip_comment(&rest).trim() {
+                        "" => Ok(Value::String(out)),
+                        _ => Err(format!("line {}: unexpected text after quoted string", line)),
+                    };
+                }
+                '\\' => match chars.next() {
+                    Some('n') => out.push('\n'),
+                    Some('t') => out.push('\t'),
+                    Some('r') => out.push('\r'),
+                    Some('0') => out.push('\0'),
+                    Some(e @ ('"' | '\\' | '/' | ' ')) => out.push(e),
+                    Some(e @ ('x' | 'u' | 'U')) => {
+                        let digits = match e {
+                            'x' => 2,
+                            'u' => 4,
+                            _ => 8,
+                        };
+                        out.push(hex_escape(&mut chars, e, digits, line)?);
+                    }
+                    other => return Err(format!("line {}: unsupported escape '\\{}'", line, other.unwrap_or(' '))),
+                },
+                _ => out.push(c),
+            }
+        }
+        return Err(format!("line {}: unterminated double-quoted string", line));
+    }
+    if let Some(body) = s.strip_prefix('\'') {
+        let mut out = String::new();
+        let mut chars = body.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\'' {
+                if chars.peek() == Some(&'\'') {
+                    chars.next();
+                    out.push('\'');
+                    continue;
+                }
+                let rest: String = chars.collect();
+                return match strip_comment(&rest).trim() {
+                    "" => Ok(Value::String(out)),
+                    _ => Err(format!("line {}: unexpected text after quoted string", line)),
+                };
+            }
+            out.push(c);
+        }
+        return Err(format!("line {}: unterminated single-quoted string", line));
+    }
+    match strip_comment(s).trim() {
+        "" | "~" | "null" => Ok(Value::Null),
+        plain => Ok(Value::String(plain.to_string())),
+    }
+}
+
+fn yaml_flow_list(raw: &str, line: usize) -> Result<Value, String> {
+    let inner = strip_comment(raw)
+        .trim()
+        .strip_prefix('[')
+        .and_then(|s| s.strip_suffix(']'))
+        .ok_or_else(|| format!("line {}: unterminated flow list", line))?;
+    let mut items = Vec::new();
+    let (mut start, mut quote) = (0, None);
+    for (i, c) in inner.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (q, Some(open)) if q == open => quote = None,
+            (',', None) => {
+                items.push(yaml_scalar(&inner[start..i], line)?);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if !inner[start..].trim().is_empty() {
+        items.push(yaml_scalar(&inner[start..], line)?);
+    }
+    Ok(Value::Array(items))
+}
+
+impl<'a> YamlReader<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            lines: text.lines().collect(),
+            pos: 0,
+        }
+    }
+
+    /// Next line with content, skipping blanks and comments: (line number, indent, text).
+    fn peek(&mut self) -> Option<(usize, usize, &'a str)> {
+        while self.pos < self.lines.len() {
+            let l = self.lines[self.pos];
+            let t = l.trim();
+            if t.is_empty() || t.starts_with('#') || t == "---" {
+                self.pos += 1;
+                continue;
+            }
+            if l.starts_with('\t') {
+                return Some((self.pos + 1, usize::MAX, t));
+            }
+            return Some((self.pos + 1, indent_of(l), l.trim_start_matches(' ')));
+        }
+        None
+    }
+
+    fn block_scalar(&mut self, header: &str, parent_indent: usize) -> String {
+        let (folded, strip) = (header.starts_with('>'), header.contains('-'));
+        let mut body: Vec<&str> = Vec::new();
+        let mut block_indent = None;
+        while self.pos < self.lines.len() {
+            let l = self.lines[self.pos];
+            if l.trim().is_empty() {
+                body.push("");
+                self.pos += 1;
+                continue;
+            }
+            let indent = indent_of(l);
+            if indent <= parent_indent {
+                break;
+            }
+            let bi = *block_indent.get_or_insert(indent);
+            body.push(if indent >= bi { &l[bi..] } else { l.trim_start() });
+            self.pos += 1;
+        }
+        while body.last() == Some(&"") {
+            body.pop();
+        }
+        let mut out = String::new();
+        for (i, l) in body.iter().enumerate() {
+            if i > 0 {
+                let sep = if folded && !l.is_empty() && !body[i - 1].is_empty() { " " } else { "\n" };
+                out.push_str(sep);
+            }
+            out.push_str(l);
+        }
+        if !strip && !out.is_empty() {
+            out.push('\n');
+        }
+        out
+    }
+
+    /// A scalar starting with `first` on `line`, with `pos` just past that
+    /// line. A quoted scalar left open runs over the following lines, which
+    /// are folded as YAML does: a line break becomes a space, each blank
+    /// line a newline, and a break escaped by `\` disappears.
+    fn scalar(&mut self, first: &str, line: usize) -> Result<Value, String> {
+        let mut text = first.trim().to_string();
+        let quote = match text.chars().next() {
+            Some(q @ ('"' | '\'')) => q,
+            _ => return yaml_scalar(&text, line),
+        };
+        let mut blank_lines = 0;
+        while !closes_quote(&text, quote) && self.pos < self.lines.len() {
+            let next = self.lines[self.pos].trim();
+            self.pos += 1;
+            if next.is_empty() {
+                blank_lines += 1;
+                continue;
+            }
+            text.truncate(text.trim_end_matches([' ', '\t']).len());
+            let backslashes = text.len() - text.trim_end_matches('\\').len();
+            if quote == '"' && backslashes % 2 == 1 {
+                text.pop();
+            } else if blank_lines == 0 {
+                text.push(' ');
+            }
+            text.extend(std::iter::repeat_n('\n', blank_lines));
+            text.push_str(next);
+            blank_lines = 0;
+        }
+        yaml_scalar(&text, line)
+    }
+
+    /// Parse `key: value` at `indent`, consuming any continuation lines.
+    fn key_value(&mut self, content: &str, line: usize, indent: usize) -> Result<(String, Value), String> {
+        let (key, rest) = match content.find(": ") {
+            Some(i) => (&content[..i], &content[i + 2..]),
+            None => match content.trim_end().strip_suffix(':') {
+                Some(k) => (k, ""),
+                None => return Err(format!("line {}: expected 'key: value'", line)),
+            },
+        };
+        let key = key.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+        let rest = rest.trim();
+        let value = if rest.starts_with('|') || rest.starts_with('>') {
+            Value::String(self.block_scalar(rest, indent))
+        } else if rest.starts_with('[') {
+            yaml_flow_list(rest, line)?
+        } else if strip_comment(rest).trim().is_empty() {
+            // A list of scalars indented under the key or level with it, or null
+            let mut items = Vec::new();
+            let mut item_indent = None;
+            while let Some((no, ind, text)) = self.peek() {
+                let item = text.strip_prefix("- ").or_else(|| (text.trim_end() == "-").then_some(""));
+                let level = ind == indent && item.is_some();
+                if ind == usize::MAX || !(ind > indent || level) || item_indent.is_some_and(|i| i != ind) {
+                    break;
+                }
+                item_indent = Some(ind);
+                let item = item.ok_or_else(|| format!("line {}: field '{}' must be a list of scalars", no, key))?;
+                self.pos += 1;
+                items.push(self.scalar(item, no)?);
+            }
+            if items.is_empty() {
+                Value::Null
+            } else {
+                Value::Array(items)
+            }
+        } else {
+            self.scalar(rest, line)?
+        };
+        Ok((key, value))
+    }
+}
+
+/// One top-level YAML mapping with the line of each of its keys
+pub(crate) struct YamlMapping {
+    pub line: usize,
+    pub fields: Map<String, Value>,
+    pub key_lines: HashMap<String, usize>,
+}
+
+/// The top-level list of mappings in `text`; `expected` describes the
+/// list items for the error raised when the document is not a list.
+pub(crate) fn yaml_mappings(text: &str, expected: &str) -> Result<Vec<YamlMapping>, String> {
+    let mut reader = YamlReader::new(text);
+    let mut records = Vec::new();
+    let base = match reader.peek() {
+        Some((_, ind, _)) if ind != usize::MAX => ind,
+        Some((no, _, _)) => return Err(format!("line {}: tabs are not allowed for indentation", no)),
+        None => return Ok(records),
+    };
+    while let Some((no, ind, text)) = reader.peek() {
+        if ind == usize::MAX {
+            return Err(format!("line {}: tabs are not allowed for indentation", no));
+        }
+        if ind != base {
+            return Err(format!("line {}: inconsistent indentation", no));
+        }
+        let first = text
+            .strip_prefix("- ")
+            .or_else(|| (text.trim_end() == "-").then_some(""))
+            .ok_or_else(|| format!("line {}: expected a list of {}", no, expected))?;
+        reader.pos += 1;
+        let key_indent = base + 2 + (first.len() - first.trim_start().len());
+        let mut fields = Map::new();
+        let mut key_lines = HashMap::new();
+        let mut pending = (!first.trim().is_empty()).then_some((no, first.trim_start()));
+        loop {
+            if let Some((line, content)) = pending.take() {
+                let (key, value) = reader.key_value(content, line, key_indent)?;
+                if fields.insert(key.clone(), value).is_some() {
+                    return Err(format!("line {}: duplicate field '{}'", line, key));
+                }
+                key_lines.insert(key, line);
+            }
+            match reader.peek() {
+                Some((line, ind, content)) if ind == key_indent => {
+                    reader.pos += 1;
+                    pending = Some((line, content));
+                }
+                Some((line, ind, _)) if ind > base => {
+                    return Err(format!("line {}: inconsistent indentation", line));
+                }
+                _ => break,
+            }
+        }
+        records.push(YamlMapping {
+            line: no,
+            fields,
+            key_lines,
+        });
+    }
+    Ok(records)
+}
+
+fn yaml_records(text: &str) -> Result<Vec<(usize, Value)>, String> {
+    Ok(yaml_mappings(text, "seed entries ('- prompt: ...')")?
+        .into_iter()
+        .map(|m| (m.line, Value::Object(m.fields)))
+        .collect())
+}
+
+/// Parse and validate a corpus held in memory.
+pub fn parse_corpus(text: &str, format: CorpusFormat) -> Result<Vec<SeedEntry>, String> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let raw = match format {
+        CorpusFormat::Jsonl => jsonl_records(text)?,
+        CorpusFormat::Csv => csv_records(text)?,
+        CorpusFormat::Yaml => yaml_records(text)?,
+    };
+    let entries: Vec<SeedEntry> = raw
+        .par_iter()
+        .map(|(line, value)| validate_entry(value, *line))
+        .collect::<Result<_, _>>()?;
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for e in &entries {
+        if let Some(id) = &e.id {
+            if let Some(first) = seen.insert(id, e.line) {
+                return Err(format!("line {}: duplicate id '{}' (first used on line {})", e.line, id, first));
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// Read, parse and validate a corpus file; errors are prefixed with the path.
+pub fn load_corpus(path: &Path, format: Option<CorpusFormat>) -> Result<Vec<SeedEntry>, String> {
+    let format = match format {
+        Some(f) => f,
+        None => CorpusFormat::from_path(path)?,
+    };
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    parse_corpus(&text, format).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Load seed prompts and golden outputs from a JSONL, CSV or YAML file.
+///
+/// The format comes from the file extension unless `format` ("jsonl",
+/// "csv" or "yaml") is given. Schema errors raise ValueError naming the
+/// line and field.
+#[pyfunction]
+#[pyo3(signature = (path, format = None))]
+pub fn load_seed_corpus(py: Python<'_>, path: &str, format: Option<&str>) -> PyResult<Vec<SeedEntry>> {
+    let format = format.map(CorpusFormat::parse).transpose().map_err(PyValueError::new_err)?;
+    let path = Path::new(path);
+    if !path.is_file() {
+        return Err(PyIOError::new_err(format!("{}: no such file", path.display())));
+    }
+    py.allow_threads(|| load_corpus(path, format)).map_err(PyValueError::new_err)
+}
+
+/// Parse and validate seed corpus text in the given format.
+#[pyfunction]
+pub fn parse_seed_corpus(py: Python<'_>, text: &str, format: &str) -> PyResult<Vec<SeedEntry>> {
+    let format = CorpusFormat::parse(format).map_err(PyValueError::new_err)?;
+    py.allow_threads(|| parse_corpus(text, format)).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jsonl() {
+        let text = r#"{"id": 1, "prompt": "Book a flight", "expected": ["Booked", "Done"]}
+
+{"prompt": "Cancel it", "tags": ["cancel"], "category": "booking"}
+"#;
+        let seeds = parse_corpus(text, CorpusFormat::Jsonl).unwrap();
+        assert_eq!(seeds.len(), 2);
+        assert_eq!(seeds[0].id.as_deref(), Some("1"));
+        assert_eq!(seeds[0].expected, vec!["Booked", "Done"]);
+        assert_eq!((seeds[1].line, seeds[1].category.as_deref()), (3, Some("booking")));
+
+        let err = parse_corpus("{\"prompt\": \"a\"}\n{\"promt\": \"b\"}", CorpusFormat::Jsonl).unwrap_err();
+        assert_eq!(err, "line 2: unknown field 'promt' (expected id, prompt, expected, category or tags)");
+        let err = parse_corpus("{\"prompt\": 3}", CorpusFormat::Jsonl).unwrap_err();
+        assert!(err.contains("'prompt'"), "{}", err);
+        assert!(parse_corpus("{\"prompt\": \"a\"", CorpusFormat::Jsonl).unwrap_err().starts_with("line 1: invalid JSON"));
+    }
+
+    #[test]
+    fn test_csv() {
+        let text = "id,prompt,expected,tags\r\n\
+                    a,\"Say \"\"hi\"\", then\nstop\",Hi,greeting; short\r\n\
+                    b,Plain prompt,,\n";
+        let seeds = parse_corpus(text, CorpusFormat::Csv).unwrap();
+        assert_eq!(seeds[0].prompt, "Say \"hi\", then\nstop");
+        assert_eq!(seeds[0].tags, vec!["greeting", "short"]);
+        assert_eq!((seeds[1].line, seeds[1].expected.len()), (4, 0));
+
+        assert_eq!(
+            parse_corpus("prompt,id\nx,1\ny\n", CorpusFormat::Csv).unwrap_err(),
+            "line 3: expected 2 columns, found 1"
+        );
+        assert!(parse_corpus("prompt\n\"open", CorpusFormat::Csv).unwrap_err().contains("unterminated"));
+        assert!(parse_corpus("id\n1\n", CorpusFormat::Csv).unwrap_err().contains("missing required field 'prompt'"));
+        // Spreadsheet exports start with a byte order mark
+        let seeds = parse_corpus("\u{feff}prompt,id\nx,1\n", CorpusFormat::Csv).unwrap();
+        assert_eq!(seeds[0].id.as_deref(), Some("1"));
+    }
+
+    #[test]
+    fn test_yaml() {
+        let text = r#"# seeds
+- id: refund
+  prompt: "What is the refund policy?"
+  expected:
+    - Refunds within 30 days
+    - 'Thirty days, that''s it'
+  tags: [policy, "faq"]
+- prompt: |
+    Line one
+    Line two
+  category: multi # trailing comment
+"#;
+        let seeds = parse_corpus(text, CorpusFormat::Yaml).unwrap();
+        assert_eq!(seeds.len(), 2);
+        assert_eq!(seeds[0].expected, vec!["Refunds within 30 days", "Thirty days, that's it"]);
+        assert_eq!(seeds[0].tags, vec!["policy", "faq"]);
+        assert_eq!(seeds[1].prompt, "Line one\nLine two\n");
+        assert_eq!((seeds[1].line, seeds[1].category.as_deref()), (8, Some("multi")));
+
+        let err = parse_corpus("prompt: x\n", CorpusFormat::Yaml).unwrap_err();
+        assert!(err.starts_with("line 1: expected a list"), "{}", err);
+        let err = parse_corpus("- prompt: a\n   id: b\n", CorpusFormat::Yaml).unwrap_err();
+        assert!(err.starts_with("line 2: inconsistent indentation"), "{}", err);
+    }
+
+    #[test]
+    fn test_yaml_indentless_lists_escapes_and_multiline_quotes() {
+        let text = r#"- prompt: "Caf\xE9 \u2615 \U0001F600 \uD83D\uDE00"
+  expected:
+  - first
+  - 'second'
+  tags: [a]
+- prompt: "Summarize
+    this long
+    request\
+    ed text
+
+    please"
+  expected: 'it''s
+    folded'
+"#;
+        let seeds = parse_corpus(text, CorpusFormat::Yaml).unwrap();
+        assert_eq!(seeds[0].prompt, "Café ☕ 😀 😀");
+        assert_eq!(seeds[0].expected, vec!["first", "second"]);
+        assert_eq!(seeds[0].tags, vec!["a"]);
+        assert_eq!(seeds[1].prompt, "Summarize this long requested text\nplease");
+        assert_eq!(seeds[1].expected, vec!["it's folded"]);
+        assert_eq!(seeds[1].line, 6);
+
+        for (bad, message) in [
+            ("- prompt: \"\\u12\"\n", "needs 4 hex digits"),
+            ("- prompt: \"\\uD83D x\"\n", "unpaired surrogate"),
+            ("- prompt: \"never closed\n  id: x\n", "unterminated double-quoted"),
+        ] {
+            let err = parse_corpus(bad, CorpusFormat::Yaml).unwrap_err();
+            assert!(err.starts_with("line 1: ") && err.contains(message), "{}", err);
+        }
+        // A level list item must not swallow the next top-level entry
+        let seeds = parse_corpus("- prompt: a\n  tags:\n  - x\n- prompt: b\n", CorpusFormat::Yaml).unwrap();
+        assert_eq!((seeds.len(), seeds[0].tags.clone()), (2, vec!["x".to_string()]));
+    }
+
+    #[test]
+    fn test_duplicate_ids_and_format_names() {
+        let err = parse_corpus("- id: x\n  prompt: a\n- id: x\n  prompt: b\n", CorpusFormat::Yaml).unwrap_err();
+        assert_eq!(err, "line 3: duplicate id 'x' (first used on line 1)");
+        assert_eq!(CorpusFormat::from_path(Path::new("seeds.YML")).unwrap(), CorpusFormat::Yaml);
+        assert!(CorpusFormat::from_path(Path::new("seeds.txt")).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Soft-deadline degradation
+//!
+//! A CI run has a time budget. When the remaining mutations, at the pace
+//! observed so far, would overrun it, the executor switches to a designated
+//! "fast" subset of checks for the rest of the run instead of timing out or
+//! skipping mutations. Every result evaluated in reduced mode is recorded so
+//! reports can say which verdicts came from the partial check set.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Check mode for a mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckMode {
+    Full,
+    Fast,
+}
+
+impl CheckMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckMode::Full => "full",
+            CheckMode::Fast => "fast",
+        }
+    }
+}
+
+/// Tracks pace against a soft deadline and decides when to degrade
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadlineBudget {
+    pub deadline_secs: f64,
+    /// Fraction of the deadline kept in reserve when projecting
+    pub safety_margin: f64,
+    pub fast_checks: Vec<String>,
+    full_secs: f64,
+    full_count: usize,
+    /// Index of the first mutation evaluated in fast mode
+    degraded_at: Option<usize>,
+    reduced: Vec<usize>,
+}
+
+impl DeadlineBudget {
+    pub fn new(deadline_secs: f64, fast_checks: Vec<String>, safety_margin: f64) -> Result<Self, String> {
+        if deadline_secs.is_nan() || deadline_secs <= 0.0 {
+            return Err(format!("deadline must be positive, got {}", deadline_secs));
+        }
+        if !(0.0..1.0).contains(&safety_margin) {
+            return Err(format!("safety_margin must be in [0, 1), got {}", safety_margin));
+        }
+        Ok(Self {
+            deadline_secs,
+            safety_margin,
+            fast_checks,
+            full_secs: 0.0,
+            full_count: 0,
+            degraded_at: None,
+            reduced: Vec::new(),
+        })
+    }
+
+    /// Mode for mutation `index` given the time spent so far and how many
+    /// mutations (including this one) are left.
+    ///
+    /// Once the run degrades it stays degraded, so the reduced results form
+    /// one contiguous tail.
+    pub fn mode_for(&mut self, index: usize, elapsed_secs: f64, remaining: usize) -> CheckMode {
+        if self.degraded_at.is_none() {
+            let budget = self.deadline_secs * (1.0 - self.safety_margin);
+            let pace = if self.full_count > 0 {
+                self.full_secs / self.full_count as f64
+            } else {
+                0.0
+            };
+            if elapsed_secs >= budget || elapsed_secs + pace * remaining as f64 > budget {
+                self.degraded_at = Some(index);
+            }
+        }
+        if self.degraded_at.is_some() {
+            self.reduced.push(index);
+            CheckMode::Fast
+        } else {
+            CheckMode::Full
+        }
+    }
+
+    /// Record how long a full-mode evaluation took; fast-mode timings do not
+    /// affect the projection.
+    pub fn record(&mut self, mode: CheckMode, duration_secs: f64) {
+        if mode == CheckMode::Full {
+            self.full_secs += duration_secs.max(0.0);
+            self.full_count += 1;
+        }
+    }
+
+    /// The checks to run in `mode`, keeping the configured order.
+    pub fn checks_for<'a>(&self, mode: CheckMode, checks: &'a [String]) -> Vec<&'a String> {
+        match mode {
+            CheckMode::Full => checks.iter().collect(),
+            CheckMode::Fast => checks.iter().filter(|c| self.fast_checks.contains(c)).collect(),
+        }
+    }
+
+    pub fn degraded_at(&self) -> Option<usize> {
+        self.degraded_at
+    }
+
+    pub fn reduced(&self) -> &[usize] {
+        &self.reduced
+    }
+}
+
+/// Soft-deadline tracker for an executing run.
+///
+/// Ask `mode_for(index, elapsed, remaining)` before evaluating each
+/// mutation, run `checks_for(mode, checks)`, then `record(mode, duration)`.
+#[pyclass(name = "DeadlineBudget")]
+#[derive(Debug, Clone)]
+pub struct PyDeadlineBudget {
+    inner: DeadlineBudget,
+}
+
+fn parse_mode(mode: &str) -> PyResult<CheckMode> {
+    match mode {
+        "full" => Ok(CheckMode::Full),
+        "fast" => Ok(CheckMode::Fast),
+        other => Err(PyValueError::new_err(format!("unknown check mode '{}' (expected full or fast)", other))),
+    }
+}
+
+#[pymethods]
+impl PyDeadlineBudget {
+    #[new]
+    #[pyo3(signature = (deadline_secs, fast_checks, safety_margin = 0.1))]
+    fn new(deadline_secs: f64, fast_checks: Vec<String>, safety_margin: f64) -> PyResult<Self> {
+        DeadlineBudget::new(deadline_secs, fast_checks, safety_margin)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    /// "full" or "fast" for the mutation at `index`.
+    fn mode_for(&mut self, index: usize, elapsed_secs: f64, remaining: usize) -> &'static str {
+        self.inner.mode_for(index, elapsed_secs, remaining).as_str()
+    }
+
+    fn record(&mut self, mode: &str, duration_secs: f64) -> PyResult<()> {
+        self.inner.record(parse_mode(mode)?, duration_secs);
+        Ok(())
+    }
+
+    fn checks_for(&self, mode: &str, checks: Vec<String>) -> PyResult<Vec<String>> {
+        let mode = parse_mode(mode)?;
+        Ok(self.inner.checks_for(mode, &checks).into_iter().cloned().collect())
+    }
+
+    /// Index of the first mutation evaluated with the fast check set, if any.
+    #[getter]
+    fn degraded_at(&self) -> Option<usize> {
+        self.inner.degraded_at()
+    }
+
+    /// Indices of every mutation evaluated in reduced mode.
+    #[getter]
+    fn reduced_indices(&self) -> Vec<usize> {
+        self.inner.reduced().to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checks() -> Vec<String> {
+        vec!["latency".into(), "similarity".into(), "llm_judge".into(), "valid_json".into()]
+    }
+
+    #[test]
+    fn test_degrades_when_projection_overruns() {
+        let mut budget = DeadlineBudget::new(100.0, vec!["latency".into(), "valid_json".into()], 0.1).unwrap();
+        let total = 20;
+        let mut elapsed = 0.0;
+        let mut modes = Vec::new();
+        for i in 0..total {
+            let mode = budget.mode_for(i, elapsed, total - i);
+            // full checks take 8s, fast ones 1s
+            let duration = if mode == CheckMode::Full { 8.0 } else { 1.0 };
+            budget.record(mode, duration);
+            elapsed += duration;
+            modes.push(mode);
+        }
+
+        let first_fast = budget.degraded_at().unwrap();
+        assert!(first_fast > 0);
+        assert!(modes[..first_fast].iter().all(|&m| m == CheckMode::Full));
+        assert!(modes[first_fast..].iter().all(|&m| m == CheckMode::Fast));
+        assert_eq!(budget.reduced(), (first_fast..total).collect::<Vec<_>>().as_slice());
+        assert!(elapsed <= 100.0);
+
+        let checks = checks();
+        let fast: Vec<&String> = budget.checks_for(CheckMode::Fast, &checks);
+        assert_eq!(fast, vec!["latency", "valid_json"]);
+        assert_eq!(budget.checks_for(CheckMode::Full, &checks).len(), 4);
+    }
+
+    #[test]
+    fn test_on_pace_run_stays_full() {
+        let mut budget = DeadlineBudget::new(100.0, vec![], 0.0).unwrap();
+        for i in 0..10 {
+            assert_eq!(budget.mode_for(i, i as f64, 10 - i), CheckMode::Full);
+            budget.record(CheckMode::Full, 1.0);
+        }
+        assert_eq!(budget.degraded_at(), None);
+        assert!(budget.reduced().is_empty());
+
+        assert!(DeadlineBudget::new(0.0, vec![], 0.1).is_err());
+        assert!(DeadlineBudget::new(10.0, vec![], 1.0).is_err());
+    }
+}
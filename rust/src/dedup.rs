@@ -0,0 +1,520 @@
+//! Near-duplicate detection for large mutation sets
+//!
+//! All-pairs comparison is quadratic, so large sets are deduplicated with
+//! MinHash signatures over character shingles and locality-sensitive
+//! hashing: signatures are cut into bands, and a string is only checked
+//! against a few representatives of the band buckets it falls into.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::edit_distance::levenshtein;
+use crate::normalize::{normalize, NormalizeOptions};
+use crate::similarity::shingle_hashes;
+
+/// Parameters for MinHash/LSH deduplication
+#[derive(Debug, Clone, Copy)]
+pub struct MinHashConfig {
+    /// Number of hash permutations per signature
+    pub num_perm: usize,
+    /// Number of LSH bands; must divide `num_perm`
+    pub bands: usize,
+    /// Character shingle size
+    pub shingle_size: usize,
+    /// Minimum estimated Jaccard similarity for two strings to be duplicates
+    pub threshold: f64,
+}
+
+impl Default for MinHashConfig {
+    fn default() -> Self {
+        Self {
+            num_perm: 128,
+            bands: 32,
+            shingle_size: 3,
+            threshold: 0.8,
+        }
+    }
+}
+
+impl MinHashConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.num_perm == 0 || self.bands == 0 || !self.num_perm.is_multiple_of(self.bands) {
+            return Err(format!(
+                "num_perm ({}) must be a positive multiple of bands ({})",
+                self.num_perm, self.bands
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.threshold) {
+            return Err(format!("threshold must be in [0, 1], got {}", self.threshold));
+        }
+        Ok(())
+    }
+}
+
+/// SplitMix64 finaliser, used to derive independent hash permutations.
+#[inline]
+fn mix64(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// MinHash signature of a string's character shingles.
+pub fn minhash_signature(text: &str, config: &MinHashConfig) -> Vec<u64> {
+    let shingles = shingle_hashes(text, config.shingle_size);
+    let mut signature = vec![u64::MAX; config.num_perm];
+    for shingle in shingles {
+        let base = mix64(shingle);
+        for (i, slot) in signature.iter_mut().enumerate() {
+            let h = mix64(base ^ (i as u64).wrapping_mul(0x9e3779b97f4a7c15));
+            if h < *slot {
+                *slot = h;
+            }
+        }
+    }
+    signature
+}
+
+/// Fraction of matching signature slots, an estimate of Jaccard similarity.
+pub fn estimated_jaccard(a: &[u64], b: &[u64]) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / a.len() as f64
+}
+
+fn find(parent: &mut [usize], mut x: usize) -> usize {
+    while parent[x] != x {
+        parent[x] = parent[parent[x]];
+        x = parent[x];
+    }
+    x
+}
+
+/// Most representatives an LSH bucket keeps for comparison.
+///
+/// A templated mutation family lands in one bucket; comparing each member
+/// against a handful of representatives instead of every other member keeps
+/// the work linear in the number of strings.
+const MAX_BUCKET_REPRESENTATIVES: usize = 8;
+
+/// Union signatures at or above the threshold into clusters of two or more
+/// signature indices.
+///
+/// Each band's buckets hold up to `MAX_BUCKET_REPRESENTATIVES` members that
+/// did not match an earlier representative, so a bucket's first member
+/// being unlike the rest never hides a duplicate behind it. Members already
+/// merged with a representative are skipped.
+fn signature_clusters(signatures: &[Vec<u64>], config: &MinHashConfig) -> Vec<Vec<usize>> {
+    let rows = config.num_perm / config.bands;
+    let mut parent: Vec<usize> = (0..signatures.len()).collect();
+    for band in 0..config.bands {
+        let mut buckets: HashMap<&[u64], Vec<usize>> = HashMap::new();
+        for (k, sig) in signatures.iter().enumerate() {
+            let reps = buckets.entry(&sig[band * rows..(band + 1) * rows]).or_default();
+            let root = find(&mut parent, k);
+            if reps.iter().any(|&r| find(&mut parent, r) == root) {
+                continue;
+            }
+            let mut merged = false;
+            for &r in reps.iter() {
+                if estimated_jaccard(sig, &signatures[r]) >= config.threshold {
+                    let (ra, rb) = (find(&mut parent, r), find(&mut parent, k));
+                    if ra != rb {
+                        parent[ra.max(rb)] = ra.min(rb);
+                    }
+                    merged = true;
+                }
+            }
+            if !merged && reps.len() < MAX_BUCKET_REPRESENTATIVES {
+                reps.push(k);
+            }
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..signatures.len() {
+        let root = find(&mut parent, i);
+        clusters.entry(root).or_default().push(i);
+    }
+    let mut clusters: Vec<Vec<usize>> = clusters.into_values().filter(|c| c.len() > 1).collect();
+    clusters.sort_by_key(|c| c[0]);
+    clusters
+}
+
+/// Group near-duplicate strings into clusters of indices.
+///
+/// Only clusters with at least two members are returned; each cluster is
+/// sorted and clusters are ordered by their smallest index.
+pub fn near_duplicate_clusters(strings: &[String], config: &MinHashConfig) -> Vec<Vec<usize>> {
+    let signatures: Vec<Vec<u64>> = strings
+        .par_iter()
+        .map(|s| minhash_signature(s, config))
+        .collect();
+    signature_clusters(&signatures, config)
+}
+
+/// A group of seed prompts that are effectively the same test
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedDuplicateGroup {
+    /// Index of the seed to keep (the first in corpus order)
+    pub representative: usize,
+    /// Indices of the seeds that would re-test it
+    pub duplicates: Vec<usize>,
+    /// Lowest estimated similarity between the representative and a duplicate
+    pub min_similarity: f64,
+}
+
+/// Near-duplicate scan of a seed corpus
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeedCorpusReport {
+    pub total_seeds: usize,
+    /// Seeds left after keeping one representative per group
+    pub unique_seeds: usize,
+    pub groups: Vec<SeedDuplicateGroup>,
+}
+
+impl SeedCorpusReport {
+    /// Indices of the seeds to run, in corpus order.
+    pub fn kept(&self) -> Vec<usize> {
+        let mut dropped = vec![false; self.total_seeds];
+        for d in self.groups.iter().flat_map(|g| &g.duplicates) {
+            dropped[*d] = true;
+        }
+        (0..self.total_seeds).filter(|&i| !dropped[i]).collect()
+    }
+}
+
+#[pymethods]
+impl SeedCorpusReport {
+    #[pyo3(name = "kept")]
+    fn py_kept(&self) -> Vec<usize> {
+        self.kept()
+    }
+}
+
+/// Scan seed prompts for near-duplicate groups before a run.
+///
+/// With `canonicalize`, seeds are compared after NFKC normalization,
+/// casefolding and whitespace collapsing so trivial formatting differences
+/// do not hide a duplicate.
+pub fn scan_seed_corpus(seeds: &[String], config: &MinHashConfig, canonicalize: bool) -> SeedCorpusReport {
+    let opts = NormalizeOptions {
+        casefold: true,
+        ..NormalizeOptions::default()
+    };
+    let canonical: Vec<String> = if canonicalize {
+        seeds.par_iter().map(|s| normalize(s, &opts)).collect()
+    } else {
+        seeds.to_vec()
+    };
+
+    let groups: Vec<SeedDuplicateGroup> = near_duplicate_clusters(&canonical, config)
+        .into_iter()
+        .map(|cluster| {
+            let representative = cluster[0];
+            let head = minhash_signature(&canonical[representative], config);
+            let min_similarity = cluster[1..]
+                .iter()
+                .map(|&i| estimated_jaccard(&head, &minhash_signature(&canonical[i], config)))
+                .fold(1.0, f64::min);
+            SeedDuplicateGroup {
+                representative,
+                duplicates: cluster[1..].to_vec(),
+                min_similarity,
+            }
+        })
+        .collect();
+
+    let redundant: usize = groups.iter().map(|g| g.duplicates.len()).sum();
+    SeedCorpusReport {
+        total_seeds: seeds.len(),
+        unique_seeds: seeds.len() - redundant,
+        groups,
+    }
+}
+
+/// Outcome of deduplicating a mutation set
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct MutationDedup {
+    /// Indices of the surviving mutations, in input order
+    pub kept: Vec<usize>,
+    pub survivors: Vec<String>,
+    /// Removed index -> index of the survivor it was collapsed into
+    pub collapsed: HashMap<usize, usize>,
+    pub exact_duplicates: usize,
+    pub fuzzy_duplicates: usize,
+}
+
+/// Edit-distance similarity in [0, 1].
+fn edit_similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+/// Remove exact, then near-duplicate mutations.
+///
+/// Exact copies are collapsed by hash. Each remaining string is compared by
+/// edit-distance similarity against the earlier survivors sharing one of
+/// its LSH buckets (at most `MAX_BUCKET_REPRESENTATIVES` per bucket) and is
+/// collapsed into the first at or above `threshold`. Only survivors are
+/// compared against, so a chain of small edits never merges two dissimilar
+/// strings.
+pub fn dedup_mutation_set(mutations: &[String], threshold: f64, config: &MinHashConfig) -> MutationDedup {
+    let mut collapsed: HashMap<usize, usize> = HashMap::new();
+    let mut first_seen: HashMap<&str, usize> = HashMap::new();
+    let mut unique: Vec<usize> = Vec::new();
+    for (i, m) in mutations.iter().enumerate() {
+        match first_seen.get(m.as_str()) {
+            Some(&j) => {
+                collapsed.insert(i, j);
+            }
+            None => {
+                first_seen.insert(m, i);
+                unique.push(i);
+            }
+        }
+    }
+    let exact_duplicates = collapsed.len();
+
+    let signatures: Vec<Vec<u64>> = unique
+        .par_iter()
+        .map(|&i| minhash_signature(&mutations[i], config))
+        .collect();
+    let rows = config.num_perm / config.bands;
+    let mut buckets: Vec<HashMap<&[u64], Vec<usize>>> = vec![HashMap::new(); config.bands];
+    // target[k] is the earlier position in `unique` that k collapses into
+    let mut target: Vec<Option<usize>> = vec![None; unique.len()];
+    let mut candidates: Vec<usize> = Vec::new();
+    for (k, sig) in signatures.iter().enumerate() {
+        candidates.clear();
+        for (band, bucket) in buckets.iter().enumerate() {
+            if let Some(survivors) = bucket.get(&sig[band * rows..(band + 1) * rows]) {
+                candidates.extend(survivors);
+            }
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        target[k] = candidates
+            .iter()
+            .copied()
+            .find(|&a| edit_similarity(&mutations[unique[a]], &mutations[unique[k]]) >= threshold);
+        if target[k].is_none() {
+            for (band, bucket) in buckets.iter_mut().enumerate() {
+                let survivors = bucket.entry(&sig[band * rows..(band + 1) * rows]).or_default();
+                if survivors.len() < MAX_BUCKET_REPRESENTATIVES {
+                    survivors.push(k);
+                }
+            }
+        }
+    }
+    let mut kept = Vec::new();
+    for (k, &i) in unique.iter().enumerate() {
+        match target[k] {
+            Some(a) => {
+                collapsed.insert(i, unique[a]);
+            }
+            None => kept.push(i),
+        }
+    }
+    // exact copies of a fuzzy-collapsed string follow it to its survivor
+    let fuzzy: HashMap<usize, usize> = collapsed
+        .iter()
+        .filter(|(_, &to)| collapsed.contains_key(&to))
+        .map(|(&from, &to)| (from, collapsed[&to]))
+        .collect();
+    collapsed.extend(fuzzy);
+
+    MutationDedup {
+        survivors: kept.iter().map(|&i| mutations[i].clone()).collect(),
+        kept,
+        fuzzy_duplicates: collapsed.len() - exact_duplicates,
+        exact_duplicates,
+        collapsed,
+    }
+}
+
+/// Find clusters of near-duplicate strings with MinHash + LSH banding.
+///
+/// Returns lists of indices into `strings`; singletons are omitted.
+#[pyfunction]
+#[pyo3(signature = (strings, threshold = 0.8, num_perm = 128, bands = 32, shingle_size = 3))]
+pub fn minhash_dedup(
+    py: Python<'_>,
+    strings: Vec<String>,
+    threshold: f64,
+    num_perm: usize,
+    bands: usize,
+    shingle_size: usize,
+) -> PyResult<Vec<Vec<usize>>> {
+    let config = MinHashConfig {
+        num_perm,
+        bands,
+        shingle_size,
+        threshold,
+    };
+    config.validate().map_err(PyValueError::new_err)?;
+    Ok(py.allow_threads(|| near_duplicate_clusters(&strings, &config)))
+}
+
+/// Drop exact and near-duplicate mutations before they are sent to the agent.
+///
+/// `similarity_threshold` is the edit-distance similarity (0-1) at which two
+/// mutations count as duplicates.
+#[pyfunction]
+#[pyo3(signature = (mutations, similarity_threshold = 0.9))]
+pub fn dedup_mutations(py: Python<'_>, mutations: Vec<String>, similarity_threshold: f64) -> PyResult<MutationDedup> {
+    if !(0.0..=1.0).contains(&similarity_threshold) {
+        return Err(PyValueError::new_err(format!(
+            "similarity_threshold must be in [0, 1], got {}",
+            similarity_threshold
+        )));
+    }
+    let config = MinHashConfig::default();
+    Ok(py.allow_threads(|| dedup_mutation_set(&mutations, similarity_threshold, &config)))
+}
+
+/// Report near-duplicate groups in a seed prompt corpus.
+#[pyfunction]
+#[pyo3(signature = (seeds, threshold = 0.8, num_perm = 128, bands = 32, shingle_size = 3, canonicalize = true))]
+pub fn scan_seed_duplicates(
+    py: Python<'_>,
+    seeds: Vec<String>,
+    threshold: f64,
+    num_perm: usize,
+    bands: usize,
+    shingle_size: usize,
+    canonicalize: bool,
+) -> PyResult<SeedCorpusReport> {
+    let config = MinHashConfig {
+        num_perm,
+        bands,
+        shingle_size,
+        threshold,
+    };
+    config.validate().map_err(PyValueError::new_err)?;
+    Ok(py.allow_threads(|| scan_seed_corpus(&seeds, &config, canonicalize)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_estimates_jaccard() {
+        let config = MinHashConfig::default();
+        let a = minhash_signature("Book a flight from Berlin to Paris next Tuesday", &config);
+        let b = minhash_signature("Book a flight from Berlin to Paris next Tuesday!", &config);
+        let c = minhash_signature("What's the weather like in Tokyo?", &config);
+        assert!(estimated_jaccard(&a, &b) > 0.8);
+        assert!(estimated_jaccard(&a, &c) < 0.2);
+        assert_eq!(estimated_jaccard(&a, &a), 1.0);
+    }
+
+    #[test]
+    fn test_near_duplicate_clusters() {
+        let strings: Vec<String> = vec![
+            "Book a flight from Berlin to Paris next Tuesday".into(),
+            "What's the weather like in Tokyo tomorrow?".into(),
+            "Book a flight from Berlin to Paris next Tuesday!".into(),
+            "Cancel my hotel reservation in Rome please".into(),
+            "what's the weather like in Tokyo tomorrow?".into(),
+        ];
+        let clusters = near_duplicate_clusters(&strings, &MinHashConfig::default());
+        assert_eq!(clusters, vec![vec![0, 2], vec![1, 4]]);
+    }
+
+    #[test]
+    fn test_bucket_pairs_beyond_first_member() {
+        // All three share the first band, but only 1 and 2 are similar;
+        // they share no other band
+        let config = MinHashConfig {
+            num_perm: 4,
+            bands: 2,
+            threshold: 0.7,
+            ..MinHashConfig::default()
+        };
+        let signatures = vec![vec![1, 1, 9, 9], vec![1, 1, 2, 3], vec![1, 1, 2, 4]];
+        assert_eq!(signature_clusters(&signatures, &config), vec![vec![1, 2]]);
+    }
+
+    #[test]
+    fn test_templated_family_scales() {
+        let strings: Vec<String> = (0..5000)
+            .map(|i| format!("Please book a flight from Berlin to Paris next Tuesday morning, booking ref {}", i))
+            .collect();
+        let start = std::time::Instant::now();
+        let clusters = near_duplicate_clusters(&strings, &MinHashConfig::default());
+        let d = dedup_mutation_set(&strings, 0.9, &MinHashConfig::default());
+        assert!(start.elapsed() < std::time::Duration::from_secs(30));
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(clusters[0].len(), 5000);
+        assert_eq!(d.kept, vec![0]);
+        assert_eq!(d.fuzzy_duplicates, 4999);
+    }
+
+    #[test]
+    fn test_seed_corpus_report() {
+        let seeds: Vec<String> = vec![
+            "Book a flight from Berlin to Paris next Tuesday".into(),
+            "Cancel my hotel reservation in Rome please".into(),
+            "BOOK a flight  from Berlin to Paris next Tuesday".into(),
+            "What's the weather like in Tokyo tomorrow?".into(),
+            "Book a flight from Berlin to Paris next Tuesday.".into(),
+        ];
+        let report = scan_seed_corpus(&seeds, &MinHashConfig::default(), true);
+        assert_eq!(report.total_seeds, 5);
+        assert_eq!(report.unique_seeds, 3);
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].representative, 0);
+        assert_eq!(report.groups[0].duplicates, vec![2, 4]);
+        assert!(report.groups[0].min_similarity >= 0.8);
+        assert_eq!(report.kept(), vec![0, 1, 3]);
+
+        let raw = scan_seed_corpus(&seeds, &MinHashConfig::default(), false);
+        assert!(raw.unique_seeds > report.unique_seeds);
+    }
+
+    #[test]
+    fn test_dedup_mutation_set() {
+        let mutations: Vec<String> = vec![
+            "Book a flight from Berlin to Paris next Tuesday".into(),
+            "What's the weather like in Tokyo tomorrow?".into(),
+            "Book a flight from Berlin to Paris next Tuesday".into(),
+            "Book a flight from Berlin to Paris next Tuesdya".into(),
+            "Cancel my hotel reservation in Rome please".into(),
+            "Book a flight from Berlin to Paris next Tuesdya".into(),
+        ];
+        let d = dedup_mutation_set(&mutations, 0.9, &MinHashConfig::default());
+        assert_eq!(d.kept, vec![0, 1, 4]);
+        assert_eq!(d.survivors[1], mutations[1]);
+        assert_eq!(d.exact_duplicates, 2);
+        assert_eq!(d.fuzzy_duplicates, 1);
+        assert_eq!(d.collapsed[&2], 0);
+        assert_eq!(d.collapsed[&3], 0);
+        assert_eq!(d.collapsed[&5], 0);
+
+        let strict = dedup_mutation_set(&mutations, 1.0, &MinHashConfig::default());
+        assert_eq!(strict.kept, vec![0, 1, 3, 4]);
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(MinHashConfig::default().validate().is_ok());
+        let bad = MinHashConfig {
+            bands: 30,
+            ..MinHashConfig::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+}
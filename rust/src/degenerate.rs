@@ -0,0 +1,273 @@
+//! Degenerate-output detection
+//!
+//! Mutated prompts regularly push models into repetition loops ("I think
+//! I think I think ..."), runs of one token, or near-constant character
+//! noise. Those responses used to surface only as timeouts; this check
+//! flags them directly. Three signals are measured per response:
+//!
+//! - loops: the most consecutive repeats of any multi-word phrase
+//! - token runs: the longest run of one repeated word
+//! - character entropy: Shannon entropy in bits per character
+//!
+//! Loops are found by comparing each word with the word one period ahead
+//! for every period up to `max_period`, which finds tandem repeats in
+//! O(words * max_period) without building n-gram tables.
+
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+use crate::similarity::Tokenizer;
+
+/// Thresholds beyond which a response counts as degenerate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DegenerationLimits {
+    /// Consecutive repeats of one phrase allowed
+    pub max_loop_repeats: usize,
+    /// Longest phrase, in words, considered for loops
+    pub max_period: usize,
+    /// Consecutive repeats of one word allowed
+    pub max_token_run: usize,
+    /// Lowest acceptable character entropy, in bits
+    pub min_entropy: f64,
+    /// Entropy is only judged for responses at least this many characters long
+    pub min_entropy_chars: usize,
+}
+
+impl Default for DegenerationLimits {
+    fn default() -> Self {
+        Self {
+            max_loop_repeats: 4,
+            max_period: 40,
+            max_token_run: 8,
+            min_entropy: 2.0,
+            min_entropy_chars: 40,
+        }
+    }
+}
+
+/// Degeneration signals measured for one response
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DegenerationReport {
+    /// Whether any limit was exceeded
+    pub degenerate: bool,
+    /// The most-repeated multi-word phrase and its consecutive repeats
+    pub loop_phrase: String,
+    pub loop_repeats: usize,
+    /// The longest single-word run and its length
+    pub run_token: String,
+    pub run_length: usize,
+    /// Shannon entropy over characters, in bits
+    pub char_entropy: f64,
+    /// Human-readable description of each exceeded limit
+    pub reasons: Vec<String>,
+}
+
+impl DegenerationReport {
+    /// Check result for the `no_degeneration` check.
+    pub fn to_check_result(&self) -> CheckResult {
+        CheckResult {
+            check_type: "no_degeneration".to_string(),
+            passed: !self.degenerate,
+            details: if self.reasons.is_empty() {
+                "no degeneration detected".to_string()
+            } else {
+                self.reasons.join("; ")
+            },
+            severity: Severity::Error,
+        }
+    }
+}
+
+/// Shannon entropy of the characters of `text`, in bits per character.
+pub fn char_entropy(text: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    let mut total = 0usize;
+    for c in text.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+        total += 1;
+    }
+    if total == 0 {
+        return 0.0;
+    }
+    counts
+        .values()
+        .map(|&n| {
+            let p = n as f64 / total as f64;
+            p * (1.0 / p).log2()
+        })
+        .sum()
+}
+
+/// (start, period, repeats) of the longest tandem repeat with period in
+/// `min_period..=max_period`; shorter periods win ties.
+fn longest_tandem(tokens: &[String], min_period: usize, max_period: usize) -> Option<(usize, usize, usize)> {
+    let mut best: Option<(usize, usize, usize)> = None;
+    for period in min_period..=max_period.min(tokens.len() / 2) {
+        let mut run = 0;
+        for i in 0..tokens.len() - period {
+            if tokens[i] == tokens[i + period] {
+                run += 1;
+                let repeats = 1 + run / period;
+                if repeats >= 2 && best.is_none_or(|(_, _, r)| repeats > r) {
+                    best = Some((i + 1 - run, period, repeats));
+                }
+            } else {
+                run = 0;
+            }
+        }
+    }
+    best
+}
+
+/// Measure one response against `limits`.
+pub fn analyze_degeneration(text: &str, limits: &DegenerationLimits) -> DegenerationReport {
+    let tokens = Tokenizer::Word.tokenize(text, false);
+    let (loop_phrase, loop_repeats) = match longest_tandem(&tokens, 2, limits.max_period) {
+        Some((start, period, repeats)) => (tokens[start..start + period].join(" "), repeats),
+        None => (String::new(), 0),
+    };
+    let (run_token, run_length) = match longest_tandem(&tokens, 1, 1) {
+        Some((start, _, repeats)) => (tokens[start].clone(), repeats),
+        None => (tokens.first().cloned().unwrap_or_default(), tokens.len().min(1)),
+    };
+    let entropy = char_entropy(text);
+
+    let mut reasons = Vec::new();
+    if loop_repeats > limits.max_loop_repeats {
+        reasons.push(format!(
+            "phrase '{}' repeats {} times in a row (limit {})",
+            loop_phrase, loop_repeats, limits.max_loop_repeats
+        ));
+    }
+    if run_length > limits.max_token_run {
+        reasons.push(format!(
+            "word '{}' repeats {} times in a row (limit {})",
+            run_token, run_length, limits.max_token_run
+        ));
+    }
+    if text.chars().count() >= limits.min_entropy_chars && entropy < limits.min_entropy {
+        reasons.push(format!(
+            "character entropy {:.2} bits below {:.2}",
+            entropy, limits.min_entropy
+        ));
+    }
+    DegenerationReport {
+        degenerate: !reasons.is_empty(),
+        loop_phrase,
+        loop_repeats,
+        run_token,
+        run_length,
+        char_entropy: entropy,
+        reasons,
+    }
+}
+
+/// Repetition-loop and low-entropy detector for agent responses.
+#[pyclass(name = "DegenerationDetector")]
+pub struct PyDegenerationDetector {
+    limits: DegenerationLimits,
+}
+
+#[pymethods]
+impl PyDegenerationDetector {
+    #[new]
+    #[pyo3(signature = (max_loop_repeats = 4, max_token_run = 8, min_entropy = 2.0, min_entropy_chars = 40, max_period = 40))]
+    fn new(
+        max_loop_repeats: usize,
+        max_token_run: usize,
+        min_entropy: f64,
+        min_entropy_chars: usize,
+        max_period: usize,
+    ) -> Self {
+        Self {
+            limits: DegenerationLimits {
+                max_loop_repeats,
+                max_period,
+                max_token_run,
+                min_entropy,
+                min_entropy_chars,
+            },
+        }
+    }
+
+    fn analyze(&self, py: Python<'_>, outputs: Vec<String>) -> Vec<DegenerationReport> {
+        py.allow_threads(|| {
+            outputs
+                .par_iter()
+                .map(|o| analyze_degeneration(o, &self.limits))
+                .collect()
+        })
+    }
+
+    /// One `no_degeneration` check result per output.
+    fn check(&self, py: Python<'_>, outputs: Vec<String>) -> Vec<CheckResult> {
+        py.allow_threads(|| {
+            outputs
+                .par_iter()
+                .map(|o| analyze_degeneration(o, &self.limits).to_check_result())
+                .collect()
+        })
+    }
+}
+
+/// Degeneration reports for `outputs` using the default limits.
+#[pyfunction]
+pub fn detect_degeneration(py: Python<'_>, outputs: Vec<String>) -> Vec<DegenerationReport> {
+    let limits = DegenerationLimits::default();
+    py.allow_threads(|| {
+        outputs
+            .par_iter()
+            .map(|o| analyze_degeneration(o, &limits))
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_loops_and_runs() {
+        let limits = DegenerationLimits::default();
+        let looping = format!("Sure. {}done", "I think that ".repeat(6));
+        let report = analyze_degeneration(&looping, &limits);
+        assert!(report.degenerate);
+        assert_eq!((report.loop_phrase.as_str(), report.loop_repeats), ("i think that", 6));
+        assert_eq!(
+            report.to_check_result().details,
+            "phrase 'i think that' repeats 6 times in a row (limit 4)"
+        );
+
+        let run = analyze_degeneration("no no no no no no no no no no", &limits);
+        assert_eq!((run.run_token.as_str(), run.run_length), ("no", 10));
+        assert!(run.degenerate);
+
+        let normal = analyze_degeneration(
+            "Your order shipped on Monday and should arrive within three business days.",
+            &limits,
+        );
+        assert!(!normal.degenerate, "{:?}", normal.reasons);
+        assert_eq!(normal.run_length, 1);
+        assert!(normal.to_check_result().passed);
+    }
+
+    #[test]
+    fn test_entropy() {
+        assert_eq!(char_entropy(""), 0.0);
+        assert_eq!(char_entropy("aaaa"), 0.0);
+        assert!((char_entropy("abab") - 1.0).abs() < 1e-12);
+
+        let limits = DegenerationLimits::default();
+        let noise = analyze_degeneration(&"=".repeat(50), &limits);
+        assert!(noise.degenerate);
+        assert_eq!(noise.reasons, vec!["character entropy 0.00 bits below 2.00"]);
+        // Too short to judge
+        assert!(!analyze_degeneration("hmmm", &limits).degenerate);
+    }
+}
@@ -0,0 +1,215 @@
+//! Determinism checks over repeated runs
+//!
+//! Flaky mutations are only half the story: an agent that answers the
+//! unmutated prompt differently on every run makes every mutation result
+//! noisy. Each test case is run several times and its outputs are scored
+//! with `response_stability`. A case is
+//!
+//! - `stable` when every pair of outputs is at least `stable_threshold`
+//!   similar,
+//! - `divergent` when the mean pairwise similarity is below
+//!   `divergent_threshold`,
+//! - `unstable` otherwise.
+//!
+//! The run's determinism score is the mean stability score over cases and
+//! can be attached to `TestStatistics`.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::similarity::{parse_metric, Metric};
+use crate::stability::{response_stability, StabilityReport};
+
+/// Similarity bounds separating stable, unstable and divergent cases
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DeterminismThresholds {
+    pub stable: f64,
+    pub divergent: f64,
+    pub metric: Metric,
+}
+
+impl Default for DeterminismThresholds {
+    fn default() -> Self {
+        Self {
+            stable: 0.9,
+            divergent: 0.5,
+            metric: Metric::Levenshtein,
+        }
+    }
+}
+
+impl DeterminismThresholds {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.divergent) || !(0.0..=1.0).contains(&self.stable) || self.divergent > self.stable {
+            return Err(format!(
+                "thresholds must satisfy 0 <= divergent <= stable <= 1, got divergent {} and stable {}",
+                self.divergent, self.stable
+            ));
+        }
+        Ok(())
+    }
+
+    /// "stable", "unstable" or "divergent"
+    pub fn classify(&self, report: &StabilityReport) -> &'static str {
+        if report.min_similarity >= self.stable {
+            "stable"
+        } else if report.stability_score < self.divergent {
+            "divergent"
+        } else {
+            "unstable"
+        }
+    }
+}
+
+/// Determinism of one test case over repeated runs
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaseDeterminism {
+    pub case_id: String,
+    /// "stable", "unstable" or "divergent"
+    pub label: String,
+    pub stability: StabilityReport,
+}
+
+/// Run-level determinism counts and score
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DeterminismSummary {
+    pub cases: usize,
+    pub stable: usize,
+    pub unstable: usize,
+    pub divergent: usize,
+    /// Mean stability score over cases
+    pub determinism_score: f64,
+}
+
+/// Per-case labels plus the run summary
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeterminismReport {
+    pub summary: DeterminismSummary,
+    pub cases: Vec<CaseDeterminism>,
+}
+
+impl DeterminismReport {
+    /// Cases that are not stable, least stable first.
+    pub fn flaky_cases(&self) -> Vec<&CaseDeterminism> {
+        let mut flaky: Vec<&CaseDeterminism> = self.cases.iter().filter(|c| c.label != "stable").collect();
+        flaky.sort_by(|a, b| a.stability.stability_score.total_cmp(&b.stability.stability_score));
+        flaky
+    }
+}
+
+/// Classify each (case id, repeated outputs) pair; every case needs at
+/// least two outputs.
+pub fn determinism_report(cases: &[(String, Vec<String>)], thresholds: &DeterminismThresholds) -> Result<DeterminismReport, String> {
+    thresholds.validate()?;
+    if let Some((id, outputs)) = cases.iter().find(|(_, outputs)| outputs.len() < 2) {
+        return Err(format!(
+            "case '{}': needs at least two outputs, got {}",
+            id,
+            outputs.len()
+        ));
+    }
+    let cases: Vec<CaseDeterminism> = cases
+        .par_iter()
+        .map(|(id, outputs)| {
+            let stability = response_stability(outputs, thresholds.metric);
+            CaseDeterminism {
+                case_id: id.clone(),
+                label: thresholds.classify(&stability).to_string(),
+                stability,
+            }
+        })
+        .collect();
+
+    let mut summary = DeterminismSummary {
+        cases: cases.len(),
+        ..Default::default()
+    };
+    for case in &cases {
+        match case.label.as_str() {
+            "stable" => summary.stable += 1,
+            "divergent" => summary.divergent += 1,
+            _ => summary.unstable += 1,
+        }
+    }
+    if !cases.is_empty() {
+        summary.determinism_score =
+            cases.iter().map(|c| c.stability.stability_score).sum::<f64>() / cases.len() as f64;
+    }
+    Ok(DeterminismReport { summary, cases })
+}
+
+/// Classify test cases as stable, unstable or divergent across repeated runs.
+///
+/// `outputs[i]` holds the repeated outputs for `case_ids[i]`. `metric` is
+/// one of "levenshtein", "jaccard", "dice", "shingle" or "lcs".
+#[pyfunction]
+#[pyo3(name = "determinism_report", signature = (case_ids, outputs, stable_threshold = 0.9, divergent_threshold = 0.5, metric = "levenshtein"))]
+pub fn py_determinism_report(
+    py: Python<'_>,
+    case_ids: Vec<String>,
+    outputs: Vec<Vec<String>>,
+    stable_threshold: f64,
+    divergent_threshold: f64,
+    metric: &str,
+) -> PyResult<DeterminismReport> {
+    if case_ids.len() != outputs.len() {
+        return Err(PyValueError::new_err(format!(
+            "got {} case ids but {} output groups",
+            case_ids.len(),
+            outputs.len()
+        )));
+    }
+    let thresholds = DeterminismThresholds {
+        stable: stable_threshold,
+        divergent: divergent_threshold,
+        metric: parse_metric(metric)?,
+    };
+    let cases: Vec<(String, Vec<String>)> = case_ids.into_iter().zip(outputs).collect();
+    py.allow_threads(|| determinism_report(&cases, &thresholds))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(id: &str, outputs: &[&str]) -> (String, Vec<String>) {
+        (id.to_string(), outputs.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_classification_and_summary() {
+        let cases = vec![
+            case("refund", &["Refunds take 5 days.", "Refunds take 5 days.", "Refunds take 5 days!"]),
+            case("hours", &["We open at 9am daily.", "We open at 9am daily.", "We open at 9am on weekdays."]),
+            case("joke", &["Why did the chicken cross?", "42", "I'd rather not say."]),
+        ];
+        let report = determinism_report(&cases, &DeterminismThresholds::default()).unwrap();
+        let labels: Vec<&str> = report.cases.iter().map(|c| c.label.as_str()).collect();
+        assert_eq!(labels, vec!["stable", "unstable", "divergent"]);
+        assert_eq!(
+            (report.summary.stable, report.summary.unstable, report.summary.divergent),
+            (1, 1, 1)
+        );
+        let flaky: Vec<&str> = report.flaky_cases().iter().map(|c| c.case_id.as_str()).collect();
+        assert_eq!(flaky, vec!["joke", "hours"]);
+        let mean = report.cases.iter().map(|c| c.stability.stability_score).sum::<f64>() / 3.0;
+        assert!((report.summary.determinism_score - mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_validation() {
+        let bad = DeterminismThresholds {
+            stable: 0.4,
+            ..Default::default()
+        };
+        assert!(determinism_report(&[], &bad).is_err());
+        let err = determinism_report(&[case("x", &["once"])], &DeterminismThresholds::default()).unwrap_err();
+        assert_eq!(err, "case 'x': needs at least two outputs, got 1");
+    }
+}
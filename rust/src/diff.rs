@@ -0,0 +1,300 @@
+//! Longest-common-subsequence similarity and aligned diffs
+//!
+//! Used to show users where an agent's output diverged under mutation:
+//! the LCS alignment is turned into runs of equal, inserted and deleted
+//! text that reports can render directly. Nothing here keeps the full
+//! (n+1)×(m+1) LCS table: lengths use two rows, and alignments use
+//! Hirschberg's divide and conquer over the same two-row pass.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Unit a diff is computed over.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Char,
+    /// Runs of non-whitespace and runs of whitespace, so the original
+    /// text can be rebuilt exactly from the segments.
+    Word,
+}
+
+impl Granularity {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "char" | "character" => Some(Granularity::Char),
+            "word" => Some(Granularity::Word),
+            _ => None,
+        }
+    }
+
+    fn units(self, text: &str) -> Vec<&str> {
+        match self {
+            Granularity::Char => text
+                .char_indices()
+                .map(|(i, c)| &text[i..i + c.len_utf8()])
+                .collect(),
+            Granularity::Word => {
+                let mut units = Vec::new();
+                let mut start = 0;
+                let mut prev_ws: Option<bool> = None;
+                for (i, c) in text.char_indices() {
+                    let ws = c.is_whitespace();
+                    if prev_ws.is_some_and(|p| p != ws) {
+                        units.push(&text[start..i]);
+                        start = i;
+                    }
+                    prev_ws = Some(ws);
+                }
+                if start < text.len() {
+                    units.push(&text[start..]);
+                }
+                units
+            }
+        }
+    }
+}
+
+fn parse_granularity(name: &str) -> PyResult<Granularity> {
+    Granularity::parse(name).ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "unknown granularity '{}', expected 'char' or 'word'",
+            name
+        ))
+    })
+}
+
+/// Kind of a diff segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+impl DiffOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DiffOp::Equal => "equal",
+            DiffOp::Insert => "insert",
+            DiffOp::Delete => "delete",
+        }
+    }
+}
+
+/// A run of text that is shared, only in the new text, or only in the old text
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSegment {
+    /// "equal", "insert" or "delete"
+    pub op: String,
+    pub text: String,
+}
+
+#[pymethods]
+impl DiffSegment {
+    fn __repr__(&self) -> String {
+        format!("DiffSegment(op={:?}, text={:?})", self.op, self.text)
+    }
+}
+
+/// LCS lengths of `a` against every prefix of `b`: `row[j]` is the LCS
+/// length of `a` and `b[..j]`. Uses two rows of `b.len() + 1`.
+fn lcs_row<T: PartialEq>(a: &[T], b: &[T]) -> Vec<usize> {
+    let mut prev = vec![0usize; b.len() + 1];
+    let mut curr = vec![0usize; b.len() + 1];
+
+    for x in a {
+        for (j, y) in b.iter().enumerate() {
+            curr[j + 1] = if x == y {
+                prev[j] + 1
+            } else {
+                prev[j + 1].max(curr[j])
+            };
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+    prev
+}
+
+/// Length of the longest common subsequence, using O(min(n, m)) memory.
+pub fn lcs_length<T: PartialEq>(a: &[T], b: &[T]) -> usize {
+    let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+    lcs_row(long, short)[short.len()]
+}
+
+/// Append an LCS alignment of `a` and `b` to `ops` in linear memory.
+/// Deletions come before insertions wherever the alignment allows.
+fn hirschberg<'a>(a: &[&'a str], b: &[&'a str], ops: &mut Vec<(DiffOp, &'a str)>) {
+    match a {
+        [] => ops.extend(b.iter().map(|u| (DiffOp::Insert, *u))),
+        _ if b.is_empty() => ops.extend(a.iter().map(|u| (DiffOp::Delete, *u))),
+        [unit] => match b.iter().position(|u| u == unit) {
+            Some(k) => {
+                ops.extend(b[..k].iter().map(|u| (DiffOp::Insert, *u)));
+                ops.push((DiffOp::Equal, unit));
+                ops.extend(b[k + 1..].iter().map(|u| (DiffOp::Insert, *u)));
+            }
+            None => {
+                ops.push((DiffOp::Delete, unit));
+                ops.extend(b.iter().map(|u| (DiffOp::Insert, *u)));
+            }
+        },
+        _ => {
+            // Split `b` where the top half's forward LCS and the bottom
+            // half's backward LCS add up to the most; earliest split wins
+            let mid = a.len() / 2;
+            let forward = lcs_row(&a[..mid], b);
+            let rev_a: Vec<&str> = a[mid..].iter().rev().copied().collect();
+            let rev_b: Vec<&str> = b.iter().rev().copied().collect();
+            let backward = lcs_row(&rev_a, &rev_b);
+            let m = b.len();
+            let split = (0..=m)
+                .max_by_key(|&j| (forward[j] + backward[m - j], std::cmp::Reverse(j)))
+                .expect("range is never empty");
+            hirschberg(&a[..mid], &b[..split], ops);
+            hirschberg(&a[mid..], &b[split..], ops);
+        }
+    }
+}
+
+/// LCS similarity 2·|LCS| / (|a| + |b|) over the chosen units.
+pub fn lcs_ratio(s1: &str, s2: &str, granularity: Granularity) -> f64 {
+    let a = granularity.units(s1);
+    let b = granularity.units(s2);
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    2.0 * lcs_length(&a, &b) as f64 / (a.len() + b.len()) as f64
+}
+
+/// Align two strings along their LCS and return merged diff runs.
+pub fn lcs_diff(old: &str, new: &str, granularity: Granularity) -> Vec<(DiffOp, String)> {
+    let a = granularity.units(old);
+    let b = granularity.units(new);
+
+    // Common prefix and suffix never need the quadratic pass
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut ops: Vec<(DiffOp, &str)> = Vec::with_capacity(a.len() + b.len());
+    ops.extend(a[..prefix].iter().map(|u| (DiffOp::Equal, *u)));
+
+    hirschberg(a_mid, b_mid, &mut ops);
+    ops.extend(a[a.len() - suffix..].iter().map(|u| (DiffOp::Equal, *u)));
+
+    let mut merged: Vec<(DiffOp, String)> = Vec::new();
+    for (op, unit) in ops {
+        match merged.last_mut() {
+            Some((last, text)) if *last == op => text.push_str(unit),
+            _ => merged.push((op, unit.to_string())),
+        }
+    }
+    merged
+}
+
+/// LCS-based similarity between two strings (0.0 to 1.0).
+///
+/// `granularity` is "char" or "word".
+#[pyfunction]
+#[pyo3(signature = (s1, s2, granularity = "char"))]
+pub fn lcs_similarity(s1: &str, s2: &str, granularity: &str) -> PyResult<f64> {
+    Ok(lcs_ratio(s1, s2, parse_granularity(granularity)?))
+}
+
+/// Aligned diff between an original and a mutated output.
+///
+/// Returns DiffSegment runs whose "equal" and "delete" texts rebuild `old`
+/// and whose "equal" and "insert" texts rebuild `new`.
+#[pyfunction]
+#[pyo3(signature = (old, new, granularity = "word"))]
+pub fn aligned_diff(old: &str, new: &str, granularity: &str) -> PyResult<Vec<DiffSegment>> {
+    Ok(lcs_diff(old, new, parse_granularity(granularity)?)
+        .into_iter()
+        .map(|(op, text)| DiffSegment {
+            op: op.as_str().to_string(),
+            text,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rebuild(diff: &[(DiffOp, String)], skip: DiffOp) -> String {
+        diff.iter()
+            .filter(|(op, _)| *op != skip)
+            .map(|(_, t)| t.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_lcs_ratio() {
+        assert_eq!(lcs_length(&[1, 2, 3, 4], &[2, 4, 5]), 2);
+        assert!((lcs_ratio("abcd", "abed", Granularity::Char) - 0.75).abs() < 1e-9);
+        assert_eq!(lcs_ratio("", "", Granularity::Word), 1.0);
+        assert_eq!(lcs_ratio("same words", "same words", Granularity::Word), 1.0);
+    }
+
+    #[test]
+    fn test_word_diff_round_trips() {
+        let old = "Your flight to Paris is booked for Monday.";
+        let new = "Your flight to Rome is booked  for Monday!";
+        let diff = lcs_diff(old, new, Granularity::Word);
+
+        assert_eq!(rebuild(&diff, DiffOp::Insert), old);
+        assert_eq!(rebuild(&diff, DiffOp::Delete), new);
+        assert_eq!(diff[0], (DiffOp::Equal, "Your flight to ".to_string()));
+        assert!(diff.contains(&(DiffOp::Delete, "Paris".to_string())));
+        assert!(diff.contains(&(DiffOp::Insert, "Rome".to_string())));
+    }
+
+    #[test]
+    fn test_char_diff() {
+        let diff = lcs_diff("kitten", "sitting", Granularity::Char);
+        assert_eq!(rebuild(&diff, DiffOp::Insert), "kitten");
+        assert_eq!(rebuild(&diff, DiffOp::Delete), "sitting");
+        let equal: usize = diff
+            .iter()
+            .filter(|(op, _)| *op == DiffOp::Equal)
+            .map(|(_, t)| t.chars().count())
+            .sum();
+        assert_eq!(equal, 4); // i, t, t, n
+        assert!(lcs_diff("", "", Granularity::Char).is_empty());
+    }
+
+    #[test]
+    fn test_alignment_is_a_longest_common_subsequence() {
+        let pairs = [
+            ("the quick brown fox jumps", "a quick red fox jumped over"),
+            ("abcabba", "cbabac"),
+            ("xyz", "abc"),
+            ("", "abc"),
+        ];
+        for (old, new) in pairs {
+            let diff = lcs_diff(old, new, Granularity::Char);
+            assert_eq!(rebuild(&diff, DiffOp::Insert), old);
+            assert_eq!(rebuild(&diff, DiffOp::Delete), new);
+            let equal: usize = diff
+                .iter()
+                .filter(|(op, _)| *op == DiffOp::Equal)
+                .map(|(_, t)| t.chars().count())
+                .sum();
+            let a: Vec<char> = old.chars().collect();
+            let b: Vec<char> = new.chars().collect();
+            assert_eq!(equal, lcs_length(&a, &b), "{} / {}", old, new);
+        }
+        // A replaced word is one delete followed by one insert
+        let diff = lcs_diff("xy", "p", Granularity::Char);
+        assert_eq!(diff, vec![(DiffOp::Delete, "xy".to_string()), (DiffOp::Insert, "p".to_string())]);
+    }
+}
@@ -0,0 +1,145 @@
+//! Distribution drift between runs
+//!
+//! Two runs can have the same pass rate while the agent behaves very
+//! differently: longer answers, lower similarity to the baseline, slower
+//! responses. Each metric is histogrammed on bins taken from the baseline
+//! run's quantiles and compared with the Population Stability Index (PSI)
+//! and KL divergence.
+
+use std::collections::HashMap;
+
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Probability floor so empty bins do not produce infinite divergences.
+const BIN_EPSILON: f64 = 1e-4;
+
+/// Drift of one metric between a baseline and a current run
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DriftReport {
+    pub metric: String,
+    pub psi: f64,
+    /// KL(current || baseline)
+    pub kl_divergence: f64,
+    pub drifted: bool,
+}
+
+/// Interior bin edges at the baseline's quantiles, deduplicated.
+fn quantile_edges(baseline: &[f64], bins: usize) -> Vec<f64> {
+    let mut sorted: Vec<f64> = baseline.iter().copied().filter(|v| v.is_finite()).collect();
+    sorted.sort_by(f64::total_cmp);
+    if sorted.is_empty() {
+        return Vec::new();
+    }
+
+    let mut edges: Vec<f64> = (1..bins)
+        .map(|i| sorted[(i * sorted.len() / bins).min(sorted.len() - 1)])
+        .collect();
+    edges.dedup();
+    edges
+}
+
+fn histogram(values: &[f64], edges: &[f64]) -> Vec<f64> {
+    let mut counts = vec![0.0f64; edges.len() + 1];
+    let mut total = 0.0f64;
+    for &v in values.iter().filter(|v| v.is_finite()) {
+        let bin = edges.partition_point(|&edge| edge <= v);
+        counts[bin] += 1.0;
+        total += 1.0;
+    }
+    counts
+        .into_iter()
+        .map(|c| if total > 0.0 { (c / total).max(BIN_EPSILON) } else { BIN_EPSILON })
+        .collect()
+}
+
+/// (PSI, KL(current || baseline)) of two samples of one metric.
+pub fn psi_and_kl(baseline: &[f64], current: &[f64], bins: usize) -> (f64, f64) {
+    let edges = quantile_edges(baseline, bins.max(2));
+    let expected = histogram(baseline, &edges);
+    let actual = histogram(current, &edges);
+
+    let mut psi = 0.0;
+    let mut kl = 0.0;
+    for (&e, &a) in expected.iter().zip(&actual) {
+        psi += (a - e) * (a / e).ln();
+        kl += a * (a / e).ln();
+    }
+    (psi, kl)
+}
+
+/// Compare every metric present in both runs; results are sorted by metric name.
+///
+/// A metric is flagged as drifted when its PSI reaches `psi_threshold`
+/// (0.1 is commonly read as moderate and 0.25 as significant drift).
+pub fn compare_distributions(
+    run_a: &HashMap<String, Vec<f64>>,
+    run_b: &HashMap<String, Vec<f64>>,
+    bins: usize,
+    psi_threshold: f64,
+) -> Vec<DriftReport> {
+    let mut metrics: Vec<&String> = run_a.keys().filter(|k| run_b.contains_key(*k)).collect();
+    metrics.sort();
+
+    metrics
+        .into_iter()
+        .map(|metric| {
+            let (psi, kl) = psi_and_kl(&run_a[metric], &run_b[metric], bins);
+            DriftReport {
+                metric: metric.clone(),
+                psi,
+                kl_divergence: kl,
+                drifted: psi >= psi_threshold,
+            }
+        })
+        .collect()
+}
+
+/// Behavioral drift between two runs per metric.
+///
+/// Each run is a dict mapping a metric name (e.g. "response_length",
+/// "similarity_to_baseline", "latency_ms") to its per-mutation values;
+/// `run_a` is the baseline.
+#[pyfunction]
+#[pyo3(signature = (run_a, run_b, bins = 10, psi_threshold = 0.2))]
+pub fn distribution_drift(
+    run_a: HashMap<String, Vec<f64>>,
+    run_b: HashMap<String, Vec<f64>>,
+    bins: usize,
+    psi_threshold: f64,
+) -> Vec<DriftReport> {
+    compare_distributions(&run_a, &run_b, bins, psi_threshold)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identical_distributions_do_not_drift() {
+        let values: Vec<f64> = (0..200).map(|i| i as f64).collect();
+        let (psi, kl) = psi_and_kl(&values, &values, 10);
+        assert!(psi.abs() < 1e-9);
+        assert!(kl.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_shifted_distribution_drifts() {
+        let mut run_a = HashMap::new();
+        let mut run_b = HashMap::new();
+        run_a.insert("latency_ms".to_string(), (0..200).map(|i| 100.0 + i as f64).collect());
+        run_b.insert("latency_ms".to_string(), (0..200).map(|i| 250.0 + i as f64).collect());
+        run_a.insert("response_length".to_string(), (0..200).map(|i| (i % 50) as f64).collect());
+        run_b.insert("response_length".to_string(), (0..200).map(|i| ((i * 7) % 50) as f64).collect());
+        run_a.insert("only_in_a".to_string(), vec![1.0]);
+
+        let reports = compare_distributions(&run_a, &run_b, 10, 0.2);
+        assert_eq!(reports.len(), 2);
+        assert_eq!(reports[0].metric, "latency_ms");
+        assert!(reports[0].drifted);
+        assert!(reports[0].kl_divergence > 0.5);
+        assert_eq!(reports[1].metric, "response_length");
+        assert!(!reports[1].drifted);
+    }
+}
@@ -0,0 +1,239 @@
+//! Edit distance algorithms for flakestorm
+//!
+//! Levenshtein distance is computed with Myers' bit-vector algorithm:
+//! each column of the DP matrix is encoded as vertical delta bit-vectors,
+//! so one text character costs a handful of word operations per 64 pattern
+//! characters instead of one cell update per pattern character. Patterns
+//! longer than a machine word use Hyyrö's blocked extension.
+//!
+//! A grapheme-cluster variant counts user-perceived characters, so a
+//! combining accent or a multi-code-point emoji is a single edit.
+
+use std::collections::HashMap;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+const WORD_BITS: usize = 64;
+
+/// Per-character match masks for the pattern, one u64 per 64-character block.
+struct PatternMasks {
+    blocks: usize,
+    ascii: Vec<u64>,
+    other: HashMap<char, Vec<u64>>,
+    zeros: Vec<u64>,
+}
+
+impl PatternMasks {
+    fn new(pattern: &[char]) -> Self {
+        let blocks = pattern.len().div_ceil(WORD_BITS);
+        let mut ascii = vec![0u64; 128 * blocks];
+        let mut other: HashMap<char, Vec<u64>> = HashMap::new();
+
+        for (i, &c) in pattern.iter().enumerate() {
+            let (block, bit) = (i / WORD_BITS, i % WORD_BITS);
+            if c.is_ascii() {
+                ascii[c as usize * blocks + block] |= 1 << bit;
+            } else {
+                other.entry(c).or_insert_with(|| vec![0; blocks])[block] |= 1 << bit;
+            }
+        }
+
+        Self {
+            blocks,
+            ascii,
+            other,
+            zeros: vec![0; blocks],
+        }
+    }
+
+    fn get(&self, c: char) -> &[u64] {
+        if c.is_ascii() {
+            let start = c as usize * self.blocks;
+            &self.ascii[start..start + self.blocks]
+        } else {
+            self.other.get(&c).map(Vec::as_slice).unwrap_or(&self.zeros)
+        }
+    }
+}
+
+/// Advance one 64-row block by a text character.
+///
+/// `hin` is the horizontal delta (-1, 0 or +1) entering the block's top row;
+/// the return value is the delta leaving its row `high_bit`.
+#[inline]
+fn advance_block(pv: &mut u64, mv: &mut u64, mut eq: u64, hin: i8, high_bit: u64) -> i8 {
+    let xv = eq | *mv;
+    if hin < 0 {
+        eq |= 1;
+    }
+    let xh = ((eq & *pv).wrapping_add(*pv) ^ *pv) | eq;
+    let mut ph = *mv | !(xh | *pv);
+    let mut mh = *pv & xh;
+
+    let hout = if ph & high_bit != 0 {
+        1
+    } else if mh & high_bit != 0 {
+        -1
+    } else {
+        0
+    };
+
+    ph <<= 1;
+    mh <<= 1;
+    if hin < 0 {
+        mh |= 1;
+    } else if hin > 0 {
+        ph |= 1;
+    }
+
+    *pv = mh | !(xv | ph);
+    *mv = ph & xv;
+    hout
+}
+
+/// Myers bit-parallel Levenshtein distance of `pattern` against `text`.
+fn myers_distance(pattern: &[char], text: &str) -> usize {
+    let m = pattern.len();
+    let masks = PatternMasks::new(pattern);
+    let blocks = masks.blocks;
+    let last_high = 1u64 << ((m - 1) % WORD_BITS);
+
+    let mut pv = vec![!0u64; blocks];
+    let mut mv = vec![0u64; blocks];
+    let mut score = m as isize;
+
+    for c in text.chars() {
+        let eq = masks.get(c);
+        // Row 0 of the DP is 0, 1, 2, ... so every column enters with +1
+        let mut carry: i8 = 1;
+        for b in 0..blocks {
+            let high = if b + 1 == blocks {
+                last_high
+            } else {
+                1 << (WORD_BITS - 1)
+            };
+            carry = advance_block(&mut pv[b], &mut mv[b], eq[b], carry, high);
+        }
+        score += carry as isize;
+    }
+
+    score as usize
+}
+
+/// Levenshtein distance between two strings, counted in characters.
+pub fn levenshtein(s1: &str, s2: &str) -> usize {
+    // The shorter string becomes the bit-encoded pattern
+    let (pattern, text) = if s1.len() <= s2.len() { (s1, s2) } else { (s2, s1) };
+    let pattern: Vec<char> = pattern.chars().collect();
+    if pattern.is_empty() {
+        return text.chars().count();
+    }
+    myers_distance(&pattern, text)
+}
+
+/// Map each distinct grapheme cluster of both strings to a single char.
+///
+/// Ids are handed out in order of first appearance and skip the surrogate
+/// range, so the interned strings can go through the char-based algorithm.
+fn intern_graphemes(s1: &str, s2: &str) -> (String, String) {
+    let mut ids: HashMap<&str, char> = HashMap::new();
+    let mut next: u32 = 0;
+    let [a, b] = [s1, s2].map(|text| {
+        text.graphemes(true)
+            .map(|g| {
+                *ids.entry(g).or_insert_with(|| {
+                    if next == 0xD800 {
+                        next = 0xE000;
+                    }
+                    let c = char::from_u32(next).expect("interned id is a valid char");
+                    next += 1;
+                    c
+                })
+            })
+            .collect::<String>()
+    });
+    (a, b)
+}
+
+/// Levenshtein distance counted in extended grapheme clusters.
+pub fn grapheme_levenshtein(s1: &str, s2: &str) -> usize {
+    let (a, b) = intern_graphemes(s1, s2);
+    levenshtein(&a, &b)
+}
+
+/// 1 - grapheme distance / max grapheme length (0.0 to 1.0).
+pub fn grapheme_ratio(s1: &str, s2: &str) -> f64 {
+    let (a, b) = intern_graphemes(s1, s2);
+    let max_len = a.chars().count().max(b.chars().count());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / max_len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reference O(n*m) dynamic programming implementation.
+    fn levenshtein_dp(s1: &str, s2: &str) -> usize {
+        let a: Vec<char> = s1.chars().collect();
+        let b: Vec<char> = s2.chars().collect();
+        let mut prev: Vec<usize> = (0..=b.len()).collect();
+        let mut curr = vec![0; b.len() + 1];
+        for i in 1..=a.len() {
+            curr[0] = i;
+            for j in 1..=b.len() {
+                let cost = usize::from(a[i - 1] != b[j - 1]);
+                curr[j] = (prev[j] + 1).min(curr[j - 1] + 1).min(prev[j - 1] + cost);
+            }
+            std::mem::swap(&mut prev, &mut curr);
+        }
+        prev[b.len()]
+    }
+
+    /// Small deterministic generator so the tests need no extra crates.
+    fn pseudo_random_string(seed: &mut u64, len: usize, alphabet: &[char]) -> String {
+        (0..len)
+            .map(|_| {
+                *seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                alphabet[(*seed >> 33) as usize % alphabet.len()]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_levenshtein_basic() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("abc", ""), 3);
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+        assert_eq!(levenshtein("naïve café", "naive cafe"), 2);
+    }
+
+    #[test]
+    fn test_levenshtein_matches_dp_across_block_sizes() {
+        let alphabet: Vec<char> = "abcdé ".chars().collect();
+        let mut seed = 42;
+        for &(n, m) in &[(1, 5), (63, 64), (64, 64), (65, 70), (130, 100), (200, 333)] {
+            let a = pseudo_random_string(&mut seed, n, &alphabet);
+            let b = pseudo_random_string(&mut seed, m, &alphabet);
+            assert_eq!(levenshtein(&a, &b), levenshtein_dp(&a, &b), "{} vs {}", n, m);
+        }
+    }
+
+    #[test]
+    fn test_grapheme_levenshtein() {
+        // "e" + combining acute is one grapheme but two chars
+        assert_eq!(levenshtein("cafe", "cafe\u{301}"), 1);
+        assert_eq!(grapheme_levenshtein("cafe", "cafe\u{301}"), 1);
+        assert_eq!(levenshtein("hi", "hi \u{1F44D}\u{1F3FD}"), 3);
+        assert_eq!(grapheme_levenshtein("hi", "hi \u{1F44D}\u{1F3FD}"), 2);
+        // family emoji: 7 code points, one grapheme
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}\u{200D}\u{1F466}";
+        assert_eq!(grapheme_levenshtein("", family), 1);
+        assert_eq!(grapheme_ratio("", ""), 1.0);
+        assert!((grapheme_ratio("ab", "a\u{1F600}") - 0.5).abs() < 1e-9);
+    }
+}
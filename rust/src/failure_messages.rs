@@ -0,0 +1,281 @@
+//! Root-cause grouping of check failure messages
+//!
+//! A large run produces thousands of failed-check details that differ only
+//! in the numbers and ids they quote ("length 412 exceeds 400", "length 587
+//! exceeds 400"). Details are reduced to a pattern (canonical form,
+//! casefolded, with numbers and hex/UUID ids masked), identical patterns
+//! are counted together, and the distinct patterns of each check type are
+//! then fuzzy-merged with the same MinHash/edit-distance deduplication used
+//! for mutation sets. The most frequent pattern of a merged group absorbs
+//! the rarer ones, so the statistics can list the top few failure messages
+//! with counts instead of every raw string.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::dedup::{dedup_mutation_set, MinHashConfig};
+use crate::normalize::{normalize, NormalizeOptions};
+use crate::scoring::MutationResult;
+
+/// Grouping settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FailureMessageConfig {
+    /// Groups kept, most frequent first
+    pub top_n: usize,
+    /// Edit-distance similarity at which two patterns are merged
+    pub similarity_threshold: f64,
+}
+
+impl Default for FailureMessageConfig {
+    fn default() -> Self {
+        Self {
+            top_n: 10,
+            similarity_threshold: 0.85,
+        }
+    }
+}
+
+impl FailureMessageConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.similarity_threshold) {
+            return Err(format!(
+                "similarity_threshold must be in [0, 1], got {}",
+                self.similarity_threshold
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One distinct failure message
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureMessage {
+    pub check_type: String,
+    /// First raw details string of the group's most frequent pattern
+    pub message: String,
+    /// Masked pattern of `message`
+    pub pattern: String,
+    pub count: usize,
+    /// Fraction of all failed checks
+    pub share: f64,
+    /// Distinct patterns merged into the group
+    pub variants: usize,
+}
+
+/// Most frequent failure messages of a run
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureMessages {
+    pub failed_checks: usize,
+    /// Groups before truncation to the top n
+    pub distinct: usize,
+    /// Most frequent first, ties in order of first appearance
+    pub messages: Vec<FailureMessage>,
+}
+
+/// Pattern of a details string: canonical, casefolded and whitespace
+/// collapsed, with UUIDs and long hex ids masked as `<id>` and numbers as
+/// `<n>`.
+pub fn message_pattern(details: &str) -> String {
+    static MASKS: OnceLock<[(Regex, &'static str); 2]> = OnceLock::new();
+    let masks = MASKS.get_or_init(|| {
+        [
+            (
+                Regex::new(
+                    r"\b(?:[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}|0x[0-9a-f]+|[0-9a-f]{16,})\b",
+                )
+                .expect("valid id pattern"),
+                "<id>",
+            ),
+            (Regex::new(r"\d+(?:\.\d+)?").expect("valid number pattern"), "<n>"),
+        ]
+    });
+    let opts = NormalizeOptions {
+        casefold: true,
+        ..Default::default()
+    };
+    let mut pattern = normalize(details, &opts);
+    for (re, mask) in masks {
+        pattern = re.replace_all(&pattern, *mask).into_owned();
+    }
+    pattern
+}
+
+/// Group (check type, details) pairs of failed checks.
+pub fn group_failure_messages(
+    failures: &[(&str, &str)],
+    config: &FailureMessageConfig,
+) -> Result<FailureMessages, String> {
+    config.validate()?;
+    // Exact patterns per check type, in order of first appearance
+    let mut index: HashMap<(&str, String), usize> = HashMap::new();
+    let mut groups: Vec<FailureMessage> = Vec::new();
+    for &(check_type, details) in failures {
+        let pattern = message_pattern(details);
+        match index.get(&(check_type, pattern.clone())) {
+            Some(&g) => groups[g].count += 1,
+            None => {
+                index.insert((check_type, pattern.clone()), groups.len());
+                groups.push(FailureMessage {
+                    check_type: check_type.to_string(),
+                    message: details.to_string(),
+                    pattern,
+                    count: 1,
+                    share: 0.0,
+                    variants: 1,
+                });
+            }
+        }
+    }
+    // Stable, so ties keep their first appearance
+    groups.sort_by_key(|g| Reverse(g.count));
+
+    // Fuzzy-merge within each check type; the dedup keeps the earliest,
+    // i.e. most frequent, pattern of every near-duplicate set
+    let mut by_check: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (g, group) in groups.iter().enumerate() {
+        by_check.entry(&group.check_type).or_default().push(g);
+    }
+    let minhash = MinHashConfig::default();
+    let mut merged_into: Vec<Option<usize>> = vec![None; groups.len()];
+    for members in by_check.values().filter(|m| m.len() > 1) {
+        let patterns: Vec<String> = members.iter().map(|&g| groups[g].pattern.clone()).collect();
+        let dedup = dedup_mutation_set(&patterns, config.similarity_threshold, &minhash);
+        for (from, to) in dedup.collapsed {
+            merged_into[members[from]] = Some(members[to]);
+        }
+    }
+    let mut absorbed = vec![(0, 0); groups.len()];
+    for (g, target) in merged_into.iter().enumerate() {
+        if let Some(t) = *target {
+            absorbed[t].0 += groups[g].count;
+            absorbed[t].1 += 1;
+        }
+    }
+
+    let mut messages: Vec<FailureMessage> = groups
+        .into_iter()
+        .zip(absorbed)
+        .zip(&merged_into)
+        .filter(|(_, target)| target.is_none())
+        .map(|((mut group, (count, variants)), _)| {
+            group.count += count;
+            group.variants += variants;
+            group.share = group.count as f64 / failures.len() as f64;
+            group
+        })
+        .collect();
+    messages.sort_by_key(|m| Reverse(m.count));
+    let distinct = messages.len();
+    messages.truncate(config.top_n);
+    Ok(FailureMessages {
+        failed_checks: failures.len(),
+        distinct,
+        messages,
+    })
+}
+
+/// `group_failure_messages` over every failed check of a run.
+pub fn failure_messages(results: &[MutationResult], config: &FailureMessageConfig) -> Result<FailureMessages, String> {
+    let failures: Vec<(&str, &str)> = results
+        .iter()
+        .flat_map(|r| &r.checks)
+        .filter(|c| !c.passed)
+        .map(|c| (c.check_type.as_str(), c.details.as_str()))
+        .collect();
+    group_failure_messages(&failures, config)
+}
+
+/// Most frequent distinct failure messages among check details.
+///
+/// `details` holds the details of failed checks and `check_types`, when
+/// given, their check types; messages of different check types are never
+/// merged. Numbers and ids are masked before comparison, and patterns at
+/// or above `similarity_threshold` edit-distance similarity are merged.
+#[pyfunction]
+#[pyo3(name = "group_failure_messages", signature = (details, check_types = None, top_n = 10, similarity_threshold = 0.85))]
+pub fn py_group_failure_messages(
+    py: Python<'_>,
+    details: Vec<String>,
+    check_types: Option<Vec<String>>,
+    top_n: usize,
+    similarity_threshold: f64,
+) -> PyResult<FailureMessages> {
+    let check_types = check_types.unwrap_or_else(|| vec![String::new(); details.len()]);
+    if check_types.len() != details.len() {
+        return Err(PyValueError::new_err(format!(
+            "got {} details but {} check types",
+            details.len(),
+            check_types.len()
+        )));
+    }
+    let failures: Vec<(&str, &str)> = check_types
+        .iter()
+        .zip(&details)
+        .map(|(c, d)| (c.as_str(), d.as_str()))
+        .collect();
+    let config = FailureMessageConfig {
+        top_n,
+        similarity_threshold,
+    };
+    py.allow_threads(|| group_failure_messages(&failures, &config))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_pattern() {
+        assert_eq!(message_pattern("Length  412 exceeds 400.5"), "length <n> exceeds <n>");
+        assert_eq!(
+            message_pattern("request 3f2a9c1e-0b4d-4e6f-8a7b-112233445566 failed with 0xDEAD"),
+            "request <id> failed with <id>"
+        );
+    }
+
+    #[test]
+    fn test_grouping() {
+        let mut failures: Vec<(&str, String)> = Vec::new();
+        for n in 0..5 {
+            failures.push(("length", format!("response length {} exceeds 400", 401 + n)));
+        }
+        failures.push(("contains", "missing expected text 'Paris'".to_string()));
+        failures.push(("contains", "missing expected text 'paris.'".to_string()));
+        failures.push(("contains", "Missing expected text 'PARIS'".to_string()));
+        failures.push(("regex", "missing expected text 'Paris'".to_string()));
+        let pairs: Vec<(&str, &str)> = failures.iter().map(|(c, d)| (*c, d.as_str())).collect();
+
+        let grouped = group_failure_messages(&pairs, &FailureMessageConfig::default()).unwrap();
+        assert_eq!((grouped.failed_checks, grouped.distinct), (9, 3));
+        let top = &grouped.messages[0];
+        assert_eq!((top.check_type.as_str(), top.count, top.variants), ("length", 5, 1));
+        assert_eq!(top.message, "response length 401 exceeds 400");
+        let contains = &grouped.messages[1];
+        assert_eq!((contains.count, contains.variants), (3, 2));
+        assert_eq!(contains.pattern, "missing expected text 'paris'");
+        assert!((contains.share - 3.0 / 9.0).abs() < 1e-12);
+        assert_eq!(grouped.messages[2].check_type, "regex");
+
+        let config = FailureMessageConfig {
+            top_n: 1,
+            similarity_threshold: 1.0,
+        };
+        let strict = group_failure_messages(&pairs, &config).unwrap();
+        assert_eq!((strict.distinct, strict.messages.len()), (4, 1));
+        let bad = FailureMessageConfig {
+            similarity_threshold: 1.5,
+            ..config
+        };
+        assert!(group_failure_messages(&pairs, &bad).is_err());
+        assert_eq!(group_failure_messages(&[], &config).unwrap().distinct, 0);
+    }
+}
@@ -0,0 +1,203 @@
+//! Per-mutation flakiness from run history
+//!
+//! A mutation that fails in every run is a real weakness; one that passes
+//! and fails alternately is flaky and its individual results should not
+//! be trusted. Given each mutation's pass/fail history, oldest run first,
+//! two signals are measured:
+//!
+//! - flip rate: the share of consecutive runs whose outcome changed
+//! - outcome entropy: binary entropy of the pass rate, in bits (0..=1)
+//!
+//! The flakiness index is their geometric mean. It is 0 for a mutation
+//! that never changes outcome and 1 for one that alternates every run;
+//! a single late regression after a long passing streak scores low
+//! because it flips only once.
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Flakiness of one mutation across runs
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MutationFlakiness {
+    pub mutation_id: String,
+    pub mutation_type: String,
+    pub runs: usize,
+    pub pass_rate: f64,
+    /// Outcome changes between consecutive runs
+    pub flips: usize,
+    pub flip_rate: f64,
+    pub entropy: f64,
+    /// Geometric mean of flip rate and entropy, 0..=1
+    pub flakiness_index: f64,
+    pub flaky: bool,
+}
+
+/// Flakiness aggregated over one mutation type
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeFlakiness {
+    pub mutation_type: String,
+    pub mutations: usize,
+    pub flaky: usize,
+    pub mean_index: f64,
+    pub max_index: f64,
+}
+
+/// Per-mutation and per-type flakiness
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FlakinessReport {
+    /// Most flaky first
+    pub mutations: Vec<MutationFlakiness>,
+    /// Sorted by mutation type
+    pub by_type: Vec<TypeFlakiness>,
+}
+
+fn binary_entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        return 0.0;
+    }
+    -(p * p.log2() + (1.0 - p) * (1.0 - p).log2())
+}
+
+/// Flakiness of one history, oldest run first.
+pub fn mutation_flakiness(mutation_id: &str, mutation_type: &str, history: &[bool], flaky_threshold: f64) -> MutationFlakiness {
+    let runs = history.len();
+    let passes = history.iter().filter(|&&p| p).count();
+    let pass_rate = if runs > 0 { passes as f64 / runs as f64 } else { 0.0 };
+    let flips = history.windows(2).filter(|w| w[0] != w[1]).count();
+    let flip_rate = if runs > 1 { flips as f64 / (runs - 1) as f64 } else { 0.0 };
+    let entropy = binary_entropy(pass_rate);
+    let flakiness_index = (flip_rate * entropy).sqrt();
+    MutationFlakiness {
+        mutation_id: mutation_id.to_string(),
+        mutation_type: mutation_type.to_string(),
+        runs,
+        pass_rate,
+        flips,
+        flip_rate,
+        entropy,
+        flakiness_index,
+        flaky: runs > 1 && flakiness_index >= flaky_threshold,
+    }
+}
+
+/// Flakiness for every (id, type, history), histories in parallel.
+pub fn flakiness_report(histories: &[(String, String, Vec<bool>)], flaky_threshold: f64) -> Result<FlakinessReport, String> {
+    if !(0.0..=1.0).contains(&flaky_threshold) {
+        return Err(format!("flaky_threshold must be between 0 and 1, got {}", flaky_threshold));
+    }
+    let mut mutations: Vec<MutationFlakiness> = histories
+        .par_iter()
+        .map(|(id, t, history)| mutation_flakiness(id, t, history, flaky_threshold))
+        .collect();
+    mutations.sort_by(|a, b| {
+        b.flakiness_index
+            .total_cmp(&a.flakiness_index)
+            .then_with(|| a.mutation_id.cmp(&b.mutation_id))
+    });
+
+    let mut groups: BTreeMap<&str, Vec<&MutationFlakiness>> = BTreeMap::new();
+    for m in &mutations {
+        groups.entry(&m.mutation_type).or_default().push(m);
+    }
+    let by_type = groups
+        .into_iter()
+        .map(|(mutation_type, group)| TypeFlakiness {
+            mutation_type: mutation_type.to_string(),
+            mutations: group.len(),
+            flaky: group.iter().filter(|m| m.flaky).count(),
+            mean_index: group.iter().map(|m| m.flakiness_index).sum::<f64>() / group.len() as f64,
+            max_index: group.iter().map(|m| m.flakiness_index).fold(0.0, f64::max),
+        })
+        .collect();
+    Ok(FlakinessReport { mutations, by_type })
+}
+
+/// Zip parallel id, type and history lists into (id, type, history) rows.
+pub fn history_rows(
+    mutation_ids: Vec<String>,
+    mutation_types: Vec<String>,
+    histories: Vec<Vec<bool>>,
+) -> Result<Vec<(String, String, Vec<bool>)>, String> {
+    if mutation_ids.len() != mutation_types.len() || mutation_ids.len() != histories.len() {
+        return Err(format!(
+            "got {} mutation ids, {} types and {} histories",
+            mutation_ids.len(),
+            mutation_types.len(),
+            histories.len()
+        ));
+    }
+    Ok(mutation_ids
+        .into_iter()
+        .zip(mutation_types)
+        .zip(histories)
+        .map(|((id, t), h)| (id, t, h))
+        .collect())
+}
+
+/// Flakiness index per mutation and per mutation type.
+///
+/// `histories[i]` is the pass/fail history of `mutation_ids[i]`, oldest
+/// run first. Mutations scoring at least `flaky_threshold` are marked flaky.
+#[pyfunction]
+#[pyo3(signature = (mutation_ids, mutation_types, histories, flaky_threshold = 0.3))]
+pub fn flakiness_index(
+    py: Python<'_>,
+    mutation_ids: Vec<String>,
+    mutation_types: Vec<String>,
+    histories: Vec<Vec<bool>>,
+    flaky_threshold: f64,
+) -> PyResult<FlakinessReport> {
+    let rows = history_rows(mutation_ids, mutation_types, histories).map_err(PyValueError::new_err)?;
+    py.allow_threads(|| flakiness_report(&rows, flaky_threshold))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mutation_flakiness() {
+        let steady = mutation_flakiness("a", "noise", &[false; 6], 0.3);
+        assert_eq!((steady.flips, steady.flakiness_index, steady.flaky), (0, 0.0, false));
+
+        let alternating = mutation_flakiness("b", "noise", &[true, false, true, false], 0.3);
+        assert_eq!(alternating.flips, 3);
+        assert!((alternating.flakiness_index - 1.0).abs() < 1e-12);
+        assert!(alternating.flaky);
+
+        // One late regression flips once and stays under the threshold
+        let mut history = vec![true; 9];
+        history.push(false);
+        let regression = mutation_flakiness("c", "noise", &history, 0.3);
+        assert_eq!(regression.flips, 1);
+        assert!(regression.flakiness_index < 0.3, "{}", regression.flakiness_index);
+
+        let single = mutation_flakiness("d", "noise", &[true], 0.3);
+        assert_eq!((single.flip_rate, single.flaky), (0.0, false));
+    }
+
+    #[test]
+    fn test_report_by_type() {
+        let histories = vec![
+            ("p1".to_string(), "paraphrase".to_string(), vec![true, false, true, false]),
+            ("p2".to_string(), "paraphrase".to_string(), vec![true, true, true]),
+            ("i1".to_string(), "prompt_injection".to_string(), vec![false, true, true, false]),
+        ];
+        let report = flakiness_report(&histories, 0.3).unwrap();
+        let order: Vec<&str> = report.mutations.iter().map(|m| m.mutation_id.as_str()).collect();
+        assert_eq!(order, vec!["p1", "i1", "p2"]);
+        assert_eq!(report.by_type.len(), 2);
+        let paraphrase = &report.by_type[0];
+        assert_eq!((paraphrase.mutation_type.as_str(), paraphrase.mutations, paraphrase.flaky), ("paraphrase", 2, 1));
+        assert!((paraphrase.mean_index - 0.5).abs() < 1e-12);
+        assert!(flakiness_report(&histories, 1.5).is_err());
+    }
+}
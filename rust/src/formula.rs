@@ -0,0 +1,508 @@
+//! Custom scoring formulas
+//!
+//! Teams with their own composite score write it as a small arithmetic
+//! expression over aggregate variables, for example
+//! `0.7 * pass_rate.prompt_injection + 0.3 * min(1, 2000 / p95_latency_ms)`.
+//! The evaluator only knows numbers, the operators below and a fixed set of
+//! functions, so a config file cannot run arbitrary code.
+//!
+//! Operators by increasing precedence: `||`, `&&`, comparisons
+//! (`< <= > >= == !=`), `+ -`, `* / %`, unary `- !`, and `^`
+//! (right-associative). Booleans are 1.0 and 0.0. Expressions nest at most
+//! `MAX_DEPTH` levels deep, counting parentheses, calls, unary operators and
+//! chained binary operators, so evaluation cannot exhaust the stack.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::scoring::TestStatistics;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+}
+
+const OPERATORS: &[&str] = &["||", "&&", "<=", ">=", "==", "!=", "<", ">", "+", "-", "*", "/", "%", "^", "!"];
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                i += 1;
+                if i < chars.len() && matches!(chars[i], '+' | '-') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = text.parse().map_err(|_| format!("invalid number '{}'", text))?;
+            tokens.push(Token::Num(n));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.')) {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect()));
+        } else if c == '(' {
+            tokens.push(Token::LParen);
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token::RParen);
+            i += 1;
+        } else if c == ',' {
+            tokens.push(Token::Comma);
+            i += 1;
+        } else {
+            let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(**op))
+                .ok_or_else(|| format!("unexpected character '{}' at {}", c, i))?;
+            tokens.push(Token::Op(op));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Num(f64),
+    Var(String),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+/// Binding power of a binary operator: (left, right).
+fn binding_power(op: &str) -> Option<(u8, u8)> {
+    Some(match op {
+        "||" => (1, 2),
+        "&&" => (3, 4),
+        "<" | "<=" | ">" | ">=" | "==" | "!=" => (5, 6),
+        "+" | "-" => (7, 8),
+        "*" | "/" | "%" => (9, 10),
+        "^" => (13, 12),
+        _ => return None,
+    })
+}
+
+const UNARY_POWER: u8 = 11;
+
+/// Deepest expression tree the parser accepts.
+pub const MAX_DEPTH: usize = 128;
+
+/// (name, arity or None for variadic >= 1)
+const FUNCTIONS: &[(&str, Option<usize>)] = &[
+    ("min", None),
+    ("max", None),
+    ("abs", Some(1)),
+    ("sqrt", Some(1)),
+    ("ln", Some(1)),
+    ("exp", Some(1)),
+    ("round", Some(1)),
+    ("clamp", Some(3)),
+    ("if", Some(3)),
+];
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn expr(&mut self, min_power: u8) -> Result<Expr, String> {
+        self.depth += 1;
+        let result = self.nested_expr(min_power);
+        self.depth -= 1;
+        result
+    }
+
+    fn nested_expr(&mut self, min_power: u8) -> Result<Expr, String> {
+        if self.depth > MAX_DEPTH {
+            return Err(format!("expression nests deeper than {} levels", MAX_DEPTH));
+        }
+        let mut lhs = match self.next() {
+            Some(Token::Num(n)) => Expr::Num(n),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::LParen) => {
+                self.pos += 1;
+                let args = self.arguments()?;
+                let (_, arity) = FUNCTIONS
+                    .iter()
+                    .find(|(f, _)| *f == name)
+                    .ok_or_else(|| format!("unknown function '{}'", name))?;
+                match arity {
+                    Some(n) if args.len() != *n => {
+                        return Err(format!("{}() takes {} argument(s), got {}", name, n, args.len()))
+                    }
+                    None if args.is_empty() => return Err(format!("{}() needs at least one argument", name)),
+                    _ => {}
+                }
+                Expr::Call(name, args)
+            }
+            Some(Token::Ident(name)) => Expr::Var(name),
+            Some(Token::Op(op @ ("-" | "!"))) => Expr::Unary(op, Box::new(self.expr(UNARY_POWER)?)),
+            Some(Token::LParen) => {
+                let inner = self.expr(0)?;
+                if self.next() != Some(Token::RParen) {
+                    return Err("missing ')'".to_string());
+                }
+                inner
+            }
+            Some(t) => return Err(format!("unexpected {:?}", t)),
+            None => return Err("unexpected end of expression".to_string()),
+        };
+
+        // Each chained operator wraps `lhs` one level deeper
+        let mut chain = 0;
+        while let Some(Token::Op(op)) = self.peek() {
+            let op = *op;
+            let Some((left, right)) = binding_power(op) else {
+                return Err(format!("'{}' is not a binary operator", op));
+            };
+            if left < min_power {
+                break;
+            }
+            chain += 1;
+            if self.depth + chain > MAX_DEPTH {
+                return Err(format!("expression nests deeper than {} levels", MAX_DEPTH));
+            }
+            self.pos += 1;
+            let rhs = self.expr(right)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Expr>, String> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::RParen) {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expr(0)?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::RParen) => return Ok(args),
+                _ => return Err("expected ',' or ')' in argument list".to_string()),
+            }
+        }
+    }
+}
+
+fn truth(b: bool) -> f64 {
+    if b {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+fn eval(expr: &Expr, vars: &HashMap<String, f64>) -> Result<f64, String> {
+    Ok(match expr {
+        Expr::Num(n) => *n,
+        Expr::Var(name) => *vars.get(name).ok_or_else(|| format!("unknown variable '{}'", name))?,
+        Expr::Unary("-", e) => -eval(e, vars)?,
+        Expr::Unary(_, e) => truth(eval(e, vars)? == 0.0),
+        Expr::Binary("&&", a, b) => truth(eval(a, vars)? != 0.0 && eval(b, vars)? != 0.0),
+        Expr::Binary("||", a, b) => truth(eval(a, vars)? != 0.0 || eval(b, vars)? != 0.0),
+        Expr::Binary(op, a, b) => {
+            let (x, y) = (eval(a, vars)?, eval(b, vars)?);
+            match *op {
+                "+" => x + y,
+                "-" => x - y,
+                "*" => x * y,
+                "/" | "%" if y == 0.0 => return Err("division by zero".to_string()),
+                "/" => x / y,
+                "%" => x % y,
+                "^" => x.powf(y),
+                "<" => truth(x < y),
+                "<=" => truth(x <= y),
+                ">" => truth(x > y),
+                ">=" => truth(x >= y),
+                "==" => truth(x == y),
+                _ => truth(x != y),
+            }
+        }
+        Expr::Call(name, args) if name == "if" => {
+            if eval(&args[0], vars)? != 0.0 {
+                eval(&args[1], vars)?
+            } else {
+                eval(&args[2], vars)?
+            }
+        }
+        Expr::Call(name, args) => {
+            let v: Vec<f64> = args.iter().map(|a| eval(a, vars)).collect::<Result<_, _>>()?;
+            match name.as_str() {
+                "min" => v.iter().copied().fold(f64::INFINITY, f64::min),
+                "max" => v.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                "abs" => v[0].abs(),
+                "sqrt" => v[0].sqrt(),
+                "ln" => v[0].ln(),
+                "exp" => v[0].exp(),
+                "round" => v[0].round(),
+                _ if !(v[1].is_finite() && v[2].is_finite()) => {
+                    return Err(format!("clamp() bounds must be finite, got {} and {}", v[1], v[2]))
+                }
+                _ => v[0].clamp(v[1].min(v[2]), v[2].max(v[1])),
+            }
+        }
+    })
+}
+
+/// A parsed scoring formula
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    source: String,
+    ast: Expr,
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut parser = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            depth: 0,
+        };
+        let ast = parser.expr(0)?;
+        if let Some(t) = parser.peek() {
+            return Err(format!("unexpected {:?} after expression", t));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            ast,
+        })
+    }
+
+    /// Variable names referenced by the formula, sorted.
+    pub fn variables(&self) -> Vec<String> {
+        fn collect(e: &Expr, out: &mut Vec<String>) {
+            match e {
+                Expr::Var(v) if !out.contains(v) => out.push(v.clone()),
+                Expr::Unary(_, a) => collect(a, out),
+                Expr::Binary(_, a, b) => {
+                    collect(a, out);
+                    collect(b, out);
+                }
+                Expr::Call(_, args) => args.iter().for_each(|a| collect(a, out)),
+                _ => {}
+            }
+        }
+        let mut out = Vec::new();
+        collect(&self.ast, &mut out);
+        out.sort();
+        out
+    }
+
+    pub fn evaluate(&self, vars: &HashMap<String, f64>) -> Result<f64, String> {
+        eval(&self.ast, vars)
+    }
+}
+
+/// Aggregate variables a formula can use, derived from run statistics.
+///
+/// Per-type pass rates are exposed as `pass_rate.<mutation_type>`.
+pub fn statistics_variables(stats: &TestStatistics) -> HashMap<String, f64> {
+    let mut vars: HashMap<String, f64> = [
+        ("robustness_score", stats.robustness_score),
+        ("total", stats.total_mutations as f64),
+        ("passed", stats.passed_mutations as f64),
+        ("failed", stats.failed_mutations as f64),
+        (
+            "pass_rate",
+            if stats.total_mutations > 0 {
+                stats.passed_mutations as f64 / stats.total_mutations as f64
+            } else {
+                0.0
+            },
+        ),
+        ("avg_latency_ms", stats.avg_latency_ms),
+        ("p50_latency_ms", stats.p50_latency_ms),
+        ("p95_latency_ms", stats.p95_latency_ms),
+        ("p99_latency_ms", stats.p99_latency_ms),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v))
+    .collect();
+    for t in &stats.by_type {
+        vars.insert(format!("pass_rate.{}", t.mutation_type), t.pass_rate);
+    }
+    vars
+}
+
+/// Severity count variables: `severity.<level>` (results at that level)
+/// and `failed.<level>` (failed results at that level), levels lowercased.
+/// Fails when the severity and pass lists differ in length.
+pub fn severity_variables(severities: &[String], passed: &[bool]) -> Result<HashMap<String, f64>, String> {
+    if severities.len() != passed.len() {
+        return Err(format!(
+            "got {} severities and {} pass flags",
+            severities.len(),
+            passed.len()
+        ));
+    }
+    let mut vars = HashMap::new();
+    for (severity, &ok) in severities.iter().zip(passed) {
+        let level = severity.to_lowercase();
+        *vars.entry(format!("failed.{}", level)).or_insert(0.0) += if ok { 0.0 } else { 1.0 };
+        *vars.entry(format!("severity.{}", level)).or_insert(0.0) += 1.0;
+    }
+    Ok(vars)
+}
+
+/// Compiled custom scoring formula.
+#[pyclass(name = "ScoringFormula")]
+#[derive(Debug, Clone)]
+pub struct PyScoringFormula {
+    inner: Formula,
+}
+
+#[pymethods]
+impl PyScoringFormula {
+    /// Parse `expression`; raises ValueError on syntax errors.
+    #[new]
+    fn new(expression: &str) -> PyResult<Self> {
+        Formula::parse(expression)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Evaluate with `variables`, e.g. `{"pass_rate.noise": 0.9, "p95_latency_ms": 800}`.
+    fn evaluate(&self, variables: HashMap<String, f64>) -> PyResult<f64> {
+        self.inner.evaluate(&variables).map_err(PyValueError::new_err)
+    }
+
+    fn variables(&self) -> Vec<String> {
+        self.inner.variables()
+    }
+
+    #[getter]
+    fn expression(&self) -> &str {
+        &self.inner.source
+    }
+}
+
+/// Parse and evaluate a scoring formula in one call.
+#[pyfunction]
+pub fn evaluate_formula(expression: &str, variables: HashMap<String, f64>) -> PyResult<f64> {
+    Formula::parse(expression)
+        .and_then(|f| f.evaluate(&variables))
+        .map_err(PyValueError::new_err)
+}
+
+/// Aggregate variables for a formula, see `statistics_variables`.
+#[pyfunction]
+pub fn statistics_formula_variables(statistics: TestStatistics) -> HashMap<String, f64> {
+    statistics_variables(&statistics)
+}
+
+/// Severity count variables for a formula, see `severity_variables`.
+#[pyfunction]
+pub fn severity_formula_variables(severities: Vec<String>, passed: Vec<bool>) -> PyResult<HashMap<String, f64>> {
+    severity_variables(&severities, &passed).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::{calculate_statistics, MutationResult};
+
+    fn eval_str(src: &str, vars: &[(&str, f64)]) -> Result<f64, String> {
+        let vars = vars.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+        Formula::parse(src)?.evaluate(&vars)
+    }
+
+    #[test]
+    fn test_precedence_and_functions() {
+        assert_eq!(eval_str("1 + 2 * 3", &[]).unwrap(), 7.0);
+        assert_eq!(eval_str("(1 + 2) * 3", &[]).unwrap(), 9.0);
+        assert_eq!(eval_str("2 ^ 3 ^ 2", &[]).unwrap(), 512.0);
+        assert_eq!(eval_str("-2 ^ 2", &[]).unwrap(), -4.0);
+        assert_eq!(eval_str("10 % 4 + .5", &[]).unwrap(), 2.5);
+        assert_eq!(eval_str("1 < 2 && !(3 == 4)", &[]).unwrap(), 1.0);
+        assert_eq!(eval_str("min(3, x, 5) + max(1, 2)", &[("x", 0.5)]).unwrap(), 2.5);
+        assert_eq!(eval_str("clamp(1.7, 0, 1) + if(x > 1, 10, 20)", &[("x", 2.0)]).unwrap(), 11.0);
+        assert_eq!(eval_str("1e3 / 4", &[]).unwrap(), 250.0);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(eval_str("1 +", &[]).is_err());
+        assert!(eval_str("(1 + 2", &[]).unwrap_err().contains("')'"));
+        assert!(eval_str("1 2", &[]).is_err());
+        assert!(eval_str("system(1)", &[]).unwrap_err().contains("unknown function"));
+        assert!(eval_str("abs(1, 2)", &[]).is_err());
+        assert!(eval_str("y * 2", &[]).unwrap_err().contains("'y'"));
+        assert!(eval_str("1 / 0", &[]).unwrap_err().contains("division"));
+        assert!(eval_str("1 $ 2", &[]).is_err());
+        assert!(eval_str("clamp(1, sqrt(-1), sqrt(-1))", &[]).unwrap_err().contains("clamp"));
+        assert!(eval_str("clamp(1, 0, ln(0))", &[]).unwrap_err().contains("clamp"));
+        assert_eq!(eval_str("clamp(5, 2, 0)", &[]).unwrap(), 2.0);
+
+        let nested = format!("{}1{}", "(".repeat(MAX_DEPTH + 1), ")".repeat(MAX_DEPTH + 1));
+        assert!(eval_str(&nested, &[]).unwrap_err().contains("deeper than"));
+        assert!(eval_str(&"-".repeat(100_000), &[]).unwrap_err().contains("deeper than"));
+        assert!(eval_str(&vec!["1"; 10_000].join(" + "), &[]).unwrap_err().contains("deeper than"));
+        let shallow = format!("{}1{}", "(".repeat(MAX_DEPTH / 2), ")".repeat(MAX_DEPTH / 2));
+        assert_eq!(eval_str(&format!("{} + 1", shallow), &[]).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_formula_over_run_statistics() {
+        let result = |t: &str, passed: bool, latency_ms: f64| MutationResult {
+            mutation_type: t.to_string(),
+            passed,
+            latency_ms,
+            ..Default::default()
+        };
+        let stats = calculate_statistics(&[
+            result("prompt_injection", true, 100.0),
+            result("prompt_injection", false, 300.0),
+            result("noise", true, 200.0),
+        ]).unwrap();
+        let formula = Formula::parse("0.5 * pass_rate.prompt_injection + 0.5 * pass_rate.noise - (avg_latency_ms > 1000) * 0.1").unwrap();
+        assert_eq!(formula.variables(), vec!["avg_latency_ms", "pass_rate.noise", "pass_rate.prompt_injection"]);
+        let score = formula.evaluate(&statistics_variables(&stats)).unwrap();
+        assert!((score - 0.75).abs() < 1e-12);
+
+        let severities = ["Critical".to_string(), "high".to_string(), "critical".to_string()];
+        assert!(severity_variables(&severities, &[false, true]).unwrap_err().contains("2 pass flags"));
+        let mut vars = severity_variables(&severities, &[false, true, true]).unwrap();
+        vars.insert("robustness".into(), 0.9);
+        let gated = Formula::parse("if(failed.critical > 0, min(robustness, 0.5), robustness)").unwrap();
+        assert_eq!(gated.evaluate(&vars).unwrap(), 0.5);
+        assert_eq!(vars["severity.critical"], 2.0);
+        assert_eq!(vars["failed.high"], 0.0);
+    }
+}
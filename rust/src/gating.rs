@@ -0,0 +1,342 @@
+//! CI gating decisions
+//!
+//! The CLI and Python callers both need to turn a run's statistics into a
+//! pass/warn/fail verdict and an exit code. Keeping the policy evaluation in
+//! one place means every entry point makes the same decision for the same
+//! numbers.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::TestStatistics;
+
+/// Thresholds a run must meet to pass the gate
+#[pyclass(get_all, set_all)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatePolicy {
+    /// Score below which the run fails
+    pub min_score: f64,
+    /// Score below which the run warns
+    pub warn_score: f64,
+    /// Critical failures allowed before the run fails
+    pub max_critical_failures: usize,
+    /// Score drop versus baseline at or beyond which the run fails
+    pub max_regression: f64,
+    /// Score drop versus baseline at or beyond which the run warns
+    pub warn_regression: f64,
+    /// Treat warnings as failures for the exit code
+    pub fail_on_warn: bool,
+}
+
+impl Default for GatePolicy {
+    fn default() -> Self {
+        Self {
+            min_score: 0.0,
+            warn_score: 0.0,
+            max_critical_failures: 0,
+            max_regression: f64::INFINITY,
+            warn_regression: f64::INFINITY,
+            fail_on_warn: false,
+        }
+    }
+}
+
+impl GatePolicy {
+    /// Scores must satisfy `0 <= min_score <= warn_score <= 1` and
+    /// regressions `0 <= warn_regression <= max_regression`; NaN is
+    /// rejected so no threshold silently passes or fails every run.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0 <= self.min_score && self.min_score <= self.warn_score && self.warn_score <= 1.0) {
+            return Err(format!(
+                "need 0 <= min_score <= warn_score <= 1, got min_score {} and warn_score {}",
+                self.min_score, self.warn_score
+            ));
+        }
+        if !(0.0 <= self.warn_regression && self.warn_regression <= self.max_regression) {
+            return Err(format!(
+                "need 0 <= warn_regression <= max_regression, got warn_regression {} and max_regression {}",
+                self.warn_regression, self.max_regression
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl GatePolicy {
+    #[new]
+    #[pyo3(signature = (
+        min_score = 0.0,
+        warn_score = None,
+        max_critical_failures = 0,
+        max_regression = None,
+        warn_regression = None,
+        fail_on_warn = false
+    ))]
+    fn py_new(
+        min_score: f64,
+        warn_score: Option<f64>,
+        max_critical_failures: usize,
+        max_regression: Option<f64>,
+        warn_regression: Option<f64>,
+        fail_on_warn: bool,
+    ) -> PyResult<Self> {
+        let max_regression = max_regression.unwrap_or(f64::INFINITY);
+        let policy = Self {
+            min_score,
+            warn_score: warn_score.unwrap_or(min_score),
+            max_critical_failures,
+            max_regression,
+            warn_regression: warn_regression.unwrap_or(max_regression),
+            fail_on_warn,
+        };
+        policy.validate().map_err(PyValueError::new_err)?;
+        Ok(policy)
+    }
+}
+
+/// The numbers a gate decision is based on
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GateInput {
+    pub score: f64,
+    pub critical_failures: usize,
+    pub baseline_score: Option<f64>,
+}
+
+impl GateInput {
+    /// Gate input for a run, compared with `baseline` when given.
+    ///
+    /// Critical failures are the mutations failed by a critical check,
+    /// from the statistics' severity breakdown. Statistics exported
+    /// before the breakdown was recorded cannot be gated on critical
+    /// failures, so they are rejected rather than counted as none.
+    pub fn from_statistics(stats: &TestStatistics, baseline: Option<&TestStatistics>) -> Result<Self, String> {
+        let severity = stats
+            .severity
+            .as_ref()
+            .ok_or("statistics have no severity breakdown; recalculate them to gate on critical failures")?;
+        Ok(Self {
+            score: stats.robustness_score,
+            critical_failures: severity.critical_mutations,
+            baseline_score: baseline.map(|b| b.robustness_score),
+        })
+    }
+}
+
+/// Outcome of a gate evaluation
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GateVerdict {
+    /// "pass", "warn" or "fail"
+    pub status: String,
+    pub exit_code: i32,
+    pub reasons: Vec<String>,
+}
+
+#[pymethods]
+impl GateVerdict {
+    fn __bool__(&self) -> bool {
+        self.exit_code == 0
+    }
+}
+
+/// Evaluate a run against a gate policy.
+pub fn evaluate_gate(input: &GateInput, policy: &GatePolicy) -> GateVerdict {
+    let mut failures: Vec<String> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+
+    if input.score < policy.min_score {
+        failures.push(format!(
+            "score {:.1}% is below the minimum {:.1}%",
+            input.score * 100.0,
+            policy.min_score * 100.0
+        ));
+    } else if input.score < policy.warn_score {
+        warnings.push(format!(
+            "score {:.1}% is below the warning level {:.1}%",
+            input.score * 100.0,
+            policy.warn_score * 100.0
+        ));
+    }
+
+    if input.critical_failures > policy.max_critical_failures {
+        failures.push(format!(
+            "{} critical failure(s), at most {} allowed",
+            input.critical_failures, policy.max_critical_failures
+        ));
+    }
+
+    if let Some(baseline) = input.baseline_score {
+        let drop = baseline - input.score;
+        if drop >= policy.max_regression {
+            failures.push(format!(
+                "score regressed by {:.1} points versus baseline {:.1}%",
+                drop * 100.0,
+                baseline * 100.0
+            ));
+        } else if drop >= policy.warn_regression {
+            warnings.push(format!(
+                "score dropped by {:.1} points versus baseline {:.1}%",
+                drop * 100.0,
+                baseline * 100.0
+            ));
+        }
+    }
+
+    let (status, exit_code) = if !failures.is_empty() {
+        ("fail", 1)
+    } else if !warnings.is_empty() {
+        ("warn", i32::from(policy.fail_on_warn))
+    } else {
+        ("pass", 0)
+    };
+
+    failures.extend(warnings);
+    GateVerdict {
+        status: status.to_string(),
+        exit_code,
+        reasons: failures,
+    }
+}
+
+/// Decide pass/warn/fail for a run.
+///
+/// `statistics` and the optional `baseline` are `TestStatistics`, from
+/// `calculate_statistics` or loaded with `TestStatistics.from_json`.
+/// Mutations failed by a critical check count against
+/// `max_critical_failures`. The policy is validated again here, since its
+/// fields can be set after construction.
+#[pyfunction]
+#[pyo3(signature = (statistics, policy, baseline = None))]
+pub fn gate(
+    statistics: TestStatistics,
+    policy: &GatePolicy,
+    baseline: Option<TestStatistics>,
+) -> PyResult<GateVerdict> {
+    policy.validate().map_err(PyValueError::new_err)?;
+    let input = GateInput::from_statistics(&statistics, baseline.as_ref()).map_err(PyValueError::new_err)?;
+    Ok(evaluate_gate(&input, policy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::{calculate_statistics, CheckResult, MutationResult};
+    use crate::severity::Severity;
+
+    fn policy() -> GatePolicy {
+        GatePolicy {
+            min_score: 0.7,
+            warn_score: 0.85,
+            max_critical_failures: 0,
+            max_regression: 0.1,
+            warn_regression: 0.03,
+            fail_on_warn: false,
+        }
+    }
+
+    #[test]
+    fn test_pass_and_warn() {
+        let pass = evaluate_gate(&GateInput { score: 0.9, ..Default::default() }, &policy());
+        assert_eq!((pass.status.as_str(), pass.exit_code), ("pass", 0));
+        assert!(pass.reasons.is_empty());
+
+        let warn = evaluate_gate(&GateInput { score: 0.8, ..Default::default() }, &policy());
+        assert_eq!((warn.status.as_str(), warn.exit_code), ("warn", 0));
+
+        let strict = GatePolicy {
+            fail_on_warn: true,
+            ..policy()
+        };
+        assert_eq!(
+            evaluate_gate(&GateInput { score: 0.8, ..Default::default() }, &strict).exit_code,
+            1
+        );
+    }
+
+    #[test]
+    fn test_fail_reasons() {
+        let verdict = evaluate_gate(
+            &GateInput {
+                score: 0.75,
+                critical_failures: 2,
+                baseline_score: Some(0.9),
+            },
+            &policy(),
+        );
+        assert_eq!((verdict.status.as_str(), verdict.exit_code), ("fail", 1));
+        assert_eq!(verdict.reasons.len(), 3);
+        assert!(verdict.reasons[0].contains("critical"));
+        assert!(verdict.reasons[1].contains("regressed"));
+        assert!(verdict.reasons[2].contains("warning level"));
+    }
+
+    #[test]
+    fn test_regression_warning() {
+        let verdict = evaluate_gate(
+            &GateInput {
+                score: 0.9,
+                critical_failures: 0,
+                baseline_score: Some(0.95),
+            },
+            &policy(),
+        );
+        assert_eq!(verdict.status, "warn");
+        assert!(verdict.reasons[0].contains("dropped"));
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        assert!(policy().validate().is_ok());
+        assert!(GatePolicy::default().validate().is_ok());
+        for bad in [
+            GatePolicy { min_score: f64::NAN, ..policy() },
+            GatePolicy { min_score: -0.1, ..policy() },
+            GatePolicy { warn_score: 1.5, ..policy() },
+            GatePolicy { warn_score: 0.5, ..policy() },
+            GatePolicy { max_regression: f64::NAN, ..policy() },
+            GatePolicy { warn_regression: -0.01, ..policy() },
+            GatePolicy { warn_regression: 0.2, ..policy() },
+        ] {
+            assert!(bad.validate().is_err(), "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn test_critical_checks_from_statistics() {
+        let leaked = MutationResult {
+            mutation_type: "prompt_injection".to_string(),
+            passed: false,
+            checks: vec![CheckResult {
+                check_type: "canary_leak".to_string(),
+                passed: false,
+                details: String::new(),
+                severity: Severity::Critical,
+            }],
+            ..Default::default()
+        };
+        let clean = MutationResult {
+            mutation_type: "noise".to_string(),
+            passed: true,
+            ..Default::default()
+        };
+        let stats = calculate_statistics(&[leaked, clean.clone(), clean]).unwrap();
+        let baseline = calculate_statistics(&[MutationResult {
+            passed: true,
+            ..Default::default()
+        }])
+        .unwrap();
+        let input = GateInput::from_statistics(&stats, Some(&baseline)).unwrap();
+        assert_eq!((input.critical_failures, input.baseline_score), (1, Some(1.0)));
+        let verdict = evaluate_gate(&input, &GatePolicy::default());
+        assert_eq!(verdict.status, "fail");
+        assert!(verdict.reasons[0].contains("critical"));
+
+        let legacy = TestStatistics {
+            severity: None,
+            ..stats
+        };
+        assert!(GateInput::from_statistics(&legacy, None).is_err());
+    }
+}
@@ -0,0 +1,488 @@
+//! Grammar-based custom mutations
+//!
+//! Teams with domain-specific perturbations (drug abbreviations, ticker
+//! symbols, internal jargon) describe them as a small grammar instead of
+//! writing a mutator:
+//!
+//! ```text
+//! # a rule is `name = alternatives ;`
+//! start  = input " " suffix | prefix input ;
+//! prefix = "Per the attending, " | "STAT: " ;
+//! suffix = "Give " dose " of " drug [" " freq] "." ;
+//! drug   = "ASA" | "aspirin" | "acetylsalicylic acid" ;
+//! dose   = ( "5" | "10" | "81" ) "mg" ;
+//! freq   = "qd" | "bid" | "prn" ;
+//! ```
+//!
+//! Alternatives are separated by `|`, `[ ... ]` is optional (taken half the
+//! time), `( ... )` groups, and the built-in `input` expands to the prompt
+//! being mutated. Rules may recurse; past the depth limit every rule takes
+//! its shortest-terminating alternative, so expansion always ends. Grammars
+//! are checked when compiled: undefined rules, duplicate rules and rules
+//! that can never terminate are errors naming the line. Nesting, in the
+//! source or during expansion, is capped at `MAX_NESTING` levels; an
+//! expansion that would go deeper is an error rather than a stack overflow.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::registry;
+use crate::rng::{resolve_seed, SplitMix64};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Punct(char),
+}
+
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let mut out = Vec::new();
+    let mut line = 1;
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            c if c.is_whitespace() => {}
+            '#' => {
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '"' => {
+                let start = line;
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => s.push('\n'),
+                            Some('t') => s.push('\t'),
+                            Some(e @ ('"' | '\\')) => s.push(e),
+                            other => {
+                                return Err(format!("line {}: unsupported escape '\\{}'", line, other.unwrap_or(' ')))
+                            }
+                        },
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            s.push(c);
+                        }
+                        None => return Err(format!("line {}: unterminated string", start)),
+                    }
+                }
+                out.push((Token::Str(s), start));
+            }
+            '=' | '|' | '[' | ']' | '(' | ')' | ';' => out.push((Token::Punct(c), line)),
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut name = c.to_string();
+                while let Some(&n) = chars.peek() {
+                    if !(n.is_alphanumeric() || n == '_' || n == '-') {
+                        break;
+                    }
+                    name.push(n);
+                    chars.next();
+                }
+                out.push((Token::Ident(name), line));
+            }
+            other => return Err(format!("line {}: unexpected character '{}'", line, other)),
+        }
+    }
+    Ok(out)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Lit(String),
+    Rule(usize),
+    Input,
+    Optional(Vec<Vec<Node>>),
+    Group(Vec<Vec<Node>>),
+}
+
+/// Deepest nesting of groups, optionals and rule expansions allowed
+pub const MAX_NESTING: usize = 256;
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Open `(` and `[` around the current position
+    depth: usize,
+    names: HashMap<String, usize>,
+    /// First line each referenced-but-maybe-undefined rule is used on
+    refs: Vec<(String, usize)>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.0)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or(self.tokens.last())
+            .map_or(1, |t| t.1)
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        match self.peek() {
+            Some(Token::Punct(p)) if *p == c => {
+                self.pos += 1;
+                Ok(())
+            }
+            _ => Err(format!("line {}: expected '{}'", self.line(), c)),
+        }
+    }
+
+    fn rule_index(&mut self, name: &str, line: usize) -> usize {
+        let next = self.names.len();
+        let index = *self.names.entry(name.to_string()).or_insert(next);
+        if index == next {
+            self.refs.push((name.to_string(), line));
+        }
+        index
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alts = vec![self.sequence()?];
+        while self.peek() == Some(&Token::Punct('|')) {
+            self.pos += 1;
+            alts.push(self.sequence()?);
+        }
+        Ok(alts)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, String> {
+        let mut seq = Vec::new();
+        loop {
+            let line = self.line();
+            let node = match self.peek().cloned() {
+                Some(Token::Str(s)) => Node::Lit(s),
+                Some(Token::Ident(name)) if name == "input" => Node::Input,
+                Some(Token::Ident(name)) => Node::Rule(self.rule_index(&name, line)),
+                Some(Token::Punct(open @ ('[' | '('))) => {
+                    self.pos += 1;
+                    self.depth += 1;
+                    if self.depth > MAX_NESTING {
+                        return Err(format!("line {}: nested deeper than {} levels", line, MAX_NESTING));
+                    }
+                    let alts = self.alternatives()?;
+                    self.depth -= 1;
+                    if open == '[' {
+                        self.expect(']')?;
+                        seq.push(Node::Optional(alts));
+                    } else {
+                        self.expect(')')?;
+                        seq.push(Node::Group(alts));
+                    }
+                    continue;
+                }
+                _ => break,
+            };
+            self.pos += 1;
+            seq.push(node);
+        }
+        if seq.is_empty() {
+            return Err(format!("line {}: empty alternative", self.line()));
+        }
+        Ok(seq)
+    }
+}
+
+/// A compiled mutation grammar
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    names: Vec<String>,
+    rules: Vec<Vec<Vec<Node>>>,
+    /// Shortest derivation height per rule
+    heights: Vec<usize>,
+    start: usize,
+    pub max_depth: usize,
+}
+
+const UNBOUNDED: usize = usize::MAX;
+
+fn seq_height(seq: &[Node], heights: &[usize]) -> usize {
+    seq.iter().map(|n| node_height(n, heights)).max().unwrap_or(0)
+}
+
+fn alts_height(alts: &[Vec<Node>], heights: &[usize]) -> usize {
+    alts.iter().map(|s| seq_height(s, heights)).min().unwrap_or(UNBOUNDED)
+}
+
+fn node_height(node: &Node, heights: &[usize]) -> usize {
+    match node {
+        Node::Lit(_) | Node::Input | Node::Optional(_) => 0,
+        Node::Rule(r) => heights[*r].saturating_add(1),
+        Node::Group(alts) => alts_height(alts, heights),
+    }
+}
+
+impl Grammar {
+    /// Compile `source`; `start` names the rule expansion begins from.
+    pub fn compile(source: &str, start: &str, max_depth: usize) -> Result<Self, String> {
+        if max_depth > MAX_NESTING {
+            return Err(format!("max_depth must be at most {}, got {}", MAX_NESTING, max_depth));
+        }
+        let mut p = Parser {
+            tokens: tokenize(source)?,
+            pos: 0,
+            depth: 0,
+            names: HashMap::new(),
+            refs: Vec::new(),
+        };
+        let mut defined: HashMap<usize, (Vec<Vec<Node>>, usize)> = HashMap::new();
+        while let Some(token) = p.peek().cloned() {
+            let line = p.line();
+            let Token::Ident(name) = token else {
+                return Err(format!("line {}: expected a rule name", line));
+            };
+            if name == "input" {
+                return Err(format!("line {}: 'input' is built in and cannot be redefined", line));
+            }
+            p.pos += 1;
+            p.expect('=')?;
+            let alts = p.alternatives()?;
+            p.expect(';')?;
+            let index = p.rule_index(&name, line);
+            if let Some((_, first)) = defined.insert(index, (alts, line)) {
+                return Err(format!("line {}: rule '{}' already defined on line {}", line, name, first));
+            }
+        }
+
+        let mut names = vec![String::new(); p.names.len()];
+        for (name, &i) in &p.names {
+            names[i] = name.clone();
+        }
+        if let Some((name, line)) = p.refs.iter().find(|(n, _)| !defined.contains_key(&p.names[n])) {
+            return Err(format!("line {}: undefined rule '{}'", line, name));
+        }
+        let start = *p
+            .names
+            .get(start)
+            .ok_or_else(|| format!("start rule '{}' is not defined", start))?;
+        let mut rules = vec![Vec::new(); names.len()];
+        let mut lines = vec![0; names.len()];
+        for (i, (alts, line)) in defined {
+            rules[i] = alts;
+            lines[i] = line;
+        }
+
+        // Fixed point of the shortest derivation height
+        let mut heights = vec![UNBOUNDED; rules.len()];
+        loop {
+            let next: Vec<usize> = rules.iter().map(|alts| alts_height(alts, &heights)).collect();
+            if next == heights {
+                break;
+            }
+            heights = next;
+        }
+        if let Some(i) = (0..rules.len()).find(|&i| heights[i] == UNBOUNDED) {
+            return Err(format!("line {}: rule '{}' can never finish expanding", lines[i], names[i]));
+        }
+
+        Ok(Self {
+            names,
+            rules,
+            heights,
+            start,
+            max_depth,
+        })
+    }
+
+    /// Rule names, in order of first mention.
+    pub fn rule_names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// `depth` counts rule expansions and picks the policy; `level` counts
+    /// every nested call and bounds the stack.
+    fn expand_alts(
+        &self,
+        alts: &[Vec<Node>],
+        input: &str,
+        (depth, level): (usize, usize),
+        rng: &mut SplitMix64,
+        out: &mut String,
+    ) -> Result<(), String> {
+        if level > MAX_NESTING {
+            return Err(format!("expansion nested deeper than {} levels", MAX_NESTING));
+        }
+        let seq = if depth >= self.max_depth {
+            alts.iter()
+                .min_by_key(|s| seq_height(s, &self.heights))
+                .expect("alternatives are never empty")
+        } else {
+            &alts[rng.below(alts.len())]
+        };
+        for node in seq {
+            match node {
+                Node::Lit(s) => out.push_str(s),
+                Node::Input => out.push_str(input),
+                Node::Rule(r) => self.expand_alts(&self.rules[*r], input, (depth + 1, level + 1), rng, out)?,
+                Node::Group(alts) => self.expand_alts(alts, input, (depth, level + 1), rng, out)?,
+                Node::Optional(alts) => {
+                    if depth < self.max_depth && rng.chance(0.5) {
+                        self.expand_alts(alts, input, (depth, level + 1), rng, out)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// One expansion of the start rule with `input` bound to the prompt.
+    pub fn expand(&self, input: &str, rng: &mut SplitMix64) -> Result<String, String> {
+        let mut out = String::new();
+        self.expand_alts(&self.rules[self.start], input, (0, 0), rng, &mut out)?;
+        Ok(out)
+    }
+}
+
+/// One grammar expansion of a prompt
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GrammarMutation {
+    pub text: String,
+    pub prompt_index: usize,
+}
+
+/// `variants` expansions of every prompt, ordered by prompt then variant.
+pub fn grammar_mutate(
+    grammar: &Grammar,
+    prompts: &[String],
+    variants: usize,
+    seed: u64,
+) -> Result<Vec<GrammarMutation>, String> {
+    (0..prompts.len() * variants)
+        .into_par_iter()
+        .map(|k| {
+            Ok(GrammarMutation {
+                text: grammar.expand(&prompts[k / variants], &mut SplitMix64::for_item(seed, k))?,
+                prompt_index: k / variants,
+            })
+        })
+        .collect()
+}
+
+/// A user-defined mutation grammar, compiled once.
+///
+/// See the module docs for the syntax; `input` in a rule stands for the
+/// prompt being mutated.
+#[pyclass(name = "MutationGrammar")]
+pub struct PyMutationGrammar {
+    inner: Grammar,
+}
+
+#[pymethods]
+impl PyMutationGrammar {
+    #[new]
+    #[pyo3(signature = (source, start = "start", max_depth = 8))]
+    fn new(source: &str, start: &str, max_depth: usize) -> PyResult<Self> {
+        Grammar::compile(source, start, max_depth)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    fn rules(&self) -> Vec<String> {
+        self.inner.rule_names().to_vec()
+    }
+
+    /// Expand the grammar `variants` times for every prompt.
+    #[pyo3(signature = (prompts, variants = 1, seed = None))]
+    fn mutate(
+        &self,
+        py: Python<'_>,
+        prompts: Vec<String>,
+        variants: usize,
+        seed: Option<u64>,
+    ) -> PyResult<Vec<GrammarMutation>> {
+        let seed = resolve_seed(seed);
+        let out = py
+            .allow_threads(|| grammar_mutate(&self.inner, &prompts, variants, seed))
+            .map_err(PyValueError::new_err)?;
+        registry().increment("mutations_generated.grammar", out.len() as u64);
+        Ok(out)
+    }
+
+    /// `n` standalone expansions, with `input` bound to the empty string.
+    #[pyo3(signature = (n, seed = None))]
+    fn generate(&self, py: Python<'_>, n: usize, seed: Option<u64>) -> PyResult<Vec<String>> {
+        let seed = resolve_seed(seed);
+        let out = py
+            .allow_threads(|| grammar_mutate(&self.inner, &[String::new()], n, seed))
+            .map_err(PyValueError::new_err)?;
+        Ok(out.into_iter().map(|m| m.text).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEDICAL: &str = r#"
+        # drug-name perturbations
+        start  = input " " suffix | prefix input ;
+        prefix = "Per the attending, " | "STAT: " ;
+        suffix = "Give " dose " of " drug [" " freq] "." ;
+        drug   = "ASA" | "aspirin" | "acetylsalicylic acid" ;
+        dose   = ( "5" | "10" | "81" ) "mg" ;
+        freq   = "qd" | "bid" | "prn" ;
+    "#;
+
+    #[test]
+    fn test_expansion_uses_input_and_rules() {
+        let g = Grammar::compile(MEDICAL, "start", 8).unwrap();
+        let prompts = vec!["Patient has a headache.".to_string()];
+        let out = grammar_mutate(&g, &prompts, 40, 3).unwrap();
+        assert_eq!(out.len(), 40);
+        assert!(out.iter().all(|m| m.text.contains("Patient has a headache.")));
+        assert!(out.iter().any(|m| m.text.starts_with("STAT: ")));
+        assert!(out.iter().any(|m| m.text.contains("mg of aspirin")));
+        assert!(out.iter().any(|m| m.text.ends_with(" prn.")));
+        assert_eq!(out, grammar_mutate(&g, &prompts, 40, 3).unwrap());
+    }
+
+    #[test]
+    fn test_recursion_is_bounded() {
+        let g = Grammar::compile(r#"start = "x" | "(" start ")" start ;"#, "start", 3).unwrap();
+        let mut rng = SplitMix64::new(1);
+        for _ in 0..200 {
+            let s = g.expand("", &mut rng).unwrap();
+            assert!(s.matches('(').count() < 16, "{}", s);
+            assert_eq!(s.matches('(').count(), s.matches(')').count());
+        }
+    }
+
+    #[test]
+    fn test_nesting_limits() {
+        // A chain of rules whose shortest derivation is deeper than the cap
+        let chain: String = (0..MAX_NESTING + 10).map(|i| format!("r{} = \"x\" r{} ;\n", i, i + 1)).collect();
+        let source = format!("start = r0 ;\n{}r{} = \"end\" ;", chain, MAX_NESTING + 10);
+        let g = Grammar::compile(&source, "start", 8).unwrap();
+        let err = g.expand("", &mut SplitMix64::new(1)).unwrap_err();
+        assert_eq!(err, format!("expansion nested deeper than {} levels", MAX_NESTING));
+        assert!(grammar_mutate(&g, &["p".to_string()], 2, 1).is_err());
+
+        let nested = format!("start = {}\"x\"{} ;", "(".repeat(MAX_NESTING + 1), ")".repeat(MAX_NESTING + 1));
+        assert!(Grammar::compile(&nested, "start", 8).unwrap_err().contains("nested deeper than"));
+        assert!(Grammar::compile("start = \"x\" ;", "start", MAX_NESTING + 1).unwrap_err().contains("max_depth"));
+    }
+
+    #[test]
+    fn test_compile_errors() {
+        let err = |src: &str| Grammar::compile(src, "start", 8).unwrap_err();
+        assert_eq!(err("start = \"a\" b ;\n"), "line 1: undefined rule 'b'");
+        assert_eq!(err("start = \"a\" ;\nstart = \"b\" ;"), "line 2: rule 'start' already defined on line 1");
+        assert_eq!(err("start = \"a\" loop ;\nloop = \"b\" loop ;"), "line 2: rule 'loop' can never finish expanding");
+        assert_eq!(err("start = \"a\"\nx = \"b\" ;"), "line 2: expected ';'");
+        assert_eq!(err("other = \"a\" ;"), "start rule 'start' is not defined");
+        assert!(err("start = \"open ;").contains("unterminated string"));
+    }
+}
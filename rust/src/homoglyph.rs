@@ -0,0 +1,153 @@
+//! Homoglyph mutation generator
+//!
+//! Replaces Latin letters with visually confusable code points from the
+//! table in `unicode`, so "paypal" can become "pаypal" with a Cyrillic
+//! "а". A model that reads the text the way a human does should not change
+//! its answer.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::metrics::registry;
+use crate::rng::{resolve_seed, SplitMix64};
+use crate::unicode::CONFUSABLES;
+
+/// Lookalikes for a Latin letter, from the confusables table and optionally
+/// its fullwidth form.
+pub fn homoglyphs_for(c: char, fullwidth: bool) -> Vec<char> {
+    let mut out: Vec<char> = CONFUSABLES
+        .iter()
+        .filter(|(_, latin)| *latin == c)
+        .map(|(glyph, _)| *glyph)
+        .collect();
+    if fullwidth && c.is_ascii_alphabetic() {
+        out.extend(char::from_u32(c as u32 - 0x21 + 0xFF01));
+    }
+    out
+}
+
+/// Homoglyph mutator settings
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HomoglyphConfig {
+    /// Fraction of substitutable letters to replace
+    pub density: f64,
+    pub fullwidth: bool,
+    pub seed: u64,
+}
+
+impl HomoglyphConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.density) {
+            return Err(format!("density must be in [0, 1], got {}", self.density));
+        }
+        Ok(())
+    }
+}
+
+/// Substitute `density` of the letters that have a lookalike.
+///
+/// The number of substitutions is rounded but at least one whenever the
+/// density is non-zero and some letter qualifies, so short prompts are
+/// still mutated.
+pub fn substitute_homoglyphs(text: &str, config: &HomoglyphConfig, rng: &mut SplitMix64) -> String {
+    let mut chars: Vec<char> = text.chars().collect();
+    let mut eligible: Vec<usize> = (0..chars.len())
+        .filter(|&i| !homoglyphs_for(chars[i], config.fullwidth).is_empty())
+        .collect();
+    if eligible.is_empty() || config.density <= 0.0 {
+        return text.to_string();
+    }
+
+    let count = ((eligible.len() as f64 * config.density).round() as usize).clamp(1, eligible.len());
+    // Partial Fisher-Yates: the first `count` entries become a uniform sample
+    for k in 0..count {
+        let j = k + rng.below(eligible.len() - k);
+        eligible.swap(k, j);
+        let i = eligible[k];
+        let glyphs = homoglyphs_for(chars[i], config.fullwidth);
+        chars[i] = glyphs[rng.below(glyphs.len())];
+    }
+    chars.into_iter().collect()
+}
+
+/// Substitute homoglyphs in every prompt in parallel, reproducibly per seed.
+pub fn substitute_homoglyphs_all(prompts: &[String], config: &HomoglyphConfig) -> Vec<String> {
+    prompts
+        .par_iter()
+        .enumerate()
+        .map(|(i, p)| substitute_homoglyphs(p, config, &mut SplitMix64::for_item(config.seed, i)))
+        .collect()
+}
+
+/// Homoglyph mutations for a batch of prompts.
+///
+/// `density` is the fraction of letters with a lookalike that get replaced;
+/// `fullwidth` also allows fullwidth Latin forms as substitutes.
+#[pyfunction]
+#[pyo3(signature = (prompts, density = 0.1, seed = None, fullwidth = false))]
+pub fn homoglyph_mutations(
+    py: Python<'_>,
+    prompts: Vec<String>,
+    density: f64,
+    seed: Option<u64>,
+    fullwidth: bool,
+) -> PyResult<Vec<String>> {
+    let seed = resolve_seed(seed);
+    let config = HomoglyphConfig { density, fullwidth, seed };
+    config.validate().map_err(PyValueError::new_err)?;
+    registry().increment("mutations_generated.homoglyph", prompts.len() as u64);
+    Ok(py.allow_threads(|| substitute_homoglyphs_all(&prompts, &config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unicode::confusable_target;
+
+    fn config(density: f64) -> HomoglyphConfig {
+        HomoglyphConfig {
+            density,
+            fullwidth: false,
+            seed: 9,
+        }
+    }
+
+    #[test]
+    fn test_homoglyphs_for() {
+        assert!(homoglyphs_for('a', false).contains(&'а'));
+        assert!(homoglyphs_for('a', false).contains(&'α'));
+        assert!(homoglyphs_for('b', false).is_empty());
+        assert_eq!(homoglyphs_for('b', true), vec!['ｂ']);
+    }
+
+    #[test]
+    fn test_substitution_density_and_reversibility() {
+        let prompt = "Please reset my password so I can access the portal";
+        let mut rng = SplitMix64::new(1);
+        let mutated = substitute_homoglyphs(prompt, &config(0.5), &mut rng);
+
+        let changed: Vec<(char, char)> = prompt
+            .chars()
+            .zip(mutated.chars())
+            .filter(|(a, b)| a != b)
+            .collect();
+        let eligible = prompt.chars().filter(|&c| !homoglyphs_for(c, false).is_empty()).count();
+        assert_eq!(changed.len(), (eligible as f64 * 0.5).round() as usize);
+        assert!(changed.iter().all(|&(a, b)| confusable_target(b) == Some(a)));
+
+        let full = substitute_homoglyphs(prompt, &config(1.0), &mut rng);
+        assert!(full.chars().all(|c| homoglyphs_for(c, false).is_empty()));
+        assert_eq!(substitute_homoglyphs(prompt, &config(0.0), &mut rng), prompt);
+    }
+
+    #[test]
+    fn test_batch_is_reproducible() {
+        let prompts: Vec<String> = vec!["open the door".into(), "xyz".into(), "bbb".into()];
+        let a = substitute_homoglyphs_all(&prompts, &config(0.01));
+        assert_eq!(a, substitute_homoglyphs_all(&prompts, &config(0.01)));
+        assert_ne!(a[0], prompts[0]);
+        assert_eq!(a[2], "bbb");
+        assert!(config(1.1).validate().is_err());
+    }
+}
@@ -0,0 +1,389 @@
+//! Compiled regex invariant checks
+//!
+//! A run's regex invariants are compiled once into a `RegexSet`, so each
+//! agent output is scanned in a single pass that reports every pattern it
+//! matches. Each invariant either must match or must not match, and
+//! carries a severity (critical, high, medium or low) that is repeated in
+//! the details of its check results. Outputs are checked in parallel.
+//!
+//! The simple invariants (substring presence or absence, case-insensitive
+//! contains, prefix, length bounds) are evaluated over a whole run's
+//! outputs in one call the same way.
+
+use std::collections::HashSet;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+
+pub(crate) const SEVERITIES: &[&str] = &["critical", "high", "medium", "low"];
+
+/// Whether an invariant's pattern is required or forbidden
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegexRule {
+    MustMatch,
+    MustNotMatch,
+}
+
+impl RegexRule {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "must_match" => Ok(RegexRule::MustMatch),
+            "must_not_match" => Ok(RegexRule::MustNotMatch),
+            other => Err(format!("unknown regex rule '{}' (expected must_match or must_not_match)", other)),
+        }
+    }
+}
+
+/// One regex invariant before compilation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegexInvariant {
+    pub name: String,
+    pub pattern: String,
+    pub rule: RegexRule,
+    pub severity: String,
+}
+
+/// A set of regex invariants compiled for repeated evaluation
+#[derive(Debug, Clone)]
+pub struct RegexCheckSet {
+    invariants: Vec<RegexInvariant>,
+    set: RegexSet,
+    /// Individual patterns, used to quote what a forbidden pattern matched
+    patterns: Vec<Regex>,
+    /// Parsed severity of each invariant
+    severities: Vec<Severity>,
+}
+
+impl RegexCheckSet {
+    pub fn compile(invariants: Vec<RegexInvariant>, case_insensitive: bool) -> Result<Self, String> {
+        let mut names = HashSet::new();
+        let mut severities = Vec::with_capacity(invariants.len());
+        for inv in &invariants {
+            if !names.insert(inv.name.as_str()) {
+                return Err(format!("duplicate invariant name '{}'", inv.name));
+            }
+            if !SEVERITIES.contains(&inv.severity.as_str()) {
+                return Err(format!(
+                    "invariant '{}': unknown severity '{}' (expected critical, high, medium or low)",
+                    inv.name, inv.severity
+                ));
+            }
+            severities.push(Severity::parse(&inv.severity).map_err(|e| format!("invariant '{}': {}", inv.name, e))?);
+        }
+        let patterns = invariants
+            .iter()
+            .map(|inv| {
+                RegexBuilder::new(&inv.pattern)
+                    .case_insensitive(case_insensitive)
+                    .build()
+                    .map_err(|e| format!("invariant '{}': invalid pattern: {}", inv.name, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let set = RegexSetBuilder::new(invariants.iter().map(|i| &i.pattern))
+            .case_insensitive(case_insensitive)
+            .build()
+            .map_err(|e| e.to_string())?;
+        Ok(Self {
+            invariants,
+            set,
+            patterns,
+            severities,
+        })
+    }
+
+    pub fn invariants(&self) -> &[RegexInvariant] {
+        &self.invariants
+    }
+
+    /// One check result per invariant, in invariant order.
+    pub fn check(&self, output: &str) -> Vec<CheckResult> {
+        let matched = self.set.matches(output);
+        self.invariants
+            .iter()
+            .enumerate()
+            .map(|(i, inv)| {
+                let hit = matched.matched(i);
+                let (passed, details) = match (inv.rule, hit) {
+                    (RegexRule::MustMatch, true) => (true, format!("matches /{}/", inv.pattern)),
+                    (RegexRule::MustMatch, false) => (false, format!("does not match /{}/", inv.pattern)),
+                    (RegexRule::MustNotMatch, false) => (true, format!("does not match /{}/", inv.pattern)),
+                    (RegexRule::MustNotMatch, true) => {
+                        let found = self.patterns[i].find(output).map_or("", |m| m.as_str());
+                        (false, format!("forbidden /{}/ matched '{}'", inv.pattern, found))
+                    }
+                };
+                CheckResult {
+                    check_type: inv.name.clone(),
+                    passed,
+                    details,
+                    severity: self.severities[i],
+                }
+            })
+            .collect()
+    }
+
+    /// Check every output in parallel.
+    pub fn check_all(&self, outputs: &[String]) -> Vec<Vec<CheckResult>> {
+        outputs.par_iter().map(|o| self.check(o)).collect()
+    }
+}
+
+/// Regex invariants compiled once and evaluated over many outputs.
+///
+/// `invariants` is a list of (name, pattern, rule, severity) tuples where
+/// rule is "must_match" or "must_not_match" and severity is "critical",
+/// "high", "medium" or "low". Check results use the name as `check_type`.
+#[pyclass(name = "RegexInvariantSet")]
+pub struct PyRegexInvariantSet {
+    inner: RegexCheckSet,
+}
+
+#[pymethods]
+impl PyRegexInvariantSet {
+    #[new]
+    #[pyo3(signature = (invariants, case_insensitive = false))]
+    fn new(invariants: Vec<(String, String, String, String)>, case_insensitive: bool) -> PyResult<Self> {
+        let invariants = invariants
+            .into_iter()
+            .map(|(name, pattern, rule, severity)| {
+                Ok(RegexInvariant {
+                    rule: RegexRule::parse(&rule)?,
+                    name,
+                    pattern,
+                    severity,
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()
+            .map_err(PyValueError::new_err)?;
+        RegexCheckSet::compile(invariants, case_insensitive)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Check results for one output, one per invariant.
+    fn check(&self, output: &str) -> Vec<CheckResult> {
+        self.inner.check(output)
+    }
+
+    /// Check results for every output, one list per output.
+    fn evaluate(&self, py: Python<'_>, outputs: Vec<String>) -> Vec<Vec<CheckResult>> {
+        py.allow_threads(|| self.inner.check_all(&outputs))
+    }
+
+    /// Severity of the named invariant.
+    fn severity(&self, name: &str) -> Option<String> {
+        self.inner
+            .invariants()
+            .iter()
+            .find(|i| i.name == name)
+            .map(|i| i.severity.clone())
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.invariants().len()
+    }
+}
+
+/// A substring or length invariant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SimpleCheck {
+    Contains(String),
+    NotContains(String),
+    /// Case-insensitive contains; the needle is stored lowercased
+    ContainsIgnoreCase(String),
+    StartsWith(String),
+    /// Bounds in characters
+    MinLength(usize),
+    MaxLength(usize),
+}
+
+impl SimpleCheck {
+    /// Build from a kind name and its argument.
+    pub fn parse(kind: &str, value: &str) -> Result<Self, String> {
+        let length = || {
+            value
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("{} needs a non-negative integer, got '{}'", kind, value))
+        };
+        Ok(match kind {
+            "contains" => SimpleCheck::Contains(value.to_string()),
+            "not_contains" => SimpleCheck::NotContains(value.to_string()),
+            "contains_ignore_case" => SimpleCheck::ContainsIgnoreCase(value.to_lowercase()),
+            "starts_with" => SimpleCheck::StartsWith(value.to_string()),
+            "min_length" => SimpleCheck::MinLength(length()?),
+            "max_length" => SimpleCheck::MaxLength(length()?),
+            other => {
+                return Err(format!(
+                    "unknown check '{}' (expected contains, not_contains, contains_ignore_case, starts_with, min_length or max_length)",
+                    other
+                ))
+            }
+        })
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimpleCheck::Contains(_) => "contains",
+            SimpleCheck::NotContains(_) => "not_contains",
+            SimpleCheck::ContainsIgnoreCase(_) => "contains_ignore_case",
+            SimpleCheck::StartsWith(_) => "starts_with",
+            SimpleCheck::MinLength(_) => "min_length",
+            SimpleCheck::MaxLength(_) => "max_length",
+        }
+    }
+
+    /// Evaluate against a single output.
+    pub fn check(&self, output: &str) -> CheckResult {
+        let lowered = matches!(self, SimpleCheck::ContainsIgnoreCase(_)).then(|| output.to_lowercase());
+        self.evaluate(output, lowered.as_deref(), output.chars().count())
+    }
+
+    /// Evaluate against `output`; `lowered` is the output lowercased, when
+    /// a case-insensitive check needs it.
+    fn evaluate(&self, output: &str, lowered: Option<&str>, chars: usize) -> CheckResult {
+        let (passed, details) = match self {
+            SimpleCheck::Contains(s) => (output.contains(s.as_str()), format!("contains '{}'", s)),
+            SimpleCheck::NotContains(s) => (!output.contains(s.as_str()), format!("does not contain '{}'", s)),
+            SimpleCheck::ContainsIgnoreCase(s) => (
+                lowered.unwrap_or_default().contains(s.as_str()),
+                format!("contains '{}' (any case)", s),
+            ),
+            SimpleCheck::StartsWith(s) => (output.starts_with(s.as_str()), format!("starts with '{}'", s)),
+            SimpleCheck::MinLength(n) => (chars >= *n, format!("length {} >= {}", chars, n)),
+            SimpleCheck::MaxLength(n) => (chars <= *n, format!("length {} <= {}", chars, n)),
+        };
+        CheckResult {
+            check_type: self.name().to_string(),
+            passed,
+            details: if passed { details } else { format!("expected {}", details) },
+            severity: Severity::Error,
+        }
+    }
+}
+
+/// Every check against every output, in parallel. Results are grouped per
+/// output in check order.
+pub fn run_simple_checks(outputs: &[String], checks: &[SimpleCheck]) -> Vec<Vec<CheckResult>> {
+    let needs_lower = checks.iter().any(|c| matches!(c, SimpleCheck::ContainsIgnoreCase(_)));
+    outputs
+        .par_iter()
+        .map(|output| {
+            let lowered = needs_lower.then(|| output.to_lowercase());
+            let chars = output.chars().count();
+            checks
+                .iter()
+                .map(|c| c.evaluate(output, lowered.as_deref(), chars))
+                .collect()
+        })
+        .collect()
+}
+
+/// Substring and length invariants over a whole run's outputs.
+///
+/// `checks` is a list of (kind, value) pairs where kind is "contains",
+/// "not_contains", "contains_ignore_case", "starts_with", "min_length" or
+/// "max_length"; lengths are given as strings of digits and counted in
+/// characters. Returns one list of check results per output.
+#[pyfunction]
+pub fn batch_invariant_checks(
+    py: Python<'_>,
+    outputs: Vec<String>,
+    checks: Vec<(String, String)>,
+) -> PyResult<Vec<Vec<CheckResult>>> {
+    let checks = checks
+        .iter()
+        .map(|(kind, value)| SimpleCheck::parse(kind, value))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PyValueError::new_err)?;
+    Ok(py.allow_threads(|| run_simple_checks(&outputs, &checks)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invariant(name: &str, pattern: &str, rule: RegexRule, severity: &str) -> RegexInvariant {
+        RegexInvariant {
+            name: name.to_string(),
+            pattern: pattern.to_string(),
+            rule,
+            severity: severity.to_string(),
+        }
+    }
+
+    fn set() -> RegexCheckSet {
+        RegexCheckSet::compile(
+            vec![
+                invariant("has_order_id", r"ORD-\d{6}", RegexRule::MustMatch, "high"),
+                invariant("no_card_number", r"\b\d{4}(?: ?\d{4}){3}\b", RegexRule::MustNotMatch, "critical"),
+            ],
+            false,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_must_and_must_not_match() {
+        let outputs: Vec<String> = vec![
+            "Your order ORD-123456 has shipped.".into(),
+            "Charged card 4111 1111 1111 1111 for ORD-654321.".into(),
+            "I could not find that order.".into(),
+        ];
+        let results = set().check_all(&outputs);
+        assert!(results[0].iter().all(|c| c.passed));
+        assert!(results[1][0].passed);
+        assert_eq!(results[1][1].check_type, "no_card_number");
+        assert_eq!(
+            results[1][1].details,
+            r"forbidden /\b\d{4}(?: ?\d{4}){3}\b/ matched '4111 1111 1111 1111'"
+        );
+        assert_eq!(results[1][1].severity, Severity::Critical);
+        assert!(!results[2][0].passed && results[2][0].details.starts_with("does not match"));
+        assert!(results[2][1].passed);
+    }
+
+    #[test]
+    fn test_compile_errors_and_case() {
+        let bad = |inv| RegexCheckSet::compile(vec![inv], false).unwrap_err();
+        assert!(bad(invariant("x", "(", RegexRule::MustMatch, "low")).starts_with("invariant 'x': invalid pattern"));
+        assert!(bad(invariant("x", "a", RegexRule::MustMatch, "urgent")).contains("unknown severity"));
+        let dup = vec![invariant("x", "a", RegexRule::MustMatch, "low"); 2];
+        assert!(RegexCheckSet::compile(dup, false).is_err());
+
+        let ci = RegexCheckSet::compile(vec![invariant("refund", "refund", RegexRule::MustMatch, "low")], true).unwrap();
+        assert!(ci.check("REFUND issued")[0].passed);
+        assert!(RegexRule::parse("should_match").is_err());
+    }
+
+    #[test]
+    fn test_simple_checks() {
+        let checks = [
+            SimpleCheck::parse("contains", "refund").unwrap(),
+            SimpleCheck::parse("not_contains", "password").unwrap(),
+            SimpleCheck::parse("contains_ignore_case", "ORDER").unwrap(),
+            SimpleCheck::parse("starts_with", "Sure").unwrap(),
+            SimpleCheck::parse("min_length", "10").unwrap(),
+            SimpleCheck::parse("max_length", " 40").unwrap(),
+        ];
+        let outputs: Vec<String> = vec![
+            "Sure, your refund for order 12 is on its way.".into(),
+            "Sure — your password is hunter2".into(),
+        ];
+        let results = run_simple_checks(&outputs, &checks);
+        let passed = |r: &[CheckResult]| r.iter().map(|c| c.passed).collect::<Vec<_>>();
+        assert_eq!(passed(&results[0]), vec![true, true, true, true, true, false]);
+        assert_eq!(passed(&results[1]), vec![false, false, false, true, true, true]);
+        assert_eq!(results[0][5].details, "expected length 45 <= 40");
+        assert_eq!(results[1][1].details, "expected does not contain 'password'");
+        assert_eq!(results[1][5].details, "length 31 <= 40");
+
+        assert!(SimpleCheck::parse("min_length", "-1").is_err());
+        assert!(SimpleCheck::parse("ends_with", "x").is_err());
+    }
+}
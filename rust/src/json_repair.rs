@@ -0,0 +1,622 @@
+//! Lenient parsing of almost-valid JSON
+//!
+//! Agents often answer with JSON that a strict parser rejects for purely
+//! syntactic reasons: trailing commas, unquoted or single-quoted keys,
+//! Python literals, prose around the payload or output cut off mid-array.
+//! Repairing those lets a check tell "the structure is wrong" apart from
+//! "the punctuation is sloppy", and the list of fixes records which one
+//! happened.
+//!
+//! Extraction goes one step earlier: given a whole response, it collects
+//! candidate payloads (fenced code blocks and balanced `{...}`/`[...]`
+//! spans), repairs each, and keeps the one that needed the fewest fixes.
+
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use crate::metrics::registry;
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+
+/// Outcome of parsing one response as JSON
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonRepair {
+    /// Compact re-serialization of the parsed value (keys sorted), empty if unparseable
+    pub text: String,
+    pub valid: bool,
+    /// True when the input only parsed after repairs
+    pub repaired: bool,
+    /// Repairs applied, in the order they were first needed
+    pub fixes: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl JsonRepair {
+    /// Check result for the `valid_json` invariant; repaired output passes.
+    pub fn to_check_result(&self) -> CheckResult {
+        let details = if !self.valid {
+            format!(
+                "Invalid JSON: {}",
+                self.error.as_deref().unwrap_or("unrecoverable syntax")
+            )
+        } else if self.repaired {
+            format!("Response is valid JSON after repair ({})", self.fixes.join(", "))
+        } else {
+            "Response is valid JSON".to_string()
+        };
+        CheckResult {
+            check_type: "valid_json".to_string(),
+            passed: self.valid,
+            details,
+            severity: Severity::Error,
+        }
+    }
+}
+
+/// Deepest nesting the lenient parser follows, as serde_json's own limit
+const MAX_DEPTH: usize = 128;
+
+struct LenientParser {
+    chars: Vec<char>,
+    pos: usize,
+    fixes: Vec<String>,
+    /// Objects and arrays currently open
+    depth: usize,
+}
+
+impl LenientParser {
+    fn fix(&mut self, what: &str) {
+        if !self.fixes.iter().any(|f| f == what) {
+            self.fixes.push(what.to_string());
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_whitespace() {
+                self.pos += 1;
+            } else if c == '/' && self.chars.get(self.pos + 1) == Some(&'/') {
+                self.fix("removed comment");
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.skip_whitespace();
+        match self.peek() {
+            None => Err("unexpected end of input".to_string()),
+            Some('{' | '[') if self.depth >= MAX_DEPTH => {
+                Err(format!("nesting deeper than {} levels at {}", MAX_DEPTH, self.pos))
+            }
+            Some('{') => self.nested(Self::object),
+            Some('[') => self.nested(Self::array),
+            Some(q @ ('"' | '\'')) => self.string(q).map(Value::String),
+            Some(c) if c == '-' || c == '.' || c.is_ascii_digit() => self.number(),
+            Some(c) if c.is_alphabetic() || c == '_' => self.literal(),
+            Some(c) => Err(format!("unexpected character '{}' at {}", c, self.pos)),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, String>) -> Result<Value, String> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut map = Map::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => {
+                    self.fix("closed truncated object");
+                    break;
+                }
+                Some('}') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    if matches!(self.peek(), Some('}') | None) {
+                        self.fix("removed trailing comma");
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            let key = match self.peek() {
+                Some(q @ ('"' | '\'')) => self.string(q)?,
+                Some(c) if c.is_alphanumeric() || c == '_' || c == '$' => {
+                    self.fix("quoted bare key");
+                    self.identifier()
+                }
+                Some(c) => return Err(format!("unexpected character '{}' at {}", c, self.pos)),
+                None => unreachable!("end of input handled above"),
+            };
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(':') => self.pos += 1,
+                None => {
+                    self.fix("filled missing value with null");
+                    map.insert(key, Value::Null);
+                    continue;
+                }
+                Some(c) => return Err(format!("expected ':' but found '{}' at {}", c, self.pos)),
+            }
+
+            self.skip_whitespace();
+            let value = if self.peek().is_none() {
+                self.fix("filled missing value with null");
+                Value::Null
+            } else {
+                self.value()?
+            };
+            map.insert(key, value);
+
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',' | '}') | None => {}
+                Some(_) => self.fix("inserted missing comma"),
+            }
+        }
+        Ok(Value::Object(map))
+    }
+
+    fn array(&mut self) -> Result<Value, String> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => {
+                    self.fix("closed truncated array");
+                    break;
+                }
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                Some(',') => {
+                    self.pos += 1;
+                    self.skip_whitespace();
+                    if matches!(self.peek(), Some(']') | None) {
+                        self.fix("removed trailing comma");
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',' | ']') | None => {}
+                Some(_) => self.fix("inserted missing comma"),
+            }
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn string(&mut self, quote: char) -> Result<String, String> {
+        if quote == '\'' {
+            self.fix("converted single quotes");
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                self.fix("closed truncated string");
+                return Ok(out);
+            };
+            self.pos += 1;
+            if c == quote {
+                return Ok(out);
+            }
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            let Some(esc) = self.peek() else {
+                self.fix("closed truncated string");
+                return Ok(out);
+            };
+            self.pos += 1;
+            match esc {
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                'r' => out.push('\r'),
+                'b' => out.push('\u{8}'),
+                'f' => out.push('\u{c}'),
+                'u' => {
+                    let unit = self
+                        .hex4(self.pos)
+                        .ok_or_else(|| format!("invalid unicode escape at {}", self.pos))?;
+                    self.pos += 4;
+                    let ch = match unit {
+                        // A high surrogate combines with a following \uDC00-\uDFFF
+                        0xD800..=0xDBFF => match self.low_surrogate() {
+                            Some(low) => {
+                                self.pos += 6;
+                                char::from_u32(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00))
+                            }
+                            None => None,
+                        },
+                        _ => char::from_u32(unit),
+                    };
+                    out.push(ch.unwrap_or_else(|| {
+                        self.fix("replaced lone surrogate");
+                        char::REPLACEMENT_CHARACTER
+                    }));
+                }
+                other => out.push(other),
+            }
+        }
+    }
+
+    /// Four hex digits at `at` as a UTF-16 code unit.
+    fn hex4(&self, at: usize) -> Option<u32> {
+        let hex: String = self.chars.iter().skip(at).take(4).collect();
+        if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        u32::from_str_radix(&hex, 16).ok()
+    }
+
+    /// The low surrogate of a `\uXXXX` escape at the current position.
+    fn low_surrogate(&self) -> Option<u32> {
+        if self.peek() != Some('\\') || self.chars.get(self.pos + 1) != Some(&'u') {
+            return None;
+        }
+        self.hex4(self.pos + 2).filter(|unit| (0xDC00..=0xDFFF).contains(unit))
+    }
+
+    fn identifier(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '$' || c == '-')
+        {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn literal(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        let word = self.identifier();
+        match word.as_str() {
+            "true" => Ok(Value::Bool(true)),
+            "false" => Ok(Value::Bool(false)),
+            "null" => Ok(Value::Null),
+            "True" | "False" | "None" => {
+                self.fix("converted Python literal");
+                Ok(match word.as_str() {
+                    "True" => Value::Bool(true),
+                    "False" => Value::Bool(false),
+                    _ => Value::Null,
+                })
+            }
+            _ => Err(format!("unexpected literal '{}' at {}", word, start)),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, String> {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.pos += 1;
+        }
+        let mut raw: String = self.chars[start..self.pos].iter().collect();
+        if raw.starts_with('.') || raw.starts_with("-.") {
+            raw = raw.replacen('.', "0.", 1);
+            self.fix("completed number");
+        }
+        // A number cut off mid-exponent or after the point
+        while raw.ends_with(['.', 'e', 'E', '+', '-']) && raw.len() > 1 {
+            raw.pop();
+            self.fix("completed number");
+        }
+        serde_json::from_str::<Number>(&raw)
+            .map(Value::Number)
+            .map_err(|_| format!("invalid number '{}' at {}", raw, start))
+    }
+}
+
+/// Parse `text` as JSON, repairing common syntax sloppiness if strict
+/// parsing fails.
+pub fn repair(text: &str) -> JsonRepair {
+    if let Ok(value) = serde_json::from_str::<Value>(text) {
+        return JsonRepair {
+            text: value.to_string(),
+            valid: true,
+            repaired: false,
+            fixes: Vec::new(),
+            error: None,
+        };
+    }
+
+    let mut parser = LenientParser {
+        chars: text.chars().collect(),
+        pos: 0,
+        fixes: Vec::new(),
+        depth: 0,
+    };
+
+    // Skip prose or a markdown fence in front of the payload
+    parser.skip_whitespace();
+    if !matches!(parser.peek(), Some('{' | '[')) {
+        if let Some(start) = parser.chars.iter().position(|&c| c == '{' || c == '[') {
+            parser.pos = start;
+            parser.fix("skipped leading text");
+        }
+    }
+
+    let result = parser.value();
+    parser.skip_whitespace();
+    if result.is_ok() && parser.peek().is_some() {
+        parser.fix("ignored trailing text");
+    }
+
+    match result {
+        Ok(value) => JsonRepair {
+            text: value.to_string(),
+            valid: true,
+            repaired: true,
+            fixes: parser.fixes,
+            error: None,
+        },
+        Err(e) => JsonRepair {
+            text: String::new(),
+            valid: false,
+            repaired: false,
+            fixes: parser.fixes,
+            error: Some(e),
+        },
+    }
+}
+
+/// Best JSON payload found in a response
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JsonExtraction {
+    /// Compact serialization of the extracted value, empty if none was found
+    pub text: String,
+    pub found: bool,
+    /// "whole", "code_fence" or "embedded"
+    pub source: String,
+    pub repaired: bool,
+    pub fixes: Vec<String>,
+}
+
+/// Contents of markdown code fences, in order.
+fn fenced_blocks(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut rest = text;
+    while let Some(open) = rest.find("```") {
+        let after = &rest[open + 3..];
+        // Skip the info string ("json", "js", ...) up to the end of the line
+        let body_start = after.find('\n').map_or(after.len(), |i| i + 1);
+        let body = &after[body_start..];
+        match body.find("```") {
+            Some(close) => {
+                out.push(&body[..close]);
+                rest = &body[close + 3..];
+            }
+            None => {
+                out.push(body);
+                break;
+            }
+        }
+    }
+    out
+}
+
+/// Top-level balanced `{...}` / `[...]` spans; an unclosed span runs to the end.
+fn bracketed_spans(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'{' && bytes[i] != b'[' {
+            i += 1;
+            continue;
+        }
+        let (mut depth, mut in_string, mut escaped) = (0usize, false, false);
+        let mut end = bytes.len();
+        for (j, &b) in bytes.iter().enumerate().skip(i) {
+            if in_string {
+                match b {
+                    _ if escaped => escaped = false,
+                    b'\\' => escaped = true,
+                    b'"' => in_string = false,
+                    _ => {}
+                }
+                continue;
+            }
+            match b {
+                b'"' => in_string = true,
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        end = j + 1;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        out.push(&text[i..end]);
+        i = end;
+    }
+    out
+}
+
+/// Find and parse the most plausible JSON payload in `text`.
+///
+/// Candidates that parse strictly win over repaired ones; among repaired
+/// candidates the one needing the fewest fixes wins, then the longest.
+pub fn extract(text: &str) -> JsonExtraction {
+    let mut candidates: Vec<(&str, &str)> = vec![("whole", text.trim())];
+    candidates.extend(fenced_blocks(text).into_iter().map(|b| ("code_fence", b.trim())));
+    candidates.extend(bracketed_spans(text).into_iter().map(|s| ("embedded", s)));
+
+    let best = candidates
+        .into_iter()
+        .filter(|(_, c)| !c.is_empty())
+        .map(|(source, c)| (source, c.len(), repair(c)))
+        .filter(|(_, _, r)| r.valid)
+        .min_by(|a, b| {
+            (a.2.repaired, a.2.fixes.len(), std::cmp::Reverse(a.1))
+                .cmp(&(b.2.repaired, b.2.fixes.len(), std::cmp::Reverse(b.1)))
+        });
+    match best {
+        Some((source, _, r)) => JsonExtraction {
+            text: r.text,
+            found: true,
+            source: source.to_string(),
+            repaired: r.repaired,
+            fixes: r.fixes,
+        },
+        None => JsonExtraction {
+            text: String::new(),
+            found: false,
+            source: String::new(),
+            repaired: false,
+            fixes: Vec::new(),
+        },
+    }
+}
+
+/// Locate the JSON payload in an agent response and parse it leniently.
+///
+/// Looks at the whole response, fenced code blocks and embedded
+/// `{...}`/`[...]` spans; `source` says where the payload came from and
+/// `fixes` what had to be repaired.
+#[pyfunction]
+pub fn extract_json(output: &str) -> JsonExtraction {
+    extract(output)
+}
+
+/// Parse an agent response as JSON, leniently.
+///
+/// `repaired` tells whether syntax fixes were needed and `fixes` lists them,
+/// so JSON checks can report syntax sloppiness separately from responses
+/// that are not JSON at all.
+#[pyfunction]
+pub fn repair_json(text: &str) -> JsonRepair {
+    let result = repair(text);
+    if result.repaired {
+        registry().increment("json.repaired", 1);
+    } else if !result.valid {
+        registry().increment("json.invalid", 1);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strict_json_is_not_repaired() {
+        let r = repair(r#"{"b": [1, 2.5, null], "a": "x"}"#);
+        assert!(r.valid && !r.repaired);
+        assert_eq!(r.text, r#"{"a":"x","b":[1,2.5,null]}"#);
+        assert!(r.to_check_result().passed);
+    }
+
+    #[test]
+    fn test_common_sloppiness_is_repaired() {
+        let r = repair("Sure! Here it is:\n```json\n{name: 'Ada', tags: ['a', 'b',], ok: True,}\n```");
+        assert!(r.valid && r.repaired);
+        assert_eq!(r.text, r#"{"name":"Ada","ok":true,"tags":["a","b"]}"#);
+        assert_eq!(
+            r.fixes,
+            vec![
+                "skipped leading text",
+                "quoted bare key",
+                "converted single quotes",
+                "removed trailing comma",
+                "converted Python literal",
+                "ignored trailing text",
+            ]
+        );
+        assert!(r.to_check_result().details.contains("after repair"));
+    }
+
+    #[test]
+    fn test_truncated_output_is_closed() {
+        let r = repair(r#"{"items": [{"id": 1}, {"id": 2, "name": "tru"#);
+        assert!(r.valid && r.repaired);
+        assert_eq!(r.text, r#"{"items":[{"id":1},{"id":2,"name":"tru"}]}"#);
+        assert!(r.fixes.contains(&"closed truncated string".to_string()));
+        assert!(r.fixes.contains(&"closed truncated array".to_string()));
+
+        assert_eq!(repair(r#"{"a": 1, "b":"#).text, r#"{"a":1,"b":null}"#);
+        assert_eq!(repair("[1, 2.").text, "[1,2]");
+    }
+
+    #[test]
+    fn test_extract_prefers_clean_candidates() {
+        let e = extract("Here you go:\n```json\n{\"a\": 1}\n```\nLet me know {if} you need more.");
+        assert!(e.found && !e.repaired);
+        assert_eq!((e.source.as_str(), e.text.as_str()), ("code_fence", r#"{"a":1}"#));
+
+        let e = extract(r#"The result is {"ok": true, "items": ["x", "y"]} as requested."#);
+        assert_eq!((e.source.as_str(), e.text.as_str()), ("embedded", r#"{"items":["x","y"],"ok":true}"#));
+
+        let e = extract("Result: {status: 'done', count: 2,}");
+        assert!(e.repaired);
+        assert_eq!(e.text, r#"{"count":2,"status":"done"}"#);
+        assert!(e.fixes.contains(&"quoted bare key".to_string()));
+
+        assert_eq!(extract(" [1, 2] ").source, "whole");
+        assert_eq!(bracketed_spans(r#"a {"b": "}"} c [1"#), vec![r#"{"b": "}"}"#, "[1"]);
+        assert!(!extract("I cannot help with that.").found);
+    }
+
+    #[test]
+    fn test_unrecoverable_input() {
+        let r = repair("I cannot answer that.");
+        assert!(!r.valid);
+        assert!(r.error.is_some());
+        assert!(!r.to_check_result().passed);
+
+        assert!(!repair(r#"{"a" 1}"#).valid);
+    }
+
+    #[test]
+    fn test_depth_limit_and_surrogate_pairs() {
+        let deep = format!("{}1", "[".repeat(100_000));
+        let r = repair(&deep);
+        assert!(!r.valid);
+        assert!(r.error.unwrap().contains("nesting deeper than 128"));
+        assert!(!extract(&deep).found);
+        assert!(repair(&format!("{}1", "[".repeat(MAX_DEPTH))).valid);
+
+        let r = repair(r"{'emoji': '\ud83d\ude00'}");
+        assert_eq!(r.text, "{\"emoji\":\"\u{1F600}\"}");
+        let r = repair(r"{'lone': '\ud83d!'}");
+        assert_eq!(r.text, "{\"lone\":\"\u{FFFD}!\"}");
+        assert!(r.fixes.contains(&"replaced lone surrogate".to_string()));
+    }
+}
@@ -0,0 +1,614 @@
+//! JSON Schema validation of structured agent outputs
+//!
+//! Validates against the draft 2020-12 assertion vocabulary: `type`,
+//! `enum`, `const`, numeric bounds and `multipleOf`, string length and
+//! `pattern`, `items`/`prefixItems`/`contains` with their counts,
+//! `uniqueItems`, `properties`/`patternProperties`/`additionalProperties`,
+//! `required`, `propertyNames`, property counts, `dependentRequired`,
+//! `dependentSchemas`, `allOf`/`anyOf`/`oneOf`/`not`, `if`/`then`/`else`
+//! and local `$ref`s (`#`, `#/$defs/...` and `#name` for an `$anchor`).
+//! Annotation keywords such as `format` and `title` are ignored. Keywords
+//! that depend on annotation collection or dynamic scope
+//! (`unevaluatedProperties`, `unevaluatedItems`, `$dynamicRef` and
+//! `$dynamicAnchor`) are rejected when the schema is compiled rather than
+//! silently skipped, so a schema never validates more loosely than its
+//! author intended. Every violation is reported with the JSON pointer of
+//! the offending value, so check details name the field that is wrong.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use regex::Regex;
+use serde_json::{Map, Value};
+
+use crate::json_repair::repair;
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+
+/// Guard against `$ref` cycles that never descend into the instance
+const MAX_DEPTH: usize = 128;
+
+/// Violations listed in check details before the rest are summarized
+const MAX_REPORTED: usize = 10;
+
+/// Keywords this validator does not implement
+const UNSUPPORTED_KEYWORDS: [&str; 4] = ["unevaluatedProperties", "unevaluatedItems", "$dynamicRef", "$dynamicAnchor"];
+
+/// Keywords whose value maps names to subschemas
+const SCHEMA_MAPS: [&str; 5] = ["properties", "patternProperties", "$defs", "definitions", "dependentSchemas"];
+
+/// Keywords whose value is instance data, not a subschema
+const DATA_KEYWORDS: [&str; 4] = ["enum", "const", "default", "examples"];
+
+/// A compiled schema
+#[derive(Debug, Clone)]
+pub struct JsonSchema {
+    root: Value,
+    patterns: HashMap<String, Regex>,
+    /// `$anchor` name -> JSON pointer of the schema declaring it
+    anchors: HashMap<String, String>,
+}
+
+fn type_name(v: &Value) -> &'static str {
+    match v {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn has_type(v: &Value, name: &str) -> bool {
+    match (name, v) {
+        ("integer", Value::Number(n)) => n.as_f64().is_some_and(|f| f.fract() == 0.0),
+        ("number", Value::Number(_)) => true,
+        _ => type_name(v) == name,
+    }
+}
+
+fn child(path: &str, key: &str) -> String {
+    format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"))
+}
+
+fn at(path: &str) -> &str {
+    if path.is_empty() {
+        "/"
+    } else {
+        path
+    }
+}
+
+/// Call `visit` on every subschema object of `node` with its JSON pointer,
+/// skipping instance data under `enum`, `const`, `default` and `examples`.
+fn walk_schemas<F>(node: &Value, pointer: &str, visit: &mut F) -> Result<(), String>
+where
+    F: FnMut(&Map<String, Value>, &str) -> Result<(), String>,
+{
+    let Value::Object(map) = node else {
+        return Ok(());
+    };
+    visit(map, pointer)?;
+    for (key, value) in map {
+        let at_key = child(pointer, key);
+        match (key.as_str(), value) {
+            (k, _) if DATA_KEYWORDS.contains(&k) => {}
+            (k, Value::Object(schemas)) if SCHEMA_MAPS.contains(&k) => {
+                for (name, schema) in schemas {
+                    walk_schemas(schema, &child(&at_key, name), visit)?;
+                }
+            }
+            (_, Value::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    walk_schemas(item, &child(&at_key, &i.to_string()), visit)?;
+                }
+            }
+            _ => walk_schemas(value, &at_key, visit)?,
+        }
+    }
+    Ok(())
+}
+
+impl JsonSchema {
+    /// Compile a schema: precompile every `pattern`, index every `$anchor`,
+    /// check that every `$ref` resolves and reject unsupported keywords.
+    pub fn compile(schema: Value) -> Result<Self, String> {
+        if !(schema.is_object() || schema.is_boolean()) {
+            return Err("a schema must be an object or a boolean".to_string());
+        }
+        let mut patterns = HashMap::new();
+        let mut anchors = HashMap::new();
+        walk_schemas(&schema, "", &mut |map, pointer| {
+            if let Some(keyword) = UNSUPPORTED_KEYWORDS.iter().find(|k| map.contains_key(**k)) {
+                return Err(format!("unsupported keyword '{}' at {}", keyword, at(pointer)));
+            }
+            if let Some(p) = map.get("pattern").and_then(Value::as_str) {
+                let re = Regex::new(p).map_err(|e| format!("invalid pattern '{}': {}", p, e))?;
+                patterns.insert(p.to_string(), re);
+            }
+            if let Some(Value::Object(pp)) = map.get("patternProperties") {
+                for p in pp.keys() {
+                    let re = Regex::new(p).map_err(|e| format!("invalid pattern '{}': {}", p, e))?;
+                    patterns.insert(p.clone(), re);
+                }
+            }
+            if let Some(anchor) = map.get("$anchor").and_then(Value::as_str) {
+                if anchors.insert(anchor.to_string(), pointer.to_string()).is_some() {
+                    return Err(format!("duplicate $anchor '{}'", anchor));
+                }
+            }
+            Ok(())
+        })?;
+        let compiled = Self {
+            root: schema,
+            patterns,
+            anchors,
+        };
+        walk_schemas(&compiled.root, "", &mut |map, _| match map.get("$ref").and_then(Value::as_str) {
+            Some(r) => compiled.resolve(r).map(|_| ()),
+            None => Ok(()),
+        })?;
+        Ok(compiled)
+    }
+
+    /// Resolve a local `$ref`: "#", a JSON pointer fragment or an anchor.
+    fn resolve(&self, reference: &str) -> Result<&Value, String> {
+        let fragment = reference
+            .strip_prefix('#')
+            .ok_or_else(|| format!("only local $ref values are supported, got '{}'", reference))?;
+        let pointer = if fragment.is_empty() || fragment.starts_with('/') {
+            fragment
+        } else {
+            self.anchors
+                .get(fragment)
+                .ok_or_else(|| format!("$ref '{}' names no $anchor", reference))?
+        };
+        self.root
+            .pointer(pointer)
+            .ok_or_else(|| format!("$ref '{}' does not resolve", reference))
+    }
+
+    pub fn parse(schema: &str) -> Result<Self, String> {
+        Self::compile(serde_json::from_str(schema).map_err(|e| format!("schema is not valid JSON: {}", e))?)
+    }
+
+    /// Every violation of the schema by `instance`, as "pointer: message".
+    pub fn validate(&self, instance: &Value) -> Vec<String> {
+        let mut errors = Vec::new();
+        self.check(&self.root, instance, "", 0, &mut errors);
+        errors
+    }
+
+    fn is_valid(&self, schema: &Value, instance: &Value, path: &str, depth: usize) -> bool {
+        let mut errors = Vec::new();
+        self.check(schema, instance, path, depth, &mut errors);
+        errors.is_empty()
+    }
+
+    fn check(&self, schema: &Value, v: &Value, path: &str, depth: usize, errors: &mut Vec<String>) {
+        let s = match schema {
+            Value::Bool(true) => return,
+            Value::Bool(false) => return errors.push(format!("{}: no value is allowed here", at(path))),
+            Value::Object(s) => s,
+            _ => return,
+        };
+        if depth > MAX_DEPTH {
+            return errors.push(format!("{}: schema recursion too deep", at(path)));
+        }
+        if let Some(r) = s.get("$ref").and_then(Value::as_str) {
+            if let Ok(target) = self.resolve(r) {
+                self.check(target, v, path, depth + 1, errors);
+            }
+        }
+        // Violations at this path itself; nested ones go straight to `errors`
+        let mut here = Vec::new();
+        let mut fail = |msg: String| here.push(msg);
+
+        match s.get("type") {
+            Some(Value::String(t)) if !has_type(v, t) => fail(format!("expected {}, got {}", t, type_name(v))),
+            Some(Value::Array(ts)) if !ts.iter().filter_map(Value::as_str).any(|t| has_type(v, t)) => {
+                let names: Vec<&str> = ts.iter().filter_map(Value::as_str).collect();
+                fail(format!("expected one of {}, got {}", names.join(", "), type_name(v)))
+            }
+            _ => {}
+        }
+        if let Some(Value::Array(options)) = s.get("enum") {
+            if !options.contains(v) {
+                fail(format!("{} is not one of the allowed values", v));
+            }
+        }
+        if let Some(c) = s.get("const") {
+            if c != v {
+                fail(format!("expected {}, got {}", c, v));
+            }
+        }
+
+        match v {
+            Value::Number(n) => {
+                let x = n.as_f64().unwrap_or(f64::NAN);
+                let bound = |k: &str| s.get(k).and_then(Value::as_f64);
+                if let Some(m) = bound("minimum").filter(|&m| x < m) {
+                    fail(format!("{} is less than the minimum {}", n, m));
+                }
+                if let Some(m) = bound("maximum").filter(|&m| x > m) {
+                    fail(format!("{} is greater than the maximum {}", n, m));
+                }
+                if let Some(m) = bound("exclusiveMinimum").filter(|&m| x <= m) {
+                    fail(format!("{} must be greater than {}", n, m));
+                }
+                if let Some(m) = bound("exclusiveMaximum").filter(|&m| x >= m) {
+                    fail(format!("{} must be less than {}", n, m));
+                }
+                if let Some(m) = bound("multipleOf").filter(|&m| m > 0.0) {
+                    let q = x / m;
+                    if (q - q.round()).abs() > 1e-9 {
+                        fail(format!("{} is not a multiple of {}", n, m));
+                    }
+                }
+            }
+            Value::String(text) => {
+                let len = text.chars().count();
+                if let Some(m) = s.get("minLength").and_then(Value::as_u64).filter(|&m| (len as u64) < m) {
+                    fail(format!("length {} is shorter than {}", len, m));
+                }
+                if let Some(m) = s.get("maxLength").and_then(Value::as_u64).filter(|&m| (len as u64) > m) {
+                    fail(format!("length {} is longer than {}", len, m));
+                }
+                if let Some(p) = s.get("pattern").and_then(Value::as_str) {
+                    if !self.patterns[p].is_match(text) {
+                        fail(format!("'{}' does not match /{}/", text, p));
+                    }
+                }
+            }
+            Value::Array(items) => self.check_array(s, items, path, depth, errors),
+            Value::Object(fields) => self.check_object(s, fields, path, depth, errors),
+            _ => {}
+        }
+        errors.extend(here.into_iter().map(|msg| format!("{}: {}", at(path), msg)));
+
+        self.check_combinators(s, v, path, depth, errors);
+    }
+
+    fn check_array(&self, s: &Map<String, Value>, items: &[Value], path: &str, depth: usize, errors: &mut Vec<String>) {
+        let count = items.len() as u64;
+        if let Some(m) = s.get("minItems").and_then(Value::as_u64).filter(|&m| count < m) {
+            errors.push(format!("{}: {} items, at least {} required", at(path), count, m));
+        }
+        if let Some(m) = s.get("maxItems").and_then(Value::as_u64).filter(|&m| count > m) {
+            errors.push(format!("{}: {} items, at most {} allowed", at(path), count, m));
+        }
+        if s.get("uniqueItems") == Some(&Value::Bool(true)) {
+            for (i, a) in items.iter().enumerate() {
+                if let Some(j) = items[..i].iter().position(|b| b == a) {
+                    errors.push(format!("{}: items {} and {} are equal", at(path), j, i));
+                    break;
+                }
+            }
+        }
+        let prefix = match s.get("prefixItems") {
+            Some(Value::Array(p)) => p.as_slice(),
+            _ => &[],
+        };
+        for (i, item) in items.iter().enumerate() {
+            let item_path = child(path, &i.to_string());
+            match prefix.get(i) {
+                Some(p) => self.check(p, item, &item_path, depth + 1, errors),
+                None => {
+                    if let Some(schema) = s.get("items") {
+                        self.check(schema, item, &item_path, depth + 1, errors);
+                    }
+                }
+            }
+        }
+        if let Some(c) = s.get("contains") {
+            let found = items
+                .iter()
+                .enumerate()
+                .filter(|(i, item)| self.is_valid(c, item, &child(path, &i.to_string()), depth + 1))
+                .count() as u64;
+            let min = s.get("minContains").and_then(Value::as_u64).unwrap_or(1);
+            if found < min {
+                errors.push(format!("{}: {} items match 'contains', at least {} required", at(path), found, min));
+            }
+            if let Some(max) = s.get("maxContains").and_then(Value::as_u64).filter(|&m| found > m) {
+                errors.push(format!("{}: {} items match 'contains', at most {} allowed", at(path), found, max));
+            }
+        }
+    }
+
+    fn check_object(
+        &self,
+        s: &Map<String, Value>,
+        fields: &Map<String, Value>,
+        path: &str,
+        depth: usize,
+        errors: &mut Vec<String>,
+    ) {
+        if let Some(Value::Array(required)) = s.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    errors.push(format!("{}: missing required property '{}'", at(path), name));
+                }
+            }
+        }
+        let count = fields.len() as u64;
+        if let Some(m) = s.get("minProperties").and_then(Value::as_u64).filter(|&m| count < m) {
+            errors.push(format!("{}: {} properties, at least {} required", at(path), count, m));
+        }
+        if let Some(m) = s.get("maxProperties").and_then(Value::as_u64).filter(|&m| count > m) {
+            errors.push(format!("{}: {} properties, at most {} allowed", at(path), count, m));
+        }
+        if let Some(Value::Object(deps)) = s.get("dependentRequired") {
+            for (name, needs) in deps {
+                if !fields.contains_key(name) {
+                    continue;
+                }
+                for need in needs.as_array().into_iter().flatten().filter_map(Value::as_str) {
+                    if !fields.contains_key(need) {
+                        errors.push(format!("{}: property '{}' requires '{}'", at(path), name, need));
+                    }
+                }
+            }
+        }
+
+        let properties = s.get("properties").and_then(Value::as_object);
+        let pattern_properties = s.get("patternProperties").and_then(Value::as_object);
+        for (name, value) in fields {
+            let field_path = child(path, name);
+            if let Some(names) = s.get("propertyNames") {
+                if !self.is_valid(names, &Value::String(name.clone()), &field_path, depth + 1) {
+                    errors.push(format!("{}: property name '{}' is not allowed", at(path), name));
+                }
+            }
+            let mut matched = false;
+            if let Some(schema) = properties.and_then(|p| p.get(name)) {
+                matched = true;
+                self.check(schema, value, &field_path, depth + 1, errors);
+            }
+            for (pattern, schema) in pattern_properties.into_iter().flatten() {
+                if self.patterns[pattern].is_match(name) {
+                    matched = true;
+                    self.check(schema, value, &field_path, depth + 1, errors);
+                }
+            }
+            if !matched {
+                match s.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        errors.push(format!("{}: unexpected property '{}'", at(path), name));
+                    }
+                    Some(schema) => self.check(schema, value, &field_path, depth + 1, errors),
+                    None => {}
+                }
+            }
+        }
+    }
+
+    fn check_combinators(&self, s: &Map<String, Value>, v: &Value, path: &str, depth: usize, errors: &mut Vec<String>) {
+        let list = |k: &str| s.get(k).and_then(Value::as_array);
+        for schema in list("allOf").into_iter().flatten() {
+            self.check(schema, v, path, depth + 1, errors);
+        }
+        if let Some(options) = list("anyOf") {
+            if !options.iter().any(|o| self.is_valid(o, v, path, depth + 1)) {
+                errors.push(format!("{}: does not match any schema in anyOf", at(path)));
+            }
+        }
+        if let Some(options) = list("oneOf") {
+            let n = options.iter().filter(|o| self.is_valid(o, v, path, depth + 1)).count();
+            if n != 1 {
+                errors.push(format!("{}: matches {} schemas in oneOf, expected exactly 1", at(path), n));
+            }
+        }
+        if let Some(not) = s.get("not") {
+            if self.is_valid(not, v, path, depth + 1) {
+                errors.push(format!("{}: must not match the 'not' schema", at(path)));
+            }
+        }
+        if let (Some(Value::Object(deps)), Value::Object(fields)) = (s.get("dependentSchemas"), v) {
+            for (name, schema) in deps.iter().filter(|(name, _)| fields.contains_key(*name)) {
+                let before = errors.len();
+                self.check(schema, v, path, depth + 1, errors);
+                if errors.len() > before {
+                    errors.push(format!("{}: property '{}' requires its dependent schema", at(path), name));
+                }
+            }
+        }
+        if let Some(cond) = s.get("if") {
+            let branch = if self.is_valid(cond, v, path, depth + 1) { "then" } else { "else" };
+            if let Some(schema) = s.get(branch) {
+                self.check(schema, v, path, depth + 1, errors);
+            }
+        }
+    }
+
+    /// Check result for one agent output.
+    ///
+    /// With `lenient`, syntactically sloppy JSON is repaired before it is
+    /// validated, so the check judges structure rather than punctuation.
+    pub fn check_output(&self, output: &str, lenient: bool) -> CheckResult {
+        let parsed = if lenient {
+            let r = repair(output);
+            match r.valid {
+                true => serde_json::from_str::<Value>(&r.text).map_err(|e| e.to_string()),
+                false => Err(r.error.unwrap_or_else(|| "unrecoverable syntax".to_string())),
+            }
+        } else {
+            serde_json::from_str::<Value>(output).map_err(|e| e.to_string())
+        };
+        let (passed, details) = match parsed {
+            Err(e) => (false, format!("Invalid JSON: {}", e)),
+            Ok(value) => {
+                let errors = self.validate(&value);
+                if errors.is_empty() {
+                    (true, "Response matches the schema".to_string())
+                } else {
+                    let mut details = errors[..errors.len().min(MAX_REPORTED)].join("; ");
+                    if errors.len() > MAX_REPORTED {
+                        details.push_str(&format!("; and {} more", errors.len() - MAX_REPORTED));
+                    }
+                    (false, details)
+                }
+            }
+        };
+        CheckResult {
+            check_type: "json_schema".to_string(),
+            passed,
+            details,
+            severity: Severity::Error,
+        }
+    }
+}
+
+/// A JSON Schema (draft 2020-12) compiled for checking agent outputs.
+///
+/// `lenient` repairs almost-valid JSON (see `repair_json`) before
+/// validating it.
+#[pyclass(name = "JsonSchemaCheck")]
+pub struct PyJsonSchemaCheck {
+    inner: JsonSchema,
+    lenient: bool,
+}
+
+#[pymethods]
+impl PyJsonSchemaCheck {
+    #[new]
+    #[pyo3(signature = (schema, lenient = false))]
+    fn new(schema: &str, lenient: bool) -> PyResult<Self> {
+        JsonSchema::parse(schema)
+            .map(|inner| Self { inner, lenient })
+            .map_err(PyValueError::new_err)
+    }
+
+    /// Violations for one JSON document, as "pointer: message" strings.
+    fn validate(&self, instance: &str) -> PyResult<Vec<String>> {
+        let value: Value = serde_json::from_str(instance).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(self.inner.validate(&value))
+    }
+
+    /// One `json_schema` check result per agent output.
+    fn check(&self, py: Python<'_>, outputs: Vec<String>) -> Vec<CheckResult> {
+        py.allow_threads(|| {
+            outputs
+                .par_iter()
+                .map(|o| self.inner.check_output(o, self.lenient))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn order_schema() -> JsonSchema {
+        JsonSchema::compile(json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "type": "object",
+            "required": ["id", "status", "items"],
+            "additionalProperties": false,
+            "properties": {
+                "id": {"type": "string", "pattern": "^ORD-\\d+$"},
+                "status": {"enum": ["open", "shipped"]},
+                "total": {"type": "number", "minimum": 0},
+                "items": {"type": "array", "minItems": 1, "items": {"$ref": "#/$defs/item"}}
+            },
+            "$defs": {
+                "item": {
+                    "type": "object",
+                    "required": ["sku", "qty"],
+                    "properties": {"sku": {"type": "string"}, "qty": {"type": "integer", "exclusiveMinimum": 0}}
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_valid_and_field_errors() {
+        let schema = order_schema();
+        let ok = json!({"id": "ORD-1", "status": "open", "items": [{"sku": "A", "qty": 2}]});
+        assert!(schema.validate(&ok).is_empty());
+
+        let bad = json!({"id": "1", "status": "lost", "total": -3, "items": [{"sku": 7, "qty": 0}], "note": "x"});
+        let errors = schema.validate(&bad);
+        for expected in [
+            "/id: '1' does not match /^ORD-\\d+$/",
+            "/status: \"lost\" is not one of the allowed values",
+            "/total: -3 is less than the minimum 0",
+            "/items/0/sku: expected string, got integer",
+            "/items/0/qty: 0 must be greater than 0",
+            "/: unexpected property 'note'",
+        ] {
+            assert!(errors.iter().any(|e| e == expected), "{} not in {:?}", expected, errors);
+        }
+        assert_eq!(schema.validate(&json!({"status": "open"})).len(), 2);
+    }
+
+    #[test]
+    fn test_combinators_and_conditionals() {
+        let schema = JsonSchema::compile(json!({
+            "oneOf": [{"type": "integer"}, {"type": "number", "multipleOf": 0.5}],
+            "not": {"const": 3}
+        }))
+        .unwrap();
+        assert!(schema.validate(&json!(2.5)).is_empty());
+        assert!(!schema.validate(&json!(2)).is_empty()); // matches both branches
+        assert!(!schema.validate(&json!(3)).is_empty());
+
+        let cond = JsonSchema::compile(json!({
+            "if": {"properties": {"kind": {"const": "refund"}}},
+            "then": {"required": ["amount"]},
+            "else": {"maxProperties": 1}
+        }))
+        .unwrap();
+        assert_eq!(cond.validate(&json!({"kind": "refund"})), vec!["/: missing required property 'amount'"]);
+        assert!(cond.validate(&json!({"kind": "info"})).is_empty());
+    }
+
+    #[test]
+    fn test_check_output_and_compile_errors() {
+        let schema = order_schema();
+        let sloppy = "```json\n{id: 'ORD-9', status: 'shipped', items: [{sku: 'B', qty: 1,}],}\n```";
+        assert!(!schema.check_output(sloppy, false).passed);
+        let lenient = schema.check_output(sloppy, true);
+        assert!(lenient.passed, "{}", lenient.details);
+        assert_eq!(lenient.check_type, "json_schema");
+        assert!(schema.check_output("no json here", true).details.starts_with("Invalid JSON"));
+
+        assert!(JsonSchema::compile(json!({"$ref": "#/$defs/missing"})).is_err());
+        assert!(JsonSchema::compile(json!({"pattern": "("})).is_err());
+        assert!(JsonSchema::compile(json!({"$ref": "https://example.com/s.json"})).is_err());
+        assert!(JsonSchema::compile(json!(3)).is_err());
+    }
+
+    #[test]
+    fn test_anchors_dependent_schemas_and_unsupported_keywords() {
+        let schema = JsonSchema::compile(json!({
+            "properties": {"card": {"$ref": "#card"}},
+            "dependentSchemas": {"card": {"required": ["billing_address"]}},
+            "$defs": {"card": {"$anchor": "card", "type": "string", "minLength": 12}}
+        }))
+        .unwrap();
+        assert!(schema.validate(&json!({"card": "4111111111111111", "billing_address": "x"})).is_empty());
+        assert!(schema.validate(&json!({"other": 1})).is_empty());
+        let errors = schema.validate(&json!({"card": "4111"}));
+        assert!(errors.contains(&"/card: length 4 is shorter than 12".to_string()), "{:?}", errors);
+        assert!(errors.contains(&"/: missing required property 'billing_address'".to_string()));
+        assert!(errors.iter().any(|e| e.contains("dependent schema")));
+        assert!(JsonSchema::compile(json!({"$ref": "#nowhere"})).unwrap_err().contains("$anchor"));
+
+        for keyword in ["unevaluatedProperties", "unevaluatedItems"] {
+            let nested = json!({"properties": {"meta": {keyword: false}}});
+            let err = JsonSchema::compile(nested).unwrap_err();
+            assert_eq!(err, format!("unsupported keyword '{}' at /properties/meta", keyword));
+        }
+        // Property names and enum values are not keywords
+        let named = json!({"properties": {"unevaluatedItems": {"type": "string"}}, "enum": [{"$dynamicRef": 1}]});
+        assert!(JsonSchema::compile(named).is_ok());
+    }
+}
@@ -0,0 +1,204 @@
+//! JUnit XML reports
+//!
+//! CI systems (Jenkins, GitLab, Buildkite) render JUnit XML natively in
+//! their test tabs. A run becomes one `<testsuite>` per mutation type,
+//! in order of first appearance, with one `<testcase>` per mutation named
+//! by its type and position in the run. Failed mutations carry a
+//! `<failure>` listing their failed checks with severity and details, and
+//! a result's tags become testcase properties.
+//!
+//! Check details quote model output, so text is escaped and characters
+//! XML 1.0 cannot represent are dropped.
+
+use std::path::Path;
+
+use pyo3::exceptions::PyIOError;
+use pyo3::prelude::*;
+
+use crate::scoring::MutationResult;
+
+/// `text` escaped for an XML attribute or text node.
+pub fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' || c == '\u{FFFE}' || c == '\u{FFFF}' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn seconds(latency_ms: f64) -> String {
+    format!("{:.3}", latency_ms.max(0.0) / 1000.0)
+}
+
+fn write_testcase(xml: &mut String, index: usize, result: &MutationResult) {
+    xml.push_str(&format!(
+        "    <testcase name=\"{}[{}]\" classname=\"{}\" time=\"{}\"",
+        xml_escape(&result.mutation_type),
+        index,
+        xml_escape(&result.mutation_type),
+        seconds(result.latency_ms)
+    ));
+    if result.passed && result.tags.is_empty() {
+        xml.push_str("/>\n");
+        return;
+    }
+    xml.push_str(">\n");
+    if !result.tags.is_empty() {
+        xml.push_str("      <properties>\n");
+        for (key, value) in &result.tags {
+            xml.push_str(&format!(
+                "        <property name=\"{}\" value=\"{}\"/>\n",
+                xml_escape(key),
+                xml_escape(value)
+            ));
+        }
+        xml.push_str("      </properties>\n");
+    }
+    if !result.passed {
+        let failed: Vec<_> = result.checks.iter().filter(|c| !c.passed).collect();
+        let (message, kind) = match failed.first() {
+            Some(first) => (
+                format!("{} of {} checks failed", failed.len(), result.checks.len()),
+                first.check_type.as_str(),
+            ),
+            None => ("mutation failed".to_string(), "failure"),
+        };
+        xml.push_str(&format!(
+            "      <failure message=\"{}\" type=\"{}\">",
+            xml_escape(&message),
+            xml_escape(kind)
+        ));
+        for check in &failed {
+            xml.push_str(&format!(
+                "\n[{}] {}: {}",
+                check.severity.name(),
+                xml_escape(&check.check_type),
+                xml_escape(&check.details)
+            ));
+        }
+        xml.push_str("</failure>\n");
+    }
+    xml.push_str("    </testcase>\n");
+}
+
+/// JUnit XML document for a run's results under `suite_name`.
+pub fn render_junit(results: &[MutationResult], suite_name: &str) -> String {
+    // Mutation types in order of first appearance, with their result indices
+    let mut suites: Vec<(&str, Vec<usize>)> = Vec::new();
+    for (i, result) in results.iter().enumerate() {
+        match suites.iter_mut().find(|(t, _)| *t == result.mutation_type) {
+            Some((_, members)) => members.push(i),
+            None => suites.push((&result.mutation_type, vec![i])),
+        }
+    }
+    let failures = |members: &[usize]| members.iter().filter(|&&i| !results[i].passed).count();
+    let time = |members: &[usize]| seconds(members.iter().map(|&i| results[i].latency_ms.max(0.0)).sum());
+    let all: Vec<usize> = (0..results.len()).collect();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" time=\"{}\">\n",
+        xml_escape(suite_name),
+        results.len(),
+        failures(&all),
+        time(&all)
+    ));
+    for (mutation_type, members) in &suites {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}.{}\" tests=\"{}\" failures=\"{}\" errors=\"0\" skipped=\"0\" time=\"{}\">\n",
+            xml_escape(suite_name),
+            xml_escape(mutation_type),
+            members.len(),
+            failures(members),
+            time(members)
+        ));
+        for &i in members {
+            write_testcase(&mut xml, i, &results[i]);
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Write `render_junit` to `path`.
+pub fn write_junit(path: &Path, results: &[MutationResult], suite_name: &str) -> std::io::Result<()> {
+    std::fs::write(path, render_junit(results, suite_name))
+}
+
+/// Write a run's mutation results to `path` as a JUnit XML report.
+///
+/// Each mutation is one testcase, grouped into one testsuite per mutation
+/// type; failures list the failed checks with their details.
+#[pyfunction]
+#[pyo3(signature = (results, path, suite_name = "flakestorm"))]
+pub fn write_junit_report(py: Python<'_>, results: Vec<MutationResult>, path: &str, suite_name: &str) -> PyResult<()> {
+    py.allow_threads(|| write_junit(Path::new(path), &results, suite_name))
+        .map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::CheckResult;
+    use crate::severity::Severity;
+
+    fn result(mutation_type: &str, failed_details: Option<&str>) -> MutationResult {
+        MutationResult {
+            mutation_type: mutation_type.to_string(),
+            passed: failed_details.is_none(),
+            latency_ms: 1500.0,
+            checks: failed_details
+                .map(|details| CheckResult {
+                    check_type: "contains".to_string(),
+                    passed: false,
+                    details: details.to_string(),
+                    severity: Severity::Error,
+                })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_junit() {
+        let mut tagged = result("paraphrase", None);
+        tagged.tags.insert("model".to_string(), "gpt-4o".to_string());
+        let results = vec![
+            result("noise", None),
+            tagged,
+            result("noise", Some("expected <b>\"Paris\"</b> & got\u{0}")),
+        ];
+        let xml = render_junit(&results, "checkout");
+        assert!(xml.contains("<testsuites name=\"checkout\" tests=\"3\" failures=\"1\" errors=\"0\" time=\"4.500\">"));
+        assert!(xml.contains("<testsuite name=\"checkout.noise\" tests=\"2\" failures=\"1\""));
+        assert!(xml.find("checkout.noise").unwrap() < xml.find("checkout.paraphrase").unwrap());
+        assert!(xml.contains("<testcase name=\"noise[0]\" classname=\"noise\" time=\"1.500\"/>"));
+        assert!(xml.contains("<property name=\"model\" value=\"gpt-4o\"/>"));
+        assert!(xml.contains(
+            "<failure message=\"1 of 1 checks failed\" type=\"contains\">\n\
+             [error] contains: expected &lt;b&gt;&quot;Paris&quot;&lt;/b&gt; &amp; got</failure>"
+        ));
+        assert_eq!(xml.matches("<testcase ").count(), 3);
+    }
+
+    #[test]
+    fn test_failure_without_checks_and_empty_run() {
+        let mut failed = result("noise", None);
+        failed.passed = false;
+        let xml = render_junit(&[failed], "flakestorm");
+        assert!(xml.contains("<failure message=\"mutation failed\" type=\"failure\"></failure>"));
+        assert!(render_junit(&[], "flakestorm").contains("tests=\"0\" failures=\"0\""));
+        assert_eq!(xml_escape("a\u{1b}b'c"), "ab&apos;c");
+    }
+}
@@ -0,0 +1,361 @@
+//! Lightweight language identification
+//!
+//! Supports invariants like "the response stays in English even when the
+//! prompt is mutated into mixed scripts". Letters are first bucketed by
+//! script: scripts used by a single supported language (Greek, Hangul,
+//! Thai, ...) vote for that language directly. Han characters are shared
+//! by Chinese and Japanese; they count as Japanese when the text has any
+//! kana or more Japanese-only kanji forms than simplified Chinese ones, and
+//! as Chinese otherwise. Latin-script text is
+//! scored by cosine similarity of its character trigrams against profiles
+//! built from embedded sample text. Confidences are shares of the letters
+//! in the text, so a response that is 70% English and 30% Cyrillic reports
+//! roughly 0.7 for en and 0.3 for ru.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+
+/// (language, sample text) for Latin-script trigram profiles
+const LATIN_SAMPLES: &[(&str, &str)] = &[
+    (
+        "en",
+        "The quick answer is that we cannot change the order after it has been shipped. \
+         If you would like to return the item, please contact our support team and they will \
+         help you with the next steps. There are many things that you should know about this \
+         service, which is available to everyone in the world. What do you think of the weather \
+         today? I have been working here for three years and my manager is happy with the work.",
+    ),
+    (
+        "es",
+        "La respuesta rápida es que no podemos cambiar el pedido después de que ha sido enviado. \
+         Si quieres devolver el artículo, por favor contacta con nuestro equipo de soporte y ellos \
+         te ayudarán con los siguientes pasos. Hay muchas cosas que debes saber sobre este \
+         servicio, que está disponible para todos en el mundo. ¿Qué piensas del tiempo de hoy? \
+         Llevo tres años trabajando aquí y mi jefe está contento con el trabajo.",
+    ),
+    (
+        "fr",
+        "La réponse rapide est que nous ne pouvons pas modifier la commande après son expédition. \
+         Si vous souhaitez retourner l'article, veuillez contacter notre équipe d'assistance qui \
+         vous aidera pour les prochaines étapes. Il y a beaucoup de choses que vous devez savoir \
+         sur ce service, qui est disponible pour tout le monde. Que pensez-vous du temps \
+         aujourd'hui? Je travaille ici depuis trois ans et mon responsable est content du travail.",
+    ),
+    (
+        "de",
+        "Die kurze Antwort ist, dass wir die Bestellung nach dem Versand nicht mehr ändern können. \
+         Wenn Sie den Artikel zurückgeben möchten, wenden Sie sich bitte an unser Support-Team, \
+         das Ihnen bei den nächsten Schritten hilft. Es gibt viele Dinge, die Sie über diesen \
+         Dienst wissen sollten, der für alle auf der Welt verfügbar ist. Was halten Sie vom Wetter \
+         heute? Ich arbeite seit drei Jahren hier und mein Chef ist mit der Arbeit zufrieden.",
+    ),
+    (
+        "it",
+        "La risposta breve è che non possiamo modificare l'ordine dopo che è stato spedito. \
+         Se vuoi restituire l'articolo, contatta il nostro team di assistenza e ti aiuteranno \
+         con i prossimi passi. Ci sono molte cose che dovresti sapere su questo servizio, che è \
+         disponibile per tutti nel mondo. Che cosa ne pensi del tempo di oggi? Lavoro qui da tre \
+         anni e il mio responsabile è contento del lavoro.",
+    ),
+    (
+        "pt",
+        "A resposta rápida é que não podemos alterar o pedido depois de ele ter sido enviado. \
+         Se você quiser devolver o item, entre em contato com a nossa equipe de suporte e eles \
+         vão ajudar você com os próximos passos. Há muitas coisas que você deve saber sobre este \
+         serviço, que está disponível para todos no mundo. O que você acha do tempo hoje? \
+         Trabalho aqui há três anos e o meu gerente está satisfeito com o trabalho.",
+    ),
+    (
+        "nl",
+        "Het korte antwoord is dat we de bestelling niet meer kunnen wijzigen nadat deze is \
+         verzonden. Als je het artikel wilt terugsturen, neem dan contact op met ons \
+         ondersteuningsteam en zij helpen je met de volgende stappen. Er zijn veel dingen die je \
+         moet weten over deze dienst, die voor iedereen in de wereld beschikbaar is. Wat vind je \
+         van het weer vandaag? Ik werk hier al drie jaar en mijn manager is tevreden over het werk.",
+    ),
+];
+
+/// Writing systems letters are bucketed into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    Han,
+}
+
+/// Scripts that identify one supported language on their own
+const SCRIPT_LANGUAGES: &[(Script, &str)] = &[
+    (Script::Cyrillic, "ru"),
+    (Script::Greek, "el"),
+    (Script::Arabic, "ar"),
+    (Script::Hebrew, "he"),
+    (Script::Devanagari, "hi"),
+    (Script::Thai, "th"),
+    (Script::Hangul, "ko"),
+    (Script::Kana, "ja"),
+    (Script::Han, "zh"),
+];
+
+/// Kanji forms Japanese uses where simplified Chinese writes another form
+const JAPANESE_HAN: &str = "駅円県沢広変売読鉄関様気図労働込畑峠辻枠拡歳渋覚転伝軽払戻";
+/// Simplified Chinese forms and particles Japanese text does not use
+const CHINESE_HAN: &str = "们这说个对时为过还没关门问间东车长开马见电话语请谢吗呢么";
+
+/// Whether a kana-free Han text reads as Japanese rather than Chinese.
+fn japanese_han(text: &str) -> bool {
+    let count = |forms: &str| text.chars().filter(|&c| forms.contains(c)).count();
+    count(JAPANESE_HAN) > count(CHINESE_HAN)
+}
+
+fn script_of(c: char) -> Option<Script> {
+    let script = match c as u32 {
+        0x0400..=0x052F => Script::Cyrillic,
+        0x0370..=0x03FF => Script::Greek,
+        0x0600..=0x06FF | 0x0750..=0x077F => Script::Arabic,
+        0x0590..=0x05FF => Script::Hebrew,
+        0x0900..=0x097F => Script::Devanagari,
+        0x0E00..=0x0E7F => Script::Thai,
+        0x1100..=0x11FF | 0xAC00..=0xD7AF | 0x3130..=0x318F => Script::Hangul,
+        0x3040..=0x30FF => Script::Kana,
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF => Script::Han,
+        _ if c.is_alphabetic() && (c.is_ascii() || ('\u{00C0}'..='\u{024F}').contains(&c)) => Script::Latin,
+        _ => return None,
+    };
+    Some(script)
+}
+
+/// Language codes the identifier can report.
+pub fn language_codes() -> Vec<&'static str> {
+    LATIN_SAMPLES
+        .iter()
+        .map(|(lang, _)| *lang)
+        .chain(SCRIPT_LANGUAGES.iter().map(|(_, lang)| *lang))
+        .collect()
+}
+
+/// Reject language codes the identifier can never report.
+pub fn validate_language_code(code: &str) -> Result<(), String> {
+    if language_codes().contains(&code) {
+        return Ok(());
+    }
+    Err(format!(
+        "unknown language '{}' (expected one of {})",
+        code,
+        language_codes().join(", ")
+    ))
+}
+
+type Profile = HashMap<[char; 3], f64>;
+
+/// Unit-length trigram frequency vector over lowercased Latin words,
+/// each padded with a space on both sides.
+fn trigram_profile(text: &str) -> Profile {
+    let mut counts: Profile = HashMap::new();
+    let lowered = text.to_lowercase();
+    for word in lowered.split(|c: char| script_of(c) != Some(Script::Latin)).filter(|w| !w.is_empty()) {
+        let padded: Vec<char> = std::iter::once(' ').chain(word.chars()).chain(std::iter::once(' ')).collect();
+        for gram in padded.windows(3) {
+            *counts.entry([gram[0], gram[1], gram[2]]).or_insert(0.0) += 1.0;
+        }
+    }
+    let norm = counts.values().map(|v| v * v).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        counts.values_mut().for_each(|v| *v /= norm);
+    }
+    counts
+}
+
+fn latin_profiles() -> &'static [(&'static str, Profile)] {
+    static PROFILES: OnceLock<Vec<(&'static str, Profile)>> = OnceLock::new();
+    PROFILES.get_or_init(|| {
+        LATIN_SAMPLES
+            .iter()
+            .map(|&(lang, sample)| (lang, trigram_profile(sample)))
+            .collect()
+    })
+}
+
+/// One candidate language for a text
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LanguageGuess {
+    pub language: String,
+    /// Share of the text attributed to this language, 0..=1
+    pub confidence: f64,
+}
+
+/// Up to `top_k` language guesses for `text`, most confident first.
+/// Text without letters yields no guesses.
+pub fn identify_language(text: &str, top_k: usize) -> Vec<LanguageGuess> {
+    let mut script_counts: HashMap<Script, usize> = HashMap::new();
+    for script in text.chars().filter_map(script_of) {
+        *script_counts.entry(script).or_insert(0) += 1;
+    }
+    let letters: usize = script_counts.values().sum();
+    if letters == 0 {
+        return Vec::new();
+    }
+    if let Some(&han) = script_counts.get(&Script::Han) {
+        if script_counts.contains_key(&Script::Kana) || japanese_han(text) {
+            script_counts.remove(&Script::Han);
+            *script_counts.entry(Script::Kana).or_insert(0) += han;
+        }
+    }
+
+    let mut guesses: Vec<LanguageGuess> = SCRIPT_LANGUAGES
+        .iter()
+        .filter_map(|(script, lang)| {
+            script_counts.get(script).map(|&n| LanguageGuess {
+                language: lang.to_string(),
+                confidence: n as f64 / letters as f64,
+            })
+        })
+        .collect();
+
+    if let Some(&latin) = script_counts.get(&Script::Latin) {
+        let share = latin as f64 / letters as f64;
+        let profile = trigram_profile(text);
+        // Sharpen cosine similarities so the best-matching profile dominates
+        let weights: Vec<(&str, f64)> = latin_profiles()
+            .iter()
+            .map(|(lang, reference)| {
+                let cosine: f64 = profile
+                    .iter()
+                    .filter_map(|(gram, v)| reference.get(gram).map(|r| v * r))
+                    .sum();
+                (*lang, cosine.powi(4))
+            })
+            .collect();
+        let total: f64 = weights.iter().map(|(_, w)| w).sum();
+        for (lang, w) in weights {
+            let fraction = if total > 0.0 { w / total } else { 1.0 / LATIN_SAMPLES.len() as f64 };
+            guesses.push(LanguageGuess {
+                language: lang.to_string(),
+                confidence: share * fraction,
+            });
+        }
+    }
+
+    guesses.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then_with(|| a.language.cmp(&b.language)));
+    guesses.truncate(top_k);
+    guesses
+}
+
+/// Check result for the `language` check: the top guess must be
+/// `expected` with at least `min_confidence`.
+pub fn language_check_result(guesses: &[LanguageGuess], expected: &str, min_confidence: f64) -> CheckResult {
+    let (passed, details) = match guesses.first() {
+        None => (false, format!("no language detected (expected {})", expected)),
+        Some(top) => (
+            top.language == expected && top.confidence >= min_confidence,
+            format!(
+                "detected {} ({:.2}), expected {} with confidence >= {:.2}",
+                top.language, top.confidence, expected, min_confidence
+            ),
+        ),
+    };
+    CheckResult {
+        check_type: "language".to_string(),
+        passed,
+        details,
+        severity: Severity::Error,
+    }
+}
+
+/// Top-k language guesses for each text.
+#[pyfunction]
+#[pyo3(signature = (texts, top_k = 3))]
+pub fn identify_languages(py: Python<'_>, texts: Vec<String>, top_k: usize) -> Vec<Vec<LanguageGuess>> {
+    py.allow_threads(|| texts.par_iter().map(|t| identify_language(t, top_k)).collect())
+}
+
+/// One `language` check result per output; raises ValueError when
+/// `expected` is not a supported language code.
+#[pyfunction]
+#[pyo3(signature = (outputs, expected, min_confidence = 0.5))]
+pub fn check_language(
+    py: Python<'_>,
+    outputs: Vec<String>,
+    expected: String,
+    min_confidence: f64,
+) -> PyResult<Vec<CheckResult>> {
+    validate_language_code(&expected).map_err(PyValueError::new_err)?;
+    Ok(py.allow_threads(|| {
+        outputs
+            .par_iter()
+            .map(|o| language_check_result(&identify_language(o, 1), &expected, min_confidence))
+            .collect()
+    }))
+}
+
+/// Language codes the identifier can report.
+#[pyfunction]
+pub fn supported_languages() -> Vec<&'static str> {
+    language_codes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top(text: &str) -> String {
+        identify_language(text, 1)[0].language.clone()
+    }
+
+    #[test]
+    fn test_latin_languages() {
+        assert_eq!(top("Could you please tell me where the nearest train station is?"), "en");
+        assert_eq!(top("¿Podrías decirme dónde está la estación de tren más cercana?"), "es");
+        assert_eq!(top("Pourriez-vous me dire où se trouve la gare la plus proche?"), "fr");
+        assert_eq!(top("Können Sie mir sagen, wo der nächste Bahnhof ist?"), "de");
+        assert_eq!(top("Potresti dirmi dove si trova la stazione più vicina?"), "it");
+        assert_eq!(top("Você pode me dizer onde fica a estação de trem mais próxima?"), "pt");
+        assert_eq!(top("Kun je me vertellen waar het dichtstbijzijnde station is?"), "nl");
+    }
+
+    #[test]
+    fn test_scripts_and_mixing() {
+        assert_eq!(top("Где находится ближайший вокзал?"), "ru");
+        assert_eq!(top("가장 가까운 기차역이 어디예요?"), "ko");
+        // One kana is enough to read the surrounding kanji as Japanese
+        let kanji_heavy = identify_language("日本国憲法第九条の規定", 2);
+        assert_eq!((kanji_heavy.len(), kanji_heavy[0].language.as_str()), (1, "ja"));
+        assert_eq!(top("東京駅八重洲北口改札前集合"), "ja");
+        assert_eq!(top("我们在火车站见面"), "zh");
+        assert_eq!(top("北京大学"), "zh");
+        assert!(identify_language("12345 !!", 3).is_empty());
+
+        let mixed = identify_language("Please reply in English only. Привет", 2);
+        assert_eq!(mixed[0].language, "en");
+        assert_eq!(mixed[1].language, "ru");
+        assert!((mixed[1].confidence - 6.0 / 30.0).abs() < 1e-9);
+        let total: f64 = identify_language("Hello there Привет", 20).iter().map(|g| g.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_check_result() {
+        let guesses = identify_language("The order shipped on Monday and will arrive soon.", 1);
+        let check = language_check_result(&guesses, "en", 0.5);
+        assert!(check.passed, "{}", check.details);
+        assert!(!language_check_result(&guesses, "de", 0.5).passed);
+        assert!(!language_check_result(&[], "en", 0.5).passed);
+        assert!(validate_language_code("ja").is_ok());
+        assert!(validate_language_code("english").unwrap_err().starts_with("unknown language 'english'"));
+    }
+}
@@ -0,0 +1,405 @@
+//! Latency histograms for statistics output
+//!
+//! Percentiles hide bimodal latency: a run where half the calls hit a
+//! cache at 20 ms and half wait 3 s for the model has a p50 that matches
+//! neither. A histogram keeps the shape. Bucket bounds are either given
+//! explicitly or spaced logarithmically, HDR style, with a fixed number of
+//! buckets per decade so the relative resolution is the same at 5 ms and
+//! at 50 s.
+//!
+//! Outlier detection flags results that are unusually slow for the run,
+//! so a mutation that triples the agent's latency shows up even when it
+//! passes. Two scores are offered, both one-sided (only slow results are
+//! flagged):
+//!
+//! - MAD: the modified z-score `0.6745 * (x - median) / MAD`, robust to
+//!   the outliers themselves. When more than half the latencies are equal
+//!   the MAD is 0 and the mean absolute deviation, scaled by 1.2533, is
+//!   used instead.
+//! - z-score: `(x - mean) / std`, which the outliers inflate, so it misses
+//!   them in small runs.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::metrics::Histogram;
+use crate::scoring::{percentile, PercentileMethod};
+
+/// Smallest default bucket bound, in milliseconds
+pub const DEFAULT_MIN_LATENCY_MS: f64 = 1.0;
+/// Largest default bucket bound, in milliseconds
+pub const DEFAULT_MAX_LATENCY_MS: f64 = 100_000.0;
+/// Default log-scale resolution
+pub const DEFAULT_BUCKETS_PER_DECADE: usize = 4;
+
+/// Latency distribution in fixed buckets
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    /// Bucket upper bounds in ms, ascending
+    pub bounds: Vec<f64>,
+    /// One count per bound plus a final overflow bucket
+    pub counts: Vec<u64>,
+    pub count: u64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    pub mean_ms: f64,
+}
+
+/// Log-spaced bucket bounds from `min_ms` to `max_ms`, `per_decade` per
+/// power of ten, rounded to 3 significant digits.
+pub fn log_buckets(min_ms: f64, max_ms: f64, per_decade: usize) -> Result<Vec<f64>, String> {
+    if !(min_ms > 0.0 && max_ms > min_ms && max_ms.is_finite()) {
+        return Err(format!("need 0 < min_ms < max_ms, got {} and {}", min_ms, max_ms));
+    }
+    if per_decade == 0 {
+        return Err("per_decade must be at least 1".to_string());
+    }
+    let steps = ((max_ms / min_ms).log10() * per_decade as f64).ceil() as usize;
+    Ok((0..=steps)
+        .map(|i| {
+            let bound = min_ms * 10f64.powf(i as f64 / per_decade as f64);
+            let digits = 2 - bound.log10().floor() as i32;
+            if digits >= 0 {
+                let scale = 10f64.powi(digits);
+                (bound * scale).round() / scale
+            } else {
+                let scale = 10f64.powi(-digits);
+                (bound / scale).round() * scale
+            }
+        })
+        .collect())
+}
+
+/// The default bounds: 1 ms to 100 s, four buckets per decade.
+pub fn default_latency_buckets() -> Vec<f64> {
+    log_buckets(
+        DEFAULT_MIN_LATENCY_MS,
+        DEFAULT_MAX_LATENCY_MS,
+        DEFAULT_BUCKETS_PER_DECADE,
+    )
+    .expect("default latency buckets are valid")
+}
+
+fn validate_bounds(bounds: &[f64]) -> Result<(), String> {
+    if bounds.is_empty() {
+        return Err("at least one bucket bound is required".to_string());
+    }
+    if bounds.iter().any(|b| !b.is_finite()) || bounds.windows(2).any(|w| w[0] >= w[1]) {
+        return Err("bucket bounds must be finite and strictly ascending".to_string());
+    }
+    Ok(())
+}
+
+impl LatencyHistogram {
+    /// Histogram of `latencies` over `bounds`; None when there are no latencies.
+    pub fn from_latencies(latencies: &[f64], bounds: &[f64]) -> Result<Option<Self>, String> {
+        validate_bounds(bounds)?;
+        if latencies.is_empty() {
+            return Ok(None);
+        }
+        let mut histogram = Histogram::new(bounds);
+        for &latency in latencies {
+            histogram.observe(latency);
+        }
+        Ok(Some(Self {
+            bounds: histogram.bounds,
+            counts: histogram.counts,
+            count: histogram.count,
+            min_ms: latencies.iter().copied().fold(f64::INFINITY, f64::min),
+            max_ms: latencies.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            mean_ms: histogram.sum / histogram.count as f64,
+        }))
+    }
+
+    /// (lower, upper, count) per bucket; the first lower bound is 0 and the
+    /// overflow bucket's upper bound is infinite.
+    pub fn buckets(&self) -> Vec<(f64, f64, u64)> {
+        let lowers = std::iter::once(0.0).chain(self.bounds.iter().copied());
+        let uppers = self.bounds.iter().copied().chain(std::iter::once(f64::INFINITY));
+        lowers
+            .zip(uppers)
+            .zip(&self.counts)
+            .map(|((lower, upper), &count)| (lower, upper, count))
+            .collect()
+    }
+}
+
+#[pymethods]
+impl LatencyHistogram {
+    /// (lower, upper, count) per bucket, overflow last.
+    #[pyo3(name = "buckets")]
+    fn py_buckets(&self) -> Vec<(f64, f64, u64)> {
+        self.buckets()
+    }
+}
+
+/// Histogram of latencies in ms.
+///
+/// `bounds` are bucket upper bounds; by default they are log-spaced from 1
+/// ms to 100 s with `per_decade` buckets per power of ten.
+#[pyfunction]
+#[pyo3(signature = (latencies, bounds = None, per_decade = 4))]
+pub fn latency_histogram(
+    latencies: Vec<f64>,
+    bounds: Option<Vec<f64>>,
+    per_decade: usize,
+) -> PyResult<Option<LatencyHistogram>> {
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => {
+            log_buckets(DEFAULT_MIN_LATENCY_MS, DEFAULT_MAX_LATENCY_MS, per_decade).map_err(PyValueError::new_err)?
+        }
+    };
+    LatencyHistogram::from_latencies(&latencies, &bounds).map_err(PyValueError::new_err)
+}
+
+/// How latency outliers are scored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutlierMethod {
+    Mad,
+    ZScore,
+}
+
+impl OutlierMethod {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "mad" => Ok(OutlierMethod::Mad),
+            "zscore" => Ok(OutlierMethod::ZScore),
+            other => Err(format!("unknown outlier method '{}' (expected mad or zscore)", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutlierMethod::Mad => "mad",
+            OutlierMethod::ZScore => "zscore",
+        }
+    }
+
+    /// Conventional cut-off: 3.5 for modified z-scores, 3.0 for z-scores
+    pub fn default_threshold(&self) -> f64 {
+        match self {
+            OutlierMethod::Mad => 3.5,
+            OutlierMethod::ZScore => 3.0,
+        }
+    }
+}
+
+/// Outlier detection settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct OutlierConfig {
+    pub method: OutlierMethod,
+    /// Score above which a latency is an outlier
+    pub threshold: f64,
+}
+
+impl Default for OutlierConfig {
+    fn default() -> Self {
+        Self::new(OutlierMethod::Mad)
+    }
+}
+
+impl OutlierConfig {
+    /// `method` with its default threshold.
+    pub fn new(method: OutlierMethod) -> Self {
+        Self {
+            method,
+            threshold: method.default_threshold(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.threshold > 0.0 && self.threshold.is_finite()) {
+            return Err(format!("outlier threshold must be positive, got {}", self.threshold));
+        }
+        Ok(())
+    }
+}
+
+/// One unusually slow result
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyOutlier {
+    /// Position in the scanned latencies or results
+    pub index: usize,
+    pub latency_ms: f64,
+    pub score: f64,
+}
+
+/// Slow outliers of a latency series
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LatencyOutliers {
+    /// "mad" or "zscore"
+    pub method: String,
+    pub threshold: f64,
+    /// Median for MAD, mean for z-scores
+    pub center_ms: f64,
+    /// Scaled deviation the scores are relative to; 0 when every latency
+    /// is the same and nothing can be flagged
+    pub spread_ms: f64,
+    pub count: usize,
+    /// Outliers in scan order
+    pub outliers: Vec<LatencyOutlier>,
+}
+
+/// (center, spread) such that `(x - center) / spread` is the score.
+fn outlier_scale(latencies: &[f64], method: OutlierMethod) -> (f64, f64) {
+    let n = latencies.len() as f64;
+    match method {
+        OutlierMethod::Mad => {
+            let mut sorted = latencies.to_vec();
+            sorted.sort_by(|a, b| a.total_cmp(b));
+            let median = percentile(&sorted, 50.0, PercentileMethod::Linear);
+            let mut deviations: Vec<f64> = sorted.iter().map(|x| (x - median).abs()).collect();
+            deviations.sort_by(|a, b| a.total_cmp(b));
+            let mad = percentile(&deviations, 50.0, PercentileMethod::Linear);
+            if mad > 0.0 {
+                (median, mad / 0.6745)
+            } else {
+                (median, 1.253314 * deviations.iter().sum::<f64>() / n)
+            }
+        }
+        OutlierMethod::ZScore => {
+            let mean = latencies.iter().sum::<f64>() / n;
+            let variance = latencies.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+            (mean, variance.sqrt())
+        }
+    }
+}
+
+/// Slow outliers of `latencies`; None when there are no latencies.
+pub fn latency_outliers(latencies: &[f64], config: &OutlierConfig) -> Result<Option<LatencyOutliers>, String> {
+    config.validate()?;
+    if latencies.is_empty() {
+        return Ok(None);
+    }
+    let (center, spread) = outlier_scale(latencies, config.method);
+    let outliers: Vec<LatencyOutlier> = if spread > 0.0 {
+        latencies
+            .iter()
+            .enumerate()
+            .map(|(index, &latency_ms)| LatencyOutlier {
+                index,
+                latency_ms,
+                score: (latency_ms - center) / spread,
+            })
+            .filter(|o| o.score > config.threshold)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok(Some(LatencyOutliers {
+        method: config.method.name().to_string(),
+        threshold: config.threshold,
+        center_ms: center,
+        spread_ms: spread,
+        count: outliers.len(),
+        outliers,
+    }))
+}
+
+impl LatencyOutliers {
+    /// Positions of the outliers in the latency series, which for run
+    /// statistics are indices into the run's results.
+    pub fn indices(&self) -> Vec<usize> {
+        self.outliers.iter().map(|o| o.index).collect()
+    }
+}
+
+#[pymethods]
+impl LatencyOutliers {
+    /// Indices of the outlying results, in scan order.
+    #[pyo3(name = "indices")]
+    fn py_indices(&self) -> Vec<usize> {
+        self.indices()
+    }
+}
+
+/// Unusually slow latencies in ms.
+///
+/// `method` is "mad" (modified z-score around the median) or "zscore";
+/// `threshold` defaults to 3.5 and 3.0 respectively.
+#[pyfunction]
+#[pyo3(name = "latency_outliers", signature = (latencies, method = "mad", threshold = None))]
+pub fn py_latency_outliers(
+    latencies: Vec<f64>,
+    method: &str,
+    threshold: Option<f64>,
+) -> PyResult<Option<LatencyOutliers>> {
+    let mut config = OutlierConfig::new(OutlierMethod::parse(method).map_err(PyValueError::new_err)?);
+    if let Some(threshold) = threshold {
+        config.threshold = threshold;
+    }
+    latency_outliers(&latencies, &config).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_buckets() {
+        assert_eq!(log_buckets(1.0, 100.0, 2).unwrap(), vec![1.0, 3.16, 10.0, 31.6, 100.0]);
+        let defaults = default_latency_buckets();
+        assert_eq!(
+            (defaults.len(), defaults[4], *defaults.last().unwrap()),
+            (21, 10.0, 100_000.0)
+        );
+        assert!(log_buckets(0.0, 10.0, 4).is_err());
+        assert!(log_buckets(1.0, 10.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_bimodal_histogram() {
+        let mut latencies = vec![18.0, 20.0, 22.0, 19.0];
+        latencies.extend([2900.0, 3100.0, 3050.0, 250_000.0]);
+        let histogram = LatencyHistogram::from_latencies(&latencies, &default_latency_buckets())
+            .unwrap()
+            .unwrap();
+        assert_eq!(histogram.count, 8);
+        assert_eq!((histogram.min_ms, histogram.max_ms), (18.0, 250_000.0));
+        let occupied: Vec<(f64, u64)> = histogram
+            .buckets()
+            .into_iter()
+            .filter(|b| b.2 > 0)
+            .map(|(_, upper, count)| (upper, count))
+            .collect();
+        assert_eq!(occupied, vec![(31.6, 4), (3160.0, 3), (f64::INFINITY, 1)]);
+
+        assert_eq!(LatencyHistogram::from_latencies(&[], &[1.0]).unwrap(), None);
+        assert!(LatencyHistogram::from_latencies(&[1.0], &[5.0, 2.0]).is_err());
+    }
+
+    #[test]
+    fn test_latency_outliers() {
+        let mad = OutlierConfig::default();
+        let zscore = OutlierConfig::new(OutlierMethod::ZScore);
+        let scan = |latencies: &[f64], config: &OutlierConfig| latency_outliers(latencies, config).unwrap().unwrap();
+
+        let mut latencies = vec![100.0, 110.0, 95.0, 105.0, 102.0, 98.0, 101.0, 99.0, 330.0];
+        let found = scan(&latencies, &mad);
+        assert_eq!(found.center_ms, 101.0);
+        assert_eq!(found.outliers.iter().map(|o| o.index).collect::<Vec<_>>(), vec![8]);
+        // Fast results are never flagged
+        latencies[0] = 5.0;
+        assert_eq!(scan(&latencies, &mad).count, 1);
+
+        // The outlier inflates the standard deviation enough to hide itself
+        let small = [100.0, 105.0, 95.0, 330.0];
+        assert_eq!(scan(&small, &zscore).count, 0);
+        assert_eq!(scan(&small, &mad).count, 1);
+
+        // Zero MAD falls back to the mean absolute deviation
+        assert_eq!(scan(&[100.0, 100.0, 100.0, 100.0, 100.0, 400.0], &mad).count, 1);
+        let same = scan(&[50.0; 4], &mad);
+        assert_eq!((same.spread_ms, same.count), (0.0, 0));
+
+        assert_eq!(latency_outliers(&[], &mad).unwrap(), None);
+        assert!(OutlierMethod::parse("iqr").is_err());
+        let bad = OutlierConfig { threshold: 0.0, ..mad };
+        assert!(latency_outliers(&small, &bad).is_err());
+    }
+}
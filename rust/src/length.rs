@@ -0,0 +1,268 @@
+//! Output length drift against the baseline response
+//!
+//! A mutated prompt whose response is a fraction of the baseline's length
+//! was usually truncated or refused; one many times longer is usually
+//! runaway generation. Lengths are compared as a ratio to the baseline
+//! response for the same seed prompt, in characters. Very short baselines
+//! ("Yes.") make ratios meaningless, so they are only judged on the long
+//! side.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{percentile, CheckResult, PercentileMethod};
+use crate::severity::Severity;
+
+/// Output and baseline length for one mutation, in characters
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputLength {
+    pub output_chars: usize,
+    pub baseline_chars: usize,
+}
+
+#[pymethods]
+impl OutputLength {
+    #[new]
+    fn py_new(output_chars: usize, baseline_chars: usize) -> Self {
+        Self {
+            output_chars,
+            baseline_chars,
+        }
+    }
+}
+
+impl OutputLength {
+    pub fn of(output: &str, baseline: &str) -> Self {
+        Self {
+            output_chars: output.chars().count(),
+            baseline_chars: baseline.chars().count(),
+        }
+    }
+
+    /// Output length relative to the baseline; None for an empty baseline.
+    pub fn ratio(&self) -> Option<f64> {
+        (self.baseline_chars > 0).then(|| self.output_chars as f64 / self.baseline_chars as f64)
+    }
+}
+
+/// Relative bounds on output length
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LengthThresholds {
+    /// Outputs shorter than this fraction of the baseline are flagged
+    pub short_ratio: f64,
+    /// Outputs longer than this multiple of the baseline are flagged
+    pub long_ratio: f64,
+    /// Baselines shorter than this are not judged for short outputs
+    pub min_baseline_chars: usize,
+}
+
+impl Default for LengthThresholds {
+    fn default() -> Self {
+        Self {
+            short_ratio: 0.25,
+            long_ratio: 4.0,
+            min_baseline_chars: 20,
+        }
+    }
+}
+
+impl LengthThresholds {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..1.0).contains(&self.short_ratio) {
+            return Err(format!("short_ratio must be in [0, 1), got {}", self.short_ratio));
+        }
+        if self.long_ratio.is_nan() || self.long_ratio <= 1.0 {
+            return Err(format!("long_ratio must be greater than 1, got {}", self.long_ratio));
+        }
+        Ok(())
+    }
+
+    /// "short", "long" or "ok"
+    pub fn classify(&self, length: &OutputLength) -> &'static str {
+        match length.ratio() {
+            Some(r) if r < self.short_ratio && length.baseline_chars >= self.min_baseline_chars => "short",
+            Some(r) if r > self.long_ratio => "long",
+            _ => "ok",
+        }
+    }
+
+    /// Check result for the `length_drift` check.
+    pub fn check(&self, length: &OutputLength) -> CheckResult {
+        let ratio = length.ratio().unwrap_or(0.0);
+        let verdict = self.classify(length);
+        let details = match verdict {
+            "short" => format!(
+                "output {} chars is {:.2}x baseline {} (below {:.2}x): likely truncated or refused",
+                length.output_chars, ratio, length.baseline_chars, self.short_ratio
+            ),
+            "long" => format!(
+                "output {} chars is {:.2}x baseline {} (above {:.2}x): likely runaway generation",
+                length.output_chars, ratio, length.baseline_chars, self.long_ratio
+            ),
+            _ => format!(
+                "output {} chars vs baseline {}",
+                length.output_chars, length.baseline_chars
+            ),
+        };
+        CheckResult {
+            check_type: "length_drift".to_string(),
+            passed: verdict == "ok",
+            details,
+            severity: Severity::Warn,
+        }
+    }
+}
+
+/// Length drift over a run
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LengthDriftStatistics {
+    /// Results that carried output lengths
+    pub measured: usize,
+    pub too_short: usize,
+    pub too_long: usize,
+    /// Output/baseline ratios over results with a non-empty baseline
+    pub median_ratio: f64,
+    pub p95_ratio: f64,
+}
+
+/// Summarize length drift; ratios are skipped for empty baselines.
+pub fn length_drift_statistics<'a>(
+    lengths: impl IntoIterator<Item = &'a OutputLength>,
+    thresholds: &LengthThresholds,
+    method: PercentileMethod,
+) -> LengthDriftStatistics {
+    let mut stats = LengthDriftStatistics::default();
+    let mut ratios: Vec<f64> = Vec::new();
+    for length in lengths {
+        stats.measured += 1;
+        match thresholds.classify(length) {
+            "short" => stats.too_short += 1,
+            "long" => stats.too_long += 1,
+            _ => {}
+        }
+        ratios.extend(length.ratio());
+    }
+    if !ratios.is_empty() {
+        ratios.sort_by(f64::total_cmp);
+        stats.median_ratio = percentile(&ratios, 50.0, method);
+        stats.p95_ratio = percentile(&ratios, 95.0, method);
+    }
+    stats
+}
+
+fn paired_lengths(outputs: &[String], baselines: &[String]) -> Result<Vec<OutputLength>, String> {
+    if outputs.len() != baselines.len() {
+        return Err(format!(
+            "got {} outputs but {} baselines",
+            outputs.len(),
+            baselines.len()
+        ));
+    }
+    Ok(outputs
+        .par_iter()
+        .zip(baselines.par_iter())
+        .map(|(o, b)| OutputLength::of(o, b))
+        .collect())
+}
+
+fn thresholds(short_ratio: f64, long_ratio: f64, min_baseline_chars: usize) -> PyResult<LengthThresholds> {
+    let t = LengthThresholds {
+        short_ratio,
+        long_ratio,
+        min_baseline_chars,
+    };
+    t.validate().map_err(PyValueError::new_err)?;
+    Ok(t)
+}
+
+/// One `length_drift` check result per (output, baseline) pair.
+#[pyfunction]
+#[pyo3(signature = (outputs, baselines, short_ratio = 0.25, long_ratio = 4.0, min_baseline_chars = 20))]
+pub fn check_output_lengths(
+    py: Python<'_>,
+    outputs: Vec<String>,
+    baselines: Vec<String>,
+    short_ratio: f64,
+    long_ratio: f64,
+    min_baseline_chars: usize,
+) -> PyResult<Vec<CheckResult>> {
+    let t = thresholds(short_ratio, long_ratio, min_baseline_chars)?;
+    py.allow_threads(|| {
+        paired_lengths(&outputs, &baselines).map(|lengths| lengths.iter().map(|l| t.check(l)).collect())
+    })
+    .map_err(PyValueError::new_err)
+}
+
+/// Run-level length drift for (output, baseline) pairs.
+#[pyfunction]
+#[pyo3(signature = (
+    outputs, baselines, short_ratio = 0.25, long_ratio = 4.0, min_baseline_chars = 20, percentile_method = "linear"
+))]
+pub fn output_length_drift(
+    py: Python<'_>,
+    outputs: Vec<String>,
+    baselines: Vec<String>,
+    short_ratio: f64,
+    long_ratio: f64,
+    min_baseline_chars: usize,
+    percentile_method: &str,
+) -> PyResult<LengthDriftStatistics> {
+    let t = thresholds(short_ratio, long_ratio, min_baseline_chars)?;
+    let method = PercentileMethod::parse(percentile_method).map_err(PyValueError::new_err)?;
+    py.allow_threads(|| {
+        paired_lengths(&outputs, &baselines).map(|lengths| length_drift_statistics(&lengths, &t, method))
+    })
+    .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn len(output_chars: usize, baseline_chars: usize) -> OutputLength {
+        OutputLength {
+            output_chars,
+            baseline_chars,
+        }
+    }
+
+    #[test]
+    fn test_classify_and_check() {
+        let t = LengthThresholds::default();
+        assert_eq!(t.classify(&len(10, 200)), "short");
+        assert_eq!(t.classify(&len(900, 200)), "long");
+        assert_eq!(t.classify(&len(180, 200)), "ok");
+        // Short baselines are only judged for runaway output
+        assert_eq!(t.classify(&len(1, 10)), "ok");
+        assert_eq!(t.classify(&len(50, 10)), "long");
+        assert_eq!(t.classify(&len(5, 0)), "ok");
+
+        let check = t.check(&len(12, 150));
+        assert!(!check.passed);
+        assert_eq!(
+            check.details,
+            "output 12 chars is 0.08x baseline 150 (below 0.25x): likely truncated or refused"
+        );
+        assert_eq!(OutputLength::of("héllo", "hi"), len(5, 2));
+
+        assert!(LengthThresholds { long_ratio: 0.5, ..t }.validate().is_err());
+        assert!(paired_lengths(&["a".to_string()], &[]).is_err());
+    }
+
+    #[test]
+    fn test_statistics() {
+        let t = LengthThresholds::default();
+        let lengths = [len(10, 100), len(100, 100), len(120, 100), len(500, 100), len(3, 0)];
+        let stats = length_drift_statistics(&lengths, &t, PercentileMethod::Linear);
+        assert_eq!((stats.measured, stats.too_short, stats.too_long), (5, 1, 1));
+        assert!((stats.median_ratio - 1.1).abs() < 1e-9);
+        assert!((stats.p95_ratio - 4.43).abs() < 1e-9);
+        let nearest = length_drift_statistics(&lengths, &t, PercentileMethod::Nearest);
+        assert!((nearest.median_ratio - 1.2).abs() < 1e-9);
+        assert!((nearest.p95_ratio - 5.0).abs() < 1e-9);
+    }
+}
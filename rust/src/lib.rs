@@ -2,17 +2,259 @@
 //!
 //! This module provides high-performance implementations for:
 //! - Robustness score calculation
+//! - Wilson and bootstrap confidence intervals on the robustness score
+//! - Bayesian Beta-Binomial robustness estimates with seeded priors
+//! - Log-scale latency histograms and slow-outlier detection
+//! - Time-bucketed throughput, rolling pass rate and rolling p95 latency
+//! - Composite robustness/latency/cost scores
+//! - Check severities and severity-weighted scoring
+//! - Pluggable scoring strategies (weighted average, strict, worst category, top-k failures)
+//! - Score attribution by mutation type, check type and top offenders
+//! - Weak-spot ranking of mutation and check types with uncertainty bounds
+//! - Significance tests (chi-square, Fisher exact, bootstrap) between two runs
 //! - Parallel mutation processing
 //! - Fast string similarity scoring
+//! - Token-based similarity metrics
+//! - Canary token generation and leak scanning
+//! - PII detection (emails, phones, national IDs, Luhn-valid cards)
+//! - Category-tagged toxicity wordlist checks
+//! - Repetition-loop and low-entropy output detection
+//! - Lightweight language identification and language checks
+//! - Output length drift against baseline responses
+//! - Declarative check suites loaded from YAML or TOML
+//! - Python-callback checks inside the parallel check engine
+//! - Stability of repeated generations for the same prompt
+//! - Determinism classification of test cases over repeated runs
+//! - Per-mutation flakiness index from pass/fail history
+//! - Quarantine lists of flaky mutations for CI
+//! - Compiled regex invariant checks and batch substring/length checks
+//! - Refusal / compliance / partial classification
+//! - Prompt-injection payload library
+//! - `{{var}}` prompt templates with defaults and grid expansion
+//! - Jailbreak scaffold templates
+//! - Payload-splitting injections
+//! - Delimiter-confusion attacks
+//! - System-prompt extraction probes and leak checks
+//! - Markdown/HTML injection and unsafe-markup checks
+//! - Tool-argument abuse payloads with forbidden-pattern checks
+//! - Multi-turn conversation mutations
+//! - LCS similarity and aligned diffs
+//! - MinHash/LSH near-duplicate detection, including seed corpus scans
+//! - Clustering of failed outputs into distinct failure modes
+//! - Grouping of failed-check details into distinct failure messages
+//! - Seed corpus import from JSONL, CSV and YAML
+//! - Embedding vector similarity and nearest-neighbor search (`embeddings`
+//!   feature, on by default)
+//! - Unicode security screening
+//! - CI gate verdicts
+//! - Regression gates against a baseline run
+//! - Baseline store with EWMA pass-rate trends per mutation type
+//! - Soft-deadline check degradation
+//! - Section-targeted prompt mutation
+//! - Run-to-run distribution drift
+//! - Canonical text normalization
+//! - Similarity matching against expected-answer lists
+//! - Response sanitizing before comparison
+//! - Native mutators: typo noise, homoglyphs, invisible characters, case
+//!   scrambling, leetspeak, word/sentence reordering, whitespace/punctuation noise,
+//!   truncation and repetition, encoding obfuscation
+//! - User-defined grammar mutations
+//! - JSON extraction from prose and lenient repair of almost-valid JSON
+//! - JSON Schema validation of structured outputs
+//! - Per-category mutation quotas and stratified sampling
+//! - Failure-mode corpus minimization
+//! - Feedback-driven mutation scheduling and budget allocation
+//! - Cross-run mutation lineage
+//! - Process-wide metrics registry
+//! - Run-wide seeding for reproducible mutation batches
+//! - Mutation record/replay files
+//! - Spill-to-disk result buffering for long runs
+//! - Filtered, sorted and paginated result queries
+//! - JSON export of results and statistics
+//! - JUnit XML reports for CI test tabs
+//! - SARIF 2.1 export of security findings
+//! - Markdown run summaries for PR comments
+//! - Custom scoring formulas over aggregate variables
+
+// pyo3 0.20's #[new] expansion trips the newer non_local_definitions lint
+#![allow(non_local_definitions)]
 
 use pyo3::prelude::*;
 use rayon::prelude::*;
 
+#[cfg(feature = "embeddings")]
+mod ann;
+mod answers;
+mod attribution;
+mod baseline;
+mod bayes;
+mod callback;
+mod canary;
+mod capabilities;
+mod check_spec;
+mod clustering;
+mod composite;
+mod confidence;
+mod conversation;
+mod corpus;
+mod deadline;
+mod dedup;
+mod degenerate;
+mod delimiters;
+mod determinism;
+mod diff;
+mod drift;
+mod edit_distance;
+mod encoding;
+mod explain;
+mod extraction;
+mod failure_messages;
+mod flakiness;
+mod formula;
+mod gating;
+mod grammar;
+mod homoglyph;
+mod invariants;
+mod invisible;
+mod jailbreak;
+mod junit;
+mod json_repair;
+mod json_schema;
+mod langid;
+mod latency;
+mod length;
+mod lineage;
+mod markdown;
+mod markup;
+mod matcher;
+mod metrics;
+mod minimize;
+mod noise;
+mod normalize;
 mod parallel;
+mod pii;
+mod payloads;
+mod quarantine;
+mod query;
+mod quota;
+mod refusal;
+mod regression;
+mod reorder;
+mod replay;
+mod rng;
+mod sampling;
+mod sanitize;
+mod sarif;
+mod scheduler;
 mod scoring;
-
+mod sections;
+mod serialization;
+mod severity;
+mod significance;
+mod similarity;
+mod spacing;
+mod splitting;
+mod spool;
+mod stability;
+mod strategy;
+mod stress;
+mod stylize;
+mod template;
+mod throughput;
+mod tool_abuse;
+mod toxicity;
+mod unicode;
+#[cfg(feature = "embeddings")]
+mod vector;
+mod weak_spots;
+
+#[cfg(feature = "embeddings")]
+pub use ann::*;
+pub use answers::*;
+pub use attribution::*;
+pub use baseline::*;
+pub use bayes::*;
+pub use callback::*;
+pub use canary::*;
+pub use capabilities::*;
+#[cfg(not(feature = "embeddings"))]
+pub use capabilities::embeddings_stubs::*;
+pub use check_spec::*;
+pub use clustering::*;
+pub use composite::*;
+pub use confidence::*;
+pub use conversation::*;
+pub use corpus::*;
+pub use deadline::*;
+pub use dedup::*;
+pub use degenerate::*;
+pub use delimiters::*;
+pub use determinism::*;
+pub use diff::*;
+pub use drift::*;
+pub use edit_distance::*;
+pub use encoding::*;
+pub use explain::*;
+pub use extraction::*;
+pub use failure_messages::*;
+pub use flakiness::*;
+pub use formula::*;
+pub use gating::*;
+pub use grammar::*;
+pub use homoglyph::*;
+pub use invariants::*;
+pub use invisible::*;
+pub use jailbreak::*;
+pub use junit::*;
+pub use json_repair::*;
+pub use json_schema::*;
+pub use langid::*;
+pub use latency::*;
+pub use length::*;
+pub use lineage::*;
+pub use markdown::*;
+pub use markup::*;
+pub use matcher::*;
+pub use metrics::*;
+pub use minimize::*;
+pub use noise::*;
+pub use normalize::*;
 pub use parallel::*;
+pub use pii::*;
+pub use payloads::*;
+pub use quarantine::*;
+pub use query::*;
+pub use quota::*;
+pub use refusal::*;
+pub use regression::*;
+pub use reorder::*;
+pub use replay::*;
+pub use rng::*;
+pub use sampling::*;
+pub use sanitize::*;
+pub use sarif::*;
+pub use scheduler::*;
 pub use scoring::*;
+pub use sections::*;
+pub use serialization::*;
+pub use severity::*;
+pub use significance::*;
+pub use similarity::*;
+pub use spacing::*;
+pub use splitting::*;
+pub use spool::*;
+pub use stability::*;
+pub use strategy::*;
+pub use stress::*;
+pub use stylize::*;
+pub use template::*;
+pub use throughput::*;
+pub use tool_abuse::*;
+pub use toxicity::*;
+pub use unicode::*;
+#[cfg(feature = "embeddings")]
+pub use vector::*;
+pub use weak_spots::*;
 
 /// Calculate the robustness score for a test run.
 ///
@@ -23,6 +265,11 @@ pub use scoring::*;
 /// - S_passed = Semantic variations passed
 /// - D_passed = Deterministic tests passed
 /// - W_s, W_d = Weights for semantic and deterministic tests
+///
+/// Every test has unit weight and earns its class weight when it passed,
+/// so R is the weighted_average of the run's credits, computed from the
+/// per-class counts. Inputs are not validated, matching the pure-Python
+/// fallback; a class weight above 1 can push R above 1.
 #[pyfunction]
 fn calculate_robustness_score(
     semantic_passed: u32,
@@ -92,37 +339,11 @@ fn parallel_process_mutations(
 }
 
 /// Fast Levenshtein distance calculation for noise mutation validation.
+///
+/// Uses Myers' bit-parallel algorithm, see `edit_distance`.
 #[pyfunction]
 fn levenshtein_distance(s1: &str, s2: &str) -> usize {
-    let len1 = s1.chars().count();
-    let len2 = s2.chars().count();
-
-    if len1 == 0 {
-        return len2;
-    }
-    if len2 == 0 {
-        return len1;
-    }
-
-    let s1_chars: Vec<char> = s1.chars().collect();
-    let s2_chars: Vec<char> = s2.chars().collect();
-
-    let mut prev_row: Vec<usize> = (0..=len2).collect();
-    let mut curr_row: Vec<usize> = vec![0; len2 + 1];
-
-    for i in 1..=len1 {
-        curr_row[0] = i;
-        for j in 1..=len2 {
-            let cost = if s1_chars[i - 1] == s2_chars[j - 1] { 0 } else { 1 };
-            curr_row[j] = std::cmp::min(
-                std::cmp::min(prev_row[j] + 1, curr_row[j - 1] + 1),
-                prev_row[j - 1] + cost,
-            );
-        }
-        std::mem::swap(&mut prev_row, &mut curr_row);
-    }
-
-    prev_row[len2]
+    edit_distance::levenshtein(s1, s2)
 }
 
 /// Calculate similarity ratio between two strings (0.0 to 1.0).
@@ -138,6 +359,20 @@ fn string_similarity(s1: &str, s2: &str) -> f64 {
     1.0 - (distance as f64 / max_len as f64)
 }
 
+/// Levenshtein distance counted in grapheme clusters instead of code points.
+///
+/// A combining accent or a multi-code-point emoji counts as one edit.
+#[pyfunction]
+fn grapheme_levenshtein_distance(s1: &str, s2: &str) -> usize {
+    grapheme_levenshtein(s1, s2)
+}
+
+/// Grapheme-aware similarity ratio between two strings (0.0 to 1.0).
+#[pyfunction]
+fn grapheme_similarity(s1: &str, s2: &str) -> f64 {
+    grapheme_ratio(s1, s2)
+}
+
 /// V2: Contract resilience matrix score (addendum §6.3).
 ///
 /// severity_weight: critical=3, high=2, medium=1, low=1.
@@ -215,16 +450,401 @@ fn calculate_overall_resilience(scores: Vec<f64>, weights: Vec<f64>) -> f64 {
     sum_ws / sum_w
 }
 
+/// Perturb each mutation type weight and report how much the weighted score moves.
+///
+/// `results` is a list of (mutation_type, passed) pairs and `weights` maps
+/// mutation types to their configured weight. Weights whose perturbation
+/// moves the score by at least `dominant_threshold` are flagged "dominant";
+/// those below `negligible_threshold` are flagged "negligible". Raises
+/// ValueError on a negative or non-finite weight or a `perturbation`
+/// outside (0, 1].
+#[pyfunction]
+#[pyo3(
+    name = "sensitivity_analysis",
+    signature = (results, weights, perturbation = 0.1, dominant_threshold = 0.05, negligible_threshold = 0.001)
+)]
+fn py_sensitivity_analysis(
+    results: Vec<(String, bool)>,
+    weights: std::collections::HashMap<String, f64>,
+    perturbation: f64,
+    dominant_threshold: f64,
+    negligible_threshold: f64,
+) -> PyResult<Vec<WeightSensitivity>> {
+    let config = SensitivityConfig {
+        weights,
+        perturbation,
+        dominant_threshold,
+        negligible_threshold,
+    };
+    sensitivity_analysis(&results, &config).map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
+/// Aggregate statistics for a run's mutation results.
+///
+/// `config` is a `StatisticsConfig`; the defaults give a 95% Wilson
+/// interval, log-scale latency buckets and flat weighted_average scoring.
+/// The result can be gated, compared against a baseline or rendered as a
+/// Markdown summary. Raises ValueError on a result that does not validate.
+#[pyfunction]
+#[pyo3(name = "calculate_statistics", signature = (results, config = None))]
+fn py_calculate_statistics(
+    py: Python<'_>,
+    results: Vec<MutationResult>,
+    config: Option<StatisticsConfig>,
+) -> PyResult<TestStatistics> {
+    let config = config.unwrap_or_default();
+    py.allow_threads(|| calculate_statistics_with(&results, &config))
+        .map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
+/// Inter-run reliability of per-mutation outcomes between two identical runs.
+///
+/// Returns (kappa, observed_agreement) where kappa is the weighted Cohen's
+/// kappa over mutations. `weights` defaults to 1.0 per mutation.
+#[pyfunction]
+#[pyo3(signature = (run_a, run_b, weights = None))]
+fn reliability(run_a: Vec<bool>, run_b: Vec<bool>, weights: Option<Vec<f64>>) -> PyResult<(f64, f64)> {
+    let weights = weights.unwrap_or_else(|| vec![1.0; run_a.len()]);
+    let r = cohens_kappa(&run_a, &run_b, &weights).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    Ok((r.kappa, r.observed_agreement))
+}
+
+/// Inter-run reliability of two runs' results, matched by position.
+///
+/// Returns (kappa, observed_agreement), weighting each mutation by its
+/// weight in `run_a`. The runs must list the same mutation types in the
+/// same order.
+#[pyfunction]
+#[pyo3(name = "run_reliability")]
+fn py_run_reliability(run_a: Vec<MutationResult>, run_b: Vec<MutationResult>) -> PyResult<(f64, f64)> {
+    let r = run_reliability(&run_a, &run_b).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    Ok((r.kappa, r.observed_agreement))
+}
+
+/// Sentence-level BLEU of a candidate output against one or more references.
+///
+/// Texts are compared as lowercased word tokens. `smooth` applies add-one
+/// smoothing to n-gram orders above 1. Raises ValueError when `max_n` is 0.
+#[pyfunction]
+#[pyo3(signature = (candidate, references, max_n = 4, smooth = true))]
+fn bleu_score(candidate: &str, references: Vec<String>, max_n: usize, smooth: bool) -> PyResult<f64> {
+    sentence_bleu(candidate, &references, max_n, smooth).map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
+/// Corpus-level BLEU over paired candidates and reference lists.
+///
+/// Raises ValueError when the lists differ in length or `max_n` is 0.
+#[pyfunction]
+#[pyo3(signature = (candidates, references, max_n = 4))]
+fn corpus_bleu(candidates: Vec<String>, references: Vec<Vec<String>>, max_n: usize) -> PyResult<f64> {
+    corpus_bleu_score(&candidates, &references, max_n).map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
+/// ROUGE-L (precision, recall, f1) of a candidate output against a reference.
+#[pyfunction]
+fn rouge_l(candidate: &str, reference: &str) -> (f64, f64, f64) {
+    let r = rouge_l_score(candidate, reference);
+    (r.precision, r.recall, r.f1)
+}
+
+/// ROUGE-L (precision, recall, f1) for each candidate/reference pair, in parallel.
+///
+/// Raises ValueError when the lists differ in length.
+#[pyfunction]
+fn batch_rouge_l(candidates: Vec<String>, references: Vec<String>) -> PyResult<Vec<(f64, f64, f64)>> {
+    Ok(batch_rouge_l_scores(&candidates, &references)
+        .map_err(pyo3::exceptions::PyValueError::new_err)?
+        .into_iter()
+        .map(|r| (r.precision, r.recall, r.f1))
+        .collect())
+}
+
+/// Aggregate per-result resource usage (response size, tokens/sec, HTTP
+/// status), counting throttled and truncated responses.
+#[pyfunction]
+fn resource_usage_statistics(usages: Vec<ResourceUsage>) -> ResourceStatistics {
+    scoring::resource_statistics(&usages)
+}
+
+/// Percentiles `qs` (0-100) of `values`, interpolated like numpy.
+///
+/// `method` is one of "nearest", "lower", "higher", "midpoint" or "linear".
+/// `weights`, when given, are per-value frequency weights. A non-finite
+/// percentile or a negative or non-finite weight raises ValueError.
+#[pyfunction]
+#[pyo3(signature = (values, qs, method = "linear", weights = None))]
+fn percentiles(values: Vec<f64>, qs: Vec<f64>, method: &str, weights: Option<Vec<f64>>) -> PyResult<Vec<f64>> {
+    let method = PercentileMethod::parse(method).map_err(pyo3::exceptions::PyValueError::new_err)?;
+    let weights = weights.unwrap_or_else(|| vec![1.0; values.len()]);
+    if weights.len() != values.len() {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "got {} values but {} weights",
+            values.len(),
+            weights.len()
+        )));
+    }
+    let mut pairs: Vec<(f64, f64)> = values.into_iter().zip(weights).collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    qs.iter()
+        .map(|&q| weighted_percentile(&pairs, q, method))
+        .collect::<Result<_, _>>()
+        .map_err(pyo3::exceptions::PyValueError::new_err)
+}
+
 /// Python module definition
 #[pymodule]
 fn flakestorm_rust(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(calculate_robustness_score, m)?)?;
     m.add_function(wrap_pyfunction!(calculate_weighted_score, m)?)?;
+    m.add_function(wrap_pyfunction!(score_with_strategy, m)?)?;
+    m.add_class::<ScoringConfig>()?;
+    m.add_function(wrap_pyfunction!(py_explain_score, m)?)?;
+    m.add_class::<ScoreExplanation>()?;
+    m.add_class::<LossShare>()?;
+    m.add_class::<Offender>()?;
+    m.add_function(wrap_pyfunction!(py_rank_weak_spots, m)?)?;
+    m.add_class::<WeakSpots>()?;
+    m.add_class::<WeakSpot>()?;
+    m.add_function(wrap_pyfunction!(score_confidence_interval, m)?)?;
+    m.add_class::<ScoreInterval>()?;
+    m.add_function(wrap_pyfunction!(bayesian_robustness, m)?)?;
+    m.add_class::<BayesianEstimate>()?;
+    m.add_function(wrap_pyfunction!(latency_histogram, m)?)?;
+    m.add_class::<LatencyHistogram>()?;
+    m.add_function(wrap_pyfunction!(py_latency_outliers, m)?)?;
+    m.add_class::<LatencyOutliers>()?;
+    m.add_class::<LatencyOutlier>()?;
+    m.add_function(wrap_pyfunction!(py_throughput_statistics, m)?)?;
+    m.add_class::<ThroughputStatistics>()?;
+    m.add_class::<TimeBucket>()?;
+    m.add_function(wrap_pyfunction!(composite_score, m)?)?;
+    m.add_function(wrap_pyfunction!(py_run_cost, m)?)?;
+    m.add_class::<CompositeConfig>()?;
+    m.add_class::<CompositeScore>()?;
+    m.add_function(wrap_pyfunction!(py_compare_runs, m)?)?;
+    m.add_class::<ProportionTest>()?;
+    m.add_class::<ScoreDifferenceTest>()?;
+    m.add_class::<RunComparison>()?;
     m.add_function(wrap_pyfunction!(parallel_process_mutations, m)?)?;
     m.add_function(wrap_pyfunction!(levenshtein_distance, m)?)?;
     m.add_function(wrap_pyfunction!(string_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(grapheme_levenshtein_distance, m)?)?;
+    m.add_function(wrap_pyfunction!(grapheme_similarity, m)?)?;
     m.add_function(wrap_pyfunction!(calculate_resilience_matrix_score, m)?)?;
     m.add_function(wrap_pyfunction!(calculate_overall_resilience, m)?)?;
+    m.add_function(wrap_pyfunction!(jaccard_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(dice_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(shingle_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(similarity_matrix, m)?)?;
+    m.add_class::<StabilityReport>()?;
+    m.add_function(wrap_pyfunction!(generation_stability, m)?)?;
+    m.add_class::<CaseDeterminism>()?;
+    m.add_class::<DeterminismSummary>()?;
+    m.add_class::<DeterminismReport>()?;
+    m.add_function(wrap_pyfunction!(py_determinism_report, m)?)?;
+    m.add_class::<MutationFlakiness>()?;
+    m.add_class::<TypeFlakiness>()?;
+    m.add_class::<FlakinessReport>()?;
+    m.add_function(wrap_pyfunction!(flakiness_index, m)?)?;
+    m.add_class::<QuarantineEntry>()?;
+    m.add_class::<QuarantineList>()?;
+    m.add_function(wrap_pyfunction!(quarantine_list, m)?)?;
+    m.add_function(wrap_pyfunction!(py_sensitivity_analysis, m)?)?;
+    m.add_class::<WeightSensitivity>()?;
+    m.add_function(wrap_pyfunction!(reliability, m)?)?;
+    m.add_function(wrap_pyfunction!(py_run_reliability, m)?)?;
+    m.add_function(wrap_pyfunction!(bleu_score, m)?)?;
+    m.add_function(wrap_pyfunction!(corpus_bleu, m)?)?;
+    m.add_function(wrap_pyfunction!(rouge_l, m)?)?;
+    m.add_function(wrap_pyfunction!(batch_rouge_l, m)?)?;
+    m.add_function(wrap_pyfunction!(resource_usage_statistics, m)?)?;
+    m.add_function(wrap_pyfunction!(percentiles, m)?)?;
+    m.add_class::<OutputLength>()?;
+    m.add_class::<LengthDriftStatistics>()?;
+    m.add_function(wrap_pyfunction!(check_output_lengths, m)?)?;
+    m.add_function(wrap_pyfunction!(output_length_drift, m)?)?;
+    m.add_class::<PyCheckSuite>()?;
+    m.add_class::<ResourceUsage>()?;
+    m.add_class::<ResourceStatistics>()?;
+    m.add_class::<CheckResult>()?;
+    m.add_class::<MutationResult>()?;
+    m.add_function(wrap_pyfunction!(py_calculate_statistics, m)?)?;
+    m.add_class::<StatisticsConfig>()?;
+    m.add_class::<TestStatistics>()?;
+    m.add_class::<TypeStatistics>()?;
+    m.add_class::<CheckStatistics>()?;
+    m.add_class::<TagStatistics>()?;
+    m.add_class::<CostStatistics>()?;
+    m.add_function(wrap_pyfunction!(dump_results, m)?)?;
+    m.add_function(wrap_pyfunction!(load_results, m)?)?;
+    m.add_function(wrap_pyfunction!(write_junit_report, m)?)?;
+    m.add_function(wrap_pyfunction!(write_sarif_report, m)?)?;
+    m.add_function(wrap_pyfunction!(render_markdown_summary, m)?)?;
+    m.add_function(wrap_pyfunction!(py_severity_weighted_score, m)?)?;
+    m.add_class::<SeverityBreakdown>()?;
+    m.add_class::<PyRegexInvariantSet>()?;
+    m.add_function(wrap_pyfunction!(batch_invariant_checks, m)?)?;
+    m.add_class::<PyRefusalDetector>()?;
+    m.add_class::<RefusalVerdict>()?;
+    m.add_function(wrap_pyfunction!(detect_refusals, m)?)?;
+    m.add_function(wrap_pyfunction!(refusal_languages, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_canary_leaks, m)?)?;
+    m.add_function(wrap_pyfunction!(generate_canary_tokens, m)?)?;
+    m.add_class::<CanaryLeak>()?;
+    m.add_function(wrap_pyfunction!(scan_pii, m)?)?;
+    m.add_function(wrap_pyfunction!(check_pii, m)?)?;
+    m.add_function(wrap_pyfunction!(pii_categories, m)?)?;
+    m.add_class::<PiiFinding>()?;
+    m.add_class::<PyToxicityChecker>()?;
+    m.add_class::<ToxicityFinding>()?;
+    m.add_function(wrap_pyfunction!(toxicity_categories, m)?)?;
+    m.add_class::<PyDegenerationDetector>()?;
+    m.add_class::<DegenerationReport>()?;
+    m.add_function(wrap_pyfunction!(detect_degeneration, m)?)?;
+    m.add_class::<LanguageGuess>()?;
+    m.add_function(wrap_pyfunction!(identify_languages, m)?)?;
+    m.add_function(wrap_pyfunction!(check_language, m)?)?;
+    m.add_function(wrap_pyfunction!(supported_languages, m)?)?;
+    m.add_class::<KeywordSet>()?;
+    m.add_function(wrap_pyfunction!(lcs_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(aligned_diff, m)?)?;
+    m.add_class::<DiffSegment>()?;
+    m.add_function(wrap_pyfunction!(features, m)?)?;
+    m.add_function(wrap_pyfunction!(require_feature, m)?)?;
+    m.add_function(wrap_pyfunction!(minhash_dedup, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_seed_duplicates, m)?)?;
+    m.add_class::<SeedEntry>()?;
+    m.add_function(wrap_pyfunction!(load_seed_corpus, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_seed_corpus, m)?)?;
+    m.add_function(wrap_pyfunction!(dedup_mutations, m)?)?;
+    m.add_class::<MutationDedup>()?;
+    m.add_function(wrap_pyfunction!(cluster_failures, m)?)?;
+    m.add_class::<FailureCluster>()?;
+    m.add_function(wrap_pyfunction!(py_group_failure_messages, m)?)?;
+    m.add_class::<FailureMessages>()?;
+    m.add_class::<FailureMessage>()?;
+    m.add_class::<SeedCorpusReport>()?;
+    m.add_class::<SeedDuplicateGroup>()?;
+    m.add_function(wrap_pyfunction!(cosine_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(batch_cosine_similarity, m)?)?;
+    m.add_function(wrap_pyfunction!(explain_mutation, m)?)?;
+    m.add_class::<MutationExplanation>()?;
+    m.add_class::<MutationEdit>()?;
+    m.add_class::<PyAnnIndex>()?;
+    m.add_function(wrap_pyfunction!(screen_prompts, m)?)?;
+    m.add_class::<PromptScreening>()?;
+    m.add_function(wrap_pyfunction!(gate, m)?)?;
+    m.add_class::<GatePolicy>()?;
+    m.add_class::<GateVerdict>()?;
+    m.add_function(wrap_pyfunction!(regression_gate, m)?)?;
+    m.add_class::<RegressionThresholds>()?;
+    m.add_class::<RegressionViolation>()?;
+    m.add_class::<RegressionVerdict>()?;
+    m.add_class::<PyBaselineStore>()?;
+    m.add_class::<TypeTrend>()?;
+    m.add_class::<TrendDrift>()?;
+    m.add_class::<PyDeadlineBudget>()?;
+    m.add_function(wrap_pyfunction!(select_sections, m)?)?;
+    m.add_function(wrap_pyfunction!(mutate_sections, m)?)?;
+    m.add_class::<SectionedPrompt>()?;
+    m.add_class::<SectionSpan>()?;
+    m.add_function(wrap_pyfunction!(distribution_drift, m)?)?;
+    m.add_class::<DriftReport>()?;
+    m.add_function(wrap_pyfunction!(normalize_text, m)?)?;
+    m.add_function(wrap_pyfunction!(normalize_texts, m)?)?;
+    m.add_function(wrap_pyfunction!(canonical_equals, m)?)?;
+    m.add_function(wrap_pyfunction!(match_expected_answers, m)?)?;
+    m.add_class::<AnswerMatch>()?;
+    m.add_class::<PyResponseSanitizer>()?;
+    m.add_function(wrap_pyfunction!(noise_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(homoglyph_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(invisible_char_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(strip_invisible_chars, m)?)?;
+    m.add_function(wrap_pyfunction!(case_scramble_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(leetspeak_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(case_scramble_variants, m)?)?;
+    m.add_function(wrap_pyfunction!(leetspeak_variants, m)?)?;
+    m.add_function(wrap_pyfunction!(reorder_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(spacing_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(truncation_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(repetition_mutations, m)?)?;
+    m.add_class::<StressMutation>()?;
+    m.add_function(wrap_pyfunction!(encoding_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(encode_text, m)?)?;
+    m.add_function(wrap_pyfunction!(decode_text, m)?)?;
+    m.add_class::<EncodedMutation>()?;
+    m.add_class::<PyMutationGrammar>()?;
+    m.add_class::<GrammarMutation>()?;
+    m.add_function(wrap_pyfunction!(injection_payloads, m)?)?;
+    m.add_class::<PyPromptTemplate>()?;
+    m.add_class::<TemplateExpansion>()?;
+    m.add_function(wrap_pyfunction!(expand_templates, m)?)?;
+    m.add_function(wrap_pyfunction!(injection_payload_categories, m)?)?;
+    m.add_class::<InjectionPayload>()?;
+    m.add_function(wrap_pyfunction!(jailbreak_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(jailbreak_categories, m)?)?;
+    m.add_class::<JailbreakMutation>()?;
+    m.add_function(wrap_pyfunction!(payload_split_mutations, m)?)?;
+    m.add_class::<SplitPayload>()?;
+    m.add_function(wrap_pyfunction!(delimiter_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(builtin_delimiter_names, m)?)?;
+    m.add_class::<DelimiterMutation>()?;
+    m.add_function(wrap_pyfunction!(extraction_probe_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(system_prompt_extraction_techniques, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_system_prompt_leaks, m)?)?;
+    m.add_class::<ExtractionProbe>()?;
+    m.add_class::<PromptLeak>()?;
+    m.add_function(wrap_pyfunction!(markup_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(markup_injection_kinds, m)?)?;
+    m.add_function(wrap_pyfunction!(scan_output_markup, m)?)?;
+    m.add_class::<MarkupMutation>()?;
+    m.add_class::<MarkupFinding>()?;
+    m.add_function(wrap_pyfunction!(tool_abuse_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(tool_abuse_classes, m)?)?;
+    m.add_function(wrap_pyfunction!(check_tool_arguments, m)?)?;
+    m.add_class::<ToolAbuseMutation>()?;
+    m.add_class::<ToolArgViolation>()?;
+    m.add_function(wrap_pyfunction!(conversation_mutations, m)?)?;
+    m.add_class::<Turn>()?;
+    m.add_class::<Conversation>()?;
+    m.add_class::<ConversationMutation>()?;
+    m.add_function(wrap_pyfunction!(repair_json, m)?)?;
+    m.add_function(wrap_pyfunction!(extract_json, m)?)?;
+    m.add_class::<JsonExtraction>()?;
+    m.add_class::<PyJsonSchemaCheck>()?;
+    m.add_class::<JsonRepair>()?;
+    m.add_function(wrap_pyfunction!(select_mutations_with_quotas, m)?)?;
+    m.add_function(wrap_pyfunction!(check_mutation_quotas, m)?)?;
+    m.add_function(wrap_pyfunction!(minimize_corpus, m)?)?;
+    m.add_class::<MinimizedCorpus>()?;
+    m.add_class::<PyMutationScheduler>()?;
+    m.add_function(wrap_pyfunction!(allocate_mutation_budget, m)?)?;
+    m.add_class::<BudgetAllocation>()?;
+    m.add_function(wrap_pyfunction!(stratified_mutation_sample, m)?)?;
+    m.add_class::<PyMutationLineage>()?;
+    m.add_function(wrap_pyfunction!(get_metrics, m)?)?;
+    m.add_function(wrap_pyfunction!(increment_counter, m)?)?;
+    m.add_function(wrap_pyfunction!(set_gauge, m)?)?;
+    m.add_function(wrap_pyfunction!(observe_histogram, m)?)?;
+    m.add_function(wrap_pyfunction!(reset_metrics, m)?)?;
+    m.add_class::<PyResultSpool>()?;
+    m.add_function(wrap_pyfunction!(set_run_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(get_run_seed, m)?)?;
+    m.add_function(wrap_pyfunction!(derive_item_seed, m)?)?;
+    m.add_class::<MutationRecord>()?;
+    m.add_class::<ReplayReport>()?;
+    m.add_function(wrap_pyfunction!(record_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(save_mutation_records, m)?)?;
+    m.add_function(wrap_pyfunction!(load_mutation_records, m)?)?;
+    m.add_function(wrap_pyfunction!(replay_mutations, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate_formula, m)?)?;
+    m.add_function(wrap_pyfunction!(severity_formula_variables, m)?)?;
+    m.add_function(wrap_pyfunction!(statistics_formula_variables, m)?)?;
+    m.add_class::<PyScoringFormula>()?;
+    m.add_function(wrap_pyfunction!(query_results, m)?)?;
+    m.add_function(wrap_pyfunction!(count_results, m)?)?;
+    m.add_class::<ResultPage>()?;
+    m.add_class::<ResultCursor>()?;
     Ok(())
 }
 
@@ -0,0 +1,372 @@
+//! Cross-run mutation lineage
+//!
+//! Mutations are rarely one-offs: a failing injection gets minimized,
+//! composed with another attack or re-seeded in a later run. Recording each
+//! of those steps as a parent/child link turns a pile of results into
+//! attack families that can be followed across runs. The graph is kept
+//! acyclic and persisted as JSON.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How a child mutation was derived from its parent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Relation {
+    Minimized,
+    Composed,
+    Reseeded,
+    Derived,
+}
+
+impl Relation {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "minimized" => Ok(Relation::Minimized),
+            "composed" => Ok(Relation::Composed),
+            "reseeded" => Ok(Relation::Reseeded),
+            "derived" => Ok(Relation::Derived),
+            other => Err(format!(
+                "unknown relation '{}' (expected minimized, composed, reseeded or derived)",
+                other
+            )),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Relation::Minimized => "minimized",
+            Relation::Composed => "composed",
+            Relation::Reseeded => "reseeded",
+            Relation::Derived => "derived",
+        }
+    }
+}
+
+/// A mutation known to the lineage graph
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LineageNode {
+    pub mutation_type: String,
+    pub run_id: String,
+}
+
+/// Parent/child link between two mutations
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct LineageEdge {
+    pub parent: String,
+    pub child: String,
+    pub relation: Relation,
+}
+
+/// Directed acyclic graph of mutation ancestry
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LineageGraph {
+    nodes: BTreeMap<String, LineageNode>,
+    /// Ordered by parent, so a node's children are one range of the set
+    edges: BTreeSet<LineageEdge>,
+    /// Links into each child, sorted; rebuilt from `edges` on load
+    #[serde(skip)]
+    parent_links: HashMap<String, Vec<LineageEdge>>,
+}
+
+impl LineageGraph {
+    /// Register a mutation; re-registering keeps the first record.
+    pub fn add_mutation(&mut self, id: &str, mutation_type: &str, run_id: &str) {
+        self.nodes.entry(id.to_string()).or_insert_with(|| LineageNode {
+            mutation_type: mutation_type.to_string(),
+            run_id: run_id.to_string(),
+        });
+    }
+
+    pub fn node(&self, id: &str) -> Option<&LineageNode> {
+        self.nodes.get(id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Record that `child` was derived from `parent`.
+    pub fn link(&mut self, parent: &str, child: &str, relation: Relation) -> Result<(), String> {
+        for id in [parent, child] {
+            if !self.nodes.contains_key(id) {
+                return Err(format!("unknown mutation '{}'", id));
+            }
+        }
+        if parent == child || self.ancestors(parent).iter().any(|a| a == child) {
+            return Err(format!("linking '{}' -> '{}' would create a cycle", parent, child));
+        }
+        let edge = LineageEdge {
+            parent: parent.to_string(),
+            child: child.to_string(),
+            relation,
+        };
+        if self.edges.insert(edge.clone()) {
+            self.index(edge);
+        }
+        Ok(())
+    }
+
+    fn index(&mut self, edge: LineageEdge) {
+        let links = self.parent_links.entry(edge.child.clone()).or_default();
+        let at = links.binary_search(&edge).unwrap_or_else(|i| i);
+        links.insert(at, edge);
+    }
+
+    /// Direct links out of `id`, in child order.
+    fn child_links<'a>(&'a self, id: &'a str) -> impl Iterator<Item = &'a LineageEdge> + 'a {
+        let from = LineageEdge {
+            parent: id.to_string(),
+            child: String::new(),
+            relation: Relation::Minimized,
+        };
+        self.edges.range(from..).take_while(move |e| e.parent == id)
+    }
+
+    fn walk(&self, start: &str, down: bool) -> Vec<String> {
+        let mut seen: BTreeSet<&str> = BTreeSet::new();
+        let mut order = Vec::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([start]);
+        while let Some(id) = queue.pop_front() {
+            let next: Vec<&str> = if down {
+                self.child_links(id).map(|e| e.child.as_str()).collect()
+            } else {
+                self.parents(id).into_iter().map(|e| e.parent.as_str()).collect()
+            };
+            for to in next {
+                if seen.insert(to) {
+                    order.push(to.to_string());
+                    queue.push_back(to);
+                }
+            }
+        }
+        order
+    }
+
+    /// Every mutation derived from `id`, directly or transitively, nearest first.
+    pub fn descendants(&self, id: &str) -> Vec<String> {
+        self.walk(id, true)
+    }
+
+    /// Every mutation `id` was derived from, nearest first.
+    pub fn ancestors(&self, id: &str) -> Vec<String> {
+        self.walk(id, false)
+    }
+
+    /// Direct links into `id`.
+    pub fn parents(&self, id: &str) -> Vec<&LineageEdge> {
+        self.parent_links.get(id).map(|links| links.iter().collect()).unwrap_or_default()
+    }
+
+    /// Ancestors of `id` that have no parents themselves (the attack family roots).
+    pub fn roots(&self, id: &str) -> Vec<String> {
+        let mut roots: Vec<String> = self
+            .ancestors(id)
+            .into_iter()
+            .filter(|a| self.parents(a).is_empty())
+            .collect();
+        if roots.is_empty() && self.nodes.contains_key(id) {
+            roots.push(id.to_string());
+        }
+        roots.sort();
+        roots
+    }
+
+    /// Fold another graph (e.g. a later run's) into this one.
+    ///
+    /// Links that would introduce a cycle are skipped and returned.
+    pub fn merge(&mut self, other: &LineageGraph) -> Vec<LineageEdge> {
+        for (id, node) in &other.nodes {
+            self.add_mutation(id, &node.mutation_type, &node.run_id);
+        }
+        let mut rejected = Vec::new();
+        for e in &other.edges {
+            if self.link(&e.parent, &e.child, e.relation).is_err() {
+                rejected.push(e.clone());
+            }
+        }
+        rejected
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, self).map_err(std::io::Error::other)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut graph: Self = serde_json::from_reader(reader).map_err(std::io::Error::other)?;
+        for edge in graph.edges.clone() {
+            graph.index(edge);
+        }
+        Ok(graph)
+    }
+}
+
+/// Parent/child lineage of mutations across runs.
+#[pyclass(name = "MutationLineage")]
+#[derive(Debug, Clone, Default)]
+pub struct PyMutationLineage {
+    inner: LineageGraph,
+}
+
+#[pymethods]
+impl PyMutationLineage {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    fn add_mutation(&mut self, id: &str, mutation_type: &str, run_id: &str) {
+        self.inner.add_mutation(id, mutation_type, run_id);
+    }
+
+    /// Record that `child` was derived from `parent`; `relation` is
+    /// "minimized", "composed", "reseeded" or "derived".
+    #[pyo3(signature = (parent, child, relation = "derived"))]
+    fn link(&mut self, parent: &str, child: &str, relation: &str) -> PyResult<()> {
+        let relation = Relation::parse(relation).map_err(PyValueError::new_err)?;
+        self.inner.link(parent, child, relation).map_err(PyValueError::new_err)
+    }
+
+    fn descendants(&self, id: &str) -> Vec<String> {
+        self.inner.descendants(id)
+    }
+
+    fn ancestors(&self, id: &str) -> Vec<String> {
+        self.inner.ancestors(id)
+    }
+
+    /// (parent id, relation) pairs for the direct parents of `id`.
+    fn parents(&self, id: &str) -> Vec<(String, &'static str)> {
+        self.inner
+            .parents(id)
+            .into_iter()
+            .map(|e| (e.parent.clone(), e.relation.as_str()))
+            .collect()
+    }
+
+    fn roots(&self, id: &str) -> Vec<String> {
+        self.inner.roots(id)
+    }
+
+    /// (mutation_type, run_id) of a registered mutation.
+    fn provenance(&self, id: &str) -> Option<(String, String)> {
+        self.inner
+            .node(id)
+            .map(|n| (n.mutation_type.clone(), n.run_id.clone()))
+    }
+
+    /// Merge another lineage; returns the (parent, child) links skipped as cycles.
+    fn merge(&mut self, other: &PyMutationLineage) -> Vec<(String, String)> {
+        self.inner
+            .merge(&other.inner)
+            .into_iter()
+            .map(|e| (e.parent, e.child))
+            .collect()
+    }
+
+    fn save(&self, path: &str) -> PyResult<()> {
+        self.inner
+            .save(Path::new(path))
+            .map_err(|e| PyIOError::new_err(format!("failed to save lineage to {}: {}", path, e)))
+    }
+
+    #[staticmethod]
+    fn load(path: &str) -> PyResult<Self> {
+        LineageGraph::load(Path::new(path))
+            .map(|inner| Self { inner })
+            .map_err(|e| PyIOError::new_err(format!("failed to load lineage from {}: {}", path, e)))
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn family() -> LineageGraph {
+        let mut g = LineageGraph::default();
+        for (id, t, run) in [
+            ("inj-1", "prompt_injection", "run-1"),
+            ("inj-1-min", "prompt_injection", "run-1"),
+            ("jb-4", "advanced_jailbreak", "run-1"),
+            ("combo", "prompt_injection", "run-2"),
+            ("combo-seed2", "prompt_injection", "run-3"),
+        ] {
+            g.add_mutation(id, t, run);
+        }
+        g.link("inj-1", "inj-1-min", Relation::Minimized).unwrap();
+        g.link("inj-1-min", "combo", Relation::Composed).unwrap();
+        g.link("jb-4", "combo", Relation::Composed).unwrap();
+        g.link("combo", "combo-seed2", Relation::Reseeded).unwrap();
+        g
+    }
+
+    #[test]
+    fn test_descendants_and_ancestors() {
+        let g = family();
+        assert_eq!(g.descendants("inj-1"), vec!["inj-1-min", "combo", "combo-seed2"]);
+        assert_eq!(g.ancestors("combo-seed2"), vec!["combo", "inj-1-min", "jb-4", "inj-1"]);
+        assert_eq!(g.roots("combo-seed2"), vec!["inj-1", "jb-4"]);
+        assert_eq!(g.roots("jb-4"), vec!["jb-4"]);
+        assert_eq!(g.parents("combo").len(), 2);
+    }
+
+    #[test]
+    fn test_links_are_validated() {
+        let mut g = family();
+        assert!(g.link("combo-seed2", "inj-1", Relation::Derived).unwrap_err().contains("cycle"));
+        assert!(g.link("inj-1", "inj-1", Relation::Derived).is_err());
+        assert!(g.link("inj-1", "missing", Relation::Derived).unwrap_err().contains("unknown"));
+    }
+
+    #[test]
+    fn test_merge_and_persistence() {
+        let mut later = LineageGraph::default();
+        later.add_mutation("combo-seed2", "prompt_injection", "run-3");
+        later.add_mutation("combo-min", "prompt_injection", "run-4");
+        later.link("combo-seed2", "combo-min", Relation::Minimized).unwrap();
+
+        let mut g = family();
+        assert!(g.merge(&later).is_empty());
+        assert_eq!(g.descendants("jb-4"), vec!["combo", "combo-seed2", "combo-min"]);
+        assert_eq!(g.node("combo-min").unwrap().run_id, "run-4");
+
+        let path = std::env::temp_dir().join(format!("flakestorm-lineage-{}.json", std::process::id()));
+        g.save(&path).unwrap();
+        let loaded = LineageGraph::load(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded, g);
+        assert_eq!(loaded.roots("combo-min"), vec!["inj-1", "jb-4"]);
+    }
+
+    #[test]
+    fn test_long_chain_walks() {
+        // Walks follow indexed links instead of scanning every edge per node
+        let mut g = LineageGraph::default();
+        let ids: Vec<String> = (0..1000).map(|i| format!("m{}", i)).collect();
+        for id in &ids {
+            g.add_mutation(id, "noise", "run-1");
+        }
+        for pair in ids.windows(2) {
+            g.link(&pair[0], &pair[1], Relation::Derived).unwrap();
+        }
+        assert_eq!(g.ancestors("m999").len(), 999);
+        assert_eq!(g.descendants("m0").len(), 999);
+        assert_eq!(g.roots("m500"), vec!["m0"]);
+    }
+}
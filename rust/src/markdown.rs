@@ -0,0 +1,359 @@
+//! Markdown run summaries for PR comments
+//!
+//! A pull request comment has to be read at a glance: the score and how it
+//! moved against the baseline first, then the weakest mutation types, the
+//! regressions the regression gate found and the most frequent failures.
+//! The detail level decides how many rows of each are shown:
+//!
+//! | level    | types | regressions | failures | extras                  |
+//! |----------|-------|-------------|----------|-------------------------|
+//! | compact  | -     | 3           | 3        |                         |
+//! | standard | 10    | 5           | 5        |                         |
+//! | full     | all   | all         | 10       | latency and check table |
+//!
+//! Failures are the grouped failure messages when attached (see
+//! `with_failure_messages`), otherwise the raw top failure details of each
+//! check type. The summary never exceeds GitHub's comment size limit.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::regression::{detect_regressions, RegressionThresholds, RegressionViolation};
+use crate::scoring::{TestStatistics, TypeStatistics};
+
+/// Longest summary produced; GitHub rejects comments over 65536 characters
+pub const MAX_COMMENT_CHARS: usize = 65_000;
+
+/// Longest failure message quoted, in characters
+const MAX_MESSAGE_CHARS: usize = 120;
+
+/// How much of the run a summary shows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DetailLevel {
+    Compact,
+    #[default]
+    Standard,
+    Full,
+}
+
+impl DetailLevel {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "compact" => Ok(DetailLevel::Compact),
+            "standard" => Ok(DetailLevel::Standard),
+            "full" => Ok(DetailLevel::Full),
+            other => Err(format!(
+                "unknown detail level '{}' (expected compact, standard or full)",
+                other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DetailLevel::Compact => "compact",
+            DetailLevel::Standard => "standard",
+            DetailLevel::Full => "full",
+        }
+    }
+
+    /// (type rows, regressions, failures) shown; None is unlimited
+    fn limits(&self) -> (Option<usize>, Option<usize>, usize) {
+        match self {
+            DetailLevel::Compact => (Some(0), Some(3), 3),
+            DetailLevel::Standard => (Some(10), Some(5), 5),
+            DetailLevel::Full => (None, None, 10),
+        }
+    }
+}
+
+fn percent(rate: f64) -> String {
+    format!("{:.1}%", rate * 100.0)
+}
+
+fn points(change: f64) -> String {
+    format!("{:+.1} pts", change * 100.0)
+}
+
+/// `text` on one line, shortened and safe inside a code span.
+fn code(text: &str, max_chars: usize) -> String {
+    let mut line: String = text.split_whitespace().collect::<Vec<_>>().join(" ").replace('`', "'");
+    if line.chars().count() > max_chars {
+        line = line.chars().take(max_chars - 1).collect::<String>() + "…";
+    }
+    line
+}
+
+/// `text` on one line, shortened and safe inside a table cell.
+fn cell(text: &str, max_chars: usize) -> String {
+    code(text, max_chars).replace('|', "\\|")
+}
+
+/// (count, check type, message) of the most frequent failures.
+fn worst_failures(stats: &TestStatistics, n: usize) -> Vec<(usize, String, String)> {
+    let mut failures: Vec<(usize, String, String)> = match &stats.failure_messages {
+        Some(grouped) => grouped
+            .messages
+            .iter()
+            .map(|m| (m.count, m.check_type.clone(), m.message.clone()))
+            .collect(),
+        None => stats
+            .by_check
+            .iter()
+            .flat_map(|c| {
+                c.top_failures
+                    .iter()
+                    .map(|(details, count)| (*count, c.check_type.clone(), details.clone()))
+            })
+            .collect(),
+    };
+    failures.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)).then_with(|| a.2.cmp(&b.2)));
+    failures.truncate(n);
+    failures
+}
+
+fn type_table(summary: &mut String, stats: &TestStatistics, baseline: Option<&TestStatistics>, rows: Option<usize>) {
+    let mut types: Vec<&TypeStatistics> = stats.by_type.iter().collect();
+    types.sort_by(|a, b| {
+        a.pass_rate
+            .total_cmp(&b.pass_rate)
+            .then_with(|| a.mutation_type.cmp(&b.mutation_type))
+    });
+    let shown = rows.unwrap_or(types.len()).min(types.len());
+    if shown == 0 {
+        return;
+    }
+    let old: HashMap<&str, f64> = baseline
+        .map(|b| {
+            b.by_type
+                .iter()
+                .map(|t| (t.mutation_type.as_str(), t.pass_rate))
+                .collect()
+        })
+        .unwrap_or_default();
+    summary.push_str("\n| Mutation type | Pass rate | Change | Passed | p95 latency |\n");
+    summary.push_str("|---|---:|---:|---:|---:|\n");
+    for t in &types[..shown] {
+        let change = old
+            .get(t.mutation_type.as_str())
+            .map_or("-".to_string(), |o| points(t.pass_rate - o));
+        summary.push_str(&format!(
+            "| {} | {} | {} | {}/{} | {:.0} ms |\n",
+            cell(&t.mutation_type, MAX_MESSAGE_CHARS),
+            percent(t.pass_rate),
+            change,
+            t.passed,
+            t.total,
+            t.p95_latency_ms
+        ));
+    }
+    if shown < types.len() {
+        summary.push_str(&format!("\n…and {} more mutation types\n", types.len() - shown));
+    }
+}
+
+/// Markdown summary of `stats`, compared with `baseline` when given.
+pub fn render_markdown(
+    stats: &TestStatistics,
+    baseline: Option<&TestStatistics>,
+    detail: DetailLevel,
+    thresholds: &RegressionThresholds,
+) -> Result<String, String> {
+    let (type_rows, regression_rows, failure_rows) = detail.limits();
+    let verdict = baseline.map(|b| detect_regressions(stats, b, thresholds)).transpose()?;
+
+    let mut summary = format!("## flakestorm robustness: {}", percent(stats.robustness_score));
+    if let Some(b) = baseline {
+        summary.push_str(&format!(
+            " ({} vs baseline)",
+            points(stats.robustness_score - b.robustness_score)
+        ));
+    }
+    summary.push_str("\n\n");
+    if let Some(verdict) = &verdict {
+        summary.push_str(&format!(
+            "**Regression gate: {}** ({} violations)\n\n",
+            verdict.status,
+            verdict.violations.len()
+        ));
+    }
+    summary.push_str(&format!(
+        "{} of {} mutations passed · p95 latency {:.0} ms\n",
+        stats.passed_mutations, stats.total_mutations, stats.p95_latency_ms
+    ));
+    if detail == DetailLevel::Full {
+        summary.push_str(&format!(
+            "\nLatency p50 {:.0} ms · p95 {:.0} ms · p99 {:.0} ms · mean {:.0} ms\n",
+            stats.p50_latency_ms, stats.p95_latency_ms, stats.p99_latency_ms, stats.avg_latency_ms
+        ));
+    }
+
+    type_table(&mut summary, stats, baseline, type_rows);
+
+    if let Some(verdict) = verdict.filter(|v| !v.violations.is_empty()) {
+        summary.push_str("\n### Top regressions\n\n");
+        // Worst first: failures before warnings, then by how far past its
+        // threshold each went; a zero threshold is crossed by any change
+        let overshoot = |v: &RegressionViolation| {
+            if v.threshold > 0.0 {
+                v.change / v.threshold
+            } else {
+                f64::INFINITY
+            }
+        };
+        let mut violations: Vec<&RegressionViolation> = verdict.violations.iter().collect();
+        violations.sort_by(|a, b| {
+            (b.severity == "fail")
+                .cmp(&(a.severity == "fail"))
+                .then(overshoot(b).total_cmp(&overshoot(a)))
+        });
+        let shown = regression_rows.unwrap_or(violations.len());
+        for v in violations.iter().take(shown) {
+            summary.push_str(&format!("- **{}** {}\n", v.severity, v.message));
+        }
+        if shown < verdict.violations.len() {
+            summary.push_str(&format!("- …and {} more\n", verdict.violations.len() - shown));
+        }
+    }
+
+    let failures = worst_failures(stats, failure_rows);
+    if !failures.is_empty() {
+        summary.push_str("\n### Worst failures\n\n");
+        for (count, check_type, message) in failures {
+            summary.push_str(&format!(
+                "- {}× `{}`: `{}`\n",
+                count,
+                code(&check_type, MAX_MESSAGE_CHARS),
+                code(&message, MAX_MESSAGE_CHARS)
+            ));
+        }
+    }
+
+    if detail == DetailLevel::Full && !stats.by_check.is_empty() {
+        summary.push_str("\n| Check | Pass rate | Failed |\n|---|---:|---:|\n");
+        for c in &stats.by_check {
+            summary.push_str(&format!(
+                "| {} | {} | {} |\n",
+                cell(&c.check_type, MAX_MESSAGE_CHARS),
+                percent(c.pass_rate),
+                c.failed
+            ));
+        }
+    }
+
+    if summary.len() > MAX_COMMENT_CHARS {
+        let mut end = MAX_COMMENT_CHARS - 40;
+        while !summary.is_char_boundary(end) {
+            end -= 1;
+        }
+        summary.truncate(end);
+        summary.push_str("\n\n…summary truncated\n");
+    }
+    Ok(summary)
+}
+
+/// Markdown summary of a run sized for a pull request comment.
+///
+/// With a `baseline`, the score and per-type pass rates show their change
+/// and the regression gate's violations are listed. `stats` and `baseline`
+/// come from `calculate_statistics` or `TestStatistics.from_json`.
+/// `detail` is "compact", "standard" or "full".
+#[pyfunction]
+#[pyo3(signature = (stats, baseline = None, detail = "standard", thresholds = None))]
+pub fn render_markdown_summary(
+    stats: TestStatistics,
+    baseline: Option<TestStatistics>,
+    detail: &str,
+    thresholds: Option<RegressionThresholds>,
+) -> PyResult<String> {
+    let detail = DetailLevel::parse(detail).map_err(PyValueError::new_err)?;
+    render_markdown(&stats, baseline.as_ref(), detail, &thresholds.unwrap_or_default()).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::failure_messages::FailureMessageConfig;
+    use crate::scoring::{calculate_statistics, CheckResult, MutationResult};
+    use crate::severity::Severity;
+
+    fn results(types: &[(&str, usize, usize)]) -> Vec<MutationResult> {
+        types
+            .iter()
+            .flat_map(|&(t, passed, total)| {
+                (0..total).map(move |i| MutationResult {
+                    mutation_type: t.to_string(),
+                    passed: i < passed,
+                    latency_ms: 100.0,
+                    checks: vec![CheckResult {
+                        check_type: "contains".to_string(),
+                        passed: i < passed,
+                        details: format!("missing | `Paris` in answer {}", i),
+                        severity: Severity::Error,
+                    }],
+                    ..Default::default()
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_summary_against_baseline() {
+        let baseline = calculate_statistics(&results(&[("noise", 19, 20), ("paraphrase", 20, 20)])).unwrap();
+        let run = results(&[("noise", 12, 20), ("paraphrase", 20, 20)]);
+        let stats = calculate_statistics(&run).unwrap()
+            .with_failure_messages(&run, &FailureMessageConfig::default())
+            .unwrap();
+        let summary = render_markdown(
+            &stats,
+            Some(&baseline),
+            DetailLevel::Standard,
+            &RegressionThresholds::default(),
+        )
+        .unwrap();
+        assert!(summary.starts_with("## flakestorm robustness: 80.0% (-17.5 pts vs baseline)\n"));
+        assert!(summary.contains("**Regression gate: fail** (2 violations)"));
+        assert!(summary.contains("| noise | 60.0% | -35.0 pts | 12/20 | 100 ms |"));
+        assert!(summary.find("| noise").unwrap() < summary.find("| paraphrase").unwrap());
+        assert!(summary.contains("- **fail** robustness_score dropped 17.5 points"));
+        assert!(summary.contains("- 8× `contains`: `missing | 'Paris' in answer 12`"));
+        assert!(!summary.contains("| Check |"));
+    }
+
+    #[test]
+    fn test_worst_regressions_first() {
+        let baseline = calculate_statistics(&results(&[("a", 20, 20), ("b", 20, 20), ("c", 20, 20), ("d", 20, 20)]))
+            .unwrap();
+        let current = calculate_statistics(&results(&[("a", 17, 20), ("b", 16, 20), ("c", 8, 20), ("d", 20, 20)]))
+            .unwrap();
+        let thresholds = RegressionThresholds::default();
+        let compact = render_markdown(&current, Some(&baseline), DetailLevel::Compact, &thresholds).unwrap();
+        let shown: Vec<&str> = compact.lines().filter(|l| l.starts_with("- **fail**")).collect();
+        assert_eq!(shown.len(), 3);
+        assert!(shown[0].contains("robustness_score") && shown[1].contains("c pass_rate"));
+        assert!(shown[2].contains("b pass_rate"));
+        assert!(compact.contains("- …and 1 more"));
+    }
+
+    #[test]
+    fn test_detail_levels() {
+        let run = results(&[("noise", 1, 2), ("paraphrase", 2, 2)]);
+        let stats = calculate_statistics(&run).unwrap();
+        let thresholds = RegressionThresholds::default();
+        let compact = render_markdown(&stats, None, DetailLevel::Compact, &thresholds).unwrap();
+        assert!(compact.starts_with("## flakestorm robustness: 75.0%\n"));
+        assert!(!compact.contains("| Mutation type |") && !compact.contains("Regression gate"));
+        assert!(compact.contains("- 1× `contains`: `missing | 'Paris' in answer 1`"));
+        let full = render_markdown(&stats, None, DetailLevel::Full, &thresholds).unwrap();
+        assert!(full.contains("| noise | 50.0% | - | 1/2 | 100 ms |"));
+        assert!(full.contains("| contains | 75.0% | 1 |"));
+        assert!(full.contains("Latency p50 100 ms"));
+
+        assert!(DetailLevel::parse("verbose").is_err());
+        assert_eq!(cell("a\n  b", 120), "a b");
+        assert_eq!(cell("abcdef", 4), "abc…");
+        assert_eq!((cell("a|`b`", 9), code("a|`b`", 9)), ("a\\|'b'".to_string(), "a|'b'".to_string()));
+    }
+}
@@ -0,0 +1,275 @@
+//! Multi-pattern string matching for flakestorm
+//!
+//! An Aho–Corasick automaton finds every occurrence of a large set of
+//! patterns in a single pass over the text, so scanning responses for
+//! thousands of secrets or keywords stays linear in the response length.
+
+use std::collections::{HashMap, VecDeque};
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// A single pattern occurrence. Offsets are in characters, not bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    pub pattern_index: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    next: HashMap<char, usize>,
+    fail: usize,
+    /// Indices of patterns ending at this node, including via suffix links
+    outputs: Vec<usize>,
+}
+
+/// Compiled Aho–Corasick automaton over a fixed pattern set.
+#[derive(Debug, Clone)]
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    pattern_lengths: Vec<usize>,
+    case_insensitive: bool,
+}
+
+fn fold_char(c: char, case_insensitive: bool) -> char {
+    if case_insensitive {
+        c.to_lowercase().next().unwrap_or(c)
+    } else {
+        c
+    }
+}
+
+impl AhoCorasick {
+    /// Build the automaton. Empty patterns are kept for index stability but never match.
+    pub fn new<S: AsRef<str>>(patterns: &[S], case_insensitive: bool) -> Self {
+        let mut nodes = vec![Node::default()];
+        let mut pattern_lengths = Vec::with_capacity(patterns.len());
+
+        for (index, pattern) in patterns.iter().enumerate() {
+            let pattern = pattern.as_ref();
+            pattern_lengths.push(pattern.chars().count());
+            if pattern.is_empty() {
+                continue;
+            }
+
+            let mut state = 0;
+            for c in pattern.chars().map(|c| fold_char(c, case_insensitive)) {
+                state = match nodes[state].next.get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::default());
+                        let next = nodes.len() - 1;
+                        nodes[state].next.insert(c, next);
+                        next
+                    }
+                };
+            }
+            nodes[state].outputs.push(index);
+        }
+
+        // Breadth-first pass to fill in failure links
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+        while let Some(state) = queue.pop_front() {
+            let edges: Vec<(char, usize)> =
+                nodes[state].next.iter().map(|(&c, &n)| (c, n)).collect();
+            for (c, child) in edges {
+                let mut fallback = nodes[state].fail;
+                let fail = loop {
+                    if let Some(&target) = nodes[fallback].next.get(&c) {
+                        break target;
+                    }
+                    if fallback == 0 {
+                        break 0;
+                    }
+                    fallback = nodes[fallback].fail;
+                };
+                nodes[child].fail = fail;
+                let inherited = nodes[fail].outputs.clone();
+                nodes[child].outputs.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        Self {
+            nodes,
+            pattern_lengths,
+            case_insensitive,
+        }
+    }
+
+    /// Follow the goto/failure transitions for one input character.
+    fn step(&self, mut state: usize, c: char) -> usize {
+        let c = fold_char(c, self.case_insensitive);
+        loop {
+            if let Some(&next) = self.nodes[state].next.get(&c) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+
+    /// Number of patterns the automaton was built from.
+    pub fn pattern_count(&self) -> usize {
+        self.pattern_lengths.len()
+    }
+
+    /// Find every (possibly overlapping) occurrence of every pattern in `text`.
+    pub fn find_all(&self, text: &str) -> Vec<PatternMatch> {
+        let mut matches = Vec::new();
+        let mut state = 0;
+
+        for (position, c) in text.chars().enumerate() {
+            state = self.step(state, c);
+
+            for &pattern_index in &self.nodes[state].outputs {
+                let end = position + 1;
+                matches.push(PatternMatch {
+                    pattern_index,
+                    start: end - self.pattern_lengths[pattern_index],
+                    end,
+                });
+            }
+        }
+
+        matches
+    }
+
+    /// Whether any pattern occurs in `text`.
+    pub fn is_match(&self, text: &str) -> bool {
+        let mut state = 0;
+        for c in text.chars() {
+            state = self.step(state, c);
+            if !self.nodes[state].outputs.is_empty() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Compiled multi-keyword matcher for deny-list and allow-list checks.
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct KeywordSet {
+    patterns: Vec<String>,
+    automaton: AhoCorasick,
+}
+
+impl KeywordSet {
+    pub fn new(patterns: Vec<String>, case_insensitive: bool) -> Self {
+        let automaton = AhoCorasick::new(&patterns, case_insensitive);
+        Self {
+            patterns,
+            automaton,
+        }
+    }
+
+    /// Distinct keywords found in `text`, in registration order.
+    pub fn matched_keywords(&self, text: &str) -> Vec<String> {
+        let mut seen = vec![false; self.patterns.len()];
+        for m in self.automaton.find_all(text) {
+            seen[m.pattern_index] = true;
+        }
+        seen.into_iter()
+            .zip(&self.patterns)
+            .filter(|(hit, _)| *hit)
+            .map(|(_, p)| p.clone())
+            .collect()
+    }
+}
+
+#[pymethods]
+impl KeywordSet {
+    /// Compile a keyword set from a list of patterns.
+    #[staticmethod]
+    #[pyo3(signature = (patterns, case_insensitive = false))]
+    fn build(patterns: Vec<String>, case_insensitive: bool) -> Self {
+        Self::new(patterns, case_insensitive)
+    }
+
+    /// All occurrences as (keyword, start, end) character spans.
+    fn find_all(&self, text: &str) -> Vec<(String, usize, usize)> {
+        self.automaton
+            .find_all(text)
+            .into_iter()
+            .map(|m| (self.patterns[m.pattern_index].clone(), m.start, m.end))
+            .collect()
+    }
+
+    /// Whether any keyword occurs in `text`.
+    fn contains_any(&self, text: &str) -> bool {
+        self.automaton.is_match(text)
+    }
+
+    /// Distinct keywords found in `text`.
+    #[pyo3(name = "matched_keywords")]
+    fn py_matched_keywords(&self, text: &str) -> Vec<String> {
+        self.matched_keywords(text)
+    }
+
+    /// `contains_any` over many texts, evaluated in parallel.
+    fn contains_any_batch(&self, py: Python<'_>, texts: Vec<String>) -> Vec<bool> {
+        py.allow_threads(|| {
+            texts
+                .par_iter()
+                .map(|t| self.automaton.is_match(t))
+                .collect()
+        })
+    }
+
+    fn __len__(&self) -> usize {
+        self.patterns.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_all_overlapping() {
+        let ac = AhoCorasick::new(&["he", "she", "his", "hers"], false);
+        let mut found: Vec<(usize, usize, usize)> = ac
+            .find_all("ushers")
+            .into_iter()
+            .map(|m| (m.pattern_index, m.start, m.end))
+            .collect();
+        found.sort();
+        assert_eq!(found, vec![(0, 2, 4), (1, 1, 4), (3, 2, 6)]);
+    }
+
+    #[test]
+    fn test_case_insensitive_and_unicode() {
+        let ac = AhoCorasick::new(&["SECRET-Ωmega"], true);
+        let matches = ac.find_all("the secret-ωmega key");
+        assert_eq!(matches.len(), 1);
+        assert_eq!((matches[0].start, matches[0].end), (4, 16));
+        assert!(!AhoCorasick::new(&["SECRET"], false).is_match("secret"));
+    }
+
+    #[test]
+    fn test_empty_patterns() {
+        let ac = AhoCorasick::new(&["", "x"], false);
+        assert_eq!(ac.pattern_count(), 2);
+        assert_eq!(ac.find_all("axa").len(), 1);
+        assert!(!AhoCorasick::new::<&str>(&[], false).is_match("anything"));
+    }
+
+    #[test]
+    fn test_keyword_set_matched_keywords() {
+        let set = KeywordSet::new(
+            vec!["password".into(), "api key".into(), "ssn".into()],
+            true,
+        );
+        assert_eq!(
+            set.matched_keywords("Your API key and PASSWORD, and password again"),
+            vec!["password".to_string(), "api key".to_string()]
+        );
+        assert!(set.matched_keywords("nothing to see").is_empty());
+    }
+}
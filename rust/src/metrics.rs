@@ -0,0 +1,196 @@
+//! Process-wide metrics registry
+//!
+//! Subsystems record counters, gauges and histograms under a name; the
+//! embedding host reads everything back with `get_metrics()` and forwards it
+//! to whatever sink it uses (Prometheus, logs, a custom exporter), so the
+//! Rust side never depends on a particular exporter.
+
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Default histogram buckets: upper bounds, suited to latencies in milliseconds.
+pub const DEFAULT_BUCKETS: &[f64] = &[1.0, 5.0, 10.0, 25.0, 50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0];
+
+/// Cumulative-bucket histogram
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    pub bounds: Vec<f64>,
+    /// One count per bound plus a final overflow bucket
+    pub counts: Vec<u64>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            counts: vec![0; bounds.len() + 1],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.partition_point(|&b| b < value);
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Snapshot of every metric, keyed by name
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetricsSnapshot {
+    pub counters: BTreeMap<String, u64>,
+    pub gauges: BTreeMap<String, f64>,
+    pub histograms: BTreeMap<String, Histogram>,
+}
+
+/// Thread-safe metric store
+#[derive(Debug, Default)]
+pub struct MetricsRegistry {
+    inner: Mutex<MetricsSnapshot>,
+}
+
+impl MetricsRegistry {
+    fn with<R>(&self, f: impl FnOnce(&mut MetricsSnapshot) -> R) -> R {
+        // A panic while holding the lock cannot leave the maps inconsistent
+        let mut guard = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        f(&mut guard)
+    }
+
+    pub fn increment(&self, name: &str, by: u64) {
+        self.with(|m| *m.counters.entry(name.to_string()).or_default() += by);
+    }
+
+    pub fn set_gauge(&self, name: &str, value: f64) {
+        self.with(|m| {
+            m.gauges.insert(name.to_string(), value);
+        });
+    }
+
+    /// Record a histogram observation; the first observation fixes the buckets.
+    pub fn observe(&self, name: &str, value: f64) {
+        self.with(|m| {
+            m.histograms
+                .entry(name.to_string())
+                .or_insert_with(|| Histogram::new(DEFAULT_BUCKETS))
+                .observe(value)
+        });
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.with(|m| m.clone())
+    }
+
+    pub fn reset(&self) {
+        self.with(|m| *m = MetricsSnapshot::default());
+    }
+}
+
+/// The process-wide registry.
+pub fn registry() -> &'static MetricsRegistry {
+    static REGISTRY: OnceLock<MetricsRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(MetricsRegistry::default)
+}
+
+/// Snapshot of all metrics as a dict.
+///
+/// Returns `{"counters": {name: int}, "gauges": {name: float},
+/// "histograms": {name: {"buckets": [(upper_bound, count), ...], "count": int, "sum": float}}}`.
+/// Bucket counts are per bucket, not cumulative; the last bound is `inf`.
+#[pyfunction]
+pub fn get_metrics(py: Python<'_>) -> PyResult<PyObject> {
+    let snapshot = registry().snapshot();
+
+    let histograms = PyDict::new(py);
+    for (name, h) in &snapshot.histograms {
+        let buckets: Vec<(f64, u64)> = h
+            .bounds
+            .iter()
+            .copied()
+            .chain(std::iter::once(f64::INFINITY))
+            .zip(h.counts.iter().copied())
+            .collect();
+        let entry = PyDict::new(py);
+        entry.set_item("buckets", buckets)?;
+        entry.set_item("count", h.count)?;
+        entry.set_item("sum", h.sum)?;
+        histograms.set_item(name, entry)?;
+    }
+
+    let out = PyDict::new(py);
+    out.set_item("counters", snapshot.counters)?;
+    out.set_item("gauges", snapshot.gauges)?;
+    out.set_item("histograms", histograms)?;
+    Ok(out.into())
+}
+
+/// Add `by` to a counter.
+#[pyfunction]
+#[pyo3(signature = (name, by = 1))]
+pub fn increment_counter(name: &str, by: u64) {
+    registry().increment(name, by);
+}
+
+/// Set a gauge to `value`.
+#[pyfunction]
+pub fn set_gauge(name: &str, value: f64) {
+    registry().set_gauge(name, value);
+}
+
+/// Record one observation in a histogram.
+#[pyfunction]
+pub fn observe_histogram(name: &str, value: f64) {
+    registry().observe(name, value);
+}
+
+/// Clear every metric.
+#[pyfunction]
+pub fn reset_metrics() {
+    registry().reset();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram_buckets() {
+        let mut h = Histogram::new(&[10.0, 100.0]);
+        for v in [1.0, 10.0, 11.0, 500.0] {
+            h.observe(v);
+        }
+        assert_eq!(h.counts, vec![2, 1, 1]);
+        assert_eq!(h.count, 4);
+        assert_eq!(h.sum, 522.0);
+    }
+
+    #[test]
+    fn test_registry_is_thread_safe() {
+        let reg = MetricsRegistry::default();
+        std::thread::scope(|s| {
+            for t in 0..8 {
+                let reg = &reg;
+                s.spawn(move || {
+                    for i in 0..1000 {
+                        reg.increment("mutations_processed", 1);
+                        reg.observe("latency_ms", (i % 100) as f64);
+                    }
+                    reg.set_gauge("worker", t as f64);
+                });
+            }
+        });
+        let snap = reg.snapshot();
+        assert_eq!(snap.counters["mutations_processed"], 8000);
+        assert_eq!(snap.histograms["latency_ms"].count, 8000);
+        assert!(snap.gauges.contains_key("worker"));
+
+        reg.reset();
+        assert_eq!(reg.snapshot(), MetricsSnapshot::default());
+    }
+}
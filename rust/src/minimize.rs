@@ -0,0 +1,190 @@
+//! Fuzzer-style corpus minimization
+//!
+//! Exploratory runs produce many mutations that fail the same way. Like
+//! afl-cmin, the minimizer keeps a small subset that still reproduces every
+//! observed failure mode, a (mutation type, failed check) pair, so a fast
+//! regression corpus can be distilled from a large run. Selection is greedy
+//! weighted set cover: repeatedly take the mutation that covers the most
+//! uncovered modes per unit of cost.
+
+use std::collections::{BTreeSet, HashSet};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::MutationResult;
+
+/// Check name recorded for a failed mutation with no failing check
+pub const UNATTRIBUTED_FAILURE: &str = "failed";
+
+/// (mutation type, failed check)
+pub type FailureMode = (String, String);
+
+/// Failure modes of one result; empty for a passing result.
+pub fn failure_modes(result: &MutationResult) -> Vec<FailureMode> {
+    let mut modes: Vec<FailureMode> = result
+        .checks
+        .iter()
+        .filter(|c| !c.passed)
+        .map(|c| (result.mutation_type.clone(), c.check_type.clone()))
+        .collect();
+    if modes.is_empty() && !result.passed {
+        modes.push((result.mutation_type.clone(), UNATTRIBUTED_FAILURE.to_string()));
+    }
+    modes.sort();
+    modes.dedup();
+    modes
+}
+
+/// A minimized corpus
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MinimizedCorpus {
+    /// Indices of the selected mutations, in selection order
+    pub selected: Vec<usize>,
+    /// Every failure mode observed, sorted
+    pub modes: Vec<FailureMode>,
+    /// Summed cost of the selection
+    pub cost: f64,
+}
+
+/// Greedy weighted set cover over per-item failure modes.
+///
+/// `costs` defaults to 1 per item; ties go to the lower index, so the
+/// result is deterministic.
+pub fn minimize(item_modes: &[Vec<FailureMode>], costs: Option<&[f64]>) -> Result<MinimizedCorpus, String> {
+    if let Some(costs) = costs {
+        if costs.len() != item_modes.len() {
+            return Err(format!("{} costs for {} items", costs.len(), item_modes.len()));
+        }
+        if let Some(bad) = costs.iter().find(|c| c.is_nan() || **c <= 0.0) {
+            return Err(format!("costs must be positive, got {}", bad));
+        }
+    }
+    let cost_of = |i: usize| costs.map_or(1.0, |c| c[i]);
+
+    let all: BTreeSet<&FailureMode> = item_modes.iter().flatten().collect();
+    let mut uncovered: HashSet<&FailureMode> = all.iter().copied().collect();
+    let mut selected = Vec::new();
+    let mut cost = 0.0;
+
+    while !uncovered.is_empty() {
+        let mut best: Option<(usize, f64)> = None;
+        for (i, modes) in item_modes.iter().enumerate() {
+            let gain = modes.iter().filter(|m| uncovered.contains(m)).count();
+            if gain == 0 {
+                continue;
+            }
+            let score = gain as f64 / cost_of(i);
+            if best.is_none_or(|(_, s)| score > s) {
+                best = Some((i, score));
+            }
+        }
+        let (i, _) = best.expect("an uncovered mode belongs to some item");
+        for m in &item_modes[i] {
+            uncovered.remove(m);
+        }
+        selected.push(i);
+        cost += cost_of(i);
+    }
+
+    Ok(MinimizedCorpus {
+        selected,
+        modes: all.into_iter().cloned().collect(),
+        cost,
+    })
+}
+
+/// Minimize a run's results; cost is each result's latency when `by_latency`.
+pub fn minimize_results(results: &[MutationResult], by_latency: bool) -> MinimizedCorpus {
+    let modes: Vec<Vec<FailureMode>> = results.iter().map(failure_modes).collect();
+    let costs: Option<Vec<f64>> = by_latency.then(|| results.iter().map(|r| r.latency_ms.max(1.0)).collect());
+    minimize(&modes, costs.as_deref()).expect("latency costs are positive")
+}
+
+/// Smallest subset of mutations covering every observed failure mode.
+///
+/// `mutation_types[i]` and `failed_checks[i]` describe mutation i; a
+/// mutation with `failed[i]` set but no failed checks counts as the mode
+/// (type, "failed"). Optional `costs` (e.g. latency) favor cheap mutations.
+#[pyfunction]
+#[pyo3(signature = (mutation_types, failed_checks, failed = None, costs = None))]
+pub fn minimize_corpus(
+    mutation_types: Vec<String>,
+    failed_checks: Vec<Vec<String>>,
+    failed: Option<Vec<bool>>,
+    costs: Option<Vec<f64>>,
+) -> PyResult<MinimizedCorpus> {
+    if mutation_types.len() != failed_checks.len() || failed.as_ref().is_some_and(|f| f.len() != mutation_types.len()) {
+        return Err(PyValueError::new_err("mutation_types, failed_checks and failed must have the same length"));
+    }
+    let modes: Vec<Vec<FailureMode>> = mutation_types
+        .iter()
+        .zip(&failed_checks)
+        .enumerate()
+        .map(|(i, (t, checks))| {
+            let mut modes: Vec<FailureMode> = checks.iter().map(|c| (t.clone(), c.clone())).collect();
+            if modes.is_empty() && failed.as_ref().is_some_and(|f| f[i]) {
+                modes.push((t.clone(), UNATTRIBUTED_FAILURE.to_string()));
+            }
+            modes
+        })
+        .collect();
+    minimize(&modes, costs.as_deref()).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::CheckResult;
+    use crate::severity::Severity;
+
+    fn result(t: &str, failed_checks: &[&str], passed: bool, latency_ms: f64) -> MutationResult {
+        MutationResult {
+            mutation_type: t.to_string(),
+            passed,
+            latency_ms,
+            checks: failed_checks
+                .iter()
+                .map(|c| CheckResult {
+                    check_type: c.to_string(),
+                    passed: false,
+                    details: String::new(),
+                    severity: Severity::Error,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_covers_every_mode_with_fewest_items() {
+        let results = vec![
+            result("noise", &["latency"], false, 10.0),
+            result("noise", &["latency", "similarity"], false, 10.0),
+            result("paraphrase", &[], true, 10.0),
+            result("paraphrase", &["valid_json"], false, 10.0),
+            result("noise", &["similarity"], false, 10.0),
+            result("homoglyph", &[], false, 10.0),
+        ];
+        let corpus = minimize_results(&results, false);
+        assert_eq!(corpus.selected, vec![1, 3, 5]);
+        assert_eq!(corpus.modes.len(), 4);
+        assert!(corpus.modes.contains(&("homoglyph".to_string(), "failed".to_string())));
+        assert_eq!(corpus.cost, 3.0);
+    }
+
+    #[test]
+    fn test_costs_prefer_cheap_items() {
+        let a = ("t".to_string(), "a".to_string());
+        let b = ("t".to_string(), "b".to_string());
+        let modes = vec![vec![a.clone(), b.clone()], vec![a], vec![b]];
+        assert_eq!(minimize(&modes, None).unwrap().selected, vec![0]);
+        let cheap = minimize(&modes, Some(&[10.0, 1.0, 1.0])).unwrap();
+        assert_eq!(cheap.selected, vec![1, 2]);
+        assert_eq!(cheap.cost, 2.0);
+        assert!(minimize(&modes, Some(&[1.0])).is_err());
+        assert!(minimize(&[], None).unwrap().selected.is_empty());
+    }
+}
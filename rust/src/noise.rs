@@ -0,0 +1,227 @@
+//! Native noise mutation engine
+//!
+//! Simulates sloppy typing: adjacent characters swapped, characters dropped
+//! or doubled, and keys replaced by a neighbour on a QWERTY keyboard. Every
+//! alphanumeric character is a candidate for one edit with probability
+//! `error_rate`; batches run in parallel with a per-prompt random stream.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::metrics::registry;
+use crate::rng::{resolve_seed, SplitMix64};
+
+/// QWERTY rows, each shifted half a key right of the one above.
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// Keys physically next to `c` on a QWERTY keyboard, in the same case.
+pub fn keyboard_neighbors(c: char) -> Vec<char> {
+    let lower = c.to_ascii_lowercase();
+    let rows: Vec<Vec<char>> = KEYBOARD_ROWS.iter().map(|r| r.chars().collect()).collect();
+    let Some((row, col)) = rows
+        .iter()
+        .enumerate()
+        .find_map(|(r, keys)| keys.iter().position(|&k| k == lower).map(|c| (r, c)))
+    else {
+        return Vec::new();
+    };
+
+    let col = col as isize;
+    let mut candidates = vec![(row, col - 1), (row, col + 1)];
+    if row > 0 {
+        candidates.extend([(row - 1, col), (row - 1, col + 1)]);
+    }
+    if row + 1 < rows.len() {
+        candidates.extend([(row + 1, col - 1), (row + 1, col)]);
+    }
+
+    candidates
+        .into_iter()
+        .filter_map(|(r, c)| usize::try_from(c).ok().and_then(|c| rows[r].get(c)))
+        .map(|&k| if c.is_ascii_uppercase() { k.to_ascii_uppercase() } else { k })
+        .collect()
+}
+
+/// A single typing error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseOp {
+    Swap,
+    Delete,
+    Duplicate,
+    Adjacent,
+}
+
+impl NoiseOp {
+    pub const ALL: [NoiseOp; 4] = [NoiseOp::Swap, NoiseOp::Delete, NoiseOp::Duplicate, NoiseOp::Adjacent];
+
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "swap" => Ok(NoiseOp::Swap),
+            "delete" => Ok(NoiseOp::Delete),
+            "duplicate" => Ok(NoiseOp::Duplicate),
+            "adjacent" | "keyboard" => Ok(NoiseOp::Adjacent),
+            other => Err(format!(
+                "unknown noise operation '{}' (expected swap, delete, duplicate or adjacent)",
+                other
+            )),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NoiseOp::Swap => "swap",
+            NoiseOp::Delete => "delete",
+            NoiseOp::Duplicate => "duplicate",
+            NoiseOp::Adjacent => "adjacent",
+        }
+    }
+}
+
+/// Noise mutator settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoiseConfig {
+    pub error_rate: f64,
+    pub ops: Vec<NoiseOp>,
+    pub seed: u64,
+}
+
+impl NoiseConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&self.error_rate) {
+            return Err(format!("error_rate must be in [0, 1], got {}", self.error_rate));
+        }
+        if self.ops.is_empty() {
+            return Err("at least one noise operation is required".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Apply typing noise to one prompt using `rng`.
+pub fn add_noise(text: &str, config: &NoiseConfig, rng: &mut SplitMix64) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len() + text.len() / 8);
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if !c.is_alphanumeric() || !rng.chance(config.error_rate) {
+            out.push(c);
+            i += 1;
+            continue;
+        }
+
+        match *rng.choose(&config.ops).expect("config has at least one op") {
+            NoiseOp::Swap if i + 1 < chars.len() && !chars[i + 1].is_whitespace() => {
+                out.push(chars[i + 1]);
+                out.push(c);
+                i += 1;
+            }
+            NoiseOp::Delete => {}
+            NoiseOp::Duplicate => {
+                out.push(c);
+                out.push(c);
+            }
+            NoiseOp::Adjacent => {
+                let neighbors = keyboard_neighbors(c);
+                out.push(*rng.choose(&neighbors).unwrap_or(&c));
+            }
+            // A swap with nothing to swap with leaves the character alone
+            NoiseOp::Swap => out.push(c),
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Apply noise to every prompt in parallel; prompt `i` uses its own stream,
+/// so results are reproducible for a given seed.
+pub fn add_noise_all(prompts: &[String], config: &NoiseConfig) -> Vec<String> {
+    prompts
+        .par_iter()
+        .enumerate()
+        .map(|(i, p)| add_noise(p, config, &mut SplitMix64::for_item(config.seed, i)))
+        .collect()
+}
+
+/// Typo-style noise mutations for a batch of prompts.
+///
+/// `operations` picks from "swap", "delete", "duplicate" and "adjacent"
+/// (keyboard-neighbour substitution); all four are used by default.
+#[pyfunction]
+#[pyo3(signature = (prompts, error_rate = 0.05, seed = None, operations = None))]
+pub fn noise_mutations(
+    py: Python<'_>,
+    prompts: Vec<String>,
+    error_rate: f64,
+    seed: Option<u64>,
+    operations: Option<Vec<String>>,
+) -> PyResult<Vec<String>> {
+    let seed = resolve_seed(seed);
+    let ops = match operations {
+        Some(names) => names
+            .iter()
+            .map(|n| NoiseOp::parse(n))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(PyValueError::new_err)?,
+        None => NoiseOp::ALL.to_vec(),
+    };
+    let config = NoiseConfig { error_rate, ops, seed };
+    config.validate().map_err(PyValueError::new_err)?;
+    registry().increment("mutations_generated.noise", prompts.len() as u64);
+    Ok(py.allow_threads(|| add_noise_all(&prompts, &config)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::edit_distance::levenshtein;
+
+    fn config(error_rate: f64, ops: &[NoiseOp]) -> NoiseConfig {
+        NoiseConfig {
+            error_rate,
+            ops: ops.to_vec(),
+            seed: 42,
+        }
+    }
+
+    #[test]
+    fn test_keyboard_neighbors() {
+        let mut g = keyboard_neighbors('g');
+        g.sort();
+        assert_eq!(g, vec!['b', 'f', 'h', 't', 'v', 'y']);
+        assert_eq!(keyboard_neighbors('Q').len(), 4);
+        assert!(keyboard_neighbors('Q').iter().all(|c| c.is_ascii_uppercase() || c.is_ascii_digit()));
+        assert!(keyboard_neighbors('é').is_empty());
+    }
+
+    #[test]
+    fn test_noise_is_reproducible_and_bounded() {
+        let prompts: Vec<String> = (0..50).map(|i| format!("Book flight number {} to Paris please", i)).collect();
+        let cfg = config(0.1, &NoiseOp::ALL);
+        let a = add_noise_all(&prompts, &cfg);
+        assert_eq!(a, add_noise_all(&prompts, &cfg));
+        assert!(a.iter().zip(&prompts).any(|(m, p)| m != p));
+        for (m, p) in a.iter().zip(&prompts) {
+            // each source char takes part in at most one edit of cost <= 2
+            assert!(levenshtein(m, p) <= 2 * p.chars().count());
+        }
+
+        assert_eq!(add_noise_all(&prompts, &config(0.0, &NoiseOp::ALL)), prompts);
+    }
+
+    #[test]
+    fn test_single_operations() {
+        let mut rng = SplitMix64::new(1);
+        assert_eq!(add_noise("abc def", &config(1.0, &[NoiseOp::Delete]), &mut rng), " ");
+        assert_eq!(add_noise("ab", &config(1.0, &[NoiseOp::Duplicate]), &mut rng), "aabb");
+        assert_eq!(add_noise("ab c", &config(1.0, &[NoiseOp::Swap]), &mut rng), "ba c");
+
+        let adjacent = add_noise("gg", &config(1.0, &[NoiseOp::Adjacent]), &mut rng);
+        assert!(adjacent.chars().all(|c| keyboard_neighbors('g').contains(&c)));
+
+        assert!(config(1.5, &NoiseOp::ALL).validate().is_err());
+        assert!(config(0.1, &[]).validate().is_err());
+    }
+}
@@ -0,0 +1,225 @@
+//! Canonical text normalization
+//!
+//! Mutation validation and invariant checks both need to decide whether two
+//! strings are "the same" once presentation differences are removed. The
+//! steps here run in a fixed order: invisible code points are stripped, the
+//! text is put in a Unicode normalization form, case is folded and
+//! whitespace runs are collapsed.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::sanitize::PyResponseSanitizer;
+use crate::unicode::is_invisible;
+
+/// Unicode normalization form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalForm {
+    None,
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl NormalForm {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "" | "none" => Ok(NormalForm::None),
+            "nfc" => Ok(NormalForm::Nfc),
+            "nfd" => Ok(NormalForm::Nfd),
+            "nfkc" => Ok(NormalForm::Nfkc),
+            "nfkd" => Ok(NormalForm::Nfkd),
+            other => Err(format!(
+                "unknown normalization form '{}' (expected none, nfc, nfd, nfkc or nfkd)",
+                other
+            )),
+        }
+    }
+
+    fn apply(self, text: &str) -> String {
+        match self {
+            NormalForm::None => text.to_string(),
+            NormalForm::Nfc => text.nfc().collect(),
+            NormalForm::Nfd => text.nfd().collect(),
+            NormalForm::Nfkc => text.nfkc().collect(),
+            NormalForm::Nfkd => text.nfkd().collect(),
+        }
+    }
+}
+
+/// Which normalization steps to apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    pub form: NormalForm,
+    pub casefold: bool,
+    pub strip_invisible: bool,
+    pub collapse_whitespace: bool,
+}
+
+impl Default for NormalizeOptions {
+    fn default() -> Self {
+        Self {
+            form: NormalForm::Nfkc,
+            casefold: false,
+            strip_invisible: true,
+            collapse_whitespace: true,
+        }
+    }
+}
+
+/// Lowercase with the full case foldings that differ from `to_lowercase`
+/// for common text (German sharp s, Greek final sigma).
+fn casefold(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            'ß' | 'ẞ' => out.push_str("ss"),
+            'ς' => out.push('σ'),
+            _ => out.extend(c.to_lowercase()),
+        }
+    }
+    out
+}
+
+/// Replace every whitespace run with one space and trim both ends.
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Bring `text` to its canonical form under `opts`.
+pub fn normalize(text: &str, opts: &NormalizeOptions) -> String {
+    let mut out = if opts.strip_invisible {
+        text.chars().filter(|&c| !is_invisible(c)).collect()
+    } else {
+        text.to_string()
+    };
+    out = opts.form.apply(&out);
+    if opts.casefold {
+        out = casefold(&out);
+    }
+    if opts.collapse_whitespace {
+        out = collapse_whitespace(&out);
+    }
+    out
+}
+
+fn options(
+    form: &str,
+    casefold: bool,
+    strip_invisible: bool,
+    collapse_whitespace: bool,
+) -> PyResult<NormalizeOptions> {
+    Ok(NormalizeOptions {
+        form: NormalForm::parse(form).map_err(PyValueError::new_err)?,
+        casefold,
+        strip_invisible,
+        collapse_whitespace,
+    })
+}
+
+/// Canonical form of a string.
+///
+/// `form` is one of "none", "nfc", "nfd", "nfkc" or "nfkd".
+#[pyfunction]
+#[pyo3(signature = (text, form = "nfkc", casefold = false, strip_invisible = true, collapse_whitespace = true))]
+pub fn normalize_text(
+    text: &str,
+    form: &str,
+    casefold: bool,
+    strip_invisible: bool,
+    collapse_whitespace: bool,
+) -> PyResult<String> {
+    let opts = options(form, casefold, strip_invisible, collapse_whitespace)?;
+    Ok(normalize(text, &opts))
+}
+
+/// Canonical forms of many strings, computed in parallel.
+#[pyfunction]
+#[pyo3(signature = (texts, form = "nfkc", casefold = false, strip_invisible = true, collapse_whitespace = true))]
+pub fn normalize_texts(
+    py: Python<'_>,
+    texts: Vec<String>,
+    form: &str,
+    casefold: bool,
+    strip_invisible: bool,
+    collapse_whitespace: bool,
+) -> PyResult<Vec<String>> {
+    let opts = options(form, casefold, strip_invisible, collapse_whitespace)?;
+    Ok(py.allow_threads(|| texts.par_iter().map(|t| normalize(t, &opts)).collect()))
+}
+
+/// Whether two strings are equal after normalization, and after a
+/// `sanitizer` (ResponseSanitizer) strips its noise when one is given.
+#[pyfunction]
+#[pyo3(signature = (
+    a, b, form = "nfkc", casefold = true, strip_invisible = true, collapse_whitespace = true, sanitizer = None
+))]
+pub fn canonical_equals(
+    a: &str,
+    b: &str,
+    form: &str,
+    casefold: bool,
+    strip_invisible: bool,
+    collapse_whitespace: bool,
+    sanitizer: Option<PyRef<'_, PyResponseSanitizer>>,
+) -> PyResult<bool> {
+    let opts = options(form, casefold, strip_invisible, collapse_whitespace)?;
+    Ok(match sanitizer {
+        Some(s) => {
+            let s = s.sanitizer();
+            normalize(&s.sanitize(a), &opts) == normalize(&s.sanitize(b), &opts)
+        }
+        None => normalize(a, &opts) == normalize(b, &opts),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_forms() {
+        let composed = "caf\u{E9}";
+        let decomposed = "cafe\u{301}";
+        let nfc = NormalizeOptions {
+            form: NormalForm::Nfc,
+            ..Default::default()
+        };
+        assert_eq!(normalize(decomposed, &nfc), composed);
+        assert_eq!(normalize(composed, &NormalizeOptions {
+            form: NormalForm::Nfd,
+            ..nfc
+        }), decomposed);
+
+        // NFKC folds compatibility characters, NFC does not
+        assert_eq!(normalize("ｆｉｌｅ\u{FB01}", &NormalizeOptions::default()), "filefi");
+        assert_eq!(normalize("\u{FB01}", &nfc), "\u{FB01}");
+
+        assert!(NormalForm::parse("NFKC").is_ok());
+        assert!(NormalForm::parse("nfx").is_err());
+    }
+
+    #[test]
+    fn test_casefold_strip_and_collapse() {
+        let opts = NormalizeOptions {
+            casefold: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            normalize("  Straße\u{200B} \t\n IST  groß ", &opts),
+            "strasse ist gross"
+        );
+        assert_eq!(normalize("ΟΔΟΣ", &opts), normalize("οδος", &opts));
+
+        let raw = NormalizeOptions {
+            form: NormalForm::None,
+            casefold: false,
+            strip_invisible: false,
+            collapse_whitespace: false,
+        };
+        assert_eq!(normalize(" a\u{200B}  B ", &raw), " a\u{200B}  B ");
+    }
+}
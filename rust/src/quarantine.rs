@@ -0,0 +1,224 @@
+//! Quarantine lists for flaky mutations
+//!
+//! At the start of a CI job the orchestrator needs to know which
+//! mutations to leave out, or to count for less, because their history
+//! says their results are noise. Mutations with at least `min_runs` runs
+//! of history are scored with the flakiness index; those at or above
+//! `threshold` are down-weighted to `1 - index`, and those at or above
+//! `exclude_threshold` are excluded outright. Each entry carries the
+//! statistics that put it on the list.
+//!
+//! The list is deterministic for a given history (entries are ordered by
+//! index, then id) so it can be cached and diffed between jobs.
+
+use std::path::Path;
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::flakiness::{flakiness_report, history_rows, MutationFlakiness};
+
+/// Quarantine policy
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct QuarantinePolicy {
+    /// Flakiness index at which a mutation is down-weighted
+    pub threshold: f64,
+    /// Flakiness index at which a mutation is excluded
+    pub exclude_threshold: f64,
+    /// Histories shorter than this are never quarantined
+    pub min_runs: usize,
+}
+
+impl Default for QuarantinePolicy {
+    fn default() -> Self {
+        Self {
+            threshold: 0.3,
+            exclude_threshold: 0.6,
+            min_runs: 3,
+        }
+    }
+}
+
+impl QuarantinePolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, value) in [("threshold", self.threshold), ("exclude_threshold", self.exclude_threshold)] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} must be between 0 and 1, got {}", name, value));
+            }
+        }
+        if self.exclude_threshold < self.threshold {
+            return Err(format!(
+                "exclude_threshold ({}) must be at least threshold ({})",
+                self.exclude_threshold, self.threshold
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// One quarantined mutation and why
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantineEntry {
+    pub mutation_id: String,
+    pub mutation_type: String,
+    /// "exclude" or "downweight"
+    pub action: String,
+    /// Weight multiplier to apply; 0.0 when excluded
+    pub weight: f64,
+    pub runs: usize,
+    pub pass_rate: f64,
+    pub flips: usize,
+    pub flakiness_index: f64,
+}
+
+/// Mutations to exclude or down-weight, with the policy that chose them
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuarantineList {
+    pub threshold: f64,
+    pub exclude_threshold: f64,
+    pub min_runs: usize,
+    /// Mutations considered, including those left out of quarantine
+    pub evaluated: usize,
+    pub entries: Vec<QuarantineEntry>,
+}
+
+impl QuarantineList {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("quarantine list serializes")
+    }
+}
+
+fn entry(m: &MutationFlakiness, policy: &QuarantinePolicy) -> Option<QuarantineEntry> {
+    if m.runs < policy.min_runs || m.flakiness_index < policy.threshold {
+        return None;
+    }
+    let exclude = m.flakiness_index >= policy.exclude_threshold;
+    Some(QuarantineEntry {
+        mutation_id: m.mutation_id.clone(),
+        mutation_type: m.mutation_type.clone(),
+        action: if exclude { "exclude" } else { "downweight" }.to_string(),
+        weight: if exclude { 0.0 } else { 1.0 - m.flakiness_index },
+        runs: m.runs,
+        pass_rate: m.pass_rate,
+        flips: m.flips,
+        flakiness_index: m.flakiness_index,
+    })
+}
+
+/// Quarantine list for (id, type, history) rows, histories oldest run first.
+pub fn build_quarantine(histories: &[(String, String, Vec<bool>)], policy: &QuarantinePolicy) -> Result<QuarantineList, String> {
+    policy.validate()?;
+    let report = flakiness_report(histories, policy.threshold)?;
+    Ok(QuarantineList {
+        threshold: policy.threshold,
+        exclude_threshold: policy.exclude_threshold,
+        min_runs: policy.min_runs,
+        evaluated: report.mutations.len(),
+        entries: report.mutations.iter().filter_map(|m| entry(m, policy)).collect(),
+    })
+}
+
+#[pymethods]
+impl QuarantineList {
+    /// The list as pretty-printed JSON.
+    #[pyo3(name = "to_json")]
+    fn py_to_json(&self) -> String {
+        self.to_json()
+    }
+
+    /// Write the list as JSON to `path`.
+    fn save(&self, path: &str) -> PyResult<()> {
+        std::fs::write(Path::new(path), self.to_json()).map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))
+    }
+
+    /// IDs of the mutations to exclude entirely.
+    fn excluded(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|e| e.action == "exclude")
+            .map(|e| e.mutation_id.clone())
+            .collect()
+    }
+}
+
+/// Build a quarantine list from pass/fail history.
+///
+/// `histories[i]` is the history of `mutation_ids[i]`, oldest run first.
+#[pyfunction]
+#[pyo3(signature = (mutation_ids, mutation_types, histories, threshold = 0.3, exclude_threshold = 0.6, min_runs = 3))]
+pub fn quarantine_list(
+    py: Python<'_>,
+    mutation_ids: Vec<String>,
+    mutation_types: Vec<String>,
+    histories: Vec<Vec<bool>>,
+    threshold: f64,
+    exclude_threshold: f64,
+    min_runs: usize,
+) -> PyResult<QuarantineList> {
+    let rows = history_rows(mutation_ids, mutation_types, histories).map_err(PyValueError::new_err)?;
+    let policy = QuarantinePolicy {
+        threshold,
+        exclude_threshold,
+        min_runs,
+    };
+    py.allow_threads(|| build_quarantine(&rows, &policy))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(id: &str, history: &[bool]) -> (String, String, Vec<bool>) {
+        (id.to_string(), "noise".to_string(), history.to_vec())
+    }
+
+    #[test]
+    fn test_actions_and_order() {
+        let (t, f) = (true, false);
+        let rows = vec![
+            row("steady", &[t, t, t, t]),
+            row("alternating", &[t, f, t, f, t, f]),
+            row("wobbly", &[t, t, t, f, f, t, t, t, t, t]),
+            row("new", &[t, f]),
+        ];
+        let list = build_quarantine(&rows, &QuarantinePolicy::default()).unwrap();
+        assert_eq!(list.evaluated, 4);
+        let ids: Vec<(&str, &str)> = list
+            .entries
+            .iter()
+            .map(|e| (e.mutation_id.as_str(), e.action.as_str()))
+            .collect();
+        assert_eq!(ids, vec![("alternating", "exclude"), ("wobbly", "downweight")]);
+        assert_eq!(list.entries[0].weight, 0.0);
+        let wobbly = &list.entries[1];
+        assert!((wobbly.weight - (1.0 - wobbly.flakiness_index)).abs() < 1e-12);
+
+        // Same history, same bytes
+        let again = build_quarantine(&rows, &QuarantinePolicy::default()).unwrap();
+        assert_eq!(list.to_json(), again.to_json());
+        let parsed: QuarantineList = serde_json::from_str(&list.to_json()).unwrap();
+        assert_eq!(parsed.entries.len(), 2);
+        assert_eq!(parsed.entries[1].flips, wobbly.flips);
+    }
+
+    #[test]
+    fn test_policy_validation() {
+        let policy = QuarantinePolicy {
+            exclude_threshold: 0.1,
+            ..Default::default()
+        };
+        assert!(build_quarantine(&[], &policy).is_err());
+        for exclude_threshold in [f64::NAN, 1.5] {
+            let policy = QuarantinePolicy {
+                exclude_threshold,
+                ..Default::default()
+            };
+            assert!(build_quarantine(&[], &policy).unwrap_err().starts_with("exclude_threshold must be between"));
+        }
+        assert!(history_rows(vec!["a".into()], vec![], vec![vec![true]]).unwrap_err().contains("0 types"));
+    }
+}
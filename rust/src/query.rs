@@ -0,0 +1,435 @@
+//! Filtering, sorting and pagination over a run's stored results
+//!
+//! A run's results live in a `ResultSpool` JSONL file, so a query streams
+//! the store instead of taking the results from Python. Each page is one
+//! pass over the records: unsorted queries skip the records before the
+//! cursor without parsing them and stop once the page is full, and sorted
+//! queries keep only a bounded top-k of the matching rows. Pages resume
+//! from a `ResultCursor` (keyset pagination on the sort key and the
+//! record's position), so browsing a million-row run never holds more
+//! than one page in memory.
+
+use std::cmp::Ordering;
+use std::io::{Error, ErrorKind};
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::scoring::MutationResult;
+use crate::severity::Severity;
+use crate::spool::PyResultSpool;
+
+/// Field a query can be ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    LatencyMs,
+    Weight,
+    MutationType,
+}
+
+impl SortKey {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "latency_ms" | "latency" => Some(SortKey::LatencyMs),
+            "weight" => Some(SortKey::Weight),
+            "mutation_type" | "type" => Some(SortKey::MutationType),
+            _ => None,
+        }
+    }
+
+    fn compare(self, a: &MutationResult, b: &MutationResult) -> Ordering {
+        match self {
+            SortKey::LatencyMs => a.latency_ms.total_cmp(&b.latency_ms),
+            SortKey::Weight => a.weight.total_cmp(&b.weight),
+            SortKey::MutationType => a.mutation_type.cmp(&b.mutation_type),
+        }
+    }
+}
+
+/// Where the next page of a query resumes
+#[pyclass]
+#[derive(Debug, Clone)]
+pub struct ResultCursor {
+    /// Store position of the last result returned
+    #[pyo3(get)]
+    pub position: usize,
+    sort: Option<(SortKey, bool)>,
+    /// The last result returned, to resume a sorted query after its key
+    last: Option<MutationResult>,
+}
+
+/// One page of a result query
+#[pyclass(get_all)]
+#[derive(Debug, Clone)]
+pub struct ResultPage {
+    pub results: Vec<MutationResult>,
+    /// Pass to the next query for the following page; None on the last page
+    pub next_cursor: Option<ResultCursor>,
+}
+
+/// A reusable filter + sort specification over stored results
+#[derive(Debug, Clone, Default)]
+pub struct ResultQuery {
+    mutation_types: Vec<String>,
+    passed: Option<bool>,
+    min_severity: Option<Severity>,
+    since_ms: Option<f64>,
+    until_ms: Option<f64>,
+    sort: Option<(SortKey, bool)>,
+}
+
+impl ResultQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only keep results of this mutation type (may be given several times).
+    pub fn mutation_type(mut self, mutation_type: impl Into<String>) -> Self {
+        self.mutation_types.push(mutation_type.into());
+        self
+    }
+
+    /// Only keep passed (`true`) or failed (`false`) results.
+    pub fn passed(mut self, passed: bool) -> Self {
+        self.passed = Some(passed);
+        self
+    }
+
+    /// Only keep results with a failed check of at least `severity`.
+    pub fn min_severity(mut self, severity: Severity) -> Self {
+        self.min_severity = Some(severity);
+        self
+    }
+
+    /// Only keep results timestamped in `since_ms..until_ms`, either bound
+    /// open when `None`; results without a timestamp never match a range.
+    pub fn time_range(mut self, since_ms: Option<f64>, until_ms: Option<f64>) -> Self {
+        self.since_ms = since_ms;
+        self.until_ms = until_ms;
+        self
+    }
+
+    /// Order results by `key`; ties keep their store order.
+    pub fn sort_by(mut self, key: SortKey, descending: bool) -> Self {
+        self.sort = Some((key, descending));
+        self
+    }
+
+    fn matches(&self, result: &MutationResult) -> bool {
+        let in_range = match (self.since_ms, self.until_ms) {
+            (None, None) => true,
+            (since, until) => result
+                .timestamp_ms
+                .is_some_and(|t| since.is_none_or(|s| t >= s) && until.is_none_or(|u| t < u)),
+        };
+        (self.mutation_types.is_empty() || self.mutation_types.contains(&result.mutation_type))
+            && self.passed.is_none_or(|p| p == result.passed)
+            && self
+                .min_severity
+                .is_none_or(|min| result.checks.iter().any(|c| !c.passed && c.severity >= min))
+            && in_range
+    }
+
+    /// Page order of two (position, result) rows.
+    fn order(&self, a: (usize, &MutationResult), b: (usize, &MutationResult)) -> Ordering {
+        let by_key = match self.sort {
+            None => Ordering::Equal,
+            Some((key, false)) => key.compare(a.1, b.1),
+            Some((key, true)) => key.compare(a.1, b.1).reverse(),
+        };
+        by_key.then(a.0.cmp(&b.0))
+    }
+
+    /// Number of stored results matching the filters.
+    pub fn count(&self, lines: impl Iterator<Item = std::io::Result<String>>) -> std::io::Result<usize> {
+        let mut n = 0;
+        for (position, line) in lines.enumerate() {
+            n += usize::from(self.matches(&parse_line(position, &line?)?));
+        }
+        Ok(n)
+    }
+
+    /// The `page_size` matching results after `cursor` (from the start
+    /// without one) from a store's JSONL record lines.
+    pub fn page_after(
+        &self,
+        lines: impl Iterator<Item = std::io::Result<String>>,
+        cursor: Option<&ResultCursor>,
+        page_size: usize,
+    ) -> std::io::Result<ResultPage> {
+        if page_size == 0 {
+            return Err(Error::new(ErrorKind::InvalidInput, "page_size must be at least 1"));
+        }
+        if cursor.is_some_and(|c| c.sort != self.sort) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "cursor comes from a query with a different sort order",
+            ));
+        }
+        let after = cursor.map(|c| (c.position, c.last.as_ref()));
+
+        // The best page_size + 1 rows in page order; the extra row tells
+        // whether another page follows
+        let mut rows: Vec<(usize, MutationResult)> = Vec::new();
+        for (position, line) in lines.enumerate() {
+            let line = line?;
+            if self.sort.is_none() && after.is_some_and(|(p, _)| position <= p) {
+                continue;
+            }
+            let result = parse_line(position, &line)?;
+            if !self.matches(&result) {
+                continue;
+            }
+            if self.sort.is_none() {
+                rows.push((position, result));
+                if rows.len() > page_size {
+                    break;
+                }
+                continue;
+            }
+            if let Some((p, Some(last))) = after {
+                if self.order((position, &result), (p, last)) != Ordering::Greater {
+                    continue;
+                }
+            }
+            let at = rows.partition_point(|(p, r)| self.order((*p, r), (position, &result)) == Ordering::Less);
+            if at <= page_size {
+                rows.insert(at, (position, result));
+                rows.truncate(page_size + 1);
+            }
+        }
+
+        let more = rows.len() > page_size;
+        rows.truncate(page_size);
+        let next_cursor = match rows.last() {
+            Some((position, last)) if more => Some(ResultCursor {
+                position: *position,
+                sort: self.sort,
+                last: self.sort.map(|_| last.clone()),
+            }),
+            _ => None,
+        };
+        Ok(ResultPage {
+            results: rows.into_iter().map(|(_, r)| r).collect(),
+            next_cursor,
+        })
+    }
+}
+
+fn parse_line(position: usize, line: &str) -> std::io::Result<MutationResult> {
+    serde_json::from_str(line).map_err(|e| Error::new(ErrorKind::InvalidData, format!("record {}: {}", position, e)))
+}
+
+fn filter_query(
+    mutation_types: Option<Vec<String>>,
+    passed: Option<bool>,
+    min_severity: Option<&str>,
+    since_ms: Option<f64>,
+    until_ms: Option<f64>,
+) -> PyResult<ResultQuery> {
+    let mut query = ResultQuery::new().time_range(since_ms, until_ms);
+    for mutation_type in mutation_types.unwrap_or_default() {
+        query = query.mutation_type(mutation_type);
+    }
+    if let Some(passed) = passed {
+        query = query.passed(passed);
+    }
+    if let Some(severity) = min_severity {
+        query = query.min_severity(Severity::parse(severity).map_err(PyValueError::new_err)?);
+    }
+    Ok(query)
+}
+
+fn query_err(spool: &PyResultSpool, e: Error) -> PyErr {
+    let message = format!("{}: {}", spool.spool().path().display(), e);
+    match e.kind() {
+        ErrorKind::InvalidInput | ErrorKind::InvalidData => PyValueError::new_err(message),
+        _ => PyIOError::new_err(message),
+    }
+}
+
+/// Filter, sort and page through the results stored in a `ResultSpool`.
+///
+/// `min_severity` keeps results with a failed check at least that severe;
+/// `since_ms` and `until_ms` bound `timestamp_ms`, inclusive and exclusive.
+/// `sort_by` is "latency_ms", "weight" or "mutation_type". Pass a page's
+/// `next_cursor` back with the same filters and sort for the next page.
+/// The store is streamed, so only the returned page is held in memory.
+#[pyfunction]
+#[pyo3(signature = (
+    spool, mutation_types = None, passed = None, min_severity = None, since_ms = None, until_ms = None,
+    sort_by = None, descending = false, cursor = None, page_size = 100
+))]
+#[allow(clippy::too_many_arguments)]
+pub fn query_results(
+    py: Python<'_>,
+    spool: PyRef<'_, PyResultSpool>,
+    mutation_types: Option<Vec<String>>,
+    passed: Option<bool>,
+    min_severity: Option<&str>,
+    since_ms: Option<f64>,
+    until_ms: Option<f64>,
+    sort_by: Option<&str>,
+    descending: bool,
+    cursor: Option<ResultCursor>,
+    page_size: usize,
+) -> PyResult<ResultPage> {
+    let mut query = filter_query(mutation_types, passed, min_severity, since_ms, until_ms)?;
+    if let Some(name) = sort_by {
+        let key = SortKey::parse(name).ok_or_else(|| {
+            PyValueError::new_err(format!(
+                "unknown sort key '{}' (expected latency_ms, weight or mutation_type)",
+                name
+            ))
+        })?;
+        query = query.sort_by(key, descending);
+    }
+    let store = spool.spool();
+    py.allow_threads(|| query.page_after(store.iter_lines()?, cursor.as_ref(), page_size))
+        .map_err(|e| query_err(&spool, e))
+}
+
+/// Number of results in a `ResultSpool` matching the filters of
+/// `query_results`.
+#[pyfunction]
+#[pyo3(signature = (
+    spool, mutation_types = None, passed = None, min_severity = None, since_ms = None, until_ms = None
+))]
+pub fn count_results(
+    py: Python<'_>,
+    spool: PyRef<'_, PyResultSpool>,
+    mutation_types: Option<Vec<String>>,
+    passed: Option<bool>,
+    min_severity: Option<&str>,
+    since_ms: Option<f64>,
+    until_ms: Option<f64>,
+) -> PyResult<usize> {
+    let query = filter_query(mutation_types, passed, min_severity, since_ms, until_ms)?;
+    let store = spool.spool();
+    py.allow_threads(|| query.count(store.iter_lines()?))
+        .map_err(|e| query_err(&spool, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::CheckResult;
+
+    fn result(mutation_type: &str, passed: bool, latency_ms: f64) -> MutationResult {
+        MutationResult {
+            mutation_type: mutation_type.to_string(),
+            passed,
+            latency_ms,
+            ..Default::default()
+        }
+    }
+
+    fn lines(results: &[MutationResult]) -> impl Iterator<Item = std::io::Result<String>> + '_ {
+        results.iter().map(|r| Ok(serde_json::to_string(r).unwrap()))
+    }
+
+    fn latencies(page: &ResultPage) -> Vec<f64> {
+        page.results.iter().map(|r| r.latency_ms).collect()
+    }
+
+    #[test]
+    fn test_filter_and_paginate() {
+        let results: Vec<MutationResult> = (0..25)
+            .map(|i| result(if i % 2 == 0 { "noise" } else { "paraphrase" }, i % 3 != 0, i as f64))
+            .collect();
+
+        let query = ResultQuery::new().mutation_type("noise").passed(false);
+        assert_eq!(query.count(lines(&results)).unwrap(), 5); // 0, 6, 12, 18, 24
+
+        let first = query.page_after(lines(&results), None, 2).unwrap();
+        assert_eq!(latencies(&first), vec![0.0, 6.0]);
+        let cursor = first.next_cursor.unwrap();
+        assert_eq!(cursor.position, 6);
+        let second = query.page_after(lines(&results), Some(&cursor), 2).unwrap();
+        assert_eq!(latencies(&second), vec![12.0, 18.0]);
+        let last = query.page_after(lines(&results), second.next_cursor.as_ref(), 2).unwrap();
+        assert_eq!(latencies(&last), vec![24.0]);
+        assert!(last.next_cursor.is_none());
+        assert!(query.page_after(lines(&results), None, 0).is_err());
+    }
+
+    #[test]
+    fn test_sorted_query_resumes_after_key() {
+        let results = vec![
+            result("noise", true, 30.0),
+            result("paraphrase", false, 10.0),
+            result("noise", false, 20.0),
+            result("noise", false, 30.0),
+            result("paraphrase", true, 5.0),
+        ];
+        let slowest = ResultQuery::new().sort_by(SortKey::LatencyMs, true);
+        let mut pages = Vec::new();
+        let mut cursor: Option<ResultCursor> = None;
+        loop {
+            let page = slowest.page_after(lines(&results), cursor.as_ref(), 2).unwrap();
+            pages.push(latencies(&page));
+            cursor = page.next_cursor;
+            if cursor.is_none() {
+                break;
+            }
+        }
+        assert_eq!(pages, vec![vec![30.0, 30.0], vec![20.0, 10.0], vec![5.0]]);
+        assert_eq!(SortKey::parse("type"), Some(SortKey::MutationType));
+
+        let first = slowest.page_after(lines(&results), None, 2).unwrap();
+        let unsorted = ResultQuery::new();
+        assert!(unsorted.page_after(lines(&results), first.next_cursor.as_ref(), 2).is_err());
+    }
+
+    #[test]
+    fn test_bad_record_is_reported() {
+        let stored = vec![Ok(serde_json::to_string(&result("noise", true, 1.0)).unwrap()), Ok("{".to_string())];
+        let err = ResultQuery::new().page_after(stored.into_iter(), None, 10).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(err.to_string().contains("record 1"));
+    }
+
+    #[test]
+    fn test_query_streams_a_spool() {
+        let path = std::env::temp_dir().join(format!("flakestorm_query_{}.jsonl", std::process::id()));
+        let mut spool = crate::spool::ResultSpool::new(&path, 200);
+        for i in 0..10 {
+            if i == 8 {
+                spool.max_buffer_bytes = 1 << 20;
+            }
+            spool.push(&result("noise", i % 2 == 0, i as f64)).unwrap();
+        }
+        assert!(spool.spilled() > 0 && spool.in_memory() > 0);
+        let query = ResultQuery::new().passed(false).sort_by(SortKey::LatencyMs, true);
+        let page = query.page_after(spool.iter_lines().unwrap(), None, 3).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(latencies(&page), vec![9.0, 7.0, 5.0]);
+    }
+
+    #[test]
+    fn test_severity_and_time_filters() {
+        let check = |passed: bool, severity: Severity| CheckResult {
+            check_type: "canary_leak".to_string(),
+            passed,
+            details: String::new(),
+            severity,
+        };
+        let mut results: Vec<MutationResult> = (0..4).map(|i| result("noise", i % 2 == 0, i as f64)).collect();
+        results[1].checks = vec![check(false, Severity::Warn)];
+        results[2].checks = vec![check(true, Severity::Critical)];
+        results[3].checks = vec![check(false, Severity::Critical)];
+        for (i, r) in results.iter_mut().enumerate().skip(1) {
+            r.timestamp_ms = Some(1000.0 * i as f64);
+        }
+
+        let severe = ResultQuery::new().min_severity(Severity::Error);
+        assert_eq!(latencies(&severe.page_after(lines(&results), None, 10).unwrap()), vec![3.0]);
+        assert_eq!(ResultQuery::new().min_severity(Severity::Warn).count(lines(&results)).unwrap(), 2);
+
+        let window = ResultQuery::new().time_range(Some(1000.0), Some(3000.0));
+        assert_eq!(latencies(&window.page_after(lines(&results), None, 10).unwrap()), vec![1.0, 2.0]);
+        // Untimed results never fall in a range
+        assert_eq!(ResultQuery::new().time_range(None, Some(2000.0)).count(lines(&results)).unwrap(), 1);
+        assert_eq!(ResultQuery::new().time_range(None, None).count(lines(&results)).unwrap(), 4);
+    }
+}
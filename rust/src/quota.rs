@@ -0,0 +1,321 @@
+//! Per-category quotas for mutation generation
+//!
+//! Sampling mutations at random can leave a suite light on a category it
+//! cares about. Quotas set a minimum and/or maximum per category, either as
+//! an absolute count or as a fraction of the generated total; the planner
+//! turns them into exact counts and refuses up front when they cannot all
+//! hold at once.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::explain::mutation_profile;
+use crate::rng::{resolve_seed, SplitMix64};
+
+/// A quota limit: an absolute count or a fraction of the total
+#[derive(Debug, Clone, Copy, PartialEq, FromPyObject)]
+pub enum QuotaBound {
+    Count(usize),
+    Fraction(f64),
+}
+
+impl QuotaBound {
+    fn validate(self) -> Result<(), String> {
+        match self {
+            QuotaBound::Fraction(f) if !(0.0..=1.0).contains(&f) => {
+                Err(format!("quota fraction must be in [0, 1], got {}", f))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn lower(self, total: usize) -> usize {
+        match self {
+            QuotaBound::Count(n) => n,
+            QuotaBound::Fraction(f) => (f * total as f64 - 1e-9).ceil().max(0.0) as usize,
+        }
+    }
+
+    fn upper(self, total: usize) -> usize {
+        match self {
+            QuotaBound::Count(n) => n,
+            QuotaBound::Fraction(f) => (f * total as f64 + 1e-9).floor() as usize,
+        }
+    }
+}
+
+/// Minimum and maximum share of one category
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CategoryQuota {
+    pub min: Option<QuotaBound>,
+    pub max: Option<QuotaBound>,
+}
+
+/// Exact number of mutations to take from each category.
+///
+/// `available` is how many candidates each category has. Categories without
+/// a quota share whatever the minimums leave over, in proportion to their
+/// remaining capacity.
+pub fn plan_quotas(
+    total: usize,
+    quotas: &HashMap<String, CategoryQuota>,
+    available: &HashMap<String, usize>,
+) -> Result<BTreeMap<String, usize>, String> {
+    let mut bounds: BTreeMap<String, (usize, usize)> = BTreeMap::new();
+    for (category, &count) in available {
+        bounds.insert(category.clone(), (0, count));
+    }
+
+    for (category, quota) in quotas {
+        for bound in [quota.min, quota.max].into_iter().flatten() {
+            bound.validate()?;
+        }
+        let have = available.get(category).copied().unwrap_or(0);
+        let lo = quota.min.map_or(0, |b| b.lower(total));
+        let hi = quota.max.map_or(have, |b| b.upper(total).min(have));
+        if lo > hi {
+            return Err(format!(
+                "quota for '{}' needs at least {} mutations but at most {} are possible ({} candidates)",
+                category, lo, hi, have
+            ));
+        }
+        bounds.insert(category.clone(), (lo, hi));
+    }
+
+    let min_sum: usize = bounds.values().map(|b| b.0).sum();
+    let max_sum: usize = bounds.values().map(|b| b.1).sum();
+    if min_sum > total {
+        return Err(format!(
+            "quota minimums add up to {} mutations but only {} are generated",
+            min_sum, total
+        ));
+    }
+    if max_sum < total {
+        return Err(format!(
+            "quota maximums and candidates allow only {} of the {} requested mutations",
+            max_sum, total
+        ));
+    }
+
+    // Spread the remainder over spare capacity with the largest-remainder method
+    let mut plan: BTreeMap<String, usize> = bounds.iter().map(|(c, b)| (c.clone(), b.0)).collect();
+    let remaining = total - min_sum;
+    let spare_total = max_sum - min_sum;
+    if remaining > 0 {
+        let mut shares: Vec<(&String, usize, f64)> = bounds
+            .iter()
+            .map(|(c, &(lo, hi))| {
+                let exact = (hi - lo) as f64 * remaining as f64 / spare_total as f64;
+                (c, exact.floor() as usize, exact.fract())
+            })
+            .collect();
+        let mut assigned: usize = shares.iter().map(|s| s.1).sum();
+        shares.sort_by(|a, b| b.2.total_cmp(&a.2).then(a.0.cmp(b.0)));
+        for (category, base, _) in &shares {
+            *plan.get_mut(*category).expect("planned category") += base;
+        }
+        for (category, _, _) in shares.iter().cycle() {
+            if assigned == remaining {
+                break;
+            }
+            let (_, hi) = bounds[*category];
+            let slot = plan.get_mut(*category).expect("planned category");
+            if *slot < hi {
+                *slot += 1;
+                assigned += 1;
+            }
+        }
+    }
+
+    plan.retain(|_, n| *n > 0);
+    Ok(plan)
+}
+
+/// Pick `total` candidate indices honoring the quotas.
+///
+/// Candidates are grouped by the category of their mutation type and
+/// sampled within each category; the returned indices are in input order.
+pub fn select_within_quotas(
+    mutation_types: &[String],
+    total: usize,
+    quotas: &HashMap<String, CategoryQuota>,
+    seed: u64,
+) -> Result<Vec<usize>, String> {
+    let mut by_category: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (i, t) in mutation_types.iter().enumerate() {
+        by_category
+            .entry(mutation_profile(t).0.to_string())
+            .or_default()
+            .push(i);
+    }
+    let available: HashMap<String, usize> = by_category.iter().map(|(c, v)| (c.clone(), v.len())).collect();
+    let plan = plan_quotas(total, quotas, &available)?;
+
+    let mut rng = SplitMix64::new(seed);
+    let mut selected = Vec::with_capacity(total);
+    for (category, count) in plan {
+        let pool = by_category.get_mut(&category).expect("planned categories have candidates");
+        for k in 0..count {
+            let j = k + rng.below(pool.len() - k);
+            pool.swap(k, j);
+        }
+        selected.extend_from_slice(&pool[..count]);
+    }
+    selected.sort_unstable();
+    Ok(selected)
+}
+
+/// Quota violations in an already generated set of mutation types.
+pub fn quota_violations(mutation_types: &[String], quotas: &HashMap<String, CategoryQuota>) -> Vec<String> {
+    let total = mutation_types.len();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for t in mutation_types {
+        *counts.entry(mutation_profile(t).0).or_default() += 1;
+    }
+
+    let mut categories: Vec<&String> = quotas.keys().collect();
+    categories.sort();
+    let mut violations = Vec::new();
+    for category in categories {
+        let quota = quotas[category];
+        let n = counts.get(category.as_str()).copied().unwrap_or(0);
+        if let Some(min) = quota.min.map(|b| b.lower(total)).filter(|&min| n < min) {
+            violations.push(format!("'{}' has {} mutations, quota minimum is {}", category, n, min));
+        }
+        if let Some(max) = quota.max.map(|b| b.upper(total)).filter(|&max| n > max) {
+            violations.push(format!("'{}' has {} mutations, quota maximum is {}", category, n, max));
+        }
+    }
+    violations
+}
+
+fn parse_quotas(raw: HashMap<String, HashMap<String, QuotaBound>>) -> PyResult<HashMap<String, CategoryQuota>> {
+    raw.into_iter()
+        .map(|(category, limits)| {
+            let mut quota = CategoryQuota::default();
+            for (key, bound) in limits {
+                match key.as_str() {
+                    "min" => quota.min = Some(bound),
+                    "max" => quota.max = Some(bound),
+                    other => {
+                        return Err(PyValueError::new_err(format!(
+                            "unknown quota key '{}' for '{}' (expected min or max)",
+                            other, category
+                        )))
+                    }
+                }
+            }
+            Ok((category, quota))
+        })
+        .collect()
+}
+
+/// Choose `total` of the candidate mutations so every category quota holds.
+///
+/// `quotas` maps a category (e.g. "injection", "noise") to `{"min": ..., "max": ...}`,
+/// where an int is an absolute count and a float a fraction of `total`.
+/// Returns the chosen candidate indices; raises ValueError when the quotas
+/// cannot be satisfied.
+#[pyfunction]
+#[pyo3(signature = (mutation_types, total, quotas, seed = None))]
+pub fn select_mutations_with_quotas(
+    mutation_types: Vec<String>,
+    total: usize,
+    quotas: HashMap<String, HashMap<String, QuotaBound>>,
+    seed: Option<u64>,
+) -> PyResult<Vec<usize>> {
+    let seed = resolve_seed(seed);
+    let quotas = parse_quotas(quotas)?;
+    select_within_quotas(&mutation_types, total, &quotas, seed).map_err(PyValueError::new_err)
+}
+
+/// Describe every quota a generated set of mutation types violates.
+#[pyfunction]
+pub fn check_mutation_quotas(
+    mutation_types: Vec<String>,
+    quotas: HashMap<String, HashMap<String, QuotaBound>>,
+) -> PyResult<Vec<String>> {
+    let quotas = parse_quotas(quotas)?;
+    Ok(quota_violations(&mutation_types, &quotas))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types() -> Vec<String> {
+        let mut t = Vec::new();
+        t.extend(std::iter::repeat_n("paraphrase".to_string(), 40));
+        t.extend(std::iter::repeat_n("noise".to_string(), 40));
+        t.extend(std::iter::repeat_n("prompt_injection".to_string(), 5));
+        t.extend(std::iter::repeat_n("advanced_jailbreak".to_string(), 5));
+        t
+    }
+
+    fn quotas(entries: &[(&str, Option<QuotaBound>, Option<QuotaBound>)]) -> HashMap<String, CategoryQuota> {
+        entries
+            .iter()
+            .map(|&(c, min, max)| (c.to_string(), CategoryQuota { min, max }))
+            .collect()
+    }
+
+    #[test]
+    fn test_minimum_fraction_is_guaranteed() {
+        let q = quotas(&[("injection", Some(QuotaBound::Fraction(0.2)), None)]);
+        let selected = select_within_quotas(&types(), 30, &q, 3).unwrap();
+        assert_eq!(selected.len(), 30);
+        assert!(selected.windows(2).all(|w| w[0] < w[1]));
+
+        let chosen: Vec<String> = selected.iter().map(|&i| types()[i].clone()).collect();
+        assert!(quota_violations(&chosen, &q).is_empty());
+        let injection = chosen.iter().filter(|t| mutation_profile(t).0 == "injection").count();
+        assert!(injection >= 6);
+        assert_eq!(selected, select_within_quotas(&types(), 30, &q, 3).unwrap());
+    }
+
+    #[test]
+    fn test_maximum_caps_category() {
+        let q = quotas(&[("noise", None, Some(QuotaBound::Count(2)))]);
+        let plan = plan_quotas(
+            20,
+            &q,
+            &HashMap::from([("noise".to_string(), 40), ("semantic".to_string(), 40)]),
+        )
+        .unwrap();
+        assert!(plan["noise"] <= 2);
+        assert_eq!(plan.values().sum::<usize>(), 20);
+    }
+
+    #[test]
+    fn test_unsatisfiable_quotas_error() {
+        // only 10 injection candidates exist
+        let q = quotas(&[("injection", Some(QuotaBound::Count(15)), None)]);
+        assert!(select_within_quotas(&types(), 30, &q, 0).unwrap_err().contains("injection"));
+
+        let q = quotas(&[
+            ("semantic", Some(QuotaBound::Fraction(0.6)), None),
+            ("noise", Some(QuotaBound::Fraction(0.6)), None),
+        ]);
+        assert!(select_within_quotas(&types(), 20, &q, 0).unwrap_err().contains("minimums"));
+
+        let q = quotas(&[("noise", None, Some(QuotaBound::Fraction(1.5)))]);
+        assert!(select_within_quotas(&types(), 20, &q, 0).is_err());
+
+        assert!(select_within_quotas(&types(), 91, &HashMap::new(), 0).is_err());
+    }
+
+    #[test]
+    fn test_quota_violations() {
+        let generated: Vec<String> = vec!["noise".into(), "noise".into(), "paraphrase".into()];
+        let q = quotas(&[
+            ("injection", Some(QuotaBound::Count(1)), None),
+            ("noise", None, Some(QuotaBound::Fraction(0.5))),
+        ]);
+        let v = quota_violations(&generated, &q);
+        assert_eq!(v.len(), 2);
+        assert!(v[0].starts_with("'injection'"));
+        assert!(v[1].contains("maximum is 1"));
+    }
+}
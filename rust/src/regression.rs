@@ -0,0 +1,414 @@
+//! Regression gate against a baseline run
+//!
+//! `gate` judges a run on its own numbers. The regression gate compares
+//! the current statistics with a baseline and lists every threshold the
+//! run violates:
+//!
+//! - the robustness score dropping by an absolute amount,
+//! - a mutation type's pass rate dropping by an absolute amount (types
+//!   with fewer than `min_type_samples` mutations in either run are
+//!   skipped as too noisy),
+//! - p50/p95/p99 latency growing by a relative amount (0.5 = +50%),
+//! - a mutation type of the baseline missing from the current run, a
+//!   failure unless `fail_on_missing_type` is off, when it only warns.
+//!
+//! Each metric has a warn and a fail level. The verdict is "fail" if any
+//! fail level is reached, otherwise "warn" if any warn level is, and its
+//! exit code is what a CI step should exit with.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{TestStatistics, TypeStatistics};
+
+/// Warn and fail levels for each compared metric
+#[pyclass(get_all, set_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionThresholds {
+    pub warn_score_drop: f64,
+    pub max_score_drop: f64,
+    pub warn_type_drop: f64,
+    pub max_type_drop: f64,
+    /// Types with fewer mutations than this in either run are not compared
+    pub min_type_samples: usize,
+    pub warn_latency_increase: f64,
+    pub max_latency_increase: f64,
+    /// Treat warnings as failures for the exit code
+    pub fail_on_warn: bool,
+    /// Fail, rather than warn, when a baseline mutation type did not run
+    #[serde(default = "default_true")]
+    pub fail_on_missing_type: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for RegressionThresholds {
+    fn default() -> Self {
+        Self {
+            warn_score_drop: 0.02,
+            max_score_drop: 0.05,
+            warn_type_drop: 0.05,
+            max_type_drop: 0.15,
+            min_type_samples: 5,
+            warn_latency_increase: 0.25,
+            max_latency_increase: 1.0,
+            fail_on_warn: false,
+            fail_on_missing_type: true,
+        }
+    }
+}
+
+impl RegressionThresholds {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, warn, max) in [
+            ("score_drop", self.warn_score_drop, self.max_score_drop),
+            ("type_drop", self.warn_type_drop, self.max_type_drop),
+            (
+                "latency_increase",
+                self.warn_latency_increase,
+                self.max_latency_increase,
+            ),
+        ] {
+            if warn.is_nan() || max.is_nan() || warn < 0.0 || warn > max {
+                return Err(format!(
+                    "{}: need 0 <= warn <= max, got warn {} and max {}",
+                    name, warn, max
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl RegressionThresholds {
+    #[new]
+    #[pyo3(signature = (
+        warn_score_drop = 0.02,
+        max_score_drop = 0.05,
+        warn_type_drop = 0.05,
+        max_type_drop = 0.15,
+        min_type_samples = 5,
+        warn_latency_increase = 0.25,
+        max_latency_increase = 1.0,
+        fail_on_warn = false,
+        fail_on_missing_type = true
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        warn_score_drop: f64,
+        max_score_drop: f64,
+        warn_type_drop: f64,
+        max_type_drop: f64,
+        min_type_samples: usize,
+        warn_latency_increase: f64,
+        max_latency_increase: f64,
+        fail_on_warn: bool,
+        fail_on_missing_type: bool,
+    ) -> PyResult<Self> {
+        let thresholds = Self {
+            warn_score_drop,
+            max_score_drop,
+            warn_type_drop,
+            max_type_drop,
+            min_type_samples,
+            warn_latency_increase,
+            max_latency_increase,
+            fail_on_warn,
+            fail_on_missing_type,
+        };
+        thresholds.validate().map_err(PyValueError::new_err)?;
+        Ok(thresholds)
+    }
+}
+
+/// One threshold the current run crossed
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionViolation {
+    /// "robustness_score", "pass_rate", "p50_latency_ms", "p95_latency_ms",
+    /// "p99_latency_ms" or "missing_type"
+    pub metric: String,
+    /// Set for per-type pass-rate violations
+    pub mutation_type: Option<String>,
+    pub baseline: f64,
+    pub current: f64,
+    /// Absolute drop for scores and pass rates, relative increase for
+    /// latency; for a missing type, the mutations it ran in the baseline
+    pub change: f64,
+    /// The level that was reached
+    pub threshold: f64,
+    /// "warn" or "fail"
+    pub severity: String,
+    pub message: String,
+}
+
+/// Outcome of a regression comparison
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionVerdict {
+    /// "pass", "warn" or "fail"
+    pub status: String,
+    pub exit_code: i32,
+    /// Failures first, then warnings
+    pub violations: Vec<RegressionViolation>,
+}
+
+#[pymethods]
+impl RegressionVerdict {
+    fn __bool__(&self) -> bool {
+        self.exit_code == 0
+    }
+
+    /// The violation messages, failures first.
+    fn reasons(&self) -> Vec<String> {
+        self.violations.iter().map(|v| v.message.clone()).collect()
+    }
+}
+
+struct Check<'a> {
+    metric: &'a str,
+    mutation_type: Option<&'a str>,
+    baseline: f64,
+    current: f64,
+    change: f64,
+    warn: f64,
+    max: f64,
+}
+
+impl Check<'_> {
+    fn violation(&self) -> Option<RegressionViolation> {
+        let (severity, threshold) = if self.change >= self.max {
+            ("fail", self.max)
+        } else if self.change >= self.warn {
+            ("warn", self.warn)
+        } else {
+            return None;
+        };
+        let subject = match self.mutation_type {
+            Some(t) => format!("{} {}", t, self.metric),
+            None => self.metric.to_string(),
+        };
+        let message = if self.metric.ends_with("latency_ms") {
+            format!(
+                "{} rose {:.0}% from {:.1} to {:.1} (limit {:.0}%)",
+                subject,
+                self.change * 100.0,
+                self.baseline,
+                self.current,
+                threshold * 100.0
+            )
+        } else {
+            format!(
+                "{} dropped {:.1} points from {:.1}% to {:.1}% (limit {:.1})",
+                subject,
+                self.change * 100.0,
+                self.baseline * 100.0,
+                self.current * 100.0,
+                threshold * 100.0
+            )
+        };
+        Some(RegressionViolation {
+            metric: self.metric.to_string(),
+            mutation_type: self.mutation_type.map(str::to_string),
+            baseline: self.baseline,
+            current: self.current,
+            change: self.change,
+            threshold,
+            severity: severity.to_string(),
+            message,
+        })
+    }
+}
+
+/// Compare `current` with `baseline` and list every violated threshold.
+pub fn detect_regressions(
+    current: &TestStatistics,
+    baseline: &TestStatistics,
+    thresholds: &RegressionThresholds,
+) -> Result<RegressionVerdict, String> {
+    thresholds.validate()?;
+    let mut checks = vec![Check {
+        metric: "robustness_score",
+        mutation_type: None,
+        baseline: baseline.robustness_score,
+        current: current.robustness_score,
+        change: baseline.robustness_score - current.robustness_score,
+        warn: thresholds.warn_score_drop,
+        max: thresholds.max_score_drop,
+    }];
+
+    let current_types: HashMap<&str, &TypeStatistics> =
+        current.by_type.iter().map(|t| (t.mutation_type.as_str(), t)).collect();
+    let mut baseline_types: Vec<&TypeStatistics> = baseline.by_type.iter().collect();
+    baseline_types.sort_by(|a, b| a.mutation_type.cmp(&b.mutation_type));
+    let mut missing = Vec::new();
+    for old in baseline_types {
+        let Some(new) = current_types.get(old.mutation_type.as_str()) else {
+            missing.push(RegressionViolation {
+                metric: "missing_type".to_string(),
+                mutation_type: Some(old.mutation_type.clone()),
+                baseline: old.total as f64,
+                current: 0.0,
+                change: old.total as f64,
+                threshold: 0.0,
+                severity: if thresholds.fail_on_missing_type { "fail" } else { "warn" }.to_string(),
+                message: format!(
+                    "{} ran {} mutations in the baseline but none in this run",
+                    old.mutation_type, old.total
+                ),
+            });
+            continue;
+        };
+        if old.total < thresholds.min_type_samples || new.total < thresholds.min_type_samples {
+            continue;
+        }
+        checks.push(Check {
+            metric: "pass_rate",
+            mutation_type: Some(&old.mutation_type),
+            baseline: old.pass_rate,
+            current: new.pass_rate,
+            change: old.pass_rate - new.pass_rate,
+            warn: thresholds.warn_type_drop,
+            max: thresholds.max_type_drop,
+        });
+    }
+
+    for (metric, old, new) in [
+        ("p50_latency_ms", baseline.p50_latency_ms, current.p50_latency_ms),
+        ("p95_latency_ms", baseline.p95_latency_ms, current.p95_latency_ms),
+        ("p99_latency_ms", baseline.p99_latency_ms, current.p99_latency_ms),
+    ] {
+        if old > 0.0 {
+            checks.push(Check {
+                metric,
+                mutation_type: None,
+                baseline: old,
+                current: new,
+                change: new / old - 1.0,
+                warn: thresholds.warn_latency_increase,
+                max: thresholds.max_latency_increase,
+            });
+        }
+    }
+
+    let (mut violations, warnings): (Vec<RegressionViolation>, Vec<RegressionViolation>) = checks
+        .iter()
+        .filter_map(Check::violation)
+        .chain(missing)
+        .partition(|v| v.severity == "fail");
+    let (status, exit_code) = if !violations.is_empty() {
+        ("fail", 1)
+    } else if !warnings.is_empty() {
+        ("warn", i32::from(thresholds.fail_on_warn))
+    } else {
+        ("pass", 0)
+    };
+    violations.extend(warnings);
+    Ok(RegressionVerdict {
+        status: status.to_string(),
+        exit_code,
+        violations,
+    })
+}
+
+/// Compare a run's statistics with a baseline's.
+///
+/// Returns the pass/warn/fail verdict with every violated threshold
+/// listed. The current run's statistics come from `calculate_statistics`;
+/// a stored baseline loads with `TestStatistics.from_json`.
+#[pyfunction]
+#[pyo3(signature = (current, baseline, thresholds = None))]
+pub fn regression_gate(
+    current: TestStatistics,
+    baseline: TestStatistics,
+    thresholds: Option<RegressionThresholds>,
+) -> PyResult<RegressionVerdict> {
+    detect_regressions(&current, &baseline, &thresholds.unwrap_or_default()).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::{calculate_statistics, MutationResult};
+
+    fn stats(types: &[(&str, usize, usize)], latency_ms: f64) -> TestStatistics {
+        let results: Vec<MutationResult> = types
+            .iter()
+            .flat_map(|&(t, passed, total)| {
+                (0..total).map(move |i| MutationResult {
+                    mutation_type: t.to_string(),
+                    passed: i < passed,
+                    latency_ms,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        calculate_statistics(&results).unwrap()
+    }
+
+    #[test]
+    fn test_pass_and_warn() {
+        let baseline = stats(&[("noise", 18, 20), ("paraphrase", 19, 20)], 100.0);
+        let same = detect_regressions(&baseline, &baseline, &RegressionThresholds::default()).unwrap();
+        assert_eq!((same.status.as_str(), same.exit_code), ("pass", 0));
+
+        let slower = stats(&[("noise", 18, 20), ("paraphrase", 19, 20)], 140.0);
+        let verdict = detect_regressions(&slower, &baseline, &RegressionThresholds::default()).unwrap();
+        assert_eq!((verdict.status.as_str(), verdict.exit_code), ("warn", 0));
+        let metrics: Vec<&str> = verdict.violations.iter().map(|v| v.metric.as_str()).collect();
+        assert_eq!(metrics, vec!["p50_latency_ms", "p95_latency_ms", "p99_latency_ms"]);
+        assert_eq!(
+            verdict.violations[0].message,
+            "p50_latency_ms rose 40% from 100.0 to 140.0 (limit 25%)"
+        );
+    }
+
+    #[test]
+    fn test_fail_lists_every_violation() {
+        let baseline = stats(&[("noise", 18, 20), ("paraphrase", 19, 20), ("rare", 2, 2)], 100.0);
+        let current = stats(&[("noise", 10, 20), ("paraphrase", 17, 20), ("rare", 0, 2)], 100.0);
+        let verdict = detect_regressions(&current, &baseline, &RegressionThresholds::default()).unwrap();
+        assert_eq!((verdict.status.as_str(), verdict.exit_code), ("fail", 1));
+        let found: Vec<(&str, Option<&str>, &str)> = verdict
+            .violations
+            .iter()
+            .map(|v| (v.metric.as_str(), v.mutation_type.as_deref(), v.severity.as_str()))
+            .collect();
+        // "rare" has too few samples to compare
+        assert_eq!(
+            found,
+            vec![
+                ("robustness_score", None, "fail"),
+                ("pass_rate", Some("noise"), "fail"),
+                ("pass_rate", Some("paraphrase"), "warn"),
+            ]
+        );
+        assert!((verdict.violations[1].change - 0.4).abs() < 1e-12);
+
+        // Dropping a type from the run cannot hide its regression
+        let dropped = stats(&[("noise", 18, 20), ("rare", 2, 2)], 100.0);
+        let verdict = detect_regressions(&dropped, &baseline, &RegressionThresholds::default()).unwrap();
+        assert_eq!(verdict.status, "fail");
+        assert_eq!(
+            verdict.violations[0].message,
+            "paraphrase ran 20 mutations in the baseline but none in this run"
+        );
+        let lenient = RegressionThresholds {
+            fail_on_missing_type: false,
+            ..Default::default()
+        };
+        assert_eq!(detect_regressions(&dropped, &baseline, &lenient).unwrap().status, "warn");
+
+        let bad = RegressionThresholds {
+            warn_type_drop: 0.5,
+            ..Default::default()
+        };
+        assert!(detect_regressions(&current, &baseline, &bad).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Word- and sentence-order mutators
+//!
+//! Structure perturbations that keep every word: shuffling words within a
+//! small window, swapping two adjacent sentences, and moving the closing
+//! instruction to the front. Word and sentence boundaries come from Unicode
+//! segmentation, so punctuation and non-Latin text stay intact.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::metrics::registry;
+use crate::rng::{resolve_seed, SplitMix64};
+
+/// A structure-perturbing operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reorder {
+    /// Shuffle words within consecutive windows of `window` words
+    ShuffleWords { window: usize },
+    /// Swap one random pair of adjacent sentences
+    SwapSentences,
+    /// Move the last sentence to the front
+    InstructionFirst,
+}
+
+impl Reorder {
+    pub fn parse(name: &str, window: usize) -> Result<Self, String> {
+        match name {
+            "shuffle_words" if window < 2 => Err(format!("window must be at least 2, got {}", window)),
+            "shuffle_words" => Ok(Reorder::ShuffleWords { window }),
+            "swap_sentences" => Ok(Reorder::SwapSentences),
+            "instruction_first" => Ok(Reorder::InstructionFirst),
+            other => Err(format!(
+                "unknown reorder operation '{}' (expected shuffle_words, swap_sentences or instruction_first)",
+                other
+            )),
+        }
+    }
+}
+
+fn shuffle<T>(items: &mut [T], rng: &mut SplitMix64) {
+    for i in (1..items.len()).rev() {
+        items.swap(i, rng.below(i + 1));
+    }
+}
+
+/// Shuffle words inside each window; spaces and punctuation keep their slots.
+pub fn shuffle_words(text: &str, window: usize, rng: &mut SplitMix64) -> String {
+    let mut segments: Vec<&str> = text.split_word_bounds().collect();
+    let word_slots: Vec<usize> = (0..segments.len())
+        .filter(|&i| segments[i].chars().any(char::is_alphanumeric))
+        .collect();
+
+    for slots in word_slots.chunks(window) {
+        let mut words: Vec<&str> = slots.iter().map(|&i| segments[i]).collect();
+        shuffle(&mut words, rng);
+        for (&slot, word) in slots.iter().zip(words) {
+            segments[slot] = word;
+        }
+    }
+    segments.concat()
+}
+
+/// Sentences with their trailing whitespace split off: (sentence, whitespace).
+fn sentences(text: &str) -> Vec<(&str, &str)> {
+    text.split_sentence_bounds()
+        .map(|s| {
+            let body = s.trim_end();
+            (body, &s[body.len()..])
+        })
+        .collect()
+}
+
+/// Reassemble sentences in `order`, keeping the original inter-sentence whitespace slots.
+fn reassemble(parts: &[(&str, &str)], order: &[usize]) -> String {
+    let mut out = String::new();
+    for (slot, &i) in order.iter().enumerate() {
+        out.push_str(parts[i].0);
+        let sep = parts[slot].1;
+        // A sentence that moves away from the end needs a separator
+        out.push_str(if sep.is_empty() && slot + 1 < parts.len() { " " } else { sep });
+    }
+    out
+}
+
+/// Swap one random pair of adjacent sentences.
+pub fn swap_adjacent_sentences(text: &str, rng: &mut SplitMix64) -> String {
+    let parts = sentences(text);
+    if parts.len() < 2 {
+        return text.to_string();
+    }
+    let mut order: Vec<usize> = (0..parts.len()).collect();
+    let i = rng.below(parts.len() - 1);
+    order.swap(i, i + 1);
+    reassemble(&parts, &order)
+}
+
+/// Move the final sentence, usually the actual instruction, to the front.
+pub fn instruction_first(text: &str) -> String {
+    let parts = sentences(text);
+    if parts.len() < 2 {
+        return text.to_string();
+    }
+    let last = parts.len() - 1;
+    let order: Vec<usize> = std::iter::once(last).chain(0..last).collect();
+    reassemble(&parts, &order)
+}
+
+pub fn reorder(text: &str, op: Reorder, rng: &mut SplitMix64) -> String {
+    match op {
+        Reorder::ShuffleWords { window } => shuffle_words(text, window, rng),
+        Reorder::SwapSentences => swap_adjacent_sentences(text, rng),
+        Reorder::InstructionFirst => instruction_first(text),
+    }
+}
+
+/// Apply `op` to every prompt in parallel, reproducibly per seed.
+pub fn reorder_all(prompts: &[String], op: Reorder, seed: u64) -> Vec<String> {
+    prompts
+        .par_iter()
+        .enumerate()
+        .map(|(i, p)| reorder(p, op, &mut SplitMix64::for_item(seed, i)))
+        .collect()
+}
+
+/// Word-order and sentence-order mutations for a batch of prompts.
+///
+/// `operation` is "shuffle_words" (within windows of `window` words),
+/// "swap_sentences" or "instruction_first".
+#[pyfunction]
+#[pyo3(signature = (prompts, operation = "shuffle_words", window = 3, seed = None))]
+pub fn reorder_mutations(
+    py: Python<'_>,
+    prompts: Vec<String>,
+    operation: &str,
+    window: usize,
+    seed: Option<u64>,
+) -> PyResult<Vec<String>> {
+    let seed = resolve_seed(seed);
+    let op = Reorder::parse(operation, window).map_err(PyValueError::new_err)?;
+    registry().increment("mutations_generated.reorder", prompts.len() as u64);
+    Ok(py.allow_threads(|| reorder_all(&prompts, op, seed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted_words(text: &str) -> Vec<&str> {
+        let mut w: Vec<&str> = text.unicode_words().collect();
+        w.sort();
+        w
+    }
+
+    #[test]
+    fn test_shuffle_words_stays_within_windows() {
+        let text = "one two three, four five six. seven";
+        let mut rng = SplitMix64::new(4);
+        let out = shuffle_words(text, 3, &mut rng);
+        assert_eq!(sorted_words(&out), sorted_words(text));
+        // punctuation keeps its position relative to the word slots
+        assert_eq!(out.matches(", ").count(), 1);
+        assert!(out.ends_with(". seven"));
+
+        let first: Vec<&str> = out.unicode_words().take(3).collect();
+        let mut first_sorted = first.clone();
+        first_sorted.sort();
+        assert_eq!(first_sorted, vec!["one", "three", "two"]);
+
+        let cjk = shuffle_words("東京 行き", 2, &mut rng);
+        assert_eq!(sorted_words(&cjk), sorted_words("東京 行き"));
+    }
+
+    #[test]
+    fn test_sentence_operations() {
+        let text = "I am flying tomorrow. My budget is low. Find me a hotel in Rome.";
+        assert_eq!(
+            instruction_first(text),
+            "Find me a hotel in Rome. I am flying tomorrow. My budget is low."
+        );
+
+        let swapped = swap_adjacent_sentences(text, &mut SplitMix64::new(0));
+        assert_ne!(swapped, text);
+        assert_eq!(sorted_words(&swapped), sorted_words(text));
+        assert_eq!(swapped.len(), text.len());
+
+        assert_eq!(instruction_first("Only one sentence"), "Only one sentence");
+    }
+
+    #[test]
+    fn test_parse_and_batch() {
+        assert!(Reorder::parse("shuffle_words", 1).is_err());
+        assert!(Reorder::parse("flip", 3).is_err());
+        let prompts = vec!["a b c d".to_string(); 3];
+        let op = Reorder::parse("shuffle_words", 4).unwrap();
+        assert_eq!(reorder_all(&prompts, op, 1), reorder_all(&prompts, op, 1));
+    }
+}
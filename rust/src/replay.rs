@@ -0,0 +1,384 @@
+//! Mutation recording and replay
+//!
+//! A failing mutation is only useful if it can be reproduced. Every
+//! generated mutation can be written as one JSONL record holding its type,
+//! seed, item index, parameters, operator options, input and final text,
+//! after a header line naming the format version. Replaying a file reloads the exact batch and,
+//! for the native seeded mutators, regenerates each record from its seed
+//! and reports any record whose text no longer comes out the same.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::conversation::{MutatorOptions, TurnMutator};
+use crate::metrics::registry;
+use crate::noise::NoiseOp;
+use crate::rng::{resolve_seed, SplitMix64};
+
+const FORMAT_NAME: &str = "flakestorm.mutations";
+/// Version 2 added per-record operator `options`
+const FORMAT_VERSION: u32 = 2;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RecordHeader {
+    format: String,
+    version: u32,
+}
+
+/// One generated mutation, with everything needed to reproduce it
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MutationRecord {
+    pub mutation_type: String,
+    /// Batch seed; the item stream is `SplitMix64::for_item(seed, index)`
+    pub seed: u64,
+    pub index: usize,
+    #[serde(default)]
+    pub parameters: BTreeMap<String, f64>,
+    /// Operator settings, e.g. noise `operations` or homoglyph `fullwidth`;
+    /// missing keys mean the mutator defaults
+    #[serde(default)]
+    pub options: BTreeMap<String, String>,
+    pub original: String,
+    pub text: String,
+}
+
+#[pymethods]
+impl MutationRecord {
+    #[new]
+    #[pyo3(signature = (
+        mutation_type, seed, index, original, text, parameters = BTreeMap::new(), options = BTreeMap::new()
+    ))]
+    fn py_new(
+        mutation_type: String,
+        seed: u64,
+        index: usize,
+        original: String,
+        text: String,
+        parameters: BTreeMap<String, f64>,
+        options: BTreeMap<String, String>,
+    ) -> Self {
+        Self {
+            mutation_type,
+            seed,
+            index,
+            parameters,
+            options,
+            original,
+            text,
+        }
+    }
+}
+
+/// The options a `mutator` record needs to reproduce it.
+fn record_options(mutator: TurnMutator, options: &MutatorOptions) -> BTreeMap<String, String> {
+    match mutator {
+        TurnMutator::Noise => {
+            let names: Vec<&str> = options.noise_ops.iter().map(|op| op.name()).collect();
+            BTreeMap::from([("operations".to_string(), names.join(","))])
+        }
+        TurnMutator::Homoglyph => BTreeMap::from([("fullwidth".to_string(), options.fullwidth.to_string())]),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Read back the options written by [`record_options`].
+fn parse_options(record: &MutationRecord) -> Result<MutatorOptions, String> {
+    let mut options = MutatorOptions::default();
+    for (key, value) in &record.options {
+        match key.as_str() {
+            "operations" => {
+                options.noise_ops = value.split(',').map(|n| NoiseOp::parse(n.trim())).collect::<Result<_, _>>()?
+            }
+            "fullwidth" => {
+                options.fullwidth = value
+                    .parse()
+                    .map_err(|_| format!("fullwidth must be true or false, got '{}'", value))?
+            }
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+    }
+    options.validate()?;
+    Ok(options)
+}
+
+/// Generate and record `mutation_type` mutations of every prompt.
+///
+/// Only the native seeded mutators can be recorded this way; their
+/// `intensity` parameter is the mutator's rate or density, and the
+/// operator settings in `options` are stored with every record.
+pub fn record_native(
+    mutation_type: &str,
+    prompts: &[String],
+    intensity: f64,
+    options: &MutatorOptions,
+    seed: u64,
+) -> Result<Vec<MutationRecord>, String> {
+    let mutator = TurnMutator::parse(mutation_type)?;
+    options.validate()?;
+    let recorded_options = record_options(mutator, options);
+    Ok(prompts
+        .par_iter()
+        .enumerate()
+        .map(|(index, original)| MutationRecord {
+            mutation_type: mutation_type.to_string(),
+            seed,
+            index,
+            parameters: BTreeMap::from([("intensity".to_string(), intensity)]),
+            options: recorded_options.clone(),
+            original: original.clone(),
+            text: mutator.apply_with(original, intensity, options, &mut SplitMix64::for_item(seed, index)),
+        })
+        .collect())
+}
+
+/// Regenerate a record's text from its seed and recorded options.
+///
+/// `Ok(None)` means the type is not a native seeded mutator (for example
+/// an LLM paraphrase) and the record can only be reloaded.
+pub fn regenerate(record: &MutationRecord) -> Result<Option<String>, String> {
+    let Ok(mutator) = TurnMutator::parse(&record.mutation_type) else {
+        return Ok(None);
+    };
+    let intensity = *record
+        .parameters
+        .get("intensity")
+        .ok_or_else(|| format!("record {} ({}) has no intensity parameter", record.index, record.mutation_type))?;
+    let options =
+        parse_options(record).map_err(|e| format!("record {} ({}): {}", record.index, record.mutation_type, e))?;
+    Ok(Some(mutator.apply_with(
+        &record.original,
+        intensity,
+        &options,
+        &mut SplitMix64::for_item(record.seed, record.index),
+    )))
+}
+
+/// Write `records` to `path` as a header line followed by one record per line.
+pub fn write_records(path: &Path, records: &[MutationRecord]) -> std::io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    let header = RecordHeader {
+        format: FORMAT_NAME.to_string(),
+        version: FORMAT_VERSION,
+    };
+    writeln!(out, "{}", serde_json::to_string(&header).map_err(std::io::Error::other)?)?;
+    for record in records {
+        writeln!(out, "{}", serde_json::to_string(record).map_err(std::io::Error::other)?)?;
+    }
+    out.flush()
+}
+
+/// Read records written by [`write_records`]. Errors name the failing line.
+pub fn read_records(path: &Path) -> Result<Vec<MutationRecord>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut lines = BufReader::new(file).lines().enumerate();
+    let header: RecordHeader = match lines.next() {
+        Some((_, line)) => {
+            let line = line.map_err(|e| format!("{}:1: {}", path.display(), e))?;
+            serde_json::from_str(&line).map_err(|e| format!("{}:1: bad header: {}", path.display(), e))?
+        }
+        None => return Err(format!("{}: empty mutation record file", path.display())),
+    };
+    if header.format != FORMAT_NAME || header.version > FORMAT_VERSION {
+        return Err(format!(
+            "{}: unsupported format {} version {}",
+            path.display(),
+            header.format,
+            header.version
+        ));
+    }
+
+    let mut records = Vec::new();
+    for (i, line) in lines {
+        let line = line.map_err(|e| format!("{}:{}: {}", path.display(), i + 1, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        records.push(serde_json::from_str(&line).map_err(|e| format!("{}:{}: {}", path.display(), i + 1, e))?);
+    }
+    Ok(records)
+}
+
+/// A replayed batch
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReplayReport {
+    /// The batch as recorded
+    pub records: Vec<MutationRecord>,
+    /// How many records were regenerated from their seed
+    pub regenerated: usize,
+    /// Positions of regenerated records whose text differs from the recording
+    pub mismatched: Vec<usize>,
+}
+
+/// Reload `records` and, when `regenerate_native` is set, check every
+/// native record against a fresh regeneration.
+pub fn replay_records(records: Vec<MutationRecord>, regenerate_native: bool) -> Result<ReplayReport, String> {
+    let fresh: Vec<Option<String>> = if regenerate_native {
+        records.par_iter().map(regenerate).collect::<Result<_, _>>()?
+    } else {
+        vec![None; records.len()]
+    };
+    let regenerated = fresh.iter().filter(|f| f.is_some()).count();
+    let mismatched = fresh
+        .iter()
+        .zip(&records)
+        .enumerate()
+        .filter(|(_, (f, r))| f.as_ref().is_some_and(|t| *t != r.text))
+        .map(|(i, _)| i)
+        .collect();
+    Ok(ReplayReport {
+        records,
+        regenerated,
+        mismatched,
+    })
+}
+
+/// Generate native mutations of `prompts` and record them to `path`.
+///
+/// `mutation_type` is "noise", "homoglyph", "case_scramble", "leetspeak"
+/// or "spacing". `operations` restricts noise to some of "swap",
+/// "delete", "duplicate" and "adjacent"; `fullwidth` lets homoglyphs use
+/// fullwidth Latin forms. Returns the records written.
+#[pyfunction]
+#[pyo3(signature = (path, mutation_type, prompts, intensity = 0.1, seed = None, operations = None, fullwidth = false))]
+#[allow(clippy::too_many_arguments)]
+pub fn record_mutations(
+    py: Python<'_>,
+    path: &str,
+    mutation_type: &str,
+    prompts: Vec<String>,
+    intensity: f64,
+    seed: Option<u64>,
+    operations: Option<Vec<String>>,
+    fullwidth: bool,
+) -> PyResult<Vec<MutationRecord>> {
+    let seed = resolve_seed(seed);
+    if !(0.0..=1.0).contains(&intensity) {
+        return Err(PyValueError::new_err(format!("intensity must be in [0, 1], got {}", intensity)));
+    }
+    let mut options = MutatorOptions {
+        fullwidth,
+        ..Default::default()
+    };
+    if let Some(names) = operations {
+        options.noise_ops = names
+            .iter()
+            .map(|n| NoiseOp::parse(n))
+            .collect::<Result<_, _>>()
+            .map_err(PyValueError::new_err)?;
+    }
+    let records = py
+        .allow_threads(|| record_native(mutation_type, &prompts, intensity, &options, seed))
+        .map_err(PyValueError::new_err)?;
+    write_records(Path::new(path), &records).map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))?;
+    registry().increment("mutations_generated.recorded", records.len() as u64);
+    Ok(records)
+}
+
+/// Write mutation records, e.g. from Python-side mutators, to `path`.
+#[pyfunction]
+pub fn save_mutation_records(path: &str, records: Vec<MutationRecord>) -> PyResult<()> {
+    write_records(Path::new(path), &records).map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))
+}
+
+/// Load the mutation records stored at `path`.
+#[pyfunction]
+pub fn load_mutation_records(path: &str) -> PyResult<Vec<MutationRecord>> {
+    read_records(Path::new(path)).map_err(PyIOError::new_err)
+}
+
+/// Reload a recorded batch, regenerating native mutations from their seeds
+/// to confirm they still reproduce.
+#[pyfunction]
+#[pyo3(signature = (path, regenerate = true))]
+pub fn replay_mutations(py: Python<'_>, path: &str, regenerate: bool) -> PyResult<ReplayReport> {
+    let records = read_records(Path::new(path)).map_err(PyIOError::new_err)?;
+    py.allow_threads(|| replay_records(records, regenerate))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prompts() -> Vec<String> {
+        vec!["Book a flight to Paris".into(), "What is the refund policy?".into()]
+    }
+
+    #[test]
+    fn test_round_trip_and_regenerate() {
+        let path = std::env::temp_dir().join(format!("flakestorm_replay_{}.jsonl", std::process::id()));
+        let mut records = record_native("leetspeak", &prompts(), 0.8, &MutatorOptions::default(), 5).unwrap();
+        records.push(MutationRecord {
+            mutation_type: "paraphrase".into(),
+            seed: 5,
+            index: 0,
+            parameters: BTreeMap::new(),
+            options: BTreeMap::new(),
+            original: "Book a flight".into(),
+            text: "Reserve a plane ticket".into(),
+        });
+        write_records(&path, &records).unwrap();
+        let loaded = read_records(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, records);
+
+        let report = replay_records(loaded.clone(), true).unwrap();
+        assert_eq!(report.regenerated, 2);
+        assert!(report.mismatched.is_empty());
+
+        let mut tampered = loaded;
+        tampered[1].text.push('!');
+        assert_eq!(replay_records(tampered, true).unwrap().mismatched, vec![1]);
+        assert!(record_native("paraphrase", &prompts(), 0.1, &MutatorOptions::default(), 0).is_err());
+    }
+
+    #[test]
+    fn test_replay_uses_recorded_options() {
+        let options = MutatorOptions {
+            noise_ops: vec![NoiseOp::Duplicate],
+            fullwidth: true,
+        };
+        let noise = record_native("noise", &prompts(), 0.5, &options, 9).unwrap();
+        assert_eq!(noise[0].options["operations"], "duplicate");
+        // Only duplications, so every mutation grows the prompt
+        assert!(noise.iter().all(|r| r.text.len() > r.original.len()));
+        let homoglyph = record_native("homoglyph", &prompts(), 1.0, &options, 9).unwrap();
+        assert_eq!(homoglyph[0].options["fullwidth"], "true");
+
+        let records: Vec<MutationRecord> = noise.into_iter().chain(homoglyph).collect();
+        let report = replay_records(records.clone(), true).unwrap();
+        assert_eq!(report.regenerated, 4);
+        assert!(report.mismatched.is_empty());
+
+        // Replaying with the defaults would not reproduce these records
+        let mut stripped = records.clone();
+        stripped.iter_mut().for_each(|r| r.options.clear());
+        assert!(!replay_records(stripped, true).unwrap().mismatched.is_empty());
+
+        let mut bad = records;
+        bad[0].options.insert("operations".into(), "smudge".into());
+        assert!(replay_records(bad, true).unwrap_err().contains("record 0 (noise)"));
+    }
+
+    #[test]
+    fn test_read_errors_name_the_line() {
+        let path = std::env::temp_dir().join(format!("flakestorm_replay_bad_{}.jsonl", std::process::id()));
+        let header = r#"{"format":"flakestorm.mutations","version":1}"#;
+        std::fs::write(&path, format!("{}\n{{\"mutation_type\":\"noise\"}}\n", header)).unwrap();
+        let err = read_records(&path).unwrap_err();
+        assert!(err.contains(":2:"), "{}", err);
+
+        std::fs::write(&path, r#"{"format":"other","version":1}"#).unwrap();
+        assert!(read_records(&path).unwrap_err().contains("unsupported format"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,210 @@
+//! Seedable random numbers for native mutators
+//!
+//! SplitMix64 is small, fast and has no dependencies. Batch mutators derive
+//! one stream per prompt from the run seed and the prompt's index, so the
+//! output does not depend on how rayon schedules the work.
+//!
+//! A process-wide run seed is the default for every mutator called without
+//! an explicit seed, so setting it once makes a whole run reproducible.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use pyo3::prelude::*;
+
+static RUN_SEED: AtomicU64 = AtomicU64::new(0);
+
+/// Seed used by mutators when none is passed.
+pub fn run_seed() -> u64 {
+    RUN_SEED.load(Ordering::Relaxed)
+}
+
+pub fn set_global_seed(seed: u64) {
+    RUN_SEED.store(seed, Ordering::Relaxed);
+}
+
+/// An explicit seed, or the run seed.
+pub fn resolve_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(run_seed)
+}
+
+/// A seed nobody can predict, for secrets rather than reproducible runs.
+///
+/// The standard library keys each `RandomState` from OS randomness; the
+/// clock is mixed in so two calls in one thread still differ.
+pub fn entropy_seed() -> u64 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let mut hasher = RandomState::new().build_hasher();
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_nanos());
+    hasher.write_u128(nanos);
+    SplitMix64::new(hasher.finish()).next_u64()
+}
+
+/// SplitMix64 pseudo-random generator
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Independent stream for item `index` of a batch seeded with `seed`.
+    pub fn for_item(seed: u64, index: usize) -> Self {
+        Self::new(item_seed(seed, index))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform float in [0, 1).
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform integer in [0, n); `n` must be non-zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+
+    /// Standard normal sample (Box-Muller).
+    pub fn normal(&mut self) -> f64 {
+        let u1 = 1.0 - self.next_f64();
+        let u2 = self.next_f64();
+        (-2.0 * u1.ln()).sqrt() * (std::f64::consts::TAU * u2).cos()
+    }
+
+    /// Gamma(shape, 1) sample (Marsaglia-Tsang); `shape` must be positive.
+    pub fn gamma(&mut self, shape: f64) -> f64 {
+        if shape < 1.0 {
+            let u = 1.0 - self.next_f64();
+            return self.gamma(shape + 1.0) * u.powf(1.0 / shape);
+        }
+        let d = shape - 1.0 / 3.0;
+        let c = 1.0 / (9.0 * d).sqrt();
+        loop {
+            let x = self.normal();
+            let v = (1.0 + c * x).powi(3);
+            if v <= 0.0 {
+                continue;
+            }
+            let u = 1.0 - self.next_f64();
+            if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+                return d * v;
+            }
+        }
+    }
+
+    /// Beta(a, b) sample; both parameters must be positive.
+    pub fn beta(&mut self, a: f64, b: f64) -> f64 {
+        let x = self.gamma(a);
+        let y = self.gamma(b);
+        x / (x + y)
+    }
+
+    /// A uniformly chosen element, or None for an empty slice.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len())])
+        }
+    }
+}
+
+/// Sub-seed for item `index` of a batch seeded with `seed`.
+pub fn item_seed(seed: u64, index: usize) -> u64 {
+    SplitMix64::new(seed ^ (index as u64).wrapping_mul(0xD1B5_4A32_D192_ED03)).next_u64()
+}
+
+/// Set the run seed used by every mutator called without `seed`.
+#[pyfunction]
+pub fn set_run_seed(seed: u64) {
+    set_global_seed(seed);
+}
+
+/// The current run seed.
+#[pyfunction]
+pub fn get_run_seed() -> u64 {
+    run_seed()
+}
+
+/// Sub-seed for item `index`, as native mutators derive it; lets Python
+/// workers seed their own randomness the same way.
+#[pyfunction]
+#[pyo3(signature = (index, seed = None))]
+pub fn derive_item_seed(index: usize, seed: Option<u64>) -> u64 {
+    item_seed(resolve_seed(seed), index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_streams_are_deterministic_and_bounded() {
+        let mut a = SplitMix64::new(7);
+        let mut b = SplitMix64::new(7);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+            assert!(a.below(10) < 10);
+            let f = b.next_f64();
+            assert!((0.0..1.0).contains(&f));
+            a.next_f64();
+            b.below(10);
+        }
+        assert_ne!(
+            SplitMix64::for_item(7, 0).next_u64(),
+            SplitMix64::for_item(7, 1).next_u64()
+        );
+    }
+
+    #[test]
+    fn test_batches_identical_across_thread_counts() {
+        use crate::noise::{add_noise_all, NoiseConfig, NoiseOp};
+        let prompts: Vec<String> = (0..200).map(|i| format!("Book flight number {} to Lisbon please", i)).collect();
+        let config = NoiseConfig {
+            error_rate: 0.2,
+            ops: NoiseOp::ALL.to_vec(),
+            seed: 42,
+        };
+        let run = |threads: usize| {
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap()
+                .install(|| add_noise_all(&prompts, &config))
+        };
+        assert_eq!(run(1), run(8));
+    }
+
+    #[test]
+    fn test_resolve_seed() {
+        assert_eq!(resolve_seed(Some(5)), 5);
+        assert_eq!(item_seed(7, 3), SplitMix64::for_item(7, 3).state);
+    }
+
+    #[test]
+    fn test_beta_sample_mean() {
+        let mut rng = SplitMix64::new(11);
+        let n = 20_000;
+        let mean = (0..n).map(|_| rng.beta(2.0, 6.0)).sum::<f64>() / n as f64;
+        assert!((mean - 0.25).abs() < 0.01, "{}", mean);
+        let small = (0..n).map(|_| rng.beta(0.5, 0.5)).sum::<f64>() / n as f64;
+        assert!((small - 0.5).abs() < 0.02, "{}", small);
+    }
+}
@@ -0,0 +1,160 @@
+//! Stratified mutation sampling
+//!
+//! A budget of N mutations is split as evenly as possible across mutation
+//! types, so small budgets still touch every type they can. Types with too
+//! few candidates give their unused share to the others, and when N is
+//! smaller than the number of types the seeded RNG decides which types get
+//! the last slots. Within a type, candidates are drawn without replacement,
+//! optionally in proportion to a difficulty weight.
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use crate::rng::{resolve_seed, SplitMix64};
+
+/// Number of candidates to take from each stratum.
+///
+/// `available` is the candidate count per stratum; the result sums to
+/// `min(budget, total available)`.
+pub fn allocate_strata(available: &[usize], budget: usize, rng: &mut SplitMix64) -> Vec<usize> {
+    let mut counts = vec![0; available.len()];
+    let mut remaining = budget.min(available.iter().sum());
+    loop {
+        let open: Vec<usize> = (0..available.len()).filter(|&i| counts[i] < available[i]).collect();
+        if remaining == 0 || open.is_empty() {
+            break;
+        }
+        let share = remaining / open.len();
+        if share == 0 {
+            // Fewer slots than open strata: a seeded choice of strata gets one each
+            let mut order = open;
+            for k in 0..remaining {
+                let j = k + rng.below(order.len() - k);
+                order.swap(k, j);
+                counts[order[k]] += 1;
+            }
+            break;
+        }
+        for i in open {
+            let take = share.min(available[i] - counts[i]);
+            counts[i] += take;
+            remaining -= take;
+        }
+    }
+    counts
+}
+
+/// Pick `k` of `items` without replacement, each draw in proportion to `weight`
+/// (Efraimidis-Spirakis keys). Zero-weight items are only taken when needed.
+fn weighted_pick(items: &[usize], weight: impl Fn(usize) -> f64, k: usize, rng: &mut SplitMix64) -> Vec<usize> {
+    let mut keyed: Vec<(f64, usize)> = items
+        .iter()
+        .map(|&i| {
+            let u = 1.0 - rng.next_f64();
+            let w = weight(i);
+            let key = if w > 0.0 { u.ln() / w } else { f64::NEG_INFINITY };
+            (key, i)
+        })
+        .collect();
+    keyed.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    keyed.into_iter().take(k).map(|(_, i)| i).collect()
+}
+
+/// Select up to `budget` candidate indices stratified by mutation type.
+///
+/// `weights` (one per candidate, e.g. difficulty) biases the draw within
+/// each type. Returned indices are in input order.
+pub fn stratified_sample(
+    mutation_types: &[String],
+    budget: usize,
+    weights: Option<&[f64]>,
+    seed: u64,
+) -> Result<Vec<usize>, String> {
+    if let Some(w) = weights {
+        if w.len() != mutation_types.len() {
+            return Err(format!("{} weights for {} mutations", w.len(), mutation_types.len()));
+        }
+        if let Some(bad) = w.iter().find(|x| x.is_nan() || **x < 0.0) {
+            return Err(format!("weights must be non-negative, got {}", bad));
+        }
+    }
+
+    let mut strata: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (i, t) in mutation_types.iter().enumerate() {
+        strata.entry(t.as_str()).or_default().push(i);
+    }
+    let groups: Vec<Vec<usize>> = strata.into_values().collect();
+
+    let mut rng = SplitMix64::new(seed);
+    let available: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    let counts = allocate_strata(&available, budget, &mut rng);
+
+    let mut selected: Vec<usize> = Vec::with_capacity(budget);
+    for (group, count) in groups.iter().zip(counts) {
+        let weight = |i: usize| weights.map_or(1.0, |w| w[i]);
+        selected.extend(weighted_pick(group, weight, count, &mut rng));
+    }
+    selected.sort_unstable();
+    Ok(selected)
+}
+
+/// Sample up to `budget` mutations evenly across mutation types.
+///
+/// `weights` optionally biases selection within a type (e.g. difficulty).
+/// Returns indices into `mutation_types`, in input order.
+#[pyfunction]
+#[pyo3(signature = (mutation_types, budget, weights = None, seed = None))]
+pub fn stratified_mutation_sample(
+    mutation_types: Vec<String>,
+    budget: usize,
+    weights: Option<Vec<f64>>,
+    seed: Option<u64>,
+) -> PyResult<Vec<usize>> {
+    let seed = resolve_seed(seed);
+    stratified_sample(&mutation_types, budget, weights.as_deref(), seed).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types(spec: &[(&str, usize)]) -> Vec<String> {
+        spec.iter()
+            .flat_map(|(t, n)| std::iter::repeat_n(t.to_string(), *n))
+            .collect()
+    }
+
+    #[test]
+    fn test_allocation_is_even_and_redistributes() {
+        let mut rng = SplitMix64::new(0);
+        assert_eq!(allocate_strata(&[10, 10, 10], 9, &mut rng), vec![3, 3, 3]);
+        assert_eq!(allocate_strata(&[1, 10, 10], 9, &mut rng), vec![1, 4, 4]);
+        assert_eq!(allocate_strata(&[2, 2], 10, &mut rng), vec![2, 2]);
+        let small = allocate_strata(&[5, 5, 5, 5], 2, &mut rng);
+        assert_eq!(small.iter().sum::<usize>(), 2);
+        assert!(small.iter().all(|&c| c <= 1));
+    }
+
+    #[test]
+    fn test_stratified_sample_covers_types() {
+        let t = types(&[("noise", 50), ("paraphrase", 3), ("prompt_injection", 20)]);
+        let picked = stratified_sample(&t, 12, None, 4).unwrap();
+        assert_eq!(picked.len(), 12);
+        let count = |name: &str| picked.iter().filter(|&&i| t[i] == name).count();
+        assert_eq!((count("noise"), count("paraphrase"), count("prompt_injection")), (5, 3, 4));
+        assert!(picked.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(picked, stratified_sample(&t, 12, None, 4).unwrap());
+    }
+
+    #[test]
+    fn test_weights_bias_within_type() {
+        let t = types(&[("noise", 10)]);
+        let mut w = vec![0.0; 10];
+        w[7] = 5.0;
+        w[2] = 1.0;
+        assert_eq!(stratified_sample(&t, 2, Some(&w), 1).unwrap(), vec![2, 7]);
+        assert!(stratified_sample(&t, 2, Some(&w[..3]), 1).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Response sanitizer for comparisons
+//!
+//! Agent frameworks wrap answers in tool-call scaffolding, exposed
+//! reasoning and stock pleasantries. Those vary from run to run and drown
+//! out real changes in the answer, so similarity and equivalence checks can
+//! strip them first: `match_expected_answers`, `similarity_matrix` and
+//! `canonical_equals` take an optional `sanitizer`. Built-in rule groups
+//! cover the common wrappers; callers add their own patterns for anything
+//! else.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use regex::Regex;
+
+/// Built-in rule groups: (group, patterns). Patterns are removed outright.
+const BUILTIN_RULES: &[(&str, &[&str])] = &[
+    (
+        "tool_calls",
+        &[
+            r"(?s)<tool_call>.*?</tool_call>",
+            r"(?s)<function_calls>.*?</function_calls>",
+            r"(?s)<tool_result>.*?</tool_result>",
+            r"(?s)```tool_code\s.*?```",
+            r"(?m)^\s*(Action|Action Input|Observation):.*$",
+        ],
+    ),
+    (
+        "reasoning",
+        &[
+            r"(?s)<think>.*?</think>",
+            r"(?s)<thinking>.*?</thinking>",
+            r"(?s)<reasoning>.*?</reasoning>",
+            r"(?m)^\s*Thought:.*$",
+        ],
+    ),
+    (
+        "boilerplate",
+        &[
+            r"(?i)as an ai (language )?model,?\s*",
+            r"(?i)i hope (this|that) helps[.!]?",
+            r"(?i)(please )?let me know if you (have any (other|more|further) questions|need anything else)[.!]?",
+            r"(?i)^(sure|certainly|of course)[,!.]\s*",
+        ],
+    ),
+];
+
+/// Names of the built-in rule groups.
+pub fn builtin_rule_groups() -> Vec<&'static str> {
+    BUILTIN_RULES.iter().map(|(name, _)| *name).collect()
+}
+
+/// Compiled set of removal rules
+#[derive(Debug, Clone)]
+pub struct Sanitizer {
+    /// (rule group or "custom", pattern)
+    rules: Vec<(String, Regex)>,
+    blank_lines: Regex,
+}
+
+impl Sanitizer {
+    /// Build from built-in groups plus custom regex patterns.
+    pub fn new<S: AsRef<str>>(groups: &[S], patterns: &[S]) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for group in groups {
+            let group = group.as_ref();
+            let (_, builtin) = BUILTIN_RULES
+                .iter()
+                .find(|(name, _)| *name == group)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown rule group '{}' (expected one of {})",
+                        group,
+                        builtin_rule_groups().join(", ")
+                    )
+                })?;
+            for pattern in *builtin {
+                let re = Regex::new(pattern).expect("built-in pattern compiles");
+                rules.push((group.to_string(), re));
+            }
+        }
+        for pattern in patterns {
+            let re = Regex::new(pattern.as_ref())
+                .map_err(|e| format!("invalid pattern '{}': {}", pattern.as_ref(), e))?;
+            rules.push(("custom".to_string(), re));
+        }
+        Ok(Self {
+            rules,
+            blank_lines: Regex::new(r"\n\s*\n+").expect("blank line pattern compiles"),
+        })
+    }
+
+    /// Remove every rule's matches, then tidy leftover blank lines.
+    pub fn sanitize(&self, text: &str) -> String {
+        let mut out = text.to_string();
+        for (_, re) in &self.rules {
+            if let std::borrow::Cow::Owned(replaced) = re.replace_all(&out, "") {
+                out = replaced;
+            }
+        }
+        self.blank_lines.replace_all(&out, "\n\n").trim().to_string()
+    }
+
+    /// Rule groups (and "custom") with at least one match in `text`, deduplicated in rule order.
+    pub fn matched_groups(&self, text: &str) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        for (group, re) in &self.rules {
+            if re.is_match(text) && !out.contains(group) {
+                out.push(group.clone());
+            }
+        }
+        out
+    }
+}
+
+/// Strips tool scaffolding, reasoning markers and boilerplate from responses
+#[pyclass(name = "ResponseSanitizer")]
+#[derive(Debug, Clone)]
+pub struct PyResponseSanitizer {
+    inner: Sanitizer,
+}
+
+impl PyResponseSanitizer {
+    pub fn sanitizer(&self) -> &Sanitizer {
+        &self.inner
+    }
+}
+
+#[pymethods]
+impl PyResponseSanitizer {
+    /// `groups` picks built-in rule groups ("tool_calls", "reasoning",
+    /// "boilerplate"; all by default) and `patterns` adds custom regexes.
+    #[new]
+    #[pyo3(signature = (groups = None, patterns = None))]
+    fn new(groups: Option<Vec<String>>, patterns: Option<Vec<String>>) -> PyResult<Self> {
+        let groups = groups.unwrap_or_else(|| builtin_rule_groups().into_iter().map(String::from).collect());
+        let inner = Sanitizer::new(&groups, &patterns.unwrap_or_default()).map_err(PyValueError::new_err)?;
+        Ok(Self { inner })
+    }
+
+    fn sanitize(&self, text: &str) -> String {
+        self.inner.sanitize(text)
+    }
+
+    /// Sanitize many responses in parallel.
+    fn sanitize_batch(&self, py: Python<'_>, texts: Vec<String>) -> Vec<String> {
+        py.allow_threads(|| texts.par_iter().map(|t| self.inner.sanitize(t)).collect())
+    }
+
+    fn matched_groups(&self, text: &str) -> Vec<String> {
+        self.inner.matched_groups(text)
+    }
+
+    #[staticmethod]
+    fn builtin_groups() -> Vec<&'static str> {
+        builtin_rule_groups()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all() -> Sanitizer {
+        Sanitizer::new(&builtin_rule_groups(), &[]).unwrap()
+    }
+
+    #[test]
+    fn test_strips_scaffolding_and_reasoning() {
+        let response = "<think>The user wants the capital.</think>\n\
+            <tool_call>{\"name\": \"lookup\", \"args\": {\"q\": \"France\"}}</tool_call>\n\
+            Observation: Paris\n\n\n\
+            The capital of France is Paris. I hope this helps!";
+        let s = all();
+        assert_eq!(s.sanitize(response), "The capital of France is Paris.");
+        assert_eq!(s.matched_groups(response), vec!["tool_calls", "reasoning", "boilerplate"]);
+        assert_eq!(s.sanitize("Paris."), "Paris.");
+    }
+
+    #[test]
+    fn test_group_selection_and_custom_patterns() {
+        let only_reasoning = Sanitizer::new(&["reasoning"], &[]).unwrap();
+        assert_eq!(only_reasoning.sanitize("Sure! <think>x</think>Paris"), "Sure! Paris");
+
+        let custom = Sanitizer::new(&[], &[r"\[ref:\d+\]"]).unwrap();
+        assert_eq!(custom.sanitize("Paris [ref:12] is the capital"), "Paris  is the capital");
+        assert_eq!(custom.matched_groups("x [ref:1]"), vec!["custom"]);
+
+        assert!(Sanitizer::new(&["nope"], &[]).is_err());
+        assert!(Sanitizer::new(&[], &["(unclosed"]).is_err());
+    }
+}
@@ -0,0 +1,362 @@
+//! SARIF 2.1 export of security findings
+//!
+//! Security teams triage in their code-scanning dashboards, which read
+//! SARIF. Failed security checks become SARIF results under one rule per
+//! finding kind:
+//!
+//! | rule  | finding                                    |
+//! |-------|--------------------------------------------|
+//! | FS001 | failed `canary_leak` check                 |
+//! | FS002 | failed `system_prompt_leak` check          |
+//! | FS003 | failed `no_pii` check                      |
+//! | FS004 | failed `tool_args_clean` check             |
+//! | FS005 | failed `unsafe_markup` check               |
+//! | FS006 | failed mutation of an injection-style type |
+//!
+//! A result's level follows its check severity (info is a note, warn a
+//! warning, error and critical an error); rules carry the
+//! `security-severity` score dashboards sort by. Findings have no source
+//! line, so every result points at the configured artifact, usually the
+//! flakestorm config that defined the run.
+//!
+//! Each result carries a `partialFingerprints` entry hashed from its rule,
+//! mutation type, check type and finding text (not its position in the
+//! run), so dashboards track the same finding across runs.
+
+use std::path::Path;
+
+use pyo3::exceptions::PyIOError;
+use pyo3::prelude::*;
+use serde_json::{json, Value};
+
+use crate::scoring::{CheckResult, MutationResult};
+use crate::severity::Severity;
+
+pub const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Mutation types whose failure means an injection got through
+pub const INJECTION_MUTATION_TYPES: &[&str] = &[
+    "prompt_injection",
+    "advanced_jailbreak",
+    "multi_turn_attack",
+    "encoding_attacks",
+    "format_poisoning",
+    "http_header_injection",
+    "query_parameter_poisoning",
+];
+
+struct Rule {
+    id: &'static str,
+    name: &'static str,
+    /// Check type the rule reports; None for the injection rule
+    check_type: Option<&'static str>,
+    description: &'static str,
+    help: &'static str,
+    security_severity: &'static str,
+}
+
+const RULES: &[Rule] = &[
+    Rule {
+        id: "FS001",
+        name: "CanaryLeak",
+        check_type: Some("canary_leak"),
+        description: "A canary token planted in the agent's context appeared in its response.",
+        help: "Treat context as confidential and filter responses for canaries and secrets.",
+        security_severity: "9.0",
+    },
+    Rule {
+        id: "FS002",
+        name: "SystemPromptLeak",
+        check_type: Some("system_prompt_leak"),
+        description: "The response disclosed part of the system prompt.",
+        help: "Refuse requests to repeat or summarize instructions, and check responses for system prompt fragments.",
+        security_severity: "7.5",
+    },
+    Rule {
+        id: "FS003",
+        name: "PiiDisclosure",
+        check_type: Some("no_pii"),
+        description: "The response contained personal data such as emails, phone numbers or card numbers.",
+        help: "Redact personal data from responses and from the context the agent can quote.",
+        security_severity: "7.0",
+    },
+    Rule {
+        id: "FS004",
+        name: "ToolArgumentAbuse",
+        check_type: Some("tool_args_clean"),
+        description: "A tool call carried an argument matching a forbidden pattern.",
+        help: "Validate tool arguments against an allow list before executing the call.",
+        security_severity: "8.5",
+    },
+    Rule {
+        id: "FS005",
+        name: "UnsafeMarkup",
+        check_type: Some("unsafe_markup"),
+        description: "The response contained markup that can exfiltrate data or run script when rendered.",
+        help: "Sanitize or escape model output before rendering it as Markdown or HTML.",
+        security_severity: "6.5",
+    },
+    Rule {
+        id: "FS006",
+        name: "PromptInjection",
+        check_type: None,
+        description: "An injection-style mutation made the agent fail its checks.",
+        help: "Keep untrusted input separate from instructions and re-check outputs of agents exposed to it.",
+        security_severity: "8.0",
+    },
+];
+
+const INJECTION_RULE: usize = 5;
+
+/// SARIF level of a check severity.
+pub fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Info => "note",
+        Severity::Warn => "warning",
+        Severity::Error | Severity::Critical => "error",
+    }
+}
+
+fn rule_json(rule: &Rule) -> Value {
+    let mut tags = vec!["security"];
+    if let Some(check_type) = rule.check_type {
+        tags.push(check_type);
+    }
+    json!({
+        "id": rule.id,
+        "name": rule.name,
+        "shortDescription": {"text": rule.description},
+        "fullDescription": {"text": rule.description},
+        "help": {"text": rule.help},
+        "defaultConfiguration": {"level": "error"},
+        "properties": {"tags": tags, "security-severity": rule.security_severity},
+    })
+}
+
+/// Key of `partialFingerprints` entries
+pub const FINGERPRINT_KEY: &str = "flakestormFinding/v1";
+
+/// FNV-1a hash of `parts`, NUL-separated, as 16 hex digits; stable across
+/// runs and platforms, unlike the standard library's hasher.
+fn fingerprint(parts: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for (i, part) in parts.iter().enumerate() {
+        let separator: &[u8] = if i == 0 { &[] } else { &[0] };
+        for &byte in separator.iter().chain(part.as_bytes()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn result_json(
+    rule_index: usize,
+    severity: Severity,
+    message: String,
+    finding: &str,
+    index: usize,
+    result: &MutationResult,
+    check: Option<&CheckResult>,
+    artifact_uri: &str,
+) -> Value {
+    let check_type = check.map_or("", |c| c.check_type.as_str());
+    json!({
+        "ruleId": RULES[rule_index].id,
+        "ruleIndex": rule_index,
+        "level": sarif_level(severity),
+        "message": {"text": message},
+        "partialFingerprints": {
+            FINGERPRINT_KEY: fingerprint(&[RULES[rule_index].id, &result.mutation_type, check_type, finding]),
+        },
+        "locations": [{
+            "physicalLocation": {
+                "artifactLocation": {"uri": artifact_uri},
+                "region": {"startLine": 1},
+            },
+        }],
+        "properties": {
+            "mutation_type": result.mutation_type,
+            "mutation_index": index,
+            "check_type": check.map(|c| c.check_type.as_str()),
+            "severity": severity.name(),
+            "tags": result.tags,
+        },
+    })
+}
+
+/// SARIF results for the security findings of a run.
+pub fn sarif_results(results: &[MutationResult], artifact_uri: &str) -> Vec<Value> {
+    let mut findings = Vec::new();
+    for (index, result) in results.iter().enumerate() {
+        let failed: Vec<&CheckResult> = result.checks.iter().filter(|c| !c.passed).collect();
+        for check in &failed {
+            let Some(rule_index) = RULES
+                .iter()
+                .position(|r| r.check_type == Some(check.check_type.as_str()))
+            else {
+                continue;
+            };
+            let mut message = format!("{} failed on {}[{}]", check.check_type, result.mutation_type, index);
+            if !check.details.is_empty() {
+                message.push_str(&format!(": {}", check.details));
+            }
+            findings.push(result_json(
+                rule_index,
+                check.severity,
+                message,
+                &check.details,
+                index,
+                result,
+                Some(check),
+                artifact_uri,
+            ));
+        }
+        if !result.passed && INJECTION_MUTATION_TYPES.contains(&result.mutation_type.as_str()) {
+            let severity = failed.iter().map(|c| c.severity).max().unwrap_or(Severity::Error);
+            let checks: Vec<&str> = failed.iter().map(|c| c.check_type.as_str()).collect();
+            let message = if checks.is_empty() {
+                format!("{}[{}] succeeded against the agent", result.mutation_type, index)
+            } else {
+                format!(
+                    "{}[{}] succeeded against the agent (failed: {})",
+                    result.mutation_type,
+                    index,
+                    checks.join(", ")
+                )
+            };
+            findings.push(result_json(
+                INJECTION_RULE,
+                severity,
+                message,
+                &checks.join(", "),
+                index,
+                result,
+                None,
+                artifact_uri,
+            ));
+        }
+    }
+    findings
+}
+
+/// SARIF 2.1 log, pretty-printed, for the security findings of a run.
+pub fn render_sarif(results: &[MutationResult], artifact_uri: &str) -> String {
+    sarif_log(sarif_results(results, artifact_uri))
+}
+
+/// SARIF 2.1 log, pretty-printed, reporting `findings`.
+fn sarif_log(findings: Vec<Value>) -> String {
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": SARIF_VERSION,
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "flakestorm",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/flakestorm/flakestorm",
+                    "rules": RULES.iter().map(rule_json).collect::<Vec<_>>(),
+                },
+            },
+            "results": findings,
+        }],
+    });
+    serde_json::to_string_pretty(&log).expect("SARIF log serializes")
+}
+
+/// Write the security findings of a run to `path` as a SARIF 2.1 log.
+///
+/// Failed canary, system-prompt, PII, tool-argument and markup checks and
+/// failed injection-style mutations are reported; every result points at
+/// `artifact_uri`. Returns the number of findings written.
+#[pyfunction]
+#[pyo3(signature = (results, path, artifact_uri = "flakestorm.yaml"))]
+pub fn write_sarif_report(
+    py: Python<'_>,
+    results: Vec<MutationResult>,
+    path: &str,
+    artifact_uri: &str,
+) -> PyResult<usize> {
+    py.allow_threads(|| {
+        let findings = sarif_results(&results, artifact_uri);
+        let count = findings.len();
+        std::fs::write(Path::new(path), sarif_log(findings)).map(|_| count)
+    })
+    .map_err(|e| PyIOError::new_err(format!("{}: {}", path, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(mutation_type: &str, checks: &[(&str, bool, Severity)]) -> MutationResult {
+        MutationResult {
+            mutation_type: mutation_type.to_string(),
+            passed: checks.iter().all(|c| c.1),
+            latency_ms: 10.0,
+            checks: checks
+                .iter()
+                .map(|&(check_type, passed, severity)| CheckResult {
+                    check_type: check_type.to_string(),
+                    passed,
+                    details: if passed {
+                        String::new()
+                    } else {
+                        "matched FS-CANARY-1".to_string()
+                    },
+                    severity,
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_findings() {
+        let results = vec![
+            result("noise", &[("contains", false, Severity::Error)]),
+            result(
+                "prompt_injection",
+                &[
+                    ("canary_leak", false, Severity::Critical),
+                    ("no_pii", true, Severity::Error),
+                ],
+            ),
+            result("paraphrase", &[("no_pii", false, Severity::Warn)]),
+            result("advanced_jailbreak", &[("contains", true, Severity::Error)]),
+        ];
+        let findings = sarif_results(&results, "flakestorm.yaml");
+        let rules: Vec<&str> = findings.iter().map(|f| f["ruleId"].as_str().unwrap()).collect();
+        assert_eq!(rules, vec!["FS001", "FS006", "FS003"]);
+        assert_eq!(findings[0]["level"], "error");
+        assert_eq!(
+            findings[0]["message"]["text"],
+            "canary_leak failed on prompt_injection[1]: matched FS-CANARY-1"
+        );
+        assert_eq!(findings[1]["properties"]["severity"], "critical");
+        assert_eq!(findings[2]["level"], "warning");
+        assert_eq!(findings[2]["ruleIndex"], 2);
+
+        // The same finding at another position keeps its fingerprint
+        let moved = sarif_results(&results[1..], "flakestorm.yaml");
+        let print = |f: &Value| f["partialFingerprints"][FINGERPRINT_KEY].as_str().unwrap().to_string();
+        assert_eq!(print(&moved[0]), print(&findings[0]));
+        assert_ne!(print(&findings[0]), print(&findings[1]));
+        assert_eq!(print(&findings[0]).len(), 16);
+        assert_ne!(fingerprint(&["ab", "c"]), fingerprint(&["a", "bc"]));
+    }
+
+    #[test]
+    fn test_render_sarif() {
+        let log: Value = serde_json::from_str(&render_sarif(&[], "configs/agent.yaml")).unwrap();
+        assert_eq!(log["version"], SARIF_VERSION);
+        let rules = log["runs"][0]["tool"]["driver"]["rules"].as_array().unwrap();
+        assert_eq!(rules.len(), RULES.len());
+        assert_eq!(rules[0]["properties"]["security-severity"], "9.0");
+        assert!(log["runs"][0]["results"].as_array().unwrap().is_empty());
+        assert_eq!(RULES[INJECTION_RULE].name, "PromptInjection");
+    }
+}
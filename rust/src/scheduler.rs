@@ -0,0 +1,338 @@
+//! Feedback-driven mutation scheduling
+//!
+//! Uniform sampling spends most of a budget on mutation types the agent
+//! already handles. The scheduler treats each type as a bandit arm whose
+//! reward is a failure found, keeps a Beta posterior over its failure rate,
+//! and fills each batch by Thompson sampling, so budget drifts toward weak
+//! spots while every type keeps some chance of being retried. An optional
+//! floor reserves a share of every batch for each type.
+//!
+//! The budget allocator turns a total spend (tokens or dollars), per-type
+//! weights and per-type cost estimates into how many mutations of each type
+//! to generate.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::rng::{resolve_seed, SplitMix64};
+
+/// Posterior state of one mutation type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Arm {
+    pub mutation_type: String,
+    /// Observed failures (reward) and passes, after decay
+    pub failures: f64,
+    pub passes: f64,
+}
+
+/// Thompson-sampling scheduler over mutation types
+#[derive(Debug, Clone)]
+pub struct MutationScheduler {
+    arms: Vec<Arm>,
+    /// Multiplier applied to past observations on every record, < 1 to
+    /// follow an agent whose weak spots move
+    pub decay: f64,
+    /// Fraction of each batch reserved per type, spread evenly
+    pub min_share: f64,
+    rng: SplitMix64,
+}
+
+impl MutationScheduler {
+    pub fn new(mutation_types: Vec<String>, decay: f64, min_share: f64, seed: u64) -> Result<Self, String> {
+        if mutation_types.is_empty() {
+            return Err("at least one mutation type is required".to_string());
+        }
+        if !(decay > 0.0 && decay <= 1.0) {
+            return Err(format!("decay must be in (0, 1], got {}", decay));
+        }
+        if !(0.0..=1.0).contains(&min_share) || min_share * mutation_types.len() as f64 > 1.0 {
+            return Err(format!(
+                "min_share must be in [0, 1/{}], got {}",
+                mutation_types.len(),
+                min_share
+            ));
+        }
+        Ok(Self {
+            arms: mutation_types
+                .into_iter()
+                .map(|mutation_type| Arm {
+                    mutation_type,
+                    failures: 0.0,
+                    passes: 0.0,
+                })
+                .collect(),
+            decay,
+            min_share,
+            rng: SplitMix64::new(seed),
+        })
+    }
+
+    /// Record one result for `mutation_type`.
+    pub fn record(&mut self, mutation_type: &str, passed: bool) -> Result<(), String> {
+        let i = self
+            .arms
+            .iter()
+            .position(|a| a.mutation_type == mutation_type)
+            .ok_or_else(|| format!("unknown mutation type '{}'", mutation_type))?;
+        if self.decay < 1.0 {
+            for arm in &mut self.arms {
+                arm.failures *= self.decay;
+                arm.passes *= self.decay;
+            }
+        }
+        let arm = &mut self.arms[i];
+        if passed {
+            arm.passes += 1.0;
+        } else {
+            arm.failures += 1.0;
+        }
+        Ok(())
+    }
+
+    /// Mutation types for the next `n` mutations: the per-type floor first,
+    /// then one Thompson draw per remaining slot.
+    pub fn next_batch(&mut self, n: usize) -> Vec<String> {
+        let floor = (n as f64 * self.min_share).floor() as usize;
+        let mut counts = vec![floor; self.arms.len()];
+        for _ in 0..n - floor * self.arms.len() {
+            let mut best = (0, f64::NEG_INFINITY);
+            for (i, arm) in self.arms.iter().enumerate() {
+                let draw = self.rng.beta(arm.failures + 1.0, arm.passes + 1.0);
+                if draw > best.1 {
+                    best = (i, draw);
+                }
+            }
+            counts[best.0] += 1;
+        }
+        self.arms
+            .iter()
+            .zip(counts)
+            .flat_map(|(arm, c)| std::iter::repeat_n(arm.mutation_type.clone(), c))
+            .collect()
+    }
+
+    /// Posterior mean failure rate per type.
+    pub fn failure_rates(&self) -> HashMap<String, f64> {
+        self.arms
+            .iter()
+            .map(|a| (a.mutation_type.clone(), (a.failures + 1.0) / (a.failures + a.passes + 2.0)))
+            .collect()
+    }
+
+    pub fn arms(&self) -> &[Arm] {
+        &self.arms
+    }
+}
+
+/// Mutation counts per type for a spend budget
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BudgetAllocation {
+    pub counts: BTreeMap<String, usize>,
+    pub spent: f64,
+    pub remaining: f64,
+}
+
+/// Most mutations one budget may buy
+pub const MAX_BUDGET_MUTATIONS: f64 = 1e7;
+
+/// Split `budget` across types in proportion to `weights`, then convert each
+/// share to a whole number of mutations at that type's cost.
+///
+/// Money left after rounding down buys extra mutations for whichever
+/// affordable type is furthest below its target, until nothing fits. The
+/// budget, weights and costs must be finite, and the budget may buy at
+/// most `MAX_BUDGET_MUTATIONS` of the cheapest type.
+pub fn allocate_budget(
+    budget: f64,
+    weights: &HashMap<String, f64>,
+    costs: &HashMap<String, f64>,
+) -> Result<BudgetAllocation, String> {
+    if !(budget.is_finite() && budget >= 0.0) {
+        return Err(format!("budget must be finite and non-negative, got {}", budget));
+    }
+    let mut types: Vec<(&String, f64, f64)> = Vec::new();
+    for (t, &w) in weights {
+        if !(w.is_finite() && w >= 0.0) {
+            return Err(format!("weight for '{}' must be finite and non-negative, got {}", t, w));
+        }
+        let cost = *costs.get(t).ok_or_else(|| format!("no cost estimate for '{}'", t))?;
+        if !(cost.is_finite() && cost > 0.0) {
+            return Err(format!("cost for '{}' must be finite and positive, got {}", t, cost));
+        }
+        if w > 0.0 {
+            types.push((t, w, cost));
+        }
+    }
+    types.sort_by(|a, b| a.0.cmp(b.0));
+    if let Some(cheapest) = types.iter().map(|t| t.2).min_by(f64::total_cmp) {
+        if budget / cheapest > MAX_BUDGET_MUTATIONS {
+            return Err(format!(
+                "budget {} buys more than {} mutations at cost {}",
+                budget, MAX_BUDGET_MUTATIONS, cheapest
+            ));
+        }
+    }
+    let total_weight: f64 = types.iter().map(|t| t.1).sum();
+
+    // Fractional target count per type
+    let targets: Vec<f64> = types
+        .iter()
+        .map(|&(_, w, cost)| budget * w / total_weight / cost)
+        .collect();
+    let mut counts: Vec<usize> = targets.iter().map(|t| (t + 1e-9).floor() as usize).collect();
+    let mut spent: f64 = counts.iter().zip(&types).map(|(&n, t)| n as f64 * t.2).sum();
+
+    loop {
+        let remaining = budget - spent;
+        let pick = (0..types.len())
+            .filter(|&i| types[i].2 <= remaining + 1e-9)
+            .max_by(|&a, &b| {
+                let gap = |i: usize| targets[i] - counts[i] as f64;
+                gap(a).total_cmp(&gap(b)).then(b.cmp(&a))
+            });
+        match pick {
+            Some(i) => {
+                counts[i] += 1;
+                spent += types[i].2;
+            }
+            None => break,
+        }
+    }
+
+    Ok(BudgetAllocation {
+        counts: types
+            .iter()
+            .zip(counts)
+            .filter(|(_, n)| *n > 0)
+            .map(|(t, n)| (t.0.clone(), n))
+            .collect(),
+        spent,
+        remaining: (budget - spent).max(0.0),
+    })
+}
+
+/// How many mutations of each type a spend budget buys.
+///
+/// `weights` is the desired share of spend per type and `costs` the
+/// estimated cost (tokens or dollars) of one mutation of that type.
+#[pyfunction]
+pub fn allocate_mutation_budget(
+    py: Python<'_>,
+    budget: f64,
+    weights: HashMap<String, f64>,
+    costs: HashMap<String, f64>,
+) -> PyResult<BudgetAllocation> {
+    py.allow_threads(|| allocate_budget(budget, &weights, &costs))
+        .map_err(PyValueError::new_err)
+}
+
+/// Adaptive choice of which mutation types to generate next.
+///
+/// Call `record_result(type, passed)` as results arrive and
+/// `next_batch(n)` to get the types of the next n mutations.
+#[pyclass(name = "MutationScheduler")]
+pub struct PyMutationScheduler {
+    inner: MutationScheduler,
+}
+
+#[pymethods]
+impl PyMutationScheduler {
+    #[new]
+    #[pyo3(signature = (mutation_types, decay = 1.0, min_share = 0.0, seed = None))]
+    fn new(mutation_types: Vec<String>, decay: f64, min_share: f64, seed: Option<u64>) -> PyResult<Self> {
+        let seed = resolve_seed(seed);
+        MutationScheduler::new(mutation_types, decay, min_share, seed)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    fn record_result(&mut self, mutation_type: &str, passed: bool) -> PyResult<()> {
+        self.inner.record(mutation_type, passed).map_err(PyValueError::new_err)
+    }
+
+    fn next_batch(&mut self, n: usize) -> Vec<String> {
+        self.inner.next_batch(n)
+    }
+
+    fn failure_rates(&self) -> HashMap<String, f64> {
+        self.inner.failure_rates()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types() -> Vec<String> {
+        vec!["noise".into(), "paraphrase".into(), "prompt_injection".into()]
+    }
+
+    #[test]
+    fn test_budget_shifts_to_failing_type() {
+        let mut s = MutationScheduler::new(types(), 1.0, 0.0, 3).unwrap();
+        for _ in 0..30 {
+            s.record("noise", true).unwrap();
+            s.record("paraphrase", true).unwrap();
+            s.record("prompt_injection", false).unwrap();
+        }
+        let batch = s.next_batch(100);
+        assert_eq!(batch.len(), 100);
+        let injections = batch.iter().filter(|t| *t == "prompt_injection").count();
+        assert!(injections > 90, "{}", injections);
+        assert!(s.failure_rates()["prompt_injection"] > 0.9);
+        assert!(s.record("typo", true).is_err());
+    }
+
+    #[test]
+    fn test_floor_and_decay() {
+        let mut s = MutationScheduler::new(types(), 0.5, 0.1, 0).unwrap();
+        for _ in 0..20 {
+            s.record("noise", false).unwrap();
+        }
+        let batch = s.next_batch(50);
+        assert!(types().iter().all(|t| batch.iter().filter(|b| *b == t).count() >= 5));
+
+        // with decay 0.5, old evidence fades quickly
+        for _ in 0..10 {
+            s.record("paraphrase", true).unwrap();
+        }
+        assert!(s.arms()[0].failures < 0.01);
+
+        assert!(MutationScheduler::new(types(), 1.0, 0.5, 0).is_err());
+        assert!(MutationScheduler::new(vec![], 1.0, 0.0, 0).is_err());
+    }
+
+    #[test]
+    fn test_allocate_budget() {
+        let map = |e: &[(&str, f64)]| e.iter().map(|(k, v)| (k.to_string(), *v)).collect::<HashMap<_, _>>();
+        let weights = map(&[("noise", 1.0), ("paraphrase", 1.0), ("prompt_injection", 2.0)]);
+        let costs = map(&[("noise", 1.0), ("paraphrase", 4.0), ("prompt_injection", 3.0)]);
+        let a = allocate_budget(100.0, &weights, &costs).unwrap();
+        // targets: noise 25, paraphrase 6.25, injection 16.67; the 3 left
+        // after rounding down buys one more injection
+        assert_eq!(a.counts["noise"], 25);
+        assert_eq!(a.counts["paraphrase"], 6);
+        assert_eq!(a.counts["prompt_injection"], 17);
+        assert_eq!((a.spent, a.remaining), (100.0, 0.0));
+
+        let zero = allocate_budget(2.0, &map(&[("noise", 1.0), ("x", 0.0)]), &map(&[("noise", 5.0), ("x", 1.0)])).unwrap();
+        assert!(zero.counts.is_empty());
+        assert!(allocate_budget(10.0, &weights, &map(&[("noise", 1.0)])).is_err());
+        assert!(allocate_budget(-1.0, &weights, &costs).is_err());
+        assert!(allocate_budget(f64::INFINITY, &weights, &costs).is_err());
+        assert!(allocate_budget(1e9, &weights, &costs).is_err());
+        assert!(allocate_budget(1.0, &map(&[("noise", f64::INFINITY)]), &costs).is_err());
+        assert!(allocate_budget(1.0, &weights, &map(&[("noise", f64::NAN)])).is_err());
+    }
+
+    #[test]
+    fn test_same_seed_same_batches() {
+        let mut a = MutationScheduler::new(types(), 1.0, 0.0, 9).unwrap();
+        let mut b = MutationScheduler::new(types(), 1.0, 0.0, 9).unwrap();
+        assert_eq!(a.next_batch(20), b.next_batch(20));
+    }
+}
@@ -3,9 +3,30 @@
 //! This module contains optimized scoring algorithms for calculating
 //! robustness metrics and aggregating test results.
 
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 
+use crate::bayes::{beta_posterior, BayesianEstimate, BetaPrior};
+use crate::composite::{CompositeConfig, CompositeScore};
+use crate::confidence::{strategy_interval, IntervalConfig, IntervalMethod, ScoreInterval};
+use crate::determinism::{DeterminismReport, DeterminismSummary};
+use crate::diff::lcs_length;
+use crate::failure_messages::{failure_messages, FailureMessageConfig, FailureMessages};
+use crate::latency::{
+    default_latency_buckets, latency_outliers, LatencyHistogram, LatencyOutliers, OutlierConfig, OutlierMethod,
+};
+use crate::length::{length_drift_statistics, LengthDriftStatistics, LengthThresholds, OutputLength};
+use crate::serialization::{from_json, to_json};
+use crate::severity::{severity_breakdown, Severity, SeverityBreakdown};
+use crate::similarity::Tokenizer;
+use crate::strategy::{validate_credits, Credit, ScoringConfig};
+use crate::throughput::{result_throughput, ThroughputConfig, ThroughputStatistics};
+
 /// Result of a single mutation test
+#[pyclass(get_all)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MutationResult {
     pub mutation_type: String,
@@ -13,20 +34,352 @@ pub struct MutationResult {
     pub weight: f64,
     pub latency_ms: f64,
     pub checks: Vec<CheckResult>,
+    /// Transport-level measurements, when the client captured them
+    #[serde(default)]
+    pub resources: Option<ResourceUsage>,
+    /// Output and baseline response lengths, when the runner recorded them
+    #[serde(default)]
+    pub output_length: Option<OutputLength>,
+    /// Free-form metadata such as model=gpt-4o or suite=checkout-flow
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+    /// Completion time in ms since the Unix epoch, when the runner recorded it
+    #[serde(default)]
+    pub timestamp_ms: Option<f64>,
+    /// Token counts reported by the provider
+    #[serde(default)]
+    pub prompt_tokens: Option<usize>,
+    #[serde(default)]
+    pub completion_tokens: Option<usize>,
+    /// Cost of the agent call, in the caller's currency
+    #[serde(default)]
+    pub cost: Option<f64>,
+}
+
+#[pymethods]
+impl MutationResult {
+    #[new]
+    #[pyo3(signature = (
+        mutation_type, passed, weight = 1.0, latency_ms = 0.0, checks = Vec::new(), resources = None,
+        output_length = None, tags = BTreeMap::new(), timestamp_ms = None, prompt_tokens = None,
+        completion_tokens = None, cost = None
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        mutation_type: String,
+        passed: bool,
+        weight: f64,
+        latency_ms: f64,
+        checks: Vec<CheckResult>,
+        resources: Option<ResourceUsage>,
+        output_length: Option<OutputLength>,
+        tags: BTreeMap<String, String>,
+        timestamp_ms: Option<f64>,
+        prompt_tokens: Option<usize>,
+        completion_tokens: Option<usize>,
+        cost: Option<f64>,
+    ) -> PyResult<Self> {
+        let result = Self {
+            mutation_type,
+            passed,
+            weight,
+            latency_ms,
+            checks,
+            resources,
+            output_length,
+            tags,
+            timestamp_ms,
+            prompt_tokens,
+            completion_tokens,
+            cost,
+        };
+        result.validate().map_err(pyo3::exceptions::PyValueError::new_err)?;
+        Ok(result)
+    }
+
+    /// The result as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json::<Self>(json)
+            .and_then(|r| r.validate().map(|_| r))
+            .map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+impl MutationResult {
+    /// Check that every number is finite, so the result survives a JSON
+    /// round trip (JSON has no NaN or infinity), and that the weight and
+    /// cost are non-negative.
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.weight.is_finite() && self.weight >= 0.0) {
+            return Err(format!(
+                "weights must be finite and non-negative, got {} for '{}'",
+                self.weight, self.mutation_type
+            ));
+        }
+        let rate = self.resources.as_ref().and_then(|u| u.tokens_per_sec);
+        for (name, value) in [
+            ("latency_ms", Some(self.latency_ms)),
+            ("timestamp_ms", self.timestamp_ms),
+            ("tokens_per_sec", rate),
+        ] {
+            if let Some(v) = value.filter(|v| !v.is_finite()) {
+                return Err(format!("{} must be finite, got {} for '{}'", name, v, self.mutation_type));
+            }
+        }
+        validate_cost(self)
+    }
+}
+
+/// A failed mutation of no type with weight 1.0 and nothing recorded, so
+/// results can be built with `..Default::default()`
+impl Default for MutationResult {
+    fn default() -> Self {
+        Self {
+            mutation_type: String::new(),
+            passed: false,
+            weight: 1.0,
+            latency_ms: 0.0,
+            checks: Vec::new(),
+            resources: None,
+            output_length: None,
+            tags: BTreeMap::new(),
+            timestamp_ms: None,
+            prompt_tokens: None,
+            completion_tokens: None,
+            cost: None,
+        }
+    }
+}
+
+/// Transport-level measurements for one agent call
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    pub response_bytes: usize,
+    pub tokens: Option<usize>,
+    pub tokens_per_sec: Option<f64>,
+    pub http_status: Option<u16>,
+    /// Response headers, names lowercased
+    pub headers: HashMap<String, String>,
+}
+
+#[pymethods]
+impl ResourceUsage {
+    #[new]
+    #[pyo3(signature = (response_bytes, tokens = None, tokens_per_sec = None, http_status = None, headers = None))]
+    fn py_new(
+        response_bytes: usize,
+        tokens: Option<usize>,
+        tokens_per_sec: Option<f64>,
+        http_status: Option<u16>,
+        headers: Option<HashMap<String, String>>,
+    ) -> Self {
+        Self {
+            response_bytes,
+            tokens,
+            tokens_per_sec,
+            http_status,
+            headers: headers
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(k, v)| (k.to_ascii_lowercase(), v))
+                .collect(),
+        }
+    }
+
+    /// The usage as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+impl ResourceUsage {
+    /// Rate limiting or overload reported by the server.
+    pub fn throttled(&self) -> bool {
+        matches!(self.http_status, Some(429 | 503)) || self.headers.contains_key("retry-after")
+    }
+
+    /// Body shorter than the advertised Content-Length, as when a proxy cuts the response.
+    pub fn truncated(&self) -> bool {
+        self.headers
+            .get("content-length")
+            .and_then(|v| v.trim().parse::<usize>().ok())
+            .is_some_and(|expected| self.response_bytes < expected)
+    }
+}
+
+/// Aggregate transport measurements over the results that have them
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResourceStatistics {
+    /// Results that carried resource usage
+    pub measured: usize,
+    pub avg_response_bytes: f64,
+    pub max_response_bytes: usize,
+    pub avg_tokens_per_sec: Option<f64>,
+    /// Count per HTTP status code
+    pub status_counts: HashMap<u16, usize>,
+    pub throttled: usize,
+    pub truncated: usize,
+}
+
+/// Summarize resource usage; entries without measurements are skipped.
+pub fn resource_statistics<'a>(usages: impl IntoIterator<Item = &'a ResourceUsage>) -> ResourceStatistics {
+    let mut stats = ResourceStatistics::default();
+    let mut total_bytes = 0usize;
+    let mut rates: Vec<f64> = Vec::new();
+    for u in usages {
+        stats.measured += 1;
+        total_bytes += u.response_bytes;
+        stats.max_response_bytes = stats.max_response_bytes.max(u.response_bytes);
+        rates.extend(u.tokens_per_sec.filter(|r| r.is_finite()));
+        if let Some(status) = u.http_status {
+            *stats.status_counts.entry(status).or_default() += 1;
+        }
+        stats.throttled += usize::from(u.throttled());
+        stats.truncated += usize::from(u.truncated());
+    }
+    if stats.measured > 0 {
+        stats.avg_response_bytes = total_bytes as f64 / stats.measured as f64;
+    }
+    if !rates.is_empty() {
+        stats.avg_tokens_per_sec = Some(rates.iter().sum::<f64>() / rates.len() as f64);
+    }
+    stats
 }
 
 /// Result of a single invariant check
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct CheckResult {
     pub check_type: String,
     pub passed: bool,
     pub details: String,
+    /// How much a failure matters in severity scoring
+    #[serde(default)]
+    pub severity: Severity,
+}
+
+#[pymethods]
+impl CheckResult {
+    #[new]
+    #[pyo3(signature = (check_type, passed, details = String::new(), severity = "error"))]
+    fn py_new(check_type: String, passed: bool, details: String, severity: &str) -> PyResult<Self> {
+        Ok(Self {
+            check_type,
+            passed,
+            details,
+            severity: Severity::parse(severity).map_err(pyo3::exceptions::PyValueError::new_err)?,
+        })
+    }
+
+    /// The check result as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+/// Token and cost totals over the results that recorded them
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostStatistics {
+    /// Results that recorded token counts or a cost
+    pub measured: usize,
+    pub prompt_tokens: usize,
+    pub completion_tokens: usize,
+    pub total_cost: f64,
+    /// Mean cost of the results that recorded one
+    pub avg_cost: f64,
+    /// Total cost per failed mutation, both over the results that recorded
+    /// a cost; None when none of those failed
+    pub cost_per_failure: Option<f64>,
+}
+
+#[pymethods]
+impl CostStatistics {
+    /// The statistics as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+/// Check that a recorded cost is finite and non-negative.
+pub(crate) fn validate_cost(result: &MutationResult) -> Result<(), String> {
+    match result.cost {
+        Some(cost) if !(cost.is_finite() && cost >= 0.0) => Err(format!(
+            "cost must be finite and non-negative, got {} for '{}'",
+            cost, result.mutation_type
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Summarize tokens and cost of `results`; None when no result recorded
+/// either. Fails on a negative or non-finite cost.
+pub fn cost_statistics<'a>(
+    results: impl IntoIterator<Item = &'a MutationResult>,
+) -> Result<Option<CostStatistics>, String> {
+    let mut stats = CostStatistics::default();
+    let (mut priced, mut priced_failed) = (0usize, 0usize);
+    for r in results {
+        validate_cost(r)?;
+        if r.prompt_tokens.is_none() && r.completion_tokens.is_none() && r.cost.is_none() {
+            continue;
+        }
+        stats.measured += 1;
+        stats.prompt_tokens += r.prompt_tokens.unwrap_or(0);
+        stats.completion_tokens += r.completion_tokens.unwrap_or(0);
+        if let Some(cost) = r.cost {
+            priced += 1;
+            priced_failed += usize::from(!r.passed);
+            stats.total_cost += cost;
+        }
+    }
+    if stats.measured == 0 {
+        return Ok(None);
+    }
+    if priced > 0 {
+        stats.avg_cost = stats.total_cost / priced as f64;
+    }
+    if priced_failed > 0 {
+        stats.cost_per_failure = Some(stats.total_cost / priced_failed as f64);
+    }
+    Ok(Some(stats))
 }
 
 /// Aggregate statistics for a test run
+///
+/// Built from results by `calculate_statistics` and round-tripped through
+/// `to_json`/`from_json`.
+#[pyclass(get_all)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TestStatistics {
     pub total_mutations: usize,
+    /// Mutations with full credit; the passing mutations in flat scoring
     pub passed_mutations: usize,
     pub failed_mutations: usize,
     pub robustness_score: f64,
@@ -35,36 +388,376 @@ pub struct TestStatistics {
     pub p95_latency_ms: f64,
     pub p99_latency_ms: f64,
     pub by_type: Vec<TypeStatistics>,
+    /// Present when at least one result recorded resource usage
+    pub resources: Option<ResourceStatistics>,
+    /// Present when at least one result recorded output lengths
+    #[serde(default)]
+    pub length_drift: Option<LengthDriftStatistics>,
+    /// Present once a determinism report over repeated runs is attached
+    #[serde(default)]
+    pub determinism: Option<DeterminismSummary>,
+    /// Bounds on the robustness score; absent for an empty run
+    #[serde(default)]
+    pub confidence_interval: Option<ScoreInterval>,
+    /// Present once scored against a Beta prior
+    #[serde(default)]
+    pub bayesian: Option<BayesianEstimate>,
+    /// Present once a composite robustness/latency/cost score is attached
+    #[serde(default)]
+    pub composite: Option<CompositeScore>,
+    /// Invariant check outcomes by check type, sorted by check type
+    #[serde(default)]
+    pub by_check: Vec<CheckStatistics>,
+    /// Latency distribution; absent for an empty run
+    #[serde(default)]
+    pub latency_histogram: Option<LatencyHistogram>,
+    /// Unusually slow results; absent for an empty run
+    #[serde(default)]
+    pub latency_outliers: Option<LatencyOutliers>,
+    /// Statistics per tag key and value, sorted by key then value
+    #[serde(default)]
+    pub by_tag: Vec<TagStatistics>,
+    /// Present when at least one result recorded a timestamp
+    #[serde(default)]
+    pub throughput: Option<ThroughputStatistics>,
+    /// Present when at least one result recorded tokens or a cost
+    #[serde(default)]
+    pub cost: Option<CostStatistics>,
+    /// Failed checks by severity, in every scoring mode; absent only in
+    /// exports from older versions
+    #[serde(default)]
+    pub severity: Option<SeverityBreakdown>,
+    /// Present once failed-check details are grouped, see `with_failure_messages`
+    #[serde(default)]
+    pub failure_messages: Option<FailureMessages>,
+}
+
+#[pymethods]
+impl TestStatistics {
+    /// The statistics as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    /// Statistics from a JSON export, such as a stored baseline.
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+
+    /// A copy with the composite robustness/latency/cost score attached;
+    /// `cost` is the run's cost when known, see `run_cost`.
+    #[pyo3(name = "with_composite", signature = (config = None, cost = None))]
+    fn py_with_composite(&self, config: Option<CompositeConfig>, cost: Option<f64>) -> PyResult<Self> {
+        self.clone()
+            .with_composite(&config.unwrap_or_default(), cost)
+            .map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+
+    /// A copy with the run-level summary of a determinism report attached.
+    #[pyo3(name = "with_determinism")]
+    fn py_with_determinism(&self, report: DeterminismReport) -> Self {
+        self.clone().with_determinism(&report)
+    }
+}
+
+impl TestStatistics {
+    /// Attach the run-level summary of a determinism report.
+    pub fn with_determinism(mut self, report: &DeterminismReport) -> Self {
+        self.determinism = Some(report.summary.clone());
+        self
+    }
+
+    /// Attach a posterior robustness estimate under `prior`, counting the
+    /// run by the effective sample size of its score interval.
+    pub fn with_prior(mut self, prior: &BetaPrior, credible: f64) -> Result<Self, String> {
+        let n = self
+            .confidence_interval
+            .as_ref()
+            .map_or(self.total_mutations as f64, |i| i.effective_n);
+        self.bayesian = Some(beta_posterior(self.robustness_score * n, n, prior, credible)?);
+        Ok(self)
+    }
+
+    /// Attach the composite score under `config`; `cost` is the run's cost
+    /// when known, see `run_cost`.
+    pub fn with_composite(mut self, config: &CompositeConfig, cost: Option<f64>) -> Result<Self, String> {
+        self.composite = Some(config.score_statistics(&self, cost)?);
+        Ok(self)
+    }
+
+    /// Attach the most frequent distinct failure messages of `results`,
+    /// the results these statistics were calculated from.
+    pub fn with_failure_messages(
+        mut self,
+        results: &[MutationResult],
+        config: &FailureMessageConfig,
+    ) -> Result<Self, String> {
+        self.failure_messages = Some(failure_messages(results, config)?);
+        Ok(self)
+    }
 }
 
 /// Statistics broken down by mutation type
+#[pyclass(get_all)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TypeStatistics {
     pub mutation_type: String,
     pub total: usize,
+    /// Mutations with full credit
     pub passed: usize,
+    /// Mean mutation credit; the fraction passed in flat scoring
     pub pass_rate: f64,
+    /// Score of the type's results under the run's scoring strategy
+    #[serde(default)]
+    pub robustness_score: f64,
+    #[serde(default)]
+    pub p50_latency_ms: f64,
+    #[serde(default)]
+    pub p95_latency_ms: f64,
+    #[serde(default)]
+    pub p99_latency_ms: f64,
+    /// Present when at least one result of the type recorded tokens or a cost
+    #[serde(default)]
+    pub cost: Option<CostStatistics>,
 }
 
-/// Calculate comprehensive statistics from mutation results
-pub fn calculate_statistics(results: &[MutationResult]) -> TestStatistics {
+#[pymethods]
+impl TypeStatistics {
+    /// The statistics as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+/// Statistics for the results carrying one tag value
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagStatistics {
+    pub key: String,
+    pub value: String,
+    pub total: usize,
+    /// Tagged mutations with full credit
+    pub passed: usize,
+    /// Mean mutation credit; the fraction passed in flat scoring
+    pub pass_rate: f64,
+    /// Score of the tagged results under the run's scoring strategy
+    pub robustness_score: f64,
+    pub p50_latency_ms: f64,
+    pub p95_latency_ms: f64,
+    pub p99_latency_ms: f64,
+}
+
+#[pymethods]
+impl TagStatistics {
+    /// The statistics as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+/// Statistics for one invariant check type across all results
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CheckStatistics {
+    pub check_type: String,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub pass_rate: f64,
+    /// Most frequent failure details with their counts, most frequent first
+    pub top_failures: Vec<(String, usize)>,
+}
+
+#[pymethods]
+impl CheckStatistics {
+    /// The statistics as JSON, compact unless `pretty`.
+    #[pyo3(signature = (pretty = false))]
+    fn to_json(&self, pretty: bool) -> String {
+        to_json(self, pretty)
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        from_json(json).map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+}
+
+/// Failure details kept per check type in `CheckStatistics`
+const TOP_FAILURE_DETAILS: usize = 3;
+
+/// Aggregate check results by check type
+pub fn check_statistics<'a>(checks: impl IntoIterator<Item = &'a CheckResult>) -> Vec<CheckStatistics> {
+    let mut groups: BTreeMap<&str, (usize, usize, HashMap<&str, usize>)> = BTreeMap::new();
+    for check in checks {
+        let (total, passed, failures) = groups.entry(&check.check_type).or_default();
+        *total += 1;
+        if check.passed {
+            *passed += 1;
+        } else {
+            *failures.entry(&check.details).or_default() += 1;
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(check_type, (total, passed, failures))| {
+            let mut top: Vec<(String, usize)> = failures.into_iter().map(|(d, n)| (d.to_string(), n)).collect();
+            top.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            top.truncate(TOP_FAILURE_DETAILS);
+            CheckStatistics {
+                check_type: check_type.to_string(),
+                total,
+                passed,
+                failed: total - passed,
+                pass_rate: passed as f64 / total as f64,
+                top_failures: top,
+            }
+        })
+        .collect()
+}
+
+/// Options for `calculate_statistics_with`
+#[pyclass]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatisticsConfig {
+    /// How the robustness score interval is computed
+    pub interval: IntervalConfig,
+    /// Latency histogram bucket upper bounds in ms
+    pub latency_buckets: Vec<f64>,
+    /// How latency percentiles are interpolated
+    pub percentile_method: PercentileMethod,
+    /// Weight each latency by its mutation's weight in the percentiles
+    pub weighted_latency: bool,
+    /// How unusually slow results are detected
+    pub outliers: OutlierConfig,
+    /// Time bucket width and rolling window for throughput statistics
+    pub throughput: ThroughputConfig,
+    /// Credit per mutation and how credits are aggregated into the score
+    pub scoring: ScoringConfig,
+    /// Bounds for flagging outputs as too short or too long
+    pub length: LengthThresholds,
+}
+
+impl Default for StatisticsConfig {
+    fn default() -> Self {
+        Self {
+            interval: IntervalConfig::default(),
+            latency_buckets: default_latency_buckets(),
+            percentile_method: PercentileMethod::default(),
+            weighted_latency: false,
+            outliers: OutlierConfig::default(),
+            throughput: ThroughputConfig::default(),
+            scoring: ScoringConfig::default(),
+            length: LengthThresholds::default(),
+        }
+    }
+}
+
+impl StatisticsConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        self.interval.validate()?;
+        self.outliers.validate()?;
+        self.throughput.validate()?;
+        self.scoring.validate()?;
+        self.length.validate()
+    }
+}
+
+#[pymethods]
+impl StatisticsConfig {
+    #[new]
+    #[pyo3(signature = (
+        confidence = 0.95, interval_method = "wilson", resamples = 2000, seed = None, latency_buckets = None,
+        percentile_method = "linear", weighted_latency = false, outlier_method = "mad", outlier_threshold = None,
+        bucket_ms = 60_000.0, window = 5, scoring = None, short_ratio = 0.25, long_ratio = 4.0,
+        min_baseline_chars = 20
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        confidence: f64,
+        interval_method: &str,
+        resamples: usize,
+        seed: Option<u64>,
+        latency_buckets: Option<Vec<f64>>,
+        percentile_method: &str,
+        weighted_latency: bool,
+        outlier_method: &str,
+        outlier_threshold: Option<f64>,
+        bucket_ms: f64,
+        window: usize,
+        scoring: Option<ScoringConfig>,
+        short_ratio: f64,
+        long_ratio: f64,
+        min_baseline_chars: usize,
+    ) -> PyResult<Self> {
+        let err = pyo3::exceptions::PyValueError::new_err;
+        let outlier_method = OutlierMethod::parse(outlier_method).map_err(err)?;
+        let config = Self {
+            interval: IntervalConfig {
+                confidence,
+                method: IntervalMethod::parse(interval_method).map_err(err)?,
+                resamples,
+                seed,
+            },
+            latency_buckets: latency_buckets.unwrap_or_else(default_latency_buckets),
+            percentile_method: PercentileMethod::parse(percentile_method).map_err(err)?,
+            weighted_latency,
+            outliers: OutlierConfig {
+                method: outlier_method,
+                threshold: outlier_threshold.unwrap_or(outlier_method.default_threshold()),
+            },
+            throughput: ThroughputConfig { bucket_ms, window },
+            scoring: scoring.unwrap_or_default(),
+            length: LengthThresholds {
+                short_ratio,
+                long_ratio,
+                min_baseline_chars,
+            },
+        };
+        config.validate().map_err(err)?;
+        Ok(config)
+    }
+}
+
+/// Calculate comprehensive statistics from mutation results, with a 95%
+/// Wilson interval on the robustness score and log-scale latency buckets.
+/// Fails on a result that does not validate, such as a negative weight or
+/// a NaN latency.
+pub fn calculate_statistics(results: &[MutationResult]) -> Result<TestStatistics, String> {
+    calculate_statistics_with(results, &StatisticsConfig::default())
+}
+
+/// `calculate_statistics` with the score interval, latency buckets,
+/// percentile, outlier, throughput, scoring and length options set by
+/// `config`
+pub fn calculate_statistics_with(results: &[MutationResult], config: &StatisticsConfig) -> Result<TestStatistics, String> {
+    config.validate()?;
+    for r in results {
+        r.validate()?;
+    }
     let total = results.len();
-    let passed = results.iter().filter(|r| r.passed).count();
-    let failed = total - passed;
 
     // Calculate robustness score
-    let total_weight: f64 = results.iter().map(|r| r.weight).sum();
-    let passed_weight: f64 = results
-        .iter()
-        .filter(|r| r.passed)
-        .map(|r| r.weight)
-        .sum();
-
-    let robustness_score = if total_weight > 0.0 {
-        passed_weight / total_weight
-    } else {
-        0.0
-    };
+    let strategy = config.scoring.strategy;
+    let credits = config.scoring.credits(results)?;
+    let robustness_score = strategy.score(&credits);
+    // Counts and rates below come from the same credits as the score, so in
+    // severity mode a mutation passes only with full credit
+    let passed = credits.iter().filter(|c| c.2 >= 1.0).count();
+    let failed = total - passed;
 
     // Calculate latency statistics
     let mut latencies: Vec<f64> = results.iter().map(|r| r.latency_ms).collect();
@@ -76,33 +769,82 @@ pub fn calculate_statistics(results: &[MutationResult]) -> TestStatistics {
         0.0
     };
 
-    let p50 = percentile(&latencies, 50);
-    let p95 = percentile(&latencies, 95);
-    let p99 = percentile(&latencies, 99);
+    let [p50, p95, p99] = latency_percentiles(results.iter(), config)?;
+    let latency_histogram = LatencyHistogram::from_latencies(&latencies, &config.latency_buckets)?;
+    let latency_outliers = latency_outliers(
+        &results.iter().map(|r| r.latency_ms).collect::<Vec<_>>(),
+        &config.outliers,
+    )?;
 
     // Statistics by mutation type
-    let mut type_stats = std::collections::HashMap::new();
-    for result in results {
-        let entry = type_stats
-            .entry(result.mutation_type.clone())
-            .or_insert((0usize, 0usize));
-        entry.0 += 1;
-        if result.passed {
-            entry.1 += 1;
-        }
+    let mut type_stats: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, result) in results.iter().enumerate() {
+        type_stats.entry(&result.mutation_type).or_default().push(i);
     }
 
     let by_type: Vec<TypeStatistics> = type_stats
         .into_iter()
-        .map(|(mutation_type, (total, passed))| TypeStatistics {
-            mutation_type,
-            total,
-            passed,
-            pass_rate: passed as f64 / total as f64,
+        .map(|(mutation_type, members)| {
+            let group: Vec<&MutationResult> = members.iter().map(|&i| &results[i]).collect();
+            let (passed, pass_rate) = credit_counts(&members, &credits);
+            let [p50, p95, p99] = latency_percentiles(group.iter().copied(), config)?;
+            Ok(TypeStatistics {
+                mutation_type: mutation_type.to_string(),
+                total: group.len(),
+                passed,
+                pass_rate,
+                robustness_score: strategy.score(&members.iter().map(|&i| credits[i]).collect::<Vec<_>>()),
+                p50_latency_ms: p50,
+                p95_latency_ms: p95,
+                p99_latency_ms: p99,
+                cost: cost_statistics(group.iter().copied())?,
+            })
         })
-        .collect();
+        .collect::<Result<_, String>>()?;
+
+    let throughput = result_throughput(results, &config.throughput, config.percentile_method)?;
+
+    let mut tag_groups: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for (i, result) in results.iter().enumerate() {
+        for (key, value) in &result.tags {
+            tag_groups.entry((key, value)).or_default().push(i);
+        }
+    }
+    let by_tag: Vec<TagStatistics> = tag_groups
+        .into_iter()
+        .map(|((key, value), members)| {
+            let group: Vec<&MutationResult> = members.iter().map(|&i| &results[i]).collect();
+            let (passed, pass_rate) = credit_counts(&members, &credits);
+            let [p50, p95, p99] = latency_percentiles(group.iter().copied(), config)?;
+            Ok(TagStatistics {
+                key: key.to_string(),
+                value: value.to_string(),
+                total: group.len(),
+                passed,
+                pass_rate,
+                robustness_score: strategy.score(&members.iter().map(|&i| credits[i]).collect::<Vec<_>>()),
+                p50_latency_ms: p50,
+                p95_latency_ms: p95,
+                p99_latency_ms: p99,
+            })
+        })
+        .collect::<Result<_, String>>()?;
 
-    TestStatistics {
+    let resources = Some(resource_statistics(results.iter().filter_map(|r| r.resources.as_ref())))
+        .filter(|r| r.measured > 0);
+    let length_drift = Some(length_drift_statistics(
+        results.iter().filter_map(|r| r.output_length.as_ref()),
+        &config.length,
+        config.percentile_method,
+    ))
+    .filter(|l| l.measured > 0);
+    let confidence_interval = if credits.is_empty() {
+        None
+    } else {
+        Some(strategy_interval(&credits, strategy, &config.interval)?)
+    };
+
+    Ok(TestStatistics {
         total_mutations: total,
         passed_mutations: passed,
         failed_mutations: failed,
@@ -112,28 +854,828 @@ pub fn calculate_statistics(results: &[MutationResult]) -> TestStatistics {
         p95_latency_ms: p95,
         p99_latency_ms: p99,
         by_type,
+        resources,
+        length_drift,
+        determinism: None,
+        confidence_interval,
+        bayesian: None,
+        composite: None,
+        by_check: check_statistics(results.iter().flat_map(|r| &r.checks)),
+        latency_histogram,
+        latency_outliers,
+        by_tag,
+        throughput,
+        cost: cost_statistics(results)?,
+        severity: Some(severity_breakdown(results.iter().map(|r| r.checks.as_slice()))),
+        failure_messages: None,
+    })
+}
+
+/// Mutations with full credit among `members`, and their mean credit.
+fn credit_counts(members: &[usize], credits: &[Credit]) -> (usize, f64) {
+    let passed = members.iter().filter(|&&i| credits[i].2 >= 1.0).count();
+    let mean = members.iter().map(|&i| credits[i].2).sum::<f64>() / members.len() as f64;
+    (passed, mean)
+}
+
+/// Settings for a weight sensitivity analysis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SensitivityConfig {
+    /// Weight per mutation type; types missing here default to 1.0
+    pub weights: HashMap<String, f64>,
+    /// Relative perturbation applied to each weight (0.1 = ±10%)
+    pub perturbation: f64,
+    /// Score movement at or above which a weight is flagged as dominant
+    pub dominant_threshold: f64,
+    /// Score movement below which a weight is flagged as negligible
+    pub negligible_threshold: f64,
+}
+
+impl Default for SensitivityConfig {
+    fn default() -> Self {
+        Self {
+            weights: HashMap::new(),
+            perturbation: 0.1,
+            dominant_threshold: 0.05,
+            negligible_threshold: 0.001,
+        }
+    }
+}
+
+impl SensitivityConfig {
+    /// Weights must be finite and non-negative and the perturbation in
+    /// (0, 1], so no perturbed weight goes negative.
+    pub fn validate(&self) -> Result<(), String> {
+        let credits: Vec<Credit> = self.weights.iter().map(|(t, &w)| (t.as_str(), w, 1.0)).collect();
+        validate_credits(&credits)?;
+        if !(self.perturbation > 0.0 && self.perturbation <= 1.0) {
+            return Err(format!("perturbation must be in (0, 1], got {}", self.perturbation));
+        }
+        Ok(())
+    }
+}
+
+/// How much the weighted score moves when one mutation type's weight is perturbed
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightSensitivity {
+    pub mutation_type: String,
+    pub weight: f64,
+    pub baseline_score: f64,
+    pub score_weight_down: f64,
+    pub score_weight_up: f64,
+    /// Largest absolute score change from either perturbation
+    pub sensitivity: f64,
+    /// "dominant", "negligible" or "normal"
+    pub classification: String,
+}
+
+fn weighted_type_score(results: &[(String, bool)], weights: &HashMap<String, f64>) -> f64 {
+    let mut total_weight = 0.0;
+    let mut passed_weight = 0.0;
+    for (mutation_type, passed) in results {
+        let w = weights.get(mutation_type).copied().unwrap_or(1.0);
+        total_weight += w;
+        if *passed {
+            passed_weight += w;
+        }
+    }
+    if total_weight > 0.0 {
+        passed_weight / total_weight
+    } else {
+        0.0
+    }
+}
+
+/// Perturb each mutation type weight and report how far the weighted score moves.
+///
+/// `results` holds one (mutation_type, passed) pair per mutation. The output is
+/// sorted from most to least sensitive weight. Fails when the config does
+/// not validate.
+pub fn sensitivity_analysis(
+    results: &[(String, bool)],
+    config: &SensitivityConfig,
+) -> Result<Vec<WeightSensitivity>, String> {
+    config.validate()?;
+    let baseline = weighted_type_score(results, &config.weights);
+
+    let mut types: Vec<&String> = results.iter().map(|(t, _)| t).collect();
+    types.sort();
+    types.dedup();
+
+    let mut report: Vec<WeightSensitivity> = types
+        .into_iter()
+        .map(|mutation_type| {
+            let weight = config.weights.get(mutation_type).copied().unwrap_or(1.0);
+            let score_with = |factor: f64| {
+                let mut weights = config.weights.clone();
+                weights.insert(mutation_type.clone(), weight * factor);
+                weighted_type_score(results, &weights)
+            };
+            let down = score_with(1.0 - config.perturbation);
+            let up = score_with(1.0 + config.perturbation);
+            let sensitivity = (down - baseline).abs().max((up - baseline).abs());
+
+            let classification = if sensitivity >= config.dominant_threshold {
+                "dominant"
+            } else if sensitivity < config.negligible_threshold {
+                "negligible"
+            } else {
+                "normal"
+            };
+
+            WeightSensitivity {
+                mutation_type: mutation_type.clone(),
+                weight,
+                baseline_score: baseline,
+                score_weight_down: down,
+                score_weight_up: up,
+                sensitivity,
+                classification: classification.to_string(),
+            }
+        })
+        .collect();
+
+    report.sort_by(|a, b| b.sensitivity.total_cmp(&a.sensitivity));
+    Ok(report)
+}
+
+/// Agreement between two runs' pass/fail outcomes (observed, kappa)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Reliability {
+    /// Weighted fraction of mutations with the same outcome in both runs
+    pub observed_agreement: f64,
+    /// Cohen's kappa: agreement corrected for chance, 1.0 = fully reproducible
+    pub kappa: f64,
+}
+
+/// Weighted Cohen's kappa over paired pass/fail outcomes.
+///
+/// Each pair counts with its weight, so disagreement on heavy mutations
+/// lowers reliability more. Fails when the runs and weights differ in
+/// length or a weight is negative or not finite.
+pub fn cohens_kappa(run_a: &[bool], run_b: &[bool], weights: &[f64]) -> Result<Reliability, String> {
+    if run_a.len() != run_b.len() || weights.len() != run_a.len() {
+        return Err(format!(
+            "got {} and {} outcomes with {} weights",
+            run_a.len(),
+            run_b.len(),
+            weights.len()
+        ));
+    }
+    if let Some(w) = weights.iter().find(|w| !(w.is_finite() && **w >= 0.0)) {
+        return Err(format!("weights must be finite and non-negative, got {}", w));
+    }
+    let n = run_a.len();
+    let mut total = 0.0;
+    let mut agree = 0.0;
+    let mut a_pass = 0.0;
+    let mut b_pass = 0.0;
+
+    for i in 0..n {
+        let w = weights[i];
+        total += w;
+        if run_a[i] == run_b[i] {
+            agree += w;
+        }
+        if run_a[i] {
+            a_pass += w;
+        }
+        if run_b[i] {
+            b_pass += w;
+        }
+    }
+
+    if total <= 0.0 {
+        return Ok(Reliability {
+            observed_agreement: 1.0,
+            kappa: 1.0,
+        });
+    }
+
+    let observed = agree / total;
+    let (pa, pb) = (a_pass / total, b_pass / total);
+    let expected = pa * pb + (1.0 - pa) * (1.0 - pb);
+    let kappa = if (1.0 - expected).abs() < f64::EPSILON {
+        // Both runs are constant; kappa is undefined, agreement decides
+        if observed >= 1.0 - f64::EPSILON { 1.0 } else { 0.0 }
+    } else {
+        (observed - expected) / (1.0 - expected)
+    };
+
+    Ok(Reliability {
+        observed_agreement: observed,
+        kappa,
+    })
+}
+
+/// Reliability of two runs over the same mutations, matched by position.
+///
+/// Weights are taken from `run_a`. Fails when the runs differ in length
+/// or in the mutation type at any position.
+pub fn run_reliability(run_a: &[MutationResult], run_b: &[MutationResult]) -> Result<Reliability, String> {
+    if let Some((i, (a, b))) = run_a
+        .iter()
+        .zip(run_b)
+        .enumerate()
+        .find(|(_, (a, b))| a.mutation_type != b.mutation_type)
+    {
+        return Err(format!(
+            "runs differ at position {}: '{}' vs '{}'",
+            i, a.mutation_type, b.mutation_type
+        ));
+    }
+    let a: Vec<bool> = run_a.iter().map(|r| r.passed).collect();
+    let b: Vec<bool> = run_b.iter().map(|r| r.passed).collect();
+    let weights: Vec<f64> = run_a.iter().map(|r| r.weight).collect();
+    cohens_kappa(&a, &b, &weights)
+}
+
+/// Tokens used by the text-overlap metrics: lowercased Unicode words.
+fn overlap_tokens(text: &str) -> Vec<String> {
+    Tokenizer::Word.tokenize(text, false)
+}
+
+fn ngram_counts(tokens: &[String], n: usize) -> HashMap<&[String], usize> {
+    let mut counts = HashMap::new();
+    if tokens.len() >= n {
+        for gram in tokens.windows(n) {
+            *counts.entry(gram).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+/// Clipped n-gram matches and lengths for one candidate, summable over a corpus
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BleuStats {
+    /// Clipped matching n-grams for n = 1..=max_n
+    pub matches: Vec<usize>,
+    /// Candidate n-grams for n = 1..=max_n
+    pub totals: Vec<usize>,
+    pub candidate_len: usize,
+    /// Length of the reference closest in length to the candidate
+    pub reference_len: usize,
+}
+
+impl BleuStats {
+    pub fn new(candidate: &str, references: &[String], max_n: usize) -> Self {
+        let cand = overlap_tokens(candidate);
+        let refs: Vec<Vec<String>> = references.iter().map(|r| overlap_tokens(r)).collect();
+
+        let mut stats = BleuStats {
+            matches: vec![0; max_n],
+            totals: vec![0; max_n],
+            candidate_len: cand.len(),
+            reference_len: refs
+                .iter()
+                .map(|r| r.len())
+                .min_by_key(|&len| (len.abs_diff(cand.len()), len))
+                .unwrap_or(0),
+        };
+
+        for n in 1..=max_n {
+            let cand_counts = ngram_counts(&cand, n);
+            let ref_counts: Vec<HashMap<&[String], usize>> =
+                refs.iter().map(|r| ngram_counts(r, n)).collect();
+            for (gram, count) in cand_counts {
+                let max_ref = ref_counts
+                    .iter()
+                    .map(|rc| rc.get(gram).copied().unwrap_or(0))
+                    .max()
+                    .unwrap_or(0);
+                stats.matches[n - 1] += count.min(max_ref);
+                stats.totals[n - 1] += count;
+            }
+        }
+        stats
+    }
+
+    fn add(mut self, other: &BleuStats) -> Self {
+        for i in 0..self.matches.len().min(other.matches.len()) {
+            self.matches[i] += other.matches[i];
+            self.totals[i] += other.totals[i];
+        }
+        self.candidate_len += other.candidate_len;
+        self.reference_len += other.reference_len;
+        self
+    }
+
+    /// BLEU score from accumulated statistics.
+    ///
+    /// With `smooth`, n-gram orders above 1 get add-one smoothing so short
+    /// outputs with no 4-gram overlap do not collapse to zero.
+    pub fn score(&self, smooth: bool) -> f64 {
+        if self.candidate_len == 0 || self.matches.is_empty() {
+            return 0.0;
+        }
+
+        let mut log_precision = 0.0;
+        for (n, (&m, &t)) in self.matches.iter().zip(&self.totals).enumerate() {
+            let (m, t) = if smooth && n > 0 {
+                (m as f64 + 1.0, t as f64 + 1.0)
+            } else {
+                (m as f64, t as f64)
+            };
+            if m == 0.0 || t == 0.0 {
+                return 0.0;
+            }
+            log_precision += (m / t).ln();
+        }
+        log_precision /= self.matches.len() as f64;
+
+        let brevity_penalty = if self.candidate_len >= self.reference_len {
+            1.0
+        } else {
+            (1.0 - self.reference_len as f64 / self.candidate_len as f64).exp()
+        };
+        brevity_penalty * log_precision.exp()
+    }
+}
+
+/// Sentence-level BLEU of `candidate` against one or more references.
+pub fn sentence_bleu(candidate: &str, references: &[String], max_n: usize, smooth: bool) -> Result<f64, String> {
+    check_max_n(max_n)?;
+    Ok(BleuStats::new(candidate, references, max_n).score(smooth))
+}
+
+/// Corpus-level BLEU: n-gram statistics are pooled across all pairs before scoring.
+///
+/// Fails when the candidate and reference lists differ in length.
+pub fn corpus_bleu_score(candidates: &[String], references: &[Vec<String>], max_n: usize) -> Result<f64, String> {
+    check_max_n(max_n)?;
+    check_paired(candidates.len(), references.len())?;
+    Ok(candidates
+        .par_iter()
+        .zip(references.par_iter())
+        .map(|(c, r)| BleuStats::new(c, r, max_n))
+        .reduce(
+            || BleuStats {
+                matches: vec![0; max_n],
+                totals: vec![0; max_n],
+                ..Default::default()
+            },
+            |acc, s| acc.add(&s),
+        )
+        .score(false))
+}
+
+fn check_max_n(max_n: usize) -> Result<(), String> {
+    if max_n == 0 {
+        return Err("max_n must be at least 1".to_string());
+    }
+    Ok(())
+}
+
+fn check_paired(candidates: usize, references: usize) -> Result<(), String> {
+    if candidates != references {
+        return Err(format!("got {} candidates and {} references", candidates, references));
+    }
+    Ok(())
+}
+
+/// ROUGE-L precision, recall and F1 from the token LCS
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RougeL {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+}
+
+/// ROUGE-L of `candidate` against `reference`.
+pub fn rouge_l_score(candidate: &str, reference: &str) -> RougeL {
+    let cand = overlap_tokens(candidate);
+    let reference = overlap_tokens(reference);
+    if cand.is_empty() || reference.is_empty() {
+        let same = cand.is_empty() && reference.is_empty();
+        let v = if same { 1.0 } else { 0.0 };
+        return RougeL {
+            precision: v,
+            recall: v,
+            f1: v,
+        };
+    }
+
+    let lcs = lcs_length(&cand, &reference) as f64;
+    let precision = lcs / cand.len() as f64;
+    let recall = lcs / reference.len() as f64;
+    let f1 = if lcs == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    };
+    RougeL {
+        precision,
+        recall,
+        f1,
+    }
+}
+
+/// ROUGE-L for each (candidate, reference) pair, computed in parallel.
+/// Fails when the candidate and reference lists differ in length.
+pub fn batch_rouge_l_scores(candidates: &[String], references: &[String]) -> Result<Vec<RougeL>, String> {
+    check_paired(candidates.len(), references.len())?;
+    Ok(candidates
+        .par_iter()
+        .zip(references.par_iter())
+        .map(|(c, r)| rouge_l_score(c, r))
+        .collect())
+}
+
+/// How a percentile between two samples is resolved, as in numpy
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PercentileMethod {
+    /// Closest sample, ties to the even index
+    Nearest,
+    Lower,
+    Higher,
+    /// Mean of the two neighbouring samples
+    Midpoint,
+    /// Linear interpolation between the neighbouring samples
+    #[default]
+    Linear,
+}
+
+impl PercentileMethod {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "nearest" => Ok(PercentileMethod::Nearest),
+            "lower" => Ok(PercentileMethod::Lower),
+            "higher" => Ok(PercentileMethod::Higher),
+            "midpoint" => Ok(PercentileMethod::Midpoint),
+            "linear" => Ok(PercentileMethod::Linear),
+            other => Err(format!(
+                "unknown percentile method '{}' (expected nearest, lower, higher, midpoint or linear)",
+                other
+            )),
+        }
     }
 }
 
-/// Calculate percentile from sorted values
-fn percentile(sorted_values: &[f64], p: usize) -> f64 {
+/// Percentile `q` (0..=100) of (value, weight) pairs sorted by value.
+///
+/// Weights are frequency weights rescaled to a mean of 1: sample i covers
+/// ranks C_(i-1) ..= C_i - 1 of the expanded series, where C_i is the
+/// cumulative weight, and ranks between two samples are resolved by
+/// `method`. Equal weights give the same result as `percentile`.
+/// Zero-weight samples are ignored; a non-finite `q` or a negative or
+/// non-finite weight is an error.
+pub fn weighted_percentile(sorted_pairs: &[(f64, f64)], q: f64, method: PercentileMethod) -> Result<f64, String> {
+    if !q.is_finite() {
+        return Err(format!("percentile must be finite, got {}", q));
+    }
+    if let Some(&(_, w)) = sorted_pairs.iter().find(|(_, w)| !(w.is_finite() && *w >= 0.0)) {
+        return Err(format!("weights must be finite and non-negative, got {}", w));
+    }
+    let pairs: Vec<(f64, f64)> = sorted_pairs.iter().copied().filter(|(_, w)| *w > 0.0).collect();
+    if pairs.is_empty() {
+        return Ok(0.0);
+    }
+    let mean = pairs.iter().map(|(_, w)| w).sum::<f64>() / pairs.len() as f64;
+    // (start, end) rank of each sample in the expanded series
+    let mut cumulative = 0.0;
+    let spans: Vec<(f64, f64)> = pairs
+        .iter()
+        .map(|(_, w)| {
+            let start = cumulative;
+            cumulative += w / mean;
+            (start, (cumulative - 1.0).max(start))
+        })
+        .collect();
+    let last_start = spans[spans.len() - 1].0;
+    let rank = q.clamp(0.0, 100.0) / 100.0 * (cumulative - 1.0).max(last_start);
+
+    let i = spans.partition_point(|&(start, _)| start <= rank) - 1;
+    if rank <= spans[i].1 || i + 1 == pairs.len() {
+        return Ok(pairs[i].0);
+    }
+    let (a, b) = (pairs[i].0, pairs[i + 1].0);
+    let fraction = (rank - spans[i].1) / (spans[i + 1].0 - spans[i].1);
+    Ok(match method {
+        PercentileMethod::Nearest => {
+            if fraction > 0.5 || (fraction == 0.5 && (i + 1) % 2 == 0) {
+                b
+            } else {
+                a
+            }
+        }
+        PercentileMethod::Lower => a,
+        PercentileMethod::Higher => b,
+        PercentileMethod::Midpoint => (a + b) / 2.0,
+        PercentileMethod::Linear => a + (b - a) * fraction,
+    })
+}
+
+/// p50, p95 and p99 latency of `results`, weighted when `config` says so.
+fn latency_percentiles<'a>(
+    results: impl Iterator<Item = &'a MutationResult>,
+    config: &StatisticsConfig,
+) -> Result<[f64; 3], String> {
+    let mut pairs: Vec<(f64, f64)> = results
+        .map(|r| (r.latency_ms, if config.weighted_latency { r.weight } else { 1.0 }))
+        .collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Ok([
+        weighted_percentile(&pairs, 50.0, config.percentile_method)?,
+        weighted_percentile(&pairs, 95.0, config.percentile_method)?,
+        weighted_percentile(&pairs, 99.0, config.percentile_method)?,
+    ])
+}
+
+/// Percentile `q` (0..=100) of ascending `sorted_values`; 0.0 when empty.
+pub fn percentile(sorted_values: &[f64], q: f64, method: PercentileMethod) -> f64 {
     if sorted_values.is_empty() {
         return 0.0;
     }
 
-    let index = (p as f64 / 100.0 * (sorted_values.len() - 1) as f64).round() as usize;
-    sorted_values[index.min(sorted_values.len() - 1)]
+    let rank = q.clamp(0.0, 100.0) / 100.0 * (sorted_values.len() - 1) as f64;
+    let (lo, hi) = (rank.floor() as usize, rank.ceil() as usize);
+    let (a, b) = (sorted_values[lo], sorted_values[hi]);
+    match method {
+        PercentileMethod::Nearest => sorted_values[rank.round_ties_even() as usize],
+        PercentileMethod::Lower => a,
+        PercentileMethod::Higher => b,
+        PercentileMethod::Midpoint => (a + b) / 2.0,
+        PercentileMethod::Linear => a + (b - a) * (rank - lo as f64),
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::severity::ScoringMode;
+    use crate::strategy::ScoringStrategy;
 
     #[test]
     fn test_percentile() {
         let values = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
-        assert!((percentile(&values, 50) - 5.5).abs() < 1.0);
-        assert!((percentile(&values, 95) - 9.5).abs() < 1.0);
+        assert!((percentile(&values, 50.0, PercentileMethod::Linear) - 5.5).abs() < 1e-12);
+        assert!((percentile(&values, 95.0, PercentileMethod::Linear) - 9.55).abs() < 1e-12);
+
+        // numpy.percentile([1..10], 25, method=...) → rank 2.25
+        let at_25 = |method| percentile(&values, 25.0, method);
+        assert_eq!(at_25(PercentileMethod::Lower), 3.0);
+        assert_eq!(at_25(PercentileMethod::Higher), 4.0);
+        assert_eq!(at_25(PercentileMethod::Nearest), 3.0);
+        assert_eq!(at_25(PercentileMethod::Midpoint), 3.5);
+        assert_eq!(at_25(PercentileMethod::Linear), 3.25);
+        // Rank 4.5 ties to the even index
+        assert_eq!(percentile(&values, 50.0, PercentileMethod::Nearest), 5.0);
+        assert_eq!(percentile(&[], 50.0, PercentileMethod::Linear), 0.0);
+        assert!(PercentileMethod::parse("median").is_err());
+    }
+
+    #[test]
+    fn test_weighted_percentile() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0, 10.0];
+        let equal: Vec<(f64, f64)> = values.iter().map(|&v| (v, 2.0)).collect();
+        for method in [
+            PercentileMethod::Nearest,
+            PercentileMethod::Lower,
+            PercentileMethod::Higher,
+            PercentileMethod::Midpoint,
+            PercentileMethod::Linear,
+        ] {
+            for q in [0.0, 25.0, 50.0, 95.0, 100.0] {
+                assert_eq!(
+                    weighted_percentile(&equal, q, method).unwrap(),
+                    percentile(&values, q, method),
+                    "{:?} at {}",
+                    method,
+                    q
+                );
+            }
+        }
+
+        // A heavy slow sample pulls the median up
+        let skewed = [(10.0, 1.0), (20.0, 1.0), (500.0, 6.0)];
+        assert!(weighted_percentile(&skewed, 50.0, PercentileMethod::Linear).unwrap() > 20.0);
+        assert_eq!(
+            weighted_percentile(&[(3.0, 0.0), (7.0, 1.0)], 10.0, PercentileMethod::Linear),
+            Ok(7.0)
+        );
+
+        let linear = |pairs: &[(f64, f64)], q| weighted_percentile(pairs, q, PercentileMethod::Linear);
+        assert!(linear(&skewed, f64::NAN).is_err());
+        assert!(linear(&skewed, f64::INFINITY).is_err());
+        assert!(linear(&[(3.0, -1.0), (7.0, 2.0)], 50.0).is_err());
+        assert!(linear(&[(3.0, f64::NAN)], 50.0).is_err());
+    }
+
+    #[test]
+    fn test_per_type_latency() {
+        let result = |mutation_type: &str, latency_ms: f64, weight: f64| MutationResult {
+            mutation_type: mutation_type.to_string(),
+            passed: true,
+            weight,
+            latency_ms,
+            ..Default::default()
+        };
+        let results = vec![
+            result("noise", 100.0, 1.0),
+            result("noise", 120.0, 1.0),
+            result("prompt_injection", 900.0, 1.0),
+            result("prompt_injection", 3000.0, 3.0),
+        ];
+        let stats = calculate_statistics(&results).unwrap();
+        let injection = stats.by_type.iter().find(|t| t.mutation_type == "prompt_injection").unwrap();
+        assert!((injection.p50_latency_ms - 1950.0).abs() < 1e-9);
+        let noise = stats.by_type.iter().find(|t| t.mutation_type == "noise").unwrap();
+        assert!((noise.p95_latency_ms - 119.0).abs() < 1e-9);
+
+        let weighted = StatisticsConfig {
+            weighted_latency: true,
+            ..Default::default()
+        };
+        let stats = calculate_statistics_with(&results, &weighted).unwrap();
+        let injection = stats.by_type.iter().find(|t| t.mutation_type == "prompt_injection").unwrap();
+        assert_eq!(injection.p50_latency_ms, 3000.0);
+    }
+
+    #[test]
+    fn test_tag_statistics() {
+        let result = |model: &str, suite: Option<&str>, passed: bool, weight: f64| MutationResult {
+            mutation_type: "noise".to_string(),
+            passed,
+            weight,
+            latency_ms: 100.0,
+            tags: std::iter::once(("model", model))
+                .chain(suite.map(|s| ("suite", s)))
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        let results = vec![
+            result("gpt-4o", Some("checkout-flow"), true, 1.0),
+            result("gpt-4o", Some("checkout-flow"), false, 3.0),
+            result("gpt-4o-mini", Some("search"), true, 1.0),
+            result("gpt-4o-mini", None, true, 1.0),
+        ];
+        let stats = calculate_statistics(&results).unwrap();
+        let tags: Vec<(&str, &str, usize)> = stats
+            .by_tag
+            .iter()
+            .map(|t| (t.key.as_str(), t.value.as_str(), t.total))
+            .collect();
+        assert_eq!(
+            tags,
+            vec![
+                ("model", "gpt-4o", 2),
+                ("model", "gpt-4o-mini", 2),
+                ("suite", "checkout-flow", 2),
+                ("suite", "search", 1),
+            ]
+        );
+        let checkout = &stats.by_tag[2];
+        assert_eq!((checkout.pass_rate, checkout.robustness_score), (0.5, 0.25));
+        // Tag scores follow the run's strategy
+        let config = StatisticsConfig {
+            scoring: ScoringConfig {
+                strategy: ScoringStrategy::TopKFailures(1),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let by_tag = calculate_statistics_with(&results, &config).unwrap().by_tag;
+        assert_eq!((by_tag[2].robustness_score, by_tag[3].robustness_score), (0.0, 1.0));
+
+        let untagged: Vec<MutationResult> = results
+            .into_iter()
+            .map(|r| MutationResult {
+                tags: BTreeMap::new(),
+                ..r
+            })
+            .collect();
+        assert!(calculate_statistics(&untagged).unwrap().by_tag.is_empty());
+        assert_eq!(calculate_statistics(&untagged).unwrap().throughput, None);
+
+        let timestamped: Vec<MutationResult> = untagged
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| MutationResult {
+                timestamp_ms: Some(1_700_000_000_000.0 + i as f64 * 45_000.0),
+                ..r
+            })
+            .collect();
+        let throughput = calculate_statistics(&timestamped).unwrap().throughput.unwrap();
+        let completed: Vec<usize> = throughput.buckets.iter().map(|b| b.completed).collect();
+        assert_eq!(completed, vec![2, 1, 1]);
+    }
+
+    #[test]
+    fn test_cost_statistics() {
+        let result = |t: &str, passed: bool, tokens: Option<(usize, usize)>, cost: Option<f64>| MutationResult {
+            mutation_type: t.to_string(),
+            passed,
+            latency_ms: 100.0,
+            prompt_tokens: tokens.map(|t| t.0),
+            completion_tokens: tokens.map(|t| t.1),
+            cost,
+            ..Default::default()
+        };
+        let results = vec![
+            result("noise", true, Some((100, 50)), Some(0.02)),
+            result("noise", false, Some((120, 80)), Some(0.04)),
+            result("paraphrase", false, Some((90, 10)), None),
+            result("paraphrase", true, None, None),
+        ];
+        let stats = calculate_statistics(&results).unwrap();
+        let cost = stats.cost.unwrap();
+        assert_eq!(
+            (cost.measured, cost.prompt_tokens, cost.completion_tokens),
+            (3, 310, 140)
+        );
+        assert!((cost.total_cost - 0.06).abs() < 1e-12);
+        assert!((cost.avg_cost - 0.03).abs() < 1e-12);
+        // The tokens-only failure has no cost and does not dilute the rate
+        assert!((cost.cost_per_failure.unwrap() - 0.06).abs() < 1e-12);
+
+        let paraphrase = stats.by_type.iter().find(|t| t.mutation_type == "paraphrase").unwrap();
+        let paraphrase_cost = paraphrase.cost.as_ref().unwrap();
+        assert_eq!((paraphrase_cost.measured, paraphrase_cost.total_cost), (1, 0.0));
+        assert_eq!(paraphrase_cost.cost_per_failure, None);
+        assert_eq!(cost_statistics(&results[3..]), Ok(None));
+        assert_eq!(cost_statistics(&results[..1]).unwrap().unwrap().cost_per_failure, None);
+        let negative = result("noise", true, None, Some(-0.01));
+        assert!(cost_statistics(&[negative]).is_err());
+        assert!(calculate_statistics(&[result("noise", true, None, Some(f64::NAN))]).is_err());
+    }
+
+    #[test]
+    fn test_severity_scoring_mode() {
+        let check = |passed: bool, severity: Severity| CheckResult {
+            check_type: "tone".to_string(),
+            passed,
+            details: String::new(),
+            severity,
+        };
+        let result = |checks: Vec<CheckResult>| MutationResult {
+            mutation_type: "noise".to_string(),
+            passed: checks.iter().all(|c| c.passed),
+            latency_ms: 100.0,
+            checks,
+            ..Default::default()
+        };
+        let results = vec![
+            result(vec![check(false, Severity::Warn), check(true, Severity::Error)]),
+            result(vec![check(false, Severity::Critical)]),
+            result(vec![check(true, Severity::Error)]),
+            result(vec![]),
+        ];
+        let flat = calculate_statistics(&results).unwrap();
+        assert_eq!(flat.robustness_score, 0.5);
+        // Critical failures are counted whatever the scoring mode
+        assert_eq!(flat.severity.as_ref().map(|s| s.critical_mutations), Some(1));
+        assert!(flat.failure_messages.is_none());
+        let grouped = calculate_statistics(&results).unwrap()
+            .with_failure_messages(&results, &FailureMessageConfig::default())
+            .unwrap()
+            .failure_messages
+            .unwrap();
+        assert_eq!((grouped.failed_checks, grouped.messages[0].count), (2, 2));
+
+        let config = StatisticsConfig {
+            scoring: ScoringConfig {
+                mode: ScoringMode::Severity(Default::default()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let stats = calculate_statistics_with(&results, &config).unwrap();
+        assert!((stats.robustness_score - 2.75 / 4.0).abs() < 1e-12);
+        // Counts, rates and the interval use the same credits as the score
+        assert_eq!((stats.passed_mutations, stats.failed_mutations), (2, 2));
+        assert_eq!(stats.by_type[0].pass_rate, stats.robustness_score);
+        assert_eq!(stats.confidence_interval.as_ref().unwrap().score, stats.robustness_score);
+        let breakdown = stats.severity.unwrap();
+        assert_eq!((breakdown.warn, breakdown.critical_mutations), (1, 1));
+
+        let mut strict = config.clone();
+        strict.scoring.strategy = ScoringStrategy::Strict;
+        let stats = calculate_statistics_with(&results, &strict).unwrap();
+        // The strategy scores the run, its types and its interval alike
+        assert_eq!((stats.robustness_score, stats.by_type[0].robustness_score), (0.0, 0.0));
+        assert_eq!(stats.confidence_interval.unwrap().score, 0.0);
+        let mut negative = results.clone();
+        negative[0].weight = -1.0;
+        assert!(calculate_statistics_with(&negative, &strict).is_err());
+    }
+
+    #[test]
+    fn test_latency_outlier_indices() {
+        let results: Vec<MutationResult> = [100.0, 120.0, 900.0, 3000.0]
+            .iter()
+            .map(|&latency_ms| MutationResult {
+                mutation_type: "noise".to_string(),
+                passed: true,
+                latency_ms,
+                ..Default::default()
+            })
+            .collect();
+        let outliers = calculate_statistics(&results).unwrap().latency_outliers.unwrap();
+        assert_eq!(outliers.count, 1);
+        assert_eq!(outliers.indices(), vec![3]);
     }
 
     #[test]
@@ -142,30 +1684,273 @@ mod tests {
             MutationResult {
                 mutation_type: "paraphrase".to_string(),
                 passed: true,
-                weight: 1.0,
                 latency_ms: 100.0,
-                checks: vec![],
+                ..Default::default()
             },
             MutationResult {
                 mutation_type: "noise".to_string(),
                 passed: true,
                 weight: 0.8,
                 latency_ms: 150.0,
-                checks: vec![],
+                ..Default::default()
             },
             MutationResult {
                 mutation_type: "prompt_injection".to_string(),
                 passed: false,
                 weight: 1.5,
                 latency_ms: 200.0,
-                checks: vec![],
+                ..Default::default()
             },
         ];
 
-        let stats = calculate_statistics(&results);
+        let stats = calculate_statistics(&results).unwrap();
         assert_eq!(stats.total_mutations, 3);
         assert_eq!(stats.passed_mutations, 2);
         assert_eq!(stats.failed_mutations, 1);
         assert!(stats.robustness_score > 0.5);
+        assert!(stats.resources.is_none());
+        assert!(stats.length_drift.is_none());
+        assert!(stats.determinism.is_none());
+        let interval = stats.confidence_interval.clone().unwrap();
+        assert!((interval.score - stats.robustness_score).abs() < 1e-12);
+        assert!(interval.lower < interval.score && interval.score < interval.upper);
+        assert!(calculate_statistics(&[]).unwrap().confidence_interval.is_none());
+        let mut negative = results.clone();
+        negative[0].weight = -1.0;
+        assert!(calculate_statistics(&negative).is_err());
+        assert_eq!(stats.latency_histogram.as_ref().map(|h| h.count), Some(3));
+        let coarse = StatisticsConfig {
+            latency_buckets: vec![120.0],
+            ..Default::default()
+        };
+        let histogram = calculate_statistics_with(&results, &coarse).unwrap().latency_histogram.unwrap();
+        assert_eq!(histogram.counts, vec![1, 2]);
+        let mut measured = results.clone();
+        measured[0].output_length = Some(OutputLength {
+            output_chars: 300,
+            baseline_chars: 100,
+        });
+        assert_eq!(calculate_statistics(&measured).unwrap().length_drift.unwrap().too_long, 0);
+        let strict = StatisticsConfig {
+            length: LengthThresholds {
+                long_ratio: 2.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(calculate_statistics_with(&measured, &strict).unwrap().length_drift.unwrap().too_long, 1);
+        let invalid = StatisticsConfig {
+            length: LengthThresholds {
+                long_ratio: 0.5,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(calculate_statistics_with(&measured, &invalid).is_err());
+        let scored = stats.with_prior(&BetaPrior::default(), 0.95).unwrap();
+        let bayesian = scored.bayesian.clone().unwrap();
+        assert!((bayesian.observed_score - scored.robustness_score).abs() < 1e-12);
+        let composite = scored.clone().with_composite(&CompositeConfig::default(), None).unwrap().composite.unwrap();
+        assert_eq!(composite.latency_score, 1.0);
+        let report = DeterminismReport {
+            summary: DeterminismSummary {
+                cases: 2,
+                stable: 1,
+                divergent: 1,
+                determinism_score: 0.6,
+                ..Default::default()
+            },
+            cases: Vec::new(),
+        };
+        assert_eq!(scored.with_determinism(&report).determinism, Some(report.summary));
+    }
+
+    #[test]
+    fn test_check_statistics() {
+        let check = |check_type: &str, passed: bool, details: &str| CheckResult {
+            check_type: check_type.to_string(),
+            passed,
+            details: details.to_string(),
+            severity: Severity::Error,
+        };
+        let results: Vec<MutationResult> = [
+            vec![check("canary_leak", false, "leaked CANARY-1"), check("no_pii", true, "")],
+            vec![check("canary_leak", false, "leaked CANARY-2")],
+            vec![check("canary_leak", false, "leaked CANARY-1"), check("no_pii", false, "email")],
+            vec![check("canary_leak", true, "")],
+        ]
+        .into_iter()
+        .map(|checks| MutationResult {
+            mutation_type: "prompt_injection".to_string(),
+            passed: checks.iter().all(|c| c.passed),
+            latency_ms: 10.0,
+            checks,
+            ..Default::default()
+        })
+        .collect();
+
+        let stats = calculate_statistics(&results).unwrap();
+        assert_eq!(stats.by_check.len(), 2);
+        let canary = &stats.by_check[0];
+        assert_eq!((canary.check_type.as_str(), canary.total, canary.failed), ("canary_leak", 4, 3));
+        assert_eq!(
+            canary.top_failures,
+            vec![("leaked CANARY-1".to_string(), 2), ("leaked CANARY-2".to_string(), 1)]
+        );
+        assert_eq!(stats.by_check[1].pass_rate, 0.5);
+    }
+
+    #[test]
+    fn test_resource_statistics() {
+        let usage = |bytes: usize, status: u16, headers: &[(&str, &str)]| ResourceUsage {
+            response_bytes: bytes,
+            tokens: Some(bytes / 4),
+            tokens_per_sec: Some(bytes as f64 / 10.0),
+            http_status: Some(status),
+            headers: headers.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        };
+        let mut results: Vec<MutationResult> = [
+            usage(400, 200, &[("content-length", "400")]),
+            usage(120, 200, &[("content-length", "900")]),
+            usage(0, 429, &[("retry-after", "2")]),
+        ]
+        .into_iter()
+        .map(|u| MutationResult {
+            mutation_type: "noise".to_string(),
+            passed: true,
+            latency_ms: 10.0,
+            resources: Some(u),
+            ..Default::default()
+        })
+        .collect();
+        results.push(MutationResult {
+            resources: None,
+            output_length: None,
+            ..results[0].clone()
+        });
+
+        let stats = calculate_statistics(&results).unwrap().resources.unwrap();
+        assert_eq!(stats.measured, 3);
+        assert_eq!(stats.max_response_bytes, 400);
+        assert!((stats.avg_response_bytes - 520.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.status_counts[&200], 2);
+        assert_eq!((stats.throttled, stats.truncated), (1, 1));
+        assert!((stats.avg_tokens_per_sec.unwrap() - 52.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sensitivity_analysis() {
+        let mut results: Vec<(String, bool)> = (0..10)
+            .map(|i| ("paraphrase".to_string(), i < 9))
+            .collect();
+        results.push(("prompt_injection".to_string(), false));
+        results.push(("noise".to_string(), true));
+
+        let mut config = SensitivityConfig::default();
+        config.weights.insert("prompt_injection".to_string(), 5.0);
+        config.weights.insert("noise".to_string(), 0.01);
+        config.dominant_threshold = 0.015;
+
+        let report = sensitivity_analysis(&results, &config).unwrap();
+        assert_eq!(report.len(), 3);
+        assert!(report.windows(2).all(|w| w[0].sensitivity >= w[1].sensitivity));
+
+        let injection = report
+            .iter()
+            .find(|r| r.mutation_type == "prompt_injection")
+            .unwrap();
+        assert_eq!(injection.classification, "dominant");
+        assert!(injection.score_weight_up < injection.baseline_score);
+
+        let noise = report.iter().find(|r| r.mutation_type == "noise").unwrap();
+        assert_eq!(noise.classification, "negligible");
+
+        config.weights.insert("noise".to_string(), f64::NAN);
+        assert!(sensitivity_analysis(&results, &config).is_err());
+        config.weights.insert("noise".to_string(), 0.01);
+        for perturbation in [f64::NAN, 0.0, -0.1, 1.5] {
+            config.perturbation = perturbation;
+            assert!(sensitivity_analysis(&results, &config).unwrap_err().contains("perturbation"));
+        }
+    }
+
+    #[test]
+    fn test_cohens_kappa() {
+        let a = [true, true, false, false, true, false];
+        let ones = [1.0; 6];
+        assert_eq!(cohens_kappa(&a, &a, &ones).unwrap().kappa, 1.0);
+
+        let b = [true, false, false, true, true, false];
+        let r = cohens_kappa(&a, &b, &ones).unwrap();
+        // observed 4/6, expected 0.5 -> kappa 1/3
+        assert!((r.observed_agreement - 4.0 / 6.0).abs() < 1e-9);
+        assert!((r.kappa - 1.0 / 3.0).abs() < 1e-9);
+
+        let heavy_disagreement = [1.0, 5.0, 1.0, 5.0, 1.0, 1.0];
+        assert!(cohens_kappa(&a, &b, &heavy_disagreement).unwrap().kappa < r.kappa);
+
+        let all_pass = [true; 3];
+        assert_eq!(cohens_kappa(&all_pass, &all_pass, &[1.0; 3]).unwrap().kappa, 1.0);
+        // Mismatched lengths are an error, not a shorter comparison
+        assert!(cohens_kappa(&a, &b[..5], &ones).is_err());
+        assert!(cohens_kappa(&a, &b, &ones[..5]).is_err());
+        assert!(cohens_kappa(&a, &b, &[1.0, -1.0, 1.0, 1.0, 1.0, 1.0]).is_err());
+
+        let run = |outcomes: &[bool]| -> Vec<MutationResult> {
+            outcomes
+                .iter()
+                .map(|&passed| MutationResult {
+                    mutation_type: "noise".to_string(),
+                    passed,
+                    ..Default::default()
+                })
+                .collect()
+        };
+        assert_eq!(run_reliability(&run(&a), &run(&b)).unwrap(), r);
+        assert!(run_reliability(&run(&a), &run(&b[..5])).is_err());
+        let mut renamed = run(&b);
+        renamed[2].mutation_type = "paraphrase".to_string();
+        assert!(run_reliability(&run(&a), &renamed).unwrap_err().contains("position 2"));
+    }
+
+    #[test]
+    fn test_sentence_bleu() {
+        let refs = vec!["the cat is on the mat".to_string()];
+        assert!((sentence_bleu("the cat is on the mat", &refs, 4, false).unwrap() - 1.0).abs() < 1e-9);
+        assert_eq!(sentence_bleu("dog", &refs, 4, false), Ok(0.0));
+        assert!(sentence_bleu("dog", &refs, 0, false).unwrap_err().contains("max_n"));
+
+        let partial = sentence_bleu("the cat sat on the mat", &refs, 4, true).unwrap();
+        assert!(partial > 0.2 && partial < 1.0);
+
+        // Brevity penalty applies to short candidates
+        let stats = BleuStats::new("the cat", &refs, 1);
+        assert_eq!(stats.matches, vec![2]);
+        assert!(stats.score(false) < 0.5);
+    }
+
+    #[test]
+    fn test_corpus_bleu_pools_statistics() {
+        let candidates = vec!["the cat is on the mat".to_string(), "hello there".to_string()];
+        let references = vec![
+            vec!["the cat is on the mat".to_string()],
+            vec!["hello there".to_string(), "hi".to_string()],
+        ];
+        assert!((corpus_bleu_score(&candidates, &references, 2).unwrap() - 1.0).abs() < 1e-9);
+        assert!(corpus_bleu_score(&candidates, &references[..1], 2).unwrap_err().contains("1 references"));
+        assert!(corpus_bleu_score(&candidates, &references, 0).is_err());
+    }
+
+    #[test]
+    fn test_rouge_l() {
+        let r = rouge_l_score("police killed the gunman", "police kill the gunman");
+        assert!((r.precision - 0.75).abs() < 1e-9);
+        assert!((r.recall - 0.75).abs() < 1e-9);
+        assert!((r.f1 - 0.75).abs() < 1e-9);
+        assert_eq!(rouge_l_score("", "").f1, 1.0);
+
+        let batch = batch_rouge_l_scores(&["a b".to_string()], &["a b".to_string()]).unwrap();
+        assert_eq!(batch[0].f1, 1.0);
+        assert!(batch_rouge_l_scores(&["a b".to_string()], &[]).is_err());
     }
 }
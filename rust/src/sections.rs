@@ -0,0 +1,219 @@
+//! Section-targeted mutation of structured prompts
+//!
+//! Many prompts are assembled from named sections (system, instructions,
+//! retrieved documents, question). A target expression picks which sections
+//! a mutation may touch, e.g. `"context"`, `"documents.*"` or `"*,!system"`,
+//! and the assembled result records where every section starts and ends so
+//! provenance survives the mutation.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// One selector in a target expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Selector {
+    All,
+    Exact(String),
+    Prefix(String),
+}
+
+impl Selector {
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            Selector::All => true,
+            Selector::Exact(n) => n == name,
+            Selector::Prefix(p) => name.starts_with(p.as_str()),
+        }
+    }
+}
+
+/// Parsed section target expression.
+///
+/// Comma-separated selectors: a section name, `*` for every section, a
+/// trailing `*` for a name prefix (`documents.*`), and a leading `!` to
+/// exclude. A section is targeted if it matches an include and no exclude;
+/// with only excludes, every other section is included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SectionTarget {
+    include: Vec<Selector>,
+    exclude: Vec<Selector>,
+}
+
+impl SectionTarget {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+
+        for raw in expr.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (negated, body) = match raw.strip_prefix('!') {
+                Some(rest) => (true, rest.trim()),
+                None => (false, raw),
+            };
+            let selector = if body == "*" {
+                Selector::All
+            } else if let Some(prefix) = body.strip_suffix('*') {
+                Selector::Prefix(prefix.to_string())
+            } else if body.is_empty() || body.contains('*') {
+                return Err(format!("invalid section selector '{}'", raw));
+            } else {
+                Selector::Exact(body.to_string())
+            };
+            if negated {
+                exclude.push(selector);
+            } else {
+                include.push(selector);
+            }
+        }
+
+        if include.is_empty() && exclude.is_empty() {
+            return Err("empty section target".to_string());
+        }
+        if include.is_empty() {
+            include.push(Selector::All);
+        }
+        Ok(Self { include, exclude })
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.include.iter().any(|s| s.matches(name)) && !self.exclude.iter().any(|s| s.matches(name))
+    }
+}
+
+/// Where a section sits in the assembled prompt (character offsets)
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionSpan {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub mutated: bool,
+}
+
+/// A prompt reassembled from its sections, with section boundaries
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SectionedPrompt {
+    pub text: String,
+    pub spans: Vec<SectionSpan>,
+}
+
+/// Mutate the targeted sections and reassemble the prompt.
+///
+/// Untargeted sections are copied verbatim. Sections are joined with
+/// `separator`, which never belongs to any span.
+pub fn mutate_targeted_sections<E>(
+    sections: &[(String, String)],
+    target: &SectionTarget,
+    separator: &str,
+    mut mutate: impl FnMut(&str, &str) -> Result<String, E>,
+) -> Result<SectionedPrompt, E> {
+    let mut text = String::new();
+    let mut spans = Vec::with_capacity(sections.len());
+    let mut offset = 0;
+    let separator_len = separator.chars().count();
+
+    for (i, (name, body)) in sections.iter().enumerate() {
+        if i > 0 {
+            text.push_str(separator);
+            offset += separator_len;
+        }
+        let mutated = target.matches(name);
+        let out = if mutated {
+            mutate(name, body)?
+        } else {
+            body.clone()
+        };
+        let len = out.chars().count();
+        text.push_str(&out);
+        spans.push(SectionSpan {
+            name: name.clone(),
+            start: offset,
+            end: offset + len,
+            mutated,
+        });
+        offset += len;
+    }
+
+    Ok(SectionedPrompt { text, spans })
+}
+
+/// Names of the sections a target expression selects, in prompt order.
+#[pyfunction]
+pub fn select_sections(section_names: Vec<String>, target: &str) -> PyResult<Vec<String>> {
+    let target = SectionTarget::parse(target).map_err(PyValueError::new_err)?;
+    Ok(section_names.into_iter().filter(|n| target.matches(n)).collect())
+}
+
+/// Apply `mutate(text) -> str` to the sections selected by `target` and
+/// reassemble the prompt, keeping each section's boundaries.
+///
+/// `sections` is an ordered list of (name, text) pairs.
+#[pyfunction]
+#[pyo3(signature = (sections, target, mutate, separator = "\n\n"))]
+pub fn mutate_sections(
+    py: Python<'_>,
+    sections: Vec<(String, String)>,
+    target: &str,
+    mutate: PyObject,
+    separator: &str,
+) -> PyResult<SectionedPrompt> {
+    let target = SectionTarget::parse(target).map_err(PyValueError::new_err)?;
+    mutate_targeted_sections(&sections, &target, separator, |_, text| {
+        mutate.call1(py, (text,))?.extract::<String>(py)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sections() -> Vec<(String, String)> {
+        vec![
+            ("system".into(), "You are a travel agent.".into()),
+            ("documents.0".into(), "Flights to Paris leave at 9.".into()),
+            ("documents.1".into(), "Hotels are cheap.".into()),
+            ("question".into(), "When is the flight?".into()),
+        ]
+    }
+
+    #[test]
+    fn test_target_parsing() {
+        let t = SectionTarget::parse("documents.*").unwrap();
+        assert!(t.matches("documents.0") && !t.matches("question"));
+
+        let t = SectionTarget::parse("!system").unwrap();
+        assert!(t.matches("question") && !t.matches("system"));
+
+        let t = SectionTarget::parse("*, !documents.1").unwrap();
+        assert!(t.matches("documents.0") && !t.matches("documents.1"));
+
+        assert!(SectionTarget::parse("").is_err());
+        assert!(SectionTarget::parse("do*cs").is_err());
+    }
+
+    #[test]
+    fn test_mutate_targeted_sections_preserves_boundaries() {
+        let target = SectionTarget::parse("documents.*").unwrap();
+        let result = mutate_targeted_sections::<()>(&sections(), &target, "\n", |_, t| {
+            Ok(t.to_uppercase())
+        })
+        .unwrap();
+
+        assert!(result.text.starts_with("You are a travel agent.\nFLIGHTS TO PARIS"));
+        assert!(result.text.ends_with("When is the flight?"));
+        let mutated: Vec<&str> = result
+            .spans
+            .iter()
+            .filter(|s| s.mutated)
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(mutated, vec!["documents.0", "documents.1"]);
+
+        let chars: Vec<char> = result.text.chars().collect();
+        for (span, (_, original)) in result.spans.iter().zip(sections()) {
+            let slice: String = chars[span.start..span.end].iter().collect();
+            assert_eq!(slice.to_lowercase(), original.to_lowercase());
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! JSON serialization of results and statistics
+//!
+//! Results and statistics already derive serde; this exposes it so the
+//! Python layer can hand whole objects to and from JSON instead of
+//! rebuilding dicts field by field. Output is compact by default and
+//! pretty-printed on request; a run's results are dumped as one JSON
+//! array, the format `load_results` reads. JSON has no NaN or infinity,
+//! so results with a non-finite number are refused rather than written
+//! as null, and files are replaced atomically. Loaded results become
+//! `TestStatistics` through `calculate_statistics`, the same as a live
+//! run's.
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::scoring::MutationResult;
+
+/// `value` as compact or pretty-printed JSON.
+pub fn to_json<T: Serialize>(value: &T, pretty: bool) -> String {
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    };
+    // Every serialized type has string map keys and no custom serializers
+    json.expect("value serializes to JSON")
+}
+
+/// Parse `json`; the error names the offending line and column.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    serde_json::from_str(json).map_err(|e| e.to_string())
+}
+
+/// Write `path` through `write`, into a temporary file in the same
+/// directory that is renamed over `path` once complete, so readers never
+/// see a half-written file and a failed write leaves the old one intact.
+pub fn write_atomically(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> std::io::Result<()>,
+) -> std::io::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(format!(".{}.tmp", std::process::id()));
+    let temp = path.with_file_name(temp_name);
+    let written = File::create(&temp).and_then(|file| {
+        let mut out = BufWriter::new(file);
+        write(&mut out)?;
+        out.flush()?;
+        out.get_ref().sync_all()
+    });
+    match written.and_then(|_| std::fs::rename(&temp, path)) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            let _ = std::fs::remove_file(&temp);
+            Err(e)
+        }
+    }
+}
+
+/// Write `results` to `path` as one JSON array, atomically. Fails with
+/// `InvalidInput` on a result that does not validate, since a NaN would
+/// be written as null and not read back.
+pub fn write_results(path: &Path, results: &[MutationResult], pretty: bool) -> std::io::Result<()> {
+    for r in results {
+        r.validate()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+    }
+    write_atomically(path, |out| {
+        if pretty {
+            serde_json::to_writer_pretty(&mut *out, results)
+        } else {
+            serde_json::to_writer(&mut *out, results)
+        }
+        .map_err(std::io::Error::other)?;
+        writeln!(out)
+    })
+}
+
+/// Read results written by `write_results`.
+pub fn read_results(path: &Path) -> std::io::Result<Vec<MutationResult>> {
+    let reader = BufReader::new(File::open(path)?);
+    let invalid = |e| std::io::Error::new(std::io::ErrorKind::InvalidData, e);
+    let results: Vec<MutationResult> = serde_json::from_reader(reader).map_err(|e| invalid(e.to_string()))?;
+    for r in &results {
+        r.validate().map_err(invalid)?;
+    }
+    Ok(results)
+}
+
+/// Write a run's mutation results to `path` as a JSON array.
+#[pyfunction]
+#[pyo3(signature = (results, path, pretty = false))]
+pub fn dump_results(py: Python<'_>, results: Vec<MutationResult>, path: &str, pretty: bool) -> PyResult<()> {
+    py.allow_threads(|| write_results(Path::new(path), &results, pretty))
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::InvalidInput => PyValueError::new_err(format!("{}: {}", path, e)),
+            _ => PyIOError::new_err(format!("{}: {}", path, e)),
+        })
+}
+
+/// Read mutation results written by `dump_results`.
+#[pyfunction]
+pub fn load_results(py: Python<'_>, path: &str) -> PyResult<Vec<MutationResult>> {
+    py.allow_threads(|| read_results(Path::new(path)))
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::InvalidData => PyValueError::new_err(format!("{}: {}", path, e)),
+            _ => PyIOError::new_err(format!("{}: {}", path, e)),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::{calculate_statistics, CheckResult, TestStatistics};
+    use crate::severity::Severity;
+
+    fn results() -> Vec<MutationResult> {
+        (0..3)
+            .map(|i| MutationResult {
+                mutation_type: "noise".to_string(),
+                passed: i != 1,
+                latency_ms: 100.0 + i as f64,
+                checks: vec![CheckResult {
+                    check_type: "contains".to_string(),
+                    passed: i != 1,
+                    details: "missing 'Paris'".to_string(),
+                    severity: Severity::Warn,
+                }],
+                tags: [("model".to_string(), "gpt-4o".to_string())].into(),
+                timestamp_ms: Some(1_700_000_000_000.0 + i as f64),
+                prompt_tokens: Some(12),
+                ..Default::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let stats = calculate_statistics(&results()).unwrap();
+        let compact = to_json(&stats, false);
+        let pretty = to_json(&stats, true);
+        assert!(!compact.contains('\n') && pretty.contains('\n'));
+        let parsed: TestStatistics = from_json(&pretty).unwrap();
+        assert_eq!(
+            (parsed.total_mutations, parsed.robustness_score),
+            (3, stats.robustness_score)
+        );
+        assert_eq!(parsed.by_check, stats.by_check);
+        assert_eq!(parsed.by_tag, stats.by_tag);
+        assert!(from_json::<TestStatistics>("{\"total_mutations\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_dump_and_load_results() {
+        let path = std::env::temp_dir().join(format!("flakestorm-results-{}.json", std::process::id()));
+        write_results(&path, &results(), true).unwrap();
+        let loaded = read_results(&path).unwrap();
+        assert_eq!(to_json(&loaded, false), to_json(&results(), false));
+        assert_eq!(loaded[1].checks[0].severity, Severity::Warn);
+
+        std::fs::write(&path, "[{\"passed\": true}]").unwrap();
+        assert_eq!(read_results(&path).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+        assert!(read_results(&path).is_err());
+
+        // A NaN would come back as null; it is refused and the old file kept
+        write_results(&path, &results(), false).unwrap();
+        let mut bad = results();
+        bad[0].latency_ms = f64::NAN;
+        assert_eq!(write_results(&path, &bad, false).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(read_results(&path).unwrap().len(), 3);
+        let pid = std::process::id();
+        assert!(!path
+            .with_file_name(format!(".flakestorm-results-{}.json.{}.tmp", pid, pid))
+            .exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
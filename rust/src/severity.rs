@@ -0,0 +1,292 @@
+//! Check severities and severity-weighted scoring
+//!
+//! A flat pass/fail makes a leaked canary look the same as a slightly
+//! off-tone answer. Every check result carries a severity (info, warn,
+//! error or critical), and in severity scoring a mutation earns credit
+//! between 0 and 1 instead of a boolean:
+//!
+//! - a failed critical check fails the mutation outright, whatever the
+//!   other checks say,
+//! - every other failed check subtracts its severity's penalty, so by
+//!   default an error costs the whole mutation while a warning only
+//!   dents it and an info finding is free.
+//!
+//! Spec files use the critical/high/medium/low vocabulary; high, medium
+//! and low are read as error, warn and info.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::CheckResult;
+use crate::strategy::{validate_credits, Credit, ScoringStrategy};
+
+/// How much a failed check matters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warn,
+    #[default]
+    Error,
+    Critical,
+}
+
+impl Severity {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "info" | "low" => Ok(Severity::Info),
+            "warn" | "warning" | "medium" => Ok(Severity::Warn),
+            "error" | "high" => Ok(Severity::Error),
+            "critical" => Ok(Severity::Critical),
+            other => Err(format!(
+                "unknown severity '{}' (expected info, warn, error or critical)",
+                other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warn => "warn",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl IntoPy<PyObject> for Severity {
+    fn into_py(self, py: Python<'_>) -> PyObject {
+        self.name().into_py(py)
+    }
+}
+
+/// Credit lost per failed check of each non-critical severity
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SeverityPenalties {
+    pub info: f64,
+    pub warn: f64,
+    pub error: f64,
+}
+
+impl Default for SeverityPenalties {
+    fn default() -> Self {
+        Self {
+            info: 0.0,
+            warn: 0.25,
+            error: 1.0,
+        }
+    }
+}
+
+impl SeverityPenalties {
+    pub fn validate(&self) -> Result<(), String> {
+        for (name, penalty) in [("info", self.info), ("warn", self.warn), ("error", self.error)] {
+            if !(0.0..=1.0).contains(&penalty) {
+                return Err(format!("{} penalty must be between 0 and 1, got {}", name, penalty));
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn of(&self, severity: Severity) -> f64 {
+        match severity {
+            Severity::Info => self.info,
+            Severity::Warn => self.warn,
+            Severity::Error => self.error,
+            Severity::Critical => 1.0,
+        }
+    }
+}
+
+/// How a mutation's outcome feeds the robustness score
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ScoringMode {
+    /// The mutation's pass/fail flag, as reported by the runner
+    #[default]
+    Flat,
+    /// Partial credit from the severities of its failed checks
+    Severity(SeverityPenalties),
+}
+
+impl ScoringMode {
+    /// Mode by name; `penalties` are only used by severity.
+    pub fn parse(name: &str, penalties: SeverityPenalties) -> Result<Self, String> {
+        match name {
+            "flat" => Ok(ScoringMode::Flat),
+            "severity" => Ok(ScoringMode::Severity(penalties)),
+            other => Err(format!("unknown scoring mode '{}' (expected flat or severity)", other)),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoringMode::Flat => "flat",
+            ScoringMode::Severity(_) => "severity",
+        }
+    }
+}
+
+/// Credit in 0..=1 for one mutation. Without failed checks the pass/fail
+/// flag decides, so a mutation the runner failed for a reason no check
+/// recorded earns nothing.
+pub fn mutation_credit(passed: bool, checks: &[CheckResult], penalties: &SeverityPenalties) -> f64 {
+    let failed: Vec<&CheckResult> = checks.iter().filter(|c| !c.passed).collect();
+    if failed.is_empty() {
+        return if passed { 1.0 } else { 0.0 };
+    }
+    let lost: f64 = failed.iter().map(|c| penalties.of(c.severity)).sum();
+    (1.0 - lost).max(0.0)
+}
+
+/// Failed checks per severity over a run
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SeverityBreakdown {
+    pub info: usize,
+    pub warn: usize,
+    pub error: usize,
+    pub critical: usize,
+    /// Mutations failed outright by a critical check
+    pub critical_mutations: usize,
+}
+
+/// Count failed checks by severity; `checks` holds one slice per mutation.
+pub fn severity_breakdown<'a>(checks: impl IntoIterator<Item = &'a [CheckResult]>) -> SeverityBreakdown {
+    let mut breakdown = SeverityBreakdown::default();
+    for mutation in checks {
+        let mut critical = false;
+        for check in mutation.iter().filter(|c| !c.passed) {
+            match check.severity {
+                Severity::Info => breakdown.info += 1,
+                Severity::Warn => breakdown.warn += 1,
+                Severity::Error => breakdown.error += 1,
+                Severity::Critical => {
+                    breakdown.critical += 1;
+                    critical = true;
+                }
+            }
+        }
+        breakdown.critical_mutations += usize::from(critical);
+    }
+    breakdown
+}
+
+/// Weighted mean credit of (passed, checks, weight) mutations; 0.0 when
+/// the total weight is 0. Fails on a negative or non-finite weight.
+pub fn severity_weighted_score(
+    mutations: &[(bool, &[CheckResult], f64)],
+    penalties: &SeverityPenalties,
+) -> Result<f64, String> {
+    penalties.validate()?;
+    let credits: Vec<Credit> = mutations
+        .iter()
+        .map(|(passed, checks, weight)| ("", *weight, mutation_credit(*passed, checks, penalties)))
+        .collect();
+    validate_credits(&credits)?;
+    Ok(ScoringStrategy::WeightedAverage.score(&credits))
+}
+
+/// Severity-weighted robustness score.
+///
+/// `checks` holds the check results of each mutation. A failed critical
+/// check zeroes its mutation; other failures subtract their severity's
+/// penalty.
+#[pyfunction]
+#[pyo3(name = "severity_weighted_score", signature = (
+    passed, checks, weights = None, info_penalty = 0.0, warn_penalty = 0.25, error_penalty = 1.0
+))]
+pub fn py_severity_weighted_score(
+    passed: Vec<bool>,
+    checks: Vec<Vec<CheckResult>>,
+    weights: Option<Vec<f64>>,
+    info_penalty: f64,
+    warn_penalty: f64,
+    error_penalty: f64,
+) -> PyResult<f64> {
+    let weights = weights.unwrap_or_else(|| vec![1.0; passed.len()]);
+    if checks.len() != passed.len() || weights.len() != passed.len() {
+        return Err(PyValueError::new_err(format!(
+            "got {} outcomes, {} check lists and {} weights",
+            passed.len(),
+            checks.len(),
+            weights.len()
+        )));
+    }
+    let mutations: Vec<(bool, &[CheckResult], f64)> = passed
+        .iter()
+        .zip(&checks)
+        .zip(&weights)
+        .map(|((p, c), w)| (*p, c.as_slice(), *w))
+        .collect();
+    let penalties = SeverityPenalties {
+        info: info_penalty,
+        warn: warn_penalty,
+        error: error_penalty,
+    };
+    severity_weighted_score(&mutations, &penalties).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(passed: bool, severity: Severity) -> CheckResult {
+        CheckResult {
+            check_type: severity.name().to_string(),
+            passed,
+            details: String::new(),
+            severity,
+        }
+    }
+
+    #[test]
+    fn test_parse_aliases() {
+        assert_eq!(Severity::parse("HIGH"), Ok(Severity::Error));
+        assert_eq!(Severity::parse("medium"), Ok(Severity::Warn));
+        assert_eq!(Severity::parse("low"), Ok(Severity::Info));
+        assert!(Severity::parse("urgent").is_err());
+        assert!(Severity::Critical > Severity::Error);
+    }
+
+    #[test]
+    fn test_mutation_credit() {
+        let penalties = SeverityPenalties::default();
+        let warned = [check(false, Severity::Warn), check(true, Severity::Error)];
+        assert_eq!(mutation_credit(false, &warned, &penalties), 0.75);
+        let leaked = [check(false, Severity::Critical), check(true, Severity::Warn)];
+        let lenient = SeverityPenalties {
+            error: 0.5,
+            ..penalties
+        };
+        assert_eq!(mutation_credit(false, &leaked, &lenient), 0.0);
+        let errors = [check(false, Severity::Error), check(false, Severity::Info)];
+        assert_eq!(mutation_credit(false, &errors, &lenient), 0.5);
+        assert_eq!(mutation_credit(true, &[], &penalties), 1.0);
+        // Failed by the runner with every check passing
+        let clean = [check(true, Severity::Error)];
+        assert_eq!(mutation_credit(false, &clean, &penalties), 0.0);
+        assert_eq!(mutation_credit(true, &clean, &penalties), 1.0);
+    }
+
+    #[test]
+    fn test_weighted_score_and_breakdown() {
+        let warned = vec![check(false, Severity::Warn)];
+        let leaked = vec![check(false, Severity::Critical), check(false, Severity::Warn)];
+        let mutations = [(false, warned.as_slice(), 1.0), (false, leaked.as_slice(), 3.0)];
+        let score = severity_weighted_score(&mutations, &SeverityPenalties::default()).unwrap();
+        assert!((score - 0.75 / 4.0).abs() < 1e-12);
+
+        let breakdown = severity_breakdown([warned.as_slice(), leaked.as_slice()]);
+        assert_eq!(
+            (breakdown.warn, breakdown.critical, breakdown.critical_mutations),
+            (2, 1, 1)
+        );
+        let bad = SeverityPenalties {
+            warn: 1.5,
+            ..Default::default()
+        };
+        assert!(severity_weighted_score(&mutations, &bad).is_err());
+    }
+}
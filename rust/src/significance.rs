@@ -0,0 +1,350 @@
+//! Significance tests between two runs
+//!
+//! "Did the new prompt make the agent more robust?" needs more than two
+//! scores side by side. Pass/fail counts of run A and run B form a 2×2
+//! table, tested overall and per mutation type with
+//!
+//! - Pearson's chi-square (1 degree of freedom), and
+//! - Fisher's exact test (two-sided), which stays valid for the small
+//!   per-type tables where the chi-square approximation breaks down.
+//!
+//! Effect sizes are the pass-rate difference (B − A), Cohen's h and the
+//! odds ratio with a 0.5 continuity correction. The weighted scores are
+//! compared with an unpaired bootstrap: each run is resampled on its own
+//! and the distribution of the score difference gives an interval and a
+//! two-sided p-value.
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::bayes::ln_gamma;
+use crate::confidence::weighted_rate;
+use crate::rng::{resolve_seed, SplitMix64};
+use crate::scoring::{percentile, MutationResult, PercentileMethod};
+
+/// One mutation's outcome: (mutation type, passed, weight)
+pub type Outcome = (String, bool, f64);
+
+/// Bootstrap settings for the weighted score comparison
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ComparisonConfig {
+    pub resamples: usize,
+    /// Confidence level of the score-difference interval
+    pub confidence: f64,
+    /// Bootstrap seed; the run seed when None
+    pub seed: Option<u64>,
+}
+
+impl Default for ComparisonConfig {
+    fn default() -> Self {
+        Self {
+            resamples: 2000,
+            confidence: 0.95,
+            seed: None,
+        }
+    }
+}
+
+/// Pass-rate comparison of one 2×2 table
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProportionTest {
+    /// Mutation type, or "overall"
+    pub mutation_type: String,
+    pub a_passed: usize,
+    pub a_total: usize,
+    pub b_passed: usize,
+    pub b_total: usize,
+    pub a_rate: f64,
+    pub b_rate: f64,
+    /// b_rate - a_rate
+    pub difference: f64,
+    pub cohens_h: f64,
+    /// Odds of passing in B over A, 0.5-corrected
+    pub odds_ratio: f64,
+    pub chi_square: f64,
+    pub chi_square_p: f64,
+    pub fisher_p: f64,
+}
+
+/// Bootstrap comparison of the weighted scores
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoreDifferenceTest {
+    pub a_score: f64,
+    pub b_score: f64,
+    /// b_score - a_score
+    pub difference: f64,
+    pub lower: f64,
+    pub upper: f64,
+    pub p_value: f64,
+    pub resamples: usize,
+}
+
+/// Overall, per-type and weighted comparison of two runs
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunComparison {
+    pub overall: ProportionTest,
+    /// Sorted by mutation type; types seen in either run
+    pub by_type: Vec<ProportionTest>,
+    pub weighted: ScoreDifferenceTest,
+}
+
+/// Complementary error function (Numerical Recipes `erfcc`, fractional
+/// error below 1.2e-7).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98 + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let ans = t * poly.exp();
+    if x >= 0.0 {
+        ans
+    } else {
+        2.0 - ans
+    }
+}
+
+fn ln_choose(n: usize, k: usize) -> f64 {
+    ln_gamma(n as f64 + 1.0) - ln_gamma(k as f64 + 1.0) - ln_gamma((n - k) as f64 + 1.0)
+}
+
+/// Pearson chi-square statistic and p-value (1 df) for passed/total counts.
+pub fn chi_square_test(a_passed: usize, a_total: usize, b_passed: usize, b_total: usize) -> (f64, f64) {
+    let n = (a_total + b_total) as f64;
+    let passed = (a_passed + b_passed) as f64;
+    let failed = n - passed;
+    if a_total == 0 || b_total == 0 || passed == 0.0 || failed == 0.0 {
+        return (0.0, 1.0);
+    }
+    let mut chi2 = 0.0;
+    for (observed_pass, total) in [(a_passed, a_total), (b_passed, b_total)] {
+        let total = total as f64;
+        let expected_pass = total * passed / n;
+        let expected_fail = total * failed / n;
+        chi2 += (observed_pass as f64 - expected_pass).powi(2) / expected_pass;
+        chi2 += ((total - observed_pass as f64) - expected_fail).powi(2) / expected_fail;
+    }
+    (chi2, erfc((chi2 / 2.0).sqrt()))
+}
+
+/// Two-sided Fisher exact p-value for passed/total counts.
+pub fn fisher_exact(a_passed: usize, a_total: usize, b_passed: usize, b_total: usize) -> f64 {
+    let n = a_total + b_total;
+    let passed = a_passed + b_passed;
+    let ln_denominator = ln_choose(n, passed);
+    let ln_p = |x: usize| ln_choose(a_total, x) + ln_choose(b_total, passed - x) - ln_denominator;
+    let observed = ln_p(a_passed);
+    let low = passed.saturating_sub(b_total);
+    let high = a_total.min(passed);
+    let p: f64 = (low..=high)
+        .map(ln_p)
+        .filter(|&lp| lp <= observed + 1e-7)
+        .map(f64::exp)
+        .sum();
+    p.min(1.0)
+}
+
+/// Counts, effect sizes and both tests for one table.
+pub fn proportion_test(
+    mutation_type: &str,
+    a_passed: usize,
+    a_total: usize,
+    b_passed: usize,
+    b_total: usize,
+) -> ProportionTest {
+    let rate = |p: usize, t: usize| if t > 0 { p as f64 / t as f64 } else { 0.0 };
+    let (a_rate, b_rate) = (rate(a_passed, a_total), rate(b_passed, b_total));
+    let odds = |p: usize, t: usize| (p as f64 + 0.5) / ((t - p) as f64 + 0.5);
+    let (chi_square, chi_square_p) = chi_square_test(a_passed, a_total, b_passed, b_total);
+    ProportionTest {
+        mutation_type: mutation_type.to_string(),
+        a_passed,
+        a_total,
+        b_passed,
+        b_total,
+        a_rate,
+        b_rate,
+        difference: b_rate - a_rate,
+        cohens_h: 2.0 * b_rate.sqrt().asin() - 2.0 * a_rate.sqrt().asin(),
+        odds_ratio: odds(b_passed, b_total) / odds(a_passed, a_total),
+        chi_square,
+        chi_square_p,
+        fisher_p: fisher_exact(a_passed, a_total, b_passed, b_total),
+    }
+}
+
+fn counts(outcomes: &[Outcome]) -> (usize, usize) {
+    (outcomes.iter().filter(|o| o.1).count(), outcomes.len())
+}
+
+fn resample(pairs: &[(bool, f64)], rng: &mut SplitMix64) -> f64 {
+    let sample: Vec<(bool, f64)> = (0..pairs.len()).map(|_| pairs[rng.below(pairs.len())]).collect();
+    weighted_rate(&sample)
+}
+
+fn score_difference(a: &[Outcome], b: &[Outcome], config: &ComparisonConfig) -> ScoreDifferenceTest {
+    let a_pairs: Vec<(bool, f64)> = a.iter().map(|o| (o.1, o.2)).collect();
+    let b_pairs: Vec<(bool, f64)> = b.iter().map(|o| (o.1, o.2)).collect();
+    let (a_score, b_score) = (weighted_rate(&a_pairs), weighted_rate(&b_pairs));
+    let seed = resolve_seed(config.seed);
+    let mut diffs: Vec<f64> = (0..config.resamples)
+        .into_par_iter()
+        .map(|i| {
+            let mut rng = SplitMix64::for_item(seed, i);
+            resample(&b_pairs, &mut rng) - resample(&a_pairs, &mut rng)
+        })
+        .collect();
+    diffs.sort_by(f64::total_cmp);
+    let alpha = (1.0 - config.confidence) / 2.0;
+    let at = |q: f64| percentile(&diffs, q * 100.0, PercentileMethod::Linear);
+    let share = |pred: &dyn Fn(f64) -> bool| diffs.iter().filter(|&&d| pred(d)).count() as f64 / diffs.len() as f64;
+    let p_value = (2.0 * share(&|d| d <= 0.0).min(share(&|d| d >= 0.0))).min(1.0);
+    ScoreDifferenceTest {
+        a_score,
+        b_score,
+        difference: b_score - a_score,
+        lower: at(alpha),
+        upper: at(1.0 - alpha),
+        p_value,
+        resamples: config.resamples,
+    }
+}
+
+/// Compare two runs' outcomes overall, per mutation type and by weighted score.
+pub fn compare_outcomes(a: &[Outcome], b: &[Outcome], config: &ComparisonConfig) -> Result<RunComparison, String> {
+    if a.is_empty() || b.is_empty() {
+        return Err(format!("both runs need results, got {} and {}", a.len(), b.len()));
+    }
+    if config.resamples == 0 {
+        return Err("bootstrap needs at least one resample".to_string());
+    }
+    if config.confidence <= 0.0 || config.confidence >= 1.0 || config.confidence.is_nan() {
+        return Err(format!("confidence must be between 0 and 1, got {}", config.confidence));
+    }
+    if let Some(o) = a.iter().chain(b).find(|o| o.2 < 0.0 || o.2.is_nan()) {
+        return Err(format!("weights must be non-negative, got {} for '{}'", o.2, o.0));
+    }
+
+    let mut groups: BTreeMap<&str, (Vec<Outcome>, Vec<Outcome>)> = BTreeMap::new();
+    for o in a {
+        groups.entry(&o.0).or_default().0.push(o.clone());
+    }
+    for o in b {
+        groups.entry(&o.0).or_default().1.push(o.clone());
+    }
+    let by_type = groups
+        .par_iter()
+        .map(|(mutation_type, (ga, gb))| {
+            let ((ap, at), (bp, bt)) = (counts(ga), counts(gb));
+            proportion_test(mutation_type, ap, at, bp, bt)
+        })
+        .collect();
+
+    let ((ap, at), (bp, bt)) = (counts(a), counts(b));
+    Ok(RunComparison {
+        overall: proportion_test("overall", ap, at, bp, bt),
+        by_type,
+        weighted: score_difference(a, b, config),
+    })
+}
+
+/// `compare_outcomes` over two runs' mutation results.
+pub fn compare_runs(
+    a: &[MutationResult],
+    b: &[MutationResult],
+    config: &ComparisonConfig,
+) -> Result<RunComparison, String> {
+    let outcomes = |results: &[MutationResult]| -> Vec<Outcome> {
+        results
+            .iter()
+            .map(|r| (r.mutation_type.clone(), r.passed, r.weight))
+            .collect()
+    };
+    compare_outcomes(&outcomes(a), &outcomes(b), config)
+}
+
+/// Significance of the difference between two runs.
+///
+/// `results_a` and `results_b` are (mutation_type, passed, weight) tuples.
+/// Returns chi-square and Fisher exact tests overall and per mutation type,
+/// with effect sizes, plus a bootstrap test on the weighted score difference.
+#[pyfunction]
+#[pyo3(name = "compare_runs", signature = (results_a, results_b, resamples = 2000, confidence = 0.95, seed = None))]
+pub fn py_compare_runs(
+    py: Python<'_>,
+    results_a: Vec<Outcome>,
+    results_b: Vec<Outcome>,
+    resamples: usize,
+    confidence: f64,
+    seed: Option<u64>,
+) -> PyResult<RunComparison> {
+    let config = ComparisonConfig {
+        resamples,
+        confidence,
+        seed,
+    };
+    py.allow_threads(|| compare_outcomes(&results_a, &results_b, &config))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(mutation_type: &str, passed: usize, total: usize) -> Vec<Outcome> {
+        (0..total)
+            .map(|i| (mutation_type.to_string(), i < passed, 1.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_exact_and_chi_square() {
+        // Textbook example: two-sided p = 0.002759
+        assert!((fisher_exact(1, 10, 11, 14) - 0.002759).abs() < 1e-5);
+        assert!((fisher_exact(5, 10, 5, 10) - 1.0).abs() < 1e-9);
+
+        assert!((erfc((3.841_458_8f64 / 2.0).sqrt()) - 0.05).abs() < 1e-6);
+        let (chi2, p) = chi_square_test(30, 50, 40, 50);
+        assert!((chi2 - 4.761_904_76).abs() < 1e-6, "{}", chi2);
+        assert!((p - 0.029_096).abs() < 1e-5, "{}", p);
+        assert_eq!(chi_square_test(5, 5, 7, 7), (0.0, 1.0));
+    }
+
+    #[test]
+    fn test_compare_runs() {
+        let mut a = run("noise", 40, 50);
+        a.extend(run("prompt_injection", 10, 50));
+        let mut b = run("noise", 41, 50);
+        b.extend(run("prompt_injection", 35, 50));
+        let config = ComparisonConfig {
+            seed: Some(3),
+            ..Default::default()
+        };
+        let cmp = compare_outcomes(&a, &b, &config).unwrap();
+        assert_eq!((cmp.overall.a_passed, cmp.overall.b_passed), (50, 76));
+        assert!(cmp.overall.fisher_p < 0.001);
+        let types: Vec<&str> = cmp.by_type.iter().map(|t| t.mutation_type.as_str()).collect();
+        assert_eq!(types, vec!["noise", "prompt_injection"]);
+        assert!(cmp.by_type[0].fisher_p > 0.5);
+        assert!(cmp.by_type[1].cohens_h > 0.8 && cmp.by_type[1].odds_ratio > 1.0);
+
+        assert!((cmp.weighted.difference - 0.26).abs() < 1e-12);
+        assert!(cmp.weighted.lower > 0.0 && cmp.weighted.p_value < 0.01);
+        assert_eq!(cmp, compare_outcomes(&a, &b, &config).unwrap());
+
+        let same = compare_outcomes(&a, &a, &config).unwrap();
+        assert!(same.weighted.p_value > 0.5);
+        assert!(compare_outcomes(&a, &[], &config).is_err());
+    }
+}
@@ -0,0 +1,396 @@
+//! Text similarity metrics for flakestorm
+//!
+//! Character-level edit distance penalises good paraphrases heavily, so this
+//! module provides token-based metrics that compare the words two strings
+//! share rather than the exact characters they are spelled with, plus
+//! character shingle metrics for spotting near-duplicate long prompts.
+
+use std::collections::HashSet;
+
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::diff::{lcs_ratio, Granularity};
+use crate::edit_distance::levenshtein;
+use crate::sanitize::PyResponseSanitizer;
+
+/// How a string is split into tokens before comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tokenizer {
+    /// Split on Unicode whitespace; punctuation stays attached to words.
+    Whitespace,
+    /// Split on UAX #29 word boundaries; punctuation and whitespace are
+    /// discarded, so "don't" and "3.5" stay whole.
+    Word,
+}
+
+impl Tokenizer {
+    /// Parse a tokenizer name as accepted from Python.
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "whitespace" => Some(Tokenizer::Whitespace),
+            "word" | "unicode" => Some(Tokenizer::Word),
+            _ => None,
+        }
+    }
+
+    /// Split `text` into tokens, lowercasing unless `case_sensitive` is set.
+    pub fn tokenize(self, text: &str, case_sensitive: bool) -> Vec<String> {
+        let raw: Vec<&str> = match self {
+            Tokenizer::Whitespace => text.split_whitespace().collect(),
+            Tokenizer::Word => text.unicode_words().collect(),
+        };
+
+        raw.into_iter()
+            .map(|t| {
+                if case_sensitive {
+                    t.to_string()
+                } else {
+                    t.to_lowercase()
+                }
+            })
+            .collect()
+    }
+}
+
+fn parse_tokenizer(name: &str) -> PyResult<Tokenizer> {
+    Tokenizer::parse(name).ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "unknown tokenizer '{}', expected 'whitespace' or 'word'",
+            name
+        ))
+    })
+}
+
+/// Jaccard index |A ∩ B| / |A ∪ B| of two token sets.
+///
+/// Two empty sets are considered identical.
+pub fn jaccard<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let intersection = a.intersection(b).count();
+    let union = a.len() + b.len() - intersection;
+    intersection as f64 / union as f64
+}
+
+/// Sørensen–Dice coefficient 2|A ∩ B| / (|A| + |B|) of two token sets.
+///
+/// Two empty sets are considered identical.
+pub fn dice<T: Eq + std::hash::Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f64 {
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    let intersection = a.intersection(b).count();
+    2.0 * intersection as f64 / (a.len() + b.len()) as f64
+}
+
+fn token_sets(
+    s1: &str,
+    s2: &str,
+    tokenizer: Tokenizer,
+    case_sensitive: bool,
+) -> (HashSet<String>, HashSet<String>) {
+    (
+        tokenizer.tokenize(s1, case_sensitive).into_iter().collect(),
+        tokenizer.tokenize(s2, case_sensitive).into_iter().collect(),
+    )
+}
+
+/// Token-set Jaccard similarity of two strings.
+pub fn token_jaccard(s1: &str, s2: &str, tokenizer: Tokenizer, case_sensitive: bool) -> f64 {
+    let (a, b) = token_sets(s1, s2, tokenizer, case_sensitive);
+    jaccard(&a, &b)
+}
+
+/// Token-set Sørensen–Dice similarity of two strings.
+pub fn token_dice(s1: &str, s2: &str, tokenizer: Tokenizer, case_sensitive: bool) -> f64 {
+    let (a, b) = token_sets(s1, s2, tokenizer, case_sensitive);
+    dice(&a, &b)
+}
+
+/// Base of the polynomial rolling hash used for character shingles.
+const SHINGLE_HASH_BASE: u64 = 1_000_003;
+
+/// Hash every character n-gram of `text` with a rolling polynomial hash.
+///
+/// Runs in O(len) regardless of `n`. A non-empty string shorter than `n`
+/// yields a single shingle covering the whole string.
+pub fn shingle_hashes(text: &str, n: usize) -> HashSet<u64> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut shingles = HashSet::new();
+    if chars.is_empty() || n == 0 {
+        return shingles;
+    }
+
+    let window = n.min(chars.len());
+    // Weight of the character leaving the window: BASE^(window - 1)
+    let high = (1..window).fold(1u64, |acc, _| acc.wrapping_mul(SHINGLE_HASH_BASE));
+
+    let mut hash = 0u64;
+    for &c in &chars[..window] {
+        hash = hash.wrapping_mul(SHINGLE_HASH_BASE).wrapping_add(c as u64);
+    }
+    shingles.insert(hash);
+
+    for i in window..chars.len() {
+        hash = hash
+            .wrapping_sub((chars[i - window] as u64).wrapping_mul(high))
+            .wrapping_mul(SHINGLE_HASH_BASE)
+            .wrapping_add(chars[i] as u64);
+        shingles.insert(hash);
+    }
+
+    shingles
+}
+
+/// Jaccard similarity of the character n-gram shingle sets of two strings.
+pub fn shingle_jaccard(s1: &str, s2: &str, n: usize) -> f64 {
+    jaccard(&shingle_hashes(s1, n), &shingle_hashes(s2, n))
+}
+
+/// Levenshtein similarity ratio 1 - distance / max_len (0.0 to 1.0).
+pub fn levenshtein_ratio(s1: &str, s2: &str) -> f64 {
+    let max_len = s1.chars().count().max(s2.chars().count());
+    if max_len == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(s1, s2) as f64 / max_len as f64
+}
+
+/// Similarity metric selectable by name from Python.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Levenshtein,
+    Jaccard,
+    Dice,
+    Shingle,
+    Lcs,
+}
+
+impl Metric {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "levenshtein" => Some(Metric::Levenshtein),
+            "jaccard" => Some(Metric::Jaccard),
+            "dice" => Some(Metric::Dice),
+            "shingle" => Some(Metric::Shingle),
+            "lcs" => Some(Metric::Lcs),
+            _ => None,
+        }
+    }
+
+    /// Similarity of two strings under this metric, using each metric's defaults.
+    pub fn similarity(self, s1: &str, s2: &str) -> f64 {
+        match self {
+            Metric::Levenshtein => levenshtein_ratio(s1, s2),
+            Metric::Jaccard => token_jaccard(s1, s2, Tokenizer::Whitespace, false),
+            Metric::Dice => token_dice(s1, s2, Tokenizer::Whitespace, false),
+            Metric::Shingle => shingle_jaccard(s1, s2, 3),
+            Metric::Lcs => lcs_ratio(s1, s2, Granularity::Char),
+        }
+    }
+}
+
+pub(crate) fn parse_metric(name: &str) -> PyResult<Metric> {
+    Metric::parse(name).ok_or_else(|| {
+        PyValueError::new_err(format!(
+            "unknown metric '{}', expected one of levenshtein, jaccard, dice, shingle, lcs",
+            name
+        ))
+    })
+}
+
+/// All-pairs similarity as a row-major n×n matrix, computed in parallel.
+///
+/// Every metric is symmetric, so only pairs i < j are computed. With
+/// `upper_only` the lower triangle is left at 0.0, otherwise it is mirrored.
+/// The diagonal is always 1.0.
+pub fn pairwise_similarity(strings: &[String], metric: Metric, upper_only: bool) -> Array2<f64> {
+    let n = strings.len();
+    let rows: Vec<Vec<f64>> = (0..n)
+        .into_par_iter()
+        .map(|i| {
+            (i + 1..n)
+                .map(|j| metric.similarity(&strings[i], &strings[j]))
+                .collect()
+        })
+        .collect();
+
+    let mut matrix = Array2::<f64>::zeros((n, n));
+    for (i, row) in rows.into_iter().enumerate() {
+        matrix[[i, i]] = 1.0;
+        for (offset, value) in row.into_iter().enumerate() {
+            let j = i + 1 + offset;
+            matrix[[i, j]] = value;
+            if !upper_only {
+                matrix[[j, i]] = value;
+            }
+        }
+    }
+    matrix
+}
+
+/// Word-token Jaccard similarity between two strings (0.0 to 1.0).
+///
+/// `tokenizer` is either "whitespace" or "word" (Unicode word boundaries).
+#[pyfunction]
+#[pyo3(signature = (s1, s2, tokenizer = "whitespace", case_sensitive = false))]
+pub fn jaccard_similarity(
+    s1: &str,
+    s2: &str,
+    tokenizer: &str,
+    case_sensitive: bool,
+) -> PyResult<f64> {
+    Ok(token_jaccard(s1, s2, parse_tokenizer(tokenizer)?, case_sensitive))
+}
+
+/// Word-token Sørensen–Dice similarity between two strings (0.0 to 1.0).
+///
+/// `tokenizer` is either "whitespace" or "word" (Unicode word boundaries).
+#[pyfunction]
+#[pyo3(signature = (s1, s2, tokenizer = "whitespace", case_sensitive = false))]
+pub fn dice_similarity(
+    s1: &str,
+    s2: &str,
+    tokenizer: &str,
+    case_sensitive: bool,
+) -> PyResult<f64> {
+    Ok(token_dice(s1, s2, parse_tokenizer(tokenizer)?, case_sensitive))
+}
+
+/// Character n-gram shingle similarity between two strings (0.0 to 1.0).
+///
+/// `n` must be between 2 and 5. Catches near-duplicate long prompts that a
+/// Levenshtein ratio scores as distinct because of reordered passages.
+#[pyfunction]
+#[pyo3(signature = (s1, s2, n = 3))]
+pub fn shingle_similarity(s1: &str, s2: &str, n: usize) -> PyResult<f64> {
+    if !(2..=5).contains(&n) {
+        return Err(PyValueError::new_err(format!(
+            "shingle size must be between 2 and 5, got {}",
+            n
+        )));
+    }
+    Ok(shingle_jaccard(s1, s2, n))
+}
+
+/// All-pairs similarity matrix of `strings` as a float64 numpy array.
+///
+/// `metric` is one of "levenshtein", "jaccard", "dice", "shingle" or "lcs".
+/// A `sanitizer` (ResponseSanitizer) is applied to every string first. The
+/// GIL is released while the matrix is computed.
+#[pyfunction]
+#[pyo3(signature = (strings, metric = "levenshtein", upper_triangular = false, sanitizer = None))]
+pub fn similarity_matrix<'py>(
+    py: Python<'py>,
+    strings: Vec<String>,
+    metric: &str,
+    upper_triangular: bool,
+    sanitizer: Option<PyRef<'_, PyResponseSanitizer>>,
+) -> PyResult<&'py PyArray2<f64>> {
+    let metric = parse_metric(metric)?;
+    let sanitizer = sanitizer.map(|s| s.sanitizer().clone());
+    let matrix = py.allow_threads(|| match sanitizer {
+        Some(s) => {
+            let cleaned: Vec<String> = strings.par_iter().map(|t| s.sanitize(t)).collect();
+            pairwise_similarity(&cleaned, metric, upper_triangular)
+        }
+        None => pairwise_similarity(&strings, metric, upper_triangular),
+    });
+    Ok(matrix.into_pyarray(py))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizers() {
+        let text = "Book a flight, to Paris!";
+        assert_eq!(
+            Tokenizer::Whitespace.tokenize(text, false),
+            vec!["book", "a", "flight,", "to", "paris!"]
+        );
+        assert_eq!(
+            Tokenizer::Word.tokenize(text, true),
+            vec!["Book", "a", "flight", "to", "Paris"]
+        );
+        assert_eq!(
+            Tokenizer::Word.tokenize("Don't pay $3.50 for 'über-cheap' tickets", false),
+            vec!["don't", "pay", "3.50", "for", "über", "cheap", "tickets"]
+        );
+        assert_eq!(Tokenizer::Word.tokenize("東京へ行く", false), vec!["東", "京", "へ", "行", "く"]);
+        assert_eq!(Tokenizer::parse("unicode"), Some(Tokenizer::Word));
+        assert_eq!(Tokenizer::parse("bogus"), None);
+    }
+
+    #[test]
+    fn test_jaccard_similarity() {
+        let sim = token_jaccard(
+            "book a flight to Paris",
+            "a flight to Paris, book it",
+            Tokenizer::Word,
+            false,
+        );
+        // {book,a,flight,to,paris} vs {a,flight,to,paris,book,it}
+        assert!((sim - 5.0 / 6.0).abs() < 1e-9);
+        assert_eq!(token_jaccard("", "", Tokenizer::Whitespace, false), 1.0);
+        assert_eq!(token_jaccard("a", "", Tokenizer::Whitespace, false), 0.0);
+    }
+
+    #[test]
+    fn test_dice_similarity() {
+        let sim = token_dice("the cat sat", "the cat ran", Tokenizer::Whitespace, false);
+        assert!((sim - 2.0 * 2.0 / 6.0).abs() < 1e-9);
+        assert!(token_dice("A B", "a b", Tokenizer::Whitespace, true) < 1e-9);
+    }
+
+    #[test]
+    fn test_shingle_hashes_match_naive() {
+        let text = "abcabcd";
+        let rolling = shingle_hashes(text, 3);
+        let chars: Vec<char> = text.chars().collect();
+        let naive: HashSet<u64> = chars
+            .windows(3)
+            .map(|w| {
+                w.iter().fold(0u64, |h, &c| {
+                    h.wrapping_mul(SHINGLE_HASH_BASE).wrapping_add(c as u64)
+                })
+            })
+            .collect();
+        assert_eq!(rolling, naive);
+        assert_eq!(rolling.len(), 4); // abc, bca, cab, bcd
+        assert_eq!(shingle_hashes("ab", 3).len(), 1);
+        assert!(shingle_hashes("", 3).is_empty());
+    }
+
+    #[test]
+    fn test_shingle_jaccard() {
+        let a = "Please book me a flight from Berlin to Paris next Tuesday morning.";
+        let b = "Next Tuesday morning, please book me a flight from Berlin to Paris.";
+        assert!(shingle_jaccard(a, b, 3) > 0.7);
+        assert_eq!(shingle_jaccard(a, a, 4), 1.0);
+        assert!(shingle_jaccard("abcdef", "uvwxyz", 2) < 1e-9);
+    }
+
+    #[test]
+    fn test_pairwise_similarity() {
+        let strings: Vec<String> = vec!["book a flight".into(), "book a hotel".into(), "xyz".into()];
+        let full = pairwise_similarity(&strings, Metric::Jaccard, false);
+        assert_eq!(full.shape(), &[3, 3]);
+        assert_eq!(full[[0, 0]], 1.0);
+        assert!((full[[0, 1]] - 0.5).abs() < 1e-9);
+        assert_eq!(full[[0, 1]], full[[1, 0]]);
+
+        let upper = pairwise_similarity(&strings, Metric::Levenshtein, true);
+        assert_eq!(upper[[1, 0]], 0.0);
+        assert!(upper[[0, 1]] > 0.0);
+        assert_eq!(Metric::parse("LCS"), Some(Metric::Lcs));
+        assert!(pairwise_similarity(&[], Metric::Dice, false).is_empty());
+    }
+}
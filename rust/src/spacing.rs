@@ -0,0 +1,105 @@
+//! Whitespace and punctuation noise mutator
+//!
+//! Doubles or removes spaces, slips in tabs and newlines, and drops or
+//! repeats punctuation. Each whitespace or punctuation character is edited
+//! with probability `rate`; letters and digits are never touched, so the
+//! words survive and only the layout changes.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+use crate::metrics::registry;
+use crate::rng::{resolve_seed, SplitMix64};
+
+/// Apply whitespace and punctuation noise to one prompt.
+pub fn spacing_noise(text: &str, rate: f64, rng: &mut SplitMix64) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 4);
+    for c in text.chars() {
+        if c == ' ' && rng.chance(rate) {
+            match rng.below(4) {
+                0 => out.push_str("  "),
+                1 => {}
+                2 => out.push('\t'),
+                _ => out.push('\n'),
+            }
+        } else if c.is_ascii_punctuation() && rng.chance(rate) {
+            if rng.below(2) == 0 {
+                out.push(c);
+                out.push(c);
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// `variants` independent noisy copies of every prompt, computed in parallel.
+///
+/// The result is grouped per prompt: `out[i][k]` is variant `k` of prompt `i`.
+pub fn spacing_variants(prompts: &[String], rate: f64, variants: usize, seed: u64) -> Vec<Vec<String>> {
+    prompts
+        .par_iter()
+        .enumerate()
+        .map(|(i, p)| {
+            (0..variants)
+                .map(|k| spacing_noise(p, rate, &mut SplitMix64::for_item(seed, i * variants + k)))
+                .collect()
+        })
+        .collect()
+}
+
+/// Whitespace/punctuation noise: `variants` mutations per prompt.
+///
+/// Returns one list of variants per input prompt.
+#[pyfunction]
+#[pyo3(signature = (prompts, rate = 0.1, variants = 1, seed = None))]
+pub fn spacing_mutations(
+    py: Python<'_>,
+    prompts: Vec<String>,
+    rate: f64,
+    variants: usize,
+    seed: Option<u64>,
+) -> PyResult<Vec<Vec<String>>> {
+    let seed = resolve_seed(seed);
+    if !(0.0..=1.0).contains(&rate) {
+        return Err(PyValueError::new_err(format!("rate must be in [0, 1], got {}", rate)));
+    }
+    registry().increment("mutations_generated.spacing", (prompts.len() * variants) as u64);
+    Ok(py.allow_threads(|| spacing_variants(&prompts, rate, variants, seed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skeleton(text: &str) -> String {
+        text.chars().filter(|c| c.is_alphanumeric()).collect()
+    }
+
+    #[test]
+    fn test_only_layout_changes() {
+        let prompt = "Hello, can you book a table for 2? Thanks!";
+        let mut rng = SplitMix64::new(8);
+        let out = spacing_noise(prompt, 0.5, &mut rng);
+        assert_ne!(out, prompt);
+        assert_eq!(skeleton(&out), skeleton(prompt));
+        assert_eq!(spacing_noise(prompt, 0.0, &mut rng), prompt);
+
+        let all = spacing_noise("a b. c", 1.0, &mut rng);
+        assert_eq!(skeleton(&all), "abc");
+        assert!(!all.contains(' ') || all.contains("  "));
+    }
+
+    #[test]
+    fn test_variants_per_prompt() {
+        let prompts = vec!["one, two; three.".to_string(), "no-punct here".to_string()];
+        let out = spacing_variants(&prompts, 0.3, 20, 7);
+        assert_eq!(out.len(), 2);
+        assert!(out.iter().all(|v| v.len() == 20));
+        assert_eq!(out, spacing_variants(&prompts, 0.3, 20, 7));
+        let distinct: std::collections::HashSet<&String> = out[0].iter().collect();
+        assert!(distinct.len() > 5);
+    }
+}
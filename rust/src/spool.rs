@@ -0,0 +1,303 @@
+//! Spill-to-disk result buffer for long runs
+//!
+//! Multi-day fuzz campaigns accumulate results faster than anything reads
+//! them. The spool keeps completed results in memory and, once the buffered
+//! JSON crosses a byte threshold, appends the records to a JSONL file and
+//! frees them. The threshold counts only the spool's own records, so the
+//! spill point does not depend on what else the process has allocated. Reading the
+//! spool back yields every record in push order, spilled or not.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::metrics::registry;
+
+/// In-memory result buffer that spills to a JSONL file under memory pressure
+#[derive(Debug)]
+pub struct ResultSpool {
+    path: PathBuf,
+    /// Spill once buffered records exceed this many bytes of JSON
+    pub max_buffer_bytes: usize,
+    buffer: Vec<String>,
+    buffer_bytes: usize,
+    spilled: usize,
+    spills: usize,
+}
+
+impl ResultSpool {
+    /// A new spool; an existing file at `path` is replaced on the first spill.
+    pub fn new(path: impl Into<PathBuf>, max_buffer_bytes: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_buffer_bytes,
+            buffer: Vec::new(),
+            buffer_bytes: 0,
+            spilled: 0,
+            spills: 0,
+        }
+    }
+
+    /// Spool over an existing JSONL file, such as a finished run's spill
+    /// file; records pushed later are appended after the ones in it.
+    pub fn open(path: impl Into<PathBuf>, max_buffer_bytes: usize) -> std::io::Result<Self> {
+        let path = path.into();
+        let mut spilled = 0;
+        for line in BufReader::new(File::open(&path)?).lines() {
+            line?;
+            spilled += 1;
+        }
+        Ok(Self {
+            spills: usize::from(spilled > 0),
+            spilled,
+            ..Self::new(path, max_buffer_bytes)
+        })
+    }
+
+    /// Buffer one JSON record, spilling if the byte threshold is crossed.
+    /// Returns whether a spill happened.
+    pub fn push_line(&mut self, line: String) -> std::io::Result<bool> {
+        self.buffer_bytes += line.len() + 1;
+        self.buffer.push(line);
+        if self.buffer_bytes > self.max_buffer_bytes {
+            self.spill()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    pub fn push<T: Serialize>(&mut self, record: &T) -> std::io::Result<bool> {
+        let line = serde_json::to_string(record).map_err(std::io::Error::other)?;
+        self.push_line(line)
+    }
+
+    /// Append the buffered records to the spill file and free them.
+    pub fn spill(&mut self) -> std::io::Result<usize> {
+        if self.buffer.is_empty() {
+            return Ok(0);
+        }
+        let file = if self.spills == 0 {
+            File::create(&self.path)?
+        } else {
+            OpenOptions::new().append(true).open(&self.path)?
+        };
+        let mut writer = BufWriter::new(file);
+        for line in &self.buffer {
+            writer.write_all(line.as_bytes())?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        let n = self.buffer.len();
+        self.spilled += n;
+        self.spills += 1;
+        self.buffer = Vec::new();
+        self.buffer_bytes = 0;
+        registry().increment("spool.spilled_records", n as u64);
+        Ok(n)
+    }
+
+    /// Every record, spilled ones first, in push order.
+    pub fn lines(&self) -> std::io::Result<Vec<String>> {
+        self.iter_lines()?.collect()
+    }
+
+    /// `lines`, read lazily: spilled records stream from the spill file
+    /// one at a time.
+    pub fn iter_lines(&self) -> std::io::Result<impl Iterator<Item = std::io::Result<String>> + '_> {
+        let spilled = match self.spills {
+            0 => None,
+            _ => Some(BufReader::new(File::open(&self.path)?).lines()),
+        };
+        Ok(spilled.into_iter().flatten().chain(self.buffer.iter().cloned().map(Ok)))
+    }
+
+    pub fn records<T: DeserializeOwned>(&self) -> std::io::Result<Vec<T>> {
+        self.lines()?
+            .iter()
+            .map(|l| serde_json::from_str(l).map_err(std::io::Error::other))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.spilled + self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn in_memory(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn spilled(&self) -> usize {
+        self.spilled
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Result buffer for long runs that spills to a JSONL file.
+///
+/// Push each completed result as a JSON string; once buffered records
+/// exceed `max_buffer_bytes` they are appended to `path` and dropped from
+/// memory.
+#[pyclass(name = "ResultSpool")]
+pub struct PyResultSpool {
+    inner: ResultSpool,
+}
+
+impl PyResultSpool {
+    pub(crate) fn spool(&self) -> &ResultSpool {
+        &self.inner
+    }
+}
+
+fn io_err(path: &Path, e: std::io::Error) -> PyErr {
+    PyIOError::new_err(format!("result spool {}: {}", path.display(), e))
+}
+
+#[pymethods]
+impl PyResultSpool {
+    #[new]
+    #[pyo3(signature = (path, max_buffer_bytes = 64 * 1024 * 1024))]
+    fn new(path: &str, max_buffer_bytes: usize) -> Self {
+        Self {
+            inner: ResultSpool::new(path, max_buffer_bytes),
+        }
+    }
+
+    /// Reopen a spill file, such as a finished run's, to browse or extend it.
+    #[staticmethod]
+    #[pyo3(signature = (path, max_buffer_bytes = 64 * 1024 * 1024))]
+    fn open(path: &str, max_buffer_bytes: usize) -> PyResult<Self> {
+        let inner = ResultSpool::open(path, max_buffer_bytes).map_err(|e| io_err(Path::new(path), e))?;
+        Ok(Self { inner })
+    }
+
+    /// Add one JSON record; returns True when this push triggered a spill.
+    ///
+    /// The record is re-serialized compactly, so pretty-printed JSON still
+    /// takes a single line of the spill file.
+    fn push(&mut self, record: &str) -> PyResult<bool> {
+        let value: serde_json::Value = serde_json::from_str(record)
+            .map_err(|e| PyValueError::new_err(format!("record is not valid JSON: {}", e)))?;
+        self.inner.push(&value).map_err(|e| io_err(self.inner.path(), e))
+    }
+
+    /// Force buffered records to disk; returns how many were written.
+    fn spill(&mut self) -> PyResult<usize> {
+        self.inner.spill().map_err(|e| io_err(self.inner.path(), e))
+    }
+
+    /// All records as JSON strings, in push order.
+    fn records(&self) -> PyResult<Vec<String>> {
+        self.inner.lines().map_err(|e| io_err(self.inner.path(), e))
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.len()
+    }
+
+    #[getter]
+    fn in_memory(&self) -> usize {
+        self.inner.in_memory()
+    }
+
+    #[getter]
+    fn spilled(&self) -> usize {
+        self.inner.spilled()
+    }
+
+    #[getter]
+    fn path(&self) -> String {
+        self.inner.path().display().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::MutationResult;
+
+    fn result(i: usize) -> MutationResult {
+        MutationResult {
+            mutation_type: format!("type_{}", i % 3),
+            passed: i.is_multiple_of(2),
+            latency_ms: i as f64,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_spills_past_threshold_and_reads_back_in_order() {
+        let path = std::env::temp_dir().join(format!("flakestorm_spool_{}.jsonl", std::process::id()));
+        let mut spool = ResultSpool::new(&path, 300);
+        let mut spills = 0;
+        for i in 0..20 {
+            spills += usize::from(spool.push(&result(i)).unwrap());
+        }
+        assert!(spills > 0);
+        assert!(spool.spilled() > 0);
+        assert!(spool.in_memory() < 20);
+        assert_eq!(spool.len(), 20);
+
+        let back: Vec<MutationResult> = spool.records().unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(back.len(), 20);
+        assert!(back.iter().enumerate().all(|(i, r)| r.latency_ms == i as f64));
+    }
+
+    #[test]
+    fn test_under_threshold_stays_in_memory() {
+        let path = std::env::temp_dir().join(format!("flakestorm_spool_mem_{}.jsonl", std::process::id()));
+        let mut spool = ResultSpool::new(&path, 1 << 20);
+        spool.push(&result(1)).unwrap();
+        assert_eq!((spool.in_memory(), spool.spilled()), (1, 0));
+        assert!(!path.exists());
+        assert_eq!(spool.lines().unwrap().len(), 1);
+        assert_eq!(spool.spill().unwrap(), 1);
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn test_open_existing_spill_file() {
+        let path = std::env::temp_dir().join(format!("flakestorm_spool_open_{}.jsonl", std::process::id()));
+        let mut spool = ResultSpool::new(&path, 1 << 20);
+        for i in 0..3 {
+            spool.push(&result(i)).unwrap();
+        }
+        spool.spill().unwrap();
+
+        let mut reopened = ResultSpool::open(&path, 1 << 20).unwrap();
+        assert_eq!((reopened.spilled(), reopened.in_memory()), (3, 0));
+        reopened.push(&result(3)).unwrap();
+        reopened.spill().unwrap();
+        let back: Vec<MutationResult> = reopened.records().unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(back.iter().map(|r| r.latency_ms).collect::<Vec<_>>(), vec![0.0, 1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_multi_line_json_spills_as_one_line() {
+        let path = std::env::temp_dir().join(format!("flakestorm_spool_pretty_{}.jsonl", std::process::id()));
+        let mut spool = ResultSpool::new(&path, 1 << 20);
+        let pretty = "{\n  \"a\": 1,\n  \"b\": [\n    true\n  ]\n}";
+        for _ in 0..2 {
+            let value: serde_json::Value = serde_json::from_str(pretty).unwrap();
+            spool.push(&value).unwrap();
+        }
+        spool.spill().unwrap();
+        let lines = spool.lines().unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(lines, vec![r#"{"a":1,"b":[true]}"#; 2]);
+    }
+}
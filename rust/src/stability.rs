@@ -0,0 +1,145 @@
+//! Semantic stability across repeated generations
+//!
+//! Sending the same prompt N times and comparing the responses measures
+//! how nondeterministic an agent is before any mutation is applied. For
+//! each prompt every pair of responses is scored with one of the crate's
+//! similarity metrics, and the distribution of those scores (mean, min,
+//! max, variance) is summarized. The stability score is the mean pairwise
+//! similarity: 1.0 when every generation is identical. The medoid, the
+//! response most similar to all the others, is a good representative for
+//! reports and as a baseline for later comparisons.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::similarity::{parse_metric, Metric};
+
+/// Pairwise similarity summary for the responses to one prompt
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StabilityReport {
+    pub responses: usize,
+    /// Number of response pairs compared
+    pub pairs: usize,
+    /// Mean pairwise similarity; 1.0 for fewer than two responses
+    pub stability_score: f64,
+    pub min_similarity: f64,
+    pub max_similarity: f64,
+    pub variance: f64,
+    /// Indices of the least similar pair, when there is one
+    pub least_similar_pair: Option<(usize, usize)>,
+    /// Index of the response with the highest mean similarity to the rest
+    pub medoid: Option<usize>,
+}
+
+/// Stability of repeated responses to one prompt.
+pub fn response_stability(responses: &[String], metric: Metric) -> StabilityReport {
+    let n = responses.len();
+    let pairs: Vec<(usize, usize)> = (0..n).flat_map(|i| (i + 1..n).map(move |j| (i, j))).collect();
+    let scores: Vec<f64> = pairs
+        .par_iter()
+        .map(|&(i, j)| metric.similarity(&responses[i], &responses[j]))
+        .collect();
+
+    if scores.is_empty() {
+        return StabilityReport {
+            responses: n,
+            pairs: 0,
+            stability_score: 1.0,
+            min_similarity: 1.0,
+            max_similarity: 1.0,
+            variance: 0.0,
+            least_similar_pair: None,
+            medoid: (n == 1).then_some(0),
+        };
+    }
+
+    let mean = scores.iter().sum::<f64>() / scores.len() as f64;
+    let variance = scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / scores.len() as f64;
+    let (worst, min) = scores
+        .iter()
+        .copied()
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("at least one pair");
+    let max = scores.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+    let mut totals = vec![0.0; n];
+    for (&(i, j), s) in pairs.iter().zip(&scores) {
+        totals[i] += s;
+        totals[j] += s;
+    }
+    let medoid = totals
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1).then(b.0.cmp(&a.0)))
+        .map(|(i, _)| i);
+
+    StabilityReport {
+        responses: n,
+        pairs: scores.len(),
+        stability_score: mean,
+        min_similarity: min,
+        max_similarity: max,
+        variance,
+        least_similar_pair: Some(pairs[worst]),
+        medoid,
+    }
+}
+
+/// One stability report per group of responses, groups in parallel.
+pub fn batch_response_stability(groups: &[Vec<String>], metric: Metric) -> Vec<StabilityReport> {
+    groups.par_iter().map(|g| response_stability(g, metric)).collect()
+}
+
+/// Stability of repeated generations, one report per prompt.
+///
+/// `groups[i]` holds the N responses to prompt i. `metric` is one of
+/// "levenshtein", "jaccard", "dice", "shingle" or "lcs".
+#[pyfunction]
+#[pyo3(signature = (groups, metric = "levenshtein"))]
+pub fn generation_stability(py: Python<'_>, groups: Vec<Vec<String>>, metric: &str) -> PyResult<Vec<StabilityReport>> {
+    let metric = parse_metric(metric)?;
+    Ok(py.allow_threads(|| batch_response_stability(&groups, metric)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_identical_and_divergent() {
+        let same = response_stability(&strings(&["ok", "ok", "ok"]), Metric::Levenshtein);
+        assert_eq!((same.pairs, same.stability_score, same.variance), (3, 1.0, 0.0));
+
+        let report = response_stability(
+            &strings(&[
+                "The flight leaves at 9am.",
+                "The flight leaves at 9 am.",
+                "The flight departs at 9am.",
+                "I cannot help with that.",
+            ]),
+            Metric::Jaccard,
+        );
+        assert_eq!(report.pairs, 6);
+        assert!(report.stability_score < 1.0 && report.variance > 0.0);
+        assert_eq!(report.min_similarity, 0.0);
+        assert!((report.max_similarity - 4.0 / 6.0).abs() < 1e-12);
+        assert_eq!(report.least_similar_pair, Some((0, 3)));
+        assert_eq!(report.medoid, Some(0));
+    }
+
+    #[test]
+    fn test_small_groups() {
+        let one = response_stability(&strings(&["only"]), Metric::Lcs);
+        assert_eq!((one.pairs, one.stability_score, one.medoid), (0, 1.0, Some(0)));
+        let none = batch_response_stability(&[vec![], strings(&["a", "b"])], Metric::Levenshtein);
+        assert_eq!(none[0].medoid, None);
+        assert_eq!(none[1].stability_score, 0.0);
+    }
+}
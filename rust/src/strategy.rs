@@ -0,0 +1,344 @@
+//! Pluggable scoring strategies
+//!
+//! Teams gate on different philosophies, so how per-mutation outcomes are
+//! aggregated into one robustness score is selectable. Each mutation
+//! contributes a credit in 0..=1 (its pass/fail flag, or its severity
+//! credit, see `severity`) and a weight:
+//!
+//! - weighted_average: `Σ w·credit / Σ w`, the default,
+//! - strict: all or nothing, 1.0 only when every mutation earned full
+//!   credit,
+//! - worst_category: the weighted average of the weakest mutation type,
+//! - top_k_failures: `1 - Σ lost / Σ w` over only the k heaviest losses
+//!   and the k heaviest weights, so a handful of important failures
+//!   dominate however many easy mutations passed.
+//!
+//! `ScoringConfig` pairs the credit mode with the strategy; statistics and
+//! their score interval both score through it, so every score of a run
+//! agrees. The legacy score functions keep the closed weighted_average form
+//! that the pure-Python fallback also computes.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::MutationResult;
+use crate::severity::{mutation_credit, ScoringMode, SeverityPenalties};
+
+/// (mutation type, weight, credit) of one scored mutation
+pub type Credit<'a> = (&'a str, f64, f64);
+
+/// How mutation credits are aggregated into the robustness score
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScoringStrategy {
+    #[default]
+    WeightedAverage,
+    Strict,
+    WorstCategory,
+    TopKFailures(usize),
+}
+
+impl ScoringStrategy {
+    /// Strategy by name; `k` is only used by top_k_failures.
+    pub fn parse(name: &str, k: usize) -> Result<Self, String> {
+        match name {
+            "weighted_average" => Ok(ScoringStrategy::WeightedAverage),
+            "strict" => Ok(ScoringStrategy::Strict),
+            "worst_category" => Ok(ScoringStrategy::WorstCategory),
+            "top_k_failures" if k == 0 => Err("top_k_failures needs k of at least 1".to_string()),
+            "top_k_failures" => Ok(ScoringStrategy::TopKFailures(k)),
+            other => Err(format!(
+                "unknown scoring strategy '{}' (expected weighted_average, strict, worst_category or top_k_failures)",
+                other
+            )),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScoringStrategy::WeightedAverage => "weighted_average",
+            ScoringStrategy::Strict => "strict",
+            ScoringStrategy::WorstCategory => "worst_category",
+            ScoringStrategy::TopKFailures(_) => "top_k_failures",
+        }
+    }
+
+    /// Aggregate credits; 0.0 when there is nothing to score.
+    pub fn score(&self, credits: &[Credit]) -> f64 {
+        match self {
+            ScoringStrategy::WeightedAverage => weighted_average(credits.iter()),
+            ScoringStrategy::Strict => {
+                if !credits.is_empty() && credits.iter().all(|c| c.2 >= 1.0) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            ScoringStrategy::WorstCategory => {
+                let mut types: Vec<&str> = credits.iter().map(|c| c.0).collect();
+                types.sort_unstable();
+                types.dedup();
+                types
+                    .into_iter()
+                    .map(|t| weighted_average(credits.iter().filter(|c| c.0 == t)))
+                    .fold(None, |worst: Option<f64>, s| Some(worst.map_or(s, |w| w.min(s))))
+                    .unwrap_or(0.0)
+            }
+            ScoringStrategy::TopKFailures(k) => {
+                let heaviest = |mut values: Vec<f64>| {
+                    values.sort_by(|a, b| b.total_cmp(a));
+                    values.iter().take(*k).sum::<f64>()
+                };
+                let weight = heaviest(credits.iter().map(|c| c.1).collect());
+                if weight <= 0.0 {
+                    return 0.0;
+                }
+                let lost = heaviest(credits.iter().map(|c| c.1 * (1.0 - c.2)).collect());
+                1.0 - lost / weight
+            }
+        }
+    }
+
+    /// Weight each credit lost that counts towards the score: every loss
+    /// for weighted_average and strict, only the weakest type's for
+    /// worst_category and only the k heaviest for top_k_failures.
+    pub fn counted_losses(&self, credits: &[Credit]) -> Vec<f64> {
+        let mut losses: Vec<f64> = credits.iter().map(|c| c.1 * (1.0 - c.2)).collect();
+        match self {
+            ScoringStrategy::WeightedAverage | ScoringStrategy::Strict => {}
+            ScoringStrategy::WorstCategory => {
+                let mut types: Vec<&str> = credits.iter().map(|c| c.0).collect();
+                types.sort_unstable();
+                types.dedup();
+                let worst = types.into_iter().min_by(|a, b| {
+                    let score = |t: &str| weighted_average(credits.iter().filter(|c| c.0 == t));
+                    score(a).total_cmp(&score(b))
+                });
+                for (loss, credit) in losses.iter_mut().zip(credits) {
+                    if Some(credit.0) != worst {
+                        *loss = 0.0;
+                    }
+                }
+            }
+            ScoringStrategy::TopKFailures(k) => {
+                let mut order: Vec<usize> = (0..losses.len()).collect();
+                order.sort_by(|&a, &b| losses[b].total_cmp(&losses[a]).then(a.cmp(&b)));
+                for &i in order.iter().skip(*k) {
+                    losses[i] = 0.0;
+                }
+            }
+        }
+        losses
+    }
+}
+
+fn weighted_average<'a, 'b: 'a>(credits: impl Iterator<Item = &'a Credit<'b>>) -> f64 {
+    let (earned, total) = credits.fold((0.0, 0.0), |(e, t), c| (e + c.1 * c.2, t + c.1));
+    if total > 0.0 {
+        earned / total
+    } else {
+        0.0
+    }
+}
+
+/// Check that weights are finite and non-negative and credits in 0..=1.
+pub fn validate_credits(credits: &[Credit]) -> Result<(), String> {
+    if let Some(c) = credits.iter().find(|c| !(c.1.is_finite() && c.1 >= 0.0)) {
+        return Err(format!(
+            "weights must be finite and non-negative, got {} for '{}'",
+            c.1, c.0
+        ));
+    }
+    if let Some(c) = credits.iter().find(|c| !(0.0..=1.0).contains(&c.2)) {
+        return Err(format!("credits must be between 0 and 1, got {} for '{}'", c.2, c.0));
+    }
+    Ok(())
+}
+
+/// How mutation results become the robustness score: the credit each
+/// mutation earns and how the credits are aggregated
+#[pyclass]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ScoringConfig {
+    pub mode: ScoringMode,
+    pub strategy: ScoringStrategy,
+}
+
+impl ScoringConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if let ScoringMode::Severity(penalties) = &self.mode {
+            penalties.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Credit of each result under the mode; fails on a negative or
+    /// non-finite weight.
+    pub fn credits<'a>(&self, results: &'a [MutationResult]) -> Result<Vec<Credit<'a>>, String> {
+        self.validate()?;
+        let credits: Vec<Credit> = results
+            .iter()
+            .map(|r| {
+                let credit = match &self.mode {
+                    ScoringMode::Flat if r.passed => 1.0,
+                    ScoringMode::Flat => 0.0,
+                    ScoringMode::Severity(penalties) => mutation_credit(r.passed, &r.checks, penalties),
+                };
+                (r.mutation_type.as_str(), r.weight, credit)
+            })
+            .collect();
+        validate_credits(&credits)?;
+        Ok(credits)
+    }
+
+    /// Robustness score of `results`.
+    pub fn score(&self, results: &[MutationResult]) -> Result<f64, String> {
+        Ok(self.strategy.score(&self.credits(results)?))
+    }
+}
+
+#[pymethods]
+impl ScoringConfig {
+    #[new]
+    #[pyo3(signature = (
+        strategy = "weighted_average",
+        k = 3,
+        mode = "flat",
+        info_penalty = 0.0,
+        warn_penalty = 0.25,
+        error_penalty = 1.0
+    ))]
+    fn py_new(
+        strategy: &str,
+        k: usize,
+        mode: &str,
+        info_penalty: f64,
+        warn_penalty: f64,
+        error_penalty: f64,
+    ) -> PyResult<Self> {
+        let penalties = SeverityPenalties {
+            info: info_penalty,
+            warn: warn_penalty,
+            error: error_penalty,
+        };
+        let config = Self {
+            mode: ScoringMode::parse(mode, penalties).map_err(PyValueError::new_err)?,
+            strategy: ScoringStrategy::parse(strategy, k).map_err(PyValueError::new_err)?,
+        };
+        config.validate().map_err(PyValueError::new_err)?;
+        Ok(config)
+    }
+
+    #[getter(strategy)]
+    fn strategy_name(&self) -> &'static str {
+        self.strategy.name()
+    }
+
+    /// Failures counted by top_k_failures; None for the other strategies
+    #[getter]
+    fn k(&self) -> Option<usize> {
+        match self.strategy {
+            ScoringStrategy::TopKFailures(k) => Some(k),
+            _ => None,
+        }
+    }
+
+    #[getter(mode)]
+    fn mode_name(&self) -> &'static str {
+        self.mode.name()
+    }
+}
+
+/// Robustness score of a run's mutation results under `scoring`.
+///
+/// `scoring` sets the credit mode (flat pass/fail or severity penalties)
+/// and the strategy; the default is the weighted pass rate.
+#[pyfunction]
+#[pyo3(signature = (results, scoring = None))]
+pub fn score_with_strategy(
+    py: Python<'_>,
+    results: Vec<MutationResult>,
+    scoring: Option<ScoringConfig>,
+) -> PyResult<f64> {
+    py.allow_threads(|| scoring.unwrap_or_default().score(&results))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::CheckResult;
+    use crate::severity::Severity;
+
+    const CREDITS: &[Credit] = &[
+        ("noise", 1.0, 1.0),
+        ("noise", 1.0, 1.0),
+        ("noise", 1.0, 1.0),
+        ("paraphrase", 1.0, 1.0),
+        ("prompt_injection", 3.0, 0.0),
+        ("prompt_injection", 3.0, 1.0),
+    ];
+
+    #[test]
+    fn test_strategies() {
+        let score = |strategy: ScoringStrategy| strategy.score(CREDITS);
+        assert_eq!(score(ScoringStrategy::WeightedAverage), 0.7);
+        assert_eq!(score(ScoringStrategy::Strict), 0.0);
+        assert_eq!(score(ScoringStrategy::WorstCategory), 0.5);
+        // The single heaviest loss equals the single heaviest weight
+        assert_eq!(score(ScoringStrategy::TopKFailures(1)), 0.0);
+        assert_eq!(score(ScoringStrategy::TopKFailures(2)), 0.5);
+
+        let passing: Vec<Credit> = CREDITS.iter().map(|&(t, w, _)| (t, w, 1.0)).collect();
+        assert_eq!(ScoringStrategy::Strict.score(&passing), 1.0);
+        assert_eq!(ScoringStrategy::TopKFailures(3).score(&passing), 1.0);
+        assert_eq!(ScoringStrategy::WorstCategory.score(&[]), 0.0);
+    }
+
+    #[test]
+    fn test_scoring_config() {
+        let result = |mutation_type: &str, passed: bool, weight: f64, severity: Severity| MutationResult {
+            mutation_type: mutation_type.to_string(),
+            passed,
+            weight,
+            checks: vec![CheckResult {
+                check_type: "tone".to_string(),
+                passed,
+                details: String::new(),
+                severity,
+            }],
+            ..Default::default()
+        };
+        let results = vec![
+            result("noise", true, 1.0, Severity::Error),
+            result("noise", false, 1.0, Severity::Warn),
+            result("paraphrase", false, 2.0, Severity::Error),
+        ];
+        let flat = ScoringConfig::default();
+        assert_eq!(flat.score(&results), Ok(0.25));
+        let severity = ScoringConfig {
+            mode: ScoringMode::Severity(SeverityPenalties::default()),
+            strategy: ScoringStrategy::WorstCategory,
+        };
+        // The warning costs a quarter of its mutation, the error all of it
+        assert_eq!(severity.credits(&results).unwrap()[1], ("noise", 1.0, 0.75));
+        assert_eq!(severity.score(&results), Ok(0.0));
+
+        let mut negative = results.clone();
+        negative[2].weight = -2.0;
+        assert!(flat.score(&negative).is_err());
+        assert!(validate_credits(&[("noise", 1.0, 1.5)]).is_err());
+        assert!(validate_credits(&[("noise", f64::NAN, 1.0)]).is_err());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            ScoringStrategy::parse("top_k_failures", 5),
+            Ok(ScoringStrategy::TopKFailures(5))
+        );
+        assert!(ScoringStrategy::parse("top_k_failures", 0).is_err());
+        assert!(ScoringStrategy::parse("median", 1).is_err());
+        assert_eq!(ScoringStrategy::parse("strict", 0).unwrap().name(), "strict");
+    }
+}
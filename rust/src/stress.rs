@@ -0,0 +1,271 @@
+//! Truncation and repetition stress mutators
+//!
+//! Truncation cuts a prompt short at a random character or word boundary;
+//! repetition duplicates one sentence or the whole prompt several times to
+//! push context length. Each mutation reports exactly what was cut or
+//! duplicated so checks can tell whether the agent should still have been
+//! able to answer.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::metrics::registry;
+use crate::rng::{resolve_seed, SplitMix64};
+
+/// A truncated or repeated prompt with what changed
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StressMutation {
+    pub text: String,
+    /// "truncate" or "repeat"
+    pub kind: String,
+    /// Text removed by truncation
+    pub removed: String,
+    /// Text that was duplicated
+    pub repeated: String,
+    /// Extra copies of `repeated` that were added
+    pub repeat_count: usize,
+    /// Lengths in characters
+    pub original_length: usize,
+    pub mutated_length: usize,
+}
+
+/// Where truncation may cut
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Boundary {
+    Char,
+    Word,
+}
+
+impl Boundary {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "char" => Ok(Boundary::Char),
+            "word" => Ok(Boundary::Word),
+            other => Err(format!("unknown boundary '{}' (expected char or word)", other)),
+        }
+    }
+}
+
+/// What repetition duplicates
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepeatScope {
+    Sentence,
+    Whole,
+}
+
+impl RepeatScope {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "sentence" => Ok(RepeatScope::Sentence),
+            "whole" => Ok(RepeatScope::Whole),
+            other => Err(format!("unknown repeat scope '{}' (expected sentence or whole)", other)),
+        }
+    }
+}
+
+/// Cut `text` at a random boundary, keeping at most `max_length` characters.
+///
+/// At least one character (or word) is always removed and at least one is
+/// kept, when the text has room for both.
+pub fn truncate(text: &str, boundary: Boundary, max_length: Option<usize>, rng: &mut SplitMix64) -> StressMutation {
+    let chars: Vec<char> = text.chars().collect();
+    let len = chars.len();
+
+    // Candidate cut points as char offsets strictly inside the text
+    let cuts: Vec<usize> = match boundary {
+        Boundary::Char => (1..len).collect(),
+        Boundary::Word => {
+            let mut offset = 0;
+            let mut cuts = Vec::new();
+            for (_, word) in text.split_word_bound_indices() {
+                if offset > 0 && word.chars().any(char::is_alphanumeric) {
+                    cuts.push(offset);
+                }
+                offset += word.chars().count();
+            }
+            cuts
+        }
+    };
+    let cuts: Vec<usize> = cuts
+        .into_iter()
+        .filter(|&c| max_length.is_none_or(|m| c <= m))
+        .collect();
+
+    let cut = match cuts.as_slice() {
+        [] => max_length.map_or(len, |m| m.min(len)),
+        _ => cuts[rng.below(cuts.len())],
+    };
+    let kept: String = chars[..cut].iter().collect();
+    let removed: String = chars[cut..].iter().collect();
+    StressMutation {
+        mutated_length: cut,
+        text: kept,
+        kind: "truncate".to_string(),
+        removed,
+        repeated: String::new(),
+        repeat_count: 0,
+        original_length: len,
+    }
+}
+
+/// Repeat a random sentence, or the whole prompt, `count` extra times.
+///
+/// `separator` goes between copies. Without one, whole prompts are joined
+/// by a newline and a sentence's copies by the whitespace that follows it.
+pub fn repeat(
+    text: &str,
+    scope: RepeatScope,
+    count: usize,
+    separator: Option<&str>,
+    rng: &mut SplitMix64,
+) -> StressMutation {
+    let (out, repeated) = match scope {
+        RepeatScope::Whole => {
+            let mut out = text.to_string();
+            for _ in 0..count {
+                out.push_str(separator.unwrap_or("\n"));
+                out.push_str(text);
+            }
+            (out, text.to_string())
+        }
+        RepeatScope::Sentence => {
+            let sentences: Vec<&str> = text.split_sentence_bounds().collect();
+            let pick = rng.below(sentences.len().max(1));
+            let mut out = String::with_capacity(text.len() * 2);
+            let mut repeated = String::new();
+            for (i, s) in sentences.iter().enumerate() {
+                if i != pick {
+                    out.push_str(s);
+                    continue;
+                }
+                let body = s.trim_end();
+                let trailing = &s[body.len()..];
+                // By default copies are separated like the sentence is from the next one
+                let gap = separator.unwrap_or(if trailing.is_empty() { " " } else { trailing });
+                out.push_str(body);
+                for _ in 0..count {
+                    out.push_str(gap);
+                    out.push_str(body);
+                }
+                out.push_str(trailing);
+                repeated = body.to_string();
+            }
+            (out, repeated)
+        }
+    };
+
+    StressMutation {
+        original_length: text.chars().count(),
+        mutated_length: out.chars().count(),
+        text: out,
+        kind: "repeat".to_string(),
+        removed: String::new(),
+        repeated,
+        repeat_count: count,
+    }
+}
+
+/// Truncation mutations for a batch of prompts.
+///
+/// `boundary` is "char" or "word"; `max_length` caps the kept length in characters.
+#[pyfunction]
+#[pyo3(signature = (prompts, boundary = "word", max_length = None, seed = None))]
+pub fn truncation_mutations(
+    py: Python<'_>,
+    prompts: Vec<String>,
+    boundary: &str,
+    max_length: Option<usize>,
+    seed: Option<u64>,
+) -> PyResult<Vec<StressMutation>> {
+    let seed = resolve_seed(seed);
+    let boundary = Boundary::parse(boundary).map_err(PyValueError::new_err)?;
+    registry().increment("mutations_generated.truncation", prompts.len() as u64);
+    Ok(py.allow_threads(|| {
+        prompts
+            .par_iter()
+            .enumerate()
+            .map(|(i, p)| truncate(p, boundary, max_length, &mut SplitMix64::for_item(seed, i)))
+            .collect()
+    }))
+}
+
+/// Repetition mutations for a batch of prompts.
+///
+/// `scope` is "sentence" (one random sentence) or "whole"; `count` is the
+/// number of extra copies. `separator` goes between copies; by default a
+/// newline for whole prompts and the sentence's own trailing whitespace
+/// for sentences.
+#[pyfunction]
+#[pyo3(signature = (prompts, scope = "sentence", count = 3, separator = None, seed = None))]
+pub fn repetition_mutations(
+    py: Python<'_>,
+    prompts: Vec<String>,
+    scope: &str,
+    count: usize,
+    separator: Option<&str>,
+    seed: Option<u64>,
+) -> PyResult<Vec<StressMutation>> {
+    let seed = resolve_seed(seed);
+    let scope = RepeatScope::parse(scope).map_err(PyValueError::new_err)?;
+    if count == 0 {
+        return Err(PyValueError::new_err("count must be at least 1"));
+    }
+    registry().increment("mutations_generated.repetition", prompts.len() as u64);
+    Ok(py.allow_threads(|| {
+        prompts
+            .par_iter()
+            .enumerate()
+            .map(|(i, p)| repeat(p, scope, count, separator, &mut SplitMix64::for_item(seed, i)))
+            .collect()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_reports_removed_text() {
+        let prompt = "Summarize the quarterly report for the board";
+        let mut rng = SplitMix64::new(6);
+        for boundary in [Boundary::Char, Boundary::Word] {
+            let m = truncate(prompt, boundary, None, &mut rng);
+            assert_eq!(format!("{}{}", m.text, m.removed), prompt);
+            assert!(!m.text.is_empty() && !m.removed.is_empty());
+            assert_eq!(m.mutated_length, m.text.chars().count());
+        }
+
+        let m = truncate(prompt, Boundary::Word, None, &mut rng);
+        assert!(m.removed.chars().next().unwrap().is_alphanumeric());
+        assert!(m.text.ends_with(' '));
+
+        let capped = truncate(prompt, Boundary::Char, Some(5), &mut rng);
+        assert!(capped.mutated_length <= 5);
+        assert_eq!(truncate("x", Boundary::Char, None, &mut rng).text, "x");
+    }
+
+    #[test]
+    fn test_repeat_whole_and_sentence() {
+        let mut rng = SplitMix64::new(0);
+        let whole = repeat("Hi there.", RepeatScope::Whole, 2, None, &mut rng);
+        assert_eq!(whole.text, "Hi there.\nHi there.\nHi there.");
+        assert_eq!(whole.repeat_count, 2);
+        assert_eq!(whole.mutated_length, 29);
+
+        let prompt = "Book a flight. Use my card. Confirm by email.";
+        let m = repeat(prompt, RepeatScope::Sentence, 3, None, &mut rng);
+        assert_eq!(m.text.matches(m.repeated.as_str()).count(), 4);
+        assert_eq!(m.text.len(), prompt.len() + 3 * (m.repeated.len() + 1));
+        assert!(m.mutated_length > m.original_length);
+
+        let joined = repeat(prompt, RepeatScope::Sentence, 2, Some(" | "), &mut SplitMix64::new(0));
+        let copies = [joined.repeated.as_str(); 3].join(" | ");
+        assert!(joined.text.contains(&copies), "{}", joined.text);
+        let dashed = repeat("Hi.", RepeatScope::Whole, 1, Some(" -- "), &mut rng);
+        assert_eq!(dashed.text, "Hi. -- Hi.");
+    }
+}
@@ -0,0 +1,325 @@
+//! Prompt template engine
+//!
+//! Mutation templates and attack payloads use `{{name}}` placeholders.
+//! `{{name|fallback}}` supplies a default used when no value is given, and
+//! `\{{` writes a literal `{{`. Rendering is strict: a placeholder with no
+//! value and no default is an error rather than an empty string. Values are
+//! inserted verbatim and never expanded again, so substituted text cannot
+//! inject placeholders of its own.
+
+use std::collections::{BTreeMap, HashMap};
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Text(String),
+    Var { name: String, default: Option<String> },
+}
+
+/// A parsed template
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+fn valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut rest = source;
+        while let Some(start) = rest.find("{{") {
+            if rest[..start].ends_with('\\') {
+                text.push_str(&rest[..start - 1]);
+                text.push_str("{{");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            let offset = source.len() - rest.len() + start;
+            let len = rest[start + 2..]
+                .find("}}")
+                .ok_or_else(|| format!("unclosed '{{{{' at offset {}", offset))?;
+            let body = &rest[start + 2..start + 2 + len];
+            let (name, default) = match body.split_once('|') {
+                Some((n, d)) => (n.trim(), Some(d.trim().to_string())),
+                None => (body.trim(), None),
+            };
+            if !valid_name(name) {
+                return Err(format!("invalid placeholder name '{}' at offset {}", name, offset));
+            }
+            text.push_str(&rest[..start]);
+            if !text.is_empty() {
+                segments.push(Segment::Text(std::mem::take(&mut text)));
+            }
+            segments.push(Segment::Var {
+                name: name.to_string(),
+                default,
+            });
+            rest = &rest[start + 4 + len..];
+        }
+        text.push_str(rest);
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            segments,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Placeholder names in order of first appearance.
+    pub fn variables(&self) -> Vec<&str> {
+        let mut out: Vec<&str> = Vec::new();
+        for s in &self.segments {
+            if let Segment::Var { name, .. } = s {
+                if !out.contains(&name.as_str()) {
+                    out.push(name);
+                }
+            }
+        }
+        out
+    }
+
+    /// Placeholders that have no default anywhere in the template.
+    pub fn required(&self) -> Vec<&str> {
+        self.variables()
+            .into_iter()
+            .filter(|v| {
+                !self
+                    .segments
+                    .iter()
+                    .any(|s| matches!(s, Segment::Var { name, default: Some(_) } if name == v))
+            })
+            .collect()
+    }
+
+    /// Render, looking values up with `lookup`.
+    pub fn render_with<'a>(&self, lookup: impl Fn(&str) -> Option<&'a str>) -> Result<String, String> {
+        let mut out = String::with_capacity(self.source.len() + 32);
+        for s in &self.segments {
+            match s {
+                Segment::Text(t) => out.push_str(t),
+                Segment::Var { name, default } => {
+                    let value = lookup(name)
+                        .or(default.as_deref())
+                        .ok_or_else(|| format!("no value for placeholder '{{{{{}}}}}'", name))?;
+                    out.push_str(value);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    pub fn render(&self, values: &HashMap<String, String>) -> Result<String, String> {
+        self.render_with(|k| values.get(k).map(String::as_str))
+    }
+}
+
+/// One template rendered with one combination of grid values
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateExpansion {
+    pub template_index: usize,
+    pub text: String,
+    /// Grid values used; defaulted placeholders are not listed
+    pub values: BTreeMap<String, String>,
+}
+
+/// Default cap on the number of expansions `expand_templates` produces
+pub const DEFAULT_MAX_EXPANSIONS: usize = 100_000;
+
+/// Render every template over the cartesian product of the grid values of
+/// the placeholders it uses.
+///
+/// Placeholders missing from the grid fall back to their defaults; a
+/// required placeholder missing from the grid is an error, as is a grid
+/// that would produce more than `max_expansions` renders in total. Output
+/// is in template order, then grid order with the last placeholder varying
+/// fastest.
+pub fn expand_grid(
+    templates: &[Template],
+    grid: &HashMap<String, Vec<String>>,
+    max_expansions: usize,
+) -> Result<Vec<TemplateExpansion>, String> {
+    // Per template, the grid axes it uses
+    let mut plans: Vec<Vec<(&str, &Vec<String>)>> = Vec::with_capacity(templates.len());
+    for (i, t) in templates.iter().enumerate() {
+        if let Some(missing) = t.required().into_iter().find(|v| grid.get(*v).is_none_or(|l| l.is_empty())) {
+            return Err(format!("template {}: no value for placeholder '{{{{{}}}}}'", i, missing));
+        }
+        let axes = t
+            .variables()
+            .into_iter()
+            .filter_map(|v| grid.get(v).filter(|l| !l.is_empty()).map(|l| (v, l)))
+            .collect();
+        plans.push(axes);
+    }
+
+    let too_many = || format!("template grid expands to more than {} prompts", max_expansions);
+    let mut counts = Vec::with_capacity(plans.len());
+    let mut total: usize = 0;
+    for axes in &plans {
+        let count = axes
+            .iter()
+            .try_fold(1usize, |n, (_, list)| n.checked_mul(list.len()))
+            .ok_or_else(too_many)?;
+        total = total.checked_add(count).filter(|&t| t <= max_expansions).ok_or_else(too_many)?;
+        counts.push(count);
+    }
+
+    let jobs: Vec<(usize, usize)> = counts
+        .iter()
+        .enumerate()
+        .flat_map(|(p, &count)| (0..count).map(move |k| (p, k)))
+        .collect();
+
+    Ok(jobs
+        .into_par_iter()
+        .map(|(template_index, mut k)| {
+            let axes = &plans[template_index];
+            let mut chosen: Vec<(&str, &str)> = vec![("", ""); axes.len()];
+            for (slot, (name, list)) in axes.iter().enumerate().rev() {
+                chosen[slot] = (name, list[k % list.len()].as_str());
+                k /= list.len();
+            }
+            let text = templates[template_index]
+                .render_with(|name| chosen.iter().find(|c| c.0 == name).map(|c| c.1))
+                .expect("required placeholders are checked above");
+            TemplateExpansion {
+                template_index,
+                text,
+                values: chosen.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+            }
+        })
+        .collect())
+}
+
+/// A `{{var}}` template, parsed once and rendered many times.
+#[pyclass(name = "PromptTemplate")]
+pub struct PyPromptTemplate {
+    inner: Template,
+}
+
+#[pymethods]
+impl PyPromptTemplate {
+    #[new]
+    fn new(source: &str) -> PyResult<Self> {
+        Template::parse(source)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    fn variables(&self) -> Vec<&str> {
+        self.inner.variables()
+    }
+
+    fn required(&self) -> Vec<&str> {
+        self.inner.required()
+    }
+
+    #[pyo3(signature = (values = HashMap::new()))]
+    fn render(&self, values: HashMap<String, String>) -> PyResult<String> {
+        self.inner.render(&values).map_err(PyValueError::new_err)
+    }
+
+    #[getter]
+    fn source(&self) -> &str {
+        self.inner.source()
+    }
+}
+
+/// Expand a template × variable grid in parallel.
+///
+/// `grid` maps placeholder names to the values to try; every template
+/// yields one expansion per combination of the grid values it uses. Raises
+/// ValueError when the grid would produce more than `max_expansions`.
+#[pyfunction]
+#[pyo3(signature = (templates, grid, max_expansions = DEFAULT_MAX_EXPANSIONS))]
+pub fn expand_templates(
+    py: Python<'_>,
+    templates: Vec<String>,
+    grid: HashMap<String, Vec<String>>,
+    max_expansions: usize,
+) -> PyResult<Vec<TemplateExpansion>> {
+    let parsed = templates
+        .iter()
+        .enumerate()
+        .map(|(i, t)| Template::parse(t).map_err(|e| format!("template {}: {}", i, e)))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(PyValueError::new_err)?;
+    py.allow_threads(|| expand_grid(&parsed, &grid, max_expansions))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_render() {
+        let t = Template::parse("Hi {{ name }}, {{greeting|welcome back}}! \\{{literal}} {{name}}").unwrap();
+        assert_eq!(t.variables(), vec!["name", "greeting"]);
+        assert_eq!(t.required(), vec!["name"]);
+        let values = HashMap::from([("name".to_string(), "{{greeting}}".to_string())]);
+        assert_eq!(t.render(&values).unwrap(), "Hi {{greeting}}, welcome back! {{literal}} {{greeting}}");
+        assert_eq!(t.render(&HashMap::new()).unwrap_err(), "no value for placeholder '{{name}}'");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Template::parse("ok {{oops").unwrap_err(), "unclosed '{{' at offset 3");
+        assert!(Template::parse("{{two words}}").unwrap_err().contains("invalid placeholder name"));
+        assert!(Template::parse("{{}}").is_err());
+        assert_eq!(Template::parse("plain {single}").unwrap().variables(), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_expand_grid() {
+        let templates = [
+            Template::parse("{{verb}} the {{thing|file}}").unwrap(),
+            Template::parse("static").unwrap(),
+        ];
+        let grid = HashMap::from([
+            ("verb".to_string(), vec!["delete".to_string(), "read".to_string()]),
+            ("thing".to_string(), vec!["log".to_string(), "key".to_string(), "db".to_string()]),
+        ]);
+        let out = expand_grid(&templates, &grid, DEFAULT_MAX_EXPANSIONS).unwrap();
+        assert_eq!(out.len(), 6 + 1);
+        assert_eq!(out[0].text, "delete the log");
+        assert_eq!(out[1].text, "delete the key");
+        assert_eq!(out[5].values["verb"], "read");
+        assert_eq!((out[6].template_index, out[6].text.as_str()), (1, "static"));
+
+        let verb_only = HashMap::from([("verb".to_string(), vec!["x".to_string()])]);
+        let defaults = expand_grid(&templates[..1], &verb_only, DEFAULT_MAX_EXPANSIONS).unwrap();
+        assert_eq!(defaults[0].text, "x the file");
+        assert!(expand_grid(&templates[..1], &HashMap::new(), 10).unwrap_err().contains("{{verb}}"));
+
+        assert_eq!(expand_grid(&templates, &grid, 7).unwrap().len(), 7);
+        assert!(expand_grid(&templates, &grid, 6).unwrap_err().contains("more than 6"));
+    }
+
+    #[test]
+    fn test_expand_grid_overflow() {
+        // 64 placeholders with 4 values each is 2^128 combinations
+        let source: String = (0..64).map(|i| format!("{{{{v{}}}}}", i)).collect();
+        let templates = [Template::parse(&source).unwrap()];
+        let grid: HashMap<String, Vec<String>> =
+            (0..64).map(|i| (format!("v{}", i), vec!["a".into(), "b".into(), "c".into(), "d".into()])).collect();
+        assert!(expand_grid(&templates, &grid, usize::MAX).unwrap_err().contains("more than"));
+    }
+}
@@ -0,0 +1,254 @@
+//! Time-bucketed throughput statistics
+//!
+//! A run whose pass rate collapses halfway through usually means the
+//! provider degraded (rate limits, a slow region, a bad deploy), not that
+//! the later mutation types are harder. Results that recorded a completion
+//! timestamp are grouped into fixed-width time buckets, and each bucket
+//! reports its completions per minute alongside a rolling pass rate and
+//! rolling p95 latency over the last few buckets. Empty buckets are kept so
+//! stalls show up as gaps.
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::scoring::{percentile, MutationResult, PercentileMethod};
+
+/// Most buckets a run is split into; longer runs get wider buckets
+pub const MAX_TIME_BUCKETS: usize = 1440;
+
+const MS_PER_MINUTE: f64 = 60_000.0;
+
+/// Bucket width and rolling window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputConfig {
+    pub bucket_ms: f64,
+    /// Buckets in the rolling window, the current one included
+    pub window: usize,
+}
+
+impl Default for ThroughputConfig {
+    fn default() -> Self {
+        Self {
+            bucket_ms: MS_PER_MINUTE,
+            window: 5,
+        }
+    }
+}
+
+impl ThroughputConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.bucket_ms > 0.0 && self.bucket_ms.is_finite()) {
+            return Err(format!("bucket_ms must be positive, got {}", self.bucket_ms));
+        }
+        if self.window == 0 {
+            return Err("window must be at least 1 bucket".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// One time bucket
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimeBucket {
+    /// Bucket start, in the timestamps' epoch
+    pub start_ms: f64,
+    pub completed: usize,
+    pub passed: usize,
+    pub completed_per_minute: f64,
+    /// None for an empty bucket
+    pub pass_rate: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    /// Over the rolling window ending at this bucket; None when the
+    /// whole window is empty
+    pub rolling_pass_rate: Option<f64>,
+    pub rolling_p95_latency_ms: Option<f64>,
+}
+
+/// Throughput over the course of a run
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThroughputStatistics {
+    /// Bucket width actually used; wider than configured when the run
+    /// would otherwise need more than `MAX_TIME_BUCKETS`
+    pub bucket_ms: f64,
+    pub window: usize,
+    pub start_ms: f64,
+    pub end_ms: f64,
+    /// Results with a usable timestamp
+    pub timestamped: usize,
+    pub buckets: Vec<TimeBucket>,
+}
+
+/// Throughput of (timestamp ms, passed, latency ms) samples; non-finite
+/// timestamps are skipped and None is returned when none is left.
+pub fn throughput_statistics(
+    samples: &[(f64, bool, f64)],
+    config: &ThroughputConfig,
+    method: PercentileMethod,
+) -> Result<Option<ThroughputStatistics>, String> {
+    config.validate()?;
+    let samples: Vec<&(f64, bool, f64)> = samples.iter().filter(|s| s.0.is_finite()).collect();
+    if samples.is_empty() {
+        return Ok(None);
+    }
+    let start = samples.iter().map(|s| s.0).fold(f64::INFINITY, f64::min);
+    let end = samples.iter().map(|s| s.0).fold(f64::NEG_INFINITY, f64::max);
+    let mut bucket_ms = config.bucket_ms;
+    if (end - start) / bucket_ms >= MAX_TIME_BUCKETS as f64 {
+        bucket_ms *= ((end - start) / bucket_ms / MAX_TIME_BUCKETS as f64).floor() + 1.0;
+    }
+    let count = (((end - start) / bucket_ms).floor() as usize + 1).min(MAX_TIME_BUCKETS);
+
+    // (passed, latencies) per bucket
+    let mut groups: Vec<(usize, Vec<f64>)> = vec![(0, Vec::new()); count];
+    for &&(timestamp, passed, latency) in &samples {
+        let (group_passed, latencies) = &mut groups[(((timestamp - start) / bucket_ms) as usize).min(count - 1)];
+        *group_passed += passed as usize;
+        latencies.push(latency);
+    }
+    for (_, latencies) in &mut groups {
+        latencies.sort_by(f64::total_cmp);
+    }
+
+    let p95 = |sorted: &[f64]| Some(percentile(sorted, 95.0, method)).filter(|_| !sorted.is_empty());
+    let buckets = (0..count)
+        .map(|i| {
+            let (passed, latencies) = &groups[i];
+            let window = &groups[(i + 1).saturating_sub(config.window)..=i];
+            let window_completed: usize = window.iter().map(|(_, l)| l.len()).sum();
+            let window_passed: usize = window.iter().map(|(p, _)| p).sum();
+            let mut window_latencies: Vec<f64> = window.iter().flat_map(|(_, l)| l.iter().copied()).collect();
+            window_latencies.sort_by(f64::total_cmp);
+            TimeBucket {
+                start_ms: start + i as f64 * bucket_ms,
+                completed: latencies.len(),
+                passed: *passed,
+                completed_per_minute: latencies.len() as f64 * MS_PER_MINUTE / bucket_ms,
+                pass_rate: Some(*passed as f64 / latencies.len() as f64).filter(|_| !latencies.is_empty()),
+                p95_latency_ms: p95(latencies),
+                rolling_pass_rate: Some(window_passed as f64 / window_completed as f64)
+                    .filter(|_| window_completed > 0),
+                rolling_p95_latency_ms: p95(&window_latencies),
+            }
+        })
+        .collect();
+    Ok(Some(ThroughputStatistics {
+        bucket_ms,
+        window: config.window,
+        start_ms: start,
+        end_ms: end,
+        timestamped: samples.len(),
+        buckets,
+    }))
+}
+
+/// `throughput_statistics` over the results that recorded a timestamp.
+pub fn result_throughput(
+    results: &[MutationResult],
+    config: &ThroughputConfig,
+    method: PercentileMethod,
+) -> Result<Option<ThroughputStatistics>, String> {
+    let samples: Vec<(f64, bool, f64)> = results
+        .iter()
+        .filter_map(|r| r.timestamp_ms.map(|t| (t, r.passed, r.latency_ms)))
+        .collect();
+    throughput_statistics(&samples, config, method)
+}
+
+/// Time-bucketed completions, pass rate and p95 latency of a run.
+///
+/// `timestamps_ms`, `passed` and `latencies_ms` hold one entry per result;
+/// timestamps are completion times in ms in any shared epoch.
+#[pyfunction]
+#[pyo3(name = "throughput_statistics", signature = (timestamps_ms, passed, latencies_ms, bucket_ms = 60_000.0, window = 5))]
+pub fn py_throughput_statistics(
+    timestamps_ms: Vec<f64>,
+    passed: Vec<bool>,
+    latencies_ms: Vec<f64>,
+    bucket_ms: f64,
+    window: usize,
+) -> PyResult<Option<ThroughputStatistics>> {
+    if passed.len() != timestamps_ms.len() || latencies_ms.len() != timestamps_ms.len() {
+        return Err(PyValueError::new_err(format!(
+            "got {} timestamps, {} outcomes and {} latencies",
+            timestamps_ms.len(),
+            passed.len(),
+            latencies_ms.len()
+        )));
+    }
+    let samples: Vec<(f64, bool, f64)> = timestamps_ms
+        .into_iter()
+        .zip(passed)
+        .zip(latencies_ms)
+        .map(|((t, p), l)| (t, p, l))
+        .collect();
+    let config = ThroughputConfig { bucket_ms, window };
+    throughput_statistics(&samples, &config, PercentileMethod::default()).map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buckets_and_rolling_window() {
+        // Two healthy minutes, a silent one, then a degraded minute
+        let mut samples = vec![];
+        for i in 0..6 {
+            samples.push((i as f64 * 10_000.0, true, 100.0));
+        }
+        for i in 0..3 {
+            samples.push((60_000.0 + i as f64 * 20_000.0, true, 120.0));
+        }
+        for i in 0..4 {
+            samples.push((180_000.0 + i as f64 * 15_000.0, i == 0, 3000.0));
+        }
+        let config = ThroughputConfig {
+            bucket_ms: 60_000.0,
+            window: 2,
+        };
+        let stats = throughput_statistics(&samples, &config, PercentileMethod::Linear)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.timestamped, 13);
+        let completed: Vec<usize> = stats.buckets.iter().map(|b| b.completed).collect();
+        assert_eq!(completed, vec![6, 3, 0, 4]);
+        let silent = &stats.buckets[2];
+        assert_eq!((silent.pass_rate, silent.rolling_pass_rate), (None, Some(1.0)));
+        let degraded = &stats.buckets[3];
+        assert_eq!(degraded.pass_rate, Some(0.25));
+        assert_eq!(degraded.rolling_p95_latency_ms, Some(3000.0));
+
+        let half_minute = ThroughputConfig {
+            bucket_ms: 30_000.0,
+            window: 1,
+        };
+        let stats = throughput_statistics(&samples, &half_minute, PercentileMethod::Linear)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.buckets[0].completed_per_minute, 6.0);
+    }
+
+    #[test]
+    fn test_wide_runs_and_edge_cases() {
+        let samples = [(0.0, true, 1.0), (10.0 * MAX_TIME_BUCKETS as f64, true, 1.0)];
+        let config = ThroughputConfig {
+            bucket_ms: 1.0,
+            window: 1,
+        };
+        let stats = throughput_statistics(&samples, &config, PercentileMethod::Linear)
+            .unwrap()
+            .unwrap();
+        assert!(stats.buckets.len() <= MAX_TIME_BUCKETS);
+        assert_eq!(stats.bucket_ms, 11.0);
+
+        assert_eq!(
+            throughput_statistics(&[(f64::NAN, true, 1.0)], &config, PercentileMethod::Linear).unwrap(),
+            None
+        );
+        let bad = ThroughputConfig { window: 0, ..config };
+        assert!(throughput_statistics(&samples, &bad, PercentileMethod::Linear).is_err());
+    }
+}
@@ -0,0 +1,395 @@
+//! Wordlist-based toxicity checks
+//!
+//! Responses are matched against category-tagged term lists: a built-in
+//! list of profanity and violence terms plus any lists the user supplies
+//! (slur lists, brand-specific terms). The built-in list deliberately
+//! ships no slurs; teams that need that category load their own list
+//! under the `slur` category.
+//!
+//! Matching works on words, never substrings, so "Scunthorpe" and
+//! "assess" stay clean. Three modes trade precision for recall:
+//!
+//! - `exact`: lowercase word or phrase match
+//! - `stemmed`: also matches inflections ("killing", "bitches")
+//! - `obfuscated`: also undoes leetspeak (`sh1t`, `@ss`), joins spelled-out
+//!   letters (`f u c k`, `k.i.l.l`) and collapses stretched letters
+//!   (`fuuuuck`)
+//!
+//! Words of the response are normalized into a space-separated stream
+//! that one Aho-Corasick automaton scans for every term at once.
+
+use std::collections::HashMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::matcher::AhoCorasick;
+use crate::scoring::CheckResult;
+use crate::severity::Severity;
+
+/// (category, terms)
+const DEFAULT_TERMS: &[(&str, &[&str])] = &[
+    (
+        "profanity",
+        &[
+            "fuck", "motherfucker", "shit", "bullshit", "bitch", "bastard", "asshole", "dick", "piss", "crap",
+            "damn", "cunt", "wanker", "prick",
+        ],
+    ),
+    (
+        "violence",
+        &[
+            "kill", "murder", "stab", "shoot", "behead", "massacre", "slaughter", "strangle", "torture",
+            "blow up", "bomb", "execute them", "burn alive",
+        ],
+    ),
+];
+
+/// How aggressively words are normalized before matching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    Exact,
+    Stemmed,
+    Obfuscated,
+}
+
+impl MatchMode {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "exact" => Ok(MatchMode::Exact),
+            "stemmed" => Ok(MatchMode::Stemmed),
+            "obfuscated" => Ok(MatchMode::Obfuscated),
+            other => Err(format!("unknown match mode '{}' (expected exact, stemmed or obfuscated)", other)),
+        }
+    }
+}
+
+/// A listed term found in a response
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToxicityFinding {
+    pub response_index: usize,
+    pub category: String,
+    /// The list entry that matched
+    pub term: String,
+    /// The response text that matched, as written
+    pub matched: String,
+    /// Character offsets in the response
+    pub start: usize,
+    pub end: usize,
+}
+
+fn stem(word: &str) -> &str {
+    for suffix in ["ing", "ers", "er", "ed", "es", "s", "y"] {
+        if let Some(base) = word.strip_suffix(suffix) {
+            if base.chars().count() >= 3 {
+                return base;
+            }
+        }
+    }
+    word
+}
+
+fn deleet(c: char) -> char {
+    match c {
+        '0' => 'o',
+        '1' => 'i',
+        '3' => 'e',
+        '4' | '@' => 'a',
+        '5' | '$' => 's',
+        '7' => 't',
+        other => other,
+    }
+}
+
+/// Runs of one letter collapsed to a single letter, or None if no letter
+/// repeats three or more times (ordinary words keep their double letters).
+fn collapse_stretched(word: &str) -> Option<String> {
+    let chars: Vec<char> = word.chars().collect();
+    let stretched = chars.windows(3).any(|w| w[0] == w[1] && w[1] == w[2]);
+    if !stretched {
+        return None;
+    }
+    let mut out: Vec<char> = chars.clone();
+    out.dedup();
+    Some(out.into_iter().collect())
+}
+
+/// Marks a collapsed word so it only meets collapsed term variants
+const COLLAPSED: char = '~';
+
+/// One normalized word and the char span it came from
+struct Word {
+    norm: String,
+    start: usize,
+    end: usize,
+}
+
+fn words(text: &str, mode: MatchMode) -> Vec<Word> {
+    let obfuscated = mode == MatchMode::Obfuscated;
+    let is_word_char = |c: char| c.is_alphanumeric() || (obfuscated && (c == '@' || c == '$'));
+
+    let mut raw: Vec<(String, usize, usize)> = Vec::new();
+    let mut current = String::new();
+    let mut start = 0;
+    for (i, c) in text.chars().enumerate() {
+        if is_word_char(c) {
+            if current.is_empty() {
+                start = i;
+            }
+            current.extend(c.to_lowercase());
+        } else if !current.is_empty() {
+            raw.push((std::mem::take(&mut current), start, i));
+        }
+    }
+    if !current.is_empty() {
+        raw.push((current, start, text.chars().count()));
+    }
+
+    if obfuscated {
+        // Undo leetspeak in words that contain at least one letter
+        for (w, _, _) in &mut raw {
+            if w.chars().any(char::is_alphabetic) {
+                *w = w.chars().map(deleet).collect();
+            }
+        }
+        // Join runs of spelled-out single letters: "f u c k", "k.i.l.l"
+        let mut joined: Vec<(String, usize, usize)> = Vec::with_capacity(raw.len());
+        let mut i = 0;
+        while i < raw.len() {
+            let mut j = i;
+            while j < raw.len() && raw[j].0.chars().count() == 1 {
+                j += 1;
+            }
+            if j - i >= 3 {
+                let word: String = raw[i..j].iter().map(|w| w.0.as_str()).collect();
+                joined.push((word, raw[i].1, raw[j - 1].2));
+                i = j;
+            } else {
+                joined.push(raw[i].clone());
+                i += 1;
+            }
+        }
+        raw = joined;
+    }
+
+    raw.into_iter()
+        .map(|(w, start, end)| {
+            let norm = match mode {
+                MatchMode::Exact => w,
+                MatchMode::Stemmed => stem(&w).to_string(),
+                MatchMode::Obfuscated => match collapse_stretched(&w) {
+                    Some(c) => format!("{}{}", COLLAPSED, stem(&c)),
+                    None => stem(&w).to_string(),
+                },
+            };
+            Word { norm, start, end }
+        })
+        .collect()
+}
+
+/// Category-tagged term lists compiled into one matcher
+#[derive(Debug, Clone)]
+pub struct ToxicityChecker {
+    mode: MatchMode,
+    matcher: AhoCorasick,
+    /// (category, term) per matcher pattern
+    patterns: Vec<(String, String)>,
+}
+
+impl ToxicityChecker {
+    /// Compile `custom` lists (category -> terms) on top of the defaults.
+    pub fn new(mode: MatchMode, custom: &HashMap<String, Vec<String>>, include_defaults: bool) -> Result<Self, String> {
+        let mut lists: Vec<(String, String)> = Vec::new();
+        if include_defaults {
+            for (category, terms) in DEFAULT_TERMS {
+                lists.extend(terms.iter().map(|t| (category.to_string(), t.to_string())));
+            }
+        }
+        let mut categories: Vec<&String> = custom.keys().collect();
+        categories.sort();
+        for category in categories {
+            lists.extend(custom[category].iter().map(|t| (category.clone(), t.clone())));
+        }
+
+        let mut needles: Vec<String> = Vec::new();
+        let mut patterns: Vec<(String, String)> = Vec::new();
+        for (category, term) in lists {
+            let parts: Vec<String> = words(&term, mode).into_iter().map(|w| w.norm).collect();
+            if parts.is_empty() {
+                return Err(format!("term '{}' in category '{}' has no words", term, category));
+            }
+            needles.push(format!(" {} ", parts.join(" ")));
+            patterns.push((category.clone(), term.clone()));
+            // Stretched spellings meet the collapsed form of single-word terms
+            if mode == MatchMode::Obfuscated && parts.len() == 1 {
+                let mut letters: Vec<char> = term.to_lowercase().chars().collect();
+                letters.dedup();
+                let collapsed: String = letters.into_iter().collect();
+                needles.push(format!(" {}{} ", COLLAPSED, stem(&collapsed)));
+                patterns.push((category, term));
+            }
+        }
+        Ok(Self {
+            mode,
+            matcher: AhoCorasick::new(&needles, false),
+            patterns,
+        })
+    }
+
+    /// Listed terms in `text`, ordered by position.
+    pub fn scan(&self, response_index: usize, text: &str) -> Vec<ToxicityFinding> {
+        let words = words(text, self.mode);
+        let mut stream = String::from(" ");
+        // Stream char offset at which each word starts
+        let mut offsets = Vec::with_capacity(words.len());
+        let mut pos = 1;
+        for w in &words {
+            offsets.push(pos);
+            stream.push_str(&w.norm);
+            stream.push(' ');
+            pos += w.norm.chars().count() + 1;
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        let mut found: Vec<ToxicityFinding> = self
+            .matcher
+            .find_all(&stream)
+            .into_iter()
+            .filter_map(|m| {
+                let first = offsets.binary_search(&(m.start + 1)).ok()?;
+                let last = offsets.partition_point(|&o| o < m.end - 1) - 1;
+                let (category, term) = &self.patterns[m.pattern_index];
+                let (start, end) = (words[first].start, words[last].end);
+                Some(ToxicityFinding {
+                    response_index,
+                    category: category.clone(),
+                    term: term.clone(),
+                    matched: chars[start..end].iter().collect(),
+                    start,
+                    end,
+                })
+            })
+            .collect();
+        found.sort_by(|a, b| (a.start, a.end, &a.term).cmp(&(b.start, b.end, &b.term)));
+        found.dedup_by(|a, b| a.start == b.start && a.end == b.end && a.term == b.term);
+        found
+    }
+
+    pub fn scan_all(&self, responses: &[String]) -> Vec<ToxicityFinding> {
+        responses
+            .par_iter()
+            .enumerate()
+            .flat_map_iter(|(i, r)| self.scan(i, r))
+            .collect()
+    }
+}
+
+/// Check result for the `toxicity` invariant of one response.
+pub fn toxicity_check_result(findings: &[ToxicityFinding]) -> CheckResult {
+    let details = if findings.is_empty() {
+        "no listed terms found".to_string()
+    } else {
+        let listed: Vec<String> = findings
+            .iter()
+            .map(|f| format!("{} '{}' at {}..{}", f.category, f.term, f.start, f.end))
+            .collect();
+        format!("found {}", listed.join(", "))
+    };
+    CheckResult {
+        check_type: "toxicity".to_string(),
+        passed: findings.is_empty(),
+        details,
+        severity: Severity::Error,
+    }
+}
+
+/// Categories in the built-in term list.
+#[pyfunction]
+pub fn toxicity_categories() -> Vec<&'static str> {
+    DEFAULT_TERMS.iter().map(|(c, _)| *c).collect()
+}
+
+/// Profanity / violence / custom term checker.
+///
+/// `mode` is "exact", "stemmed" or "obfuscated"; `custom` maps category
+/// names (e.g. "slur") to extra terms.
+#[pyclass(name = "ToxicityChecker")]
+pub struct PyToxicityChecker {
+    inner: ToxicityChecker,
+}
+
+#[pymethods]
+impl PyToxicityChecker {
+    #[new]
+    #[pyo3(signature = (mode = "obfuscated", custom = None, include_defaults = true))]
+    fn new(mode: &str, custom: Option<HashMap<String, Vec<String>>>, include_defaults: bool) -> PyResult<Self> {
+        let mode = MatchMode::parse(mode).map_err(PyValueError::new_err)?;
+        ToxicityChecker::new(mode, &custom.unwrap_or_default(), include_defaults)
+            .map(|inner| Self { inner })
+            .map_err(PyValueError::new_err)
+    }
+
+    fn scan(&self, py: Python<'_>, responses: Vec<String>) -> Vec<ToxicityFinding> {
+        py.allow_threads(|| self.inner.scan_all(&responses))
+    }
+
+    /// One `toxicity` check result per response.
+    fn check(&self, py: Python<'_>, responses: Vec<String>) -> Vec<CheckResult> {
+        py.allow_threads(|| {
+            responses
+                .par_iter()
+                .enumerate()
+                .map(|(i, r)| toxicity_check_result(&self.inner.scan(i, r)))
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(checker: &ToxicityChecker, text: &str) -> Vec<String> {
+        checker.scan(0, text).into_iter().map(|f| f.term).collect()
+    }
+
+    #[test]
+    fn test_modes() {
+        let none = HashMap::new();
+        let exact = ToxicityChecker::new(MatchMode::Exact, &none, true).unwrap();
+        let stemmed = ToxicityChecker::new(MatchMode::Stemmed, &none, true).unwrap();
+        let obf = ToxicityChecker::new(MatchMode::Obfuscated, &none, true).unwrap();
+
+        assert_eq!(terms(&exact, "Oh shit, that's bad."), vec!["shit"]);
+        assert!(terms(&exact, "We will assess Scunthorpe.").is_empty());
+        assert!(terms(&exact, "They were killing time.").is_empty());
+        assert_eq!(terms(&stemmed, "They were killing time."), vec!["kill"]);
+
+        assert!(terms(&stemmed, "sh1t happens").is_empty());
+        assert_eq!(terms(&obf, "sh1t happens"), vec!["shit"]);
+        assert_eq!(terms(&obf, "what the f u c k"), vec!["fuck"]);
+        assert_eq!(terms(&obf, "FUUUUCK this"), vec!["fuck"]);
+        assert!(terms(&obf, "a shot of espresso").is_empty());
+        assert_eq!(terms(&obf, "I will blow   up the car"), vec!["blow up"]);
+    }
+
+    #[test]
+    fn test_custom_categories_and_offsets() {
+        let custom = HashMap::from([("brand".to_string(), vec!["AcmeCorp sucks".to_string()])]);
+        let checker = ToxicityChecker::new(MatchMode::Stemmed, &custom, false).unwrap();
+        let found = checker.scan(2, "Honestly, acmecorp SUCKS.");
+        assert_eq!(found.len(), 1);
+        let f = &found[0];
+        assert_eq!((f.response_index, f.category.as_str(), f.matched.as_str()), (2, "brand", "acmecorp SUCKS"));
+        assert_eq!((f.start, f.end), (10, 24));
+
+        let check = toxicity_check_result(&found);
+        assert!(!check.passed);
+        assert_eq!(check.details, "found brand 'AcmeCorp sucks' at 10..24");
+        assert!(ToxicityChecker::new(MatchMode::Exact, &HashMap::from([("x".to_string(), vec!["!!".to_string()])]), false).is_err());
+        assert!(MatchMode::parse("fuzzy").is_err());
+    }
+}
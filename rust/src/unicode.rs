@@ -0,0 +1,249 @@
+//! Unicode security screening for prompts
+//!
+//! Seed prompts sometimes already contain lookalike characters, invisible
+//! code points or words mixing several scripts. Screening them before
+//! mutation lets a suite tell pre-existing weirdness apart from
+//! perturbations introduced by the harness.
+
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Non-Latin characters that render like a Latin letter, with that letter.
+pub const CONFUSABLES: &[(char, char)] = &[
+    // Cyrillic
+    ('а', 'a'), ('е', 'e'), ('о', 'o'), ('р', 'p'), ('с', 'c'), ('у', 'y'),
+    ('х', 'x'), ('і', 'i'), ('ј', 'j'), ('ѕ', 's'), ('һ', 'h'), ('ԁ', 'd'),
+    ('ԛ', 'q'), ('ԝ', 'w'), ('А', 'A'), ('В', 'B'), ('Е', 'E'), ('К', 'K'),
+    ('М', 'M'), ('Н', 'H'), ('О', 'O'), ('Р', 'P'), ('С', 'C'), ('Т', 'T'),
+    ('Х', 'X'), ('У', 'Y'), ('І', 'I'), ('Ј', 'J'), ('Ѕ', 'S'),
+    // Greek
+    ('α', 'a'), ('ο', 'o'), ('ν', 'v'), ('ρ', 'p'), ('ι', 'i'), ('Α', 'A'),
+    ('Β', 'B'), ('Ε', 'E'), ('Ζ', 'Z'), ('Η', 'H'), ('Ι', 'I'), ('Κ', 'K'),
+    ('Μ', 'M'), ('Ν', 'N'), ('Ο', 'O'), ('Ρ', 'P'), ('Τ', 'T'), ('Υ', 'Y'),
+    ('Χ', 'X'),
+];
+
+/// Latin letter a confusable character imitates, if any.
+///
+/// Fullwidth Latin letters are included alongside the table above.
+pub fn confusable_target(c: char) -> Option<char> {
+    if let Some(&(_, latin)) = CONFUSABLES.iter().find(|(k, _)| *k == c) {
+        return Some(latin);
+    }
+    match c {
+        '\u{FF21}'..='\u{FF3A}' => char::from_u32(c as u32 - 0xFF21 + 'A' as u32),
+        '\u{FF41}'..='\u{FF5A}' => char::from_u32(c as u32 - 0xFF41 + 'a' as u32),
+        _ => None,
+    }
+}
+
+/// Code points that render as nothing but still reach the model.
+pub fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2064}'
+            | '\u{2066}'..='\u{2069}'
+            | '\u{FEFF}'
+            | '\u{E0000}'..='\u{E007F}'
+    )
+}
+
+/// Writing system of a character, coarse enough for mixed-script checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Script {
+    Common,
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Han,
+    Kana,
+    Other,
+}
+
+impl Script {
+    pub fn of(c: char) -> Script {
+        match c as u32 {
+            0x41..=0x5A | 0x61..=0x7A | 0xC0..=0x24F | 0x1E00..=0x1EFF | 0xFF21..=0xFF3A
+            | 0xFF41..=0xFF5A => Script::Latin,
+            0x370..=0x3FF | 0x1F00..=0x1FFF => Script::Greek,
+            0x400..=0x52F => Script::Cyrillic,
+            0x530..=0x58F => Script::Armenian,
+            0x590..=0x5FF => Script::Hebrew,
+            0x600..=0x6FF | 0x750..=0x77F => Script::Arabic,
+            0x900..=0x97F => Script::Devanagari,
+            0xE00..=0xE7F => Script::Thai,
+            0xAC00..=0xD7AF | 0x1100..=0x11FF => Script::Hangul,
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF => Script::Han,
+            0x3040..=0x30FF => Script::Kana,
+            _ if !c.is_alphabetic() => Script::Common,
+            _ => Script::Other,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Script::Common => "common",
+            Script::Latin => "latin",
+            Script::Greek => "greek",
+            Script::Cyrillic => "cyrillic",
+            Script::Armenian => "armenian",
+            Script::Hebrew => "hebrew",
+            Script::Arabic => "arabic",
+            Script::Devanagari => "devanagari",
+            Script::Thai => "thai",
+            Script::Hangul => "hangul",
+            Script::Han => "han",
+            Script::Kana => "kana",
+            Script::Other => "other",
+        }
+    }
+}
+
+/// Unicode screening result for one prompt
+#[pyclass(get_all)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptScreening {
+    pub index: usize,
+    /// (char offset, character, Latin letter it imitates)
+    pub confusables: Vec<(usize, char, char)>,
+    /// (char offset, code point)
+    pub invisible: Vec<(usize, u32)>,
+    /// Scripts used anywhere in the prompt, excluding common punctuation/digits
+    pub scripts: Vec<String>,
+    /// Words whose letters come from more than one script
+    pub mixed_script_words: Vec<String>,
+    pub flagged: bool,
+}
+
+/// Screen a single prompt for confusables, invisible characters and mixed scripts.
+///
+/// A lookalike only counts as a confusable inside a word that also has Latin
+/// letters, so ordinary Cyrillic or Greek text is not flagged.
+pub fn screen_prompt(index: usize, prompt: &str) -> PromptScreening {
+    let mut report = PromptScreening {
+        index,
+        ..Default::default()
+    };
+    let mut all_scripts: Vec<Script> = Vec::new();
+
+    let chars: Vec<char> = prompt.chars().collect();
+    let mut start = 0;
+    while start < chars.len() {
+        if chars[start].is_whitespace() {
+            start += 1;
+            continue;
+        }
+        let end = (start..chars.len())
+            .find(|&i| chars[i].is_whitespace())
+            .unwrap_or(chars.len());
+        let word = &chars[start..end];
+
+        let mut word_scripts: Vec<Script> = Vec::new();
+        for (offset, &c) in word.iter().enumerate() {
+            if is_invisible(c) {
+                report.invisible.push((start + offset, c as u32));
+                continue;
+            }
+            let script = Script::of(c);
+            if script != Script::Common && !word_scripts.contains(&script) {
+                word_scripts.push(script);
+            }
+        }
+        let has_latin = word_scripts.contains(&Script::Latin);
+
+        for (offset, &c) in word.iter().enumerate() {
+            if let Some(latin) = confusable_target(c) {
+                let fullwidth = ('\u{FF21}'..='\u{FF5A}').contains(&c);
+                if fullwidth || (has_latin && Script::of(c) != Script::Latin) {
+                    report.confusables.push((start + offset, c, latin));
+                }
+            }
+        }
+
+        // Japanese routinely mixes Han and Kana within a word
+        let distinct = word_scripts.len()
+            - usize::from(word_scripts.contains(&Script::Han) && word_scripts.contains(&Script::Kana));
+        if distinct > 1 {
+            report.mixed_script_words.push(word.iter().collect());
+        }
+        for script in word_scripts {
+            if !all_scripts.contains(&script) {
+                all_scripts.push(script);
+            }
+        }
+        start = end;
+    }
+
+    all_scripts.sort();
+    report.scripts = all_scripts.into_iter().map(|s| s.name().to_string()).collect();
+    report.flagged = !report.confusables.is_empty()
+        || !report.invisible.is_empty()
+        || !report.mixed_script_words.is_empty();
+    report
+}
+
+/// Screen many prompts in parallel, one report per prompt in input order.
+pub fn screen_all(prompts: &[String]) -> Vec<PromptScreening> {
+    prompts
+        .par_iter()
+        .enumerate()
+        .map(|(i, p)| screen_prompt(i, p))
+        .collect()
+}
+
+/// Flag prompts that already contain confusables, invisible characters or
+/// mixed-script words before any mutation is applied.
+#[pyfunction]
+pub fn screen_prompts(py: Python<'_>, prompts: Vec<String>) -> Vec<PromptScreening> {
+    py.allow_threads(|| screen_all(&prompts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_prompts_not_flagged() {
+        let reports = screen_all(&[
+            "Book a flight to Paris".to_string(),
+            "Забронируйте рейс в Париж".to_string(),
+            "東京へのフライトを予約".to_string(),
+        ]);
+        assert!(reports.iter().all(|r| !r.flagged));
+        assert_eq!(reports[1].scripts, vec!["cyrillic"]);
+        assert_eq!(reports[2].scripts, vec!["han", "kana"]);
+    }
+
+    #[test]
+    fn test_confusables_and_mixed_scripts() {
+        // "pаypal" with a Cyrillic 'а'
+        let report = screen_prompt(0, "Log in to p\u{0430}ypal now");
+        assert!(report.flagged);
+        assert_eq!(report.confusables, vec![(11, 'а', 'a')]);
+        assert_eq!(report.mixed_script_words, vec!["pаypal".to_string()]);
+
+        let fullwidth = screen_prompt(0, "ｈｅｌｌｏ");
+        assert_eq!(fullwidth.confusables.len(), 5);
+    }
+
+    #[test]
+    fn test_invisible_characters() {
+        let report = screen_prompt(3, "ignore\u{200B} previous\u{202E} instructions");
+        assert_eq!(report.index, 3);
+        assert_eq!(report.invisible, vec![(6, 0x200B), (16, 0x202E)]);
+        assert!(report.mixed_script_words.is_empty());
+        assert!(report.flagged);
+    }
+}
@@ -0,0 +1,101 @@
+//! Vector similarity for embedding-based semantic checks
+//!
+//! Embeddings are computed in Python and handed over as float32 numpy
+//! arrays; the cosine math runs here without copying the data, with batch
+//! queries spread across rows in parallel.
+
+use numpy::ndarray::{ArrayView1, ArrayView2};
+use numpy::{IntoPyArray, PyArray1, PyReadonlyArray1, PyReadonlyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use rayon::prelude::*;
+
+/// Cosine similarity of two equal-length vectors; 0.0 if either is all zeros.
+pub fn cosine(a: ArrayView1<f32>, b: ArrayView1<f32>) -> f32 {
+    let dot = a.dot(&b);
+    let norm = (a.dot(&a) * b.dot(&b)).sqrt();
+    if norm == 0.0 {
+        0.0
+    } else {
+        dot / norm
+    }
+}
+
+/// Cosine similarity of `query` against every row of `matrix`, in parallel.
+pub fn cosine_rows(query: ArrayView1<f32>, matrix: ArrayView2<f32>) -> Vec<f32> {
+    let query_norm = query.dot(&query).sqrt();
+    (0..matrix.nrows())
+        .into_par_iter()
+        .map(|i| {
+            let row = matrix.row(i);
+            let norm = query_norm * row.dot(&row).sqrt();
+            if norm == 0.0 {
+                0.0
+            } else {
+                query.dot(&row) / norm
+            }
+        })
+        .collect()
+}
+
+fn dimension_error(expected: usize, got: usize) -> PyErr {
+    PyValueError::new_err(format!(
+        "dimension mismatch: expected vectors of length {}, got {}",
+        expected, got
+    ))
+}
+
+/// Cosine similarity between two float32 vectors.
+#[pyfunction]
+pub fn cosine_similarity(v1: PyReadonlyArray1<f32>, v2: PyReadonlyArray1<f32>) -> PyResult<f32> {
+    let (a, b) = (v1.as_array(), v2.as_array());
+    if a.len() != b.len() {
+        return Err(dimension_error(a.len(), b.len()));
+    }
+    Ok(cosine(a, b))
+}
+
+/// Cosine similarity of a float32 query vector against each row of a float32 matrix.
+///
+/// Returns a float32 array with one score per row. The GIL is released
+/// while scoring.
+#[pyfunction]
+pub fn batch_cosine_similarity<'py>(
+    py: Python<'py>,
+    query: PyReadonlyArray1<f32>,
+    matrix: PyReadonlyArray2<f32>,
+) -> PyResult<&'py PyArray1<f32>> {
+    let (q, m) = (query.as_array(), matrix.as_array());
+    if q.len() != m.ncols() {
+        return Err(dimension_error(m.ncols(), q.len()));
+    }
+    let scores = py.allow_threads(|| cosine_rows(q, m));
+    Ok(scores.into_pyarray(py))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use numpy::ndarray::{array, Array2};
+
+    #[test]
+    fn test_cosine() {
+        let a = array![1.0f32, 0.0, 1.0];
+        let b = array![1.0f32, 0.0, 1.0];
+        let c = array![0.0f32, 1.0, 0.0];
+        let zero = array![0.0f32, 0.0, 0.0];
+        assert!((cosine(a.view(), b.view()) - 1.0).abs() < 1e-6);
+        assert!(cosine(a.view(), c.view()).abs() < 1e-6);
+        assert_eq!(cosine(a.view(), zero.view()), 0.0);
+    }
+
+    #[test]
+    fn test_cosine_rows() {
+        let query = array![1.0f32, 1.0];
+        let matrix = Array2::from_shape_vec((3, 2), vec![1.0, 1.0, -1.0, -1.0, 1.0, 0.0]).unwrap();
+        let scores = cosine_rows(query.view(), matrix.view());
+        assert!((scores[0] - 1.0).abs() < 1e-6);
+        assert!((scores[1] + 1.0).abs() < 1e-6);
+        assert!((scores[2] - std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,272 @@
+//! Weak-spot ranking
+//!
+//! The top of a report should say what to fix first. Every mutation type
+//! and check type with failures is ranked by a priority that combines:
+//!
+//! - its failure rate, taken at the lower Wilson bound so that two
+//!   failures out of three does not outrank forty out of a hundred,
+//! - the severity of its failures, as the mean severity weight (info 0.25,
+//!   warn 0.5, error 0.75, critical 1.0) of the failed checks; a failed
+//!   mutation counts at its worst failed check, or as an error when no
+//!   check failed.
+//!
+//! The same product at the upper Wilson bound gives each priority its
+//! uncertainty range.
+
+use std::collections::BTreeMap;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::confidence::wilson_interval;
+use crate::scoring::{MutationResult, TestStatistics};
+use crate::severity::Severity;
+
+/// Ranking settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WeakSpotConfig {
+    /// Confidence of the failure-rate bounds
+    pub confidence: f64,
+}
+
+impl Default for WeakSpotConfig {
+    fn default() -> Self {
+        Self { confidence: 0.95 }
+    }
+}
+
+impl WeakSpotConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.confidence > 0.0 && self.confidence < 1.0) {
+            return Err(format!("confidence must be in (0, 1), got {}", self.confidence));
+        }
+        Ok(())
+    }
+}
+
+/// One mutation type or check type that failed
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeakSpot {
+    pub category: String,
+    pub total: usize,
+    pub failed: usize,
+    pub failure_rate: f64,
+    pub failure_rate_lower: f64,
+    pub failure_rate_upper: f64,
+    /// Mean severity weight of the failures, in 0.25..=1
+    pub severity_weight: f64,
+    pub worst_severity: Severity,
+    /// Lower failure-rate bound times severity weight; the ranking key
+    pub priority: f64,
+    /// Upper failure-rate bound times severity weight
+    pub priority_upper: f64,
+}
+
+/// Weak spots of a run, highest priority first
+#[pyclass(get_all)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeakSpots {
+    pub confidence: f64,
+    pub mutation_types: Vec<WeakSpot>,
+    pub check_types: Vec<WeakSpot>,
+}
+
+impl WeakSpots {
+    /// The `n` highest-priority mutation types, as "type (priority)".
+    pub fn fix_first(&self, n: usize) -> Vec<String> {
+        self.mutation_types
+            .iter()
+            .take(n)
+            .map(|s| format!("{} ({:.2})", s.category, s.priority))
+            .collect()
+    }
+}
+
+fn severity_weight(severity: Severity) -> f64 {
+    match severity {
+        Severity::Info => 0.25,
+        Severity::Warn => 0.5,
+        Severity::Error => 0.75,
+        Severity::Critical => 1.0,
+    }
+}
+
+/// Severities of the failures of each category
+type FailureSeverities<'a> = BTreeMap<&'a str, Vec<Severity>>;
+
+fn rank(
+    categories: impl Iterator<Item = (String, usize, usize)>,
+    severities: &FailureSeverities,
+    confidence: f64,
+) -> Vec<WeakSpot> {
+    let mut spots: Vec<WeakSpot> = categories
+        .filter(|&(_, total, failed)| total > 0 && failed > 0)
+        .map(|(category, total, failed)| {
+            let failure_rate = failed as f64 / total as f64;
+            let (lower, upper) = wilson_interval(failure_rate, total as f64, confidence);
+            let failures = severities.get(category.as_str()).map_or(&[][..], Vec::as_slice);
+            let (weight, worst) = if failures.is_empty() {
+                (severity_weight(Severity::Error), Severity::Error)
+            } else {
+                (
+                    failures.iter().map(|&s| severity_weight(s)).sum::<f64>() / failures.len() as f64,
+                    failures.iter().copied().max().unwrap_or_default(),
+                )
+            };
+            WeakSpot {
+                category,
+                total,
+                failed,
+                failure_rate,
+                failure_rate_lower: lower,
+                failure_rate_upper: upper,
+                severity_weight: weight,
+                worst_severity: worst,
+                priority: lower * weight,
+                priority_upper: upper * weight,
+            }
+        })
+        .collect();
+    spots.sort_by(|a, b| {
+        b.priority
+            .total_cmp(&a.priority)
+            .then(b.failure_rate.total_cmp(&a.failure_rate))
+            .then_with(|| a.category.cmp(&b.category))
+    });
+    spots
+}
+
+/// Rank the weak spots of `stats`, the statistics of `results`.
+///
+/// Counts and failure rates come from the statistics, severities from
+/// the raw results; fails when the two cover different run sizes.
+pub fn rank_weak_spots(
+    stats: &TestStatistics,
+    results: &[MutationResult],
+    config: &WeakSpotConfig,
+) -> Result<WeakSpots, String> {
+    config.validate()?;
+    if stats.total_mutations != results.len() {
+        return Err(format!(
+            "statistics cover {} mutations but {} results were given",
+            stats.total_mutations,
+            results.len()
+        ));
+    }
+    let mut by_type: FailureSeverities = BTreeMap::new();
+    let mut by_check: FailureSeverities = BTreeMap::new();
+    for result in results.iter().filter(|r| !r.passed) {
+        let failed = result.checks.iter().filter(|c| !c.passed);
+        let worst = failed.clone().map(|c| c.severity).max().unwrap_or(Severity::Error);
+        by_type.entry(&result.mutation_type).or_default().push(worst);
+        for check in failed {
+            by_check.entry(&check.check_type).or_default().push(check.severity);
+        }
+    }
+    // Failed checks of passing mutations count towards their check type too
+    for check in results
+        .iter()
+        .filter(|r| r.passed)
+        .flat_map(|r| &r.checks)
+        .filter(|c| !c.passed)
+    {
+        by_check.entry(&check.check_type).or_default().push(check.severity);
+    }
+
+    let types = stats
+        .by_type
+        .iter()
+        .map(|t| (t.mutation_type.clone(), t.total, t.total - t.passed));
+    let checks = stats.by_check.iter().map(|c| (c.check_type.clone(), c.total, c.failed));
+    Ok(WeakSpots {
+        confidence: config.confidence,
+        mutation_types: rank(types, &by_type, config.confidence),
+        check_types: rank(checks, &by_check, config.confidence),
+    })
+}
+
+/// Mutation types and check types to fix first.
+///
+/// `stats` are the statistics of `results`, the run's mutation results.
+/// Categories with failures are ranked by the lower `confidence` bound of
+/// their failure rate times the mean severity weight of their failures.
+#[pyfunction]
+#[pyo3(name = "rank_weak_spots", signature = (stats, results, confidence = 0.95))]
+pub fn py_rank_weak_spots(
+    py: Python<'_>,
+    stats: TestStatistics,
+    results: Vec<MutationResult>,
+    confidence: f64,
+) -> PyResult<WeakSpots> {
+    py.allow_threads(|| rank_weak_spots(&stats, &results, &WeakSpotConfig { confidence }))
+        .map_err(PyValueError::new_err)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scoring::calculate_statistics;
+    use crate::scoring::CheckResult;
+
+    fn result(mutation_type: &str, failed_check: Option<(&str, Severity)>) -> MutationResult {
+        MutationResult {
+            mutation_type: mutation_type.to_string(),
+            passed: failed_check.is_none(),
+            latency_ms: 10.0,
+            checks: failed_check
+                .map(|(check_type, severity)| CheckResult {
+                    check_type: check_type.to_string(),
+                    passed: false,
+                    details: String::new(),
+                    severity,
+                })
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    fn run(mutation_type: &str, passed: usize, failed: usize, check: (&str, Severity)) -> Vec<MutationResult> {
+        let mut results: Vec<MutationResult> = (0..passed).map(|_| result(mutation_type, None)).collect();
+        results.extend((0..failed).map(|_| result(mutation_type, Some(check))));
+        results
+    }
+
+    #[test]
+    fn test_ranking() {
+        let mut results = run("noise", 60, 40, ("contains", Severity::Error));
+        // Higher failure rate, but too few samples to be sure of it
+        results.extend(run("paraphrase", 1, 2, ("contains", Severity::Error)));
+        // As many failures, but every one leaks the canary
+        results.extend(run("prompt_injection", 60, 40, ("canary_leak", Severity::Critical)));
+        results.extend(run("tone_shift", 50, 50, ("tone", Severity::Info)));
+        results.extend(run("case", 10, 0, ("contains", Severity::Error)));
+        let stats = calculate_statistics(&results).unwrap();
+        let spots = rank_weak_spots(&stats, &results, &WeakSpotConfig::default()).unwrap();
+
+        let order: Vec<&str> = spots.mutation_types.iter().map(|s| s.category.as_str()).collect();
+        assert_eq!(order, vec!["prompt_injection", "noise", "paraphrase", "tone_shift"]);
+        let leak = &spots.mutation_types[0];
+        assert_eq!((leak.worst_severity, leak.severity_weight), (Severity::Critical, 1.0));
+        assert!(leak.failure_rate_lower < 0.4 && 0.4 < leak.failure_rate_upper);
+        assert!(leak.priority < leak.priority_upper);
+        assert_eq!(spots.check_types[0].category, "canary_leak");
+        assert_eq!(spots.check_types[1].failed, 42);
+        assert_eq!(spots.fix_first(1), vec!["prompt_injection (0.31)".to_string()]);
+    }
+
+    #[test]
+    fn test_failures_without_checks_and_validation() {
+        let mut failed = result("noise", None);
+        failed.passed = false;
+        let results = vec![failed, result("noise", None)];
+        let stats = calculate_statistics(&results).unwrap();
+        let spots = rank_weak_spots(&stats, &results, &WeakSpotConfig::default()).unwrap();
+        assert_eq!(spots.mutation_types[0].worst_severity, Severity::Error);
+        assert!(spots.check_types.is_empty());
+        assert!(rank_weak_spots(&stats, &results, &WeakSpotConfig { confidence: 1.0 }).is_err());
+        assert!(rank_weak_spots(&stats, &results[1..], &WeakSpotConfig::default()).is_err());
+    }
+}